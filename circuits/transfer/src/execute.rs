@@ -39,6 +39,7 @@ pub struct ExecuteCircuit<const I: usize, T, const H: usize, const A: usize> {
     crossover: CircuitCrossover,
     outputs: [Option<CircuitOutput>; OUTPUTS],
     tx_hash: BlsScalar,
+    chain_id: BlsScalar,
 }
 
 impl<const I: usize, T, const H: usize, const A: usize>
@@ -53,6 +54,7 @@ impl<const I: usize, T, const H: usize, const A: usize>
             crossover: CircuitCrossover::default(),
             outputs: [Self::NONE_OUTPUT; OUTPUTS],
             tx_hash: BlsScalar::zero(),
+            chain_id: BlsScalar::zero(),
         }
     }
 
@@ -173,6 +175,14 @@ impl<const I: usize, T, const H: usize, const A: usize>
         self.tx_hash = tx_hash;
     }
 
+    /// Set the network the transaction is meant for.
+    ///
+    /// The chain ID is a public input of the proof, so that a transaction
+    /// cannot be replayed on another network.
+    pub fn set_chain_id(&mut self, chain_id: u8) {
+        self.chain_id = BlsScalar::from(chain_id as u64);
+    }
+
     pub fn add_input(
         &mut self,
         input: CircuitInput<T, H, A>,
@@ -191,6 +201,10 @@ impl<const I: usize, T, const H: usize, const A: usize>
         &self.tx_hash
     }
 
+    pub const fn chain_id(&self) -> &BlsScalar {
+        &self.chain_id
+    }
+
     /// Return the anchor root of the inputs.
     ///
     /// The circuit expects a single root for all the inputs.
@@ -257,6 +271,8 @@ impl<const I: usize, T, const H: usize, const A: usize>
 
         pi.extend(outputs);
 
+        pi.push(self.chain_id);
+
         pi
     }
 
@@ -424,6 +440,9 @@ where
 
         composer.assert_equal(inputs, o);
 
+        // 5. The network the transaction is meant for
+        composer.append_public(self.chain_id);
+
         Ok(())
     }
 }
@@ -168,6 +168,35 @@ fn execute_1_2() {
     }
 }
 
+#[test]
+fn execute_binds_chain_id() {
+    let mut rng = StdRng::seed_from_u64(424242u64);
+
+    let tx_hash = BlsScalar::random(&mut rng);
+    let mut circuit: ExecuteCircuitOneTwo =
+        create_test_circuit::<1>(&mut rng, true, tx_hash)
+            .expect("test circuit creation should pass");
+    circuit.set_chain_id(1);
+
+    let (prover, verifier) = load_keys("ExecuteCircuitOneTwo")
+        .expect("loading the keys should succeed");
+
+    let (proof, mut pi) = prover
+        .prove(&mut rng, &circuit)
+        .expect("creating a proof should succeed");
+
+    verifier
+        .verify(&proof, &pi)
+        .expect("Proof verification should be successful");
+
+    // The same proof is rejected on another network
+    *pi.last_mut().expect("chain id to be a public input") =
+        BlsScalar::from(2u64);
+    verifier
+        .verify(&proof, &pi)
+        .expect_err("Proof verification should fail on another chain");
+}
+
 #[test]
 fn execute_2_2() {
     let mut rng = StdRng::seed_from_u64(424242u64);
@@ -4,13 +4,14 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use crate::config::MAX_MISSING_VOTES;
 use crate::user::cluster::Cluster;
 use crate::user::committee::Committee;
 use dusk_bytes::Serializable;
 use node_data::bls::PublicKey;
 use node_data::ledger::{to_str, StepVotes};
 use node_data::message::payload::Vote;
-use node_data::message::SignInfo;
+use node_data::message::{Message, SignInfo};
 use std::collections::BTreeMap;
 use std::fmt;
 use thiserror::Error;
@@ -19,10 +20,14 @@ use tracing::{debug, error};
 /// Aggregator collects votes per a block hash by aggregating signatures of
 /// voters.StepVotes Mapping of a block hash to both an aggregated signatures
 /// and a cluster of bls voters.
+///
+/// The messages of the votes aggregated are kept as well, for them to be
+/// rebroadcast to the peers missing them.
 #[derive(Default)]
-pub struct Aggregator(
-    BTreeMap<(u16, Vote), (AggrSignature, Cluster<PublicKey>)>,
-);
+pub struct Aggregator {
+    votes: BTreeMap<(u16, Vote), (AggrSignature, Cluster<PublicKey>)>,
+    msgs: BTreeMap<(u16, PublicKey), Message>,
+}
 
 #[derive(Debug, Error)]
 pub enum AggregatorError {
@@ -58,7 +63,8 @@ impl Aggregator {
             .votes_for(signer)
             .ok_or(AggregatorError::NotCommitteeMember)?;
 
-        let (aggr_sign, cluster) = self.0.entry((msg_step, *vote)).or_default();
+        let (aggr_sign, cluster) =
+            self.votes.entry((msg_step, *vote)).or_default();
 
         // Each committee has 64 slots.
         //
@@ -101,10 +107,7 @@ impl Aggregator {
 
         let step_votes = StepVotes::new(aggregate_signature, bitset);
 
-        let quorum_target = match &vote {
-            Vote::Valid(_) => committee.super_majority_quorum(),
-            _ => committee.majority_quorum(),
-        };
+        let quorum_target = quorum_target(committee, vote);
 
         let quorum_reached = total >= quorum_target;
         if quorum_reached {
@@ -121,11 +124,71 @@ impl Aggregator {
 
         Ok((step_votes, quorum_reached))
     }
+
+    /// Keeps the message of a vote already collected.
+    pub fn keep_msg(&mut self, msg: Message) {
+        if let Some(signer) = msg.get_signer() {
+            self.msgs.insert((msg.get_step(), signer.clone()), msg);
+        }
+    }
+
+    /// Returns the messages of the votes for `step` cast by the committee
+    /// members at the positions set in `signers`.
+    pub fn msgs_of(
+        &self,
+        committee: &Committee,
+        step: u16,
+        signers: u64,
+    ) -> Vec<Message> {
+        committee
+            .intersect(signers)
+            .iter()
+            .filter_map(|(pk, _)| self.msgs.get(&(step, pk.clone())))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the positions in the committee of the members that did not
+    /// vote for `step` yet, if a vote is at most [`MAX_MISSING_VOTES`] short
+    /// of its quorum.
+    pub fn missing_votes(
+        &self,
+        committee: &Committee,
+        step: u16,
+    ) -> Option<u64> {
+        let mut voted = 0;
+        let mut shortfall = usize::MAX;
+
+        for ((_, vote), (_, cluster)) in
+            self.votes.iter().filter(|((s, _), _)| *s == step)
+        {
+            voted |= committee.bits(cluster);
+
+            let total = cluster.total_occurrences();
+            let missing = quorum_target(committee, vote).saturating_sub(total);
+            shortfall = shortfall.min(missing);
+        }
+
+        if shortfall == 0 || shortfall > MAX_MISSING_VOTES {
+            return None;
+        }
+
+        let members = committee.bits(&committee.intersect(u64::MAX));
+        let missing = members & !voted;
+        (missing != 0).then_some(missing)
+    }
+}
+
+fn quorum_target(committee: &Committee, vote: &Vote) -> usize {
+    match vote {
+        Vote::Valid(_) => committee.super_majority_quorum(),
+        _ => committee.majority_quorum(),
+    }
 }
 
 impl fmt::Display for Aggregator {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (hash, value) in self.0.iter() {
+        for (hash, value) in self.votes.iter() {
             writeln!(
                 f,
                 "hash: {:?} total: {}",
@@ -169,27 +232,29 @@ mod tests {
     use super::*;
     use crate::aggregator::Aggregator;
     use crate::commons::RoundUpdate;
+    use crate::signer::LocalSigner;
     use crate::user::committee::Committee;
     use crate::user::provisioners::{Provisioners, DUSK};
-    use crate::user::sortition::Config;
+    use crate::user::sortition::{Config, Exclusion};
     use dusk_bls12_381_sign::{PublicKey, SecretKey};
     use dusk_bytes::DeserializableSlice;
     use hex::FromHex;
     use node_data::ledger::{Header, Seed};
     use node_data::message::StepMessage;
     use std::collections::HashMap;
+    use std::sync::Arc;
 
     impl Aggregator {
         pub fn get_total(&self, step: u16, vote: Vote) -> Option<usize> {
-            if let Some(value) = self.0.get(&(step, vote)) {
+            if let Some(value) = self.votes.get(&(step, vote)) {
                 return Some(value.1.total_occurrences());
             }
             None
         }
     }
 
-    #[test]
-    fn test_collect_votes() {
+    #[tokio::test]
+    async fn test_collect_votes() {
         let sks = [
             "7f6f2ccdb23f2abb7b69278e947c01c6160a31cf02c19d06d0f6e5ab1d768b15",
             "611830d3641a68f94a690dcc25d1f4b0dac948325ac18f6dd32564371735f32c",
@@ -232,18 +297,21 @@ mod tests {
 
             p.add_member_with_value(pubkey_bls.clone(), 1000 * DUSK);
 
+            let signer = LocalSigner::new(secret_key, pubkey_bls);
             let ru = RoundUpdate::new(
-                pubkey_bls,
-                secret_key,
+                Arc::new(signer),
                 &mrb_header,
                 HashMap::new(),
+                Default::default(),
             );
 
             let msg = crate::build_validation_payload(
                 init_vote.clone(),
                 &ru,
                 iteration,
-            );
+            )
+            .await
+            .expect("signing to succeed");
 
             // Message headers to be used in test for voting for hash:
             // block_hash
@@ -251,7 +319,13 @@ mod tests {
         }
 
         // Execute sortition with specific config
-        let cfg = Config::raw(Seed::from([4u8; 48]), round, 1, 10, None);
+        let cfg = Config::raw(
+            Seed::from([4u8; 48]),
+            round,
+            1,
+            10,
+            Exclusion::default(),
+        );
         let c = Committee::new(&p, &cfg);
 
         let target_quorum = 7;
@@ -316,6 +390,18 @@ mod tests {
             collected_votes += expected_votes[i];
             assert_eq!(a.get_total(step, vote.clone()), Some(collected_votes));
 
+            // Missing votes are worth requesting only close to the quorum
+            let missing = a.missing_votes(&c, step);
+            if target_quorum - collected_votes <= MAX_MISSING_VOTES {
+                assert!(missing.is_some(), "missing votes should be returned");
+            } else {
+                assert!(missing.is_none(), "quorum is too far to be reached");
+            }
+
+            a.keep_msg(Message::new_validation(msg.clone()));
+            assert_eq!(a.msgs_of(&c, step, u64::MAX).len(), i + 1);
+            assert!(a.msgs_of(&c, step + 1, u64::MAX).is_empty());
+
             // Ensure a duplicated vote is discarded
             if i == 0 {
                 match a.collect_vote(&c, sign_info, &vote, step) {
@@ -14,46 +14,56 @@ use std::collections::HashMap;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
-use dusk_bls12_381_sign::SecretKey;
 use node_data::bls::PublicKey;
 use node_data::message::{AsyncQueue, Message, Payload};
 use node_data::StepName;
+use std::sync::Arc;
 use tracing::error;
 
+use crate::config::ConsensusParams;
+use crate::signer::ConsensusSigner;
+
 pub type TimeoutSet = HashMap<StepName, Duration>;
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Debug)]
 pub struct RoundUpdate {
     // Current round number of the ongoing consensus
     pub round: u64,
 
     // This provisioner consensus keys
     pub pubkey_bls: PublicKey,
-    pub secret_key: SecretKey,
+    pub signer: Arc<dyn ConsensusSigner>,
 
     seed: Seed,
     hash: [u8; 32],
+    timestamp: u64,
     cert: Certificate,
+    chain_id: u8,
 
     pub base_timeouts: TimeoutSet,
+
+    params: ConsensusParams,
 }
 
 impl RoundUpdate {
     pub fn new(
-        pubkey_bls: PublicKey,
-        secret_key: SecretKey,
+        signer: Arc<dyn ConsensusSigner>,
         mrb_header: &Header,
         base_timeouts: TimeoutSet,
+        params: ConsensusParams,
     ) -> Self {
         let round = mrb_header.height + 1;
         RoundUpdate {
             round,
-            pubkey_bls,
-            secret_key,
+            pubkey_bls: signer.public_key().clone(),
+            signer,
             cert: mrb_header.cert,
             hash: mrb_header.hash,
+            timestamp: mrb_header.timestamp,
             seed: mrb_header.seed,
+            chain_id: mrb_header.chain_id,
             base_timeouts,
+            params,
         }
     }
 
@@ -65,9 +75,24 @@ impl RoundUpdate {
         self.hash
     }
 
+    /// Returns the timestamp of the block this round builds on
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the chain ID messages of this round are bound to
+    pub fn chain_id(&self) -> u8 {
+        self.chain_id
+    }
+
     pub fn cert(&self) -> &Certificate {
         &self.cert
     }
+
+    /// Returns the committee sizes and quorum thresholds of the network
+    pub fn params(&self) -> &ConsensusParams {
+        &self.params
+    }
 }
 
 #[derive(Debug, Clone, Copy, Error)]
@@ -100,6 +125,7 @@ pub enum ConsensusError {
     InvalidQuorumType,
     InvalidVote(Vote),
     InvalidMsgIteration(u8),
+    InvalidChainId(u8),
     FutureEvent,
     PastEvent,
     NotCommitteeMember,
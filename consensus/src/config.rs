@@ -6,21 +6,417 @@
 
 use std::time::Duration;
 
+use node_data::ledger::{HEADER_VERSION_EXTENSIBLE, HEADER_VERSION_LEGACY};
+
+use stake_contract_types::EPOCH;
+
 /// Maximum number of iterations Consensus runs per a single round.
 pub const CONSENSUS_MAX_ITER: u8 = 255;
 
 /// Number of consecutive attested blocks needed to consider a final block.
 pub const CONSENSUS_ROLLING_FINALITY_THRESHOLD: u64 = 20;
 
-/// Percentage number that determines quorums.
+/// Default percentage numbers that determine quorums.
 pub const SUPERMAJORITY_THRESHOLD: f64 = 0.67;
 pub const MAJORITY_THRESHOLD: f64 = 0.5;
 
 /// Steps committee sizes
 pub const PROPOSAL_COMMITTEE_SIZE: usize = 1;
+/// Default voting committees sizes
 pub const VALIDATION_COMMITTEE_SIZE: usize = 64;
 pub const RATIFICATION_COMMITTEE_SIZE: usize = 64;
 
+/// Maximum size of a voting committee, as votes are aggregated over a 64-bit
+/// bitset of the committee members.
+pub const MAX_COMMITTEE_SIZE: usize = 64;
+
+/// Chain ID of the development networks, running small voting committees
+pub const DEVNET_CHAIN_ID: u8 = 0xFF;
+/// Voting committees sizes of the development networks
+pub const DEVNET_COMMITTEE_SIZE: usize = 8;
+
+/// Maximum time a block timestamp may be ahead of the local time of the
+/// nodes verifying it.
+pub const MAX_BLOCK_TIMESTAMP_DRIFT: Duration = Duration::from_secs(30);
+
+/// Height from which the block timestamps are verified on the networks not
+/// given a height of their own.
+///
+/// Like [`HOST_GAS_LIMITS_HEIGHT`], it is set ahead of their tips by the
+/// release verifying them, their blocks having been generated with
+/// timestamps possibly equal to the ones of the previous blocks.
+pub const BLOCK_TIMESTAMPS_HEIGHT: u64 = u64::MAX;
+
+/// Height from which the competing blocks are chosen by the credits of
+/// their certificates on the networks not given a height of their own.
+///
+/// Like [`HOST_GAS_LIMITS_HEIGHT`], it is set ahead of their tips by the
+/// release switching the rule, their forks having been resolved by the
+/// lowest iteration so far.
+pub const CERTIFICATE_WEIGHT_HEIGHT: u64 = u64::MAX;
+
+/// Height from which the block headers are generated with the optional
+/// fields section on the networks not given a height of their own.
+///
+/// Like [`HOST_GAS_LIMITS_HEIGHT`], it is set ahead of their tips by the
+/// release accepting them, their nodes rejecting any header version but the
+/// legacy one so far.
+pub const HEADER_EXTENSIONS_HEIGHT: u64 = u64::MAX;
+
+/// Hard bound of the boost of any stake age weighting, in basis points,
+/// enforced by [`StakeAgeWeighting::validate`]
+pub const STAKE_AGE_BOOST_BPS_LIMIT: u64 = 10_000;
+
+/// Default boost of the stake age weighting for each full epoch of
+/// eligibility, in basis points
+pub const DEFAULT_STAKE_AGE_BOOST_PER_EPOCH_BPS: u64 = 10;
+/// Default cap of the boost of the stake age weighting, in basis points,
+/// used by the networks weighting the stakes
+pub const DEFAULT_STAKE_AGE_BOOST_BPS: u64 = 500;
+
+/// Gas limit of each call made by the host while executing a block
+pub const HOST_GAS_LIMIT: u64 = 1_000_000_000;
+
+/// Height from which the host calls are bounded on the networks not given
+/// limits of their own.
+///
+/// Their blocks were accepted with unbounded host calls: the release
+/// bounding them on those networks sets the height, ahead of their tips, so
+/// that the blocks accepted before are replayed as they were executed.
+pub const HOST_GAS_LIMITS_HEIGHT: u64 = u64::MAX;
+
+/// Price every transaction is charged at least, per unit of gas
+pub const BASE_GAS_PRICE: u64 = 1;
+/// Maximum tip paid over the base price, per unit of gas
+pub const MAX_GAS_TIP: u64 = 1_000;
+
+/// Height from which the transactions are charged the effective gas price on
+/// the networks not given a pricing of their own.
+///
+/// Like [`HOST_GAS_LIMITS_HEIGHT`], it is set ahead of their tips by the
+/// release pricing them, their transactions having been charged their fee
+/// cap so far.
+pub const GAS_PRICING_HEIGHT: u64 = u64::MAX;
+
+/// Committee sizes and quorum thresholds of a network.
+///
+/// All the provisioners of a network must use the same parameters, as they
+/// determine the committees extracted and the quorums to reach. They are
+/// thus part of the protocol, and given by [`ConsensusParams::for_chain`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConsensusParams {
+    pub validation_committee_size: usize,
+    pub ratification_committee_size: usize,
+    /// Fraction of the committee needed for a `Valid` quorum
+    pub supermajority_threshold: f64,
+    /// Fraction of the committee exceeded by any other quorum
+    pub majority_threshold: f64,
+    /// Lets a lone eligible provisioner reach its own quorums without
+    /// waiting on the network or on the artificial block delay.
+    ///
+    /// Meant for CI and local development networks. It has no effect as soon
+    /// as more than one provisioner is eligible.
+    pub instant_finality: bool,
+    /// Boosts the credits of the stakes eligible for several epochs in the
+    /// voting committees, disabled if `None`.
+    ///
+    /// Since it changes the committees extracted, it is enabled per network
+    /// from a given height.
+    pub stake_age_weighting: Option<StakeAgeWeighting>,
+    /// Gas limits of the calls made by the host while executing a block
+    pub host_gas: HostGasLimits,
+    /// Pricing of the gas spent by the transactions
+    pub gas_pricing: GasPricing,
+    /// Height of the first block whose timestamp must follow the one of the
+    /// previous block, and not be ahead of the local time by more than
+    /// [`MAX_BLOCK_TIMESTAMP_DRIFT`]
+    pub block_timestamps_height: u64,
+    /// Height of the first block chosen among its competitors by the credits
+    /// of its certificate rather than by the lowest iteration
+    pub certificate_weight_height: u64,
+    /// Height of the first block whose header may be of version
+    /// [`HEADER_VERSION_EXTENSIBLE`]
+    pub header_extensions_height: u64,
+}
+
+impl Default for ConsensusParams {
+    fn default() -> Self {
+        Self {
+            validation_committee_size: VALIDATION_COMMITTEE_SIZE,
+            ratification_committee_size: RATIFICATION_COMMITTEE_SIZE,
+            supermajority_threshold: SUPERMAJORITY_THRESHOLD,
+            majority_threshold: MAJORITY_THRESHOLD,
+            instant_finality: false,
+            stake_age_weighting: None,
+            host_gas: HostGasLimits {
+                from_height: HOST_GAS_LIMITS_HEIGHT,
+                ..HostGasLimits::default()
+            },
+            gas_pricing: GasPricing {
+                from_height: GAS_PRICING_HEIGHT,
+                ..GasPricing::default()
+            },
+            block_timestamps_height: BLOCK_TIMESTAMPS_HEIGHT,
+            certificate_weight_height: CERTIFICATE_WEIGHT_HEIGHT,
+            header_extensions_height: HEADER_EXTENSIONS_HEIGHT,
+        }
+    }
+}
+
+impl ConsensusParams {
+    /// Returns the parameters of the network with the given chain ID.
+    pub fn for_chain(chain_id: u8) -> Self {
+        match chain_id {
+            DEVNET_CHAIN_ID => Self {
+                validation_committee_size: DEVNET_COMMITTEE_SIZE,
+                ratification_committee_size: DEVNET_COMMITTEE_SIZE,
+                stake_age_weighting: Some(StakeAgeWeighting::default()),
+                host_gas: HostGasLimits::default(),
+                gas_pricing: GasPricing::default(),
+                block_timestamps_height: 0,
+                certificate_weight_height: 0,
+                header_extensions_height: 0,
+                ..Self::default()
+            },
+            _ => Self::default(),
+        }
+    }
+
+    /// Returns the latest header version of the block at `block_height`,
+    /// which is also the version it is generated with.
+    pub fn header_version(&self, block_height: u64) -> u8 {
+        match block_height < self.header_extensions_height {
+            true => HEADER_VERSION_LEGACY,
+            false => HEADER_VERSION_EXTENSIBLE,
+        }
+    }
+
+    /// Checks the parameters allow quorums to be reached.
+    pub fn validate(&self) -> Result<(), String> {
+        for size in [
+            self.validation_committee_size,
+            self.ratification_committee_size,
+        ] {
+            if size == 0 || size > MAX_COMMITTEE_SIZE {
+                return Err(format!(
+                    "committee size {size} not in 1..={MAX_COMMITTEE_SIZE}"
+                ));
+            }
+        }
+
+        if !(self.majority_threshold >= 0.5 && self.majority_threshold < 1.0) {
+            return Err(format!(
+                "majority threshold {} not in [0.5, 1)",
+                self.majority_threshold
+            ));
+        }
+
+        if !(self.supermajority_threshold > self.majority_threshold
+            && self.supermajority_threshold <= 1.0)
+        {
+            return Err(format!(
+                "supermajority threshold {} not in ({}, 1]",
+                self.supermajority_threshold, self.majority_threshold
+            ));
+        }
+
+        if let Some(weighting) = &self.stake_age_weighting {
+            weighting.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the supermajority and majority quorums of a committee of the
+    /// given size.
+    pub fn quorums(&self, committee_size: usize) -> (usize, usize) {
+        let committee_size = committee_size as f64;
+
+        let super_majority =
+            (committee_size * self.supermajority_threshold).ceil() as usize;
+        let majority = (committee_size * self.majority_threshold) as usize + 1;
+
+        (super_majority, majority)
+    }
+}
+
+/// Weighting of the stakes in the sortition of the voting committees, where
+/// each full epoch a stake has been eligible for slightly boosts its weight,
+/// up to a cap.
+///
+/// It rewards long-standing stakes over freshly deposited ones, discouraging
+/// provisioners from rapidly moving their stake around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StakeAgeWeighting {
+    /// Boost of the weight for each full epoch of eligibility, in basis
+    /// points of the stake
+    pub boost_per_epoch_bps: u64,
+    /// Maximum boost of the weight, in basis points of the stake
+    pub max_boost_bps: u64,
+    /// Height of the first round whose committees are weighted
+    pub from_height: u64,
+}
+
+impl Default for StakeAgeWeighting {
+    fn default() -> Self {
+        Self {
+            boost_per_epoch_bps: DEFAULT_STAKE_AGE_BOOST_PER_EPOCH_BPS,
+            max_boost_bps: DEFAULT_STAKE_AGE_BOOST_BPS,
+            from_height: 0,
+        }
+    }
+}
+
+impl StakeAgeWeighting {
+    /// Checks the boost per epoch is within the cap `max_boost_bps`, itself
+    /// within the hard bound [`STAKE_AGE_BOOST_BPS_LIMIT`].
+    ///
+    /// The default cap [`DEFAULT_STAKE_AGE_BOOST_BPS`] is not enforced, as
+    /// networks may pick their own.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.boost_per_epoch_bps == 0
+            || self.boost_per_epoch_bps > self.max_boost_bps
+        {
+            return Err(format!(
+                "stake age boost per epoch {} not in 1..={}",
+                self.boost_per_epoch_bps, self.max_boost_bps
+            ));
+        }
+
+        if self.max_boost_bps > STAKE_AGE_BOOST_BPS_LIMIT {
+            return Err(format!(
+                "max stake age boost {} not in 1..={STAKE_AGE_BOOST_BPS_LIMIT}",
+                self.max_boost_bps
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the sortition weight of a stake of `value`, eligible since
+    /// `eligible_since`, at the given `round`.
+    ///
+    /// The weight is the value of the stake below [`Self::from_height`].
+    pub fn weight(&self, value: u64, eligible_since: u64, round: u64) -> u64 {
+        if round < self.from_height {
+            return value;
+        }
+
+        let epochs = round.saturating_sub(eligible_since) / EPOCH;
+        let boost_bps = epochs
+            .saturating_mul(self.boost_per_epoch_bps)
+            .min(self.max_boost_bps);
+        let boost = value as u128 * boost_bps as u128 / 10_000;
+
+        value.saturating_add(boost as u64)
+    }
+}
+
+/// Gas limits of the calls made by the host while executing a block, outside
+/// of any transaction.
+///
+/// They bound the compute a faulty genesis contract can consume during the
+/// block acceptance. Since exceeding a limit fails the execution of the
+/// block, they are part of the protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostGasLimits {
+    /// Limit of each call rewarding a provisioner
+    pub reward: u64,
+    /// Limit of each call penalizing a provisioner, the registration of the
+    /// fault included
+    pub slash: u64,
+    /// Limit of the refund of the unspent gas of each transaction
+    pub refund: u64,
+    /// Limit of each update of the root of the transfer and stake trees
+    pub update_root: u64,
+    /// Height of the first block whose host calls are bounded
+    pub from_height: u64,
+}
+
+impl Default for HostGasLimits {
+    fn default() -> Self {
+        Self {
+            reward: HOST_GAS_LIMIT,
+            slash: HOST_GAS_LIMIT,
+            refund: HOST_GAS_LIMIT,
+            update_root: HOST_GAS_LIMIT,
+            from_height: 0,
+        }
+    }
+}
+
+impl HostGasLimits {
+    /// Returns the limits of the host calls of the block at `block_height`,
+    /// unbounded below [`Self::from_height`].
+    pub fn at(&self, block_height: u64) -> Self {
+        match block_height < self.from_height {
+            true => Self {
+                reward: u64::MAX,
+                slash: u64::MAX,
+                refund: u64::MAX,
+                update_root: u64::MAX,
+                from_height: self.from_height,
+            },
+            false => *self,
+        }
+    }
+}
+
+/// Pricing of the gas spent by the transactions.
+///
+/// The gas price of a transaction is its fee cap, the most it is willing to
+/// pay per unit of gas, deposited upfront for its whole gas limit. It is
+/// charged the effective price instead: the base price, plus a tip made of
+/// the part of the cap above the base price, bounded by the maximum tip. The
+/// difference is refunded to the payer, along with the unspent gas.
+///
+/// Since it decides the refunds, it is part of the protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasPricing {
+    /// Price every transaction is charged at least. Transactions capped below
+    /// it cannot be executed.
+    pub base_price: u64,
+    /// Maximum tip paid over the base price, unbounded if missing
+    pub max_tip: Option<u64>,
+    /// Height of the first block whose transactions are charged the
+    /// effective price
+    pub from_height: u64,
+}
+
+impl Default for GasPricing {
+    fn default() -> Self {
+        Self {
+            base_price: BASE_GAS_PRICE,
+            max_tip: Some(MAX_GAS_TIP),
+            from_height: 0,
+        }
+    }
+}
+
+impl GasPricing {
+    /// Returns the pricing of the transactions of the block at
+    /// `block_height`, charging them their fee cap below
+    /// [`Self::from_height`].
+    pub fn at(&self, block_height: u64) -> Self {
+        match block_height < self.from_height {
+            true => Self {
+                base_price: 0,
+                max_tip: None,
+                from_height: self.from_height,
+            },
+            false => *self,
+        }
+    }
+
+    /// Returns the price a transaction capped at `fee_cap` is charged, or
+    /// `None` if the cap is below the base price.
+    pub fn effective_price(&self, fee_cap: u64) -> Option<u64> {
+        let tip = fee_cap.checked_sub(self.base_price)?;
+        let tip = self.max_tip.map_or(tip, |max_tip| tip.min(max_tip));
+        Some(self.base_price + tip)
+    }
+}
+
 /// Artifical delay on each Proposal step.
 pub const CONSENSUS_DELAY_MS: u64 = 1000;
 
@@ -31,6 +427,19 @@ pub const RELAX_ITERATION_THRESHOLD: u8 = 10;
 /// Emergency mode is enabled only for the last N iterations
 pub const EMERGENCY_MODE_ITERATION_THRESHOLD: u8 = CONSENSUS_MAX_ITER - 50;
 
+/// Maximum number of messages buffered for future rounds and steps
+pub const MAX_FUTURE_MSGS: usize = 10_000;
+
+/// Maximum number of inbound messages prioritized at once
+pub const MAX_INBOUND_BATCH: usize = 256;
+
+/// Maximum number of votes a step may be short of its quorum for the missing
+/// votes to be requested to the peers.
+pub const MAX_MISSING_VOTES: usize = 2;
+
+/// Time left before a step timeout at which the missing votes are requested.
+pub const MISSING_VOTES_REQUEST_LEAD: Duration = Duration::from_secs(2);
+
 pub const MIN_STEP_TIMEOUT: Duration = Duration::from_secs(7);
 pub const MAX_STEP_TIMEOUT: Duration = Duration::from_secs(40);
 pub const TIMEOUT_INCREASE: Duration = Duration::from_secs(2);
@@ -11,22 +11,27 @@ use crate::phase::Phase;
 
 use node_data::ledger::Block;
 
-use node_data::message::{AsyncQueue, Message, Topics};
+use node_data::message::payload::{RatificationResult, Vote};
+use node_data::message::{AsyncQueue, Message, Payload, Topics};
 
-use crate::execution_ctx::ExecutionCtx;
+use crate::execution_ctx::{instant_finality, ExecutionCtx};
 use crate::proposal;
 use crate::queue::Queue;
 use crate::quorum::task;
+use crate::user::committee::{CommitteeCache, CommitteeSet};
 use crate::user::provisioners::Provisioners;
 use crate::{ratification, validation};
-use tracing::{info, Instrument};
+use tracing::{info, warn, Instrument};
 
 use crate::iteration_ctx::IterationCtx;
-use crate::step_votes_reg::CertInfoRegistry;
+use crate::round_state::RoundState;
+use crate::step_votes_reg::{CertInfoRegistry, SafeCertificateInfoRegistry};
+use crate::vote_cache::VoteCache;
+use crate::vote_stats::{SafeVoteStats, VoteStats};
 use std::env;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tokio::sync::{oneshot, Mutex};
+use tokio::sync::{oneshot, Mutex, RwLock};
 use tokio::task::JoinHandle;
 
 pub struct Consensus<T: Operations, D: Database> {
@@ -103,18 +108,32 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
     ) -> Result<Block, ConsensusError> {
         let round = ru.round;
 
+        // Shared by validation and ratification handlers to keep track of
+        // the votes received within the round
+        let vote_stats = Arc::new(std::sync::Mutex::new(VoteStats::new(round)));
+
+        // Shared by the main and the quorum loops to extract each committee
+        // of the round only once
+        let committee_cache = CommitteeCache::default();
+
         let mut quorum_task_handle = self.quorum_process.spawn(
             ru.clone(),
             provisioners.clone(),
             self.db.clone(),
+            committee_cache.clone(),
         );
 
         let sender =
             QuorumMsgSender::new(self.quorum_process.inbound_queue.clone());
 
         // Consensus loop - proposal-validation-ratificaton loop
-        let mut main_task_handle =
-            self.spawn_main_loop(ru, provisioners, sender);
+        let mut main_task_handle = self.spawn_main_loop(
+            ru,
+            provisioners,
+            sender,
+            vote_stats.clone(),
+            committee_cache,
+        );
 
         // Wait for any of the tasks to complete.
         let result;
@@ -140,14 +159,29 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
         abort(&mut quorum_task_handle).await;
         abort(&mut main_task_handle).await;
 
+        self.report_vote_stats(&vote_stats).await;
+
         result
     }
 
+    /// Reports the statistics of the votes received within the round
+    async fn report_vote_stats(&self, vote_stats: &SafeVoteStats) {
+        let mut stats = vote_stats
+            .lock()
+            .expect("vote stats lock to be acquired")
+            .clone();
+        stats.close();
+
+        let _ = self.executor.lock().await.add_vote_stats(stats).await;
+    }
+
     fn spawn_main_loop(
         &self,
         ru: RoundUpdate,
         provisioners: Arc<Provisioners>,
         sender: QuorumMsgSender,
+        vote_stats: SafeVoteStats,
+        committee_cache: CommitteeCache,
     ) -> JoinHandle<Result<Block, ConsensusError>> {
         let inbound = self.inbound.clone();
         let outbound = self.outbound.clone();
@@ -157,12 +191,56 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
 
         tokio::spawn(async move {
             if ru.round > 0 {
-                future_msgs.lock().await.clear_round(ru.round - 1);
+                let mut future_msgs = future_msgs.lock().await;
+                future_msgs.clear_round(ru.round - 1);
+
+                let stats = future_msgs.stats();
+                info!(
+                    event = "future msgs",
+                    round = ru.round,
+                    buffered = stats.buffered,
+                    received = stats.received,
+                    evicted = stats.evicted,
+                );
             }
 
             let sv_registry =
                 Arc::new(Mutex::new(CertInfoRegistry::new(ru.clone())));
 
+            // Rejoin the round where it was left if the node restarted in
+            // the middle of it
+            let mut iter: u8 = 0;
+            let mut stored_state = None;
+            let round_state = executor.lock().await.load_round_state().await;
+            if let Some(state) = round_state {
+                if state.is_for(ru.round, &ru.hash()) {
+                    let committees_set = RwLock::new(CommitteeSet::with_cache(
+                        &provisioners,
+                        *ru.params(),
+                        committee_cache.clone(),
+                    ));
+                    let certs = sv_registry
+                        .lock()
+                        .await
+                        .restore(state.certs.clone(), &committees_set)
+                        .await;
+                    info!(
+                        event = "round state restored",
+                        round = ru.round,
+                        iter = state.iteration,
+                        certs,
+                        persisted = state.certs.len(),
+                    );
+                    iter = state.iteration.min(CONSENSUS_MAX_ITER - 1);
+                    stored_state = Some(state);
+                }
+            }
+
+            // Shared by validation and ratification handlers to avoid
+            // re-verifying the same vote within the round
+            let vote_cache =
+                Arc::new(std::sync::Mutex::new(VoteCache::default()));
+
             let proposal_handler = Arc::new(Mutex::new(
                 proposal::handler::ProposalHandler::new(db.clone()),
             ));
@@ -170,12 +248,16 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
             let validation_handler = Arc::new(Mutex::new(
                 validation::handler::ValidationHandler::new(
                     sv_registry.clone(),
+                    vote_cache.clone(),
+                    vote_stats.clone(),
                 ),
             ));
 
             let ratification_handler = Arc::new(Mutex::new(
                 ratification::handler::RatificationHandler::new(
                     sv_registry.clone(),
+                    vote_cache,
+                    vote_stats,
                 ),
             ));
 
@@ -198,7 +280,6 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
             // Consensus loop
             // Initialize and run consensus loop
 
-            let mut iter: u8 = 0;
             let mut iter_ctx = IterationCtx::new(
                 ru.round,
                 iter,
@@ -206,8 +287,11 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
                 validation_handler,
                 ratification_handler,
                 ru.base_timeouts.clone(),
+                committee_cache,
             );
 
+            let instant = instant_finality(&ru, &provisioners);
+
             while iter < CONSENSUS_MAX_ITER {
                 Self::consensus_delay().await;
 
@@ -255,8 +339,30 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
                     // During execution of any step we may encounter that an
                     // quorum is generated for a former or current iteration.
                     if msg.topic() == Topics::Quorum {
+                        // The quorum of a lone provisioner is the outcome of
+                        // the round, there is no need to wait for the quorum
+                        // loop to verify it again
+                        if instant {
+                            if let Some(block) =
+                                Self::winning_block(&msg, &db).await
+                            {
+                                outbound.send(msg).await.unwrap_or_else(
+                                    |err| warn!("quorum not published {err}"),
+                                );
+                                return Ok(block);
+                            }
+                        }
                         sender.send_quorum(msg.clone()).await;
                     }
+
+                    Self::store_round_state(
+                        &executor,
+                        &sv_registry,
+                        &ru,
+                        iter,
+                        &mut stored_state,
+                    )
+                    .await;
                 }
 
                 iter_ctx.on_close();
@@ -269,6 +375,54 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
         })
     }
 
+    /// Persists the state of the round, for a restarted node to rejoin it.
+    ///
+    /// The state is only written if it changed since the `stored` one, that
+    /// is when the iteration moved on or a certificate was collected.
+    async fn store_round_state(
+        executor: &Arc<Mutex<T>>,
+        sv_registry: &SafeCertificateInfoRegistry,
+        ru: &RoundUpdate,
+        iteration: u8,
+        stored: &mut Option<RoundState>,
+    ) {
+        let state = RoundState {
+            round: ru.round,
+            prev_block_hash: ru.hash(),
+            iteration,
+            certs: sv_registry.lock().await.snapshot(),
+        };
+        if stored.as_ref() == Some(&state) {
+            return;
+        }
+
+        let res = executor.lock().await.store_round_state(state.clone()).await;
+        match res {
+            Ok(_) => *stored = Some(state),
+            Err(err) => {
+                warn!(event = "round state not stored", round = ru.round, ?err)
+            }
+        }
+    }
+
+    /// Returns the winning block certified by `msg`, if it is a quorum on a
+    /// valid candidate.
+    async fn winning_block(msg: &Message, db: &Arc<Mutex<D>>) -> Option<Block> {
+        let Payload::Quorum(quorum) = &msg.payload else {
+            return None;
+        };
+        let RatificationResult::Success(Vote::Valid(hash)) =
+            &quorum.cert.result
+        else {
+            return None;
+        };
+
+        let db = db.lock().await;
+        let mut block = db.get_candidate_block_by_hash(hash).await.ok()?;
+        block.set_certificate(quorum.cert);
+        Some(block)
+    }
+
     async fn consensus_delay() {
         let spin_time: u64 = env::var("RUSK_CONSENSUS_SPIN_TIME")
             .unwrap_or_default()
@@ -13,18 +13,24 @@ use crate::queue::Queue;
 use crate::step_votes_reg::SafeCertificateInfoRegistry;
 use crate::user::committee::Committee;
 use crate::user::provisioners::Provisioners;
-use crate::user::sortition;
+use crate::user::sortition::{self, Exclusion};
 
-use node_data::bls::PublicKeyBytes;
+use dusk_bytes::Serializable;
 use node_data::ledger::Block;
-use node_data::message::{AsyncQueue, Message, Payload};
+use node_data::message::{
+    AsyncQueue, ConsensusHeader, Message, Payload, SignInfo,
+};
 
 use node_data::StepName;
 
-use crate::config::EMERGENCY_MODE_ITERATION_THRESHOLD;
+use crate::config::{
+    EMERGENCY_MODE_ITERATION_THRESHOLD, MAX_INBOUND_BATCH,
+    MISSING_VOTES_REQUEST_LEAD,
+};
 use crate::ratification::step::RatificationStep;
 use crate::validation::step::ValidationStep;
-use node_data::message::payload::{QuorumType, ValidationResult};
+use node_data::message::payload::{GetVotes, QuorumType, ValidationResult};
+use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio::time;
@@ -56,6 +62,23 @@ pub struct ExecutionCtx<'a, DB: Database, T> {
     quorum_sender: QuorumMsgSender,
 }
 
+/// Returns true if the node is the only eligible provisioner of the round
+/// `ru` of an instant finality network.
+pub(crate) fn instant_finality(
+    ru: &RoundUpdate,
+    provisioners: &Provisioners,
+) -> bool {
+    if !ru.params().instant_finality {
+        return false;
+    }
+
+    let mut eligibles = provisioners.eligibles(ru.round);
+    match (eligibles.next(), eligibles.next()) {
+        (Some((pk, _)), None) => pk == &ru.pubkey_bls,
+        _ => false,
+    }
+}
+
 impl<'a, DB: Database, T: Operations + 'static> ExecutionCtx<'a, DB, T> {
     /// Creates step execution context.
     #[allow(clippy::too_many_arguments)]
@@ -105,8 +128,27 @@ impl<'a, DB: Database, T: Operations + 'static> ExecutionCtx<'a, DB, T> {
         committee.is_member(&self.round_update.pubkey_bls)
     }
 
-    pub(crate) fn save_committee(&mut self, committee: Committee) {
-        self.iter_ctx.committees.insert(self.step(), committee);
+    /// Returns true if this node may short-circuit the steps of the current
+    /// round, being the only eligible provisioner of an instant finality
+    /// network.
+    ///
+    /// The votes cast are the ones of a normal round, so the resulting
+    /// certificates verify as usual.
+    pub(crate) fn instant_finality(&self) -> bool {
+        instant_finality(&self.round_update, self.provisioners)
+    }
+
+    /// Extracts the committee of the current step, unless already extracted
+    /// within the round, and keeps it as the current committee.
+    pub(crate) fn extract_committee(
+        &mut self,
+        exclusion: Exclusion,
+    ) -> &Committee {
+        let cfg = self.get_sortition_config(exclusion);
+        let step = self.step();
+        self.iter_ctx
+            .committees
+            .extract(step, self.provisioners, &cfg)
     }
 
     pub(crate) fn get_current_committee(&self) -> Option<&Committee> {
@@ -121,6 +163,9 @@ impl<'a, DB: Database, T: Operations + 'static> ExecutionCtx<'a, DB, T> {
     /// In an event of timeout, it also increases the step timeout value
     /// accordingly.
     ///
+    /// Shortly before the timeout, the votes missing for the step to reach a
+    /// quorum are requested to the peers.
+    ///
     /// By design, the loop is terminated by aborting the consensus task.
     pub async fn event_loop<C: MsgHandler>(
         &mut self,
@@ -130,24 +175,47 @@ impl<'a, DB: Database, T: Operations + 'static> ExecutionCtx<'a, DB, T> {
 
         let timeout = self.iter_ctx.get_timeout(self.step_name());
         let deadline = Instant::now().checked_add(timeout).unwrap();
+        let request_at = deadline
+            .checked_sub(MISSING_VOTES_REQUEST_LEAD)
+            .unwrap_or(deadline);
+        let mut votes_requested = false;
 
         let inbound = self.inbound.clone();
 
         // Handle both timeout event and messages from inbound queue.
         loop {
-            match time::timeout_at(deadline, inbound.recv()).await {
+            let wake_at = if votes_requested {
+                deadline
+            } else {
+                request_at
+            };
+            let next = match self.iter_ctx.leftovers.pop_front() {
+                Some(msg) => Ok(Ok(msg)),
+                None => time::timeout_at(wake_at, inbound.recv()).await,
+            };
+            match next {
                 // Inbound message event
                 Ok(Ok(msg)) => {
-                    if let Some(step_result) =
-                        self.process_inbound_msg(phase.clone(), msg).await
-                    {
-                        self.report_elapsed_time().await;
-                        return Ok(step_result);
+                    let mut batch = self.prioritized_batch(msg);
+                    while let Some(msg) = batch.pop_front() {
+                        if let Some(step_result) =
+                            self.process_inbound_msg(phase.clone(), msg).await
+                        {
+                            self.requeue(batch);
+                            self.report_elapsed_time().await;
+                            return Ok(step_result);
+                        }
                     }
                 }
                 Ok(Err(e)) => {
                     warn!("Error while receiving msg: {e}");
                 }
+                // Phase is about to time out. Ask the peers for the votes it
+                // may be missing.
+                Err(_) if !votes_requested => {
+                    votes_requested = true;
+                    self.request_missing_votes(phase.clone()).await;
+                }
                 // Timeout event. Phase could not reach its final goal.
                 // Increase timeout for next execution of this step and move on.
                 Err(_) => {
@@ -159,6 +227,155 @@ impl<'a, DB: Database, T: Operations + 'static> ExecutionCtx<'a, DB, T> {
         }
     }
 
+    /// Requests to the peers the votes missing for the current step to reach
+    /// a quorum, if it is close enough to one.
+    async fn request_missing_votes<C: MsgHandler>(&self, phase: Arc<Mutex<C>>) {
+        let committee = match self.get_current_committee() {
+            Some(committee) => committee,
+            None => return,
+        };
+
+        let step = self.step();
+        let signers = match phase.lock().await.missing_votes(step, committee) {
+            Some(signers) => signers,
+            None => return,
+        };
+
+        info!(
+            event = "request missing votes",
+            step,
+            signers = format!("{signers:#066b}"),
+        );
+
+        let header = ConsensusHeader {
+            chain_id: self.round_update.chain_id(),
+            prev_block_hash: self.round_update.hash(),
+            round: self.round_update.round,
+            iteration: self.iteration,
+        };
+        let mut req = GetVotes {
+            header,
+            step,
+            signers,
+            sign_info: SignInfo::default(),
+        };
+
+        let signer = self.round_update.signer.as_ref();
+        match signer.sign(&req.signable()).await {
+            Ok(signature) => {
+                req.sign_info.signature = signature.to_bytes().into();
+                req.sign_info.signer = signer.public_key().clone();
+            }
+            Err(err) => {
+                error!("unable to sign a missing votes request {err}");
+                return;
+            }
+        }
+
+        self.outbound
+            .send(Message::new_get_votes(req))
+            .await
+            .unwrap_or_else(|err| {
+                error!("unable to send a missing votes request {:?}", err)
+            });
+    }
+
+    /// Sends the votes requested by a peer, if collected, back to it only.
+    ///
+    /// Requests are only served to the members of the step committee, once
+    /// per step. The cheap checks are run before the signature of the
+    /// request is verified.
+    async fn serve_missing_votes(&mut self, msg: &Message, req: &GetVotes) {
+        let Some(src_addr) = msg.metadata.as_ref().map(|md| md.src_addr) else {
+            return;
+        };
+
+        if req.header.chain_id != self.round_update.chain_id()
+            || req.header.round != self.round_update.round
+            || req.header.prev_block_hash != self.round_update.hash()
+        {
+            return;
+        }
+
+        let requester = req.sign_info.signer.clone();
+        let is_member = self
+            .iter_ctx
+            .committees
+            .get_committee(req.step)
+            .is_some_and(|committee| committee.is_member(&requester));
+        if !is_member {
+            return;
+        }
+
+        let key = (req.step, *requester.bytes());
+        if self.iter_ctx.served_votes.contains(&key) {
+            return;
+        }
+        if !msg.is_sig_verified() && req.verify_signature().is_err() {
+            return;
+        }
+        self.iter_ctx.served_votes.insert(key);
+
+        let votes = self
+            .iter_ctx
+            .votes_of(req.header.iteration, req.step, req.signers)
+            .await;
+
+        debug!(
+            event = "serve missing votes",
+            step = req.step,
+            requested = req.signers.count_ones(),
+            served = votes.len(),
+            to = ?src_addr,
+        );
+
+        for mut vote in votes {
+            vote.metadata = None;
+            vote.recipient = Some(src_addr);
+            self.outbound.send(vote).await.unwrap_or_else(|err| {
+                error!("unable to send a requested vote {:?}", err)
+            });
+        }
+    }
+
+    /// Collects the messages left by the previous step and the ones already
+    /// waiting in the inbound queue along with `first`, ordered so that
+    /// messages of the current round and iteration are processed before past
+    /// and future ones.
+    fn prioritized_batch(&mut self, first: Message) -> VecDeque<Message> {
+        let mut batch = vec![first];
+        batch.extend(self.iter_ctx.leftovers.drain(..));
+        while batch.len() < MAX_INBOUND_BATCH {
+            match self.inbound.try_recv() {
+                Ok(msg) => batch.push(msg),
+                Err(_) => break,
+            }
+        }
+
+        // Stable sort, preserving the arrival order within a priority class
+        batch.sort_by_key(|msg| self.priority(msg));
+        batch.into()
+    }
+
+    /// Returns the processing priority of a message, lower being first.
+    fn priority(&self, msg: &Message) -> u8 {
+        let round = self.round_update.round;
+        match msg.header.round {
+            r if r == round && msg.header.iteration == self.iteration => 0,
+            r if r == round => 1,
+            r if r > round => 2,
+            _ => 3,
+        }
+    }
+
+    /// Keeps the messages left unprocessed once the step is over, so that
+    /// the next step handles them before the inbound queue.
+    ///
+    /// They are not put back in the inbound queue, which may be full.
+    fn requeue(&mut self, batch: VecDeque<Message>) {
+        self.iter_ctx.leftovers.extend(batch);
+    }
+
     /// Cast a validation vote for a candidate that originates from former
     /// iteration
     pub(crate) async fn try_cast_validation_vote(&mut self, candidate: &Block) {
@@ -172,7 +389,7 @@ impl<'a, DB: Database, T: Operations + 'static> ExecutionCtx<'a, DB, T> {
                     Some(candidate),
                     &self.round_update,
                     self.outbound.clone(),
-                    self.inbound.clone(),
+                    Some(self.inbound.clone()),
                     self.client.clone(),
                 )
                 .await;
@@ -273,6 +490,11 @@ impl<'a, DB: Database, T: Operations + 'static> ExecutionCtx<'a, DB, T> {
         phase: Arc<Mutex<C>>,
         msg: Message,
     ) -> Option<Message> {
+        if let Payload::GetVotes(req) = &msg.payload {
+            self.serve_missing_votes(&msg, req).await;
+            return None;
+        }
+
         let committee = self
             .get_current_committee()
             .expect("committee to be created before run");
@@ -435,7 +657,7 @@ impl<'a, DB: Database, T: Operations + 'static> ExecutionCtx<'a, DB, T> {
 
     pub fn get_sortition_config(
         &self,
-        exclusion: Option<PublicKeyBytes>,
+        exclusion: Exclusion,
     ) -> sortition::Config {
         sortition::Config::new(
             self.round_update.seed(),
@@ -443,6 +665,7 @@ impl<'a, DB: Database, T: Operations + 'static> ExecutionCtx<'a, DB, T> {
             self.iteration,
             self.step_name(),
             exclusion,
+            self.round_update.params(),
         )
     }
 
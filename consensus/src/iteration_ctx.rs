@@ -12,13 +12,15 @@ use crate::config::{MAX_STEP_TIMEOUT, TIMEOUT_INCREASE};
 use crate::msg_handler::HandleMsgOutput;
 use crate::msg_handler::MsgHandler;
 
-use crate::user::committee::Committee;
+use crate::user::committee::{Committee, CommitteeCache};
+use crate::user::provisioners::Provisioners;
+use crate::user::sortition;
 
 use crate::{proposal, ratification, validation};
 use node_data::bls::PublicKeyBytes;
 
 use node_data::message::Message;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::Add;
 use std::sync::Arc;
 use std::time::Duration;
@@ -29,14 +31,25 @@ use node_data::StepName;
 use tracing::debug;
 
 /// A pool of all generated committees
+///
+/// The committees are extracted through a [`CommitteeCache`] shared with the
+/// other handlers of the round, so that each is only extracted once.
 #[derive(Default)]
 pub struct RoundCommittees {
-    committees: HashMap<u16, Committee>,
+    committees: HashMap<u16, Arc<Committee>>,
+    cache: CommitteeCache,
 }
 
 impl RoundCommittees {
+    pub(crate) fn new(cache: CommitteeCache) -> Self {
+        Self {
+            committees: HashMap::new(),
+            cache,
+        }
+    }
+
     pub(crate) fn get_committee(&self, step: u16) -> Option<&Committee> {
-        self.committees.get(&step)
+        self.committees.get(&step).map(Arc::as_ref)
     }
 
     pub(crate) fn get_generator(&self, iter: u8) -> Option<PublicKeyBytes> {
@@ -53,8 +66,17 @@ impl RoundCommittees {
         self.get_committee(step)
     }
 
-    pub(crate) fn insert(&mut self, step: u16, committee: Committee) {
+    /// Extracts the committee of `step` with `cfg`, unless cached, and
+    /// keeps it for the step.
+    pub(crate) fn extract(
+        &mut self,
+        step: u16,
+        provisioners: &Provisioners,
+        cfg: &sortition::Config,
+    ) -> &Committee {
+        let committee = self.cache.get_or_create(provisioners, cfg);
         self.committees.insert(step, committee);
+        self.get_committee(step).expect("committee to be inserted")
     }
 }
 
@@ -77,6 +99,14 @@ pub struct IterationCtx<DB: Database> {
 
     /// Implements the adaptive timeout algorithm
     timeouts: TimeoutSet,
+
+    /// Inbound messages left unprocessed by the last step, handled first by
+    /// the next one
+    pub(crate) leftovers: VecDeque<Message>,
+
+    /// Steps and requesters whose request for missing votes was served, each
+    /// requester being served once per step
+    pub(crate) served_votes: HashSet<(u16, PublicKeyBytes)>,
 }
 
 impl<D: Database> IterationCtx<D> {
@@ -89,6 +119,7 @@ impl<D: Database> IterationCtx<D> {
             Mutex<ratification::handler::RatificationHandler>,
         >,
         timeouts: TimeoutSet,
+        committee_cache: CommitteeCache,
     ) -> Self {
         Self {
             round,
@@ -97,8 +128,10 @@ impl<D: Database> IterationCtx<D> {
             proposal_handler,
             validation_handler,
             ratification_handler,
-            committees: Default::default(),
+            committees: RoundCommittees::new(committee_cache),
             timeouts,
+            leftovers: VecDeque::new(),
+            served_votes: HashSet::new(),
         }
     }
 
@@ -175,6 +208,30 @@ impl<D: Database> IterationCtx<D> {
 
         None
     }
+
+    /// Returns the collected votes for `step` of an iteration of the round
+    /// cast by the committee members at the positions set in `signers`.
+    pub(crate) async fn votes_of(
+        &self,
+        iteration: u8,
+        step: u16,
+        signers: u64,
+    ) -> Vec<Message> {
+        let committee = match self.committees.get_committee(step) {
+            Some(committee) => committee,
+            None => return vec![],
+        };
+
+        if step == StepName::Validation.to_step(iteration) {
+            let handler = self.validation_handler.lock().await;
+            handler.votes_of(step, committee, signers)
+        } else if step == StepName::Ratification.to_step(iteration) {
+            let handler = self.ratification_handler.lock().await;
+            handler.votes_of(step, committee, signers)
+        } else {
+            vec![]
+        }
+    }
 }
 
 impl<DB: Database> Drop for IterationCtx<DB> {
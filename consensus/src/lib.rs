@@ -20,8 +20,12 @@ mod proposal;
 mod queue;
 pub mod quorum;
 mod ratification;
+pub mod round_state;
+pub mod signer;
 mod step_votes_reg;
 mod validation;
+mod vote_cache;
+pub mod vote_stats;
 
 pub use ratification::step::build_ratification_payload;
 pub use validation::step::build_validation_payload;
@@ -4,6 +4,8 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+pub(crate) mod step_state;
+
 use crate::commons::{ConsensusError, RoundUpdate};
 use crate::iteration_ctx::RoundCommittees;
 use crate::user::committee::Committee;
@@ -47,6 +49,11 @@ pub trait MsgHandler {
 
         trace!(event = "msg received", msg = format!("{:#?}", msg),);
 
+        // Discard messages signed for a different network
+        if msg.header.chain_id != ru.chain_id() {
+            return Err(ConsensusError::InvalidChainId(msg.header.chain_id));
+        }
+
         match msg.compare(ru.round, iteration, step) {
             Status::Past => Err(ConsensusError::PastEvent),
             Status::Present => {
@@ -96,4 +103,21 @@ pub trait MsgHandler {
 
     /// handle_timeout allows each Phase to handle a timeout event.
     fn handle_timeout(&self) -> Result<HandleMsgOutput, ConsensusError>;
+
+    /// missing_votes returns the positions in `committee` of the members
+    /// whose votes for `step` are worth requesting to the peers, if any.
+    fn missing_votes(&self, _step: u16, _committee: &Committee) -> Option<u64> {
+        None
+    }
+
+    /// votes_of returns the collected votes for `step` of the members at the
+    /// positions set in `signers`.
+    fn votes_of(
+        &self,
+        _step: u16,
+        _committee: &Committee,
+        _signers: u64,
+    ) -> Vec<Message> {
+        vec![]
+    }
 }
@@ -0,0 +1,423 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::cmp::Ordering;
+
+use node_data::ledger::StepVotes;
+use node_data::message::payload::{Ratification, Validation, Vote};
+use node_data::message::{Message, StepMessage};
+use node_data::StepName;
+use tracing::warn;
+
+use crate::aggregator::Aggregator;
+use crate::commons::ConsensusError;
+use crate::step_votes_reg::SafeCertificateInfoRegistry;
+use crate::user::committee::Committee;
+use crate::vote_stats::{record_invalid_signature, record_vote, SafeVoteStats};
+
+/// A vote handled by a voting step.
+pub(crate) trait VoteMsg: StepMessage + Clone {
+    fn vote(&self) -> &Vote;
+    fn into_msg(self) -> Message;
+}
+
+impl VoteMsg for Validation {
+    fn vote(&self) -> &Vote {
+        &self.vote
+    }
+
+    fn into_msg(self) -> Message {
+        Message::new_validation(self)
+    }
+}
+
+impl VoteMsg for Ratification {
+    fn vote(&self) -> &Vote {
+        &self.vote
+    }
+
+    fn into_msg(self) -> Message {
+        Message::new_ratification(self)
+    }
+}
+
+/// Position of the iteration of a vote relative to the iteration the step
+/// handler is reset to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Route {
+    Past,
+    Current,
+    Future,
+}
+
+impl Route {
+    pub(crate) fn of(iteration: u8, curr_iteration: u8) -> Self {
+        match iteration.cmp(&curr_iteration) {
+            Ordering::Less => Route::Past,
+            Ordering::Equal => Route::Current,
+            Ordering::Greater => Route::Future,
+        }
+    }
+}
+
+/// Bookkeeping shared by the voting steps: the iteration the step is reset
+/// to and the votes collected for all the iterations of the round.
+///
+/// Votes of the current iteration are collected with [`Self::collect`] while
+/// the step runs. Once the step is over, the votes of its iteration and of
+/// the former ones are collected with [`Self::collect_past`]. Votes of future
+/// iterations are rejected by both.
+pub(crate) struct StepState {
+    step: StepName,
+    iteration: u8,
+    aggregator: Aggregator,
+    sv_registry: SafeCertificateInfoRegistry,
+    vote_stats: SafeVoteStats,
+}
+
+impl StepState {
+    pub(crate) fn new(
+        step: StepName,
+        sv_registry: SafeCertificateInfoRegistry,
+        vote_stats: SafeVoteStats,
+    ) -> Self {
+        Self {
+            step,
+            iteration: 0,
+            aggregator: Aggregator::default(),
+            sv_registry,
+            vote_stats,
+        }
+    }
+
+    /// Resets the state to `iteration`, keeping the votes collected so far.
+    pub(crate) fn reset(&mut self, iteration: u8) {
+        self.iteration = iteration;
+    }
+
+    fn route(&self, iteration: u8) -> Route {
+        Route::of(iteration, self.iteration)
+    }
+
+    pub(crate) fn record_invalid_signature<M: StepMessage>(&self, msg: &M) {
+        record_invalid_signature(&self.vote_stats, msg);
+    }
+
+    /// Collects a vote of the current iteration.
+    ///
+    /// Returns the votes aggregated so far for the same vote and whether
+    /// they reach the quorum.
+    pub(crate) async fn collect<M: VoteMsg>(
+        &mut self,
+        msg: &M,
+        committee: &Committee,
+    ) -> Result<(StepVotes, bool), ConsensusError> {
+        let iteration = msg.header().iteration;
+        if self.route(iteration) != Route::Current {
+            // Votes of a former iteration must be collected with
+            // collect_past
+            return Err(ConsensusError::InvalidMsgIteration(iteration));
+        }
+
+        let (sv, quorum_reached) = self.aggregate(msg, committee)?;
+        _ = self.register(msg, committee, sv, quorum_reached).await;
+
+        Ok((sv, quorum_reached))
+    }
+
+    /// Collects a vote of an iteration whose step is over.
+    ///
+    /// Returns the quorum message if the vote completes the certificate of
+    /// its iteration.
+    pub(crate) async fn collect_past<M: VoteMsg>(
+        &mut self,
+        msg: &M,
+        committee: &Committee,
+    ) -> Result<Option<Message>, ConsensusError> {
+        let iteration = msg.header().iteration;
+        if self.route(iteration) == Route::Future {
+            return Err(ConsensusError::InvalidMsgIteration(iteration));
+        }
+
+        let (sv, quorum_reached) = self.aggregate(msg, committee)?;
+        Ok(self.register(msg, committee, sv, quorum_reached).await)
+    }
+
+    fn aggregate<M: VoteMsg>(
+        &mut self,
+        msg: &M,
+        committee: &Committee,
+    ) -> Result<(StepVotes, bool), ConsensusError> {
+        let collect_vote = self.aggregator.collect_vote(
+            committee,
+            msg.sign_info(),
+            msg.vote(),
+            msg.get_step(),
+        );
+        record_vote(&self.vote_stats, msg, committee, &collect_vote);
+
+        let collected = collect_vote.map_err(|error| {
+            warn!(
+                event = "Cannot collect vote",
+                ?error,
+                from = msg.sign_info().signer.to_bs58(),
+                vote = ?msg.vote(),
+                msg_step = msg.get_step(),
+                msg_round = msg.header().round,
+            );
+            ConsensusError::InvalidVote(*msg.vote())
+        })?;
+        self.aggregator.keep_msg(msg.clone().into_msg());
+
+        Ok(collected)
+    }
+
+    /// Records the aggregated votes in the round registry.
+    async fn register<M: VoteMsg>(
+        &self,
+        msg: &M,
+        committee: &Committee,
+        sv: StepVotes,
+        quorum_reached: bool,
+    ) -> Option<Message> {
+        self.sv_registry.lock().await.add_step_votes(
+            msg.header().iteration,
+            msg.vote(),
+            sv,
+            self.step,
+            quorum_reached,
+            committee
+                .excluded()
+                .generator()
+                .expect("Generator to be excluded"),
+        )
+    }
+
+    pub(crate) fn missing_votes(
+        &self,
+        step: u16,
+        committee: &Committee,
+    ) -> Option<u64> {
+        self.aggregator.missing_votes(committee, step)
+    }
+
+    pub(crate) fn votes_of(
+        &self,
+        step: u16,
+        committee: &Committee,
+        signers: u64,
+    ) -> Vec<Message> {
+        self.aggregator.msgs_of(committee, step, signers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use dusk_bls12_381_sign::{PublicKey as BlsPublicKey, SecretKey};
+    use node_data::bls::PublicKey;
+    use node_data::ledger::{Header, Seed};
+    use node_data::message::payload::Validation;
+    use node_data::message::{ConsensusHeader, SignInfo};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::commons::RoundUpdate;
+    use crate::signer::LocalSigner;
+    use crate::step_votes_reg::CertInfoRegistry;
+    use crate::user::provisioners::{Provisioners, DUSK};
+    use crate::user::sortition::{Config, Exclusion};
+    use crate::vote_stats::VoteStats;
+
+    const ROUND: u64 = 1;
+    const ITERATIONS: u8 = 6;
+
+    struct Fixture {
+        keys: Vec<(SecretKey, PublicKey)>,
+        provisioners: Provisioners,
+        ru: RoundUpdate,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let mut rng = StdRng::seed_from_u64(0xbeef);
+            let keys: Vec<_> = (0..10)
+                .map(|_| {
+                    let sk = SecretKey::random(&mut rng);
+                    let pk = PublicKey::new(BlsPublicKey::from(&sk));
+                    (sk, pk)
+                })
+                .collect();
+
+            let mut provisioners = Provisioners::empty();
+            for (_, pk) in &keys {
+                provisioners.add_member_with_value(pk.clone(), 1000 * DUSK);
+            }
+
+            let (sk, pk) = keys[0].clone();
+            let ru = RoundUpdate::new(
+                Arc::new(LocalSigner::new(sk, pk)),
+                &Header::default(),
+                HashMap::new(),
+                Default::default(),
+            );
+
+            Self {
+                keys,
+                provisioners,
+                ru,
+            }
+        }
+
+        fn state(&self) -> StepState {
+            StepState::new(
+                StepName::Validation,
+                Arc::new(tokio::sync::Mutex::new(CertInfoRegistry::new(
+                    self.ru.clone(),
+                ))),
+                Arc::new(Mutex::new(VoteStats::new(ROUND))),
+            )
+        }
+
+        fn committee(&self, iteration: u8) -> Committee {
+            let generator = self.keys[0].1.bytes();
+            let cfg = Config::raw(
+                Seed::from([4u8; 48]),
+                ROUND,
+                StepName::Validation.to_step(iteration),
+                10,
+                Exclusion::from_generator(*generator),
+            );
+            Committee::new(&self.provisioners, &cfg)
+        }
+
+        /// Returns a vote of a member of the committee of `iteration`.
+        fn vote(&self, iteration: u8, committee: &Committee) -> Validation {
+            let (sk, pk) = self
+                .keys
+                .iter()
+                .find(|(_, pk)| committee.is_member(pk))
+                .expect("the committee to have a member");
+
+            let mut validation = Validation {
+                header: ConsensusHeader {
+                    round: ROUND,
+                    iteration,
+                    ..Default::default()
+                },
+                vote: Vote::Valid([1u8; 32]),
+                sign_info: SignInfo::default(),
+            };
+            validation.sign(sk, pk.inner());
+            validation
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_routes_current_iteration_only() {
+        let fixture = Fixture::new();
+
+        for curr in 0..ITERATIONS {
+            for iteration in 0..ITERATIONS {
+                let committee = fixture.committee(iteration);
+                let vote = fixture.vote(iteration, &committee);
+
+                let mut state = fixture.state();
+                state.reset(curr);
+                let res = state.collect(&vote, &committee).await;
+
+                match Route::of(iteration, curr) {
+                    Route::Current => assert!(res.is_ok(), "{iteration}"),
+                    Route::Past | Route::Future => assert!(matches!(
+                        res,
+                        Err(ConsensusError::InvalidMsgIteration(i)) if i == iteration
+                    )),
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_past_routes_former_iterations() {
+        let fixture = Fixture::new();
+
+        for curr in 0..ITERATIONS {
+            for iteration in 0..ITERATIONS {
+                let committee = fixture.committee(iteration);
+                let vote = fixture.vote(iteration, &committee);
+
+                let mut state = fixture.state();
+                state.reset(curr);
+                let res = state.collect_past(&vote, &committee).await;
+
+                match Route::of(iteration, curr) {
+                    Route::Past | Route::Current => {
+                        assert!(res.is_ok(), "{iteration}")
+                    }
+                    Route::Future => assert!(matches!(
+                        res,
+                        Err(ConsensusError::InvalidMsgIteration(i)) if i == iteration
+                    )),
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_votes_are_kept_across_resets() {
+        let fixture = Fixture::new();
+        let committee = fixture.committee(0);
+        let vote = fixture.vote(0, &committee);
+
+        let mut state = fixture.state();
+        state
+            .collect(&vote, &committee)
+            .await
+            .expect("vote collected");
+
+        // The vote is not collected again, whatever the route it takes
+        state.reset(1);
+        let res = state.collect_past(&vote, &committee).await;
+        assert!(matches!(res, Err(ConsensusError::InvalidVote(_))));
+
+        // Future votes are rejected before being aggregated
+        let committee = fixture.committee(2);
+        let vote = fixture.vote(2, &committee);
+        let res = state.collect(&vote, &committee).await;
+        assert!(matches!(res, Err(ConsensusError::InvalidMsgIteration(2))));
+        state.reset(2);
+        state
+            .collect(&vote, &committee)
+            .await
+            .expect("vote collected");
+    }
+
+    #[test]
+    fn test_route() {
+        for curr in 0..=u8::MAX {
+            for iteration in 0..=u8::MAX {
+                let route = Route::of(iteration, curr);
+
+                // Exactly one iteration is current
+                assert_eq!(route == Route::Current, iteration == curr);
+                // Past and future iterations are on opposite sides
+                assert_eq!(route == Route::Past, iteration < curr);
+                assert_eq!(route == Route::Future, iteration > curr);
+                // Routing is antisymmetric
+                let reversed = match route {
+                    Route::Past => Route::Future,
+                    Route::Current => Route::Current,
+                    Route::Future => Route::Past,
+                };
+                assert_eq!(Route::of(curr, iteration), reversed);
+            }
+        }
+    }
+}
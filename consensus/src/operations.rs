@@ -8,9 +8,12 @@ use std::fmt;
 use std::time::Duration;
 
 use dusk_bls12_381_sign::PublicKey;
-use node_data::ledger::{Block, Header, SpentTransaction, Transaction};
+use node_data::ledger::{Block, Header, Seed, SpentTransaction, Transaction};
 use node_data::StepName;
 
+use crate::round_state::RoundState;
+use crate::vote_stats::VoteStats;
+
 pub type StateRoot = [u8; 32];
 pub type EventHash = [u8; 32];
 
@@ -26,6 +29,10 @@ pub struct CallParams {
     pub block_gas_limit: u64,
     pub generator_pubkey: node_data::bls::PublicKey,
     pub missed_generators: Vec<PublicKey>,
+    /// Timestamp of the block to generate
+    pub timestamp: u64,
+    /// Seed of the block to generate
+    pub seed: Seed,
 }
 
 #[derive(Default)]
@@ -35,7 +42,7 @@ pub struct Output {
     pub discarded_txs: Vec<Transaction>,
 }
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct VerificationOutput {
     pub state_root: StateRoot,
     pub event_hash: EventHash,
@@ -76,4 +83,12 @@ pub trait Operations: Send + Sync {
         step_name: StepName,
         elapsed: Duration,
     ) -> Result<(), Error>;
+
+    async fn add_vote_stats(&self, stats: VoteStats) -> Result<(), Error>;
+
+    /// Persists the state of the ongoing round, replacing the previous one.
+    async fn store_round_state(&self, state: RoundState) -> Result<(), Error>;
+
+    /// Returns the last persisted round state, if any.
+    async fn load_round_state(&self) -> Option<RoundState>;
 }
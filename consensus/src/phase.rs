@@ -7,7 +7,7 @@
 use crate::commons::{ConsensusError, Database};
 use crate::execution_ctx::ExecutionCtx;
 use crate::operations::Operations;
-use crate::user::committee::Committee;
+use crate::user::sortition::Exclusion;
 use crate::{proposal, ratification, validation};
 use node_data::message::Message;
 use node_data::StepName;
@@ -62,13 +62,13 @@ impl<T: Operations + 'static, D: Database + 'static> Phase<T, D> {
         debug!(event = "execute_step", ?timeout);
 
         let exclusion = match step_name {
-            StepName::Proposal => None,
+            StepName::Proposal => Exclusion::default(),
             _ => {
                 let generator = ctx
                     .iter_ctx
                     .get_generator(ctx.iteration)
                     .expect("Proposal committee to be already generated");
-                Some(generator)
+                Exclusion::from_generator(generator)
             }
         };
 
@@ -77,18 +77,13 @@ impl<T: Operations + 'static, D: Database + 'static> Phase<T, D> {
         // particular round and step. In the context of Proposal phase,
         // the extracted member is the one eligible to generate the candidate
         // block.
-        let step_committee = Committee::new(
-            ctx.provisioners,
-            &ctx.get_sortition_config(exclusion),
-        );
+        let step_committee = ctx.extract_committee(exclusion);
 
         debug!(
             event = "committee_generated",
-            members = format!("{}", &step_committee)
+            members = format!("{}", step_committee)
         );
 
-        ctx.save_committee(step_committee);
-
         // Execute step
         await_phase!(self, run(ctx))
     }
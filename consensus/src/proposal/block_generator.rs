@@ -10,15 +10,16 @@ use node_data::ledger::{to_str, Block, Certificate, IterationsInfo, Seed};
 
 use crate::config;
 use crate::merkle::merkle_root;
+use crate::signer::sign_message;
 
 use dusk_bytes::Serializable;
 use node_data::ledger;
 use node_data::message::payload::Candidate;
-use node_data::message::{ConsensusHeader, Message, SignInfo, StepMessage};
+use node_data::message::{ConsensusHeader, Message, SignInfo};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
 pub struct Generator<T: Operations> {
     executor: Arc<Mutex<T>>,
@@ -34,17 +35,29 @@ impl<T: Operations> Generator<T> {
         ru: &RoundUpdate,
         iteration: u8,
         failed_iterations: IterationsInfo,
+        instant_finality: bool,
     ) -> Result<Message, crate::operations::Error> {
         // Sign seed
         let seed = ru
-            .secret_key
-            .sign(ru.pubkey_bls.inner(), &ru.seed().inner()[..])
+            .signer
+            .sign(&ru.seed().inner()[..])
+            .await
+            .map_err(|err| {
+                error!(event = "failed_sign_seed", ?err);
+                crate::operations::Error::Failed
+            })?
             .to_bytes();
 
         let start = Instant::now();
 
         let candidate = self
-            .generate_block(ru, Seed::from(seed), iteration, failed_iterations)
+            .generate_block(
+                ru,
+                Seed::from(seed),
+                iteration,
+                failed_iterations,
+                instant_finality,
+            )
             .await?;
 
         info!(
@@ -57,6 +70,7 @@ impl<T: Operations> Generator<T> {
         debug!("block: {:?}", &candidate);
 
         let header = ConsensusHeader {
+            chain_id: ru.chain_id(),
             prev_block_hash: ru.hash(),
             round: ru.round,
             iteration,
@@ -68,7 +82,12 @@ impl<T: Operations> Generator<T> {
             sign_info,
         };
 
-        candidate.sign(&ru.secret_key, ru.pubkey_bls.inner());
+        sign_message(ru.signer.as_ref(), &mut candidate)
+            .await
+            .map_err(|err| {
+                error!(event = "failed_sign_candidate", ?err);
+                crate::operations::Error::Failed
+            })?;
 
         Ok(Message::new_candidate(candidate))
     }
@@ -79,6 +98,7 @@ impl<T: Operations> Generator<T> {
         seed: Seed,
         iteration: u8,
         failed_iterations: IterationsInfo,
+        instant_finality: bool,
     ) -> Result<Block, crate::operations::Error> {
         let start_time = Instant::now();
 
@@ -86,11 +106,18 @@ impl<T: Operations> Generator<T> {
             .to_missed_generators()
             .map_err(|_| crate::operations::Error::InvalidIterationInfo)?;
 
+        // The timestamp is fixed before the execution, as the contracts can
+        // read it. It must follow the one of the previous block.
+        let timestamp =
+            get_current_timestamp().max(ru.timestamp().saturating_add(1));
+
         let call_params = CallParams {
             round: ru.round,
             block_gas_limit: config::DEFAULT_BLOCK_GAS_LIMIT,
             generator_pubkey: ru.pubkey_bls.clone(),
             missed_generators,
+            timestamp,
+            seed,
         };
 
         let result = self
@@ -107,9 +134,10 @@ impl<T: Operations> Generator<T> {
 
         let prev_block_hash = ru.hash();
         let blk_header = ledger::Header {
-            version: 0,
+            version: ru.params().header_version(ru.round),
+            chain_id: ru.chain_id(),
             height: ru.round,
-            timestamp: get_current_timestamp(),
+            timestamp,
             gas_limit: config::DEFAULT_BLOCK_GAS_LIMIT,
             prev_block_hash,
             seed,
@@ -122,15 +150,20 @@ impl<T: Operations> Generator<T> {
             txroot,
             iteration,
             failed_iterations,
+            extensions: Default::default(),
         };
 
         // Apply a delay in block generator accordingly
         // In case EST call costs a second (assuming CONSENSUS_DELAY_MS=1000ms),
         // we should not sleep here
-        if let Some(delay) = Duration::from_millis(config::CONSENSUS_DELAY_MS)
-            .checked_sub(start_time.elapsed())
-        {
-            tokio::time::sleep(delay).await;
+        // With instant finality, there is no one else to wait for
+        if !instant_finality {
+            if let Some(delay) =
+                Duration::from_millis(config::CONSENSUS_DELAY_MS)
+                    .checked_sub(start_time.elapsed())
+            {
+                tokio::time::sleep(delay).await;
+            }
         }
 
         Ok(Block::new(blk_header, txs).expect("block should be valid"))
@@ -81,8 +81,11 @@ impl<D: Database> ProposalHandler<D> {
         expected_generator: &PublicKeyBytes,
     ) -> Result<(), ConsensusError> {
         let p = Self::unwrap_msg(msg)?;
-        //  Verify new_block msg signature
-        p.verify_signature()?;
+        //  Verify new_block msg signature, unless already verified by the
+        //  node upon receipt
+        if !msg.is_sig_verified() {
+            p.verify_signature()?;
+        }
 
         if msg.header.prev_block_hash != p.candidate.header().prev_block_hash {
             return Err(ConsensusError::InvalidBlockHash);
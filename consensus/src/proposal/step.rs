@@ -69,6 +69,7 @@ impl<T: Operations + 'static, D: Database> ProposalStep<T, D> {
                     &ctx.round_update,
                     ctx.iteration,
                     IterationsInfo::new(failed_certificates),
+                    ctx.instant_finality(),
                 )
                 .await
             {
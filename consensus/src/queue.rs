@@ -7,40 +7,136 @@
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 
+use tracing::warn;
+
+use crate::config::MAX_FUTURE_MSGS;
+
 type StepMap<T> = BTreeMap<u16, Vec<T>>;
 type RoundMap<T> = BTreeMap<u64, StepMap<T>>;
 
-/// Atomic message queue to store messages by round and step
-#[derive(Debug, Default)]
-pub struct Queue<T: ?Sized>(RoundMap<T>, usize)
+/// Counters describing the usage of a [`Queue`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueueStats {
+    /// Number of messages currently buffered
+    pub buffered: usize,
+    /// Number of messages buffered since creation
+    pub received: u64,
+    /// Number of messages evicted because the queue was full
+    pub evicted: u64,
+}
+
+/// Atomic message queue to store messages by round and step.
+///
+/// The queue holds at most `capacity` messages. Once full, the messages
+/// farthest in the future are evicted first, as they are the least likely
+/// to be needed soon.
+#[derive(Debug)]
+pub struct Queue<T: ?Sized>
 where
-    T: Debug + Clone;
+    T: Debug + Clone,
+{
+    items: RoundMap<T>,
+    capacity: usize,
+    stats: QueueStats,
+}
+
+impl<T: Debug + Clone> Default for Queue<T> {
+    fn default() -> Self {
+        Self::with_capacity(MAX_FUTURE_MSGS)
+    }
+}
 
 impl<T: Debug + Clone> Queue<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            items: RoundMap::new(),
+            capacity,
+            stats: QueueStats::default(),
+        }
+    }
+
     pub fn put_event(&mut self, round: u64, step: u16, msg: T) {
+        self.stats.received += 1;
+
+        if self.stats.buffered >= self.capacity {
+            // Drop the new message if it is the farthest one
+            let farthest = self.farthest();
+            if farthest.map_or(true, |f| (round, step) >= f) {
+                self.on_evicted(round, step);
+                return;
+            }
+            self.evict_farthest();
+        }
+
         // insert entry [round] -> [u8 -> Vec<T>]
-        self.0
+        self.items
             .entry(round)
             .or_default()
             .entry(step)
             .or_default()
             .push(msg);
 
-        self.1 += 1;
+        self.stats.buffered += 1;
     }
 
     pub fn drain_events(&mut self, round: u64, step: u16) -> Option<Vec<T>> {
-        self.0
+        let events = self
+            .items
             .get_mut(&round)
-            .and_then(|r| r.remove_entry(&step).map(|(_, v)| v))
+            .and_then(|r| r.remove_entry(&step).map(|(_, v)| v))?;
+
+        self.stats.buffered -= events.len();
+        Some(events)
     }
 
     pub fn clear_round(&mut self, round: u64) {
-        if let Some(r) = self.0.get_mut(&round) {
-            r.clear();
+        if let Some(r) = self.items.remove(&round) {
+            self.stats.buffered -= r.values().map(Vec::len).sum::<usize>();
+        }
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        self.stats
+    }
+
+    /// Returns the round and step of the farthest buffered message
+    fn farthest(&self) -> Option<(u64, u16)> {
+        self.items.iter().rev().find_map(|(round, steps)| {
+            steps
+                .iter()
+                .rev()
+                .find(|(_, msgs)| !msgs.is_empty())
+                .map(|(step, _)| (*round, *step))
+        })
+    }
+
+    fn evict_farthest(&mut self) {
+        let Some((round, step)) = self.farthest() else {
+            return;
         };
 
-        self.0.remove(&round);
+        let steps = self.items.get_mut(&round).expect("round to exist");
+        let msgs = steps.get_mut(&step).expect("step to exist");
+        msgs.pop();
+        if msgs.is_empty() {
+            steps.remove(&step);
+            if steps.is_empty() {
+                self.items.remove(&round);
+            }
+        }
+
+        self.stats.buffered -= 1;
+        self.on_evicted(round, step);
+    }
+
+    fn on_evicted(&mut self, round: u64, step: u16) {
+        self.stats.evicted += 1;
+        warn!(
+            event = "future msg evicted",
+            round,
+            step,
+            evicted = self.stats.evicted,
+        );
     }
 }
 
@@ -77,4 +173,29 @@ mod tests {
 
         assert!(queue.drain_events(round, 2).is_none());
     }
+
+    #[test]
+    pub fn test_evict_farthest() {
+        let mut queue = Queue::<u16>::with_capacity(3);
+        queue.put_event(10, 1, 1);
+        queue.put_event(12, 0, 2);
+        queue.put_event(11, 5, 3);
+
+        // A nearer message evicts the farthest one
+        queue.put_event(10, 2, 4);
+        assert!(queue.drain_events(12, 0).is_none());
+
+        // A farther message is dropped
+        queue.put_event(13, 0, 5);
+        assert!(queue.drain_events(13, 0).is_none());
+
+        assert_eq!(queue.stats().evicted, 2);
+        assert_eq!(queue.stats().received, 5);
+        assert_eq!(queue.stats().buffered, 3);
+
+        assert_eq!(queue.drain_events(10, 1), Some(vec![1]));
+        assert_eq!(queue.drain_events(10, 2), Some(vec![4]));
+        queue.clear_round(11);
+        assert_eq!(queue.stats().buffered, 0);
+    }
 }
@@ -7,7 +7,7 @@
 use crate::commons::{ConsensusError, Database, RoundUpdate};
 
 use crate::queue::Queue;
-use crate::user::committee::CommitteeSet;
+use crate::user::committee::{CommitteeCache, CommitteeSet};
 use crate::user::provisioners::Provisioners;
 use node_data::ledger::{to_str, Block, Certificate};
 use node_data::message::payload::{RatificationResult, Vote};
@@ -45,6 +45,7 @@ impl Quorum {
         ru: RoundUpdate,
         provisioners: Arc<Provisioners>,
         db: Arc<Mutex<D>>,
+        committee_cache: CommitteeCache,
     ) -> JoinHandle<Result<Block, ConsensusError>> {
         let future_msgs = self.future_msgs.clone();
         let outbound = self.outbound_queue.clone();
@@ -54,10 +55,17 @@ impl Quorum {
             let round = ru.round;
             let pubkey = ru.pubkey_bls.to_bs58();
             // Run quorum life-cycle loop
-            Executor::new(ru, &provisioners, inbound, outbound, db)
-                .run(future_msgs)
-                .instrument(tracing::info_span!("agr_task", round, pubkey))
-                .await
+            Executor::new(
+                ru,
+                &provisioners,
+                inbound,
+                outbound,
+                db,
+                committee_cache,
+            )
+            .run(future_msgs)
+            .instrument(tracing::info_span!("agr_task", round, pubkey))
+            .await
         })
     }
 }
@@ -81,12 +89,17 @@ impl<'p, D: Database> Executor<'p, D> {
         inbound_queue: AsyncQueue<Message>,
         outbound_queue: AsyncQueue<Message>,
         db: Arc<Mutex<D>>,
+        committee_cache: CommitteeCache,
     ) -> Self {
         Self {
             inbound_queue,
             outbound_queue,
             ru,
-            committees_set: RwLock::new(CommitteeSet::new(provisioners)),
+            committees_set: RwLock::new(CommitteeSet::with_cache(
+                provisioners,
+                *ru.params(),
+                committee_cache,
+            )),
             db,
         }
     }
@@ -151,6 +164,11 @@ impl<'p, D: Database> Executor<'p, D> {
 
     async fn collect_quorum(&self, msg: Message) -> Option<Block> {
         if let Payload::Quorum(quorum) = &msg.payload {
+            // Discard quorums certified for a different network
+            if quorum.header.chain_id != self.ru.chain_id() {
+                return None;
+            }
+
             // Verify quorum
             verifiers::verify_quorum(
                 quorum,
@@ -13,7 +13,7 @@ use node_data::{Serializable, StepName};
 use crate::commons::StepSigError;
 use crate::user::cluster::Cluster;
 use crate::user::committee::{Committee, CommitteeSet};
-use crate::user::sortition;
+use crate::user::sortition::{self, Exclusion};
 
 use dusk_bytes::Serializable as BytesSerializable;
 use tokio::sync::RwLock;
@@ -77,23 +77,20 @@ pub async fn verify_step_votes(
     let round = header.round;
     let iteration = header.iteration;
 
-    let generator = committees_set
-        .read()
-        .await
-        .provisioners()
-        .get_generator(iteration, seed, round);
-
-    let cfg =
-        sortition::Config::new(seed, round, iteration, step, Some(generator));
-
-    if committees_set.read().await.get(&cfg).is_none() {
-        let _ = committees_set.write().await.get_or_create(&cfg);
-    }
-
     let set = committees_set.read().await;
-    let committee = set.get(&cfg).expect("committee to be created");
+    let generator = set.get_generator(iteration, seed, round);
 
-    verify_votes(header, step, vote, sv, committee)
+    let cfg = sortition::Config::new(
+        seed,
+        round,
+        iteration,
+        step,
+        Exclusion::from_generator(generator),
+        set.params(),
+    );
+    let committee = set.get_or_create(&cfg);
+
+    verify_votes(header, step, vote, sv, &committee)
 }
 
 #[derive(Default)]
@@ -5,14 +5,15 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use crate::commons::{ConsensusError, RoundUpdate};
+use crate::msg_handler::step_state::StepState;
 use crate::msg_handler::{HandleMsgOutput, MsgHandler};
 use crate::step_votes_reg::SafeCertificateInfoRegistry;
+use crate::vote_cache::SafeVoteCache;
+use crate::vote_stats::SafeVoteStats;
 use async_trait::async_trait;
 use node_data::ledger::Certificate;
 use node_data::{ledger, StepName};
-use tracing::{error, warn};
-
-use crate::aggregator::Aggregator;
+use tracing::error;
 
 use crate::iteration_ctx::RoundCommittees;
 use crate::quorum::verifiers::verify_votes;
@@ -24,11 +25,10 @@ use node_data::message::{
 use crate::user::committee::Committee;
 
 pub struct RatificationHandler {
-    pub(crate) sv_registry: SafeCertificateInfoRegistry,
+    state: StepState,
+    vote_cache: SafeVoteCache,
 
-    pub(crate) aggregator: Aggregator,
     validation_result: ValidationResult,
-    pub(crate) curr_iteration: u8,
 }
 
 #[async_trait]
@@ -40,7 +40,17 @@ impl MsgHandler for RatificationHandler {
         round_committees: &RoundCommittees,
     ) -> Result<(), ConsensusError> {
         if let Payload::Ratification(p) = &msg.payload {
-            p.verify_signature()?;
+            // Unless already verified by the node upon receipt
+            if !msg.is_sig_verified() {
+                self.vote_cache
+                    .lock()
+                    .expect("vote cache lock to be acquired")
+                    .verify_signature(p)
+                    .map_err(|err| {
+                        self.state.record_invalid_signature(p);
+                        err
+                    })?;
+            }
             Self::verify_validation_result(
                 &msg.header,
                 iteration,
@@ -63,38 +73,7 @@ impl MsgHandler for RatificationHandler {
     ) -> Result<HandleMsgOutput, ConsensusError> {
         let p = Self::unwrap_msg(msg)?;
         let iteration = p.header().iteration;
-
-        if iteration != self.curr_iteration {
-            // Message that belongs to step from the past must be handled with
-            // collect_from_past fn
-            return Err(ConsensusError::InvalidMsgIteration(iteration));
-        }
-
-        // Collect vote, if msg payload is of ratification type
-        let (sv, quorum_reached) = self
-            .aggregator
-            .collect_vote(committee, p.sign_info(), &p.vote, p.get_step())
-            .map_err(|error| {
-                warn!(
-                    event = "Cannot collect vote",
-                    ?error,
-                    from = p.sign_info().signer.to_bs58(),
-                    ?p.vote,
-                    msg_step = p.get_step(),
-                    msg_round = p.header().round,
-                );
-                ConsensusError::InvalidVote(p.vote)
-            })?;
-
-        // Record any signature in global registry
-        _ = self.sv_registry.lock().await.add_step_votes(
-            iteration,
-            &p.vote,
-            sv,
-            StepName::Ratification,
-            quorum_reached,
-            committee.excluded().expect("Generator to be excluded"),
-        );
+        let (sv, quorum_reached) = self.state.collect(&p, committee).await?;
 
         if quorum_reached {
             return Ok(HandleMsgOutput::Ready(self.build_quorum_msg(
@@ -118,58 +97,45 @@ impl MsgHandler for RatificationHandler {
     ) -> Result<HandleMsgOutput, ConsensusError> {
         let p = Self::unwrap_msg(msg)?;
 
-        // Collect vote, if msg payload is ratification type
-        let collect_vote = self.aggregator.collect_vote(
-            committee,
-            p.sign_info(),
-            &p.vote,
-            p.get_step(),
-        );
-
-        match collect_vote {
-            Ok((sv, quorum_reached)) => {
-                // Record any signature in global registry
-                if let Some(quorum_msg) =
-                    self.sv_registry.lock().await.add_step_votes(
-                        p.header().iteration,
-                        &p.vote,
-                        sv,
-                        StepName::Ratification,
-                        quorum_reached,
-                        committee.excluded().expect("Generator to be excluded"),
-                    )
-                {
-                    return Ok(HandleMsgOutput::Ready(quorum_msg));
-                };
-            }
-            Err(error) => {
-                warn!(
-                    event = "Cannot collect vote",
-                    ?error,
-                    from = p.sign_info().signer.to_bs58(),
-                    vote = ?p.vote,
-                    msg_step = p.get_step(),
-                    msg_round = p.header().round,
-                );
-            }
-        };
-
-        Ok(HandleMsgOutput::Pending)
+        match self.state.collect_past(&p, committee).await? {
+            Some(quorum_msg) => Ok(HandleMsgOutput::Ready(quorum_msg)),
+            None => Ok(HandleMsgOutput::Pending),
+        }
     }
 
     /// Handle of an event of step execution timeout
     fn handle_timeout(&self) -> Result<HandleMsgOutput, ConsensusError> {
         Ok(HandleMsgOutput::Ready(Message::empty()))
     }
+
+    fn missing_votes(&self, step: u16, committee: &Committee) -> Option<u64> {
+        self.state.missing_votes(step, committee)
+    }
+
+    fn votes_of(
+        &self,
+        step: u16,
+        committee: &Committee,
+        signers: u64,
+    ) -> Vec<Message> {
+        self.state.votes_of(step, committee, signers)
+    }
 }
 
 impl RatificationHandler {
-    pub(crate) fn new(sv_registry: SafeCertificateInfoRegistry) -> Self {
+    pub(crate) fn new(
+        sv_registry: SafeCertificateInfoRegistry,
+        vote_cache: SafeVoteCache,
+        vote_stats: SafeVoteStats,
+    ) -> Self {
         Self {
-            sv_registry,
-            aggregator: Default::default(),
+            state: StepState::new(
+                StepName::Ratification,
+                sv_registry,
+                vote_stats,
+            ),
+            vote_cache,
             validation_result: Default::default(),
-            curr_iteration: 0,
         }
     }
 
@@ -182,6 +148,7 @@ impl RatificationHandler {
         ratification: ledger::StepVotes,
     ) -> Message {
         let header = node_data::message::ConsensusHeader {
+            chain_id: ru.chain_id(),
             prev_block_hash: ru.hash(),
             round: ru.round,
             iteration,
@@ -201,7 +168,7 @@ impl RatificationHandler {
 
     pub(crate) fn reset(&mut self, iter: u8, validation: ValidationResult) {
         self.validation_result = validation;
-        self.curr_iteration = iter;
+        self.state.reset(iter);
     }
 
     pub(crate) fn validation_result(&self) -> &ValidationResult {
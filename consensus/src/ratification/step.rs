@@ -13,9 +13,10 @@ use std::marker::PhantomData;
 
 use crate::msg_handler::{HandleMsgOutput, MsgHandler};
 use crate::ratification::handler;
+use crate::signer::{sign_message, SignerError};
 use node_data::message;
 use node_data::message::payload::{self, ValidationResult};
-use node_data::message::{AsyncQueue, Message, Payload, StepMessage};
+use node_data::message::{AsyncQueue, Message, Payload};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -35,10 +36,17 @@ impl<T: Operations + 'static, DB: Database> RatificationStep<T, DB> {
         iteration: u8,
         result: &ValidationResult,
         outbound: AsyncQueue<Message>,
-    ) -> Message {
+    ) -> Option<Message> {
         // Sign and construct ratification message
         let ratification =
-            self::build_ratification_payload(ru, iteration, result);
+            match self::build_ratification_payload(ru, iteration, result).await
+            {
+                Ok(ratification) => ratification,
+                Err(err) => {
+                    error!(event = "failed_sign_ratification", ?err);
+                    return None;
+                }
+            };
 
         let msg = Message::new_ratification(ratification);
 
@@ -50,16 +58,17 @@ impl<T: Operations + 'static, DB: Database> RatificationStep<T, DB> {
             error!("could not publish ratification msg {:?}", err)
         });
 
-        msg
+        Some(msg)
     }
 }
 
-pub fn build_ratification_payload(
+pub async fn build_ratification_payload(
     ru: &RoundUpdate,
     iteration: u8,
     result: &ValidationResult,
-) -> payload::Ratification {
+) -> Result<payload::Ratification, SignerError> {
     let header = message::ConsensusHeader {
+        chain_id: ru.chain_id(),
         prev_block_hash: ru.hash(),
         round: ru.round,
         iteration,
@@ -73,8 +82,8 @@ pub fn build_ratification_payload(
         validation_result: result.clone(),
         timestamp: get_current_timestamp(),
     };
-    ratification.sign(&ru.secret_key, ru.pubkey_bls.inner());
-    ratification
+    sign_message(ru.signer.as_ref(), &mut ratification).await?;
+    Ok(ratification)
 }
 
 impl<T: Operations + 'static, DB: Database> RatificationStep<T, DB> {
@@ -141,11 +150,13 @@ impl<T: Operations + 'static, DB: Database> RatificationStep<T, DB> {
             .await;
 
             // Collect my own vote
-            let res = handler
-                .collect(vote_msg, &ctx.round_update, committee)
-                .await?;
-            if let HandleMsgOutput::Ready(m) = res {
-                return Ok(m);
+            if let Some(vote_msg) = vote_msg {
+                let res = handler
+                    .collect(vote_msg, &ctx.round_update, committee)
+                    .await?;
+                if let HandleMsgOutput::Ready(m) = res {
+                    return Ok(m);
+                }
             }
         }
 
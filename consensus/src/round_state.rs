@@ -0,0 +1,152 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::io::{self, Read, Write};
+
+use node_data::ledger::Certificate;
+use node_data::Serializable;
+
+/// Minimal state of an ongoing consensus round, persisted so that a
+/// restarted node can rejoin the round where it left it, instead of waiting
+/// for the round to time out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundState {
+    pub round: u64,
+    /// Hash of the block the round builds on
+    pub prev_block_hash: [u8; 32],
+    /// Iteration in progress
+    pub iteration: u8,
+    /// Certificates collected so far, quorum reached or not
+    pub certs: Vec<RoundCert>,
+}
+
+/// A certificate of an iteration, as collected by the step votes registry.
+///
+/// Only the step votes flagged as having reached a quorum are restored, once
+/// verified against the committees of the round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundCert {
+    pub iteration: u8,
+    pub cert: Certificate,
+    pub quorum_reached_validation: bool,
+    pub quorum_reached_ratification: bool,
+}
+
+impl RoundState {
+    /// Returns `true` if the state is the one of the round building on
+    /// `prev_block_hash`.
+    pub fn is_for(&self, round: u64, prev_block_hash: &[u8; 32]) -> bool {
+        self.round == round && &self.prev_block_hash == prev_block_hash
+    }
+}
+
+impl Serializable for RoundState {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.round.to_le_bytes())?;
+        w.write_all(&self.prev_block_hash)?;
+        w.write_all(&[self.iteration])?;
+
+        let len = self.certs.len() as u32;
+        w.write_all(&len.to_le_bytes())?;
+        for cert in &self.certs {
+            cert.write(w)?;
+        }
+
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let round = Self::read_u64_le(r)?;
+        let prev_block_hash = Self::read_bytes(r)?;
+        let iteration = Self::read_u8(r)?;
+
+        let len = Self::read_u32_le(r)?;
+        let certs = (0..len)
+            .map(|_| RoundCert::read(r))
+            .collect::<io::Result<_>>()?;
+
+        Ok(RoundState {
+            round,
+            prev_block_hash,
+            iteration,
+            certs,
+        })
+    }
+}
+
+impl Serializable for RoundCert {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&[self.iteration])?;
+        self.cert.write(w)?;
+        w.write_all(&[
+            self.quorum_reached_validation as u8,
+            self.quorum_reached_ratification as u8,
+        ])?;
+
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let iteration = Self::read_u8(r)?;
+        let cert = Certificate::read(r)?;
+        let quorum_reached_validation = Self::read_u8(r)? != 0;
+        let quorum_reached_ratification = Self::read_u8(r)? != 0;
+
+        Ok(RoundCert {
+            iteration,
+            cert,
+            quorum_reached_validation,
+            quorum_reached_ratification,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use node_data::message::payload::{RatificationResult, Vote};
+
+    #[test]
+    fn round_state_roundtrip() {
+        let state = RoundState {
+            round: 42,
+            prev_block_hash: [7; 32],
+            iteration: 3,
+            certs: vec![
+                RoundCert {
+                    iteration: 0,
+                    cert: Certificate {
+                        result: RatificationResult::Fail(Vote::NoCandidate),
+                        ..Default::default()
+                    },
+                    quorum_reached_validation: true,
+                    quorum_reached_ratification: true,
+                },
+                RoundCert {
+                    iteration: 2,
+                    cert: Certificate::default(),
+                    quorum_reached_validation: false,
+                    quorum_reached_ratification: true,
+                },
+            ],
+        };
+
+        let mut buf = vec![];
+        state.write(&mut buf).expect("state to be written");
+        let read = RoundState::read(&mut &buf[..]).expect("state to be read");
+
+        assert_eq!(read, state);
+        assert!(read.is_for(42, &[7; 32]));
+        assert!(!read.is_for(43, &[7; 32]));
+    }
+}
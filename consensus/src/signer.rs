@@ -0,0 +1,81 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fmt;
+
+use async_trait::async_trait;
+use dusk_bls12_381_sign::{SecretKey, Signature};
+use dusk_bytes::Serializable;
+use node_data::bls::PublicKey;
+use node_data::message::StepMessage;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SignerError {
+    #[error("signer unavailable: {0}")]
+    Unavailable(String),
+    #[error("signer refused to sign: {0}")]
+    Refused(String),
+    #[error("invalid signature returned by the signer")]
+    InvalidSignature,
+}
+
+/// Produces the BLS signatures of the consensus messages of this provisioner.
+///
+/// Implementations may keep the secret key off the consensus host, e.g. in
+/// an HSM or behind a remote signing service.
+#[async_trait]
+pub trait ConsensusSigner: Send + Sync {
+    /// Returns the public key the signatures are verified against
+    fn public_key(&self) -> &PublicKey;
+
+    /// Signs `msg` with the provisioner secret key
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, SignerError>;
+}
+
+impl fmt::Debug for dyn ConsensusSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsensusSigner")
+            .field("public_key", self.public_key())
+            .finish()
+    }
+}
+
+/// Signs with a secret key held in memory.
+pub struct LocalSigner {
+    sk: SecretKey,
+    pk: PublicKey,
+}
+
+impl LocalSigner {
+    pub fn new(sk: SecretKey, pk: PublicKey) -> Self {
+        Self { sk, pk }
+    }
+}
+
+#[async_trait]
+impl ConsensusSigner for LocalSigner {
+    fn public_key(&self) -> &PublicKey {
+        &self.pk
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, SignerError> {
+        Ok(self.sk.sign(self.pk.inner(), msg))
+    }
+}
+
+/// Signs a consensus message, filling its sign info.
+pub async fn sign_message<M: StepMessage>(
+    signer: &dyn ConsensusSigner,
+    msg: &mut M,
+) -> Result<(), SignerError> {
+    let signature = signer.sign(&msg.signable()).await?;
+
+    let sign_info = msg.sign_info_mut();
+    sign_info.signature = signature.to_bytes().into();
+    sign_info.signer = signer.public_key().clone();
+    Ok(())
+}
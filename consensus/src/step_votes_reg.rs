@@ -5,6 +5,9 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use crate::commons::RoundUpdate;
+use crate::quorum::verifiers::verify_step_votes;
+use crate::round_state::RoundCert;
+use crate::user::committee::CommitteeSet;
 use node_data::bls::PublicKeyBytes;
 use node_data::ledger::{Certificate, IterationInfo, StepVotes};
 use node_data::message::payload::{RatificationResult, Vote};
@@ -13,7 +16,7 @@ use node_data::StepName;
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, warn};
 
 #[derive(Clone)]
@@ -195,6 +198,7 @@ impl CertInfoRegistry {
         cert: Certificate,
     ) -> Message {
         let header = node_data::message::ConsensusHeader {
+            chain_id: ru.chain_id(),
             prev_block_hash: ru.hash(),
             round: ru.round,
             iteration,
@@ -205,6 +209,105 @@ impl CertInfoRegistry {
         Message::new_quorum(payload)
     }
 
+    /// Returns the certificates collected so far, to be persisted.
+    pub(crate) fn snapshot(&self) -> Vec<RoundCert> {
+        let mut certs: Vec<_> = self
+            .cert_list
+            .iter()
+            .flat_map(|(iteration, iter)| {
+                iter.votes.values().map(|ci| RoundCert {
+                    iteration: *iteration,
+                    cert: ci.cert,
+                    quorum_reached_validation: ci.quorum_reached_validation,
+                    quorum_reached_ratification: ci.quorum_reached_ratification,
+                })
+            })
+            .collect();
+        // Sorted, for snapshots of the same certificates to be equal
+        certs.sort_by_key(|c| (c.iteration, *c.cert.result.vote()));
+        certs
+    }
+
+    /// Restores the certificates persisted with [`Self::snapshot`],
+    /// returning how many were restored.
+    ///
+    /// The persisted state is not trusted: the step votes of a certificate
+    /// are only restored if they reach the quorum they were recorded with.
+    /// Any other step votes are dropped, to be collected again from the
+    /// votes of the round.
+    pub(crate) async fn restore(
+        &mut self,
+        certs: Vec<RoundCert>,
+        committees_set: &RwLock<CommitteeSet<'_>>,
+    ) -> usize {
+        let mut restored = 0;
+
+        for mut c in certs {
+            let header = node_data::message::ConsensusHeader {
+                chain_id: self.ru.chain_id(),
+                prev_block_hash: self.ru.hash(),
+                round: self.ru.round,
+                iteration: c.iteration,
+            };
+            let vote = *c.cert.result.vote();
+
+            for (step, sv, reached) in [
+                (
+                    StepName::Validation,
+                    &mut c.cert.validation,
+                    &mut c.quorum_reached_validation,
+                ),
+                (
+                    StepName::Ratification,
+                    &mut c.cert.ratification,
+                    &mut c.quorum_reached_ratification,
+                ),
+            ] {
+                let verified = *reached
+                    && verify_step_votes(
+                        &header,
+                        &vote,
+                        sv,
+                        committees_set,
+                        self.ru.seed(),
+                        step,
+                    )
+                    .await
+                    .is_ok();
+                if !verified {
+                    *sv = StepVotes::default();
+                    *reached = false;
+                }
+            }
+
+            if !c.quorum_reached_validation && !c.quorum_reached_ratification {
+                warn!(event = "round cert dropped", iter = c.iteration, ?vote);
+                continue;
+            }
+
+            let generator = committees_set.read().await.get_generator(
+                c.iteration,
+                self.ru.seed(),
+                self.ru.round,
+            );
+            let iter = self
+                .cert_list
+                .entry(c.iteration)
+                .or_insert_with(|| IterationCerts::new(generator));
+            iter.votes.insert(
+                vote,
+                CertificateInfo {
+                    cert: c.cert,
+                    quorum_reached_validation: c.quorum_reached_validation,
+                    quorum_reached_ratification: c.quorum_reached_ratification,
+                },
+            );
+            restored += 1;
+        }
+
+        restored
+    }
+
     pub(crate) fn get_failed_certs(
         &self,
         to: u8,
@@ -226,3 +329,58 @@ impl CertInfoRegistry {
         res
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::LocalSigner;
+    use crate::user::provisioners::{Provisioners, DUSK};
+    use dusk_bls12_381_sign::{PublicKey, SecretKey};
+    use node_data::ledger::Header;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[tokio::test]
+    async fn test_restore_drops_unverified_certs() {
+        let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+        let mut provisioners = Provisioners::empty();
+        let mut keys = vec![];
+        for _ in 0..10 {
+            let sk = SecretKey::random(rng);
+            let pk = node_data::bls::PublicKey::new(PublicKey::from(&sk));
+            provisioners.add_member_with_value(pk.clone(), 1000 * DUSK);
+            keys.push((sk, pk));
+        }
+
+        let (sk, pk) = keys.pop().expect("a key");
+        let ru = RoundUpdate::new(
+            Arc::new(LocalSigner::new(sk, pk)),
+            &Header::default(),
+            HashMap::new(),
+            Default::default(),
+        );
+        let committees_set =
+            RwLock::new(CommitteeSet::new(&provisioners, *ru.params()));
+
+        // Step votes claiming the whole committee, with no valid signature
+        let forged = StepVotes::new([0; 48], u64::MAX);
+        let certs = vec![RoundCert {
+            iteration: 0,
+            cert: Certificate {
+                result: RatificationResult::Fail(Vote::NoCandidate),
+                validation: forged,
+                ratification: forged,
+            },
+            quorum_reached_validation: true,
+            quorum_reached_ratification: true,
+        }];
+
+        let mut registry = CertInfoRegistry::new(ru);
+        let restored = registry.restore(certs, &committees_set).await;
+
+        assert_eq!(restored, 0);
+        assert!(registry.snapshot().is_empty());
+        assert!(registry.get_failed_certs(1).iter().all(Option::is_none));
+    }
+}
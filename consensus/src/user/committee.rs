@@ -5,21 +5,23 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use crate::user::provisioners::Provisioners;
-use crate::user::sortition;
+use crate::user::sortition::{self, Exclusion};
 
 use super::cluster::Cluster;
-use crate::config;
+use crate::config::ConsensusParams;
 use node_data::bls::{PublicKey, PublicKeyBytes};
+use node_data::ledger::Seed;
 use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::mem;
+use std::sync::{Arc, Mutex};
 
 #[derive(Default, Debug, Clone)]
 pub struct Committee {
     members: BTreeMap<PublicKey, usize>,
     super_majority: usize,
     majority: usize,
-    excluded: Option<PublicKeyBytes>,
+    excluded: Exclusion,
 }
 
 impl Committee {
@@ -36,20 +38,14 @@ impl Committee {
     pub fn new(provisioners: &Provisioners, cfg: &sortition::Config) -> Self {
         // Generate committee using deterministic sortition.
         let extracted = provisioners.create_committee(cfg);
-        let committe_size = cfg.committee_size() as f64;
-
-        let super_majority =
-            (committe_size * config::SUPERMAJORITY_THRESHOLD).ceil() as usize;
-        let majority =
-            (committe_size * config::MAJORITY_THRESHOLD) as usize + 1;
 
         // Turn the raw vector into a hashmap where we map a pubkey to its
         // occurrences.
         let mut committee = Self {
             members: BTreeMap::new(),
-            super_majority,
-            majority,
-            excluded: cfg.exclusion().copied(),
+            super_majority: cfg.super_majority_quorum(),
+            majority: cfg.majority_quorum(),
+            excluded: cfg.exclusion().clone(),
         };
 
         for member_key in extracted {
@@ -59,8 +55,9 @@ impl Committee {
         committee
     }
 
-    pub fn excluded(&self) -> Option<&PublicKeyBytes> {
-        self.excluded.as_ref()
+    /// Returns the provisioners excluded from the extraction.
+    pub fn excluded(&self) -> &Exclusion {
+        &self.excluded
     }
 
     /// Returns true if `pubkey_bls` is a member of the generated committee.
@@ -143,33 +140,118 @@ impl fmt::Display for &Committee {
     }
 }
 
+/// Committees extracted from the provisioners of a round, by sortition
+/// config, i.e. by seed, round, step, committee size and exclusions.
+///
+/// The extraction is deterministic, so a committee is extracted once and
+/// shared by all the clones of the cache. It must only be shared by handlers
+/// of the same round, extracting from the same provisioners.
+#[derive(Debug, Default, Clone)]
+pub struct CommitteeCache(
+    Arc<Mutex<HashMap<sortition::Config, Arc<Committee>>>>,
+);
+
+impl CommitteeCache {
+    /// Returns the committee of `cfg`, extracting it from `provisioners` if
+    /// not cached.
+    pub fn get_or_create(
+        &self,
+        provisioners: &Provisioners,
+        cfg: &sortition::Config,
+    ) -> Arc<Committee> {
+        self.0
+            .lock()
+            .expect("committee cache lock to be acquired")
+            .entry(cfg.clone())
+            .or_insert_with_key(|cfg| {
+                Arc::new(Committee::new(provisioners, cfg))
+            })
+            .clone()
+    }
+
+    /// Returns the committee of `cfg`, if cached.
+    pub fn get(&self, cfg: &sortition::Config) -> Option<Arc<Committee>> {
+        self.0
+            .lock()
+            .expect("committee cache lock to be acquired")
+            .get(cfg)
+            .cloned()
+    }
+
+    /// Returns the number of cached committees.
+    pub fn len(&self) -> usize {
+        self.0
+            .lock()
+            .expect("committee cache lock to be acquired")
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 /// Implements a cache of generated committees so that they can be reused.
 pub struct CommitteeSet<'p> {
-    committees: HashMap<sortition::Config, Committee>,
+    committees: CommitteeCache,
     provisioners: &'p Provisioners,
+    params: ConsensusParams,
 }
 
 impl<'p> CommitteeSet<'p> {
-    pub fn new(provisioners: &'p Provisioners) -> Self {
+    pub fn new(
+        provisioners: &'p Provisioners,
+        params: ConsensusParams,
+    ) -> Self {
+        Self::with_cache(provisioners, params, CommitteeCache::default())
+    }
+
+    /// Creates a set reusing the committees of `cache`, extracted from the
+    /// same `provisioners`.
+    pub fn with_cache(
+        provisioners: &'p Provisioners,
+        params: ConsensusParams,
+        cache: CommitteeCache,
+    ) -> Self {
         CommitteeSet {
             provisioners,
-            committees: HashMap::new(),
+            committees: cache,
+            params,
         }
     }
 
-    pub fn get_or_create(&mut self, cfg: &sortition::Config) -> &Committee {
-        self.committees
-            .entry(cfg.clone())
-            .or_insert_with_key(|config| {
-                Committee::new(self.provisioners, config)
-            })
+    pub fn get_or_create(&self, cfg: &sortition::Config) -> Arc<Committee> {
+        self.committees.get_or_create(self.provisioners, cfg)
     }
 
-    pub fn get(&self, cfg: &sortition::Config) -> Option<&Committee> {
+    pub fn get(&self, cfg: &sortition::Config) -> Option<Arc<Committee>> {
         self.committees.get(cfg)
     }
 
+    /// Returns the generator of the given iteration, extracting the
+    /// committee of its Proposal step if not cached.
+    pub fn get_generator(
+        &self,
+        iteration: u8,
+        seed: Seed,
+        round: u64,
+    ) -> PublicKeyBytes {
+        let cfg = sortition::Config::generator(seed, round, iteration);
+        let committee = self.get_or_create(&cfg);
+        let generator = *committee
+            .iter()
+            .next()
+            .expect("committee to have 1 entry")
+            .bytes();
+        generator
+    }
+
     pub fn provisioners(&self) -> &Provisioners {
         self.provisioners
     }
+
+    /// Returns the parameters the committees are extracted with.
+    pub fn params(&self) -> &ConsensusParams {
+        &self.params
+    }
 }
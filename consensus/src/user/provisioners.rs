@@ -4,15 +4,15 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use crate::user::sortition;
+use crate::config::StakeAgeWeighting;
+use crate::user::sortition::{self, Exclusion};
 use crate::user::stake::Stake;
 use node_data::bls::{PublicKey, PublicKeyBytes};
-use node_data::StepName;
-
 use node_data::ledger::Seed;
 use num_bigint::BigInt;
 use std::collections::BTreeMap;
 use std::mem;
+use thiserror::Error;
 
 use super::committee::Committee;
 
@@ -30,6 +30,26 @@ impl Provisioners {
     }
 }
 
+/// Change of the stake of a single provisioner, as resulting from a block.
+#[derive(Clone, Debug)]
+pub enum ProvisionerDelta {
+    /// A stake has been deposited, possibly by a new provisioner
+    Stake(PublicKey, Stake),
+    /// The stake of an existing provisioner has changed, e.g. rewarded or
+    /// slashed
+    Update(PublicKey, Stake),
+    /// The stake of an existing provisioner has been withdrawn
+    Remove(PublicKey),
+}
+
+#[derive(Debug, Error)]
+pub enum DeltaError {
+    #[error("updated a not existing stake {0}")]
+    UpdateMissing(String),
+    #[error("removed a not existing stake {0}")]
+    RemoveMissing(String),
+}
+
 #[derive(Clone, Debug)]
 pub struct ContextProvisioners {
     current: Provisioners,
@@ -68,6 +88,23 @@ impl ContextProvisioners {
         self.prev = Some(prev);
     }
 
+    /// Applies the stake changes of a block on top of `self.current`, keeping
+    /// it as `self.prev`.
+    ///
+    /// The changes are applied atomically: if any of them is inconsistent
+    /// with the current set, the provisioners are left untouched.
+    pub fn apply_deltas<I>(&mut self, deltas: I) -> Result<(), DeltaError>
+    where
+        I: IntoIterator<Item = ProvisionerDelta>,
+    {
+        let mut new = self.current.clone();
+        for delta in deltas {
+            new.apply(delta)?;
+        }
+        self.update_and_swap(new);
+        Ok(())
+    }
+
     /// Change `self.current` with [new] and set `self.prev` to [None]
     pub fn update(&mut self, new: Provisioners) {
         self.current = new;
@@ -93,6 +130,11 @@ impl Provisioners {
         self.members.entry(pubkey_bls).or_insert_with(|| stake);
     }
 
+    /// Returns the stake of the given provisioner, if any.
+    pub fn get_stake(&self, pubkey_bls: &PublicKey) -> Option<&Stake> {
+        self.members.get(pubkey_bls)
+    }
+
     pub fn replace_stake(
         &mut self,
         pubkey_bls: PublicKey,
@@ -105,6 +147,28 @@ impl Provisioners {
         self.members.remove(pubkey_bls)
     }
 
+    /// Applies a single stake change, returning the replaced stake.
+    pub fn apply(
+        &mut self,
+        delta: ProvisionerDelta,
+    ) -> Result<Option<Stake>, DeltaError> {
+        match delta {
+            ProvisionerDelta::Stake(pk, stake) => {
+                Ok(self.replace_stake(pk, stake))
+            }
+            ProvisionerDelta::Update(pk, stake) => {
+                if !self.members.contains_key(&pk) {
+                    return Err(DeltaError::UpdateMissing(pk.to_bs58()));
+                }
+                Ok(self.replace_stake(pk, stake))
+            }
+            ProvisionerDelta::Remove(pk) => self
+                .remove_stake(&pk)
+                .ok_or_else(|| DeltaError::RemoveMissing(pk.to_bs58()))
+                .map(Some),
+        }
+    }
+
     /// Adds a new member with reward=0 and elibile_since=0.
     ///
     /// Useful for implementing unit tests.
@@ -129,6 +193,14 @@ impl Provisioners {
         })
     }
 
+    /// Returns whether `pubkey_bls` is an eligible provisioner for the
+    /// specified round.
+    pub fn is_eligible(&self, pubkey_bls: &PublicKey, round: u64) -> bool {
+        self.members
+            .get(pubkey_bls)
+            .is_some_and(|m| m.is_eligible(round) && m.value() >= MINIMUM_STAKE)
+    }
+
     /// Runs the deterministic sortition algorithm which determines the
     /// committee members for a given round, step and seed.
     ///
@@ -144,6 +216,7 @@ impl Provisioners {
             self,
             cfg.round(),
             cfg.exclusion(),
+            cfg.stake_age_weighting(),
         );
 
         let mut total_weight = comm.total_weight().into();
@@ -183,13 +256,7 @@ impl Provisioners {
         seed: Seed,
         round: u64,
     ) -> PublicKeyBytes {
-        let cfg = sortition::Config::new(
-            seed,
-            round,
-            iteration,
-            StepName::Proposal,
-            None,
-        );
+        let cfg = sortition::Config::generator(seed, round, iteration);
         let committee_keys = Committee::new(self, &cfg);
 
         let generator = *committee_keys
@@ -210,27 +277,30 @@ impl<'a> CommitteeGenerator<'a> {
     fn from_provisioners(
         provisioners: &'a Provisioners,
         round: u64,
-        exclusion: Option<&PublicKeyBytes>,
+        exclusion: &Exclusion,
+        weighting: Option<&StakeAgeWeighting>,
     ) -> Self {
-        let eligibles = provisioners
-            .eligibles(round)
-            .map(|(p, stake)| (p, stake.clone()));
-
-        let members = match exclusion {
-            None => BTreeMap::from_iter(eligibles),
-            Some(excluded) => {
-                let eligibles =
-                    eligibles.filter(|(p, _)| p.bytes() != excluded);
-                BTreeMap::from_iter(eligibles)
+        // Only the value of the stakes matters to the extraction, thus it is
+        // replaced by the weight of the stake when weighting by age
+        let weighted = |(p, stake): (&'a PublicKey, &Stake)| match weighting {
+            Some(w) => {
+                let weight =
+                    w.weight(stake.value(), stake.eligible_since, round);
+                (p, Stake::from_value(weight))
             }
+            None => (p, stake.clone()),
         };
 
+        let eligibles = provisioners.eligibles(round).map(weighted);
+
+        let members = BTreeMap::from_iter(
+            eligibles.filter(|(p, _)| !exclusion.contains(p.bytes())),
+        );
+
         if members.is_empty() {
             // This is the edge case when there is only 1 active provisioner.
             // Handling it just for single node cluster scenario
-            let eligibles = provisioners
-                .eligibles(round)
-                .map(|(p, stake)| (p, stake.clone()));
+            let eligibles = provisioners.eligibles(round).map(weighted);
 
             Self {
                 members: BTreeMap::from_iter(eligibles),
@@ -269,3 +339,41 @@ impl<'a> CommitteeGenerator<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_deltas() {
+        let alice = PublicKey::from_sk_seed_u64(1);
+        let bob = PublicKey::from_sk_seed_u64(2);
+        let carol = PublicKey::from_sk_seed_u64(3);
+
+        let mut p = Provisioners::empty();
+        p.add_member_with_value(alice.clone(), 1000 * DUSK);
+        p.add_member_with_value(bob.clone(), 1000 * DUSK);
+        let mut ctx = ContextProvisioners::new(p);
+
+        ctx.apply_deltas([
+            ProvisionerDelta::Update(alice.clone(), Stake::from_value(DUSK)),
+            ProvisionerDelta::Remove(bob.clone()),
+            ProvisionerDelta::Stake(carol.clone(), Stake::from_value(DUSK)),
+        ])
+        .expect("deltas to be applied");
+
+        let current: Vec<_> = ctx.current().iter().map(|(pk, _)| pk).collect();
+        assert_eq!(current.len(), 2);
+        assert!(current.contains(&&alice) && current.contains(&&carol));
+        assert_eq!(ctx.prev().iter().count(), 2);
+        assert!(ctx.prev().iter().any(|(pk, _)| pk == &bob));
+
+        // Inconsistent deltas leave the provisioners untouched
+        let res = ctx.apply_deltas([
+            ProvisionerDelta::Remove(alice.clone()),
+            ProvisionerDelta::Update(bob.clone(), Stake::from_value(DUSK)),
+        ]);
+        assert!(matches!(res, Err(DeltaError::UpdateMissing(_))));
+        assert!(ctx.current().iter().any(|(pk, _)| pk == &alice));
+    }
+}
@@ -12,17 +12,72 @@ use sha3::{Digest, Sha3_256};
 use node_data::{bls::PublicKeyBytes, ledger::Seed, StepName};
 
 use crate::config::{
-    PROPOSAL_COMMITTEE_SIZE, RATIFICATION_COMMITTEE_SIZE,
-    VALIDATION_COMMITTEE_SIZE,
+    ConsensusParams, StakeAgeWeighting, PROPOSAL_COMMITTEE_SIZE,
 };
 
+/// Provisioners excluded from a committee extraction.
+///
+/// The generator of the iteration is excluded from the voting committees.
+/// Further provisioners can be excluded as well, e.g. the ones slashed during
+/// the round.
+#[derive(Debug, Clone, Default, Eq, Hash, PartialEq)]
+pub struct Exclusion {
+    generator: Option<PublicKeyBytes>,
+    /// Other excluded provisioners, sorted so that equal exclusions result in
+    /// equal configs
+    others: Vec<PublicKeyBytes>,
+}
+
+impl Exclusion {
+    /// Excludes the generator of the iteration.
+    pub fn from_generator(generator: PublicKeyBytes) -> Self {
+        Self {
+            generator: Some(generator),
+            others: vec![],
+        }
+    }
+
+    /// Excludes a provisioner in addition to the generator.
+    pub fn insert(&mut self, pk: PublicKeyBytes) {
+        if self.contains(&pk) {
+            return;
+        }
+        let pos = self
+            .others
+            .binary_search_by(|other| other.inner().cmp(pk.inner()))
+            .unwrap_or_else(|pos| pos);
+        self.others.insert(pos, pk);
+    }
+
+    /// Returns the excluded generator, if any.
+    pub fn generator(&self) -> Option<&PublicKeyBytes> {
+        self.generator.as_ref()
+    }
+
+    pub fn contains(&self, pk: &PublicKeyBytes) -> bool {
+        self.generator.as_ref() == Some(pk) || self.others.contains(pk)
+    }
+
+    /// Returns all the excluded provisioners, generator first.
+    pub fn iter(&self) -> impl Iterator<Item = &PublicKeyBytes> {
+        self.generator.iter().chain(self.others.iter())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.generator.is_none() && self.others.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Default, Eq, Hash, PartialEq)]
 pub struct Config {
     seed: Seed,
     round: u64,
     step: u16,
     committee_size: usize,
-    exclusion: Option<PublicKeyBytes>,
+    super_majority: usize,
+    majority: usize,
+    exclusion: Exclusion,
+    stake_age_weighting: Option<StakeAgeWeighting>,
 }
 
 impl Config {
@@ -31,27 +86,61 @@ impl Config {
         round: u64,
         iteration: u8,
         step: StepName,
-        exclusion: Option<PublicKeyBytes>,
+        exclusion: Exclusion,
+        params: &ConsensusParams,
     ) -> Config {
         let committee_size = match step {
             StepName::Proposal => PROPOSAL_COMMITTEE_SIZE,
-            StepName::Ratification => RATIFICATION_COMMITTEE_SIZE,
-            StepName::Validation => VALIDATION_COMMITTEE_SIZE,
+            StepName::Ratification => params.ratification_committee_size,
+            StepName::Validation => params.validation_committee_size,
+        };
+        // The generator extraction is not weighted by the stake age
+        let stake_age_weighting = match step {
+            StepName::Proposal => None,
+            _ => params.stake_age_weighting,
         };
+        let (super_majority, majority) = params.quorums(committee_size);
         let step = step.to_step(iteration);
         Self {
             seed,
             round,
             step,
             committee_size,
+            super_majority,
+            majority,
             exclusion,
+            stake_age_weighting,
         }
     }
 
+    /// Returns the config extracting the generator of an iteration.
+    ///
+    /// The proposal committee size is not part of the [`ConsensusParams`].
+    pub fn generator(seed: Seed, round: u64, iteration: u8) -> Config {
+        Self::new(
+            seed,
+            round,
+            iteration,
+            StepName::Proposal,
+            Exclusion::default(),
+            &ConsensusParams::default(),
+        )
+    }
+
     pub fn committee_size(&self) -> usize {
         self.committee_size
     }
 
+    /// Returns the votes needed for a supermajority quorum.
+    pub fn super_majority_quorum(&self) -> usize {
+        self.super_majority
+    }
+
+    /// Returns the votes needed for a majority quorum.
+    pub fn majority_quorum(&self) -> usize {
+        self.majority
+    }
+
     pub fn step(&self) -> u16 {
         self.step
     }
@@ -60,8 +149,12 @@ impl Config {
         self.round
     }
 
-    pub fn exclusion(&self) -> Option<&PublicKeyBytes> {
-        self.exclusion.as_ref()
+    pub fn exclusion(&self) -> &Exclusion {
+        &self.exclusion
+    }
+
+    pub fn stake_age_weighting(&self) -> Option<&StakeAgeWeighting> {
+        self.stake_age_weighting.as_ref()
     }
 }
 
@@ -96,15 +189,17 @@ mod tests {
 
     use super::*;
 
-    use crate::user::committee::Committee;
+    use crate::user::committee::{Committee, CommitteeCache, CommitteeSet};
     use crate::user::provisioners::{Provisioners, DUSK};
-    use crate::user::sortition::Config;
+    use crate::user::sortition::{Config, Exclusion};
+    use crate::user::stake::Stake;
     use dusk_bls12_381_sign::{PublicKey as BlsPublicKey, SecretKey};
     use dusk_bytes::DeserializableSlice;
 
     use node_data::bls::PublicKey;
 
     use node_data::ledger::Seed;
+    use std::sync::Arc;
 
     impl Config {
         pub fn raw(
@@ -112,14 +207,19 @@ mod tests {
             round: u64,
             step: u16,
             committee_size: usize,
-            exclusion: Option<PublicKeyBytes>,
+            exclusion: Exclusion,
         ) -> Config {
+            let (super_majority, majority) =
+                ConsensusParams::default().quorums(committee_size);
             Self {
                 seed,
                 round,
                 step,
                 committee_size,
+                super_majority,
+                majority,
                 exclusion,
+                stake_age_weighting: None,
             }
         }
     }
@@ -134,7 +234,13 @@ mod tests {
 
         assert_eq!(
             create_sortition_hash(
-                &Config::raw(Seed::from([3; 48]), 10, 3, 0, None),
+                &Config::raw(
+                    Seed::from([3; 48]),
+                    10,
+                    3,
+                    0,
+                    Exclusion::default()
+                ),
                 1
             )[..],
             hash[..],
@@ -150,7 +256,7 @@ mod tests {
 
         for (seed, total_weight, expected_score) in dataset {
             let hash = create_sortition_hash(
-                &Config::raw(Seed::from(seed), 10, 3, 0, None),
+                &Config::raw(Seed::from(seed), 10, 3, 0, Exclusion::default()),
                 1,
             );
 
@@ -168,7 +274,7 @@ mod tests {
         let committee_size = 64;
 
         // Execute sortition with specific config
-        let cfg = Config::raw(Seed::default(), 1, 1, 64, None);
+        let cfg = Config::raw(Seed::default(), 1, 1, 64, Exclusion::default());
 
         let committee = Committee::new(&p, &cfg);
 
@@ -187,8 +293,13 @@ mod tests {
         let p = generate_provisioners(5);
 
         let committee_size = 45;
-        let cfg =
-            Config::raw(Seed::from([3u8; 48]), 7777, 8, committee_size, None);
+        let cfg = Config::raw(
+            Seed::from([3u8; 48]),
+            7777,
+            8,
+            committee_size,
+            Exclusion::default(),
+        );
 
         let committee = Committee::new(&p, &cfg);
         assert_eq!(
@@ -209,7 +320,13 @@ mod tests {
         let relative_step = 2;
         let step = iteration as u16 * 3 + relative_step;
 
-        let cfg = Config::raw(seed, round, step, committee_size, None);
+        let cfg = Config::raw(
+            seed,
+            round,
+            step,
+            committee_size,
+            Exclusion::default(),
+        );
         let generator = p.get_generator(iteration, seed, round);
         let committee = Committee::new(&p, &cfg);
 
@@ -224,8 +341,13 @@ mod tests {
         assert_eq!(vec![3, 17, 9, 16], committee.get_occurrences());
 
         // Run the same extraction, with the generator excluded
-        let cfg =
-            Config::raw(seed, round, step, committee_size, Some(generator));
+        let cfg = Config::raw(
+            seed,
+            round,
+            step,
+            committee_size,
+            Exclusion::from_generator(generator),
+        );
         let committee = Committee::new(&p, &cfg);
 
         assert!(
@@ -242,21 +364,240 @@ mod tests {
         assert_eq!(vec![5, 23, 17], committee.get_occurrences());
     }
 
+    #[test]
+    fn test_deterministic_sortition_multiple_exclusions() {
+        let p = generate_provisioners(5);
+
+        let seed = Seed::from([3u8; 48]);
+        let round = 7777;
+        let committee_size = 45;
+        let iteration = 2;
+        let step = iteration as u16 * 3 + 2;
+
+        let generator = p.get_generator(iteration, seed, round);
+        let other = *p
+            .eligibles(round)
+            .map(|(pk, _)| pk.bytes())
+            .find(|&pk| pk != &generator)
+            .expect("Another provisioner to be eligible");
+
+        let mut exclusion = Exclusion::from_generator(generator);
+        exclusion.insert(other);
+        exclusion.insert(other);
+        assert_eq!(exclusion.iter().count(), 2);
+        assert_eq!(exclusion.generator(), Some(&generator));
+
+        let cfg =
+            Config::raw(seed, round, step, committee_size, exclusion.clone());
+        let committee = Committee::new(&p, &cfg);
+
+        assert!(
+            committee
+                .iter()
+                .all(|pk| pk.bytes() != &generator && pk.bytes() != &other),
+            "Generator and other provisioner to be excluded"
+        );
+        assert_eq!(
+            committee_size,
+            committee.get_occurrences().iter().sum::<usize>()
+        );
+        assert_eq!(committee.excluded(), &exclusion);
+    }
+
     #[test]
     fn test_quorum() {
         let p = generate_provisioners(5);
 
-        let cfg = Config::raw(Seed::default(), 7777, 8, 64, None);
+        let cfg =
+            Config::raw(Seed::default(), 7777, 8, 64, Exclusion::default());
 
         let c = Committee::new(&p, &cfg);
         assert_eq!(c.super_majority_quorum(), 43);
     }
 
+    #[test]
+    fn test_lone_provisioner_quorum() {
+        let p = generate_provisioners(1);
+        let (pk, _) = p.iter().next().expect("one provisioner");
+
+        let cfg =
+            Config::raw(Seed::default(), 7777, 8, 64, Exclusion::default());
+
+        // A lone provisioner holds every credit of the committee, so its own
+        // vote is enough for a certificate
+        let c = Committee::new(&p, &cfg);
+        assert_eq!(c.size(), 1);
+        assert_eq!(c.votes_for(pk), Some(64));
+        assert!(c.votes_for(pk).unwrap() >= c.super_majority_quorum());
+    }
+
+    #[test]
+    fn test_custom_params() {
+        let p = generate_provisioners(5);
+
+        let params = ConsensusParams {
+            validation_committee_size: 10,
+            ratification_committee_size: 16,
+            supermajority_threshold: 0.75,
+            majority_threshold: 0.5,
+            ..Default::default()
+        };
+        params.validate().expect("params to be valid");
+
+        let cfg = Config::new(
+            Seed::default(),
+            7777,
+            2,
+            StepName::Validation,
+            Exclusion::default(),
+            &params,
+        );
+        let c = Committee::new(&p, &cfg);
+        assert_eq!(c.get_occurrences().iter().sum::<usize>(), 10);
+        assert_eq!(c.super_majority_quorum(), 8);
+        assert_eq!(c.majority_quorum(), 6);
+
+        let cfg = Config::new(
+            Seed::default(),
+            7777,
+            2,
+            StepName::Ratification,
+            Exclusion::default(),
+            &params,
+        );
+        assert_eq!(cfg.committee_size(), 16);
+        assert_eq!(cfg.super_majority_quorum(), 12);
+
+        let invalid = ConsensusParams {
+            validation_committee_size: 65,
+            ..params
+        };
+        assert!(invalid.validate().is_err());
+        let invalid = ConsensusParams {
+            supermajority_threshold: 0.5,
+            ..params
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_chain_params() {
+        use crate::config::{DEVNET_CHAIN_ID, DEVNET_COMMITTEE_SIZE};
+
+        let mainnet = ConsensusParams::for_chain(0);
+        mainnet.validate().expect("params to be valid");
+        assert_eq!(mainnet, ConsensusParams::default());
+
+        let devnet = ConsensusParams::for_chain(DEVNET_CHAIN_ID);
+        devnet.validate().expect("params to be valid");
+        assert_eq!(devnet.validation_committee_size, DEVNET_COMMITTEE_SIZE);
+        assert_eq!(devnet.ratification_committee_size, DEVNET_COMMITTEE_SIZE);
+    }
+
+    #[test]
+    fn test_stake_age_weighting() {
+        let weighting = StakeAgeWeighting {
+            boost_per_epoch_bps: 100,
+            max_boost_bps: 10_000,
+            from_height: 0,
+        };
+        weighting.validate().expect("weighting to be valid");
+
+        let epoch = stake_contract_types::EPOCH;
+        assert_eq!(weighting.weight(1000 * DUSK, 0, epoch - 1), 1000 * DUSK);
+        assert_eq!(weighting.weight(1000 * DUSK, 0, 3 * epoch), 1030 * DUSK);
+        assert_eq!(weighting.weight(1000 * DUSK, 0, 500 * epoch), 2000 * DUSK);
+        assert_eq!(weighting.weight(1000 * DUSK, epoch, 0), 1000 * DUSK);
+
+        // Stakes are only weighted from the activation height
+        let later = StakeAgeWeighting {
+            from_height: 10 * epoch,
+            ..weighting
+        };
+        assert_eq!(later.weight(1000 * DUSK, 0, 3 * epoch), 1000 * DUSK);
+        assert_eq!(later.weight(1000 * DUSK, 0, 10 * epoch), 1100 * DUSK);
+
+        // The weighting is part of the protocol of each network
+        assert!(ConsensusParams::for_chain(1).stake_age_weighting.is_none());
+        let devnet = ConsensusParams::for_chain(crate::config::DEVNET_CHAIN_ID);
+        devnet.validate().expect("devnet params to be valid");
+        assert!(devnet.stake_age_weighting.is_some());
+
+        let old = PublicKey::from_sk_seed_u64(1);
+        let new = PublicKey::from_sk_seed_u64(2);
+        let round = 200 * epoch;
+        let mut p = Provisioners::empty();
+        p.add_member_with_stake(old.clone(), Stake::new(1000 * DUSK, 0, 0, 0));
+        p.add_member_with_stake(
+            new.clone(),
+            Stake::new(1000 * DUSK, 0, round, 0),
+        );
+
+        let unweighted = Config::raw(
+            Seed::from([7u8; 48]),
+            round,
+            1,
+            200,
+            Exclusion::default(),
+        );
+        let weighted = Config {
+            stake_age_weighting: Some(weighting),
+            ..unweighted.clone()
+        };
+
+        // The old stake weighs twice the new one
+        let c = Committee::new(&p, &weighted);
+        assert!(c.votes_for(&old).unwrap() > c.votes_for(&new).unwrap());
+
+        // Stakes of the same age are extracted as without weighting
+        let mut p = Provisioners::empty();
+        p.add_member_with_stake(old.clone(), Stake::new(1000 * DUSK, 0, 0, 0));
+        p.add_member_with_stake(new.clone(), Stake::new(2000 * DUSK, 0, 0, 0));
+        let weighted = Config {
+            round: epoch - 1,
+            ..weighted
+        };
+        let unweighted = Config {
+            round: epoch - 1,
+            ..unweighted
+        };
+        assert_eq!(
+            Committee::new(&p, &weighted).get_occurrences(),
+            Committee::new(&p, &unweighted).get_occurrences()
+        );
+
+        // The generator extraction is never weighted
+        let params = ConsensusParams {
+            stake_age_weighting: Some(weighting),
+            ..Default::default()
+        };
+        let cfg = Config::new(
+            Seed::default(),
+            round,
+            1,
+            StepName::Proposal,
+            Exclusion::default(),
+            &params,
+        );
+        assert!(cfg.stake_age_weighting().is_none());
+
+        let invalid = StakeAgeWeighting {
+            max_boost_bps: 20_000,
+            ..weighting
+        };
+        assert!(invalid.validate().is_err());
+        let invalid = StakeAgeWeighting {
+            boost_per_epoch_bps: 0,
+            ..weighting
+        };
+        assert!(invalid.validate().is_err());
+    }
+
     #[test]
     fn test_intersect() {
         let p = generate_provisioners(10);
 
-        let cfg = Config::raw(Seed::default(), 1, 3, 200, None);
+        let cfg = Config::raw(Seed::default(), 1, 3, 200, Exclusion::default());
         // println!("{:#?}", p);
 
         let c = Committee::new(&p, &cfg);
@@ -277,6 +618,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_committee_cache() {
+        let p = generate_provisioners(5);
+        let params = ConsensusParams::default();
+        let cache = CommitteeCache::default();
+
+        let cfg = Config::raw(Seed::default(), 1, 1, 64, Exclusion::default());
+        let committee = cache.get_or_create(&p, &cfg);
+        assert_eq!(
+            committee.get_occurrences(),
+            Committee::new(&p, &cfg).get_occurrences()
+        );
+
+        // A set sharing the cache reuses the extracted committees
+        let set = CommitteeSet::with_cache(&p, params, cache.clone());
+        assert!(Arc::ptr_eq(&set.get_or_create(&cfg), &committee));
+
+        let generator = set.get_generator(0, Seed::default(), 1);
+        assert_eq!(generator, p.get_generator(0, Seed::default(), 1));
+        assert_eq!(cache.len(), 2);
+
+        // Another step is another committee
+        let other =
+            Config::raw(Seed::default(), 1, 2, 64, Exclusion::default());
+        assert!(cache.get(&other).is_none());
+    }
+
     fn generate_provisioners(n: usize) -> Provisioners {
         let sks = [
             "7f6f2ccdb23f2abb7b69278e947c01c6160a31cf02c19d06d0f6e5ab1d768b15",
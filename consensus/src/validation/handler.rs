@@ -4,20 +4,22 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use crate::aggregator::Aggregator;
 use crate::commons::{ConsensusError, RoundUpdate};
+use crate::msg_handler::step_state::StepState;
 use crate::msg_handler::{HandleMsgOutput, MsgHandler};
 use crate::step_votes_reg::SafeCertificateInfoRegistry;
+use crate::vote_cache::SafeVoteCache;
+use crate::vote_stats::SafeVoteStats;
 use async_trait::async_trait;
 use node_data::ledger::{Block, StepVotes};
 use node_data::StepName;
-use tracing::{info, warn};
+use tracing::info;
 
 use crate::user::committee::Committee;
 
 use crate::iteration_ctx::RoundCommittees;
 use node_data::message::payload::{QuorumType, Validation, Vote};
-use node_data::message::{payload, Message, Payload, StepMessage};
+use node_data::message::{payload, Message, Payload};
 
 fn final_result(
     sv: StepVotes,
@@ -31,25 +33,31 @@ fn final_result(
 }
 
 pub struct ValidationHandler {
-    pub(crate) aggr: Aggregator,
     pub(crate) candidate: Option<Block>,
-    sv_registry: SafeCertificateInfoRegistry,
-    curr_iteration: u8,
+    state: StepState,
+    vote_cache: SafeVoteCache,
 }
 
 impl ValidationHandler {
-    pub(crate) fn new(sv_registry: SafeCertificateInfoRegistry) -> Self {
+    pub(crate) fn new(
+        sv_registry: SafeCertificateInfoRegistry,
+        vote_cache: SafeVoteCache,
+        vote_stats: SafeVoteStats,
+    ) -> Self {
         Self {
-            sv_registry,
-            aggr: Aggregator::default(),
             candidate: None,
-            curr_iteration: 0,
+            state: StepState::new(
+                StepName::Validation,
+                sv_registry,
+                vote_stats,
+            ),
+            vote_cache,
         }
     }
 
     pub(crate) fn reset(&mut self, curr_iteration: u8) {
         self.candidate = None;
-        self.curr_iteration = curr_iteration;
+        self.state.reset(curr_iteration);
     }
 
     fn unwrap_msg(msg: Message) -> Result<Validation, ConsensusError> {
@@ -70,7 +78,17 @@ impl MsgHandler for ValidationHandler {
         _round_committees: &RoundCommittees,
     ) -> Result<(), ConsensusError> {
         match &msg.payload {
-            Payload::Validation(p) => p.verify_signature()?,
+            // Already verified by the node upon receipt
+            Payload::Validation(_) if msg.is_sig_verified() => (),
+            Payload::Validation(p) => self
+                .vote_cache
+                .lock()
+                .expect("vote cache lock to be acquired")
+                .verify_signature(p)
+                .map_err(|err| {
+                    self.state.record_invalid_signature(p);
+                    err
+                })?,
             Payload::Empty => (),
             _ => Err(ConsensusError::InvalidMsgType)?,
         };
@@ -92,36 +110,7 @@ impl MsgHandler for ValidationHandler {
             return Err(ConsensusError::InvalidVote(p.vote));
         }
 
-        let iteration = p.header().iteration;
-        if iteration != self.curr_iteration {
-            // Message that belongs to step from the past must be handled with
-            // collect_from_past fn
-            return Err(ConsensusError::InvalidMsgIteration(iteration));
-        }
-
-        let (sv, quorum_reached) = self
-            .aggr
-            .collect_vote(committee, p.sign_info(), &p.vote, p.get_step())
-            .map_err(|error| {
-                warn!(
-                    event = "Cannot collect vote",
-                    ?error,
-                    from = p.sign_info().signer.to_bs58(),
-                    ?p.vote,
-                    msg_step = p.get_step(),
-                    msg_round = p.header().round,
-                );
-                ConsensusError::InvalidVote(p.vote)
-            })?;
-        // Record result in global round registry
-        _ = self.sv_registry.lock().await.add_step_votes(
-            iteration,
-            &p.vote,
-            sv,
-            StepName::Validation,
-            quorum_reached,
-            committee.excluded().expect("Generator to be excluded"),
-        );
+        let (sv, quorum_reached) = self.state.collect(&p, committee).await?;
 
         if quorum_reached {
             let vote = p.vote;
@@ -155,45 +144,27 @@ impl MsgHandler for ValidationHandler {
             return Err(ConsensusError::InvalidVote(p.vote));
         }
 
-        // Collect vote, if msg payload is validation type
-        let collect_vote = self.aggr.collect_vote(
-            committee,
-            p.sign_info(),
-            &p.vote,
-            p.get_step(),
-        );
-
-        match collect_vote {
-            Ok((sv, quorum_reached)) => {
-                if let Some(quorum_msg) =
-                    self.sv_registry.lock().await.add_step_votes(
-                        p.header().iteration,
-                        &p.vote,
-                        sv,
-                        StepName::Validation,
-                        quorum_reached,
-                        committee.excluded().expect("Generator to be excluded"),
-                    )
-                {
-                    return Ok(HandleMsgOutput::Ready(quorum_msg));
-                }
-            }
-            Err(error) => {
-                warn!(
-                    event = "Cannot collect vote",
-                    ?error,
-                    from = p.sign_info().signer.to_bs58(),
-                    vote = ?p.vote,
-                    msg_step = p.get_step(),
-                    msg_round = p.header().round,
-                );
-            }
+        match self.state.collect_past(&p, committee).await? {
+            Some(quorum_msg) => Ok(HandleMsgOutput::Ready(quorum_msg)),
+            None => Ok(HandleMsgOutput::Pending),
         }
-        Ok(HandleMsgOutput::Pending)
     }
 
     /// Handles of an event of step execution timeout
     fn handle_timeout(&self) -> Result<HandleMsgOutput, ConsensusError> {
         Ok(HandleMsgOutput::Ready(Message::empty()))
     }
+
+    fn missing_votes(&self, step: u16, committee: &Committee) -> Option<u64> {
+        self.state.missing_votes(step, committee)
+    }
+
+    fn votes_of(
+        &self,
+        step: u16,
+        committee: &Committee,
+        signers: u64,
+    ) -> Vec<Message> {
+        self.state.votes_of(step, committee, signers)
+    }
 }
@@ -7,13 +7,15 @@
 use crate::commons::{ConsensusError, Database, RoundUpdate};
 use crate::config;
 use crate::execution_ctx::ExecutionCtx;
+use crate::msg_handler::{HandleMsgOutput, MsgHandler};
 use crate::operations::Operations;
+use crate::signer::{sign_message, SignerError};
 use crate::validation::handler;
 use anyhow::anyhow;
 use node_data::ledger::{to_str, Block};
 use node_data::message::payload::{Validation, Vote};
 use node_data::message::{
-    AsyncQueue, ConsensusHeader, Message, Payload, SignInfo, StepMessage,
+    AsyncQueue, ConsensusHeader, Message, Payload, SignInfo,
 };
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -48,25 +50,29 @@ impl<T: Operations + 'static> ValidationStep<T> {
                     candidate.as_ref(),
                     &ru,
                     outbound,
-                    inbound,
+                    Some(inbound),
                     executor,
                 )
-                .await
+                .await;
             }
             .instrument(tracing::info_span!("validation", hash,)),
         );
     }
 
+    /// Verifies the candidate, if any, then signs and publishes the vote.
+    ///
+    /// The vote is registered locally through `inbound`, if provided, and
+    /// returned in any case.
     pub(crate) async fn try_vote(
         iteration: u8,
         candidate: Option<&Block>,
         ru: &RoundUpdate,
         outbound: AsyncQueue<Message>,
-        inbound: AsyncQueue<Message>,
+        inbound: Option<AsyncQueue<Message>>,
         executor: Arc<Mutex<T>>,
-    ) {
+    ) -> Option<Message> {
         if candidate.is_none() {
-            Self::cast_vote(
+            return Self::cast_vote(
                 Vote::NoCandidate,
                 ru,
                 iteration,
@@ -74,7 +80,6 @@ impl<T: Operations + 'static> ValidationStep<T> {
                 inbound,
             )
             .await;
-            return;
         }
         let candidate = candidate.expect("Candidate to be already checked");
         let header = candidate.header();
@@ -92,7 +97,7 @@ impl<T: Operations + 'static> ValidationStep<T> {
             // block producer.
             // However, this is already verified in the Candidate message
             // verification, so it's safe to vote invalid here
-            Self::cast_vote(
+            return Self::cast_vote(
                 Vote::Invalid(header.hash),
                 ru,
                 iteration,
@@ -100,7 +105,6 @@ impl<T: Operations + 'static> ValidationStep<T> {
                 inbound,
             )
             .await;
-            return;
         };
 
         // Call Verify State Transition to make sure transactions set is valid
@@ -112,7 +116,7 @@ impl<T: Operations + 'static> ValidationStep<T> {
             }
         };
 
-        Self::cast_vote(vote, ru, iteration, outbound, inbound).await;
+        Self::cast_vote(vote, ru, iteration, outbound, inbound).await
     }
 
     async fn cast_vote(
@@ -120,10 +124,17 @@ impl<T: Operations + 'static> ValidationStep<T> {
         ru: &RoundUpdate,
         iteration: u8,
         outbound: AsyncQueue<Message>,
-        inbound: AsyncQueue<Message>,
-    ) {
+        inbound: Option<AsyncQueue<Message>>,
+    ) -> Option<Message> {
         // Sign and construct validation message
-        let validation = self::build_validation_payload(vote, ru, iteration);
+        let validation =
+            match self::build_validation_payload(vote, ru, iteration).await {
+                Ok(validation) => validation,
+                Err(err) => {
+                    error!(event = "failed_sign_validation", ?err);
+                    return None;
+                }
+            };
         info!(event = "send_vote", vote = ?validation.vote);
         let msg = Message::new_validation(validation);
 
@@ -133,9 +144,13 @@ impl<T: Operations + 'static> ValidationStep<T> {
         });
 
         // Register my vote locally
-        inbound.send(msg).await.unwrap_or_else(|err| {
-            error!("could not register validation {err:?}")
-        });
+        if let Some(inbound) = inbound {
+            inbound.send(msg.clone()).await.unwrap_or_else(|err| {
+                error!("could not register validation {err:?}")
+            });
+        }
+
+        Some(msg)
     }
 
     async fn call_vst(
@@ -178,12 +193,13 @@ impl<T: Operations + 'static> ValidationStep<T> {
     }
 }
 
-pub fn build_validation_payload(
+pub async fn build_validation_payload(
     vote: Vote,
     ru: &RoundUpdate,
     iteration: u8,
-) -> Validation {
+) -> Result<Validation, SignerError> {
     let header = ConsensusHeader {
+        chain_id: ru.chain_id(),
         prev_block_hash: ru.hash(),
         round: ru.round,
         iteration,
@@ -195,8 +211,8 @@ pub fn build_validation_payload(
         vote,
         sign_info,
     };
-    validation.sign(&ru.secret_key, ru.pubkey_bls.inner());
-    validation
+    sign_message(ru.signer.as_ref(), &mut validation).await?;
+    Ok(validation)
 }
 
 impl<T: Operations + 'static> ValidationStep<T> {
@@ -249,7 +265,32 @@ impl<T: Operations + 'static> ValidationStep<T> {
             let voting_enabled = candidate.is_some()
                 || ctx.iteration < config::EMERGENCY_MODE_ITERATION_THRESHOLD;
 
-            if voting_enabled {
+            if voting_enabled && ctx.instant_finality() {
+                // Being the whole committee, there is no point in waiting for
+                // the vote to be verified in the background
+                let vote_msg = Self::try_vote(
+                    ctx.iteration,
+                    candidate.as_ref(),
+                    &ctx.round_update,
+                    ctx.outbound.clone(),
+                    None,
+                    self.executor.clone(),
+                )
+                .await;
+
+                // Collect my own vote
+                if let Some(vote_msg) = vote_msg {
+                    let res = self
+                        .handler
+                        .lock()
+                        .await
+                        .collect(vote_msg, &ctx.round_update, committee)
+                        .await?;
+                    if let HandleMsgOutput::Ready(m) = res {
+                        return Ok(m);
+                    }
+                }
+            } else if voting_enabled {
                 Self::spawn_try_vote(
                     &mut ctx.iter_ctx.join_set,
                     ctx.iteration,
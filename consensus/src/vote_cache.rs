@@ -0,0 +1,93 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use node_data::bls::PublicKeyBytes;
+use node_data::ledger::Signature;
+use node_data::message::StepMessage;
+use sha3::{Digest, Sha3_256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::trace;
+
+pub type SafeVoteCache = Arc<Mutex<VoteCache>>;
+
+/// Cache of the votes whose signature has already been verified in the
+/// current round.
+///
+/// The same vote may reach a node several times (re-propagation, drained
+/// future messages, emergency mode). Votes are keyed by signer and hash of
+/// the whole signed message, header included, so that a vote is BLS-verified
+/// only once per round, as long as it comes with the very same signature.
+#[derive(Default)]
+pub struct VoteCache {
+    verified: HashMap<(PublicKeyBytes, [u8; 32]), Signature>,
+}
+
+impl VoteCache {
+    /// Verifies the signature of a step message, unless the same signature
+    /// has already been verified for the same signed message.
+    pub(crate) fn verify_signature<M: StepMessage>(
+        &mut self,
+        msg: &M,
+    ) -> Result<(), dusk_bls12_381_sign::Error> {
+        let sign_info = msg.sign_info();
+        let signed: [u8; 32] = Sha3_256::digest(msg.signable()).into();
+        let key = (*sign_info.signer.bytes(), signed);
+
+        if self.verified.get(&key) == Some(&sign_info.signature) {
+            trace!(event = "vote cache hit", step = msg.get_step());
+            return Ok(());
+        }
+
+        msg.verify_signature()?;
+        self.verified.insert(key, sign_info.signature);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dusk_bls12_381_sign::{PublicKey, SecretKey};
+    use node_data::message::payload::{Validation, Vote};
+    use node_data::message::{ConsensusHeader, SignInfo};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_vote_cache() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let sk = SecretKey::random(&mut rng);
+        let pk = PublicKey::from(&sk);
+
+        let mut msg = Validation {
+            header: ConsensusHeader::default(),
+            vote: Vote::Valid([1u8; 32]),
+            sign_info: SignInfo::default(),
+        };
+        msg.sign(&sk, &pk);
+
+        let mut cache = VoteCache::default();
+
+        cache.verify_signature(&msg).expect("valid signature");
+        cache.verify_signature(&msg).expect("cached signature");
+
+        // The cached signature is not accepted for another header casting
+        // the same vote
+        let mut other = msg.clone();
+        other.header.prev_block_hash = [2u8; 32];
+        assert!(cache.verify_signature(&other).is_err());
+
+        other.header = msg.header.clone();
+        other.header.round += 1;
+        assert!(cache.verify_signature(&other).is_err());
+
+        // A different signature for the same message is verified again
+        msg.sign_info.signature = Signature::default();
+        assert!(cache.verify_signature(&msg).is_err());
+    }
+}
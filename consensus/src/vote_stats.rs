@@ -0,0 +1,424 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use node_data::bls::PublicKey;
+use node_data::ledger::StepVotes;
+use node_data::message::StepMessage;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::aggregator::AggregatorError;
+use crate::user::committee::Committee;
+
+pub type SafeVoteStats = Arc<Mutex<VoteStats>>;
+
+/// Time given to the committee members to vote once a step reached its
+/// quorum, or received its first vote if it never did, before the members
+/// that did not are counted as missing.
+///
+/// Votes keep being collected after the quorum, but the round ends as soon as
+/// the quorum of its last step is reached: slow members of that step cannot
+/// be told apart from absent ones.
+pub const LATE_VOTE_WINDOW: Duration = Duration::from_secs(2);
+
+/// Outcome of the collection of a vote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteOutcome {
+    Collected,
+    Duplicated,
+    InvalidSignature,
+    NotCommitteeMember,
+}
+
+/// Statistics of the votes received for a single step
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepStats {
+    pub votes: u32,
+    pub duplicates: u32,
+    pub invalid_signatures: u32,
+    pub not_members: u32,
+    /// Votes collected after the quorum was reached
+    pub late: u32,
+    /// Time between the first vote and the quorum, in milliseconds
+    pub quorum_time_ms: Option<u64>,
+    /// Committee members that didn't vote
+    pub missing: Vec<String>,
+    /// Committee members that didn't vote before the round was closed, too
+    /// soon for their votes to be told missing rather than late
+    pub unconfirmed: Vec<String>,
+
+    #[serde(skip)]
+    first_vote: Option<Instant>,
+    #[serde(skip)]
+    quorum: Option<Instant>,
+    #[serde(skip)]
+    members: BTreeSet<String>,
+    #[serde(skip)]
+    voters: BTreeSet<String>,
+}
+
+/// Statistics of the votes cast by a single provisioner during a round
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvisionerStats {
+    pub votes: u32,
+    pub duplicates: u32,
+    pub invalid_signatures: u32,
+    /// Number of votes received after the quorum of their step was reached
+    pub late: u32,
+    /// Number of steps the provisioner was a member of without voting
+    pub missed: u32,
+    /// Cumulative time between the first vote of a step and the vote of the
+    /// provisioner, in milliseconds
+    pub delay_ms: u64,
+}
+
+/// Statistics of the votes received during a round, by step and by
+/// provisioner.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteStats {
+    pub round: u64,
+    pub steps: BTreeMap<u16, StepStats>,
+    /// Stats by base58 encoded provisioner key
+    pub provisioners: BTreeMap<String, ProvisionerStats>,
+}
+
+impl VoteStats {
+    pub fn new(round: u64) -> Self {
+        Self {
+            round,
+            ..Default::default()
+        }
+    }
+
+    /// Records a vote cast by `signer` for `step`.
+    pub(crate) fn on_vote(
+        &mut self,
+        step: u16,
+        signer: &PublicKey,
+        committee: Option<&Committee>,
+        outcome: VoteOutcome,
+    ) {
+        let now = Instant::now();
+        let signer = signer.to_base58();
+
+        let step = self.steps.entry(step).or_default();
+        let first_vote = *step.first_vote.get_or_insert(now);
+        if let Some(committee) = committee {
+            if step.members.is_empty() {
+                step.members =
+                    committee.iter().map(PublicKey::to_base58).collect();
+            }
+        }
+
+        let provisioner = self.provisioners.entry(signer.clone()).or_default();
+        match outcome {
+            VoteOutcome::Collected => {
+                step.votes += 1;
+                step.voters.insert(signer);
+                provisioner.votes += 1;
+                if step.quorum.is_some() {
+                    step.late += 1;
+                    provisioner.late += 1;
+                }
+                provisioner.delay_ms +=
+                    now.duration_since(first_vote).as_millis() as u64;
+            }
+            VoteOutcome::Duplicated => {
+                step.duplicates += 1;
+                provisioner.duplicates += 1;
+            }
+            VoteOutcome::InvalidSignature => {
+                step.invalid_signatures += 1;
+                provisioner.invalid_signatures += 1;
+            }
+            VoteOutcome::NotCommitteeMember => step.not_members += 1,
+        }
+    }
+
+    /// Records the result of the aggregation of a vote cast by `signer`.
+    pub(crate) fn on_collect(
+        &mut self,
+        step: u16,
+        signer: &PublicKey,
+        committee: &Committee,
+        result: &Result<(StepVotes, bool), AggregatorError>,
+    ) {
+        let outcome = match result {
+            Ok(_) => VoteOutcome::Collected,
+            Err(AggregatorError::DuplicatedVote) => VoteOutcome::Duplicated,
+            Err(AggregatorError::NotCommitteeMember) => {
+                VoteOutcome::NotCommitteeMember
+            }
+            Err(AggregatorError::InvalidSignature(_)) => {
+                VoteOutcome::InvalidSignature
+            }
+        };
+        self.on_vote(step, signer, Some(committee), outcome);
+
+        if let Ok((_, true)) = result {
+            self.on_quorum(step);
+        }
+    }
+
+    /// Records that a quorum has been reached for `step`.
+    pub(crate) fn on_quorum(&mut self, step: u16) {
+        let step = self.steps.entry(step).or_default();
+        if step.quorum.is_none() {
+            let now = Instant::now();
+            step.quorum = Some(now);
+            step.quorum_time_ms = step
+                .first_vote
+                .map(|first| now.duration_since(first).as_millis() as u64);
+        }
+    }
+
+    /// Computes the members that didn't vote, once the round is over.
+    pub fn close(&mut self) {
+        self.close_at(Instant::now())
+    }
+
+    fn close_at(&mut self, now: Instant) {
+        for step in self.steps.values_mut() {
+            let absent = step.members.difference(&step.voters).cloned();
+
+            let settled = step.quorum.or(step.first_vote).map_or(true, |at| {
+                now.saturating_duration_since(at) >= LATE_VOTE_WINDOW
+            });
+            if !settled {
+                step.unconfirmed = absent.collect();
+                continue;
+            }
+
+            step.missing = absent.collect();
+            for member in &step.missing {
+                self.provisioners.entry(member.clone()).or_default().missed +=
+                    1;
+            }
+        }
+    }
+}
+
+/// Rounds after which a provisioner no longer expected to vote is forgotten
+const ABSENCE_RETENTION_ROUNDS: u64 = 10_000;
+
+/// Absence record of a provisioner across rounds
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Absence {
+    /// Consecutive rounds the provisioner was a committee member of without
+    /// casting any vote
+    pub streak: u32,
+    pub longest_streak: u32,
+    /// Rounds the provisioner was a committee member of
+    pub expected_rounds: u64,
+    /// Rounds the provisioner was a committee member of without voting
+    pub absent_rounds: u64,
+    /// Last round the provisioner was a committee member of
+    pub last_expected: u64,
+    /// Last round a vote of the provisioner was received
+    pub last_vote: Option<u64>,
+}
+
+/// Absence streaks of the committee members, by base58 encoded provisioner
+/// key.
+///
+/// A round counts as an absence for a provisioner that was a member of at
+/// least one step without any of its votes being received within the
+/// [`LATE_VOTE_WINDOW`], whether it was the generator or not. Rounds it was
+/// not a member of leave its streak untouched.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbsenceStreaks {
+    pub round: u64,
+    pub provisioners: BTreeMap<String, Absence>,
+}
+
+impl AbsenceStreaks {
+    /// Updates the streaks with the stats of a closed round.
+    pub fn update(&mut self, stats: &VoteStats) {
+        self.round = stats.round;
+
+        for (key, p) in &stats.provisioners {
+            if p.votes == 0 && p.missed == 0 {
+                continue;
+            }
+
+            let absence = self.provisioners.entry(key.clone()).or_default();
+            absence.expected_rounds += 1;
+            absence.last_expected = stats.round;
+            if p.votes > 0 {
+                absence.streak = 0;
+                absence.last_vote = Some(stats.round);
+            } else {
+                absence.streak += 1;
+                absence.absent_rounds += 1;
+                absence.longest_streak =
+                    absence.longest_streak.max(absence.streak);
+            }
+        }
+
+        let round = stats.round;
+        self.provisioners.retain(|_, absence| {
+            round.saturating_sub(absence.last_expected)
+                <= ABSENCE_RETENTION_ROUNDS
+        });
+    }
+
+    /// Returns the provisioners currently absent for at least `min_streak`
+    /// rounds, the longest streak first.
+    pub fn absent(&self, min_streak: u32) -> Vec<(&String, &Absence)> {
+        let mut absent: Vec<_> = self
+            .provisioners
+            .iter()
+            .filter(|(_, absence)| {
+                absence.streak > 0 && absence.streak >= min_streak
+            })
+            .collect();
+        absent.sort_by(|a, b| b.1.streak.cmp(&a.1.streak));
+        absent
+    }
+}
+
+/// Records the result of the aggregation of the vote carried by `msg`.
+pub(crate) fn record_vote<M: StepMessage>(
+    stats: &SafeVoteStats,
+    msg: &M,
+    committee: &Committee,
+    result: &Result<(StepVotes, bool), AggregatorError>,
+) {
+    stats
+        .lock()
+        .expect("vote stats lock to be acquired")
+        .on_collect(msg.get_step(), &msg.sign_info().signer, committee, result);
+}
+
+/// Records a vote whose signature failed the verification.
+pub(crate) fn record_invalid_signature<M: StepMessage>(
+    stats: &SafeVoteStats,
+    msg: &M,
+) {
+    stats
+        .lock()
+        .expect("vote stats lock to be acquired")
+        .on_vote(
+            msg.get_step(),
+            &msg.sign_info().signer,
+            None,
+            VoteOutcome::InvalidSignature,
+        );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vote_stats() {
+        let alice = PublicKey::from_sk_seed_u64(1);
+        let bob = PublicKey::from_sk_seed_u64(2);
+
+        let mut stats = VoteStats::new(10);
+        stats.steps.entry(1).or_default().members =
+            [alice.to_base58(), bob.to_base58()].into();
+
+        stats.on_vote(1, &alice, None, VoteOutcome::Collected);
+        stats.on_vote(1, &alice, None, VoteOutcome::Duplicated);
+        stats.on_vote(2, &bob, None, VoteOutcome::InvalidSignature);
+        stats.on_quorum(1);
+        stats.on_vote(3, &alice, None, VoteOutcome::Collected);
+        stats.on_quorum(3);
+        stats.on_vote(3, &bob, None, VoteOutcome::Collected);
+        stats.close_at(Instant::now() + LATE_VOTE_WINDOW);
+
+        let step = &stats.steps[&1];
+        assert_eq!(step.votes, 1);
+        assert_eq!(step.duplicates, 1);
+        assert_eq!(step.late, 0);
+        assert!(step.quorum_time_ms.is_some());
+        assert_eq!(stats.steps[&3].late, 1);
+        assert_eq!(step.missing, vec![bob.to_base58()]);
+        assert_eq!(stats.steps[&2].invalid_signatures, 1);
+
+        let alice = &stats.provisioners[&alice.to_base58()];
+        assert_eq!((alice.votes, alice.duplicates, alice.missed), (2, 1, 0));
+        assert_eq!(alice.late, 0);
+        let bob = &stats.provisioners[&bob.to_base58()];
+        assert_eq!((bob.votes, bob.invalid_signatures, bob.missed), (1, 1, 1));
+        assert_eq!(bob.late, 1);
+    }
+
+    #[test]
+    fn test_late_votes_are_not_missed() {
+        let alice = PublicKey::from_sk_seed_u64(1);
+        let bob = PublicKey::from_sk_seed_u64(2);
+
+        let mut stats = VoteStats::new(10);
+        stats.steps.entry(1).or_default().members =
+            [alice.to_base58(), bob.to_base58()].into();
+        stats.on_vote(1, &alice, None, VoteOutcome::Collected);
+        stats.on_quorum(1);
+
+        // The round ends with the quorum, bob may still be voting
+        let mut closed = stats.clone();
+        closed.close();
+        assert!(closed.steps[&1].missing.is_empty());
+        assert_eq!(closed.steps[&1].unconfirmed, vec![bob.to_base58()]);
+        assert!(!closed.provisioners.contains_key(&bob.to_base58()));
+
+        let mut closed = stats.clone();
+        closed.close_at(Instant::now() + LATE_VOTE_WINDOW);
+        assert_eq!(closed.steps[&1].missing, vec![bob.to_base58()]);
+        assert_eq!(closed.provisioners[&bob.to_base58()].missed, 1);
+    }
+
+    #[test]
+    fn test_absence_streaks() {
+        let alice = PublicKey::from_sk_seed_u64(1).to_base58();
+        let bob = PublicKey::from_sk_seed_u64(2).to_base58();
+
+        let round = |round, voted: &[&String], missed: &[&String]| {
+            let mut stats = VoteStats::new(round);
+            for key in voted {
+                stats.provisioners.entry(key.to_string()).or_default().votes =
+                    1;
+            }
+            for key in missed {
+                stats
+                    .provisioners
+                    .entry(key.to_string())
+                    .or_default()
+                    .missed = 1;
+            }
+            stats
+        };
+
+        let mut streaks = AbsenceStreaks::default();
+        streaks.update(&round(1, &[&alice], &[&bob]));
+        streaks.update(&round(2, &[&alice], &[&bob]));
+        // Not a member of the committee, the streak goes on
+        streaks.update(&round(3, &[&alice], &[]));
+        streaks.update(&round(4, &[], &[&alice, &bob]));
+
+        assert_eq!(streaks.round, 4);
+        let absent = streaks.absent(2);
+        assert_eq!(absent.len(), 1);
+        assert_eq!(absent[0].0, &bob);
+        assert_eq!(absent[0].1.streak, 3);
+        assert_eq!(absent[0].1.expected_rounds, 3);
+        assert_eq!(absent[0].1.last_vote, None);
+        assert_eq!(streaks.absent(0).len(), 2);
+
+        // A vote ends the streak, keeping the longest one
+        streaks.update(&round(5, &[&bob], &[]));
+        let bob = &streaks.provisioners[&bob];
+        assert_eq!((bob.streak, bob.longest_streak), (0, 3));
+        assert_eq!(bob.last_vote, Some(5));
+
+        // Provisioners no longer expected to vote are forgotten
+        streaks.update(&round(6 + ABSENCE_RETENTION_ROUNDS, &[], &[]));
+        assert!(streaks.provisioners.is_empty());
+    }
+}
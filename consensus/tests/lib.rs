@@ -3,3 +3,191 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
+
+mod sim;
+
+use std::time::Duration;
+
+use dusk_bls12_381_sign::{PublicKey as BlsPublicKey, SecretKey};
+use dusk_consensus::config::ConsensusParams;
+use dusk_consensus::operations::{CallParams, Operations};
+use node_data::bls::PublicKey;
+use node_data::ledger::{Block, Header};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use sim::mocks::Executor;
+use sim::network::{Network, Peer};
+use sim::Simulation;
+
+/// Upper bound of the time taken by the simulated networks to reach a height
+const LIVENESS_TIMEOUT: Duration = Duration::from_secs(180);
+
+#[tokio::test]
+async fn test_executor_rejects_tampered_candidates() {
+    let genesis = Header {
+        seed: [5; 48].into(),
+        ..Default::default()
+    };
+    let genesis = Block::new(genesis, vec![]).expect("valid block");
+    let network = Network::new(vec![Peer::default()], genesis.clone(), 0);
+    let executor = Executor::new(network, 0);
+
+    let sk = SecretKey::random(&mut StdRng::seed_from_u64(0xbeef));
+    let generator = PublicKey::new(BlsPublicKey::from(&sk));
+    let params = CallParams {
+        round: 1,
+        block_gas_limit: 0,
+        generator_pubkey: generator.clone(),
+        missed_generators: vec![],
+        timestamp: 1,
+        seed: Default::default(),
+    };
+    let output = executor
+        .execute_state_transition(params)
+        .await
+        .expect("execution to succeed")
+        .verification_output;
+
+    let header = Header {
+        height: 1,
+        timestamp: 1,
+        prev_block_hash: genesis.header().hash,
+        generator_bls_pubkey: *generator.bytes(),
+        state_hash: output.state_root,
+        ..Default::default()
+    };
+    let candidate = Block::new(header.clone(), vec![]).expect("valid block");
+    assert!(executor.verify_block_header(&header, true).await.is_ok());
+    let verified = executor.verify_state_transition(&candidate).await;
+    assert_eq!(verified.expect("verification to succeed"), output);
+
+    let tampered_headers = [
+        Header {
+            height: 2,
+            ..header.clone()
+        },
+        Header {
+            prev_block_hash: [1; 32],
+            ..header.clone()
+        },
+        Header {
+            timestamp: 0,
+            ..header.clone()
+        },
+    ];
+    for tampered in tampered_headers {
+        assert!(executor.verify_block_header(&tampered, true).await.is_err());
+    }
+
+    // Validators compare the verified state root with the one of the header
+    let tampered = Header {
+        state_hash: [1; 32],
+        ..header
+    };
+    let tampered = Block::new(tampered, vec![]).expect("valid block");
+    let verified = executor.verify_state_transition(&tampered).await;
+    assert_ne!(
+        verified.expect("verification to succeed").state_root,
+        tampered.header().state_hash
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "wall-clock simulation, run with `cargo test -- --ignored`"]
+async fn test_liveness_with_drops_and_delays() {
+    let sim = Simulation::start(5, 0xbeef);
+    sim.network.set_drop_rate(0.05);
+    sim.network.set_max_delay(Duration::from_millis(200));
+
+    let all: Vec<_> = (0..5).collect();
+    assert!(
+        sim.wait_for_height(&all, 3, LIVENESS_TIMEOUT).await,
+        "heights stuck at {:?}",
+        sim.network.heights()
+    );
+    sim.assert_safety();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "wall-clock simulation, run with `cargo test -- --ignored`"]
+async fn test_isolated_node_catches_up() {
+    let sim = Simulation::start(7, 0xbeef);
+    let connected: Vec<_> = (0..6).collect();
+    sim.network.partition(&[&connected]);
+
+    assert!(
+        sim.wait_for_height(&connected, 2, LIVENESS_TIMEOUT).await,
+        "heights stuck at {:?}",
+        sim.network.heights()
+    );
+    // Alone, the isolated node cannot reach any quorum
+    assert_eq!(sim.network.height(6), 0);
+
+    sim.network.heal();
+    let all: Vec<_> = (0..7).collect();
+    assert!(
+        sim.wait_for_height(&all, 3, LIVENESS_TIMEOUT).await,
+        "heights stuck at {:?}",
+        sim.network.heights()
+    );
+    sim.assert_safety();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "wall-clock simulation, run with `cargo test -- --ignored`"]
+async fn test_split_network_halts_and_recovers() {
+    let sim = Simulation::start(6, 0xbeef);
+    let all: Vec<_> = (0..6).collect();
+    assert!(
+        sim.wait_for_height(&all, 1, LIVENESS_TIMEOUT).await,
+        "heights stuck at {:?}",
+        sim.network.heights()
+    );
+
+    sim.network.partition(&[&[0, 1, 2], &[3, 4, 5]]);
+    let split_height = sim.network.heights().into_iter().max().unwrap();
+
+    // Neither half holds a supermajority of the committees. At most the
+    // round whose quorum was in flight at the split is finalized.
+    tokio::time::sleep(Duration::from_secs(15)).await;
+    let heights = sim.network.heights();
+    assert!(
+        heights.iter().all(|&h| h <= split_height + 1),
+        "heights moved from {split_height} to {heights:?} while split"
+    );
+
+    sim.network.heal();
+    assert!(
+        sim.wait_for_height(&all, split_height + 2, LIVENESS_TIMEOUT)
+            .await,
+        "heights stuck at {:?}",
+        sim.network.heights()
+    );
+    sim.assert_safety();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "wall-clock simulation, run with `cargo test -- --ignored`"]
+async fn test_instant_finality() {
+    // Without instant finality, each block of a lone provisioner waits for
+    // the block generation delay
+    let sim = Simulation::start(1, 0xbeef);
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    let height = sim.network.height(0);
+    assert!(height <= 3, "reached {height} without instant finality");
+    drop(sim);
+
+    let params = ConsensusParams {
+        instant_finality: true,
+        ..Default::default()
+    };
+    let sim = Simulation::start_with(1, 0xbeef, params);
+    assert!(
+        sim.wait_for_height(&[0], 10, Duration::from_secs(3)).await,
+        "reached {} with instant finality",
+        sim.network.height(0)
+    );
+
+    // The certificates are the ones of a normal round
+    sim.assert_certificates(0).await;
+}
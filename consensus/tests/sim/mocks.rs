@@ -0,0 +1,142 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use dusk_consensus::commons::Database;
+use dusk_consensus::operations::{
+    CallParams, Error, Operations, Output, VerificationOutput,
+};
+use dusk_consensus::round_state::RoundState;
+use dusk_consensus::vote_stats::VoteStats;
+use node_data::ledger::{Block, Hash, Header};
+use node_data::StepName;
+use sha3::{Digest, Sha3_256};
+
+use super::network::Network;
+
+/// Executor of a node, producing empty blocks.
+///
+/// Candidates are checked against the tip of the node, and the state root of
+/// a block is derived from its height and generator, so that a candidate
+/// with a tampered header or state is voted invalid.
+pub struct Executor {
+    network: Network,
+    node: usize,
+}
+
+impl Executor {
+    pub fn new(network: Network, node: usize) -> Self {
+        Self { network, node }
+    }
+}
+
+/// Returns the state root of the block generated by `generator` at `height`.
+pub fn state_root(height: u64, generator: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(height.to_le_bytes());
+    hasher.update(generator);
+    hasher.finalize().into()
+}
+
+#[async_trait::async_trait]
+impl Operations for Executor {
+    async fn verify_block_header(
+        &self,
+        candidate_header: &Header,
+        _disable_winning_cert_check: bool,
+    ) -> Result<(), Error> {
+        let tip = self.network.tip(self.node);
+        let tip = tip.header();
+
+        if candidate_header.prev_block_hash != tip.hash
+            || candidate_header.height != tip.height + 1
+            || candidate_header.timestamp <= tip.timestamp
+        {
+            return Err(Error::Failed);
+        }
+        Ok(())
+    }
+
+    async fn verify_state_transition(
+        &self,
+        blk: &Block,
+    ) -> Result<VerificationOutput, Error> {
+        if !blk.txs().is_empty() {
+            return Err(Error::Failed);
+        }
+
+        let header = blk.header();
+        Ok(VerificationOutput {
+            state_root: state_root(
+                header.height,
+                &header.generator_bls_pubkey.0,
+            ),
+            event_hash: [0; 32],
+        })
+    }
+
+    async fn execute_state_transition(
+        &self,
+        params: CallParams,
+    ) -> Result<Output, Error> {
+        let generator = params.generator_pubkey.bytes();
+        Ok(Output {
+            verification_output: VerificationOutput {
+                state_root: state_root(params.round, &generator.0),
+                event_hash: [0; 32],
+            },
+            ..Default::default()
+        })
+    }
+
+    async fn add_step_elapsed_time(
+        &self,
+        _round: u64,
+        _step_name: StepName,
+        _elapsed: Duration,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn add_vote_stats(&self, _stats: VoteStats) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn store_round_state(&self, _state: RoundState) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn load_round_state(&self) -> Option<RoundState> {
+        None
+    }
+}
+
+/// In-memory storage of the candidate blocks.
+#[derive(Default)]
+pub struct CandidateDb(HashMap<Hash, Block>);
+
+#[async_trait::async_trait]
+impl Database for CandidateDb {
+    fn store_candidate_block(&mut self, b: Block) {
+        self.0.insert(b.header().hash, b);
+    }
+
+    async fn get_candidate_block_by_hash(
+        &self,
+        h: &Hash,
+    ) -> anyhow::Result<Block> {
+        self.0
+            .get(h)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("candidate not found"))
+    }
+
+    fn delete_candidate_blocks(&mut self) {
+        self.0.clear();
+    }
+}
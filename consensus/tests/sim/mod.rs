@@ -0,0 +1,251 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Simulation of a network of provisioners running in-process.
+//!
+//! Each node runs the consensus rounds over its own chain, with the messages
+//! broadcast by a node routed to the others by an in-memory [`Network`] able
+//! to partition the nodes, delay and drop messages. The chain module is
+//! emulated: winning blocks are appended to the chain, and a node behind a
+//! reachable peer catches up with the peer's blocks.
+
+pub mod mocks;
+pub mod network;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use dusk_bls12_381_sign::{PublicKey as BlsPublicKey, SecretKey};
+use dusk_consensus::commons::{RoundUpdate, TimeoutSet};
+use dusk_consensus::config::ConsensusParams;
+use dusk_consensus::consensus::Consensus;
+use dusk_consensus::quorum::verifiers;
+use dusk_consensus::signer::{ConsensusSigner, LocalSigner};
+use dusk_consensus::user::committee::CommitteeSet;
+use dusk_consensus::user::provisioners::Provisioners;
+use node_data::bls::PublicKey;
+use node_data::ledger::{Block, Header};
+use node_data::message::{AsyncQueue, ConsensusHeader, Message};
+use node_data::StepName;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use tokio::sync::{oneshot, Mutex, RwLock};
+use tokio::task::JoinHandle;
+
+use mocks::{CandidateDb, Executor};
+use network::{Network, Peer};
+
+const STAKE: u64 = 1_000_000_000_000;
+
+/// How often a node checks whether it is behind its peers
+const SYNC_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A running simulation, aborted on drop.
+pub struct Simulation {
+    pub network: Network,
+    provisioners: Arc<Provisioners>,
+    params: ConsensusParams,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Simulation {
+    /// Starts `nodes` provisioners of equal stake from the same genesis.
+    pub fn start(nodes: usize, seed: u64) -> Self {
+        Self::start_with(nodes, seed, ConsensusParams::default())
+    }
+
+    /// Starts `nodes` provisioners of equal stake from the same genesis,
+    /// running the consensus with the given parameters.
+    pub fn start_with(
+        nodes: usize,
+        seed: u64,
+        params: ConsensusParams,
+    ) -> Self {
+        let rng = &mut StdRng::seed_from_u64(seed);
+        let mut provisioners = Provisioners::empty();
+        let mut signers = vec![];
+        for _ in 0..nodes {
+            let sk = SecretKey::random(rng);
+            let pk = PublicKey::new(BlsPublicKey::from(&sk));
+            provisioners.add_member_with_value(pk.clone(), STAKE);
+            let signer: Arc<dyn ConsensusSigner> =
+                Arc::new(LocalSigner::new(sk, pk));
+            signers.push(signer);
+        }
+        let provisioners = Arc::new(provisioners);
+
+        let genesis = Header {
+            seed: [5; 48].into(),
+            ..Default::default()
+        };
+        let genesis = Block::new(genesis, vec![]).expect("valid block");
+
+        let peers: Vec<_> = (0..nodes).map(|_| Peer::default()).collect();
+        let network = Network::new(peers.clone(), genesis, seed);
+
+        let mut tasks = vec![];
+        for (index, (peer, signer)) in
+            peers.into_iter().zip(signers).enumerate()
+        {
+            let outbound = AsyncQueue::unbounded();
+            tasks.push(network.spawn_router(index, outbound.clone()));
+            tasks.push(tokio::spawn(run_node(
+                index,
+                network.clone(),
+                signer,
+                provisioners.clone(),
+                params,
+                peer,
+                outbound,
+            )));
+        }
+
+        Self {
+            network,
+            provisioners,
+            params,
+            tasks,
+        }
+    }
+
+    /// Waits until all the `nodes` reach `height`, returning whether they
+    /// did before `timeout`.
+    pub async fn wait_for_height(
+        &self,
+        nodes: &[usize],
+        height: u64,
+        timeout: Duration,
+    ) -> bool {
+        let reached = async {
+            while nodes.iter().any(|&n| self.network.height(n) < height) {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        };
+        tokio::time::timeout(timeout, reached).await.is_ok()
+    }
+
+    /// Asserts no two nodes accepted different blocks at the same height.
+    pub fn assert_safety(&self) {
+        if let Some(fork) = self.network.forks().first() {
+            panic!(
+                "node {} accepted {} at height {} instead of {}",
+                fork.node,
+                hex::encode(fork.conflicting),
+                fork.height,
+                hex::encode(fork.accepted),
+            );
+        }
+    }
+
+    /// Asserts the certificate of every block accepted by `node` verifies
+    /// against the committees of its round.
+    pub async fn assert_certificates(&self, node: usize) {
+        let committees =
+            RwLock::new(CommitteeSet::new(&self.provisioners, self.params));
+
+        let chain = self.network.chain(node);
+        for blocks in chain.windows(2) {
+            let (prev, header) = (blocks[0].header(), blocks[1].header());
+            let cert = &header.cert;
+            let consensus_header = ConsensusHeader {
+                chain_id: header.chain_id,
+                prev_block_hash: prev.hash,
+                round: header.height,
+                iteration: header.iteration,
+            };
+
+            for (sv, step) in [
+                (&cert.validation, StepName::Validation),
+                (&cert.ratification, StepName::Ratification),
+            ] {
+                let res = verifiers::verify_step_votes(
+                    &consensus_header,
+                    cert.result.vote(),
+                    sv,
+                    &committees,
+                    prev.seed,
+                    step,
+                )
+                .await;
+                let quorum = res.unwrap_or_else(|err| {
+                    panic!("invalid {step:?} at {}: {err:?}", header.height)
+                });
+                assert!(quorum.quorum_reached(), "no quorum at {step:?}");
+            }
+        }
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        self.tasks.iter().for_each(|task| task.abort());
+    }
+}
+
+fn timeouts() -> TimeoutSet {
+    [
+        (StepName::Proposal, Duration::from_secs(3)),
+        (StepName::Validation, Duration::from_secs(3)),
+        (StepName::Ratification, Duration::from_secs(3)),
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// Runs the consensus rounds of a node over its chain.
+async fn run_node(
+    index: usize,
+    network: Network,
+    signer: Arc<dyn ConsensusSigner>,
+    provisioners: Arc<Provisioners>,
+    params: ConsensusParams,
+    peer: Peer,
+    outbound: AsyncQueue<Message>,
+) {
+    let executor = Arc::new(Mutex::new(Executor::new(network.clone(), index)));
+    let db = Arc::new(Mutex::new(CandidateDb::default()));
+
+    loop {
+        let tip = network.tip(index);
+        let ru =
+            RoundUpdate::new(signer.clone(), tip.header(), timeouts(), params);
+
+        let consensus = Consensus::new(
+            peer.main_inbound.clone(),
+            outbound.clone(),
+            peer.quorum_inbound.clone(),
+            outbound.clone(),
+            executor.clone(),
+            db.clone(),
+        );
+
+        let (cancel_tx, cancel_rx) = oneshot::channel::<i32>();
+        let mut cancel_tx = Some(cancel_tx);
+        let mut spin =
+            Box::pin(consensus.spin(ru, provisioners.clone(), cancel_rx));
+        let mut sync = tokio::time::interval(SYNC_INTERVAL);
+
+        let result = loop {
+            tokio::select! {
+                result = &mut spin => break result,
+                _ = sync.tick(), if cancel_tx.is_some() => {
+                    // Drop the round to catch up, as the chain does on
+                    // receiving a block of a higher height
+                    if network.is_behind(index) {
+                        if let Some(tx) = cancel_tx.take() {
+                            let _ = tx.send(0);
+                        }
+                    }
+                }
+            }
+        };
+
+        match result {
+            Ok(block) => network.accept(index, block),
+            Err(_) => network.sync(index),
+        }
+    }
+}
@@ -0,0 +1,250 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use node_data::ledger::{Block, Hash};
+use node_data::message::{AsyncQueue, Message, Payload};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::task::JoinHandle;
+
+/// Inbound queues of a simulated node
+#[derive(Clone)]
+pub struct Peer {
+    pub main_inbound: AsyncQueue<Message>,
+    pub quorum_inbound: AsyncQueue<Message>,
+}
+
+impl Default for Peer {
+    fn default() -> Self {
+        Self {
+            main_inbound: AsyncQueue::unbounded(),
+            quorum_inbound: AsyncQueue::unbounded(),
+        }
+    }
+}
+
+impl Peer {
+    /// Enqueues `msg` the way the acceptor of a node does.
+    fn deliver(&self, msg: Message) {
+        let _ = match &msg.payload {
+            Payload::Candidate(_)
+            | Payload::Validation(_)
+            | Payload::Ratification(_)
+            | Payload::GetVotes(_) => self.main_inbound.try_send(msg),
+            Payload::Quorum(_) => self.quorum_inbound.try_send(msg),
+            _ => Ok(()),
+        };
+    }
+}
+
+/// Conditions of the links between the nodes
+struct Links {
+    /// Partition of each node, messages only flow within a partition
+    partitions: Vec<usize>,
+    /// Probability of a message to be lost on a link
+    drop_rate: f64,
+    /// Upper bound of the random delay of a message on a link
+    max_delay: Duration,
+    rng: StdRng,
+}
+
+/// Two nodes accepting different blocks at the same height
+#[derive(Debug, Clone)]
+pub struct Fork {
+    pub height: u64,
+    pub node: usize,
+    pub accepted: Hash,
+    pub conflicting: Hash,
+}
+
+/// In-memory network of the simulated nodes, along with their chains.
+#[derive(Clone)]
+pub struct Network {
+    peers: Arc<Vec<Peer>>,
+    links: Arc<Mutex<Links>>,
+    chains: Arc<Vec<Mutex<Vec<Block>>>>,
+    /// First block accepted at each height by any node
+    accepted: Arc<Mutex<BTreeMap<u64, Hash>>>,
+    forks: Arc<Mutex<Vec<Fork>>>,
+}
+
+impl Network {
+    pub fn new(peers: Vec<Peer>, genesis: Block, seed: u64) -> Self {
+        let nodes = peers.len();
+        let chains = (0..nodes).map(|_| Mutex::new(vec![genesis.clone()]));
+
+        Self {
+            peers: Arc::new(peers),
+            links: Arc::new(Mutex::new(Links {
+                partitions: vec![0; nodes],
+                drop_rate: 0.0,
+                max_delay: Duration::ZERO,
+                rng: StdRng::seed_from_u64(seed),
+            })),
+            chains: Arc::new(chains.collect()),
+            accepted: Default::default(),
+            forks: Default::default(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Splits the nodes in `groups`. Nodes left out of all groups are
+    /// isolated together.
+    pub fn partition(&self, groups: &[&[usize]]) {
+        let mut links = self.links.lock().expect("lock to be acquired");
+        links.partitions.fill(0);
+        for (i, group) in groups.iter().enumerate() {
+            for &node in group.iter() {
+                links.partitions[node] = i + 1;
+            }
+        }
+    }
+
+    /// Reconnects all the nodes.
+    pub fn heal(&self) {
+        self.partition(&[]);
+    }
+
+    pub fn set_drop_rate(&self, drop_rate: f64) {
+        assert!((0.0..=1.0).contains(&drop_rate), "invalid drop rate");
+        self.links.lock().expect("lock to be acquired").drop_rate = drop_rate;
+    }
+
+    pub fn set_max_delay(&self, max_delay: Duration) {
+        self.links.lock().expect("lock to be acquired").max_delay = max_delay;
+    }
+
+    fn connected(&self, a: usize, b: usize) -> bool {
+        let links = self.links.lock().expect("lock to be acquired");
+        links.partitions[a] == links.partitions[b]
+    }
+
+    /// Returns the delay of a message sent from `from` to `to`, or `None`
+    /// if the message is lost.
+    fn route(&self, from: usize, to: usize) -> Option<Duration> {
+        let mut links = self.links.lock().expect("lock to be acquired");
+        if links.partitions[from] != links.partitions[to] {
+            return None;
+        }
+
+        let drop_rate = links.drop_rate;
+        if links.rng.gen_bool(drop_rate) {
+            return None;
+        }
+
+        let max_delay = links.max_delay;
+        Some(links.rng.gen_range(Duration::ZERO..=max_delay))
+    }
+
+    /// Forwards the messages broadcast by `from` to the other nodes.
+    pub fn spawn_router(
+        &self,
+        from: usize,
+        outbound: AsyncQueue<Message>,
+    ) -> JoinHandle<()> {
+        let network = self.clone();
+        tokio::spawn(async move {
+            while let Ok(msg) = outbound.recv().await {
+                for to in (0..network.len()).filter(|&to| to != from) {
+                    let Some(delay) = network.route(from, to) else {
+                        continue;
+                    };
+
+                    let peer = network.peers[to].clone();
+                    let msg = msg.clone();
+                    tokio::spawn(async move {
+                        tokio::time::sleep(delay).await;
+                        peer.deliver(msg);
+                    });
+                }
+            }
+        })
+    }
+
+    pub fn tip(&self, node: usize) -> Block {
+        let chain = self.chains[node].lock().expect("lock to be acquired");
+        chain.last().expect("genesis to be in the chain").clone()
+    }
+
+    /// Returns the chain of `node`, from the genesis block.
+    pub fn chain(&self, node: usize) -> Vec<Block> {
+        self.chains[node]
+            .lock()
+            .expect("lock to be acquired")
+            .clone()
+    }
+
+    pub fn height(&self, node: usize) -> u64 {
+        self.tip(node).header().height
+    }
+
+    pub fn heights(&self) -> Vec<u64> {
+        (0..self.len()).map(|node| self.height(node)).collect()
+    }
+
+    /// Appends `block` to the chain of `node`, if it extends its tip.
+    pub fn accept(&self, node: usize, block: Block) {
+        let mut chain = self.chains[node].lock().expect("lock to be acquired");
+        let tip = chain.last().expect("genesis to be in the chain").header();
+        let header = block.header();
+        if header.height != tip.height + 1 || header.prev_block_hash != tip.hash
+        {
+            return;
+        }
+
+        let mut accepted = self.accepted.lock().expect("lock to be acquired");
+        let first = *accepted.entry(header.height).or_insert(header.hash);
+        if first != header.hash {
+            self.forks.lock().expect("lock to be acquired").push(Fork {
+                height: header.height,
+                node,
+                accepted: first,
+                conflicting: header.hash,
+            });
+        }
+
+        chain.push(block);
+    }
+
+    /// Returns whether a peer reachable from `node` has a higher chain.
+    pub fn is_behind(&self, node: usize) -> bool {
+        let height = self.height(node);
+        (0..self.len())
+            .filter(|&peer| peer != node && self.connected(node, peer))
+            .any(|peer| self.height(peer) > height)
+    }
+
+    /// Catches up the chain of `node` with the highest chain among the
+    /// reachable peers, as the block synchronization of a node does.
+    pub fn sync(&self, node: usize) {
+        let peer = (0..self.len())
+            .filter(|&peer| peer != node && self.connected(node, peer))
+            .max_by_key(|&peer| self.height(peer));
+        let Some(peer) = peer else {
+            return;
+        };
+
+        let from = self.height(node) as usize + 1;
+        let blocks: Vec<_> = {
+            let chain = self.chains[peer].lock().expect("lock to be acquired");
+            chain.iter().skip(from).cloned().collect()
+        };
+        for block in blocks {
+            self.accept(node, block);
+        }
+    }
+
+    pub fn forks(&self) -> Vec<Fork> {
+        self.forks.lock().expect("lock to be acquired").clone()
+    }
+}
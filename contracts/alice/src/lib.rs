@@ -39,11 +39,21 @@ mod wasm {
         rusk_abi::wrap_call(arg_len, |arg| STATE.withdraw_obfuscated(arg))
     }
 
+    #[no_mangle]
+    unsafe fn withdraw_to_note(arg_len: u32) -> u32 {
+        rusk_abi::wrap_call(arg_len, |arg| STATE.withdraw_to_note(arg))
+    }
+
     #[no_mangle]
     unsafe fn withdraw_to_contract(arg_len: u32) -> u32 {
         rusk_abi::wrap_call(arg_len, |arg| STATE.withdraw_to_contract(arg))
     }
 
+    #[no_mangle]
+    unsafe fn subscribe(arg_len: u32) -> u32 {
+        rusk_abi::wrap_call(arg_len, |arg| STATE.subscribe(arg))
+    }
+
     const PAYMENT_INFO: PaymentInfo = PaymentInfo::Any(None);
 
     #[no_mangle]
@@ -6,6 +6,7 @@
 
 use phoenix_core::transaction::*;
 use rusk_abi::TRANSFER_CONTRACT;
+use transfer_contract_types::{Subscription, Wfctn};
 
 /// Alice contract.
 #[derive(Debug, Clone)]
@@ -26,8 +27,18 @@ impl Alice {
             .expect("Obfuscated withdrawal transaction should succeed");
     }
 
+    pub fn withdraw_to_note(&mut self, wfctn: Wfctn) {
+        let _: bool = rusk_abi::call(TRANSFER_CONTRACT, "wfctn", &wfctn)
+            .expect("Withdrawal to note transaction should succeed");
+    }
+
     pub fn withdraw_to_contract(&mut self, wfctc: Wfctc) {
         let _: bool = rusk_abi::call(TRANSFER_CONTRACT, "wfctc", &wfctc)
             .expect("Withdrawal tco contract transaction should succeed");
     }
+
+    pub fn subscribe(&mut self, subscription: Subscription) {
+        rusk_abi::call::<_, ()>(TRANSFER_CONTRACT, "subscribe", &subscription)
+            .expect("Subscribing should succeed");
+    }
 }
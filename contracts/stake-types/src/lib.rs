@@ -68,6 +68,43 @@ pub struct Withdraw {
     pub nonce: BlsScalar,
 }
 
+/// Enable or disable the automatic compounding of rewards into the stake.
+#[derive(Debug, Clone, Archive, Deserialize, Serialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct SetAutoCompound {
+    /// Public key to change the compounding setting for.
+    pub public_key: PublicKey,
+    /// Signature belonging to the given public key.
+    pub signature: Signature,
+    /// Whether rewards should be added to the stake when received.
+    pub enabled: bool,
+}
+
+/// Destination of the rewards received by a provisioner.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Deserialize, Serialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub enum RewardAddress {
+    /// Credit the rewards to the stake of another key, e.g. one kept offline.
+    Key(PublicKey),
+    /// Mint the rewards as a transparent note to a Phoenix address.
+    Phoenix(StealthAddress),
+}
+
+/// Set, or unset, the destination of the rewards of a key.
+#[derive(Debug, Clone, Archive, Deserialize, Serialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct SetRewardAddress {
+    /// Public key to change the reward address for.
+    pub public_key: PublicKey,
+    /// Signature belonging to the given public key.
+    pub signature: Signature,
+    /// Address the rewards are sent to, or `None` to credit them to the key
+    /// itself.
+    pub address: Option<RewardAddress>,
+}
+
 ///
 /// Events
 
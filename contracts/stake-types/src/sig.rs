@@ -9,12 +9,16 @@
 use alloc::vec::Vec;
 
 use dusk_bls12_381::BlsScalar;
+use dusk_bls12_381_sign::PublicKey;
 use dusk_bytes::Serializable;
 use dusk_pki::StealthAddress;
 
+use crate::RewardAddress;
+
 const STAKE_MESSAGE_SIZE: usize = u64::SIZE + u64::SIZE;
 const WITHDRAW_MESSAGE_SIZE: usize =
     u64::SIZE + StealthAddress::SIZE + BlsScalar::SIZE;
+const AUTO_COMPOUND_MESSAGE_SIZE: usize = u64::SIZE + 1;
 
 /// Return the digest to be signed in the `stake` function of the stake
 /// contract.
@@ -61,3 +65,43 @@ pub fn withdraw_signature_message(
 
     bytes
 }
+
+/// Signature message used for [`SetAutoCompound`].
+#[must_use]
+pub fn auto_compound_signature_message(
+    counter: u64,
+    enabled: bool,
+) -> [u8; AUTO_COMPOUND_MESSAGE_SIZE] {
+    let mut bytes = [0u8; AUTO_COMPOUND_MESSAGE_SIZE];
+
+    bytes[..u64::SIZE].copy_from_slice(&counter.to_bytes());
+    bytes[u64::SIZE] = u8::from(enabled);
+
+    bytes
+}
+
+/// Signature message used for [`SetRewardAddress`].
+#[must_use]
+pub fn reward_address_signature_message(
+    counter: u64,
+    address: Option<&RewardAddress>,
+) -> Vec<u8> {
+    let mut vec = Vec::with_capacity(
+        u64::SIZE + 1 + PublicKey::SIZE.max(StealthAddress::SIZE),
+    );
+
+    vec.extend_from_slice(&counter.to_bytes());
+    match address {
+        None => vec.push(0),
+        Some(RewardAddress::Key(pk)) => {
+            vec.push(1);
+            vec.extend_from_slice(&pk.to_bytes());
+        }
+        Some(RewardAddress::Phoenix(address)) => {
+            vec.push(2);
+            vec.extend_from_slice(&address.to_bytes());
+        }
+    }
+
+    vec
+}
@@ -5,6 +5,7 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use bytecheck::CheckBytes;
+use dusk_bls12_381::BlsScalar;
 use rkyv::{Archive, Deserialize, Serialize};
 
 /// Block height type alias
@@ -13,6 +14,9 @@ pub type BlockHeight = u64;
 /// Epoch used for stake operations
 pub const EPOCH: u64 = 2160;
 
+/// Depth of the tree committing to the stakes in the stake contract.
+pub const STAKE_TREE_DEPTH: usize = 17;
+
 /// Calculate the block height at which the next epoch takes effect.
 #[must_use]
 pub const fn next_epoch(block_height: BlockHeight) -> u64 {
@@ -114,6 +118,19 @@ impl StakeData {
         self.reward += value;
     }
 
+    /// Adds the given `value` to the [`amount`] staked, keeping its
+    /// eligibility.
+    ///
+    /// # Panics
+    /// If the stake has no amount.
+    pub fn increase_amount(&mut self, value: u64) {
+        let (amount, _) = self
+            .amount
+            .as_mut()
+            .expect("Can't increase non-existing amount!");
+        *amount += value;
+    }
+
     /// Removes the total [`amount`] staked.
     ///
     /// # Panics
@@ -144,6 +161,21 @@ impl StakeData {
             .unwrap_or_default()
     }
 
+    /// Returns the scalars the stake is committed to in the stake tree.
+    ///
+    /// The leaf of a stake is the poseidon hash of the hash of the compressed
+    /// public key owning it, followed by these scalars.
+    #[must_use]
+    pub fn hash_inputs(&self) -> [BlsScalar; 4] {
+        let (value, eligibility) = self.amount.unwrap_or_default();
+        [
+            BlsScalar::from(value),
+            BlsScalar::from(eligibility),
+            BlsScalar::from(self.reward),
+            BlsScalar::from(self.counter),
+        ]
+    }
+
     /// Compute the eligibility of a stake from the starting block height.
     #[must_use]
     pub const fn eligibility_from_height(block_height: BlockHeight) -> u64 {
@@ -13,6 +13,7 @@ extern crate alloc;
 use rusk_abi::dusk::*;
 
 mod state;
+mod tree;
 use state::StakeState;
 
 /// The minimum amount of Dusk one can stake.
@@ -52,6 +53,22 @@ unsafe fn withdraw(arg_len: u32) -> u32 {
     })
 }
 
+#[no_mangle]
+unsafe fn set_auto_compound(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        assert_transfer_caller();
+        STATE.set_auto_compound(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn set_reward_address(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        assert_transfer_caller();
+        STATE.set_reward_address(arg)
+    })
+}
+
 // Queries
 
 #[no_mangle]
@@ -59,11 +76,36 @@ unsafe fn get_stake(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |pk: PublicKey| STATE.get_stake(&pk).cloned())
 }
 
+#[no_mangle]
+unsafe fn auto_compound(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |pk: PublicKey| STATE.auto_compound(&pk))
+}
+
+#[no_mangle]
+unsafe fn reward_address(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |pk: PublicKey| STATE.reward_address(&pk))
+}
+
+#[no_mangle]
+unsafe fn faults(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |pk: PublicKey| STATE.faults(&pk))
+}
+
 #[no_mangle]
 unsafe fn slashed_amount(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |_: ()| STATE.slashed_amount())
 }
 
+#[no_mangle]
+unsafe fn stakes_root(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.stakes_root())
+}
+
+#[no_mangle]
+unsafe fn stake_opening(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |pk: PublicKey| STATE.stake_opening(&pk))
+}
+
 #[no_mangle]
 unsafe fn get_version(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |_: ()| STATE.get_version())
@@ -81,6 +123,11 @@ unsafe fn prev_state_changes(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |_: ()| STATE.prev_state_changes())
 }
 
+#[no_mangle]
+unsafe fn prev_faults_changes(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.prev_faults_changes())
+}
+
 // "Management" transactions
 
 #[no_mangle]
@@ -115,6 +162,22 @@ unsafe fn slash(arg_len: u32) -> u32 {
     })
 }
 
+#[no_mangle]
+unsafe fn register_fault(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |pk| {
+        assert_external_caller();
+        STATE.register_fault(&pk)
+    })
+}
+
+#[no_mangle]
+unsafe fn suspend(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |(pk, epochs)| {
+        assert_external_caller();
+        STATE.suspend(&pk, epochs);
+    })
+}
+
 #[no_mangle]
 unsafe fn hard_slash(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |(pk, value)| {
@@ -123,6 +186,14 @@ unsafe fn hard_slash(arg_len: u32) -> u32 {
     })
 }
 
+#[no_mangle]
+unsafe fn update_root(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| {
+        assert_external_caller();
+        STATE.update_root()
+    })
+}
+
 #[no_mangle]
 unsafe fn set_slashed_amount(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |slashed_amount| {
@@ -6,10 +6,12 @@
 
 use core::cmp::min;
 
+use crate::tree::{StakeOpening, StakeTree};
 use crate::*;
 
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, BTreeSet};
 
+use dusk_bls12_381::BlsScalar;
 use dusk_bls12_381_sign::PublicKey;
 use dusk_bytes::Serializable;
 
@@ -24,33 +26,57 @@ use transfer_contract_types::*;
 /// eligible to participate in the consensus.
 ///
 /// Rewards may be received by a public key regardless of whether they have a
-/// valid stake.
-#[derive(Debug, Default, Clone)]
+/// valid stake. Keys that opted into auto-compounding have their rewards added
+/// directly to their stake, if any. Keys that registered a reward address have
+/// their rewards sent there instead, allowing the staking key to be kept
+/// offline.
 pub struct StakeState {
     stakes: BTreeMap<[u8; PublicKey::SIZE], (StakeData, PublicKey)>,
+    /// Tree committing to the stakes as of the end of the last block
+    tree: StakeTree,
+    /// Keys whose stake may have changed since the tree was last updated
+    changed: BTreeSet<[u8; PublicKey::SIZE]>,
     slashed_amount: u64,
+    auto_compound: BTreeSet<[u8; PublicKey::SIZE]>,
+    reward_addresses: BTreeMap<[u8; PublicKey::SIZE], RewardAddress>,
+    faults: BTreeMap<[u8; PublicKey::SIZE], u32>,
     previous_block_state:
         BTreeMap<[u8; PublicKey::SIZE], (Option<StakeData>, PublicKey)>,
+    /// Fault counts of the keys whose count changed in the current block, as
+    /// they were before the block
+    previous_faults: BTreeMap<[u8; PublicKey::SIZE], (u32, PublicKey)>,
+    /// Number of reward notes minted in the current block, keeping their
+    /// nonces unique when a key is rewarded more than once
+    reward_mints: u64,
     // This is needed just to keep track of blocks to automatically clear the
     // prev_block_state. Future implementations will rely on
     // `before_state_transition` to handle that
     previous_block_height: u64,
 }
 
-const STAKE_CONTRACT_VERSION: u64 = 8;
+const STAKE_CONTRACT_VERSION: u64 = 10;
 
 impl StakeState {
     pub const fn new() -> Self {
         Self {
             stakes: BTreeMap::new(),
+            tree: StakeTree::new(),
+            changed: BTreeSet::new(),
             slashed_amount: 0u64,
+            auto_compound: BTreeSet::new(),
+            reward_addresses: BTreeMap::new(),
+            faults: BTreeMap::new(),
             previous_block_state: BTreeMap::new(),
+            previous_faults: BTreeMap::new(),
+            reward_mints: 0,
             previous_block_height: 0,
         }
     }
 
     pub fn before_state_transition(&mut self) {
-        self.previous_block_state.clear()
+        self.previous_block_state.clear();
+        self.previous_faults.clear();
+        self.reward_mints = 0;
     }
 
     fn clear_prev_if_needed(&mut self) {
@@ -159,6 +185,11 @@ impl StakeState {
         self.previous_block_state
             .entry(key)
             .or_insert((prev_value, unstake.public_key));
+
+        // A later stake has to opt into compounding again, and starts with
+        // a clean record
+        self.auto_compound.remove(&key);
+        self.set_faults(&unstake.public_key, 0);
     }
 
     pub fn withdraw(&mut self, withdraw: Withdraw) {
@@ -216,6 +247,83 @@ impl StakeState {
         );
     }
 
+    pub fn set_auto_compound(&mut self, set: SetAutoCompound) {
+        let loaded_stake = self
+            .get_stake_mut(&set.public_key)
+            .expect("A stake should exist in the map to set auto-compound!");
+
+        let counter = loaded_stake.counter();
+        loaded_stake.increment_counter();
+
+        // verify signature
+        let digest = auto_compound_signature_message(counter, set.enabled);
+
+        if !rusk_abi::verify_bls(digest.to_vec(), set.public_key, set.signature)
+        {
+            panic!("Invalid signature!");
+        }
+
+        let key = set.public_key.to_bytes();
+        if set.enabled {
+            self.auto_compound.insert(key);
+        } else {
+            self.auto_compound.remove(&key);
+        }
+
+        rusk_abi::emit(
+            "auto_compound",
+            StakingEvent {
+                public_key: set.public_key,
+                value: u64::from(set.enabled),
+            },
+        );
+    }
+
+    pub fn set_reward_address(&mut self, set: SetRewardAddress) {
+        let loaded_stake = self
+            .get_stake_mut(&set.public_key)
+            .expect("A stake should exist in the map to set a reward address!");
+
+        let counter = loaded_stake.counter();
+        loaded_stake.increment_counter();
+
+        // verify signature
+        let digest =
+            reward_address_signature_message(counter, set.address.as_ref());
+
+        if !rusk_abi::verify_bls(digest, set.public_key, set.signature) {
+            panic!("Invalid signature!");
+        }
+
+        let key = set.public_key.to_bytes();
+        match set.address {
+            Some(address) => {
+                self.reward_addresses.insert(key, address);
+            }
+            None => {
+                self.reward_addresses.remove(&key);
+            }
+        }
+
+        rusk_abi::emit(
+            "reward_address",
+            StakingEvent {
+                public_key: set.public_key,
+                value: u64::from(set.address.is_some()),
+            },
+        );
+    }
+
+    /// Returns the address the rewards of a key are sent to, if any.
+    pub fn reward_address(&self, key: &PublicKey) -> Option<RewardAddress> {
+        self.reward_addresses.get(&key.to_bytes()).copied()
+    }
+
+    /// Returns whether the rewards of a key are compounded into its stake.
+    pub fn auto_compound(&self, key: &PublicKey) -> bool {
+        self.auto_compound.contains(&key.to_bytes())
+    }
+
     /// Gets a reference to a stake.
     pub fn get_stake(&self, key: &PublicKey) -> Option<&StakeData> {
         self.stakes.get(&key.to_bytes()).map(|(s, _)| s)
@@ -223,11 +331,14 @@ impl StakeState {
 
     /// Gets a mutable reference to a stake.
     pub fn get_stake_mut(&mut self, key: &PublicKey) -> Option<&mut StakeData> {
-        self.stakes.get_mut(&key.to_bytes()).map(|(s, _)| s)
+        let key = key.to_bytes();
+        self.changed.insert(key);
+        self.stakes.get_mut(&key).map(|(s, _)| s)
     }
 
     /// Pushes the given `stake` onto the state for a given `public_key`.
     pub fn insert_stake(&mut self, public_key: PublicKey, stake: StakeData) {
+        self.changed.insert(public_key.to_bytes());
         self.stakes
             .insert(public_key.to_bytes(), (stake, public_key));
     }
@@ -239,6 +350,7 @@ impl StakeState {
         &mut self,
         pk: &PublicKey,
     ) -> &mut StakeData {
+        self.changed.insert(pk.to_bytes());
         let is_missing = self.stakes.get(&pk.to_bytes()).is_none();
 
         if is_missing {
@@ -250,20 +362,100 @@ impl StakeState {
         self.stakes.get_mut(&pk.to_bytes()).map(|(s, _)| s).unwrap()
     }
 
-    /// Rewards a `public_key` with the given `value`. If a stake does not exist
-    /// in the map for the key one will be created.
+    /// Rewards a `public_key` with the given `value`.
+    ///
+    /// If the key registered a reward address, the reward is sent there.
+    /// Otherwise it is credited to the key itself.
     pub fn reward(&mut self, public_key: &PublicKey, value: u64) {
         self.clear_prev_if_needed();
 
+        // Being rewarded means the key is participating again, so its record
+        // of missed generations is cleared
+        self.set_faults(public_key, 0);
+
+        match self.reward_address(public_key) {
+            None => self.credit(public_key, value),
+            // The reward address of the beneficiary is not followed, to
+            // prevent loops
+            Some(RewardAddress::Key(beneficiary)) => {
+                self.credit(&beneficiary, value)
+            }
+            Some(RewardAddress::Phoenix(address)) => {
+                let mut nonce_input =
+                    rusk_abi::block_height().to_bytes().to_vec();
+                nonce_input.extend(self.reward_mints.to_bytes());
+                nonce_input.extend(public_key.to_bytes());
+                self.reward_mints += 1;
+
+                let _: bool = rusk_abi::call(
+                    TRANSFER_CONTRACT,
+                    "mint",
+                    &Mint {
+                        address,
+                        value,
+                        nonce: rusk_abi::hash(nonce_input),
+                    },
+                )
+                .expect("Minting a reward note should succeed");
+
+                rusk_abi::emit(
+                    "reward",
+                    StakingEvent {
+                        public_key: *public_key,
+                        value,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Credits the given `value` to the reward of a `public_key`. If a stake
+    /// does not exist in the map for the key one will be created.
+    ///
+    /// If the key opted into auto-compounding and has an amount staked, the
+    /// value is added to the staked amount instead. The amount keeps its
+    /// eligibility, so a maturing stake still has to wait for its maturity.
+    fn credit(&mut self, public_key: &PublicKey, value: u64) {
+        let compound = self.auto_compound(public_key);
+
         let stake = self.load_or_create_stake_mut(public_key);
-        stake.increase_reward(value);
+
+        if !compound || stake.amount.is_none() {
+            stake.increase_reward(value);
+            rusk_abi::emit(
+                "reward",
+                StakingEvent {
+                    public_key: *public_key,
+                    value,
+                },
+            );
+            return;
+        }
+
+        let prev_value = Some(stake.clone());
+        stake.increase_amount(value);
+
+        // The compounded reward is now withdrawable from the contract as part
+        // of the stake, so the module balance must reflect it
+        let _: () = rusk_abi::call(
+            TRANSFER_CONTRACT,
+            "add_module_balance",
+            &(STAKE_CONTRACT, value),
+        )
+        .expect("Adding balance should succeed");
+
         rusk_abi::emit(
-            "reward",
+            "compound",
             StakingEvent {
                 public_key: *public_key,
                 value,
             },
         );
+
+        let key = public_key.to_bytes();
+        self.previous_block_state
+            .entry(key)
+            .or_insert((prev_value, *public_key));
     }
 
     /// Total amount slashed from the genesis
@@ -328,6 +520,92 @@ impl StakeState {
             .or_insert((prev_value, *public_key));
     }
 
+    /// Number of generations missed by a `public_key` since it was last
+    /// rewarded
+    pub fn faults(&self, public_key: &PublicKey) -> u32 {
+        self.faults
+            .get(&public_key.to_bytes())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Record a missed generation for a `public_key`, returning the number of
+    /// generations it missed since it was last rewarded.
+    ///
+    /// Keys without an amount staked have nothing to be penalized, so no
+    /// fault is recorded for them and zero is returned.
+    pub fn register_fault(&mut self, public_key: &PublicKey) -> u32 {
+        self.clear_prev_if_needed();
+
+        let staked = self
+            .get_stake(public_key)
+            .map_or(false, |stake| stake.amount.is_some());
+        if !staked {
+            return 0;
+        }
+
+        let faults = self.faults(public_key).saturating_add(1);
+        self.set_faults(public_key, faults);
+        faults
+    }
+
+    /// Sets the fault count of a `public_key`, recording the count it had
+    /// before the current block
+    fn set_faults(&mut self, public_key: &PublicKey, faults: u32) {
+        let key = public_key.to_bytes();
+        let prev = self.faults(public_key);
+        if prev == faults {
+            return;
+        }
+
+        self.previous_faults
+            .entry(key)
+            .or_insert((prev, *public_key));
+
+        match faults {
+            0 => self.faults.remove(&key),
+            faults => self.faults.insert(key, faults),
+        };
+    }
+
+    /// Suspend the eligibility of a `public_key` stake for the given number
+    /// of `epochs`, starting from the next epoch
+    pub fn suspend(&mut self, public_key: &PublicKey, epochs: u64) {
+        self.clear_prev_if_needed();
+
+        let stake = self
+            .get_stake_mut(public_key)
+            .expect("The stake to suspend should exist");
+
+        let prev_value = Some(stake.clone());
+
+        // stake.amount can be None if the provisioner unstake in the same
+        // block
+        let Some((_, eligibility)) = stake.amount.as_mut() else {
+            return;
+        };
+
+        let suspended_until =
+            next_epoch(rusk_abi::block_height()) + epochs * EPOCH;
+        if *eligibility >= suspended_until {
+            return;
+        }
+
+        *eligibility = suspended_until;
+        rusk_abi::emit(
+            "suspended",
+            StakingEvent {
+                public_key: *public_key,
+                value: suspended_until,
+            },
+        );
+
+        let key = public_key.to_bytes();
+        self.previous_block_state
+            .entry(key)
+            .or_insert((prev_value, *public_key));
+    }
+
     /// Slash the given `to_slash` amount from a `public_key` stake
     ///
     /// If the stake is less than the `to_slash` amount, then the stake is
@@ -394,10 +672,51 @@ impl StakeState {
         }
     }
 
+    /// Updates the tree committing to the stakes with the ones changed since
+    /// its last update, and emits its root as a `stakes_root` event.
+    ///
+    /// Called by the host at the end of every block, so that the tree of a
+    /// committed state is up to date and its root is part of the event hash
+    /// of the block that led to it.
+    pub fn update_root(&mut self) {
+        for key in core::mem::take(&mut self.changed) {
+            if let Some((stake_data, pk)) = self.stakes.get(&key) {
+                self.tree.update(pk, stake_data);
+            }
+        }
+
+        rusk_abi::emit("stakes_root", self.tree.root());
+    }
+
+    /// Returns the root of the tree committing to the stakes.
+    pub fn stakes_root(&self) -> BlsScalar {
+        self.tree.root()
+    }
+
+    /// Returns the stake of a key, if any, along with its opening in the tree
+    /// committing to the stakes.
+    pub fn stake_opening(
+        &self,
+        key: &PublicKey,
+    ) -> Option<(StakeData, StakeOpening)> {
+        let (stake_data, _) = self.stakes.get(&key.to_bytes())?;
+        let opening = self.tree.opening(key)?;
+
+        Some((stake_data.clone(), opening))
+    }
+
     /// Feeds the host with previous state of the changed provisioners.
     pub fn prev_state_changes(&self) {
         for (stake_data, pk) in self.previous_block_state.values() {
             rusk_abi::feed((*pk, stake_data.clone()));
         }
     }
+
+    /// Feeds the host with the fault counts the changed keys had before the
+    /// current block, so they can be restored along with their stakes.
+    pub fn prev_faults_changes(&self) {
+        for (faults, pk) in self.previous_faults.values() {
+            rusk_abi::feed((*pk, *faults));
+        }
+    }
 }
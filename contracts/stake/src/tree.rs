@@ -0,0 +1,64 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+
+use dusk_bls12_381::BlsScalar;
+use dusk_bls12_381_sign::PublicKey;
+use dusk_bytes::Serializable;
+use poseidon_merkle::{
+    Item as PoseidonItem, Opening as PoseidonOpening, Tree as PoseidonTree,
+};
+
+use stake_contract_types::{StakeData, STAKE_TREE_DEPTH};
+
+/// Arity of the tree committing to the stakes.
+const A: usize = 4;
+
+pub type StakeOpening = PoseidonOpening<(), STAKE_TREE_DEPTH, A>;
+
+/// Tree committing to the stakes.
+///
+/// Each key gets a leaf the first time its stake is committed to, at the next
+/// free position, and keeps it since stakes are never removed. The leaf of a
+/// stake is the poseidon hash of the hash of the compressed public key owning
+/// it, followed by the `hash_inputs` of the stake.
+pub struct StakeTree {
+    tree: PoseidonTree<(), STAKE_TREE_DEPTH, A>,
+    positions: BTreeMap<[u8; PublicKey::SIZE], u64>,
+}
+
+impl StakeTree {
+    pub const fn new() -> Self {
+        Self {
+            tree: PoseidonTree::new(),
+            positions: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the leaf of `pk` to commit to the given `stake`.
+    pub fn update(&mut self, pk: &PublicKey, stake: &StakeData) {
+        let next = self.positions.len() as u64;
+        let pos = *self.positions.entry(pk.to_bytes()).or_insert(next);
+
+        let mut inputs = vec![rusk_abi::hash(pk.to_bytes().to_vec())];
+        inputs.extend(stake.hash_inputs());
+        let hash = rusk_abi::poseidon_hash(inputs);
+
+        self.tree.insert(pos, PoseidonItem { hash, data: () });
+    }
+
+    pub fn root(&self) -> BlsScalar {
+        self.tree.root().hash
+    }
+
+    /// Returns the opening of the leaf of `pk`, if it has one.
+    pub fn opening(&self, pk: &PublicKey) -> Option<StakeOpening> {
+        let pos = self.positions.get(&pk.to_bytes())?;
+        self.tree.opening(*pos)
+    }
+}
@@ -109,7 +109,7 @@ pub fn execute(
         .call::<_, ()>(
             TRANSFER_CONTRACT,
             "refund",
-            &(tx.fee, receipt.gas_spent),
+            &(tx.fee, receipt.gas_spent, tx.fee.gas_price),
             u64::MAX,
         )
         .expect("Refunding must succeed");
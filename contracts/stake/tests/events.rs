@@ -7,16 +7,22 @@
 pub mod common;
 
 use dusk_bls12_381_sign::{PublicKey, SecretKey};
+use dusk_jubjub::JubJubScalar;
 use dusk_pki::{PublicSpendKey, SecretSpendKey};
+use ff::Field;
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 use rusk_abi::dusk::dusk;
 use rusk_abi::Error;
 use rusk_abi::{STAKE_CONTRACT, TRANSFER_CONTRACT};
-use stake_contract_types::StakeData;
+use stake_contract_types::{
+    auto_compound_signature_message, reward_address_signature_message,
+    RewardAddress, SetAutoCompound, SetRewardAddress, StakeData,
+};
 
 use crate::common::assert::assert_event;
 use crate::common::init::instantiate;
+use crate::common::utils::leaves_from_height;
 
 const GENESIS_VALUE: u64 = dusk(1_000_000.0);
 
@@ -105,3 +111,226 @@ fn stake_hard_slash() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn reward_auto_compound() -> Result<(), Error> {
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let vm = &mut rusk_abi::new_ephemeral_vm()
+        .expect("Creating ephemeral VM should work");
+
+    let ssk = SecretSpendKey::random(rng);
+    let psk = PublicSpendKey::from(&ssk);
+
+    let sk = SecretKey::random(rng);
+    let pk = PublicKey::from(&sk);
+
+    let mut session = instantiate(rng, vm, &psk, GENESIS_VALUE);
+
+    let balance = dusk(14.0);
+    let reward_amount = dusk(10.0);
+
+    let stake_data = StakeData {
+        reward: 0,
+        amount: Some((balance, 0)),
+        counter: 0,
+    };
+
+    session.call::<_, ()>(
+        TRANSFER_CONTRACT,
+        "add_module_balance",
+        &(STAKE_CONTRACT, balance),
+        u64::MAX,
+    )?;
+    session.call::<_, ()>(
+        STAKE_CONTRACT,
+        "insert_stake",
+        &(pk, stake_data),
+        u64::MAX,
+    )?;
+
+    let digest = auto_compound_signature_message(0, true);
+    let set = SetAutoCompound {
+        public_key: pk,
+        signature: sk.sign(&pk, &digest),
+        enabled: true,
+    };
+    let receipt = session.call::<_, ()>(
+        STAKE_CONTRACT,
+        "set_auto_compound",
+        &set,
+        u64::MAX,
+    )?;
+    assert_event(&receipt.events, "auto_compound", &pk, 1);
+
+    let receipt = session.call::<_, ()>(
+        STAKE_CONTRACT,
+        "reward",
+        &(pk, reward_amount),
+        u64::MAX,
+    )?;
+    assert_event(&receipt.events, "compound", &pk, reward_amount);
+
+    let stake_data: Option<StakeData> = session
+        .call(STAKE_CONTRACT, "get_stake", &pk, u64::MAX)?
+        .data;
+    let stake_data = stake_data.expect("The stake should exist");
+    assert_eq!(stake_data.reward, 0, "The reward should be compounded");
+    assert_eq!(stake_data.amount, Some((balance + reward_amount, 0)));
+
+    // A key that didn't opt in is rewarded as usual
+    let other = PublicKey::from(&SecretKey::random(rng));
+    let receipt = session.call::<_, ()>(
+        STAKE_CONTRACT,
+        "reward",
+        &(other, reward_amount),
+        u64::MAX,
+    )?;
+    assert_event(&receipt.events, "reward", &other, reward_amount);
+
+    Ok(())
+}
+
+#[test]
+fn faults_reset_on_reward() -> Result<(), Error> {
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let vm = &mut rusk_abi::new_ephemeral_vm()
+        .expect("Creating ephemeral VM should work");
+
+    let ssk = SecretSpendKey::random(rng);
+    let psk = PublicSpendKey::from(&ssk);
+
+    let sk = SecretKey::random(rng);
+    let pk = PublicKey::from(&sk);
+
+    let mut session = instantiate(rng, vm, &psk, GENESIS_VALUE);
+
+    // A fault of a key without stake is not registered
+    let faults: u32 = session
+        .call(STAKE_CONTRACT, "register_fault", &pk, u64::MAX)?
+        .data;
+    assert_eq!(faults, 0);
+
+    let balance = dusk(14.0);
+    let stake_data = StakeData {
+        reward: 0,
+        amount: Some((balance, 0)),
+        counter: 0,
+    };
+    session.call::<_, ()>(
+        TRANSFER_CONTRACT,
+        "add_module_balance",
+        &(STAKE_CONTRACT, balance),
+        u64::MAX,
+    )?;
+    session.call::<_, ()>(
+        STAKE_CONTRACT,
+        "insert_stake",
+        &(pk, stake_data),
+        u64::MAX,
+    )?;
+
+    for expected in 1..=2 {
+        let faults: u32 = session
+            .call(STAKE_CONTRACT, "register_fault", &pk, u64::MAX)?
+            .data;
+        assert_eq!(faults, expected);
+    }
+
+    session.call::<_, ()>(
+        STAKE_CONTRACT,
+        "reward",
+        &(pk, dusk(10.0)),
+        u64::MAX,
+    )?;
+    let faults: u32 =
+        session.call(STAKE_CONTRACT, "faults", &pk, u64::MAX)?.data;
+    assert_eq!(faults, 0, "The faults should be reset by the reward");
+
+    Ok(())
+}
+
+#[test]
+fn reward_address_mints_distinct_notes() -> Result<(), Error> {
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let vm = &mut rusk_abi::new_ephemeral_vm()
+        .expect("Creating ephemeral VM should work");
+
+    let ssk = SecretSpendKey::random(rng);
+    let psk = PublicSpendKey::from(&ssk);
+
+    let sk = SecretKey::random(rng);
+    let pk = PublicKey::from(&sk);
+
+    let mut session = instantiate(rng, vm, &psk, GENESIS_VALUE);
+
+    let balance = dusk(14.0);
+    let reward_amount = dusk(10.0);
+
+    let stake_data = StakeData {
+        reward: 0,
+        amount: Some((balance, 0)),
+        counter: 0,
+    };
+    session.call::<_, ()>(
+        TRANSFER_CONTRACT,
+        "add_module_balance",
+        &(STAKE_CONTRACT, balance),
+        u64::MAX,
+    )?;
+    session.call::<_, ()>(
+        STAKE_CONTRACT,
+        "insert_stake",
+        &(pk, stake_data),
+        u64::MAX,
+    )?;
+
+    let address = RewardAddress::Phoenix(
+        psk.gen_stealth_address(&JubJubScalar::random(rng)),
+    );
+    let digest = reward_address_signature_message(0, Some(&address));
+    let set = SetRewardAddress {
+        public_key: pk,
+        signature: sk.sign(&pk, &digest),
+        address: Some(address),
+    };
+    let receipt = session.call::<_, ()>(
+        STAKE_CONTRACT,
+        "set_reward_address",
+        &set,
+        u64::MAX,
+    )?;
+    assert_event(&receipt.events, "reward_address", &pk, 1);
+
+    // The same key is rewarded twice in the same block
+    for _ in 0..2 {
+        let receipt = session.call::<_, ()>(
+            STAKE_CONTRACT,
+            "reward",
+            &(pk, reward_amount),
+            u64::MAX,
+        )?;
+        assert_event(&receipt.events, "reward", &pk, reward_amount);
+    }
+
+    let notes: Vec<_> = leaves_from_height(&mut session, 1)?
+        .into_iter()
+        .map(|leaf| leaf.note)
+        .collect();
+    assert_eq!(notes.len(), 2, "Both rewards should be minted");
+    assert_ne!(
+        notes[0].hash(),
+        notes[1].hash(),
+        "The minted notes should be distinct"
+    );
+
+    let stake_data: Option<StakeData> = session
+        .call(STAKE_CONTRACT, "get_stake", &pk, u64::MAX)?
+        .data;
+    let stake_data = stake_data.expect("The stake should exist");
+    assert_eq!(stake_data.reward, 0, "The reward should be minted");
+
+    Ok(())
+}
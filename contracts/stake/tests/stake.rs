@@ -16,13 +16,15 @@ use dusk_jubjub::{JubJubScalar, GENERATOR_NUMS_EXTENDED};
 use dusk_pki::{Ownable, PublicSpendKey, SecretSpendKey};
 use ff::Field;
 use phoenix_core::{Fee, Note, Transaction};
+use poseidon_merkle::{Item as PoseidonItem, Opening as PoseidonOpening};
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 use rusk_abi::dusk::{dusk, LUX};
 use rusk_abi::STAKE_CONTRACT;
 use stake_contract_types::{
-    stake_signature_message, unstake_signature_message,
-    withdraw_signature_message, Stake, StakeData, Unstake, Withdraw,
+    auto_compound_signature_message, stake_signature_message,
+    unstake_signature_message, withdraw_signature_message, SetAutoCompound,
+    Stake, StakeData, Unstake, Withdraw, STAKE_TREE_DEPTH,
 };
 use transfer_circuits::{
     CircuitInput, CircuitInputSignature, ExecuteCircuitOneTwo,
@@ -33,6 +35,8 @@ use transfer_circuits::{
 const GENESIS_VALUE: u64 = dusk(1_000_000.0);
 const POINT_LIMIT: u64 = 0x100_000_000;
 
+type StakeOpening = PoseidonOpening<(), STAKE_TREE_DEPTH, 4>;
+
 #[test]
 fn stake_withdraw_unstake() {
     const STCT_FEE: u64 = dusk(1.0);
@@ -453,6 +457,32 @@ fn stake_withdraw_unstake() {
     assert_eq!(stake_data.reward, 0, "Reward should be set to zero");
     assert_eq!(stake_data.counter, 2, "Counter should increment once");
 
+    // Opt into compounding the rewards, which is cleared by the unstake
+
+    let compound_digest =
+        auto_compound_signature_message(stake_data.counter, true);
+    let compound = SetAutoCompound {
+        public_key: pk,
+        signature: sk.sign(&pk, &compound_digest),
+        enabled: true,
+    };
+    let receipt = session
+        .call::<_, ()>(
+            STAKE_CONTRACT,
+            "set_auto_compound",
+            &compound,
+            POINT_LIMIT,
+        )
+        .expect("Setting auto-compound should succeed");
+    assert_event(&receipt.events, "auto_compound", &pk, 1);
+
+    let stake_data: Option<StakeData> = session
+        .call(STAKE_CONTRACT, "get_stake", &pk, POINT_LIMIT)
+        .expect("Getting the stake should succeed")
+        .data;
+    let stake_data = stake_data.expect("The stake should exist");
+    assert_eq!(stake_data.counter, 3, "Counter should increment once");
+
     // Start unstaking the previously staked amount
 
     let leaves = leaves_from_height(&mut session, 2)
@@ -667,4 +697,86 @@ fn stake_withdraw_unstake() {
     update_root(&mut session).expect("Updating the root should succeed");
 
     println!("UNSTAKE : {gas_spent} gas");
+
+    let compound: bool = session
+        .call(STAKE_CONTRACT, "auto_compound", &pk, POINT_LIMIT)
+        .expect("Querying auto-compound should succeed")
+        .data;
+    assert!(!compound, "Unstaking should clear auto-compounding");
+}
+
+#[test]
+fn stake_opening() -> Result<(), rusk_abi::Error> {
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let vm = &mut rusk_abi::new_ephemeral_vm()
+        .expect("Creating ephemeral VM should work");
+
+    let ssk = SecretSpendKey::random(rng);
+    let psk = PublicSpendKey::from(&ssk);
+
+    let mut session = instantiate(rng, vm, &psk, GENESIS_VALUE);
+
+    let keys: Vec<_> = (0..3)
+        .map(|_| PublicKey::from(&SecretKey::random(rng)))
+        .collect();
+    for (i, pk) in keys.iter().enumerate() {
+        let stake_data = StakeData::new(dusk(1_000.0) * (i as u64 + 1), 0, 0);
+        session.call::<_, ()>(
+            STAKE_CONTRACT,
+            "insert_stake",
+            &(*pk, stake_data),
+            u64::MAX,
+        )?;
+    }
+
+    // The tree is only updated at the end of the block
+    let empty = session
+        .call::<_, BlsScalar>(STAKE_CONTRACT, "stakes_root", &(), u64::MAX)?
+        .data;
+    let receipt =
+        session.call::<_, ()>(STAKE_CONTRACT, "update_root", &(), u64::MAX)?;
+    let root = session
+        .call::<_, BlsScalar>(STAKE_CONTRACT, "stakes_root", &(), u64::MAX)?
+        .data;
+    assert_ne!(root, empty, "The root should commit to the new stakes");
+
+    let emitted: Vec<_> = receipt
+        .events
+        .iter()
+        .filter(|e| e.topic == "stakes_root")
+        .collect();
+    assert_eq!(emitted.len(), 1, "The root should be emitted");
+
+    for pk in &keys {
+        let (stake_data, opening) = session
+            .call::<_, Option<(StakeData, StakeOpening)>>(
+                STAKE_CONTRACT,
+                "stake_opening",
+                pk,
+                u64::MAX,
+            )?
+            .data
+            .expect("The stake should have an opening");
+
+        let mut inputs = vec![rusk_abi::hash(pk.to_bytes().to_vec())];
+        inputs.extend(stake_data.hash_inputs());
+        let hash = rusk_abi::poseidon_hash(inputs);
+
+        assert_eq!(opening.root().hash, root);
+        assert!(opening.verify(PoseidonItem { hash, data: () }));
+    }
+
+    let unknown = PublicKey::from(&SecretKey::random(rng));
+    let opening = session
+        .call::<_, Option<(StakeData, StakeOpening)>>(
+            STAKE_CONTRACT,
+            "stake_opening",
+            &unknown,
+            u64::MAX,
+        )?
+        .data;
+    assert!(opening.is_none(), "An unknown key should have no opening");
+
+    Ok(())
 }
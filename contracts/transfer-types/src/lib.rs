@@ -11,10 +11,12 @@
 #![deny(clippy::pedantic)]
 
 extern crate alloc;
+use alloc::string::String;
 use alloc::vec::Vec;
 
 use dusk_bls12_381::BlsScalar;
-use dusk_pki::StealthAddress;
+use dusk_pki::{PublicSpendKey, StealthAddress};
+use dusk_schnorr::Signature;
 
 use bytecheck::CheckBytes;
 use phoenix_core::{Message, Note};
@@ -23,6 +25,32 @@ use rkyv::{Archive, Deserialize, Serialize};
 /// Module Id
 pub type ModuleId = [u8; 32];
 
+/// Name of the transfer contract function authorizing a [`Migration`].
+pub const MIGRATE_FN: &str = "migrate";
+
+/// Name of the transfer contract function executing a [`Multicall`].
+pub const MULTICALL_FN: &str = "multicall";
+
+/// Name of the function a migrated contract's new bytecode must export. It is
+/// called with the [`ModuleId`] of the replaced contract, and is responsible
+/// for copying its state over.
+pub const MIGRATE_STATE_FN: &str = "migrate_state";
+
+/// Name of the function a contract subscribed to events must export. It is
+/// called at the end of each block with the `Vec` of [`HookEvent`]s matching
+/// its [`Subscription`]s, in the order they were emitted.
+pub const HOOK_FN: &str = "on_events";
+
+/// Amount in LUX charged to a contract for each of its [`Subscription`]s,
+/// deducted from its balance.
+pub const SUBSCRIPTION_FEE: u64 = 1_000_000_000;
+
+/// Maximum number of [`Subscription`]s, of all contracts together.
+pub const MAX_SUBSCRIPTIONS: usize = 64;
+
+/// Maximum length in bytes of the topic of a [`Subscription`].
+pub const MAX_SUBSCRIPTION_TOPIC_LEN: usize = 64;
+
 /// A leaf of the transfer tree.
 #[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
 #[archive_attr(derive(CheckBytes))]
@@ -84,6 +112,20 @@ pub struct WfctRaw {
     pub proof: Vec<u8>,
 }
 
+/// Withdraw value from the balance of the calling contract to a transparent
+/// note.
+///
+/// Since the value of a transparent note is public, it is checked by the
+/// transfer contract itself and no proof is required.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Deserialize, Serialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Wfctn {
+    /// The value to withdraw
+    pub value: u64,
+    /// The transparent note to withdraw to
+    pub note: Note,
+}
+
 /// Withdraw value from a contract anonymously.
 #[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
 #[archive_attr(derive(bytecheck::CheckBytes))]
@@ -142,3 +184,111 @@ pub struct Mint {
     /// A nonce to prevent replay.
     pub nonce: BlsScalar,
 }
+
+/// Replace the bytecode of a contract, keeping its state.
+///
+/// The migration is authorized by the transfer contract, and the bytecode is
+/// then replaced by the host.
+#[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Migration {
+    /// The contract to migrate.
+    pub contract: ModuleId,
+    /// The owner of the contract, as registered when it was deployed.
+    pub owner: PublicSpendKey,
+    /// The new bytecode of the contract.
+    pub bytecode: Vec<u8>,
+    /// The version the contract is migrated to. Must be the current version
+    /// of the contract plus one, preventing replays.
+    pub version: u64,
+    /// Signature of the [`migration_signature_message`] with the `A` key of
+    /// the owner.
+    pub signature: Signature,
+}
+
+/// Event emitted after a contract migration is authorized.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct MigrationEvent {
+    /// The migrated contract.
+    pub contract: ModuleId,
+    /// The version the contract was migrated to.
+    pub version: u64,
+}
+
+/// A call to a contract function, made as part of a [`Multicall`].
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ContractCall {
+    /// The called contract.
+    pub contract: ModuleId,
+    /// The name of the called function.
+    pub fn_name: String,
+    /// The serialized argument of the function.
+    pub fn_args: Vec<u8>,
+}
+
+/// Calls executed in order as the call of a single transaction.
+///
+/// The calls are atomic: if any of them fails, the state changes of the ones
+/// before it are reverted, and the transaction call fails as a whole. They
+/// share the gas limit of the transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Multicall {
+    /// The calls, in the order they are executed.
+    pub calls: Vec<ContractCall>,
+}
+
+/// Interest of a contract in the events emitted by another one.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Archive,
+    Serialize,
+    Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Subscription {
+    /// The contract emitting the events.
+    pub source: ModuleId,
+    /// The topic of the events.
+    pub topic: String,
+}
+
+/// An event passed to the [`HOOK_FN`] of a subscribed contract.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct HookEvent {
+    /// The contract that emitted the event.
+    pub source: ModuleId,
+    /// The topic of the event.
+    pub topic: String,
+    /// The data of the event.
+    pub data: Vec<u8>,
+}
+
+/// Signature message used for [`Migration`].
+///
+/// The message is bound to the id of the chain the migration is made on, so
+/// that it can't be replayed on another network.
+#[must_use]
+pub fn migration_signature_message(
+    chain_id: u8,
+    contract: &ModuleId,
+    version: u64,
+    bytecode: &[u8],
+) -> Vec<u8> {
+    let mut vec = Vec::with_capacity(1 + contract.len() + 8 + bytecode.len());
+
+    vec.push(chain_id);
+    vec.extend_from_slice(contract);
+    vec.extend_from_slice(&version.to_le_bytes());
+    vec.extend_from_slice(bytecode);
+
+    vec
+}
@@ -49,6 +49,13 @@ unsafe fn wfct_raw(arg_len: u32) -> u32 {
     })
 }
 
+#[no_mangle]
+unsafe fn wfctn(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.withdraw_from_contract_to_note(arg)
+    })
+}
+
 #[no_mangle]
 unsafe fn stco(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |arg| STATE.send_to_contract_obfuscated(arg))
@@ -75,6 +82,26 @@ unsafe fn wfctc(arg_len: u32) -> u32 {
     })
 }
 
+#[no_mangle]
+unsafe fn migrate(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.migrate(arg))
+}
+
+#[no_mangle]
+unsafe fn multicall(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.multicall(arg))
+}
+
+#[no_mangle]
+unsafe fn subscribe(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.subscribe(arg))
+}
+
+#[no_mangle]
+unsafe fn unsubscribe(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.unsubscribe(arg))
+}
+
 // Queries
 
 #[no_mangle]
@@ -87,6 +114,11 @@ unsafe fn module_balance(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |module| STATE.balance(&module))
 }
 
+#[no_mangle]
+unsafe fn contract_version(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |contract| STATE.version(&contract))
+}
+
 #[no_mangle]
 unsafe fn message(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |(module, pk)| STATE.message(&module, &pk))
@@ -99,8 +131,8 @@ unsafe fn opening(arg_len: u32) -> u32 {
 
 #[no_mangle]
 unsafe fn existing_nullifiers(arg_len: u32) -> u32 {
-    rusk_abi::wrap_call(arg_len, |nullifiers| {
-        STATE.existing_nullifiers(nullifiers)
+    rusk_abi::wrap_call(arg_len, |nullifiers: Vec<_>| {
+        STATE.existing_nullifiers(&nullifiers)
     })
 }
 
@@ -109,6 +141,11 @@ unsafe fn num_notes(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |_: ()| STATE.num_notes())
 }
 
+#[no_mangle]
+unsafe fn subscriptions(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.subscriptions())
+}
+
 // "Feeder" queries
 
 #[no_mangle]
@@ -133,9 +170,9 @@ unsafe fn spend_and_execute(arg_len: u32) -> u32 {
 
 #[no_mangle]
 unsafe fn refund(arg_len: u32) -> u32 {
-    rusk_abi::wrap_call(arg_len, |(fee, gas_spent)| {
+    rusk_abi::wrap_call(arg_len, |(fee, gas_spent, gas_price)| {
         assert_external_caller();
-        STATE.refund(fee, gas_spent)
+        STATE.refund(fee, gas_spent, gas_price)
     })
 }
 
@@ -157,8 +194,16 @@ unsafe fn update_root(arg_len: u32) -> u32 {
 
 #[no_mangle]
 unsafe fn add_module_balance(arg_len: u32) -> u32 {
-    rusk_abi::wrap_call(arg_len, |(module, value)| {
-        assert_external_caller();
+    rusk_abi::wrap_call(arg_len, |(module, value): (ContractId, u64)| {
+        // The stake contract can only increase its own balance, which happens
+        // when rewards are compounded into a stake
+        if rusk_abi::caller() == STAKE_CONTRACT {
+            if module != STAKE_CONTRACT {
+                panic!("The stake contract can only add to its own balance!")
+            }
+        } else {
+            assert_external_caller();
+        }
         STATE.add_balance(module, value)
     })
 }
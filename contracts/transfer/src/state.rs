@@ -14,15 +14,22 @@ use alloc::vec::Vec;
 
 use dusk_bls12_381::BlsScalar;
 use dusk_bytes::{DeserializableSlice, Serializable};
-use dusk_jubjub::{JubJubAffine, JubJubExtended};
-use dusk_pki::{Ownable, PublicKey, StealthAddress};
+use dusk_jubjub::{
+    JubJubAffine, JubJubExtended, JubJubScalar, GENERATOR_EXTENDED,
+};
+use dusk_pki::{Ownable, PublicKey, PublicSpendKey, StealthAddress};
 use phoenix_core::transaction::*;
-use phoenix_core::{Crossover, Fee, Message, Note};
+use phoenix_core::{Crossover, Fee, Message, Note, NoteType};
 use poseidon_merkle::Opening as PoseidonOpening;
 use rusk_abi::{
     ContractError, ContractId, PaymentInfo, PublicInput, STAKE_CONTRACT,
 };
-use transfer_contract_types::{Mint, Stct, Wfco, WfcoRaw, Wfct, Wfctc};
+use transfer_contract_types::{
+    migration_signature_message, Migration, MigrationEvent, Mint, ModuleId,
+    Multicall, Stct, Subscription, Wfco, WfcoRaw, Wfct, Wfctc, Wfctn,
+    MAX_SUBSCRIPTIONS, MAX_SUBSCRIPTION_TOPIC_LEN, MIGRATE_FN, MULTICALL_FN,
+    SUBSCRIPTION_FEE,
+};
 
 /// Arity of the transfer tree.
 pub const A: usize = 4;
@@ -37,6 +44,8 @@ pub struct TransferState {
     message_mapping_set: BTreeMap<ContractId, StealthAddress>,
     var_crossover: Option<Crossover>,
     var_crossover_addr: Option<StealthAddress>,
+    versions: BTreeMap<ContractId, u64>,
+    subscriptions: BTreeSet<(ContractId, Subscription)>,
 }
 
 impl TransferState {
@@ -50,6 +59,8 @@ impl TransferState {
             message_mapping_set: BTreeMap::new(),
             var_crossover: None,
             var_crossover_addr: None,
+            versions: BTreeMap::new(),
+            subscriptions: BTreeSet::new(),
         }
     }
 
@@ -152,6 +163,42 @@ impl TransferState {
         })
     }
 
+    pub fn withdraw_from_contract_to_note(&mut self, wfctn: Wfctn) -> bool {
+        let address = rusk_abi::caller();
+        if address.is_uninitialized() {
+            panic!("Can only be called by a contract!")
+        }
+
+        //  1. The note's value is public and matches the withdrawn value,
+        //     both in clear and committed to, since the commitment is what
+        //     the note is spent against
+        if wfctn.note.note_type() != NoteType::Transparent {
+            panic!("Can only withdraw to a transparent note");
+        }
+        let value = wfctn
+            .note
+            .value(None)
+            .expect("The value of a transparent note should be public");
+        if value != wfctn.value {
+            panic!("The note value doesn't match the withdrawn value");
+        }
+        let commitment = GENERATOR_EXTENDED * JubJubScalar::from(value);
+        if *wfctn.note.value_commitment() != commitment {
+            panic!(
+                "The note value commitment doesn't match the withdrawn value"
+            );
+        }
+
+        //  2. B_a↦ ← B_a↦ − v
+        self.sub_balance(&address, wfctn.value)
+            .expect("Failed to subtract the balance from the provided address");
+
+        //  3. N.append(N_p^t)
+        self.push_note_current_height(wfctn.note);
+
+        true
+    }
+
     pub fn send_to_contract_obfuscated(&mut self, stco: Stco) -> bool {
         let (crossover, stealth_addr) = self
             .take_crossover()
@@ -370,15 +417,168 @@ impl TransferState {
         result
     }
 
+    /// Execute the calls of a multicall in order, returning their results.
+    ///
+    /// The multicall is only allowed as the call of a transaction, so that
+    /// the calls are made on behalf of the transaction, sharing its gas
+    /// limit. Failing on any call reverts the ones made before it.
+    ///
+    /// # Panics
+    /// When not called by a transaction, when one of the calls is a migration
+    /// or a multicall, or when one of the calls fails.
+    pub fn multicall(&mut self, multicall: Multicall) -> Vec<Vec<u8>> {
+        if rusk_abi::caller() != rusk_abi::self_id() {
+            panic!("Multicalls can only be performed by a transaction");
+        }
+
+        let mut results = Vec::with_capacity(multicall.calls.len());
+
+        for (i, call) in multicall.calls.into_iter().enumerate() {
+            let contract = ContractId::from_bytes(call.contract);
+
+            // The host only carries out migrations made as the call of a
+            // transaction, and multicalls are not nested
+            if contract == rusk_abi::self_id()
+                && (call.fn_name == MIGRATE_FN || call.fn_name == MULTICALL_FN)
+            {
+                panic!("Call {i} of the multicall is not allowed");
+            }
+
+            match rusk_abi::call_raw(contract, &call.fn_name, &call.fn_args) {
+                Ok(result) => results.push(result),
+                Err(err) => panic!("Call {i} of the multicall failed: {err:?}"),
+            }
+        }
+
+        results
+    }
+
+    /// Authorize the migration of a contract to new bytecode, bumping its
+    /// version.
+    ///
+    /// The migration must be signed by the registered owner of the contract,
+    /// and is only allowed as the call of a transaction. The bytecode is then
+    /// replaced by the host.
+    ///
+    /// # Panics
+    /// When any of the checks fail.
+    pub fn migrate(&mut self, migration: Migration) {
+        if rusk_abi::caller() != rusk_abi::self_id() {
+            panic!("Migrations can only be performed by a transaction");
+        }
+
+        let contract = ContractId::from_bytes(migration.contract);
+        let owner = rusk_abi::owner::<{ PublicSpendKey::SIZE }>(contract)
+            .expect("The migrated contract should exist");
+        if owner != migration.owner.to_bytes() {
+            panic!("Migration not submitted by the contract owner");
+        }
+
+        let version = self.version(&contract) + 1;
+        if migration.version != version {
+            panic!("Invalid migration version");
+        }
+
+        let msg = migration_signature_message(
+            rusk_abi::chain_id(),
+            &migration.contract,
+            migration.version,
+            &migration.bytecode,
+        );
+        let msg = rusk_abi::hash(msg);
+        let pk = PublicKey::from(migration.owner.A());
+        if !rusk_abi::verify_schnorr(msg, pk, migration.signature) {
+            panic!("Invalid migration signature");
+        }
+
+        self.versions.insert(contract, version);
+
+        rusk_abi::emit(
+            "migration",
+            MigrationEvent {
+                contract: migration.contract,
+                version,
+            },
+        );
+    }
+
+    /// Return the number of migrations a contract went through.
+    pub fn version(&self, contract_id: &ContractId) -> u64 {
+        self.versions.get(contract_id).copied().unwrap_or_default()
+    }
+
+    /// Subscribe the calling contract to the events matching `subscription`,
+    /// charging it the `SUBSCRIPTION_FEE`.
+    ///
+    /// At the end of each block, the host calls the `HOOK_FN` of subscribed
+    /// contracts with the matching events emitted by the block transactions.
+    ///
+    /// # Panics
+    /// When not called by a contract, when the topic is longer than
+    /// `MAX_SUBSCRIPTION_TOPIC_LEN`, when there are already
+    /// `MAX_SUBSCRIPTIONS`, or when the contract cannot pay the fee.
+    pub fn subscribe(&mut self, subscription: Subscription) {
+        let caller = rusk_abi::caller();
+        if caller.is_uninitialized() {
+            panic!("Only contracts can subscribe to events");
+        }
+        if subscription.topic.len() > MAX_SUBSCRIPTION_TOPIC_LEN {
+            panic!("The subscription topic is too long");
+        }
+
+        let subscription = (caller, subscription);
+        if self.subscriptions.contains(&subscription) {
+            return;
+        }
+        if self.subscriptions.len() >= MAX_SUBSCRIPTIONS {
+            panic!("Too many subscriptions");
+        }
+
+        self.sub_balance(&caller, SUBSCRIPTION_FEE)
+            .expect("Subscribing contract should pay the subscription fee");
+        self.subscriptions.insert(subscription);
+    }
+
+    /// Unsubscribe the calling contract from the events matching
+    /// `subscription`.
+    pub fn unsubscribe(&mut self, subscription: Subscription) {
+        let caller = rusk_abi::caller();
+        self.subscriptions.remove(&(caller, subscription));
+    }
+
+    /// Return all the subscriptions, ordered by subscriber.
+    pub fn subscriptions(&self) -> Vec<(ModuleId, Subscription)> {
+        self.subscriptions
+            .iter()
+            .map(|(subscriber, s)| (subscriber.to_bytes(), s.clone()))
+            .collect()
+    }
+
     /// Refund the previously performed transaction, taking into account the
-    /// given gas spent. The notes produced will be refunded to the address
-    /// present in the fee structure.
+    /// given gas spent and the price it is charged. The notes produced will be
+    /// refunded to the address present in the fee structure.
+    ///
+    /// The fee deposited is the gas limit at the gas price of the fee, its
+    /// cap. The gas spent is charged at the given price, bounded by the cap,
+    /// and the rest of the deposit is refunded.
     ///
     /// This function guarantees that it will not panic.
-    pub fn refund(&mut self, fee: Fee, gas_spent: u64) {
+    pub fn refund(&mut self, fee: Fee, gas_spent: u64, gas_price: u64) {
         let block_height = rusk_abi::block_height();
 
-        let remainder = fee.gen_remainder(gas_spent);
+        let deposit = fee.gas_limit.saturating_mul(fee.gas_price);
+        let charged = gas_spent
+            .min(fee.gas_limit)
+            .saturating_mul(gas_price.min(fee.gas_price));
+
+        // The remainder of a fee is its gas limit at its gas price, minus the
+        // gas consumed. The refund is then the remainder of a fee with a
+        // price of one, and the refund as gas limit.
+        let mut refund = fee;
+        refund.gas_limit = deposit - charged;
+        refund.gas_price = 1;
+
+        let remainder = refund.gen_remainder(0);
         let remainder = Note::from(remainder);
 
         let remainder_value = remainder
@@ -451,11 +651,12 @@ impl TransferState {
     /// already exists in the contract
     pub fn existing_nullifiers(
         &self,
-        nullifiers: Vec<BlsScalar>,
+        nullifiers: &[BlsScalar],
     ) -> Vec<BlsScalar> {
         nullifiers
-            .into_iter()
-            .filter_map(|n| self.nullifiers.get(&n).map(|_| n))
+            .iter()
+            .filter(|n| self.nullifiers.contains(*n))
+            .copied()
             .collect()
     }
 
@@ -628,6 +829,9 @@ fn verify_tx_proof(tx: &Transaction) -> bool {
             .map(|_| ZERO_COMMITMENT.into()),
     );
 
+    // The proof is bound to the network the transaction is meant for
+    pis.push(BlsScalar::from(rusk_abi::chain_id() as u64).into());
+
     let vd = verifier_data_execute(n_nullifiers)
         .expect("No circuit available for given number of inputs!")
         .to_vec();
@@ -651,8 +855,8 @@ mod test_transfer {
             BlsScalar::from(11),
         );
 
-        let existing = transfer
-            .existing_nullifiers(vec![zero, one, two, three, ten, eleven]);
+        let existing =
+            transfer.existing_nullifiers(&[zero, one, two, three, ten, eleven]);
 
         assert_eq!(existing.len(), 0);
 
@@ -660,8 +864,8 @@ mod test_transfer {
             transfer.nullifiers.insert(BlsScalar::from(i));
         }
 
-        let existing = transfer
-            .existing_nullifiers(vec![zero, one, two, three, ten, eleven]);
+        let existing =
+            transfer.existing_nullifiers(&[zero, one, two, three, ten, eleven]);
 
         assert_eq!(existing.len(), 3);
 
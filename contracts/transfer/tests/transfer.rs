@@ -9,8 +9,11 @@ use std::sync::mpsc;
 use dusk_bls12_381::BlsScalar;
 use dusk_bytes::Serializable;
 use dusk_jubjub::{JubJubScalar, GENERATOR_NUMS_EXTENDED};
-use dusk_pki::{Ownable, PublicKey, PublicSpendKey, SecretSpendKey, ViewKey};
+use dusk_pki::{
+    Ownable, PublicKey, PublicSpendKey, SecretKey, SecretSpendKey, ViewKey,
+};
 use dusk_plonk::prelude::*;
+use dusk_schnorr::Signature;
 use phoenix_core::transaction::*;
 use phoenix_core::{Fee, Message, Note};
 use poseidon_merkle::Opening as PoseidonOpening;
@@ -28,6 +31,11 @@ use transfer_circuits::{
     WfoCommitment, WithdrawFromObfuscatedCircuit,
     WithdrawFromTransparentCircuit,
 };
+use transfer_contract_types::{
+    migration_signature_message, ContractCall, Migration, Multicall,
+    Subscription, Wfctn, MAX_SUBSCRIPTIONS, MAX_SUBSCRIPTION_TOPIC_LEN,
+    MIGRATE_FN, MULTICALL_FN, SUBSCRIPTION_FEE,
+};
 
 const GENESIS_VALUE: u64 = dusk(1_000.0);
 const POINT_LIMIT: u64 = 0x10000000;
@@ -232,7 +240,7 @@ fn execute(session: &mut Session, tx: Transaction) -> Result<u64> {
         .call::<_, ()>(
             TRANSFER_CONTRACT,
             "refund",
-            &(tx.fee, gas_spent),
+            &(tx.fee, gas_spent, tx.fee.gas_price),
             u64::MAX,
         )
         .expect("Refunding must succeed");
@@ -513,6 +521,219 @@ fn alice_ping() {
     );
 }
 
+#[test]
+fn alice_multicall() {
+    const PING_FEE: u64 = dusk(1.0);
+
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let vm = &mut rusk_abi::new_ephemeral_vm()
+        .expect("Creating ephemeral VM should work");
+
+    let ssk = SecretSpendKey::random(rng);
+    let psk = PublicSpendKey::from(&ssk);
+
+    let session = &mut instantiate(rng, vm, &psk);
+
+    let leaves = leaves_from_height(session, 0)
+        .expect("Getting leaves in the given range should succeed");
+
+    assert_eq!(leaves.len(), 1, "There should be one note in the state");
+
+    let input_note = leaves[0].note;
+    let input_value = input_note
+        .value(None)
+        .expect("The value should be transparent");
+    let input_blinder = input_note
+        .blinding_factor(None)
+        .expect("The blinder should be transparent");
+    let input_nullifier = input_note.gen_nullifier(&ssk);
+
+    let gas_limit = PING_FEE;
+    let gas_price = LUX;
+
+    let fee = Fee::new(rng, gas_limit, gas_price, &psk);
+
+    // The change note should have the value of the input note, minus what is
+    // maximally spent.
+    let change_value = input_value - gas_price * gas_limit;
+    let change_blinder = JubJubScalar::random(rng);
+    let change_note = Note::obfuscated(rng, &psk, change_value, change_blinder);
+
+    let ping = ContractCall {
+        contract: ALICE_ID.to_bytes(),
+        fn_name: String::from("ping"),
+        fn_args: vec![],
+    };
+    let multicall = Multicall {
+        calls: vec![ping.clone(), ping],
+    };
+    let multicall = rkyv::to_bytes::<_, 256>(&multicall)
+        .expect("Serializing the multicall should succeed")
+        .to_vec();
+    let call = Some((
+        TRANSFER_CONTRACT.to_bytes(),
+        String::from(MULTICALL_FN),
+        multicall,
+    ));
+
+    // Compose the circuit. In this case we're using one input and one output.
+    let mut circuit = ExecuteCircuitOneTwo::new();
+
+    circuit.set_fee(&fee);
+    circuit
+        .add_output_with_data(change_note, change_value, change_blinder)
+        .expect("appending input or output should succeed");
+
+    let opening = opening(session, *input_note.pos())
+        .expect("Querying the opening for the given position should succeed")
+        .expect("An opening should exist for a note in the tree");
+
+    // Generate pk_r_p
+    let sk_r = ssk.sk_r(input_note.stealth_address());
+    let pk_r_p = GENERATOR_NUMS_EXTENDED * sk_r.as_ref();
+
+    // The transaction hash must be computed before signing
+    let anchor =
+        root(session).expect("Getting the anchor should be successful");
+
+    let tx_hash_input_bytes = Transaction::hash_input_bytes_from_components(
+        &[input_nullifier],
+        &[change_note],
+        &anchor,
+        &fee,
+        &None,
+        &call,
+    );
+    let tx_hash = rusk_abi::hash(tx_hash_input_bytes);
+
+    circuit.set_tx_hash(tx_hash);
+
+    let circuit_input_signature =
+        CircuitInputSignature::sign(rng, &ssk, &input_note, tx_hash);
+    let circuit_input = CircuitInput::new(
+        opening,
+        input_note,
+        pk_r_p.into(),
+        input_value,
+        input_blinder,
+        input_nullifier,
+        circuit_input_signature,
+    );
+
+    circuit
+        .add_input(circuit_input)
+        .expect("appending input or output should succeed");
+
+    let (prover, _) = prover_verifier("ExecuteCircuitOneTwo");
+    let (proof, _) = prover
+        .prove(rng, &circuit)
+        .expect("creating a proof should succeed");
+
+    let tx = Transaction {
+        anchor,
+        nullifiers: vec![input_nullifier],
+        outputs: vec![change_note],
+        fee,
+        crossover: None,
+        proof: proof.to_bytes().to_vec(),
+        call,
+    };
+
+    let receipt = session
+        .call::<_, Result<Vec<u8>, ContractError>>(
+            TRANSFER_CONTRACT,
+            "spend_and_execute",
+            &tx,
+            u64::MAX,
+        )
+        .expect("Executing TX should succeed");
+
+    let results = receipt.data.expect("The multicall should succeed");
+    let results: Vec<Vec<u8>> = rkyv::from_bytes(&results)
+        .expect("Deserializing the multicall results should succeed");
+    assert_eq!(results.len(), 2, "Both calls should be executed");
+
+    println!("EXECUTE_MULTICALL: {} gas", receipt.gas_spent);
+}
+
+#[test]
+fn multicall_failure_reverts_earlier_calls() {
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let vm = &mut rusk_abi::new_ephemeral_vm()
+        .expect("Creating ephemeral VM should work");
+
+    let ssk = SecretSpendKey::random(rng);
+    let psk = PublicSpendKey::from(&ssk);
+
+    let session = &mut instantiate(rng, vm, &psk);
+
+    session
+        .call::<_, ()>(
+            TRANSFER_CONTRACT,
+            "add_module_balance",
+            &(ALICE_ID, SUBSCRIPTION_FEE),
+            POINT_LIMIT,
+        )
+        .expect("Adding balance to alice should succeed");
+
+    // The first subscription is paid for, the second one fails for its topic
+    // being too long
+    let subscribe = |topic: String| ContractCall {
+        contract: ALICE_ID.to_bytes(),
+        fn_name: String::from("subscribe"),
+        fn_args: rkyv::to_bytes::<_, 256>(&Subscription {
+            source: BOB_ID.to_bytes(),
+            topic,
+        })
+        .expect("Serializing the subscription should succeed")
+        .to_vec(),
+    };
+    let multicall = Multicall {
+        calls: vec![
+            subscribe(String::from("t")),
+            subscribe("t".repeat(MAX_SUBSCRIPTION_TOPIC_LEN + 1)),
+        ],
+    };
+    let multicall = rkyv::to_bytes::<_, 256>(&multicall)
+        .expect("Serializing the multicall should succeed")
+        .to_vec();
+
+    let tx = transfer_call_tx(rng, session, &ssk, MULTICALL_FN, multicall);
+    let receipt = session
+        .call::<_, Result<Vec<u8>, ContractError>>(
+            TRANSFER_CONTRACT,
+            "spend_and_execute",
+            &tx,
+            u64::MAX,
+        )
+        .expect("Executing TX should succeed");
+
+    receipt.data.expect_err("The multicall should fail");
+
+    let subscriptions = session
+        .call::<_, Vec<([u8; 32], Subscription)>>(
+            TRANSFER_CONTRACT,
+            "subscriptions",
+            &(),
+            POINT_LIMIT,
+        )
+        .expect("Querying the subscriptions should succeed")
+        .data;
+    assert!(
+        subscriptions.is_empty(),
+        "The subscription should be reverted"
+    );
+
+    let alice_balance = module_balance(session, ALICE_ID)
+        .expect("Querying the module balance should succeed");
+    assert_eq!(
+        alice_balance, SUBSCRIPTION_FEE,
+        "The fee should be reverted"
+    );
+}
+
 #[test]
 fn send_and_withdraw_transparent() {
     const STCT_FEE: u64 = dusk(1.0);
@@ -1308,3 +1529,366 @@ fn send_and_withdraw_obfuscated() {
         "Remaining value should what was put in minus what is taken out"
     );
 }
+
+#[test]
+fn withdraw_to_note() {
+    const BALANCE: u64 = dusk(10.0);
+
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let vm = &mut rusk_abi::new_ephemeral_vm()
+        .expect("Creating ephemeral VM should work");
+
+    let ssk = SecretSpendKey::random(rng);
+    let psk = PublicSpendKey::from(&ssk);
+
+    let session = &mut instantiate(rng, vm, &psk);
+
+    session
+        .call::<_, ()>(
+            TRANSFER_CONTRACT,
+            "add_module_balance",
+            &(ALICE_ID, BALANCE),
+            POINT_LIMIT,
+        )
+        .expect("Adding balance to alice should succeed");
+
+    let num_notes_before =
+        num_notes(session).expect("Getting num_notes should succeed");
+
+    // Withdrawing to an obfuscated note is rejected
+    let blinder = JubJubScalar::random(rng);
+    let wfctn = Wfctn {
+        value: BALANCE,
+        note: Note::obfuscated(rng, &psk, BALANCE, blinder),
+    };
+    session
+        .call::<_, ()>(ALICE_ID, "withdraw_to_note", &wfctn, POINT_LIMIT)
+        .expect_err("Withdrawing to an obfuscated note should fail");
+
+    // Withdrawing more than the note value is rejected
+    let wfctn = Wfctn {
+        value: BALANCE,
+        note: Note::transparent(rng, &psk, BALANCE / 2),
+    };
+    session
+        .call::<_, ()>(ALICE_ID, "withdraw_to_note", &wfctn, POINT_LIMIT)
+        .expect_err("Withdrawing a mismatched value should fail");
+
+    // Withdrawing to a note committing to more than its value is rejected
+    let note = Note::transparent(rng, &psk, BALANCE / 2);
+    let richer = Note::transparent(rng, &psk, BALANCE);
+    let mut bytes = note.to_bytes();
+    bytes[1..33].copy_from_slice(&richer.to_bytes()[1..33]);
+    let forged = Note::from_bytes(&bytes).expect("note to be valid");
+    assert_eq!(forged.value_commitment(), richer.value_commitment());
+    let wfctn = Wfctn {
+        value: BALANCE / 2,
+        note: forged,
+    };
+    session
+        .call::<_, ()>(ALICE_ID, "withdraw_to_note", &wfctn, POINT_LIMIT)
+        .expect_err("Withdrawing to a mismatched commitment should fail");
+
+    // Withdrawing part of the balance to a transparent note succeeds
+    let wfctn = Wfctn {
+        value: BALANCE / 2,
+        note: Note::transparent(rng, &psk, BALANCE / 2),
+    };
+    session
+        .call::<_, ()>(ALICE_ID, "withdraw_to_note", &wfctn, POINT_LIMIT)
+        .expect("Withdrawing to a transparent note should succeed");
+
+    let alice_balance = module_balance(session, ALICE_ID)
+        .expect("Querying the module balance should succeed");
+    assert_eq!(alice_balance, BALANCE / 2);
+
+    let num_notes_after =
+        num_notes(session).expect("Getting num_notes should succeed");
+    assert_eq!(num_notes_after, num_notes_before + 1);
+
+    // Withdrawing more than the balance is rejected
+    let wfctn = Wfctn {
+        value: BALANCE,
+        note: Note::transparent(rng, &psk, BALANCE),
+    };
+    session
+        .call::<_, ()>(ALICE_ID, "withdraw_to_note", &wfctn, POINT_LIMIT)
+        .expect_err("Withdrawing more than the balance should fail");
+
+    // Only contracts can withdraw
+    session
+        .call::<_, bool>(TRANSFER_CONTRACT, "wfctn", &wfctn, POINT_LIMIT)
+        .expect_err("Withdrawing from outside the VM should fail");
+}
+
+#[test]
+fn subscriptions_are_charged_and_capped() {
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let vm = &mut rusk_abi::new_ephemeral_vm()
+        .expect("Creating ephemeral VM should work");
+
+    let ssk = SecretSpendKey::random(rng);
+    let psk = PublicSpendKey::from(&ssk);
+
+    let session = &mut instantiate(rng, vm, &psk);
+
+    let subscription = |topic: &str| Subscription {
+        source: BOB_ID.to_bytes(),
+        topic: topic.into(),
+    };
+    let subscriptions = |session: &mut Session| {
+        session
+            .call::<_, Vec<([u8; 32], Subscription)>>(
+                TRANSFER_CONTRACT,
+                "subscriptions",
+                &(),
+                POINT_LIMIT,
+            )
+            .expect("Querying the subscriptions should succeed")
+            .data
+    };
+
+    // Subscribing without paying the fee is rejected
+    session
+        .call::<_, ()>(ALICE_ID, "subscribe", &subscription("t"), POINT_LIMIT)
+        .expect_err("Subscribing without balance should fail");
+    assert!(subscriptions(session).is_empty());
+
+    let balance = SUBSCRIPTION_FEE * MAX_SUBSCRIPTIONS as u64;
+    session
+        .call::<_, ()>(
+            TRANSFER_CONTRACT,
+            "add_module_balance",
+            &(ALICE_ID, balance),
+            POINT_LIMIT,
+        )
+        .expect("Adding balance to alice should succeed");
+
+    session
+        .call::<_, ()>(ALICE_ID, "subscribe", &subscription("t"), POINT_LIMIT)
+        .expect("Subscribing should succeed");
+    assert_eq!(
+        subscriptions(session),
+        vec![(ALICE_ID.to_bytes(), subscription("t"))]
+    );
+    let alice_balance = module_balance(session, ALICE_ID)
+        .expect("Querying the module balance should succeed");
+    assert_eq!(alice_balance, balance - SUBSCRIPTION_FEE);
+
+    // Subscribing again is free
+    session
+        .call::<_, ()>(ALICE_ID, "subscribe", &subscription("t"), POINT_LIMIT)
+        .expect("Subscribing again should succeed");
+    let alice_balance = module_balance(session, ALICE_ID)
+        .expect("Querying the module balance should succeed");
+    assert_eq!(alice_balance, balance - SUBSCRIPTION_FEE);
+
+    let long_topic = "t".repeat(MAX_SUBSCRIPTION_TOPIC_LEN + 1);
+    session
+        .call::<_, ()>(
+            ALICE_ID,
+            "subscribe",
+            &subscription(&long_topic),
+            POINT_LIMIT,
+        )
+        .expect_err("Subscribing to a long topic should fail");
+
+    for i in 1..MAX_SUBSCRIPTIONS {
+        let subscription = subscription(&format!("t{i}"));
+        session
+            .call::<_, ()>(ALICE_ID, "subscribe", &subscription, POINT_LIMIT)
+            .expect("Subscribing should succeed");
+    }
+    assert_eq!(subscriptions(session).len(), MAX_SUBSCRIPTIONS);
+    let alice_balance = module_balance(session, ALICE_ID)
+        .expect("Querying the module balance should succeed");
+    assert_eq!(alice_balance, 0);
+
+    session
+        .call::<_, ()>(
+            TRANSFER_CONTRACT,
+            "add_module_balance",
+            &(ALICE_ID, SUBSCRIPTION_FEE),
+            POINT_LIMIT,
+        )
+        .expect("Adding balance to alice should succeed");
+    session
+        .call::<_, ()>(ALICE_ID, "subscribe", &subscription("u"), POINT_LIMIT)
+        .expect_err("Subscribing past the cap should fail");
+}
+
+/// Creates a transaction spending the single note in the state, calling the
+/// given function of the transfer contract.
+fn transfer_call_tx<Rng: RngCore + CryptoRng>(
+    rng: &mut Rng,
+    session: &mut Session,
+    ssk: &SecretSpendKey,
+    fn_name: &str,
+    fn_args: Vec<u8>,
+) -> Transaction {
+    const CALL_FEE: u64 = dusk(1.0);
+
+    let psk = PublicSpendKey::from(ssk);
+
+    let leaves = leaves_from_height(session, 0)
+        .expect("Getting leaves in the given range should succeed");
+    assert_eq!(leaves.len(), 1, "There should be one note in the state");
+
+    let input_note = leaves[0].note;
+    let input_value = input_note
+        .value(None)
+        .expect("The value should be transparent");
+    let input_blinder = input_note
+        .blinding_factor(None)
+        .expect("The blinder should be transparent");
+    let input_nullifier = input_note.gen_nullifier(ssk);
+
+    let gas_limit = CALL_FEE;
+    let gas_price = LUX;
+
+    let fee = Fee::new(rng, gas_limit, gas_price, &psk);
+
+    let change_value = input_value - gas_price * gas_limit;
+    let change_blinder = JubJubScalar::random(rng);
+    let change_note = Note::obfuscated(rng, &psk, change_value, change_blinder);
+
+    let call =
+        Some((TRANSFER_CONTRACT.to_bytes(), String::from(fn_name), fn_args));
+
+    let mut circuit = ExecuteCircuitOneTwo::new();
+
+    circuit.set_fee(&fee);
+    circuit
+        .add_output_with_data(change_note, change_value, change_blinder)
+        .expect("appending input or output should succeed");
+
+    let opening = opening(session, *input_note.pos())
+        .expect("Querying the opening for the given position should succeed")
+        .expect("An opening should exist for a note in the tree");
+
+    let sk_r = ssk.sk_r(input_note.stealth_address());
+    let pk_r_p = GENERATOR_NUMS_EXTENDED * sk_r.as_ref();
+
+    let anchor =
+        root(session).expect("Getting the anchor should be successful");
+
+    let tx_hash_input_bytes = Transaction::hash_input_bytes_from_components(
+        &[input_nullifier],
+        &[change_note],
+        &anchor,
+        &fee,
+        &None,
+        &call,
+    );
+    let tx_hash = rusk_abi::hash(tx_hash_input_bytes);
+
+    circuit.set_tx_hash(tx_hash);
+
+    let circuit_input_signature =
+        CircuitInputSignature::sign(rng, ssk, &input_note, tx_hash);
+    let circuit_input = CircuitInput::new(
+        opening,
+        input_note,
+        pk_r_p.into(),
+        input_value,
+        input_blinder,
+        input_nullifier,
+        circuit_input_signature,
+    );
+
+    circuit
+        .add_input(circuit_input)
+        .expect("appending input or output should succeed");
+
+    let (prover, _) = prover_verifier("ExecuteCircuitOneTwo");
+    let (proof, _) = prover
+        .prove(rng, &circuit)
+        .expect("creating a proof should succeed");
+
+    Transaction {
+        anchor,
+        nullifiers: vec![input_nullifier],
+        outputs: vec![change_note],
+        fee,
+        crossover: None,
+        proof: proof.to_bytes().to_vec(),
+        call,
+    }
+}
+
+#[test]
+fn migration_is_bound_to_chain_id() {
+    const CAROL_ID: ContractId = {
+        let mut bytes = [0u8; 32];
+        bytes[0] = 0xFC;
+        ContractId::from_bytes(bytes)
+    };
+
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let vm = &mut rusk_abi::new_ephemeral_vm()
+        .expect("Creating ephemeral VM should work");
+
+    let ssk = SecretSpendKey::random(rng);
+    let psk = PublicSpendKey::from(&ssk);
+
+    let bytecode = include_bytes!(
+        "../../../target/wasm32-unknown-unknown/release/alice.wasm"
+    );
+
+    // The sessions are not given a chain id, so they run on chain 0
+    for (chain_id, migrated) in [(1, false), (0, true)] {
+        let session = &mut instantiate(rng, vm, &psk);
+
+        session
+            .deploy(
+                bytecode,
+                ContractData::builder()
+                    .owner(psk.to_bytes())
+                    .contract_id(CAROL_ID),
+                POINT_LIMIT,
+            )
+            .expect("Deploying the carol contract should succeed");
+
+        let contract = CAROL_ID.to_bytes();
+        let msg = migration_signature_message(chain_id, &contract, 1, bytecode);
+        let sk = SecretKey::from(*ssk.a());
+        let signature = Signature::new(&sk, rng, rusk_abi::hash(msg));
+
+        let migration = Migration {
+            contract,
+            owner: psk,
+            bytecode: bytecode.to_vec(),
+            version: 1,
+            signature,
+        };
+        let migration = rkyv::to_bytes::<_, 4096>(&migration)
+            .expect("Serializing the migration should succeed")
+            .to_vec();
+
+        let tx = transfer_call_tx(rng, session, &ssk, MIGRATE_FN, migration);
+
+        let receipt = session
+            .call::<_, Result<Vec<u8>, ContractError>>(
+                TRANSFER_CONTRACT,
+                "spend_and_execute",
+                &tx,
+                u64::MAX,
+            )
+            .expect("Executing TX should succeed");
+        assert_eq!(receipt.data.is_ok(), migrated);
+
+        let version = session
+            .call::<_, u64>(
+                TRANSFER_CONTRACT,
+                "contract_version",
+                &CAROL_ID,
+                POINT_LIMIT,
+            )
+            .expect("Querying the contract version should succeed")
+            .data;
+        assert_eq!(version, u64::from(migrated));
+    }
+}
@@ -20,6 +20,8 @@ use std::fs;
 use std::path::PathBuf;
 use tracing::warn;
 
+use crate::keystore::Keystore;
+
 pub const PUBLIC_BLS_SIZE: usize = dusk_bls12_381_sign::PublicKey::SIZE;
 
 /// Extends dusk_bls12_381_sign::PublicKey by implementing a few traits
@@ -132,7 +134,8 @@ impl Debug for PublicKeyBytes {
     }
 }
 
-/// Loads consensus keys from an encrypted file.
+/// Loads consensus keys from an encrypted file, either a keystore or a
+/// consensus keys file exported by the wallet.
 ///
 /// Panics on any error.
 pub fn load_keys(
@@ -140,6 +143,15 @@ pub fn load_keys(
     pwd: String,
 ) -> anyhow::Result<(dusk_bls12_381_sign::SecretKey, PublicKey)> {
     let path_buf = PathBuf::from(path);
+
+    if let Ok(keystore) = fs::read(&path_buf)
+        .map_err(anyhow::Error::from)
+        .and_then(|bytes| Keystore::from_slice(&bytes))
+    {
+        let (sk, pk) = keystore.decrypt(&pwd)?;
+        return Ok((sk, PublicKey::new(pk)));
+    }
+
     let (pk, sk) = read_from_file(path_buf, &pwd)?;
 
     Ok((sk, PublicKey::new(pk)))
@@ -148,7 +160,7 @@ pub fn load_keys(
 /// Fetches BLS public and secret keys from an encrypted consensus keys file.
 ///
 /// Panics on any error.
-fn read_from_file(
+pub fn read_from_file(
     path: PathBuf,
     pwd: &str,
 ) -> anyhow::Result<(
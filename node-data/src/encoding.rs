@@ -6,7 +6,8 @@
 
 use crate::bls::PublicKeyBytes;
 use crate::ledger::{
-    Block, Certificate, Header, IterationsInfo, Label, SpentTransaction,
+    Block, CallGas, Certificate, ContractEvent, ContractGas, Header,
+    HeaderExtensions, HeaderField, IterationsInfo, Label, SpentTransaction,
     StepVotes, Transaction,
 };
 use crate::message::payload::{
@@ -55,6 +56,9 @@ impl Serializable for Transaction {
         //Write TxType
         w.write_all(&self.r#type.to_le_bytes())?;
 
+        //Write ChainID
+        w.write_all(&[self.chain_id])?;
+
         let data = self.inner.to_var_bytes();
 
         // Write inner transaction
@@ -69,15 +73,18 @@ impl Serializable for Transaction {
     {
         let version = Self::read_u32_le(r)?;
         let tx_type = Self::read_u32_le(r)?;
+        let chain_id = Self::read_u8(r)?;
 
-        let tx_payload = Self::read_var_le_bytes32(r)?;
-        let inner = phoenix_core::Transaction::from_slice(&tx_payload[..])
-            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        let inner = Self::read_var_le_with(r, |tx_payload| {
+            phoenix_core::Transaction::from_slice(tx_payload)
+                .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))
+        })?;
 
         Ok(Self {
             inner,
             version,
             r#type: tx_type,
+            chain_id,
         })
     }
 }
@@ -87,6 +94,7 @@ impl Serializable for SpentTransaction {
         self.inner.write(w)?;
         w.write_all(&self.block_height.to_le_bytes())?;
         w.write_all(&self.gas_spent.to_le_bytes())?;
+        w.write_all(&self.gas_price.to_le_bytes())?;
 
         match &self.err {
             Some(e) => {
@@ -95,10 +103,22 @@ impl Serializable for SpentTransaction {
                 w.write_all(b)?;
             }
             None => {
-                w.write_all(&0_u64.to_le_bytes())?;
+                w.write_all(&0_u32.to_le_bytes())?;
             }
         }
 
+        let events_len = self.events.len() as u32;
+        w.write_all(&events_len.to_le_bytes())?;
+        for event in &self.events {
+            event.write(w)?;
+        }
+
+        let call_gas_len = self.call_gas.len() as u32;
+        w.write_all(&call_gas_len.to_le_bytes())?;
+        for call in &self.call_gas {
+            call.write(w)?;
+        }
+
         Ok(())
     }
 
@@ -110,22 +130,121 @@ impl Serializable for SpentTransaction {
 
         let block_height = Self::read_u64_le(r)?;
         let gas_spent = Self::read_u64_le(r)?;
+        let gas_price = Self::read_u64_le(r)?;
         let error_len = Self::read_u32_le(r)?;
 
         let err = if error_len > 0 {
             let mut buf = vec![0u8; error_len as usize];
             r.read_exact(&mut buf[..])?;
 
-            Some(String::from_utf8(buf).expect("Cannot from_utf8"))
+            let err = String::from_utf8(buf)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            Some(err)
         } else {
             None
         };
 
+        // Transactions stored before the events were recorded end here
+        let events_len = match Self::read_u32_le(r) {
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => 0,
+            Err(e) => return Err(e),
+        };
+        let events = (0..events_len)
+            .map(|_| ContractEvent::read(r))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Transactions stored before the call gas was recorded end here
+        let call_gas_len = match Self::read_u32_le(r) {
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => 0,
+            Err(e) => return Err(e),
+        };
+        let call_gas = (0..call_gas_len)
+            .map(|_| CallGas::read(r))
+            .collect::<Result<Vec<_>, _>>()?;
+
         Ok(Self {
             inner,
             block_height,
             gas_spent,
+            gas_price,
             err,
+            events,
+            call_gas,
+        })
+    }
+}
+
+impl Serializable for ContractEvent {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.source)?;
+        Self::write_var_le_bytes32(w, self.topic.as_bytes())?;
+        Self::write_var_le_bytes32(w, &self.data)?;
+
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let source = Self::read_bytes(r)?;
+        let topic = String::from_utf8(Self::read_var_le_bytes32(r)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let data = Self::read_var_le_bytes32(r)?;
+
+        Ok(Self {
+            source,
+            topic,
+            data,
+        })
+    }
+}
+
+impl Serializable for CallGas {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.contract)?;
+        w.write_all(&self.gas_spent.to_le_bytes())?;
+
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let contract = Self::read_bytes(r)?;
+        let gas_spent = Self::read_u64_le(r)?;
+
+        Ok(Self {
+            contract,
+            gas_spent,
+        })
+    }
+}
+
+impl Serializable for ContractGas {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.contract)?;
+        w.write_all(&self.gas_spent.to_le_bytes())?;
+        w.write_all(&self.transactions.to_le_bytes())?;
+
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let contract = Self::read_bytes(r)?;
+        let gas_spent = Self::read_u64_le(r)?;
+        let transactions = Self::read_u32_le(r)?;
+
+        Ok(Self {
+            contract,
+            gas_spent,
+            transactions,
         })
     }
 }
@@ -150,6 +269,37 @@ impl Serializable for Header {
     }
 }
 
+impl Serializable for HeaderExtensions {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut section = vec![];
+        for field in self.iter() {
+            section.write_all(&field.tag.to_le_bytes())?;
+            Self::write_var_le_bytes32(&mut section, &field.data)?;
+        }
+
+        // The whole section is length-prefixed, allowing to skip it
+        // regardless of its content
+        Self::write_var_le_bytes32(w, &section)
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let fields = Self::read_var_le_with(r, |mut section| {
+            let mut fields = vec![];
+            while !section.is_empty() {
+                let tag = Self::read_u16_le(&mut section)?;
+                let data = Self::read_var_le_bytes32(&mut section)?;
+                fields.push(HeaderField { tag, data });
+            }
+            Ok(fields)
+        })?;
+
+        Ok(HeaderExtensions(fields))
+    }
+}
+
 impl Serializable for Certificate {
     fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
         self.result.write(w)?;
@@ -384,6 +534,7 @@ impl Serializable for QuorumType {
 
 #[cfg(test)]
 mod tests {
+    use crate::ledger::{HEADER_VERSION_EXTENSIBLE, HEADER_VERSION_LEGACY};
     use crate::message::payload::{Candidate, Validation};
 
     use super::*;
@@ -434,6 +585,50 @@ mod tests {
         assert_serializable::<SpentTransaction>();
     }
 
+    #[test]
+    fn test_decoding_spent_transaction_without_events() {
+        let mut tx: SpentTransaction = Faker.fake();
+        tx.err = Some("error".to_string());
+        tx.events = vec![];
+        tx.call_gas = vec![];
+
+        let mut buf = vec![];
+        tx.write(&mut buf).expect("should be writable");
+
+        // Drop the events and call gas counts, as stored before the events
+        // were recorded
+        buf.truncate(buf.len() - 8);
+        let read =
+            SpentTransaction::read(&mut &buf[..]).expect("should be readable");
+        assert_eq!(read, tx);
+        assert_eq!(read.err, tx.err);
+    }
+
+    #[test]
+    fn test_decoding_spent_transaction_without_call_gas() {
+        let mut tx: SpentTransaction = Faker.fake();
+        tx.call_gas = vec![];
+
+        let mut buf = vec![];
+        tx.write(&mut buf).expect("should be writable");
+
+        // Drop the call gas count, as stored before the call gas was recorded
+        buf.truncate(buf.len() - 4);
+        let read =
+            SpentTransaction::read(&mut &buf[..]).expect("should be readable");
+        assert_eq!(read, tx);
+    }
+
+    #[test]
+    fn test_encoding_call_gas() {
+        assert_serializable::<CallGas>();
+    }
+
+    #[test]
+    fn test_encoding_contract_gas() {
+        assert_serializable::<ContractGas>();
+    }
+
     #[test]
     fn test_encoding_header() {
         assert_serializable::<ConsensusHeader>();
@@ -448,4 +643,128 @@ mod tests {
     fn test_encoding_ratification_result() {
         assert_serializable::<RatificationResult>();
     }
+
+    fn header_with_version(version: u8) -> Header {
+        let mut header: Header = Faker.fake();
+        header.version = version;
+        header.hash = [0; 32];
+        header
+    }
+
+    fn to_bytes<S: Serializable>(obj: &S) -> Vec<u8> {
+        let mut buf = vec![];
+        obj.write(&mut buf).expect("should be writable");
+        buf
+    }
+
+    fn hash(header: Header) -> [u8; 32] {
+        Block::new(header, vec![])
+            .expect("block should be valid")
+            .header()
+            .hash
+    }
+
+    #[test]
+    fn test_encoding_header_legacy_layout() {
+        let legacy = header_with_version(HEADER_VERSION_LEGACY);
+
+        let mut extensible = legacy.clone();
+        extensible.version = HEADER_VERSION_EXTENSIBLE;
+
+        // Legacy headers are encoded without the optional fields section
+        let legacy_bytes = to_bytes(&legacy);
+        let extensible_bytes = to_bytes(&extensible);
+        assert_eq!(legacy_bytes.len() + 4, extensible_bytes.len());
+
+        let mut legacy_with_fields = legacy.clone();
+        legacy_with_fields.extensions.set(1, vec![1, 2, 3]);
+        assert_eq!(to_bytes(&legacy_with_fields), legacy_bytes);
+        assert_eq!(hash(legacy_with_fields), hash(legacy));
+    }
+
+    #[test]
+    fn test_encoding_header_optional_fields() {
+        let mut header = header_with_version(HEADER_VERSION_EXTENSIBLE);
+        header.extensions.set(1, vec![1; 96]);
+        header.extensions.set(7, vec![]);
+
+        let bytes = to_bytes(&header);
+        let read = Header::read(&mut &bytes[..]).expect("should be readable");
+        assert_eq!(read, header);
+        assert_eq!(read.extensions.get(1), Some(&[1; 96][..]));
+        assert_eq!(read.extensions.get(7), Some(&[][..]));
+        assert_eq!(read.extensions.get(2), None);
+
+        // Optional fields are part of the block hash
+        let mut other = header.clone();
+        other.extensions.set(7, vec![0]);
+        assert_ne!(hash(other), hash(header));
+    }
+
+    #[test]
+    fn test_encoding_block_extensible_version() {
+        // Headers generated from the activation of the extensible version
+        // carry an empty section of optional fields
+        let mut header = header_with_version(HEADER_VERSION_EXTENSIBLE);
+        header.extensions = HeaderExtensions::default();
+        let block = Block::new(header, vec![]).expect("block should be valid");
+
+        let bytes = to_bytes(&block);
+        let read = Block::read(&mut &bytes[..]).expect("should be readable");
+        assert_eq!(read.header(), block.header());
+        assert_eq!(read.header().version, HEADER_VERSION_EXTENSIBLE);
+        assert_eq!(to_bytes(&read), bytes);
+
+        // The version is part of the block hash
+        let mut legacy = read.header().clone();
+        legacy.version = HEADER_VERSION_LEGACY;
+        legacy.hash = [0; 32];
+        assert_ne!(hash(legacy), block.header().hash);
+    }
+
+    #[test]
+    fn test_encoding_header_future_version() {
+        // A header of a later version, with fields unknown to this node,
+        // duplicated tags included, must be read and re-encoded unchanged
+        let mut header = header_with_version(HEADER_VERSION_EXTENSIBLE + 1);
+        header.extensions = HeaderExtensions(vec![
+            HeaderField {
+                tag: u16::MAX,
+                data: vec![42; 10],
+            },
+            HeaderField {
+                tag: 3,
+                data: vec![1],
+            },
+            HeaderField {
+                tag: 3,
+                data: vec![2],
+            },
+        ]);
+        let block = Block::new(header, vec![]).expect("block should be valid");
+
+        let bytes = to_bytes(&block);
+        let read = Block::read(&mut &bytes[..]).expect("should be readable");
+        assert_eq!(to_bytes(&read), bytes);
+        assert_eq!(read.header(), block.header());
+
+        let mut rehashed = read.header().clone();
+        rehashed.hash = [0; 32];
+        assert_eq!(hash(rehashed), block.header().hash);
+    }
+
+    #[test]
+    fn test_encoding_header_malformed_fields() {
+        let header = header_with_version(HEADER_VERSION_EXTENSIBLE);
+        let mut bytes = vec![];
+        header
+            .marshal_hashable(&mut bytes)
+            .expect("should be writable");
+
+        // Replace the empty section with a truncated field
+        bytes.truncate(bytes.len() - 4);
+        bytes.extend(3u32.to_le_bytes());
+        bytes.extend([1, 0, 5]);
+        assert!(Header::unmarshal_hashable(&mut &bytes[..]).is_err());
+    }
 }
@@ -0,0 +1,141 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ledger::SizeLimitError;
+
+/// gRPC status code of failures of unknown kind
+pub const GRPC_UNKNOWN: u8 = 2;
+const GRPC_FAILED_PRECONDITION: u8 = 9;
+const GRPC_INTERNAL: u8 = 13;
+const GRPC_UNAVAILABLE: u8 = 14;
+
+/// How a failure should be handled by whoever requested the operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The operation may succeed if retried later
+    Transient,
+    /// The operation will fail again if retried as is
+    Permanent,
+    /// The node state or the agreement with the network is at stake
+    ConsensusCritical,
+}
+
+impl ErrorKind {
+    /// Returns true if retrying the failed operation makes sense.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ErrorKind::Transient)
+    }
+
+    /// Returns the gRPC status code clients should be answered with.
+    ///
+    /// Transient failures map to `UNAVAILABLE`, the only code gRPC clients
+    /// retry by default, permanent ones to `FAILED_PRECONDITION` and
+    /// consensus-critical ones to `INTERNAL`.
+    pub fn grpc_code(&self) -> u8 {
+        match self {
+            ErrorKind::Transient => GRPC_UNAVAILABLE,
+            ErrorKind::Permanent => GRPC_FAILED_PRECONDITION,
+            ErrorKind::ConsensusCritical => GRPC_INTERNAL,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Transient => "transient",
+            ErrorKind::Permanent => "permanent",
+            ErrorKind::ConsensusCritical => "consensus_critical",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Errors that can tell what kind of failure they are.
+pub trait Classify {
+    fn kind(&self) -> ErrorKind;
+}
+
+impl Classify for SizeLimitError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Permanent
+    }
+}
+
+/// An error crossing a crate boundary as an [`anyhow::Error`], tagged with
+/// the kind of failure it is.
+#[derive(Debug)]
+pub struct Classified {
+    kind: ErrorKind,
+    source: anyhow::Error,
+}
+
+impl Classified {
+    pub fn new(kind: ErrorKind, source: impl Into<anyhow::Error>) -> Self {
+        Self {
+            kind,
+            source: source.into(),
+        }
+    }
+}
+
+impl Classify for Classified {
+    fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+}
+
+impl std::fmt::Display for Classified {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl std::error::Error for Classified {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Returns the kind of the first classified error in the chain of `err`, if
+/// any.
+pub fn kind_of(err: &anyhow::Error) -> Option<ErrorKind> {
+    err.chain().find_map(|e| {
+        if let Some(e) = e.downcast_ref::<Classified>() {
+            return Some(e.kind());
+        }
+        e.downcast_ref::<SizeLimitError>().map(Classify::kind)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_of_chain() {
+        let err = anyhow::anyhow!("cannot read the state");
+        let err = Classified::new(ErrorKind::Transient, err);
+        let err = anyhow::Error::from(err).context("cannot preverify");
+        assert_eq!(kind_of(&err), Some(ErrorKind::Transient));
+        assert_eq!(err.root_cause().to_string(), "cannot read the state");
+
+        assert_eq!(kind_of(&anyhow::anyhow!("unknown")), None);
+    }
+
+    #[test]
+    fn grpc_codes() {
+        assert_eq!(ErrorKind::Transient.grpc_code(), 14);
+        assert_eq!(ErrorKind::Permanent.grpc_code(), 9);
+        assert_eq!(ErrorKind::ConsensusCritical.grpc_code(), 13);
+    }
+}
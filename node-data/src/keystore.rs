@@ -0,0 +1,265 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Encrypted storage of the consensus keys.
+//!
+//! The BLS secret key is encrypted with AES-256-CBC, using a key derived from
+//! a passphrase with PBKDF2-HMAC-SHA256. The passphrase is never stored, and
+//! is provided through the environment when the node starts.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use aes::Aes256;
+use anyhow::{anyhow, Result};
+use block_modes::block_padding::Pkcs7;
+use block_modes::{BlockMode, Cbc};
+use dusk_bls12_381_sign::{PublicKey, SecretKey};
+use dusk_bytes::{DeserializableSlice, Serializable};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+type Aes256Cbc = Cbc<Aes256, Pkcs7>;
+
+/// Env var holding the passphrase of the consensus keys
+pub const PASSPHRASE_ENV: &str = "DUSK_CONSENSUS_KEYS_PASS";
+
+/// Env var holding the path of a file containing the passphrase of the
+/// consensus keys, used if [`PASSPHRASE_ENV`] is not set
+pub const PASSPHRASE_FILE_ENV: &str = "DUSK_CONSENSUS_KEYS_PASS_FILE";
+
+pub const KEYSTORE_VERSION: u8 = 1;
+
+/// Default number of PBKDF2 iterations for new keystores
+pub const DEFAULT_ITERATIONS: u32 = 600_000;
+
+const KDF_NAME: &str = "pbkdf2-hmac-sha256";
+const CIPHER_NAME: &str = "aes-256-cbc";
+const SALT_SIZE: usize = 32;
+const IV_SIZE: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub name: String,
+    pub iterations: u32,
+    /// Base64 encoded salt
+    pub salt: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub name: String,
+    /// Base64 encoded initialization vector
+    pub iv: String,
+}
+
+/// Keystore file holding an encrypted BLS secret key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u8,
+    /// Base58 encoded public key, allowing to identify the keystore without
+    /// decrypting it
+    pub public_key: String,
+    pub kdf: KdfParams,
+    pub cipher: CipherParams,
+    /// Base64 encoded encrypted secret key
+    pub ciphertext: String,
+}
+
+impl Keystore {
+    /// Encrypts `sk` with a key derived from `passphrase`.
+    pub fn encrypt<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        sk: &SecretKey,
+        passphrase: &str,
+        iterations: u32,
+    ) -> Result<Self> {
+        let mut salt = [0u8; SALT_SIZE];
+        let mut iv = [0u8; IV_SIZE];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut iv);
+
+        let key = derive_key(passphrase, &salt, iterations);
+        let ciphertext = Aes256Cbc::new_from_slices(&key, &iv)
+            .map_err(|e| anyhow!("invalid cipher parameters {e}"))?
+            .encrypt_vec(&sk.to_bytes());
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            public_key: bs58::encode(PublicKey::from(sk).to_bytes())
+                .into_string(),
+            kdf: KdfParams {
+                name: KDF_NAME.into(),
+                iterations,
+                salt: base64::encode(salt),
+            },
+            cipher: CipherParams {
+                name: CIPHER_NAME.into(),
+                iv: base64::encode(iv),
+            },
+            ciphertext: base64::encode(ciphertext),
+        })
+    }
+
+    /// Decrypts the secret key with a key derived from `passphrase`,
+    /// checking it matches the stored public key.
+    pub fn decrypt(&self, passphrase: &str) -> Result<(SecretKey, PublicKey)> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(anyhow!(
+                "unsupported keystore version {}",
+                self.version
+            ));
+        }
+        if self.kdf.name != KDF_NAME || self.cipher.name != CIPHER_NAME {
+            return Err(anyhow!(
+                "unsupported keystore scheme {}/{}",
+                self.kdf.name,
+                self.cipher.name
+            ));
+        }
+
+        let salt = base64::decode(&self.kdf.salt)
+            .map_err(|e| anyhow!("salt should be base64 {e}"))?;
+        let iv = base64::decode(&self.cipher.iv)
+            .map_err(|e| anyhow!("iv should be base64 {e}"))?;
+        let ciphertext = base64::decode(&self.ciphertext)
+            .map_err(|e| anyhow!("ciphertext should be base64 {e}"))?;
+
+        let key = derive_key(passphrase, &salt, self.kdf.iterations);
+        let sk_bytes = Aes256Cbc::new_from_slices(&key, &iv)
+            .map_err(|e| anyhow!("invalid cipher parameters {e}"))?
+            .decrypt_vec(&ciphertext)
+            .map_err(|_| anyhow!("Invalid consensus keys passphrase"))?;
+
+        let sk = SecretKey::from_slice(&sk_bytes)
+            .map_err(|_| anyhow!("Invalid consensus keys passphrase"))?;
+        let pk = PublicKey::from(&sk);
+
+        if bs58::encode(pk.to_bytes()).into_string() != self.public_key {
+            return Err(anyhow!("Invalid consensus keys passphrase"));
+        }
+
+        Ok((sk, pk))
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read(path).map_err(|e| {
+            anyhow!("{} should be a valid keystore file {e}", path.display())
+        })?;
+        Self::from_slice(&content)
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        serde_json::from_slice(bytes)
+            .map_err(|e| anyhow!("keystore should contain json {e}"))
+    }
+
+    /// Writes the keystore to `path`, failing if the file already exists.
+    ///
+    /// On unix the file is readable and writable by its owner only.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options.open(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => {
+                anyhow!("{} already exists", path.display())
+            }
+            _ => anyhow!("cannot create {}: {e}", path.display()),
+        })?;
+        file.write_all(&serde_json::to_vec_pretty(self)?)?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(
+        passphrase.as_bytes(),
+        salt,
+        iterations,
+        &mut key,
+    );
+    key
+}
+
+/// Returns the passphrase of the consensus keys, read from
+/// [`PASSPHRASE_ENV`] or from the file pointed by [`PASSPHRASE_FILE_ENV`].
+pub fn passphrase() -> Result<String> {
+    if let Ok(pwd) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(pwd);
+    }
+
+    let path = std::env::var(PASSPHRASE_FILE_ENV).map_err(|_| {
+        anyhow!("neither {PASSPHRASE_ENV} nor {PASSPHRASE_FILE_ENV} are set")
+    })?;
+    let pwd = fs::read_to_string(&path)
+        .map_err(|e| anyhow!("cannot read passphrase file {path}: {e}"))?;
+
+    Ok(pwd.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn keystore_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let sk = SecretKey::random(&mut rng);
+
+        let keystore = Keystore::encrypt(&mut rng, &sk, "passphrase", 1_000)
+            .expect("encryption to succeed");
+        let keystore = Keystore::from_slice(
+            &serde_json::to_vec(&keystore).expect("serialization to succeed"),
+        )
+        .expect("deserialization to succeed");
+
+        let (decrypted, pk) = keystore
+            .decrypt("passphrase")
+            .expect("decryption to succeed");
+        assert_eq!(decrypted, sk);
+        assert_eq!(pk, PublicKey::from(&sk));
+
+        assert!(keystore.decrypt("wrong passphrase").is_err());
+    }
+
+    #[test]
+    fn keystore_write() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let sk = SecretKey::random(&mut rng);
+        let keystore = Keystore::encrypt(&mut rng, &sk, "passphrase", 1_000)
+            .expect("encryption to succeed");
+
+        let dir = tempfile::tempdir().expect("temp dir to be created");
+        let path = dir.path().join("consensus.keys");
+        keystore.write(&path).expect("keystore to be written");
+        assert!(keystore.write(&path).is_err(), "existing file is kept");
+
+        let read = Keystore::read(&path).expect("keystore to be read");
+        assert_eq!(read.decrypt("passphrase").expect("valid keystore").0, sk);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).expect("metadata").permissions();
+            assert_eq!(mode.mode() & 0o777, 0o600);
+        }
+    }
+}
@@ -19,6 +19,20 @@ use fake::{Dummy, Fake, Faker};
 pub type Seed = Signature;
 pub type Hash = [u8; 32];
 
+/// Chain ID used when none is configured (e.g. local networks)
+pub const DEFAULT_CHAIN_ID: u8 = 0;
+
+/// Header version with the original fixed layout
+pub const HEADER_VERSION_LEGACY: u8 = 0;
+
+/// First header version whose hashable fields end with a length-prefixed
+/// section of optional fields.
+///
+/// Any later version keeps this layout and only introduces new optional
+/// fields, so that nodes unaware of them can still parse, hash and relay
+/// the headers.
+pub const HEADER_VERSION_EXTENSIBLE: u8 = 1;
+
 #[derive(Default, Debug, Clone)]
 pub struct Block {
     header: Header,
@@ -30,6 +44,7 @@ pub struct Block {
 pub struct Header {
     // Hashable fields
     pub version: u8,
+    pub chain_id: u8,
     pub height: u64,
     pub timestamp: u64,
     pub prev_block_hash: Hash,
@@ -42,6 +57,9 @@ pub struct Header {
     pub iteration: u8,
     pub prev_block_cert: Certificate,
     pub failed_iterations: IterationsInfo,
+    /// Optional fields, only encoded if `version` is at least
+    /// [`HEADER_VERSION_EXTENSIBLE`]
+    pub extensions: HeaderExtensions,
 
     // Block hash
     pub hash: Hash,
@@ -58,6 +76,7 @@ impl std::fmt::Debug for Header {
 
         f.debug_struct("Header")
             .field("version", &self.version)
+            .field("chain_id", &self.chain_id)
             .field("height", &self.height)
             .field("timestamp", &timestamp)
             .field("prev_block_hash", &to_str(&self.prev_block_hash))
@@ -66,6 +85,7 @@ impl std::fmt::Debug for Header {
             .field("event_hash", &to_str(&self.event_hash))
             .field("gen_bls_pubkey", &to_str(self.generator_bls_pubkey.inner()))
             .field("gas_limit", &self.gas_limit)
+            .field("extensions", &self.extensions)
             .field("hash", &to_str(&self.hash))
             .field("cert", &self.cert)
             .finish()
@@ -76,6 +96,8 @@ impl std::fmt::Debug for Header {
 pub struct Transaction {
     pub version: u32,
     pub r#type: u32,
+    /// Network the transaction is meant for
+    pub chain_id: u8,
     pub inner: phoenix_core::Transaction,
 }
 
@@ -85,6 +107,7 @@ impl From<phoenix_core::Transaction> for Transaction {
             inner: value,
             r#type: 1,
             version: 1,
+            chain_id: DEFAULT_CHAIN_ID,
         }
     }
 }
@@ -94,13 +117,141 @@ pub struct SpentTransaction {
     pub inner: Transaction,
     pub block_height: u64,
     pub gas_spent: u64,
+    /// Price the gas spent was charged at, up to the gas price of the
+    /// transaction
+    pub gas_price: u64,
     pub err: Option<String>,
+    /// Events emitted by contracts while executing this transaction
+    pub events: Vec<ContractEvent>,
+    /// Gas spent in the calls to each contract while executing this
+    /// transaction, nested calls included
+    pub call_gas: Vec<CallGas>,
+}
+
+/// Gas spent in the calls to a contract, including the calls it made to
+/// other contracts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(any(feature = "faker", test), derive(Dummy))]
+pub struct CallGas {
+    pub contract: [u8; 32],
+    pub gas_spent: u64,
+}
+
+/// An event emitted by a contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractEvent {
+    pub source: [u8; 32],
+    pub topic: String,
+    pub data: Vec<u8>,
+}
+
+/// Incremental computation of the event hash of a block.
+///
+/// The event hash is the SHA3-256 of the source, topic and data of every
+/// event emitted while executing the block, in emission order, with no
+/// separator nor length prefix. This includes the events emitted by the
+/// hooks and by the rewards and slashes applied at the end of the block.
+#[derive(Debug, Default, Clone)]
+pub struct EventHasher(sha3::Sha3_256);
+
+impl EventHasher {
+    /// Feeds an event to the hasher.
+    pub fn update(&mut self, source: &[u8], topic: &str, data: &[u8]) {
+        self.0.update(source);
+        self.0.update(topic.as_bytes());
+        self.0.update(data);
+    }
+
+    /// Returns the hash of the events fed so far.
+    pub fn finalize(self) -> [u8; 32] {
+        self.0.finalize().into()
+    }
+}
+
+/// Computes the event hash of the given events, as stored in the
+/// `event_hash` of the header of the block that emitted them.
+pub fn event_hash<'a, I>(events: I) -> [u8; 32]
+where
+    I: IntoIterator<Item = &'a ContractEvent>,
+{
+    let mut hasher = EventHasher::default();
+    for event in events {
+        hasher.update(&event.source, &event.topic, &event.data);
+    }
+    hasher.finalize()
+}
+
+/// Gas spent in a block by the transactions calling a contract
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(any(feature = "faker", test), derive(Dummy))]
+pub struct ContractGas {
+    pub contract: [u8; 32],
+    pub gas_spent: u64,
+    pub transactions: u32,
+}
+
+/// Aggregates the gas spent by the given transactions per contract, ordered by
+/// contract id.
+///
+/// Every contract called while executing a transaction, directly or by
+/// another contract, is attributed the gas spent in its calls, so the gas of a
+/// nested call counts for both the caller and the callee. Transactions stored
+/// without their call gas are attributed to the contract they call only.
+pub fn gas_by_contract<'a, I>(txs: I) -> Vec<ContractGas>
+where
+    I: IntoIterator<Item = &'a SpentTransaction>,
+{
+    let mut usage = std::collections::BTreeMap::new();
+    let mut add = |contract: [u8; 32], gas_spent: u64| {
+        let entry = usage.entry(contract).or_insert_with(|| ContractGas {
+            contract,
+            ..Default::default()
+        });
+        entry.gas_spent += gas_spent;
+        entry.transactions += 1;
+    };
+
+    for tx in txs {
+        if tx.call_gas.is_empty() {
+            add(tx.inner.called_contract(), tx.gas_spent);
+        }
+        for call in &tx.call_gas {
+            add(call.contract, call.gas_spent);
+        }
+    }
+    usage.into_values().collect()
 }
 
 impl Transaction {
+    /// Returns the id of the contract called by the transaction, the transfer
+    /// contract for the transactions not calling any.
+    pub fn called_contract(&self) -> [u8; 32] {
+        match &self.inner.call {
+            Some((contract, _, _)) => *contract,
+            None => rusk_abi::TRANSFER_CONTRACT.to_bytes(),
+        }
+    }
+
     pub fn hash(&self) -> [u8; 32] {
         Hasher::digest(self.inner.to_hash_input_bytes()).to_bytes()
     }
+
+    /// Sets the network the transaction is meant for.
+    ///
+    /// The proof of the transaction is bound to its network, so a
+    /// transaction set for another network than the one it was proven for
+    /// fails verification.
+    pub fn with_chain_id(mut self, chain_id: u8) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Returns the size in bytes of the serialized transaction.
+    pub fn size(&self) -> usize {
+        let mut buf = vec![];
+        self.write(&mut buf).expect("writing to vec should succeed");
+        buf.len()
+    }
     pub fn gas_price(&self) -> u64 {
         self.inner.fee().gas_price
     }
@@ -128,6 +279,7 @@ impl Header {
         w: &mut W,
     ) -> io::Result<()> {
         w.write_all(&self.version.to_le_bytes())?;
+        w.write_all(&self.chain_id.to_le_bytes())?;
         w.write_all(&self.height.to_le_bytes())?;
         w.write_all(&self.timestamp.to_le_bytes())?;
         w.write_all(&self.prev_block_hash)?;
@@ -143,11 +295,16 @@ impl Header {
         self.prev_block_cert.write(w)?;
         self.failed_iterations.write(w)?;
 
+        if self.version >= HEADER_VERSION_EXTENSIBLE {
+            self.extensions.write(w)?;
+        }
+
         Ok(())
     }
 
     pub(crate) fn unmarshal_hashable<R: Read>(r: &mut R) -> io::Result<Self> {
         let version = Self::read_u8(r)?;
+        let chain_id = Self::read_u8(r)?;
         let height = Self::read_u64_le(r)?;
         let timestamp = Self::read_u64_le(r)?;
 
@@ -163,8 +320,15 @@ impl Header {
         let prev_block_cert = Certificate::read(r)?;
         let failed_iterations = IterationsInfo::read(r)?;
 
+        let extensions = if version >= HEADER_VERSION_EXTENSIBLE {
+            HeaderExtensions::read(r)?
+        } else {
+            HeaderExtensions::default()
+        };
+
         Ok(Header {
             version,
+            chain_id,
             height,
             timestamp,
             gas_limit,
@@ -179,10 +343,137 @@ impl Header {
             cert: Default::default(),
             prev_block_cert,
             failed_iterations,
+            extensions,
         })
     }
 }
 
+/// An optional header field, identified by its tag.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderField {
+    pub tag: u16,
+    pub data: Vec<u8>,
+}
+
+/// Optional fields of a header.
+///
+/// Fields with a tag unknown to this node are kept as they are, so that
+/// re-encoding a header always yields the same bytes, and thus the same
+/// hash.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderExtensions(pub(crate) Vec<HeaderField>);
+
+impl HeaderExtensions {
+    /// Returns the data of the field with the given tag, if any.
+    pub fn get(&self, tag: u16) -> Option<&[u8]> {
+        self.0
+            .iter()
+            .find(|f| f.tag == tag)
+            .map(|f| f.data.as_slice())
+    }
+
+    /// Sets the data of the field with the given tag, replacing any
+    /// previous value.
+    pub fn set(&mut self, tag: u16, data: Vec<u8>) {
+        match self.0.iter_mut().find(|f| f.tag == tag) {
+            Some(field) => field.data = data,
+            None => self.0.push(HeaderField { tag, data }),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &HeaderField> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Size limits of transactions and blocks enforced by the protocol.
+pub const SIZE_LIMITS: SizeLimits = SizeLimits {
+    max_tx_bytes: 1024 * 1024,
+    max_block_txs: 1000,
+    max_block_bytes: 8 * 1024 * 1024,
+};
+
+/// Size limits enforced on transactions and blocks.
+///
+/// Blocks exceeding any of these limits are invalid, thus the limits are
+/// part of the protocol rather than of the node configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeLimits {
+    /// Maximum size in bytes of a serialized transaction
+    pub max_tx_bytes: usize,
+    /// Maximum number of transactions in a block
+    pub max_block_txs: usize,
+    /// Maximum cumulative size in bytes of the serialized transactions of a
+    /// block
+    pub max_block_bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeLimitError {
+    /// Transaction size exceeding `max_tx_bytes` (size, max)
+    TxTooLarge(usize, usize),
+    /// Transactions count exceeding `max_block_txs` (count, max)
+    TooManyTxs(usize, usize),
+    /// Transactions size exceeding `max_block_bytes` (size, max)
+    BlockTooLarge(usize, usize),
+}
+
+impl std::fmt::Display for SizeLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TxTooLarge(size, max) => {
+                write!(f, "transaction of {size} bytes exceeds {max} bytes")
+            }
+            Self::TooManyTxs(count, max) => {
+                write!(f, "block of {count} transactions exceeds {max}")
+            }
+            Self::BlockTooLarge(size, max) => {
+                write!(f, "block of {size} bytes exceeds {max} bytes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SizeLimitError {}
+
+impl SizeLimits {
+    /// Checks a transaction of the given serialized size.
+    pub fn check_tx_size(&self, size: usize) -> Result<(), SizeLimitError> {
+        if size > self.max_tx_bytes {
+            return Err(SizeLimitError::TxTooLarge(size, self.max_tx_bytes));
+        }
+        Ok(())
+    }
+
+    /// Checks the transactions of a block.
+    pub fn check_block(&self, blk: &Block) -> Result<(), SizeLimitError> {
+        let count = blk.txs().len();
+        if count > self.max_block_txs {
+            return Err(SizeLimitError::TooManyTxs(count, self.max_block_txs));
+        }
+
+        let mut size = 0;
+        for tx in blk.txs() {
+            let tx_size = tx.size();
+            self.check_tx_size(tx_size)?;
+            size += tx_size;
+        }
+
+        if size > self.max_block_bytes {
+            return Err(SizeLimitError::BlockTooLarge(
+                size,
+                self.max_block_bytes,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 impl Block {
     /// Creates a new block and calculates block hash, if missing.
     pub fn new(header: Header, txs: Vec<Transaction>) -> io::Result<Self> {
@@ -324,7 +615,10 @@ impl Eq for Transaction {}
 
 impl PartialEq<Self> for SpentTransaction {
     fn eq(&self, other: &Self) -> bool {
-        self.inner == other.inner && self.gas_spent == other.gas_spent
+        self.inner == other.inner
+            && self.gas_spent == other.gas_spent
+            && self.events == other.events
+            && self.call_gas == other.call_gas
     }
 }
 
@@ -394,6 +688,14 @@ pub mod faker {
     use hex;
     use rand::Rng;
 
+    impl<T> Dummy<T> for HeaderExtensions {
+        /// Creates empty optional fields, as they would be lost when
+        /// encoding a random legacy header.
+        fn dummy_with_rng<R: Rng + ?Sized>(_config: &T, _rng: &mut R) -> Self {
+            Self::default()
+        }
+    }
+
     impl<T> Dummy<T> for Block {
         /// Creates a block with 3 transactions and random header.
         fn dummy_with_rng<R: Rng + ?Sized>(_config: &T, rng: &mut R) -> Self {
@@ -421,7 +723,17 @@ pub mod faker {
                 inner: tx,
                 block_height: 0,
                 gas_spent: 3,
+                gas_price: 1,
                 err: Some("error".to_string()),
+                events: vec![ContractEvent {
+                    source: [1; 32],
+                    topic: "topic".to_string(),
+                    data: vec![1, 2, 3],
+                }],
+                call_gas: vec![CallGas {
+                    contract: [2; 32],
+                    gas_spent: 3,
+                }],
             }
         }
     }
@@ -6,11 +6,22 @@
 
 pub mod bls;
 pub mod encoding;
+pub mod error;
+pub mod keystore;
 pub mod ledger;
 pub mod message;
 
+use std::cell::RefCell;
 use std::io::{self, Read, Write};
 
+/// Capacity above which the scratch buffer is released once used
+const MAX_SCRATCH_CAPACITY: usize = 4 * 1024 * 1024;
+
+thread_local! {
+    /// Buffer the length-prefixed fields are read into, reused across reads
+    static SCRATCH: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StepName {
     Proposal = 0,
@@ -73,4 +84,27 @@ pub trait Serializable {
 
         Ok(buf)
     }
+
+    /// Reads a length-prefixed field, handing it to `f` as a borrowed slice.
+    ///
+    /// The field is read into a per-thread scratch buffer, sparing an
+    /// allocation for fields that are decoded right away.
+    fn read_var_le_with<R: Read, T, F>(r: &mut R, f: F) -> io::Result<T>
+    where
+        F: FnOnce(&[u8]) -> io::Result<T>,
+    {
+        let len = Self::read_u32_le(r)? as usize;
+
+        // Taken out of the cell, for `f` to be free to read nested fields
+        let mut buf = SCRATCH.with(|scratch| scratch.take());
+        buf.resize(len, 0);
+        let res = r.read_exact(&mut buf).and_then(|_| f(&buf));
+
+        if buf.capacity() <= MAX_SCRATCH_CAPACITY {
+            buf.clear();
+            SCRATCH.with(|scratch| scratch.replace(buf));
+        }
+
+        res
+    }
 }
@@ -15,7 +15,7 @@ use std::cmp::Ordering;
 use std::io::{self, Read, Write};
 use std::net::SocketAddr;
 
-use async_channel::TrySendError;
+use async_channel::{TryRecvError, TrySendError};
 
 use self::payload::{Candidate, Ratification, Validation};
 
@@ -46,6 +46,8 @@ pub struct Message {
     pub payload: Payload,
 
     pub metadata: Option<Metadata>,
+    /// Peer the message is sent to, instead of being broadcast
+    pub recipient: Option<SocketAddr>,
 }
 
 impl Message {
@@ -68,11 +70,31 @@ impl Message {
         };
         Some(signer)
     }
+
+    /// Verifies the signature of a consensus step message. Other messages
+    /// carry no signature to verify.
+    pub fn verify_signature(&self) -> Result<(), dusk_bls12_381_sign::Error> {
+        match &self.payload {
+            Payload::Candidate(c) => c.verify_signature(),
+            Payload::Validation(v) => v.verify_signature(),
+            Payload::Ratification(r) => r.verify_signature(),
+            Payload::GetVotes(g) => g.verify_signature(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns whether the signature of the message has already been verified
+    /// by the node, upon receipt.
+    pub fn is_sig_verified(&self) -> bool {
+        self.metadata.as_ref().is_some_and(|md| md.sig_verified)
+    }
+
     pub fn get_step(&self) -> u16 {
         match &self.payload {
             Payload::Candidate(c) => c.get_step(),
             Payload::Validation(v) => v.get_step(),
             Payload::Ratification(r) => r.get_step(),
+            Payload::GetVotes(p) => p.step,
             Payload::Quorum(_) => {
                 // This should be removed in future
                 StepName::Ratification.to_step(self.header.iteration)
@@ -88,6 +110,8 @@ impl Message {
 pub struct Metadata {
     pub height: u8,
     pub src_addr: SocketAddr,
+    /// Whether the signature of the payload has been verified upon receipt
+    pub sig_verified: bool,
 }
 
 impl Serializable for Message {
@@ -107,6 +131,7 @@ impl Serializable for Message {
             Payload::GetBlocks(p) => p.write(w),
             Payload::GetData(p) => p.write(w),
             Payload::Ratification(p) => p.write(w),
+            Payload::GetVotes(p) => p.write(w),
             Payload::Empty | Payload::ValidationResult(_) => Ok(()), /* internal message, not sent on the wire */
         }
     }
@@ -148,6 +173,9 @@ impl Serializable for Message {
                 Message::new_get_mempool(payload::GetMempool::read(r)?)
             }
             Topics::GetInv => Message::new_inv(payload::Inv::read(r)?),
+            Topics::GetVotes => {
+                Message::new_get_votes(payload::GetVotes::read(r)?)
+            }
             Topics::Unknown => {
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidData,
@@ -228,6 +256,16 @@ impl Message {
         }
     }
 
+    /// Creates topics.GetVotes message
+    pub fn new_get_votes(p: payload::GetVotes) -> Message {
+        Self {
+            header: p.header.clone(),
+            topic: Topics::GetVotes,
+            payload: Payload::GetVotes(p),
+            ..Default::default()
+        }
+    }
+
     /// Creates topics.Inv (inventory) message
     pub fn new_inv(p: payload::Inv) -> Message {
         Self {
@@ -299,6 +337,7 @@ impl Message {
 #[derive(Default, Clone, PartialEq, Eq)]
 #[cfg_attr(any(feature = "faker", test), derive(fake::Dummy))]
 pub struct ConsensusHeader {
+    pub chain_id: u8,
     pub prev_block_hash: Hash,
     pub round: u64,
     pub iteration: u8,
@@ -307,6 +346,7 @@ pub struct ConsensusHeader {
 impl std::fmt::Debug for ConsensusHeader {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ConsensusHeader")
+            .field("chain_id", &self.chain_id)
             .field("prev_block_hash", &to_str(&self.prev_block_hash))
             .field("round", &self.round)
             .field("iteration", &self.iteration)
@@ -316,6 +356,7 @@ impl std::fmt::Debug for ConsensusHeader {
 
 impl Serializable for ConsensusHeader {
     fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&[self.chain_id])?;
         w.write_all(&self.prev_block_hash)?;
         w.write_all(&self.round.to_le_bytes())?;
         w.write_all(&[self.iteration])?;
@@ -327,11 +368,13 @@ impl Serializable for ConsensusHeader {
     where
         Self: Sized,
     {
+        let chain_id = Self::read_u8(r)?;
         let prev_block_hash = Self::read_bytes(r)?;
         let round = Self::read_u64_le(r)?;
         let iteration = Self::read_u8(r)?;
 
         Ok(ConsensusHeader {
+            chain_id,
             prev_block_hash,
             round,
             iteration,
@@ -374,6 +417,7 @@ pub enum Payload {
     GetBlocks(payload::GetBlocks),
     GetData(payload::GetData),
     CandidateResp(Box<payload::GetCandidateResp>),
+    GetVotes(payload::GetVotes),
 
     // Internal messages payload
     /// Result message passed from Validation step to Ratification step
@@ -726,6 +770,74 @@ pub mod payload {
         }
     }
 
+    /// Request for the votes of a step cast by specific committee members,
+    /// sent by a node short of quorum near the step timeout.
+    ///
+    /// The request is signed by the requester, as only the members of the
+    /// step committee are served.
+    #[derive(Debug, Clone, Default)]
+    #[cfg_attr(any(feature = "faker", test), derive(Eq, PartialEq))]
+    pub struct GetVotes {
+        pub header: ConsensusHeader,
+        pub step: u16,
+        /// Bitset of the positions in the step committee of the members whose
+        /// votes are requested
+        pub signers: u64,
+        pub sign_info: SignInfo,
+    }
+
+    impl GetVotes {
+        const SIGN_SEED: &'static [u8] = &[4u8];
+
+        pub fn signable(&self) -> Vec<u8> {
+            let mut signable = self.header.signable();
+            signable.extend_from_slice(Self::SIGN_SEED);
+            signable.extend_from_slice(&self.step.to_le_bytes());
+            signable.extend_from_slice(&self.signers.to_le_bytes());
+            signable
+        }
+
+        pub fn verify_signature(
+            &self,
+        ) -> Result<(), dusk_bls12_381_sign::Error> {
+            use dusk_bytes::Serializable;
+
+            let signature = self.sign_info.signature.inner();
+            let sig = dusk_bls12_381_sign::Signature::from_bytes(signature)?;
+            let pk =
+                dusk_bls12_381_sign::APK::from(self.sign_info.signer.inner());
+            pk.verify(&sig, &self.signable())
+        }
+    }
+
+    impl Serializable for GetVotes {
+        fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+            self.header.write(w)?;
+            w.write_all(&self.step.to_le_bytes())?;
+            w.write_all(&self.signers.to_le_bytes())?;
+            self.sign_info.write(w)?;
+
+            Ok(())
+        }
+
+        fn read<R: Read>(r: &mut R) -> io::Result<Self>
+        where
+            Self: Sized,
+        {
+            let header = ConsensusHeader::read(r)?;
+            let step = Self::read_u16_le(r)?;
+            let signers = Self::read_u64_le(r)?;
+            let sign_info = SignInfo::read(r)?;
+
+            Ok(GetVotes {
+                header,
+                step,
+                signers,
+                sign_info,
+            })
+        }
+    }
+
     #[derive(Debug, Clone, Default)]
     pub struct GetMempool {}
 
@@ -910,6 +1022,7 @@ pub enum Topics {
     GetMempool = 13, // NB: This is aliased as Mempool in the golang impl
     GetInv = 14,     // NB: This is aliased as Inv in the golang impl
     GetCandidate = 46,
+    GetVotes = 47,
 
     // Fire-and-forget messaging
     Tx = 10,
@@ -950,6 +1063,7 @@ impl From<u8> for Topics {
         map_topic!(v, Topics::GetInv);
         map_topic!(v, Topics::GetCandidateResp);
         map_topic!(v, Topics::GetCandidate);
+        map_topic!(v, Topics::GetVotes);
         map_topic!(v, Topics::Candidate);
         map_topic!(v, Topics::Validation);
         map_topic!(v, Topics::Ratification);
@@ -996,6 +1110,10 @@ impl<M: Clone> AsyncQueue<M> {
     pub fn recv(&self) -> async_channel::Recv<'_, M> {
         self.receiver.recv()
     }
+
+    pub fn try_recv(&self) -> Result<M, TryRecvError> {
+        self.receiver.try_recv()
+    }
 }
 
 pub trait StepMessage {
@@ -1147,6 +1265,7 @@ mod tests {
     #[test]
     fn test_serialize() {
         let consensus_header = ConsensusHeader {
+            chain_id: 2,
             iteration: 1,
             prev_block_hash: [2; 32],
             round: 4,
@@ -1155,6 +1274,7 @@ mod tests {
 
         let header = ledger::Header {
             version: 3,
+            chain_id: 2,
             height: 1888881,
             timestamp: 123456789,
             gas_limit: 111111111,
@@ -1223,6 +1343,39 @@ mod tests {
                 ratification: ledger::StepVotes::new([2; 48], 98765),
             },
         });
+
+        assert_serialize(payload::GetVotes {
+            header: consensus_header.clone(),
+            step: 5,
+            signers: 0b1010,
+            sign_info: sign_info.clone(),
+        });
+    }
+
+    #[test]
+    fn test_get_votes_signature() {
+        use dusk_bls12_381_sign::{PublicKey as BlsPublicKey, SecretKey};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let sk = SecretKey::random(&mut StdRng::seed_from_u64(0xbeef));
+        let pk = BlsPublicKey::from(&sk);
+
+        let mut req = payload::GetVotes {
+            header: ConsensusHeader::default(),
+            step: 4,
+            signers: 0b11,
+            sign_info: SignInfo::default(),
+        };
+        req.sign_info.signature =
+            sk.sign(&pk, &req.signable()).to_bytes().into();
+        req.sign_info.signer = bls::PublicKey::new(pk);
+        assert!(req.verify_signature().is_ok());
+
+        // The requested votes are part of the signature
+        let mut tampered = req.clone();
+        tampered.signers = u64::MAX;
+        assert!(tampered.verify_signature().is_err());
     }
 
     fn assert_serialize<S: Serializable + PartialEq + core::fmt::Debug>(v: S) {
@@ -5,9 +5,12 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use dusk_consensus::commons::RoundUpdate;
+use dusk_consensus::config::ConsensusParams;
+use dusk_consensus::signer::LocalSigner;
 use node::chain;
 
 use criterion::async_executor::FuturesExecutor;
@@ -22,8 +25,10 @@ use dusk_bls12_381_sign::{
 };
 use dusk_bytes::Serializable;
 use dusk_consensus::user::{
-    cluster::Cluster, committee::Committee, provisioners::Provisioners,
-    sortition::Config as SortitionConfig,
+    cluster::Cluster,
+    committee::Committee,
+    provisioners::Provisioners,
+    sortition::{Config as SortitionConfig, Exclusion},
 };
 use node_data::message::payload::{
     QuorumType, RatificationResult, ValidationResult, Vote,
@@ -49,32 +54,44 @@ fn create_step_votes(
 
     let generator = provisioners.get_generator(iteration, seed, round);
 
-    let sortition_config =
-        SortitionConfig::new(seed, round, iteration, step, Some(generator));
+    let sortition_config = SortitionConfig::new(
+        seed,
+        round,
+        iteration,
+        step,
+        Exclusion::from_generator(generator),
+        &ConsensusParams::default(),
+    );
 
     let committee = Committee::new(provisioners, &sortition_config);
 
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime to be created");
+
     let mut signatures = vec![];
     let mut cluster = Cluster::<PublicKey>::default();
     for (pk, sk) in keys.iter() {
         if let Some(weight) = committee.votes_for(pk) {
             let vote = vote.clone();
+            let signer = LocalSigner::new(*sk, pk.clone());
             let ru = RoundUpdate::new(
-                pk.clone(),
-                *sk,
+                Arc::new(signer),
                 mrb_header,
                 HashMap::default(),
+                ConsensusParams::default(),
             );
             let sig = match step {
                 StepName::Validation => {
-                    dusk_consensus::build_validation_payload(
+                    rt.block_on(dusk_consensus::build_validation_payload(
                         vote, &ru, iteration,
-                    )
+                    ))
+                    .expect("signing to succeed")
                     .sign_info
                     .signature
                 }
                 StepName::Ratification => {
-                    dusk_consensus::build_ratification_payload(
+                    rt.block_on(dusk_consensus::build_ratification_payload(
                         &ru,
                         iteration,
                         &ValidationResult::new(
@@ -82,7 +99,8 @@ fn create_step_votes(
                             vote,
                             QuorumType::Valid,
                         ),
-                    )
+                    ))
+                    .expect("signing to succeed")
                     .sign_info
                     .signature
                 }
@@ -162,9 +180,11 @@ pub fn verify_block_cert(c: &mut Criterion) {
                 move |b| {
                     b.to_async(FuturesExecutor).iter(|| async {
                         chain::verify_block_cert(
+                            mrb_header.chain_id,
                             [0u8; 32],
                             mrb_header.seed,
                             &provisioners,
+                            &ConsensusParams::default(),
                             mrb_header.height + 1,
                             &cert,
                             iteration,
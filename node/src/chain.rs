@@ -5,33 +5,57 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 mod acceptor;
+pub mod anchor;
+pub mod checkpoint;
 mod consensus;
+pub mod epoch;
 mod fallback;
+pub mod finality;
+pub mod fork_choice;
 mod fsm;
 mod genesis;
 
 mod header_validation;
 mod metrics;
-
-use self::acceptor::Acceptor;
+pub mod performance;
+mod provisioners_snapshot;
+mod quorum_dedup;
+pub mod remote_signer;
+mod revert;
+pub mod schedule;
+mod signature_pool;
+mod validation_cache;
+mod watchdog;
+
+use self::acceptor::{Acceptor, RevertTarget};
+use self::anchor::Anchors;
+use self::checkpoint::Checkpoint;
 use self::fsm::SimpleFSM;
-use crate::database::rocksdb::MD_HASH_KEY;
+use self::quorum_dedup::QuorumDedup;
+use self::remote_signer::RemoteSignerConfig;
+use self::revert::RevertRequest;
+pub use self::revert::{ChainReverts, RevertOutcome};
+use self::signature_pool::SignaturePool;
+pub use self::watchdog::StaleTipAlerts;
+use self::watchdog::StaleTipWatchdog;
+use crate::database::rocksdb::{MD_HASH_KEY, MD_STALE_TIP, MD_STATE_ROOT_KEY};
 use crate::database::{Ledger, Metadata};
 use crate::{database, vm, Network};
 use crate::{LongLivedService, Message};
 use anyhow::Result;
 use async_trait::async_trait;
 use dusk_consensus::commons::ConsensusError;
+use dusk_consensus::config::ConsensusParams;
 pub use header_validation::verify_block_cert;
 use node_data::ledger::{to_str, BlockWithLabel, Label};
 use node_data::message::AsyncQueue;
 use node_data::message::{Payload, Topics};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
 
 use tokio::time::{sleep_until, Instant};
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 
 const TOPICS: &[u8] = &[
     Topics::Block as u8,
@@ -39,16 +63,42 @@ const TOPICS: &[u8] = &[
     Topics::Validation as u8,
     Topics::Ratification as u8,
     Topics::Quorum as u8,
+    Topics::GetVotes as u8,
 ];
 
-const ACCEPT_BLOCK_TIMEOUT_SEC: Duration = Duration::from_secs(20);
+pub(crate) const ACCEPT_BLOCK_TIMEOUT_SEC: Duration = Duration::from_secs(20);
 const HEARTBEAT_SEC: Duration = Duration::from_secs(1);
+/// Time without accepting any block, while peers are ahead, after which the
+/// tip is considered stale and a resync is triggered
+const STALE_TIP_TIMEOUT: Duration = Duration::from_secs(60);
 
 pub struct ChainSrv<N: Network, DB: database::DB, VM: vm::VMExecution> {
     /// Inbound wire messages queue
     inbound: AsyncQueue<Message>,
     keys_path: String,
     acceptor: Option<Arc<RwLock<Acceptor<N, DB, VM>>>>,
+
+    /// Keys allowed to certify blocks when consensus stalls, if emergency
+    /// mode is enabled
+    checkpoint: Option<Checkpoint>,
+
+    /// Network this node is part of
+    chain_id: u8,
+
+    /// Remote service signing the consensus messages in place of the local
+    /// consensus keys, if any
+    remote_signer: Option<RemoteSignerConfig>,
+
+    /// Committee sizes and quorum thresholds of the network
+    params: ConsensusParams,
+
+    /// Endpoints the state anchors are published to and verified against,
+    /// if any
+    anchors: Option<Anchors>,
+
+    /// Reverts requested by the node operator
+    reverts: ChainReverts,
+    revert_requests: mpsc::Receiver<RevertRequest>,
 }
 
 #[async_trait]
@@ -61,23 +111,36 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
         db: Arc<RwLock<DB>>,
         vm: Arc<RwLock<VM>>,
     ) -> anyhow::Result<()> {
-        let mrb = Self::load_most_recent_block(db.clone(), vm.clone()).await?;
+        let mrb =
+            Self::load_most_recent_block(db.clone(), vm.clone(), self.chain_id)
+                .await?;
 
-        let state_hash = mrb.inner().header().state_hash;
-        let provisioners_list = vm.read().await.get_provisioners(state_hash)?;
+        // The blocks accepted so far are checked against the anchors as well,
+        // since they may have been synced before the anchors were pinned
+        if let Some(anchors) = &self.anchors {
+            let pinned = anchors.fetch().await?;
+            Anchors::check_ledger(&pinned, &db).await?;
+
+            tokio::spawn(anchors.clone().refresh(db.clone()));
+        }
 
         // Initialize Acceptor
         let acc = Acceptor::init_consensus(
             &self.keys_path,
             mrb,
-            provisioners_list,
             db,
             network.clone(),
             vm.clone(),
+            self.checkpoint.clone(),
+            self.remote_signer.clone(),
+            self.params,
         )
-        .await?;
+        .await?
+        .with_anchors(self.anchors.clone());
 
-        self.acceptor = Some(Arc::new(RwLock::new(acc)));
+        let acc = Arc::new(RwLock::new(acc));
+        tokio::spawn(Acceptor::verify_provisioners(acc.clone()));
+        self.acceptor = Some(acc);
 
         Ok(())
     }
@@ -85,7 +148,7 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
     async fn execute(
         &mut self,
         network: Arc<RwLock<N>>,
-        _db: Arc<RwLock<DB>>,
+        db: Arc<RwLock<DB>>,
         _vm: Arc<RwLock<VM>>,
     ) -> anyhow::Result<usize> {
         // Register routes
@@ -111,6 +174,19 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
         let mut timeout = Self::next_timeout();
         let mut heartbeat = Instant::now().checked_add(HEARTBEAT_SEC).unwrap();
 
+        // Signatures of the consensus messages are verified off the loop
+        let sig_pool = SignaturePool::default();
+        let verified_chan = sig_pool.verified();
+
+        // Quorum messages are handled once, however many times delivered
+        let mut quorum_dedup = QuorumDedup::default();
+
+        let mut watchdog = StaleTipWatchdog::new(
+            STALE_TIP_TIMEOUT,
+            acc.read().await.get_curr_height().await,
+            Instant::now(),
+        );
+
         // Message loop for Chain context
         loop {
             tokio::select! {
@@ -156,6 +232,10 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
                     let msg = recv?;
                     match &msg.payload {
                         Payload::Block(blk) => {
+                            if let Some(md) = &msg.metadata {
+                                watchdog.on_peer_height(blk.header().height, md.src_addr);
+                            }
+
                            info!(
                                 event = "block received",
                                 src = "wire",
@@ -174,15 +254,34 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
                             }
                         }
 
-                        // Re-route message to the acceptor
+                        // Verify the signature before re-routing the message
+                        // to the acceptor
                         Payload::Candidate(_)
                         | Payload::Validation(_)
                         | Payload::Ratification(_) => {
+                            let acc = acc.read().await;
+                            let tip_height = acc.get_curr_height().await;
+                            let provisioners =
+                                acc.provisioners_list.read().await;
+                            sig_pool.submit(msg, tip_height, &provisioners);
+                        },
+                        // Re-route request for missing votes to the acceptor
+                        Payload::GetVotes(_) => {
                             if let Err(e) = acc.read().await.reroute_msg(msg).await {
                                 warn!("msg discarded: {e}");
                             }
                         },
                         Payload::Quorum(payload) => {
+                            if !quorum_dedup.insert(payload) {
+                                debug!(
+                                    event = "quorum msg discarded",
+                                    reason = "duplicated",
+                                    round = payload.header.round,
+                                    iter = payload.header.iteration,
+                                );
+                                continue;
+                            }
+
                             if let Err(e) = acc.read().await.reroute_msg(msg.clone()).await {
                                 warn!("msg discarded: {e}");
                             }
@@ -194,13 +293,41 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
                         _ => warn!("invalid inbound message"),
                     }
                 },
+                // Re-routes messages whose signature has been verified to the acceptor
+                recv = verified_chan.recv() => {
+                    let msg = recv?;
+                    // A message for round R is sent by a peer with a tip at
+                    // height R - 1
+                    if let Some(md) = &msg.metadata {
+                        let height = msg.header.round.saturating_sub(1);
+                        watchdog.on_peer_height(height, md.src_addr);
+                    }
+                    if let Err(e) = acc.read().await.reroute_msg(msg).await {
+                        warn!("msg discarded: {e}");
+                    }
+                },
                 // Re-routes messages originated from Consensus (upper) layer to the network layer.
                 recv = &mut outbound_chan.recv() => {
                     let msg = recv?;
-                    if let Err(e) = network.read().await.broadcast(&msg).await {
+                    let network = network.read().await;
+                    let res = match msg.recipient {
+                        Some(addr) => network.send_to_peer(&msg, addr).await,
+                        None => network.broadcast(&msg).await,
+                    };
+                    if let Err(e) = res {
                         warn!("Unable to re-route message {e}");
                     }
                 },
+                // Handles the reverts requested by the node operator
+                Some(reply) = self.revert_requests.recv() => {
+                    let res = Self::revert_to_finalized(acc).await;
+                    match &res {
+                        Ok(outcome) => info!(event = "chain reverted", ?outcome),
+                        Err(err) => error!(event = "revert failed", ?err),
+                    }
+                    let _ = reply.send(res);
+                    timeout = Self::next_timeout();
+                },
                 // Handles accept_block_timeout event
                 _ = sleep_until(timeout) => {
                     fsm.on_idle(ACCEPT_BLOCK_TIMEOUT_SEC).await;
@@ -212,6 +339,23 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
                         error!(event = "heartbeat_failed", ?err);
                     }
 
+                    let tip_height = acc.read().await.get_curr_height().await;
+                    if let Some(stale) = watchdog.check(tip_height, Instant::now()) {
+                        warn!(
+                            event = "stale tip",
+                            tip_height = stale.tip_height,
+                            peer_height = stale.peer_height,
+                            peer = ?stale.peer,
+                            stalled_secs = stale.stalled_for.as_secs(),
+                            alerts = watchdog.alerts().alerts,
+                        );
+                        fsm.on_stale_tip(stale.peer).await;
+
+                        if let Err(err) = Self::store_stale_tip_alerts(&db, watchdog.alerts()).await {
+                            warn!(event = "stale tip alerts not stored", ?err);
+                        }
+                    }
+
                     heartbeat = Instant::now().checked_add(HEARTBEAT_SEC).unwrap();
                 },
             }
@@ -225,22 +369,99 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
 }
 
 impl<N: Network, DB: database::DB, VM: vm::VMExecution> ChainSrv<N, DB, VM> {
-    pub fn new(keys_path: String) -> Self {
+    pub fn new(
+        keys_path: String,
+        checkpoint: Option<Checkpoint>,
+        chain_id: u8,
+        remote_signer: Option<RemoteSignerConfig>,
+        params: ConsensusParams,
+    ) -> Self {
+        if let Some(checkpoint) = &checkpoint {
+            warn!(
+                event = "emergency mode enabled",
+                max_stalled_rounds = checkpoint.max_stalled_rounds(),
+            );
+        }
+
+        if params != ConsensusParams::default() {
+            warn!(event = "custom consensus params", ?params);
+        }
+
+        let (reverts, revert_requests) = ChainReverts::new();
+
         Self {
             inbound: AsyncQueue::unbounded(),
             keys_path,
             acceptor: None,
+            checkpoint,
+            chain_id,
+            remote_signer,
+            params,
+            anchors: None,
+            reverts,
+            revert_requests,
         }
     }
 
+    /// Returns a handle requesting the chain to be reverted to its last
+    /// finalized state.
+    pub fn reverts(&self) -> ChainReverts {
+        self.reverts.clone()
+    }
+
+    /// Reverts the chain to its last finalized state and restarts the
+    /// consensus on top of it.
+    async fn revert_to_finalized(
+        acc: &RwLock<Acceptor<N, DB, VM>>,
+    ) -> Result<RevertOutcome> {
+        let from = acc.read().await.tip_header().await;
+        let res = acc
+            .read()
+            .await
+            .try_revert(RevertTarget::LastFinalizedState)
+            .await;
+        // The consensus is restarted even on failure, as the revert may have
+        // aborted it
+        acc.write().await.restart_consensus().await;
+        res?;
+
+        let to = acc.read().await.tip_header().await;
+        Ok(RevertOutcome {
+            from_height: from.height,
+            to_height: to.height,
+            from_state_root: from.state_hash,
+            to_state_root: to.state_hash,
+        })
+    }
+
+    /// Publishes the state anchors and verifies the blocks against them.
+    pub fn with_anchors(mut self, anchors: Option<Anchors>) -> Self {
+        self.anchors = anchors;
+        self
+    }
+
+    /// Persists the stale tip alerts for monitoring.
+    async fn store_stale_tip_alerts(
+        db: &Arc<RwLock<DB>>,
+        alerts: &StaleTipAlerts,
+    ) -> Result<()> {
+        let alerts = serde_json::to_vec(alerts)?;
+        db.read().await.update(|t| t.op_write(MD_STALE_TIP, alerts))
+    }
+
     /// Load both most recent and last_finalized blocks from persisted ledger.
     ///
     /// Panics
     ///
     /// If register entry is read but block is not found.
+    ///
+    /// Errors
+    ///
+    /// If the persisted ledger belongs to a network other than `chain_id`.
     async fn load_most_recent_block(
         db: Arc<RwLock<DB>>,
         vm: Arc<RwLock<VM>>,
+        chain_id: u8,
     ) -> Result<BlockWithLabel> {
         let stored_block = db.read().await.update(|t| {
             Ok(t.op_read(MD_HASH_KEY)?.and_then(|mrb_hash| {
@@ -265,7 +486,7 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution> ChainSrv<N, DB, VM> {
                 // Lack of register record means the loaded database is
                 // either malformed or empty.
                 let state = vm.read().await.get_state_root()?;
-                let genesis_blk = genesis::generate_state(state);
+                let genesis_blk = genesis::generate_state(state, chain_id);
                 db.write().await.update(|t| {
                     // Persist genesis block
                     t.store_block(genesis_blk.header(), &[], Label::Final)
@@ -275,8 +496,43 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution> ChainSrv<N, DB, VM> {
             }
         };
 
+        // The ledger may be ahead of the VM if the node stopped before the
+        // state of the blocks synced last was committed. These are deleted,
+        // to be downloaded again.
+        // NB. After restart, state_root returned by VM is always the last
+        // finalized one.
+        let state_root = vm.read().await.get_state_root()?;
+        info!(
+            event = "VM state loaded",
+            state_root = hex::encode(state_root),
+        );
+        let block = if block.inner().header().state_hash != state_root {
+            warn!(
+                event = "ledger ahead of the VM",
+                height = block.inner().header().height,
+                state_root = hex::encode(state_root),
+            );
+            let tip = block.inner().header().height;
+            let (blk, label) = db.read().await.update(|t| {
+                let (blk, label) = acceptor::revert_ledger(t, tip, state_root)?;
+                t.op_write(MD_HASH_KEY, blk.header().hash)?;
+                t.op_write(MD_STATE_ROOT_KEY, blk.header().state_hash)?;
+                Ok((blk, label))
+            })?;
+            BlockWithLabel::new_with_label(blk, label)
+        } else {
+            block
+        };
+
         let block_header = block.inner().header();
 
+        if block_header.chain_id != chain_id {
+            anyhow::bail!(
+                "ledger belongs to chain {}, configured chain is {chain_id}",
+                block_header.chain_id
+            );
+        }
+
         tracing::info!(
             event = "Ledger block loaded",
             height = block_header.height,
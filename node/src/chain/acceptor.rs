@@ -9,25 +9,37 @@ use crate::{vm, Message, Network};
 use anyhow::{anyhow, Result};
 use dusk_consensus::commons::{ConsensusError, TimeoutSet};
 use dusk_consensus::config::{
-    CONSENSUS_ROLLING_FINALITY_THRESHOLD, MAX_STEP_TIMEOUT, MIN_STEP_TIMEOUT,
+    ConsensusParams, CONSENSUS_ROLLING_FINALITY_THRESHOLD, MAX_STEP_TIMEOUT,
+    MIN_STEP_TIMEOUT,
+};
+use dusk_consensus::user::provisioners::{
+    ContextProvisioners, ProvisionerDelta, Provisioners,
 };
-use dusk_consensus::user::provisioners::{ContextProvisioners, Provisioners};
 use node_data::bls::PublicKey;
 use node_data::ledger::{
-    self, to_str, Block, BlockWithLabel, Label, Seed, SpentTransaction,
+    self, to_str, Block, BlockWithLabel, Hash, Label, Seed, SpentTransaction,
 };
 use node_data::message::AsyncQueue;
 use node_data::message::Payload;
 
 use node_data::{Serializable, StepName};
 use stake_contract_types::Unstake;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, LazyLock};
 use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::{debug, info, warn};
+use tracing::{debug, error, info, warn};
 
+use super::anchor::Anchors;
+use super::checkpoint::Checkpoint;
 use super::consensus::Task;
-use crate::chain::header_validation::Validator;
+use super::epoch;
+use super::finality;
+use super::provisioners_snapshot;
+use super::remote_signer::{RemoteSigner, RemoteSignerConfig};
+use crate::chain::header_validation::{
+    verify_block_txs, verify_certs_in_parallel, Validator,
+};
 use crate::chain::metrics::AverageElapsedTime;
 use crate::database::rocksdb::{
     MD_AVG_PROPOSAL, MD_AVG_RATIFICATION, MD_AVG_VALIDATION, MD_HASH_KEY,
@@ -59,6 +71,28 @@ pub(crate) struct Acceptor<N: Network, DB: database::DB, VM: vm::VMExecution> {
     pub(crate) db: Arc<RwLock<DB>>,
     pub(crate) vm: Arc<RwLock<VM>>,
     network: Arc<RwLock<N>>,
+
+    /// Keys allowed to certify blocks in emergency mode
+    pub(crate) checkpoint: Option<Arc<Checkpoint>>,
+
+    /// Committee sizes and quorum thresholds of the network
+    pub(crate) params: ConsensusParams,
+
+    /// State anchors the blocks are verified against and published to
+    anchors: Option<Arc<Anchors>>,
+
+    /// Number of consecutive accept-block timeouts since the last accepted
+    /// block
+    stalled_rounds: u64,
+
+    /// Blocks downloaded while syncing up whose certificates have already
+    /// been verified against the current provisioners, along with whether
+    /// each of their failed iterations has a quorum
+    prevalidated: RwLock<HashMap<Hash, bool>>,
+
+    /// Whether the provisioners were loaded from the snapshot, and are yet
+    /// to be checked against the VM
+    provisioners_from_snapshot: bool,
 }
 
 impl<DB: database::DB, VM: vm::VMExecution, N: Network> Drop
@@ -71,9 +105,13 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Drop
     }
 }
 
+/// Number of times the provisioners loaded from the snapshot are queried to
+/// the VM, while blocks are being accepted, before giving up checking them
+const PROVISIONERS_CHECK_ATTEMPTS: usize = 3;
+
 const STAKE: &str = "stake";
 const UNSTAKE: &str = "unstake";
-const STAKE_CONTRACT: [u8; 32] = stake_contract_id();
+pub(crate) const STAKE_CONTRACT: [u8; 32] = stake_contract_id();
 const fn stake_contract_id() -> [u8; 32] {
     let mut bytes = [0u8; 32];
     bytes[0] = 2;
@@ -119,56 +157,160 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
     pub async fn init_consensus(
         keys_path: &str,
         mrb: BlockWithLabel,
-        provisioners_list: Provisioners,
         db: Arc<RwLock<DB>>,
         network: Arc<RwLock<N>>,
         vm: Arc<RwLock<VM>>,
+        checkpoint: Option<Checkpoint>,
+        remote_signer: Option<RemoteSignerConfig>,
+        params: ConsensusParams,
     ) -> anyhow::Result<Self> {
-        let mrb_height = mrb.inner().header().height;
-        let mrb_state_hash = mrb.inner().header().state_hash;
+        let (provisioners_list, provisioners_from_snapshot) =
+            Self::load_provisioners(&db, &vm, mrb.inner().header()).await?;
+
+        let checkpoint = checkpoint.map(Arc::new);
+
+        let task = match remote_signer {
+            Some(config) => {
+                let signer = RemoteSigner::connect(config).await?;
+                Task::new_with_signer(
+                    Arc::new(signer),
+                    checkpoint.clone(),
+                    params,
+                )
+            }
+            None => Task::new_with_keys(
+                keys_path.to_string(),
+                checkpoint.clone(),
+                params,
+            )?,
+        };
 
-        let mut provisioners_list = ContextProvisioners::new(provisioners_list);
+        let acc = Self {
+            mrb: RwLock::new(mrb),
+            provisioners_list: RwLock::new(provisioners_list),
+            db: db.clone(),
+            vm: vm.clone(),
+            network: network.clone(),
+            task: RwLock::new(task),
+            checkpoint,
+            params,
+            anchors: None,
+            stalled_rounds: 0,
+            prevalidated: RwLock::new(HashMap::new()),
+            provisioners_from_snapshot,
+        };
+
+        Ok(acc)
+    }
 
-        if mrb.inner().header().height > 0 {
+    /// Returns the provisioners of the round following `mrb`, along with
+    /// whether they were taken from the snapshot.
+    ///
+    /// They are taken from the snapshot if it is still valid for `mrb`,
+    /// queried to the VM otherwise.
+    async fn load_provisioners(
+        db: &Arc<RwLock<DB>>,
+        vm: &Arc<RwLock<VM>>,
+        mrb: &ledger::Header,
+    ) -> Result<(ContextProvisioners, bool)> {
+        let snapshot = db
+            .read()
+            .await
+            .view(|t| provisioners_snapshot::load(&t, mrb));
+        match snapshot {
+            Ok(Some(provisioners)) => {
+                info!(
+                    event = "provisioners loaded from snapshot",
+                    height = mrb.height,
+                );
+                return Ok((provisioners, true));
+            }
+            Ok(None) => {}
+            Err(err) => warn!(event = "provisioners snapshot not loaded", ?err),
+        }
+
+        Ok((Self::query_provisioners(db, vm, mrb).await?, false))
+    }
+
+    /// Queries the provisioners of the round following `mrb` to the VM.
+    async fn query_provisioners(
+        db: &Arc<RwLock<DB>>,
+        vm: &Arc<RwLock<VM>>,
+        mrb: &ledger::Header,
+    ) -> Result<ContextProvisioners> {
+        let vm = vm.read().await;
+        let mut provisioners =
+            ContextProvisioners::new(vm.get_provisioners(mrb.state_hash)?);
+
+        if mrb.height > 0 {
             let (prev_header, _) = db
                 .read()
                 .await
-                .view(|t| {
-                    t.fetch_block_header(&mrb.inner().header().prev_block_hash)
-                })?
+                .view(|t| t.fetch_block_header(&mrb.prev_block_hash))?
                 .expect("Previous block to be found");
 
-            let prev_provisioners =
-                vm.read().await.get_provisioners(prev_header.state_hash)?;
-            provisioners_list.set_previous(prev_provisioners);
+            provisioners
+                .set_previous(vm.get_provisioners(prev_header.state_hash)?);
         }
 
-        let acc = Self {
-            mrb: RwLock::new(mrb),
-            provisioners_list: RwLock::new(provisioners_list),
-            db: db.clone(),
-            vm: vm.clone(),
-            network: network.clone(),
-            task: RwLock::new(Task::new_with_keys(keys_path.to_string())?),
-        };
+        Ok(provisioners)
+    }
 
-        // NB. After restart, state_root returned by VM is always the last
-        // finalized one.
-        let state_root = vm.read().await.get_state_root()?;
+    /// Checks the provisioners loaded from the snapshot against the ones of
+    /// the VM, replacing them by the latter.
+    ///
+    /// The VM is queried without holding the acceptor, and again whenever a
+    /// block has been accepted meanwhile.
+    pub(crate) async fn verify_provisioners(acc: Arc<RwLock<Self>>) {
+        if !acc.read().await.provisioners_from_snapshot {
+            return;
+        }
 
-        info!(
-            event = "VM state loaded",
-            state_root = hex::encode(state_root),
-        );
+        for _ in 0..PROVISIONERS_CHECK_ATTEMPTS {
+            let (db, vm, tip) = {
+                let acc = acc.read().await;
+                let tip = acc.mrb.read().await.inner().header().clone();
+                (acc.db.clone(), acc.vm.clone(), tip)
+            };
 
-        // Detect a consistency issue between VM and Ledger states.
-        if mrb_height > 0 && mrb_state_hash != state_root {
-            info!("revert to last finalized state");
-            // Revert to last known finalized state.
-            acc.try_revert(RevertTarget::LastFinalizedState).await?;
+            let expected = match Self::query_provisioners(&db, &vm, &tip).await
+            {
+                Ok(expected) => expected,
+                Err(err) => {
+                    warn!(event = "provisioners not verified", ?err);
+                    return;
+                }
+            };
+
+            let acc = acc.read().await;
+            if acc.mrb.read().await.inner().header().hash != tip.hash {
+                continue;
+            }
+
+            let mut provisioners_list = acc.provisioners_list.write().await;
+            let drifted = epoch::provisioners_hash(provisioners_list.current())
+                != epoch::provisioners_hash(expected.current())
+                || epoch::provisioners_hash(provisioners_list.prev())
+                    != epoch::provisioners_hash(expected.prev());
+            if drifted {
+                warn!(
+                    event = "provisioners snapshot drifted",
+                    height = tip.height
+                );
+                acc.prevalidated.write().await.clear();
+            }
+            *provisioners_list = expected;
+            return;
         }
 
-        Ok(acc)
+        warn!(event = "provisioners not verified", reason = "tip moving");
+    }
+
+    pub(crate) fn with_anchors(mut self, anchors: Option<Anchors>) -> Self {
+        let signer = self.task.get_mut().signer.clone();
+        self.anchors =
+            anchors.map(|anchors| Arc::new(anchors.with_signer(signer)));
+        self
     }
 
     pub async fn spawn_task(&self) {
@@ -210,6 +352,13 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
                     task.main_inbound.try_send(msg)?;
                 }
             }
+            Payload::GetVotes(_) => {
+                // Only a running consensus task holds the votes requested
+                let task = self.task.read().await;
+                if task.is_running() && enable_enqueue {
+                    task.main_inbound.try_send(msg)?;
+                }
+            }
             Payload::Quorum(_) => {
                 let task = self.task.read().await;
                 if !task.is_running() {
@@ -225,6 +374,8 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
         Ok(())
     }
 
+    /// Applies the changes of the stakes made by `blk`, returning whether
+    /// the stakes the consensus runs with changed.
     fn selective_update(
         blk: &Block,
         txs: &[SpentTransaction],
@@ -233,39 +384,60 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
             '_,
             ContextProvisioners,
         >,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         let src = "selective";
         let changed_prov = Self::changed_provisioners(blk, txs)?;
         if changed_prov.is_empty() {
             provisioners_list.remove_previous();
-        } else {
-            let mut new_prov = provisioners_list.current().clone();
-            for change in changed_prov {
-                let is_stake = change.is_stake();
-                info!(event = "provisioner_update", src, ?change);
-                let pk = change.into_public_key();
-                let prov = pk.to_bs58();
-                match vm.get_provisioner(pk.inner())? {
-                    Some(stake) => {
-                        debug!(event = "new_stake", src, prov, ?stake);
-                        let replaced = new_prov.replace_stake(pk, stake);
-                        if replaced.is_none() && !is_stake {
-                            anyhow::bail!("Replaced a not existing stake")
-                        };
-                        debug!(event = "old_stake", src, prov, ?replaced);
-                    }
-                    _ => {
-                        let removed = new_prov.remove_stake(&pk).ok_or(
-                            anyhow::anyhow!("Removed a not existing stake"),
-                        )?;
-                        debug!(event = "removed_stake", src, prov, ?removed);
-                    }
-                }
+            return Ok(false);
+        }
+
+        // Query the stake of each changed provisioner only once
+        let mut changed: Vec<(PublicKey, bool)> = vec![];
+        for change in changed_prov {
+            info!(event = "provisioner_update", src, ?change);
+            let is_stake = change.is_stake();
+            let pk = change.into_public_key();
+            match changed.iter_mut().find(|(p, _)| p == &pk) {
+                Some((_, s)) => *s |= is_stake,
+                None => changed.push((pk, is_stake)),
             }
-            // Update new prov
-            provisioners_list.update_and_swap(new_prov);
         }
-        Ok(())
+
+        let mut deltas = Vec::with_capacity(changed.len());
+        for (pk, is_stake) in changed {
+            let prov = pk.to_bs58();
+            let delta = match vm.get_provisioner(pk.inner())? {
+                Some(stake) if is_stake => {
+                    debug!(event = "new_stake", src, prov, ?stake);
+                    ProvisionerDelta::Stake(pk, stake)
+                }
+                Some(stake) => {
+                    debug!(event = "updated_stake", src, prov, ?stake);
+                    ProvisionerDelta::Update(pk, stake)
+                }
+                None => {
+                    debug!(event = "removed_stake", src, prov);
+                    ProvisionerDelta::Remove(pk)
+                }
+            };
+            deltas.push(delta);
+        }
+
+        // Rewards alone do not change the stakes the consensus runs with
+        let current = provisioners_list.current();
+        let changed = deltas.iter().any(|delta| match delta {
+            ProvisionerDelta::Update(pk, stake) => {
+                current.get_stake(pk).map_or(true, |s| {
+                    s.value() != stake.value()
+                        || s.eligible_since != stake.eligible_since
+                })
+            }
+            _ => true,
+        });
+
+        provisioners_list.apply_deltas(deltas)?;
+        Ok(changed)
     }
 
     fn changed_provisioners(
@@ -364,6 +536,9 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
         // Reset Consensus
         task.abort_with_wait().await;
 
+        // The provisioners are reloaded for the new tip
+        self.prevalidated.write().await.clear();
+
         //  Update register.
         self.db.read().await.update(|t| {
             t.op_write(MD_HASH_KEY, blk.header().hash)?;
@@ -382,12 +557,28 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
         provisioners_list.update(current_prov);
         let previous_prov = vm.get_provisioners(prev_header.state_hash)?;
         provisioners_list.set_previous(previous_prov);
+        self.store_provisioners(blk.header(), &provisioners_list)
+            .await;
 
         *mrb = BlockWithLabel::new_with_label(blk.clone(), label);
 
         Ok(())
     }
 
+    /// Stores the snapshot of the provisioners following the block `header`.
+    async fn store_provisioners(
+        &self,
+        header: &ledger::Header,
+        provisioners_list: &ContextProvisioners,
+    ) {
+        let snapshot = self.db.read().await.update(|t| {
+            provisioners_snapshot::store(t, header, provisioners_list)
+        });
+        if let Err(err) = snapshot {
+            warn!(event = "provisioners snapshot not stored", ?err);
+        }
+    }
+
     fn log_missing_iterations(
         &self,
         provisioners_list: &Provisioners,
@@ -414,23 +605,98 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
 
         let mut mrb = self.mrb.write().await;
         let mut provisioners_list = self.provisioners_list.write().await;
-        let block_time =
-            blk.header().timestamp - mrb.inner().header().timestamp;
+        let block_time = blk
+            .header()
+            .timestamp
+            .saturating_sub(mrb.inner().header().timestamp);
+
+        // A block certified by the checkpoint keys is accepted in place of
+        // a consensus one only if consensus has stalled. The stall is
+        // checked against the block timestamp when verifying the header, so
+        // that syncing nodes apply the same gate. A node running consensus
+        // must also have observed the stall itself.
+        let is_checkpoint = match &self.checkpoint {
+            Some(checkpoint)
+                if Checkpoint::is_checkpoint_cert(&blk.header().cert) =>
+            {
+                if enable_consensus
+                    && self.stalled_rounds < checkpoint.max_stalled_rounds()
+                {
+                    anyhow::bail!(
+                        "checkpoint block received while consensus is running"
+                    );
+                }
+                true
+            }
+            _ => false,
+        };
 
         // Verify Block Header
-        let attested = verify_block_header(
-            self.db.clone(),
-            &mrb.inner().header().clone(),
-            &provisioners_list,
-            blk.header(),
-        )
-        .await?;
+        let attested = if is_checkpoint {
+            verify_checkpoint_header(
+                self.db.clone(),
+                &mrb.inner().header().clone(),
+                &provisioners_list,
+                &self.params,
+                self.checkpoint.as_deref().expect("checkpoint to be set"),
+                blk.header(),
+            )
+            .await?;
+
+            warn!(
+                event = "checkpoint block",
+                height = blk.header().height,
+                hash = to_str(&blk.header().hash),
+                stalled_rounds = self.stalled_rounds,
+            );
+            true
+        } else {
+            let prevalidated =
+                self.prevalidated.write().await.remove(&blk.header().hash);
+            match prevalidated {
+                Some(attested) => {
+                    Validator::new(
+                        self.db.clone(),
+                        mrb.inner().header(),
+                        &provisioners_list,
+                        &self.params,
+                        self.checkpoint.as_deref(),
+                    )
+                    .verify_basic_fields(blk.header())
+                    .await?;
+                    attested
+                }
+                None => {
+                    verify_block_header(
+                        self.db.clone(),
+                        &mrb.inner().header().clone(),
+                        &provisioners_list,
+                        &self.params,
+                        self.checkpoint.as_deref(),
+                        blk.header(),
+                    )
+                    .await?
+                }
+            }
+        };
+
+        verify_block_txs(blk)?;
+
+        // Refuse to sync past a block not matching the pinned anchors
+        if let Some(anchors) = &self.anchors {
+            if let Err(err) = anchors.verify(blk.header()) {
+                error!(event = "anchor mismatch", %err);
+                return Err(err);
+            }
+        }
 
         // Final from rolling
         let mut ffr = false;
 
         // Define new block label
         let label = match (attested, mrb.is_final()) {
+            // Checkpoint blocks are final by definition
+            _ if is_checkpoint => Label::Final,
             (true, true) => Label::Final,
             (false, _) => Label::Accepted,
             (true, _) => {
@@ -469,8 +735,14 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
         {
             let vm = self.vm.write().await;
             let txs = self.db.read().await.update(|t| {
-                let (txs, verification_output) = if blk.is_final() {
-                    vm.finalize(blk.inner())?
+                let (txs, events, verification_output) = if blk.is_final() {
+                    // While syncing up, the state of final blocks is
+                    // committed in batches
+                    if enable_consensus {
+                        vm.finalize(blk.inner())?
+                    } else {
+                        vm.finalize_deferred(blk.inner())?
+                    }
                 } else {
                     vm.accept(blk.inner())?
                 };
@@ -480,6 +752,10 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
 
                 // Store block with updated transactions with Error and GasSpent
                 t.store_block(header, &txs, blk.label())?;
+                t.store_block_events(header.height, &events)?;
+                if blk.is_final() {
+                    finality::record(t, header.height)?;
+                }
 
                 Ok(txs)
             })?;
@@ -503,15 +779,87 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
                 &mut provisioners_list,
             );
 
-            if let Err(e) = selective_update {
-                warn!("Resync provisioners due to {e:?}");
-                let state_hash = blk.inner().header().state_hash;
-                let new_prov = vm.get_provisioners(state_hash)?;
-                provisioners_list.update_and_swap(new_prov)
+            let changed = match selective_update {
+                Ok(changed) => changed,
+                Err(e) => {
+                    warn!("Resync provisioners due to {e:?}");
+                    let state_hash = blk.inner().header().state_hash;
+                    let new_prov = vm.get_provisioners(state_hash)?;
+                    provisioners_list.update_and_swap(new_prov);
+                    true
+                }
+            };
+
+            // Certificates verified in advance are only valid as long as the
+            // stakes do not change
+            if changed {
+                self.prevalidated.write().await.clear();
+            }
+
+            // Spares the queries of the provisioners on restart
+            if changed || epoch::is_last_block(header.height) {
+                self.store_provisioners(header, &provisioners_list).await;
+            }
+
+            // The blocks of the epoch are read off the accept path
+            if epoch::is_last_block(header.height) {
+                let db = self.db.clone();
+                let height = header.height;
+                let provisioners = provisioners_list.to_current();
+                tokio::spawn(async move {
+                    let summary = db.read().await.update(|t| {
+                        let summary =
+                            epoch::summarize(t, height, &provisioners)?;
+                        epoch::store(t, &summary)?;
+                        Ok(summary)
+                    });
+
+                    match summary {
+                        Ok(summary) => info!(
+                            event = "epoch summary",
+                            epoch = summary.epoch,
+                            provisioners = summary.provisioners,
+                            total_stake = summary.total_stake,
+                            rewards = summary.rewards,
+                            slashed = summary.slashed,
+                        ),
+                        Err(err) => {
+                            warn!(event = "epoch summary not stored", ?err)
+                        }
+                    }
+                });
+            }
+
+            // Blocks may become final after being accepted, so the anchor
+            // due is published once a later block is final
+            let due = match (&self.anchors, blk.is_final()) {
+                (Some(anchors), true) => {
+                    anchors.due(header.height).map(|height| (anchors, height))
+                }
+                _ => None,
+            };
+            if let Some((anchors, height)) = due {
+                let boundary = match height == header.height {
+                    true => Some(header.clone()),
+                    false => self.db.read().await.view(|t| {
+                        let hash = t.fetch_block_hash_by_height(height)?;
+                        let header = hash
+                            .map(|hash| t.fetch_block_header(&hash))
+                            .transpose()?
+                            .flatten()
+                            .map(|(header, _)| header);
+                        anyhow::Ok(header)
+                    })?,
+                };
+                match boundary {
+                    Some(boundary) => anchors.publish(&boundary),
+                    None => warn!(event = "anchor not published", height),
+                }
             }
 
             // Update most_recent_block
             *mrb = blk;
+            self.stalled_rounds = 0;
 
             anyhow::Ok(())
         }?;
@@ -625,53 +973,19 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
             RevertTarget::LastEpoch => unimplemented!(),
         }?;
 
-        // Delete any block until we reach the target_state_hash, the
-        // VM was reverted to.
-
-        // The blockchain tip (most recent block) after reverting
-        let (blk, label) = self.db.read().await.update(|t| {
-            let mut height = curr_height;
-            while height != 0 {
-                let b = Ledger::fetch_block_by_height(t, height)?
-                    .ok_or_else(|| anyhow::anyhow!("could not fetch block"))?;
-                let h = b.header();
-                let label =
-                    t.fetch_block_label_by_height(h.height)?.ok_or_else(
-                        || anyhow::anyhow!("could not fetch block label"),
-                    )?;
-
-                if h.state_hash == target_state_hash {
-                    return Ok((b, label));
-                }
-
-                info!(
-                    event = "block deleted",
-                    height = h.height,
-                    iter = h.iteration,
-                    label = ?label,
-                    hash = hex::encode(h.hash)
-                );
-
-                // Delete any rocksdb record related to this block
-                t.delete_block(&b)?;
-
-                // Attempt to resubmit transactions back to mempool.
-                // An error here is not considered critical.
-                for tx in b.txs().iter() {
-                    if let Err(e) = Mempool::add_tx(t, tx) {
-                        warn!("failed to resubmit transactions: {e}")
-                    };
-                }
-
-                height -= 1;
-            }
+        self.revert_ledger_to(target_state_hash).await
+    }
 
-            Err(anyhow!("not found"))
-        })?;
+    /// Deletes the blocks following the one whose state is `state_hash`,
+    /// making it the blockchain tip.
+    async fn revert_ledger_to(&self, state_hash: [u8; 32]) -> Result<()> {
+        let curr_height = self.get_curr_height().await;
 
-        if blk.header().state_hash != target_state_hash {
-            return Err(anyhow!("Failed to revert to proper state"));
-        }
+        let (blk, label) = self
+            .db
+            .read()
+            .await
+            .update(|t| revert_ledger(t, curr_height, state_hash))?;
 
         // Update blockchain tip to be the one we reverted to.
         info!(
@@ -684,8 +998,100 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
         self.update_most_recent_block(&blk, label).await
     }
 
+    /// Verifies at once the certificates of consecutive blocks downloaded
+    /// while syncing up, following the tip, so that they are not verified
+    /// again one at a time when accepted.
+    pub(crate) async fn prevalidate_synced_blocks(&self, blocks: &[&Block]) {
+        if blocks.len() < 2 {
+            return;
+        }
+
+        let mrb = self.mrb.read().await;
+        let tip = mrb.inner().header();
+        let provisioners_list = self.provisioners_list.read().await;
+
+        let tip_prev_seed = match tip.height {
+            0 => None,
+            _ => self
+                .db
+                .read()
+                .await
+                .view(|t| t.fetch_block_header(&tip.prev_block_hash))
+                .ok()
+                .flatten()
+                .map(|(header, _)| header.seed),
+        };
+
+        // Blocks left over from a previous window are not verified again
+        let verified: HashSet<_> =
+            self.prevalidated.read().await.keys().copied().collect();
+
+        let headers = blocks.iter().map(|b| b.header().clone()).collect();
+        let start = std::time::Instant::now();
+        let results = verify_certs_in_parallel(
+            tip,
+            tip_prev_seed,
+            headers,
+            |header| verified.contains(&header.hash),
+            &provisioners_list,
+            &self.params,
+        )
+        .await;
+
+        let mut prevalidated = self.prevalidated.write().await;
+        for (blk, res) in blocks.iter().zip(results) {
+            if let Some(attested) = res {
+                prevalidated.insert(blk.header().hash, attested);
+            }
+        }
+
+        debug!(
+            event = "blocks prevalidated",
+            count = prevalidated.len(),
+            dur_ms = start.elapsed().as_millis(),
+        );
+    }
+
+    /// Accepts a block downloaded while syncing up.
+    ///
+    /// Should the block fail to be accepted, the VM may hold the state of a
+    /// block that is not in the ledger. The final blocks whose state was not
+    /// committed yet are then discarded by the VM, and deleted from the
+    /// ledger to be downloaded again.
+    pub(crate) async fn try_accept_synced_block(
+        &mut self,
+        blk: &Block,
+    ) -> anyhow::Result<Label> {
+        let res = self.try_accept_block(blk, false).await;
+
+        if res.is_err() {
+            let state_root = {
+                let vm = self.vm.read().await;
+                vm.discard_deferred();
+                vm.get_state_root()?
+            };
+            let mrb_state_hash =
+                self.mrb.read().await.inner().header().state_hash;
+            if state_root != mrb_state_hash {
+                warn!(
+                    event = "deferred state lost",
+                    state_root = hex::encode(state_root),
+                );
+                self.revert_ledger_to(state_root).await?;
+            }
+        }
+
+        res
+    }
+
     /// Spawns consensus algorithm after aborting currently running one
     pub(crate) async fn restart_consensus(&mut self) {
+        self.prevalidated.write().await.clear();
+
+        if let Err(err) = self.vm.read().await.commit_deferred() {
+            warn!(event = "deferred state not committed", ?err);
+        }
+
         let mut task = self.task.write().await;
         let mrb = self.mrb.read().await;
         let provisioners_list = self.provisioners_list.read().await.clone();
@@ -709,6 +1115,28 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
         );
     }
 
+    /// Registers an accept-block timeout, returning true if consensus is
+    /// now considered stalled and checkpoint blocks can be accepted.
+    pub(crate) fn on_stalled_round(&mut self) -> bool {
+        self.stalled_rounds += 1;
+
+        match &self.checkpoint {
+            Some(checkpoint) => {
+                let stalled =
+                    self.stalled_rounds >= checkpoint.max_stalled_rounds();
+                if stalled {
+                    warn!(
+                        event = "consensus stalled",
+                        stalled_rounds = self.stalled_rounds,
+                        "accepting checkpoint blocks"
+                    );
+                }
+                stalled
+            }
+            None => false,
+        }
+    }
+
     pub(crate) async fn get_curr_height(&self) -> u64 {
         self.mrb.read().await.inner().header().height
     }
@@ -829,8 +1257,131 @@ pub(crate) async fn verify_block_header<DB: database::DB>(
     db: Arc<RwLock<DB>>,
     prev_header: &ledger::Header,
     provisioners: &ContextProvisioners,
+    params: &ConsensusParams,
+    checkpoint: Option<&Checkpoint>,
     header: &ledger::Header,
 ) -> anyhow::Result<bool> {
-    let validator = Validator::new(db, prev_header, provisioners);
+    let validator =
+        Validator::new(db, prev_header, provisioners, params, checkpoint);
     validator.execute_checks(header, false).await
 }
+
+/// Performs verification of a block certified by the checkpoint keys.
+///
+/// The winning certificate and failed iterations are not checked as the
+/// block has not been produced by consensus. The block must instead prove
+/// that consensus has stalled.
+pub(crate) async fn verify_checkpoint_header<DB: database::DB>(
+    db: Arc<RwLock<DB>>,
+    prev_header: &ledger::Header,
+    provisioners: &ContextProvisioners,
+    params: &ConsensusParams,
+    checkpoint: &Checkpoint,
+    header: &ledger::Header,
+) -> anyhow::Result<()> {
+    let validator =
+        Validator::new(db, prev_header, provisioners, params, Some(checkpoint));
+    validator.verify_basic_fields(header).await?;
+    validator.verify_prev_block_cert(header).await?;
+    checkpoint.verify_stall(prev_header, header)?;
+    checkpoint.verify(&header.hash, &header.cert)
+}
+
+/// Deletes the blocks down from `tip_height` until the one whose state is
+/// `state_hash`, which is returned with its label.
+///
+/// The transactions of the deleted blocks are resubmitted to the mempool.
+/// Fails if no such block is found, the transaction being then rolled back.
+pub(crate) fn revert_ledger<T: Ledger + Mempool>(
+    t: &T,
+    tip_height: u64,
+    state_hash: [u8; 32],
+) -> Result<(Block, Label)> {
+    let mut height = tip_height;
+    loop {
+        let b = Ledger::fetch_block_by_height(t, height)?
+            .ok_or_else(|| anyhow::anyhow!("could not fetch block"))?;
+        let h = b.header();
+        let label = t
+            .fetch_block_label_by_height(h.height)?
+            .ok_or_else(|| anyhow::anyhow!("could not fetch block label"))?;
+
+        if h.state_hash == state_hash {
+            return Ok((b, label));
+        }
+        if height == 0 {
+            return Err(anyhow!(
+                "state {} not found in the ledger",
+                hex::encode(state_hash)
+            ));
+        }
+
+        info!(
+            event = "block deleted",
+            height = h.height,
+            iter = h.iteration,
+            label = ?label,
+            hash = hex::encode(h.hash)
+        );
+
+        // Delete any rocksdb record related to this block
+        t.delete_block(&b)?;
+
+        // Attempt to resubmit transactions back to mempool.
+        // An error here is not considered critical.
+        for tx in b.txs().iter() {
+            if let Err(e) = Mempool::add_tx(t, tx) {
+                warn!("failed to resubmit transactions: {e}")
+            };
+        }
+
+        height -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::rocksdb::Backend;
+    use crate::database::DB;
+
+    #[test]
+    fn ledger_reverted_to_vm_state() {
+        let dir = tempdir::TempDir::new("ledger_reverted_to_vm_state").unwrap();
+        let db = Backend::create_or_open(dir.path());
+
+        db.update(|t| {
+            for height in 0..=5u64 {
+                let header = ledger::Header {
+                    height,
+                    hash: [height as u8 + 1; 32],
+                    state_hash: [height as u8 + 10; 32],
+                    ..Default::default()
+                };
+                t.store_block(&header, &[], Label::Final)?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        let exists = |height| {
+            db.view(|t| t.fetch_block_hash_by_height(height))
+                .unwrap()
+                .is_some()
+        };
+
+        // A state the ledger never had leaves it untouched
+        let res = db.update(|t| revert_ledger(t, 5, [0; 32]));
+        assert!(res.is_err());
+        assert!((0..=5).all(exists));
+
+        // Blocks the VM has no state for are deleted, e.g. after a crash
+        // while their state was pending
+        let (blk, label) =
+            db.update(|t| revert_ledger(t, 5, [13; 32])).unwrap();
+        assert_eq!(blk.header().height, 3);
+        assert_eq!(label, Label::Final);
+        assert!((0..=3).all(exists));
+        assert!(!exists(4) && !exists(5));
+    }
+}
@@ -0,0 +1,625 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! State anchors, i.e. finalized blocks and their state roots published out
+//! of band.
+//!
+//! A node may publish the finalized state roots, signed with its consensus
+//! key, to an HTTPS endpoint, or to a file holding the value of a DNS TXT
+//! record for the operator to serve. Fresh nodes may pin the anchors signed
+//! by the keys they trust, fetched from such endpoints, refusing to sync past
+//! a block not matching them. This protects them from long-range attacks,
+//! where old keys are used to forge an alternative history.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use dusk_bytes::Serializable;
+use dusk_consensus::signer::ConsensusSigner;
+use hickory_resolver::TokioAsyncResolver;
+use node_data::bls::PublicKey;
+use node_data::ledger::{to_str, Header};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock as AsyncRwLock;
+use tracing::{error, info, warn};
+
+use crate::database::{self, Ledger};
+
+/// Prefix of the DNS TXT records holding an anchor
+const RECORD_PREFIX: &str = "dusk-anchor=";
+/// Domain separator of the signatures of the anchors
+const SIGN_SEED: &[u8] = b"dusk-anchor";
+
+const DEFAULT_PUBLISH_INTERVAL: u64 = stake_contract_types::EPOCH;
+/// Interval between two fetches of the anchors to verify
+const FETCH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Configuration of the state anchors.
+///
+/// Both the publication and the verification are disabled unless an
+/// endpoint is configured.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Params {
+    /// HTTPS endpoint the anchors are POSTed to
+    pub publish_url: Option<String>,
+    /// File the DNS TXT record of the last anchor is written to
+    pub publish_record_file: Option<PathBuf>,
+    /// Interval, in blocks, between the published anchors
+    pub publish_interval: Option<u64>,
+    /// HTTPS endpoints serving the anchors to verify, as a JSON array
+    pub verify_urls: Vec<String>,
+    /// Domain names whose TXT records hold the anchors to verify
+    pub verify_dns: Vec<String>,
+    /// Base58 encoded BLS public keys of the nodes publishing the anchors to
+    /// verify, required to verify anchors
+    pub verify_keys: Vec<String>,
+}
+
+/// A finalized block, along with its state root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Anchor {
+    pub height: u64,
+    #[serde(with = "hex::serde")]
+    pub hash: [u8; 32],
+    #[serde(with = "hex::serde")]
+    pub state_root: [u8; 32],
+}
+
+/// An anchor, signed by the node publishing it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedAnchor {
+    #[serde(flatten)]
+    pub anchor: Anchor,
+    #[serde(with = "hex::serde")]
+    pub signature: Vec<u8>,
+}
+
+impl Anchor {
+    pub fn from_header(header: &Header) -> Self {
+        Self {
+            height: header.height,
+            hash: header.hash,
+            state_root: header.state_hash,
+        }
+    }
+
+    /// Returns the message signed by the node publishing the anchor.
+    pub fn signable(&self) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(SIGN_SEED.len() + 8 + 32 + 32);
+        msg.extend_from_slice(SIGN_SEED);
+        msg.extend_from_slice(&self.height.to_le_bytes());
+        msg.extend_from_slice(&self.hash);
+        msg.extend_from_slice(&self.state_root);
+        msg
+    }
+}
+
+impl SignedAnchor {
+    /// Returns the value of the DNS TXT record holding the anchor, i.e.
+    /// `dusk-anchor=<height>:<hash>:<state root>:<signature>`, hex encoded.
+    pub fn to_record(&self) -> String {
+        format!(
+            "{RECORD_PREFIX}{}:{}:{}:{}",
+            self.anchor.height,
+            hex::encode(self.anchor.hash),
+            hex::encode(self.anchor.state_root),
+            hex::encode(&self.signature),
+        )
+    }
+
+    /// Parses the value of a DNS TXT record, returning `None` if it does not
+    /// hold an anchor.
+    pub fn from_record(record: &str) -> Option<Result<Self>> {
+        let record = record.strip_prefix(RECORD_PREFIX)?;
+        let parse = || -> Result<Self> {
+            let mut fields = record.split(':');
+            let mut next = || fields.next().ok_or(anyhow!("missing field"));
+            let height = next()?.parse()?;
+            let mut hash = [0u8; 32];
+            hex::decode_to_slice(next()?, &mut hash)?;
+            let mut state_root = [0u8; 32];
+            hex::decode_to_slice(next()?, &mut state_root)?;
+            let signature = hex::decode(next()?)?;
+            if fields.next().is_some() {
+                return Err(anyhow!("trailing field"));
+            }
+            Ok(Self {
+                anchor: Anchor {
+                    height,
+                    hash,
+                    state_root,
+                },
+                signature,
+            })
+        };
+        Some(parse().with_context(|| format!("invalid anchor record {record}")))
+    }
+
+    /// Checks the anchor is signed by one of `keys`.
+    pub fn verify(&self, keys: &[PublicKey]) -> Result<()> {
+        let sig = <[u8; 48]>::try_from(&self.signature[..])
+            .ok()
+            .and_then(|sig| {
+                dusk_bls12_381_sign::Signature::from_bytes(&sig).ok()
+            })
+            .ok_or(anyhow!("invalid anchor signature bytes"))?;
+
+        let msg = self.anchor.signable();
+        if keys.iter().any(|pk| pk.inner().verify(&sig, &msg).is_ok()) {
+            return Ok(());
+        }
+
+        Err(anyhow!(
+            "anchor at height {} not signed by a verification key",
+            self.anchor.height
+        ))
+    }
+}
+
+/// Publishes the anchors, and verifies the blocks against the pinned ones.
+///
+/// Clones share the pinned anchors, refreshed in the background.
+#[derive(Debug, Clone, Default)]
+pub struct Anchors {
+    publish_url: Option<String>,
+    publish_record_file: Option<PathBuf>,
+    publish_interval: u64,
+    verify_urls: Vec<String>,
+    verify_dns: Vec<String>,
+    verify_keys: Vec<PublicKey>,
+    /// Signer of the published anchors
+    signer: Option<Arc<dyn ConsensusSigner>>,
+    /// Height of the last anchor published since startup
+    published: Arc<Mutex<Option<u64>>>,
+    /// Anchors fetched from the verification endpoints, by height
+    pinned: Arc<RwLock<BTreeMap<u64, Anchor>>>,
+}
+
+impl Params {
+    /// Returns `None` if neither the publication nor the verification of the
+    /// anchors are enabled.
+    pub fn into_anchors(self) -> Result<Option<Anchors>> {
+        let publish_interval =
+            self.publish_interval.unwrap_or(DEFAULT_PUBLISH_INTERVAL);
+        if publish_interval == 0 {
+            return Err(anyhow!("anchors publish interval cannot be zero"));
+        }
+
+        let verify_keys = self
+            .verify_keys
+            .iter()
+            .map(|key| {
+                let bytes: [u8; 96] = bs58::decode(key)
+                    .into_vec()?
+                    .try_into()
+                    .map_err(|_| anyhow!("invalid anchors key length"))?;
+                PublicKey::try_from(bytes)
+                    .map_err(|e| anyhow!("invalid anchors key: {e:?}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let anchors = Anchors {
+            publish_url: self.publish_url,
+            publish_record_file: self.publish_record_file,
+            publish_interval,
+            verify_urls: self.verify_urls,
+            verify_dns: self.verify_dns,
+            verify_keys,
+            ..Default::default()
+        };
+
+        if anchors.verifies() && anchors.verify_keys.is_empty() {
+            return Err(anyhow!("anchors cannot be verified without keys"));
+        }
+
+        Ok((anchors.publishes() || anchors.verifies()).then_some(anchors))
+    }
+}
+
+impl Anchors {
+    fn publishes(&self) -> bool {
+        self.publish_url.is_some() || self.publish_record_file.is_some()
+    }
+
+    fn verifies(&self) -> bool {
+        !self.verify_urls.is_empty() || !self.verify_dns.is_empty()
+    }
+
+    /// Signs the published anchors with `signer`.
+    pub(crate) fn with_signer(
+        mut self,
+        signer: Arc<dyn ConsensusSigner>,
+    ) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Returns the pinned anchors, by height.
+    pub fn pinned(&self) -> BTreeMap<u64, Anchor> {
+        self.pinned.read().expect("lock to be acquired").clone()
+    }
+
+    /// Fetches the anchors to verify from all the configured endpoints,
+    /// pinning the ones signed by one of the verification keys, and returns
+    /// the ones newly pinned.
+    ///
+    /// Endpoints that cannot be reached and anchors not properly signed are
+    /// skipped, as whoever can block or spoof them could otherwise keep the
+    /// node from starting. Fails if two signed anchors disagree on the block
+    /// at the same height, since the blocks could not be verified otherwise.
+    pub async fn fetch(&self) -> Result<Vec<Anchor>> {
+        if !self.verifies() {
+            return Ok(vec![]);
+        }
+
+        let mut fetched = vec![];
+        for url in &self.verify_urls {
+            match fetch_https(url).await {
+                Ok(anchors) => fetched.extend(anchors),
+                Err(err) => warn!(event = "anchors not fetched", %url, ?err),
+            }
+        }
+        for name in &self.verify_dns {
+            match fetch_dns(name).await {
+                Ok(anchors) => fetched.extend(anchors),
+                Err(err) => warn!(event = "anchors not fetched", %name, ?err),
+            }
+        }
+
+        let mut pinned = vec![];
+        for signed in fetched {
+            if let Err(err) = signed.verify(&self.verify_keys) {
+                warn!(event = "anchor not pinned", ?err);
+                continue;
+            }
+            if self.pin(signed.anchor)? {
+                pinned.push(signed.anchor);
+            }
+        }
+
+        info!(
+            event = "anchors pinned",
+            count = pinned.len(),
+            last_height = ?self.pinned().keys().last(),
+        );
+
+        Ok(pinned)
+    }
+
+    /// Pins `anchor`, returning whether it was not pinned yet.
+    fn pin(&self, anchor: Anchor) -> Result<bool> {
+        let mut pinned = self.pinned.write().expect("lock to be acquired");
+        match pinned.get(&anchor.height) {
+            Some(p) if p != &anchor => {
+                Err(anyhow!("conflicting anchors at height {}", anchor.height))
+            }
+            Some(_) => Ok(false),
+            None => {
+                pinned.insert(anchor.height, anchor);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Checks the blocks already in the ledger match the given `anchors`,
+    /// since they may have been synced before the anchors were pinned.
+    pub(crate) async fn check_ledger<DB: database::DB>(
+        anchors: &[Anchor],
+        db: &AsyncRwLock<DB>,
+    ) -> Result<()> {
+        for anchor in anchors {
+            let hash = db
+                .read()
+                .await
+                .view(|t| t.fetch_block_hash_by_height(anchor.height))?;
+            if let Some(hash) = hash {
+                verify_hash(anchor, &hash)
+                    .map_err(|e| anyhow!("{e}, the ledger must be resynced"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Refetches the anchors every [`FETCH_INTERVAL`], checking the ones
+    /// newly pinned against the ledger.
+    pub(crate) async fn refresh<DB: database::DB>(
+        self,
+        db: Arc<AsyncRwLock<DB>>,
+    ) {
+        if !self.verifies() {
+            return;
+        }
+
+        loop {
+            tokio::time::sleep(FETCH_INTERVAL).await;
+
+            let checked = match self.fetch().await {
+                Ok(pinned) => Self::check_ledger(&pinned, &db).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = checked {
+                error!(event = "anchor mismatch", ?err);
+            }
+        }
+    }
+
+    /// Checks the block of `header` matches the anchor pinned at its height,
+    /// if any.
+    pub fn verify(&self, header: &Header) -> Result<()> {
+        self.verify_hash(header.height, &header.hash)
+    }
+
+    /// Checks `hash` matches the anchor pinned at `height`, if any.
+    ///
+    /// The hash of a block commits to its state root, thus matching the
+    /// hash is enough.
+    pub fn verify_hash(&self, height: u64, hash: &[u8; 32]) -> Result<()> {
+        match self
+            .pinned
+            .read()
+            .expect("lock to be acquired")
+            .get(&height)
+        {
+            Some(anchor) => verify_hash(anchor, hash),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the height of the anchor to publish once the block at
+    /// `final_height` is final, if any.
+    ///
+    /// Blocks may become final a while after being accepted, so the last
+    /// multiple of the publish interval is returned as soon as it is final,
+    /// unless it was published already.
+    pub fn due(&self, final_height: u64) -> Option<u64> {
+        if !self.publishes() {
+            return None;
+        }
+
+        let height = final_height - final_height % self.publish_interval;
+        let published = self.published.lock().expect("lock to be acquired");
+        match *published {
+            Some(last) if last >= height => None,
+            _ => Some(height),
+        }
+    }
+
+    /// Publishes the anchor of the final block of `header`, signed by the
+    /// node.
+    ///
+    /// The publication runs in the background, failures being logged.
+    pub fn publish(&self, header: &Header) {
+        let Some(signer) = self.signer.clone() else {
+            warn!(event = "anchor not published", reason = "no signer");
+            return;
+        };
+        *self.published.lock().expect("lock to be acquired") =
+            Some(header.height);
+
+        let anchor = Anchor::from_header(header);
+        let record_file = self.publish_record_file.clone();
+        let url = self.publish_url.clone();
+
+        tokio::spawn(async move {
+            let signed = match signer.sign(&anchor.signable()).await {
+                Ok(sig) => SignedAnchor {
+                    anchor,
+                    signature: sig.to_bytes().to_vec(),
+                },
+                Err(err) => {
+                    warn!(event = "anchor not signed", ?err);
+                    return;
+                }
+            };
+
+            if let Some(path) = record_file {
+                let tmp = path.with_extension("tmp");
+                let written = std::fs::write(&tmp, signed.to_record())
+                    .and_then(|_| std::fs::rename(&tmp, &path));
+                if let Err(err) = written {
+                    warn!(event = "anchor not written", ?path, %err);
+                }
+            }
+
+            if let Some(url) = url {
+                match publish_https(&url, &signed).await {
+                    Ok(_) => info!(
+                        event = "anchor published",
+                        height = anchor.height,
+                        hash = to_str(&anchor.hash),
+                    ),
+                    Err(err) => {
+                        warn!(event = "anchor not published", %url, %err)
+                    }
+                }
+            }
+        });
+    }
+}
+
+fn verify_hash(anchor: &Anchor, hash: &[u8; 32]) -> Result<()> {
+    if &anchor.hash != hash {
+        return Err(anyhow!(
+            "block {} at height {} does not match the anchor {} \
+             (state root {})",
+            to_str(hash),
+            anchor.height,
+            to_str(&anchor.hash),
+            to_str(&anchor.state_root),
+        ));
+    }
+    Ok(())
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .https_only(true)
+        .timeout(HTTP_TIMEOUT)
+        .build()?)
+}
+
+async fn publish_https(url: &str, anchor: &SignedAnchor) -> Result<()> {
+    http_client()?
+        .post(url)
+        .json(anchor)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+async fn fetch_https(url: &str) -> Result<Vec<SignedAnchor>> {
+    let anchors = http_client()?
+        .get(url)
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .with_context(|| format!("cannot fetch anchors from {url}"))?
+        .json()
+        .await
+        .with_context(|| format!("invalid anchors from {url}"))?;
+    Ok(anchors)
+}
+
+async fn fetch_dns(name: &str) -> Result<Vec<SignedAnchor>> {
+    let resolver = TokioAsyncResolver::tokio_from_system_conf()?;
+    let records = resolver
+        .txt_lookup(name)
+        .await
+        .with_context(|| format!("cannot resolve anchors of {name}"))?;
+
+    records
+        .iter()
+        .filter_map(|txt| SignedAnchor::from_record(&txt.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use dusk_bls12_381_sign::SecretKey;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn header(height: u64, hash: u8, state_root: u8) -> Header {
+        Header {
+            height,
+            hash: [hash; 32],
+            state_hash: [state_root; 32],
+            ..Default::default()
+        }
+    }
+
+    fn signed(anchor: Anchor, sk: &SecretKey) -> SignedAnchor {
+        let pk = dusk_bls12_381_sign::PublicKey::from(sk);
+        SignedAnchor {
+            anchor,
+            signature: sk.sign(&pk, &anchor.signable()).to_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn blocks_are_verified_against_pinned_anchors() {
+        let sk = SecretKey::random(&mut StdRng::seed_from_u64(1));
+        let pk = PublicKey::new(dusk_bls12_381_sign::PublicKey::from(&sk));
+
+        let anchor = Anchor::from_header(&header(2160, 1, 2));
+        let signed = signed(anchor, &sk);
+        let record = signed.to_record();
+        assert_eq!(
+            SignedAnchor::from_record(&record).unwrap().unwrap(),
+            signed
+        );
+        assert!(SignedAnchor::from_record("v=spf1 -all").is_none());
+        assert!(SignedAnchor::from_record("dusk-anchor=1:00")
+            .unwrap()
+            .is_err());
+
+        let json = serde_json::to_string(&signed).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SignedAnchor>(&json).unwrap(),
+            signed
+        );
+
+        let anchors =
+            Params {
+                verify_dns: vec!["anchors.example.org".into()],
+                verify_keys: vec![
+                    bs58::encode(pk.inner().to_bytes()).into_string()
+                ],
+                ..Default::default()
+            }
+            .into_anchors()
+            .unwrap()
+            .expect("verification to be enabled");
+        assert!(anchors.pin(anchor).unwrap());
+        assert!(!anchors.pin(anchor).unwrap());
+        let forged = Anchor::from_header(&header(2160, 3, 2));
+        assert!(anchors.pin(forged).is_err());
+
+        anchors.verify(&header(2160, 1, 2)).unwrap();
+        anchors.verify(&header(2161, 3, 3)).unwrap();
+        assert!(anchors.verify(&header(2160, 3, 2)).is_err());
+
+        // Clones share the anchors pinned
+        assert_eq!(anchors.clone().pinned().len(), 1);
+
+        assert!(Params::default().into_anchors().unwrap().is_none());
+    }
+
+    #[test]
+    fn anchors_must_be_signed_by_a_verification_key() {
+        let sk = SecretKey::random(&mut StdRng::seed_from_u64(1));
+        let other = SecretKey::random(&mut StdRng::seed_from_u64(2));
+        let keys = [PublicKey::new(dusk_bls12_381_sign::PublicKey::from(&sk))];
+
+        let anchor = Anchor::from_header(&header(2160, 1, 2));
+        signed(anchor, &sk).verify(&keys).unwrap();
+        assert!(signed(anchor, &other).verify(&keys).is_err());
+
+        // A record tampered with does not verify
+        let mut tampered = signed(anchor, &sk);
+        tampered.anchor.hash = [3; 32];
+        assert!(tampered.verify(&keys).is_err());
+        tampered.signature.pop();
+        assert!(tampered.verify(&keys).is_err());
+
+        // Anchors cannot be verified without keys
+        let params = Params {
+            verify_urls: vec!["https://anchors.example.org".into()],
+            ..Default::default()
+        };
+        assert!(params.into_anchors().is_err());
+    }
+
+    #[test]
+    fn boundary_blocks_are_published_once_final() {
+        let anchors = Params {
+            publish_record_file: Some("anchor.txt".into()),
+            publish_interval: Some(10),
+            ..Default::default()
+        }
+        .into_anchors()
+        .unwrap()
+        .expect("publication to be enabled");
+
+        assert_eq!(anchors.due(9), Some(0));
+        *anchors.published.lock().unwrap() = Some(0);
+        assert_eq!(anchors.due(9), None);
+
+        // The block at height 10 became final along with a later one
+        assert_eq!(anchors.due(13), Some(10));
+        *anchors.published.lock().unwrap() = Some(10);
+        assert_eq!(anchors.due(19), None);
+        assert_eq!(anchors.due(25), Some(20));
+    }
+}
@@ -0,0 +1,278 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use anyhow::{anyhow, Result};
+use node_data::bls::PublicKey;
+use node_data::ledger::{Certificate, Hash, Header};
+use serde::{Deserialize, Serialize};
+
+use super::ACCEPT_BLOCK_TIMEOUT_SEC;
+
+/// Domain separator of the message signed by checkpoint keys
+const SIGN_SEED: &[u8] = b"checkpoint";
+
+/// Configuration of the emergency checkpoint mode.
+///
+/// Checkpoint mode is disabled unless at least one key is configured.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Params {
+    /// Base58-encoded BLS public keys allowed to sign checkpoint blocks
+    pub keys: Vec<String>,
+
+    /// Minimum number of distinct keys that must sign a checkpoint block
+    pub threshold: usize,
+
+    /// Number of consecutive accept-block timeouts after which consensus is
+    /// considered stalled and checkpoint blocks are accepted
+    pub max_stalled_rounds: u64,
+}
+
+/// Set of keys allowed to sign blocks when consensus stalls.
+///
+/// A checkpoint block carries, in place of the winning certificate, a
+/// `Certificate` with empty validation votes and whose ratification votes
+/// hold the aggregated signature of the checkpoint keys over the block hash.
+/// The bitset marks which of the configured keys signed it.
+///
+/// Whether consensus has stalled must be decided the same way by nodes
+/// following the chain and by nodes syncing it later. A checkpoint block is
+/// therefore only valid if its timestamp is at least `max_stalled_rounds`
+/// accept-block timeouts past the one of the block it follows.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    keys: Vec<PublicKey>,
+    threshold: usize,
+    max_stalled_rounds: u64,
+}
+
+impl Params {
+    /// Parses the configured keys, returning `None` if checkpoint mode is
+    /// disabled.
+    pub fn into_checkpoint(self) -> Result<Option<Checkpoint>> {
+        if self.keys.is_empty() {
+            return Ok(None);
+        }
+
+        let keys = self
+            .keys
+            .iter()
+            .map(|key| {
+                let bytes: [u8; 96] = bs58::decode(key)
+                    .into_vec()?
+                    .try_into()
+                    .map_err(|_| anyhow!("invalid checkpoint key length"))?;
+                PublicKey::try_from(bytes)
+                    .map_err(|e| anyhow!("invalid checkpoint key: {e:?}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Checkpoint::new(keys, self.threshold, self.max_stalled_rounds).map(Some)
+    }
+}
+
+impl Checkpoint {
+    pub fn new(
+        keys: Vec<PublicKey>,
+        threshold: usize,
+        max_stalled_rounds: u64,
+    ) -> Result<Self> {
+        if keys.len() > u64::BITS as usize {
+            return Err(anyhow!("too many checkpoint keys"));
+        }
+
+        if threshold == 0 || threshold > keys.len() {
+            return Err(anyhow!(
+                "invalid checkpoint threshold {threshold} for {} keys",
+                keys.len()
+            ));
+        }
+
+        Ok(Self {
+            keys,
+            threshold,
+            max_stalled_rounds,
+        })
+    }
+
+    pub fn max_stalled_rounds(&self) -> u64 {
+        self.max_stalled_rounds
+    }
+
+    /// Returns true if the certificate has the shape of a checkpoint one.
+    ///
+    /// Certificates produced by consensus always carry validation votes,
+    /// while checkpoint certificates only carry the signature of the
+    /// checkpoint keys.
+    pub fn is_checkpoint_cert(cert: &Certificate) -> bool {
+        cert.validation.is_empty() && !cert.ratification.is_empty()
+    }
+
+    /// Verifies that consensus was stalled when the checkpoint block `header`
+    /// following `prev` was produced.
+    pub fn verify_stall(&self, prev: &Header, header: &Header) -> Result<()> {
+        let stall = self
+            .max_stalled_rounds
+            .saturating_mul(ACCEPT_BLOCK_TIMEOUT_SEC.as_secs());
+        let stalled_for = header.timestamp.saturating_sub(prev.timestamp);
+
+        if stalled_for < stall {
+            return Err(anyhow!(
+                "checkpoint block {stalled_for}s after the previous block, \
+                 {stall}s required"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Verifies that a threshold of checkpoint keys has signed `hash`.
+    pub fn verify(&self, hash: &Hash, cert: &Certificate) -> Result<()> {
+        if !Self::is_checkpoint_cert(cert) {
+            return Err(anyhow!("not a checkpoint certificate"));
+        }
+
+        let bitset = cert.ratification.bitset;
+        let signers: Vec<_> = self
+            .keys
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| bitset & (1 << i) != 0)
+            .map(|(_, pk)| *pk.inner())
+            .collect();
+
+        if self.keys.len() < u64::BITS as usize
+            && bitset >> self.keys.len() != 0
+        {
+            return Err(anyhow!("checkpoint bitset exceeds configured keys"));
+        }
+
+        if signers.len() < self.threshold {
+            return Err(anyhow!(
+                "checkpoint signed by {} keys, {} required",
+                signers.len(),
+                self.threshold
+            ));
+        }
+
+        let (first, rest) = signers
+            .split_first()
+            .ok_or(anyhow!("no checkpoint signer"))?;
+        let mut apk = dusk_bls12_381_sign::APK::from(first);
+        apk.aggregate(rest);
+
+        let sig = dusk_bls12_381_sign::Signature::from_bytes(
+            cert.ratification.aggregate_signature().inner(),
+        )
+        .map_err(|e| anyhow!("invalid checkpoint signature bytes: {e:?}"))?;
+
+        apk.verify(&sig, &Self::signable(hash))
+            .map_err(|e| anyhow!("invalid checkpoint signature: {e:?}"))
+    }
+
+    /// Returns the message checkpoint keys sign for a block hash.
+    pub fn signable(hash: &Hash) -> Vec<u8> {
+        let mut msg = Vec::with_capacity(SIGN_SEED.len() + hash.len());
+        msg.extend_from_slice(SIGN_SEED);
+        msg.extend_from_slice(hash);
+        msg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dusk_bls12_381_sign::SecretKey;
+    use node_data::ledger::StepVotes;
+    use node_data::message::payload::{RatificationResult, Vote};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn checkpoint_cert(
+        hash: &Hash,
+        sks: &[(usize, &SecretKey)],
+    ) -> Certificate {
+        let msg = Checkpoint::signable(hash);
+        let mut bitset = 0u64;
+        let mut signature: Option<dusk_bls12_381_sign::Signature> = None;
+        for (i, sk) in sks {
+            let pk = dusk_bls12_381_sign::PublicKey::from(*sk);
+            let sig = sk.sign(&pk, &msg);
+            signature = Some(match signature {
+                Some(s) => s.aggregate(&[sig]),
+                None => sig,
+            });
+            bitset |= 1 << i;
+        }
+
+        Certificate {
+            result: RatificationResult::Success(Vote::Valid(*hash)),
+            validation: StepVotes::default(),
+            ratification: StepVotes::new(
+                signature.expect("at least one signer").to_bytes(),
+                bitset,
+            ),
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_threshold() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let sks: Vec<_> = (0..3).map(|_| SecretKey::random(&mut rng)).collect();
+        let keys = sks
+            .iter()
+            .map(|sk| PublicKey::new(dusk_bls12_381_sign::PublicKey::from(sk)))
+            .collect();
+
+        let checkpoint = Checkpoint::new(keys, 2, 3).expect("valid params");
+        let hash = [7u8; 32];
+
+        let cert = checkpoint_cert(&hash, &[(0, &sks[0]), (2, &sks[2])]);
+        assert!(Checkpoint::is_checkpoint_cert(&cert));
+        checkpoint
+            .verify(&hash, &cert)
+            .expect("checkpoint to be valid");
+
+        // Signature over a different block
+        assert!(checkpoint.verify(&[8u8; 32], &cert).is_err());
+
+        // Below threshold
+        let cert = checkpoint_cert(&hash, &[(1, &sks[1])]);
+        assert!(checkpoint.verify(&hash, &cert).is_err());
+
+        // Bitset not matching the signers
+        let cert = checkpoint_cert(&hash, &[(0, &sks[0]), (1, &sks[2])]);
+        assert!(checkpoint.verify(&hash, &cert).is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_stall() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let sk = SecretKey::random(&mut rng);
+        let keys =
+            vec![PublicKey::new(dusk_bls12_381_sign::PublicKey::from(&sk))];
+        let checkpoint = Checkpoint::new(keys, 1, 3).expect("valid params");
+
+        let prev = Header {
+            timestamp: 1_000,
+            ..Default::default()
+        };
+        let stall = 3 * ACCEPT_BLOCK_TIMEOUT_SEC.as_secs();
+
+        let header = Header {
+            timestamp: prev.timestamp + stall,
+            ..Default::default()
+        };
+        checkpoint
+            .verify_stall(&prev, &header)
+            .expect("consensus to be stalled");
+
+        let header = Header {
+            timestamp: prev.timestamp + stall - 1,
+            ..Default::default()
+        };
+        assert!(checkpoint.verify_stall(&prev, &header).is_err());
+    }
+}
@@ -8,11 +8,16 @@ use crate::database::{self, Candidate, Mempool, Metadata};
 use crate::{vm, Message, Network};
 use async_trait::async_trait;
 use dusk_consensus::commons::{ConsensusError, RoundUpdate, TimeoutSet};
+use dusk_consensus::config::ConsensusParams;
 use dusk_consensus::consensus::Consensus;
 use dusk_consensus::operations::{
     CallParams, Error, Operations, Output, VerificationOutput,
 };
-use dusk_consensus::user::provisioners::ContextProvisioners;
+use dusk_consensus::round_state::RoundState;
+use dusk_consensus::signer::{ConsensusSigner, LocalSigner};
+use dusk_consensus::user::provisioners::{ContextProvisioners, Provisioners};
+use dusk_consensus::vote_stats::{AbsenceStreaks, VoteStats};
+use node_data::bls::PublicKey;
 use node_data::ledger::{Block, Hash, Header};
 use node_data::message::payload::GetCandidate;
 use node_data::message::AsyncQueue;
@@ -21,15 +26,33 @@ use tokio::sync::{oneshot, Mutex, RwLock};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, trace, warn};
 
-use crate::chain::header_validation::Validator;
+use crate::chain::checkpoint::Checkpoint;
+use crate::chain::header_validation::{verify_block_txs, Validator};
 use crate::chain::metrics::AverageElapsedTime;
+use crate::chain::performance;
+use crate::chain::schedule;
+use crate::chain::validation_cache::{
+    is_verdict, Check, Outcome, ValidationCache,
+};
 use crate::database::rocksdb::{
-    MD_AVG_PROPOSAL, MD_AVG_RATIFICATION, MD_AVG_VALIDATION,
+    md_vote_stats_key, MD_ABSENCE_STREAKS, MD_AVG_PROPOSAL,
+    MD_AVG_RATIFICATION, MD_AVG_VALIDATION, MD_ROUND_STATE,
 };
 use node_data::{ledger, Serializable, StepName};
 use std::sync::Arc;
 use std::time::Duration;
 
+/// Consecutive rounds without votes from a committee member after which its
+/// absence is reported
+const ABSENCE_ALERT_STREAK: u32 = 10;
+
+/// Rounds between two writes of the absence streaks to the database
+const ABSENCE_WRITE_INTERVAL: u64 = 100;
+
+/// Absence streaks of the committee members, loaded from the database on the
+/// first round and kept in memory afterwards
+type SafeAbsenceStreaks = Arc<std::sync::Mutex<Option<AbsenceStreaks>>>;
+
 /// Consensus Service Task is responsible for running the consensus layer.
 ///
 /// It manages consensus lifecycle and provides a way to interact with it.
@@ -47,33 +70,64 @@ pub(crate) struct Task {
     /// task id a counter to track consensus tasks
     task_id: u64,
 
-    /// Loaded Consensus keys
-    pub keys: (dusk_bls12_381_sign::SecretKey, node_data::bls::PublicKey),
+    /// Signer of the consensus messages of this provisioner
+    pub signer: Arc<dyn ConsensusSigner>,
+
+    /// Verification outcomes of the latest candidates
+    validations: Arc<std::sync::Mutex<ValidationCache>>,
+
+    absences: SafeAbsenceStreaks,
+
+    /// Keys allowed to certify blocks in emergency mode
+    checkpoint: Option<Arc<Checkpoint>>,
+
+    /// Committee sizes and quorum thresholds of the network
+    params: ConsensusParams,
 }
 
 impl Task {
-    /// Creates a new consensus task with the given keys encrypted with password
-    /// from env var DUSK_CONSENSUS_KEYS_PASS.
-    pub(crate) fn new_with_keys(path: String) -> anyhow::Result<Self> {
-        let pwd = std::env::var("DUSK_CONSENSUS_KEYS_PASS")
-            .map_err(|_| anyhow::anyhow!("DUSK_CONSENSUS_KEYS_PASS not set"))?;
+    /// Creates a new consensus task with the given keys encrypted with the
+    /// passphrase provided by the environment.
+    pub(crate) fn new_with_keys(
+        path: String,
+        checkpoint: Option<Arc<Checkpoint>>,
+        params: ConsensusParams,
+    ) -> anyhow::Result<Self> {
+        let pwd = node_data::keystore::passphrase()?;
         info!(event = "loading consensus keys", path = path);
-        let keys = node_data::bls::load_keys(path, pwd)?;
+        let (sk, pk) = node_data::bls::load_keys(path, pwd)?;
 
         info!(
             event = "loaded consensus keys",
-            pubkey = format!("{:?}", keys.1)
+            pubkey = format!("{:?}", pk)
         );
 
-        Ok(Self {
+        Ok(Self::new_with_signer(
+            Arc::new(LocalSigner::new(sk, pk)),
+            checkpoint,
+            params,
+        ))
+    }
+
+    /// Creates a new consensus task signing with the given signer.
+    pub(crate) fn new_with_signer(
+        signer: Arc<dyn ConsensusSigner>,
+        checkpoint: Option<Arc<Checkpoint>>,
+        params: ConsensusParams,
+    ) -> Self {
+        Self {
             quorum_inbound: AsyncQueue::unbounded(),
             main_inbound: AsyncQueue::unbounded(),
             outbound: AsyncQueue::unbounded(),
             result: AsyncQueue::unbounded(),
             running_task: None,
             task_id: 0,
-            keys,
-        })
+            signer,
+            validations: Default::default(),
+            absences: Default::default(),
+            checkpoint,
+            params,
+        }
     }
 
     pub(crate) fn spawn<D: database::DB, VM: vm::VMExecution, N: Network>(
@@ -86,6 +140,12 @@ impl Task {
         base_timeout: TimeoutSet,
     ) {
         let current = provisioners_list.to_current();
+        self.store_duty_schedule(
+            db,
+            provisioners_list.to_current(),
+            most_recent_block.header(),
+        );
+
         let c = Consensus::new(
             self.main_inbound.clone(),
             self.outbound.clone(),
@@ -96,15 +156,20 @@ impl Task {
                 vm,
                 most_recent_block.header().clone(),
                 provisioners_list, // TODO: Avoid cloning
+                self.checkpoint.clone(),
+                self.params,
+                self.validations.clone(),
+                self.absences.clone(),
+                self.signer.public_key().clone(),
             ))),
             Arc::new(Mutex::new(CandidateDB::new(db.clone(), network.clone()))),
         );
 
         let ru = RoundUpdate::new(
-            self.keys.1.clone(),
-            self.keys.0,
+            self.signer.clone(),
             most_recent_block.header(),
             base_timeout.clone(),
+            self.params,
         );
 
         self.task_id += 1;
@@ -138,6 +203,49 @@ impl Task {
         ));
     }
 
+    /// Computes the duties of this provisioner in the round following `mrb`,
+    /// storing them so that operators can schedule their maintenance.
+    fn store_duty_schedule<D: database::DB>(
+        &self,
+        db: &Arc<RwLock<D>>,
+        provisioners: Provisioners,
+        mrb: &Header,
+    ) {
+        let pk = self.signer.public_key().clone();
+        let params = self.params;
+        let seed = mrb.seed;
+        let round = mrb.height + 1;
+        let db = db.clone();
+
+        tokio::spawn(async move {
+            let schedule = tokio::task::spawn_blocking(move || {
+                schedule::schedule(&provisioners, &params, seed, round, &pk)
+            })
+            .await;
+
+            let schedule = match schedule {
+                Ok(schedule) => schedule,
+                Err(err) => {
+                    error!(event = "duty schedule failed", ?err);
+                    return;
+                }
+            };
+
+            info!(
+                event = "duty schedule",
+                round,
+                eligible = schedule.eligible,
+                duties = schedule.duties.len(),
+            );
+
+            if let Err(err) =
+                db.read().await.update(|t| schedule::store(t, &schedule))
+            {
+                error!(event = "duty schedule not stored", ?err);
+            }
+        });
+    }
+
     /// Aborts the running consensus task and waits for its termination.
     pub(crate) async fn abort_with_wait(&mut self) {
         if let Some((handle, cancel_chan)) = self.running_task.take() {
@@ -172,7 +280,7 @@ pub struct CandidateDB<DB: database::DB, N: Network> {
 }
 
 impl<DB: database::DB, N: Network> CandidateDB<DB, N> {
-    pub fn new(db: Arc<RwLock<DB>>, network: Arc<RwLock<N>>) -> Self {
+    pub(crate) fn new(db: Arc<RwLock<DB>>, network: Arc<RwLock<N>>) -> Self {
         Self { db, network }
     }
 }
@@ -245,6 +353,15 @@ impl<DB: database::DB, N: Network> dusk_consensus::commons::Database
                     hex::ToHex::encode_hex::<String>(&b.header().hash)
                 );
 
+                // Keep it along with the other candidates of the latest
+                // rounds, so that it's not requested again
+                let db = self.db.read().await;
+                if let Err(e) =
+                    db.update(|t| t.store_candidate_block(b.clone()))
+                {
+                    warn!("Unable to store candidate block: {e}");
+                }
+
                 Ok(b)
             }
             _ => Err(anyhow::anyhow!("couldn't get candidate block")),
@@ -271,22 +388,89 @@ pub struct Executor<DB: database::DB, VM: vm::VMExecution> {
     vm: Arc<RwLock<VM>>,
     mrb_header: ledger::Header,
     provisioners: ContextProvisioners,
+    checkpoint: Option<Arc<Checkpoint>>,
+    params: ConsensusParams,
+    validations: Arc<std::sync::Mutex<ValidationCache>>,
+    absences: SafeAbsenceStreaks,
+    /// Key of the node's own provisioner
+    pk: PublicKey,
 }
 
 impl<DB: database::DB, VM: vm::VMExecution> Executor<DB, VM> {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         db: &Arc<RwLock<DB>>,
         vm: &Arc<RwLock<VM>>,
         mrb_header: ledger::Header,
         provisioners: ContextProvisioners,
+        checkpoint: Option<Arc<Checkpoint>>,
+        params: ConsensusParams,
+        validations: Arc<std::sync::Mutex<ValidationCache>>,
+        absences: SafeAbsenceStreaks,
+        pk: PublicKey,
     ) -> Self {
         Executor {
             db: db.clone(),
             vm: vm.clone(),
             mrb_header,
             provisioners,
+            checkpoint,
+            params,
+            validations,
+            absences,
+            pk,
         }
     }
+
+    fn validations(&self) -> std::sync::MutexGuard<'_, ValidationCache> {
+        self.validations
+            .lock()
+            .expect("validation cache lock to be acquired")
+    }
+
+    /// Returns the outcome of the given check of a candidate, if it was
+    /// already performed on top of the current tip.
+    fn cached_outcome(
+        &self,
+        candidate: &Hash,
+        check: Check,
+    ) -> Option<Outcome> {
+        let key = (*candidate, self.mrb_header.hash, check);
+        let outcome = self.validations().get(&key);
+        if outcome.is_some() {
+            debug!(
+                event = "cached verification",
+                candidate = hex::encode(candidate),
+                ?check,
+                ?outcome,
+            );
+        }
+        outcome
+    }
+
+    async fn verify_state_transition_uncached(
+        &self,
+        blk: &Block,
+    ) -> anyhow::Result<VerificationOutput> {
+        verify_block_txs(blk).map_err(|err| {
+            error!("failed to verify txs {}", err);
+            err
+        })?;
+
+        let vm = self.vm.read().await;
+
+        vm.verify_state_transition(blk).map_err(|err| {
+            error!("failed to call VST {}", err);
+            err
+        })
+    }
+
+    fn cache_outcome(&self, candidate: &Hash, check: Check, outcome: Outcome) {
+        let key = (*candidate, self.mrb_header.hash, check);
+        let mut validations = self.validations();
+        validations.insert(key, outcome);
+        trace!(event = "verification cached", cached = validations.len());
+    }
 }
 
 #[async_trait::async_trait]
@@ -296,35 +480,77 @@ impl<DB: database::DB, VM: vm::VMExecution> Operations for Executor<DB, VM> {
         candidate_header: &Header,
         disable_winning_cert_check: bool,
     ) -> Result<(), Error> {
+        let check = Check::Header {
+            disable_winning_cert_check,
+        };
+        match self.cached_outcome(&candidate_header.hash, check) {
+            Some(Outcome::Invalid) => return Err(Error::Failed),
+            Some(_) => return Ok(()),
+            None => {}
+        }
+
         let validator = Validator::new(
             self.db.clone(),
             &self.mrb_header,
             &self.provisioners,
+            &self.params,
+            self.checkpoint.as_deref(),
         );
 
-        validator
+        let res = validator
             .execute_checks(candidate_header, disable_winning_cert_check)
-            .await
-            .map_err(|err| {
+            .await;
+
+        match res {
+            Ok(_) => {
+                self.cache_outcome(
+                    &candidate_header.hash,
+                    check,
+                    Outcome::Valid,
+                );
+                Ok(())
+            }
+            Err(err) => {
                 error!("failed to verify header {}", err);
-                Error::Failed
-            })?;
-
-        Ok(())
+                if is_verdict(&err) {
+                    self.cache_outcome(
+                        &candidate_header.hash,
+                        check,
+                        Outcome::Invalid,
+                    );
+                }
+                Err(Error::Failed)
+            }
+        }
     }
 
     async fn verify_state_transition(
         &self,
         blk: &Block,
     ) -> Result<VerificationOutput, dusk_consensus::operations::Error> {
-        info!("verifying state");
+        let hash = blk.header().hash;
+        match self.cached_outcome(&hash, Check::StateTransition) {
+            Some(Outcome::Transition(output)) => return Ok(output),
+            Some(_) => return Err(Error::Failed),
+            None => {}
+        }
 
-        let vm = self.vm.read().await;
+        info!("verifying state");
 
-        Ok(vm.verify_state_transition(blk).map_err(|err| {
-            error!("failed to call VST {}", err);
-            Error::Failed
-        })?)
+        match self.verify_state_transition_uncached(blk).await {
+            Ok(output) => {
+                let outcome = Outcome::Transition(output);
+                self.cache_outcome(&hash, Check::StateTransition, outcome);
+                Ok(output)
+            }
+            Err(err) => {
+                if is_verdict(&err) {
+                    let outcome = Outcome::Invalid;
+                    self.cache_outcome(&hash, Check::StateTransition, outcome);
+                }
+                Err(Error::Failed)
+            }
+        }
     }
 
     async fn execute_state_transition(
@@ -399,4 +625,123 @@ impl<DB: database::DB, VM: vm::VMExecution> Operations for Executor<DB, VM> {
 
         Ok(())
     }
+
+    async fn add_vote_stats(&self, stats: VoteStats) -> Result<(), Error> {
+        let missing = stats
+            .provisioners
+            .iter()
+            .filter(|(_, p)| p.missed > 0)
+            .count();
+        let late = stats
+            .provisioners
+            .iter()
+            .filter(|(_, p)| p.late > 0)
+            .count();
+        let steps = stats.steps.values();
+        let quorum_times: Vec<_> = stats
+            .steps
+            .iter()
+            .filter_map(|(step, s)| s.quorum_time_ms.map(|ms| (*step, ms)))
+            .collect();
+        info!(
+            event = "vote stats",
+            round = stats.round,
+            steps = stats.steps.len(),
+            voters = stats.provisioners.len(),
+            votes = steps.clone().map(|s| s.votes).sum::<u32>(),
+            duplicates = steps.clone().map(|s| s.duplicates).sum::<u32>(),
+            invalid_signatures =
+                steps.map(|s| s.invalid_signatures).sum::<u32>(),
+            ?quorum_times,
+            missing,
+            late,
+        );
+
+        let db = self.db.read().await;
+        let streaks = {
+            let mut absences =
+                self.absences.lock().expect("absences lock to be acquired");
+            let streaks = absences.get_or_insert_with(|| {
+                db.view(|t| t.op_read(MD_ABSENCE_STREAKS))
+                    .ok()
+                    .flatten()
+                    .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                    .unwrap_or_default()
+            });
+            streaks.update(&stats);
+            // Reported once per streak, as it reaches the alert threshold
+            let alerts = streaks.provisioners.iter().filter(|(_, absence)| {
+                absence.streak == ABSENCE_ALERT_STREAK
+                    && absence.last_expected == stats.round
+            });
+            for (provisioner, absence) in alerts {
+                warn!(
+                    event = "votes withheld",
+                    %provisioner,
+                    streak = absence.streak,
+                    last_vote = ?absence.last_vote,
+                );
+            }
+
+            // The streaks cover all the provisioners ever expected to vote,
+            // they are only written once in a while
+            match stats.round % ABSENCE_WRITE_INTERVAL == 0 {
+                true => Some(serde_json::to_vec(streaks)),
+                false => None,
+            }
+        };
+
+        db.update(|t| {
+            if let Some(streaks) = streaks {
+                t.op_write(MD_ABSENCE_STREAKS, streaks?)?;
+            }
+
+            performance::record(
+                t,
+                &self.pk,
+                self.provisioners.prev(),
+                &self.params,
+                &self.mrb_header,
+                &stats,
+            )?;
+
+            t.op_write(
+                &md_vote_stats_key(stats.round),
+                serde_json::to_vec(&stats)?,
+            )
+        })
+        .map_err(|err: anyhow::Error| {
+            error!("{err}");
+            Error::Failed
+        })?;
+
+        Ok(())
+    }
+
+    async fn store_round_state(&self, state: RoundState) -> Result<(), Error> {
+        let mut bytes = vec![];
+        state.write(&mut bytes).map_err(|_| Error::Failed)?;
+
+        let db = self.db.read().await;
+        db.update(|t| t.op_write(MD_ROUND_STATE, bytes)).map_err(
+            |err: anyhow::Error| {
+                error!("{err}");
+                Error::Failed
+            },
+        )
+    }
+
+    async fn load_round_state(&self) -> Option<RoundState> {
+        let bytes = self
+            .db
+            .read()
+            .await
+            .view(|t| t.op_read(MD_ROUND_STATE))
+            .ok()
+            .flatten()?;
+
+        RoundState::read(&mut &bytes[..])
+            .map_err(|err| warn!(event = "invalid round state", ?err))
+            .ok()
+    }
 }
@@ -0,0 +1,212 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Summaries of the consensus weight at the end of each epoch.
+
+use anyhow::{anyhow, Result};
+use dusk_consensus::user::provisioners::Provisioners;
+use node_data::ledger::ContractEvent;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use stake_contract_types::{StakingEvent, EPOCH};
+
+use super::acceptor::STAKE_CONTRACT;
+use crate::database::{Ledger, Metadata};
+
+/// Metadata key prefix of the epoch summaries, followed by the epoch number
+const MD_EPOCH_SUMMARY: &[u8] = b"epoch_summary";
+
+/// Record of an epoch, taken once its last block is accepted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochSummary {
+    pub epoch: u64,
+    /// Height of the last block of the epoch
+    pub height: u64,
+    /// Hash of the provisioners set at the end of the epoch
+    pub provisioners_hash: [u8; 32],
+    pub provisioners: usize,
+    pub total_stake: u64,
+    /// Rewards distributed by the blocks of the epoch
+    pub rewards: u64,
+    /// Amount slashed by the blocks of the epoch
+    pub slashed: u64,
+    /// Amount slashed since genesis, unknown if the summary of the previous
+    /// epoch is missing
+    pub total_slashed: Option<u64>,
+}
+
+/// Returns the epoch of the block at the given height.
+pub const fn epoch(height: u64) -> u64 {
+    height / EPOCH
+}
+
+/// Returns whether the block at the given height is the last of its epoch.
+pub(crate) const fn is_last_block(height: u64) -> bool {
+    height % EPOCH == EPOCH - 1
+}
+
+/// Returns the hash of the provisioners set, committing to the key, stake
+/// and eligibility of each provisioner.
+pub fn provisioners_hash(provisioners: &Provisioners) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    for (pk, stake) in provisioners.iter() {
+        hasher.update(pk.bytes().inner());
+        hasher.update(stake.value().to_le_bytes());
+        hasher.update(stake.eligible_since.to_le_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// Summarizes the epoch ending with the block at `height`, given the
+/// provisioners set following it.
+///
+/// The rewards and slashes are the ones recorded by the events of the blocks
+/// of the epoch, which must all be stored.
+pub(crate) fn summarize<T: Ledger + Metadata>(
+    t: &T,
+    height: u64,
+    provisioners: &Provisioners,
+) -> Result<EpochSummary> {
+    let epoch = epoch(height);
+
+    let mut rewards = 0;
+    let mut slashed = 0;
+    for h in epoch * EPOCH..=height {
+        let (r, s) = tally(&t.fetch_block_events(h)?)?;
+        rewards += r;
+        slashed += s;
+    }
+
+    let total_slashed = match epoch.checked_sub(1) {
+        Some(prev) => fetch(t, prev)?
+            .and_then(|prev| prev.total_slashed)
+            .map(|total| total + slashed),
+        None => Some(slashed),
+    };
+
+    Ok(EpochSummary {
+        epoch,
+        height,
+        provisioners_hash: provisioners_hash(provisioners),
+        provisioners: provisioners.iter().count(),
+        total_stake: provisioners.iter().map(|(_, s)| s.value()).sum(),
+        rewards,
+        slashed,
+        total_slashed,
+    })
+}
+
+/// Returns the amounts rewarded and slashed by the stake contract, as
+/// recorded by the given block events.
+fn tally(events: &[ContractEvent]) -> Result<(u64, u64)> {
+    let mut rewards = 0;
+    let mut slashed = 0;
+    for event in events.iter().filter(|e| e.source == STAKE_CONTRACT) {
+        match event.topic.as_str() {
+            "reward" | "compound" => rewards += staked_value(event)?,
+            "slash" | "hard_slash" => slashed += staked_value(event)?,
+            _ => {}
+        }
+    }
+    Ok((rewards, slashed))
+}
+
+fn staked_value(event: &ContractEvent) -> Result<u64> {
+    let event: StakingEvent = rkyv::from_bytes(&event.data)
+        .map_err(|e| anyhow!("Cannot deserialize staking event rkyv {e:?}"))?;
+    Ok(event.value)
+}
+
+pub(crate) fn store<T: Metadata>(t: &T, summary: &EpochSummary) -> Result<()> {
+    t.op_write(&key(summary.epoch), serde_json::to_vec(summary)?)
+}
+
+/// Returns the summary of the given epoch, if recorded.
+pub fn fetch<T: Metadata>(t: &T, epoch: u64) -> Result<Option<EpochSummary>> {
+    t.op_read(&key(epoch))?
+        .map(|bytes| serde_json::from_slice(&bytes).map_err(Into::into))
+        .transpose()
+}
+
+fn key(epoch: u64) -> Vec<u8> {
+    [MD_EPOCH_SUMMARY, &epoch.to_be_bytes()].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use dusk_bls12_381_sign::{PublicKey as BlsPublicKey, SecretKey};
+    use node_data::bls::PublicKey;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_epoch_boundaries() {
+        assert_eq!(epoch(0), 0);
+        assert_eq!(epoch(EPOCH - 1), 0);
+        assert_eq!(epoch(EPOCH), 1);
+
+        assert!(!is_last_block(0));
+        assert!(is_last_block(EPOCH - 1));
+        assert!(!is_last_block(EPOCH));
+        assert!(is_last_block(2 * EPOCH - 1));
+    }
+
+    #[test]
+    fn test_provisioners_hash() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let keys: Vec<_> = (0..3)
+            .map(|_| {
+                let sk = SecretKey::random(&mut rng);
+                PublicKey::new(BlsPublicKey::from(&sk))
+            })
+            .collect();
+
+        let mut provisioners = Provisioners::empty();
+        let mut reversed = Provisioners::empty();
+        for (i, pk) in keys.iter().enumerate() {
+            provisioners.add_member_with_value(pk.clone(), 1000 + i as u64);
+        }
+        for (i, pk) in keys.iter().enumerate().rev() {
+            reversed.add_member_with_value(pk.clone(), 1000 + i as u64);
+        }
+        let hash = provisioners_hash(&provisioners);
+        assert_eq!(hash, provisioners_hash(&reversed));
+
+        let mut changed = Provisioners::empty();
+        for pk in keys.iter() {
+            changed.add_member_with_value(pk.clone(), 1000);
+        }
+        assert_ne!(hash, provisioners_hash(&changed));
+        assert_ne!(hash, provisioners_hash(&Provisioners::empty()));
+    }
+
+    #[test]
+    fn test_tally() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let public_key = BlsPublicKey::from(&SecretKey::random(&mut rng));
+
+        let event = |source, topic: &str, value| ContractEvent {
+            source,
+            topic: topic.into(),
+            data: rkyv::to_bytes::<_, 256>(&StakingEvent { public_key, value })
+                .unwrap()
+                .to_vec(),
+        };
+
+        let events = [
+            event(STAKE_CONTRACT, "reward", 10),
+            event(STAKE_CONTRACT, "compound", 20),
+            event(STAKE_CONTRACT, "slash", 3),
+            event(STAKE_CONTRACT, "hard_slash", 4),
+            event(STAKE_CONTRACT, "withdraw", 100),
+            event([0xab; 32], "reward", 1_000),
+        ];
+        assert_eq!(tally(&events).unwrap(), (30, 7));
+        assert_eq!(tally(&[]).unwrap(), (0, 0));
+    }
+}
@@ -5,10 +5,10 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use anyhow::{anyhow, Result};
-use dusk_consensus::user::provisioners::ContextProvisioners;
+use dusk_consensus::config::ConsensusParams;
+use dusk_consensus::user::provisioners::{ContextProvisioners, Provisioners};
 use node_data::ledger;
 use node_data::ledger::Header;
-use std::cmp::Ordering;
 use tracing::info;
 
 use crate::{
@@ -18,6 +18,8 @@ use crate::{
 };
 
 use super::acceptor::{Acceptor, RevertTarget};
+use super::fork_choice::{Contender, Rule};
+use super::header_validation::verify_block_cert;
 
 /// Wraps up any handlers or data needed by fallback to complete.
 pub(crate) struct WithContext<
@@ -55,19 +57,9 @@ impl<'a, N: Network, DB: database::DB, VM: vm::VMExecution>
         local: &Header,
         remote: &Header,
     ) -> Result<()> {
-        match (local.height, remote.iteration.cmp(&local.iteration)) {
-            (0, _) => Err(anyhow!("cannot fallback over genesis block")),
-            (_, Ordering::Greater) => Err(anyhow!(
-                "iteration {:?} is higher than the current {:?}",
-                remote.iteration,
-                local.iteration
-            )),
-            (_, Ordering::Equal) => Err(anyhow!(
-                "iteration is equal to the current {:?}",
-                local.iteration
-            )), // TODO: This may be a slashing condition
-            _ => Ok(()),
-        }?;
+        if local.height == 0 {
+            return Err(anyhow!("cannot fallback over genesis block"));
+        }
 
         let (prev_header, prev_prev_header) =
             self.acc.db.read().await.view(|t| {
@@ -109,16 +101,62 @@ impl<'a, N: Network, DB: database::DB, VM: vm::VMExecution>
         let mut provisioners_list = ContextProvisioners::new(provisioners_list);
         provisioners_list.set_previous(prev_provisioners_list);
 
+        // Both certificates are verified against the committees they were
+        // produced by, so that the credits of their votes can be compared
+        let provisioners = provisioners_list.current();
+        let params = &self.acc.params;
+        let local = Contender {
+            header: local,
+            credits: cert_credits(&prev_header, provisioners, params, local)
+                .await?,
+        };
+        let remote = Contender {
+            header: remote,
+            credits: cert_credits(&prev_header, provisioners, params, remote)
+                .await?,
+        };
+
+        let fork_choice =
+            Rule::at(params, local.header.height).into_fork_choice();
+        fork_choice.choose(&local, &remote).map_err(|e| {
+            anyhow!("{} rule prefers local block: {e}", fork_choice.name())
+        })?;
+
         // Ensure header of the new block is valid according to prev_block
         // header
         let _ = acceptor::verify_block_header(
             self.acc.db.clone(),
             &prev_header,
             &provisioners_list,
-            remote,
+            &self.acc.params,
+            self.acc.checkpoint.as_deref(),
+            remote.header,
         )
         .await?;
 
         Ok(())
     }
 }
+
+/// Verifies the certificate of a block built on top of `prev_header`,
+/// returning the committee credits of its votes.
+async fn cert_credits(
+    prev_header: &Header,
+    provisioners: &Provisioners,
+    params: &ConsensusParams,
+    header: &Header,
+) -> Result<usize> {
+    let (validation, ratification) = verify_block_cert(
+        prev_header.chain_id,
+        prev_header.hash,
+        prev_header.seed,
+        provisioners,
+        params,
+        header.height,
+        &header.cert,
+        header.iteration,
+    )
+    .await?;
+
+    Ok(validation.total + ratification.total)
+}
@@ -0,0 +1,174 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Finality of the blocks under the rolling finality rules.
+//!
+//! A block is labelled once, when it is accepted. Since a final block
+//! finalizes all the blocks preceding it, a block labelled as accepted or
+//! attested is final as soon as any block following it is, regardless of the
+//! label it was stored with.
+
+use anyhow::{anyhow, Result};
+use node_data::ledger::Label;
+
+use crate::database::rocksdb::{MD_HASH_KEY, MD_LAST_FINAL};
+use crate::database::{Ledger, Metadata};
+
+/// Finality of a block, from the weakest to the strongest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Finality {
+    /// The block was accepted without a quorum on its certificate, and can
+    /// still be replaced by a fallback
+    Accepted,
+    /// The block was accepted with a quorum, but not enough blocks followed
+    /// it to make it final
+    Attested,
+    /// The block cannot be reverted
+    Final,
+}
+
+impl Finality {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accepted => "accepted",
+            Self::Attested => "attested",
+            Self::Final => "final",
+        }
+    }
+}
+
+impl From<Label> for Finality {
+    fn from(label: Label) -> Self {
+        match label {
+            Label::Accepted => Self::Accepted,
+            Label::Attested => Self::Attested,
+            Label::Final => Self::Final,
+        }
+    }
+}
+
+/// Finality of a block of the ledger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockFinality {
+    pub height: u64,
+    pub hash: [u8; 32],
+    pub finality: Finality,
+    /// Number of blocks accepted on top of the block
+    pub confirmations: u64,
+    /// Height of the most recent final block
+    pub last_final: u64,
+}
+
+/// Records the block at `height` as the most recent final one.
+pub(crate) fn record<T: Metadata>(t: &T, height: u64) -> Result<()> {
+    t.op_write(MD_LAST_FINAL, height.to_le_bytes())
+}
+
+/// Returns the height of the most recent final block.
+///
+/// Only the genesis block is final until a final block is recorded, which
+/// the databases predating the record are migrated to.
+pub fn last_final<T: Metadata>(t: &T) -> Result<u64> {
+    let Some(bytes) = t.op_read(MD_LAST_FINAL)? else {
+        return Ok(0);
+    };
+
+    let bytes = bytes
+        .try_into()
+        .map_err(|_| anyhow!("invalid last final height"))?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Returns the finality of the block at `height`, if in the ledger.
+pub fn fetch<T: Ledger + Metadata>(
+    t: &T,
+    height: u64,
+) -> Result<Option<BlockFinality>> {
+    let Some(label) = t.fetch_block_label_by_height(height)? else {
+        return Ok(None);
+    };
+    let hash = t
+        .fetch_block_hash_by_height(height)?
+        .ok_or_else(|| anyhow!("missing block at height {height}"))?;
+
+    let last_final = last_final(t)?;
+    let finality = match height <= last_final {
+        true => Finality::Final,
+        false => label.into(),
+    };
+
+    Ok(Some(BlockFinality {
+        height,
+        hash,
+        finality,
+        confirmations: tip_height(t)?.saturating_sub(height),
+        last_final,
+    }))
+}
+
+/// Returns the height of the tip of the ledger.
+pub fn tip_height<T: Ledger + Metadata>(t: &T) -> Result<u64> {
+    let Some(tip) = t.op_read(MD_HASH_KEY)? else {
+        return Ok(0);
+    };
+    let (header, _) = t
+        .fetch_block_header(&tip)?
+        .ok_or_else(|| anyhow!("missing tip header"))?;
+    Ok(header.height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use fake::{Fake, Faker};
+    use node_data::ledger::Header;
+
+    use crate::database::rocksdb::Backend;
+    use crate::database::DB;
+
+    #[test]
+    fn test_rolling_finality() {
+        let dir = tempdir::TempDir::new("test_rolling_finality").unwrap();
+        let db = Backend::create_or_open(dir.path());
+
+        let labels = [
+            Label::Final,
+            Label::Attested,
+            Label::Accepted,
+            Label::Attested,
+            Label::Attested,
+        ];
+        db.update(|t| {
+            for (height, label) in labels.iter().enumerate() {
+                let mut header: Header = Faker.fake();
+                header.height = height as u64;
+                header.hash = [height as u8; 32];
+                t.store_block(&header, &[], *label)?;
+                t.op_write(MD_HASH_KEY, header.hash)?;
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        // Only the genesis block is final until a final block is recorded
+        let finality = |height| {
+            db.view(|t| fetch(&t, height))
+                .unwrap()
+                .map(|f| (f.finality, f.confirmations, f.last_final))
+        };
+        assert_eq!(finality(0), Some((Finality::Final, 4, 0)));
+        assert_eq!(finality(1), Some((Finality::Attested, 3, 0)));
+        assert_eq!(finality(2), Some((Finality::Accepted, 2, 0)));
+        assert_eq!(finality(5), None);
+
+        // A final block finalizes the blocks preceding it
+        db.update(|t| record(t, 3)).unwrap();
+        assert_eq!(finality(1), Some((Finality::Final, 3, 3)));
+        assert_eq!(finality(2), Some((Finality::Final, 2, 3)));
+        assert_eq!(finality(4), Some((Finality::Attested, 0, 3)));
+    }
+}
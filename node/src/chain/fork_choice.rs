@@ -0,0 +1,186 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use dusk_consensus::config::ConsensusParams;
+use node_data::ledger::Header;
+
+/// Block competing for a height, along with the committee credits of the
+/// votes of its certificate.
+pub struct Contender<'a> {
+    pub header: &'a Header,
+    /// Credits of the validation and ratification votes, summed up
+    pub credits: usize,
+}
+
+/// Rule deciding which of two competing blocks, built on top of the same
+/// parent, belongs to the chain.
+pub trait ForkChoice: Send + Sync {
+    /// Returns `Ok` if the block `remote` should replace the block `local`,
+    /// or the reason why it should not.
+    fn choose(&self, local: &Contender, remote: &Contender) -> Result<()>;
+
+    fn name(&self) -> &'static str;
+}
+
+/// Fork-choice rule of a network.
+///
+/// All the nodes of a network must apply the same rule, or they would settle
+/// on different branches: it is thus given by the [`ConsensusParams`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// The block produced at the lowest iteration wins
+    LowestIteration,
+    /// The block whose certificate gathered the most committee credits wins
+    CertificateWeight,
+}
+
+impl Rule {
+    /// Returns the rule choosing among the blocks at `block_height`.
+    pub fn at(params: &ConsensusParams, block_height: u64) -> Self {
+        match block_height < params.certificate_weight_height {
+            true => Rule::LowestIteration,
+            false => Rule::CertificateWeight,
+        }
+    }
+
+    pub fn into_fork_choice(self) -> Arc<dyn ForkChoice> {
+        match self {
+            Rule::LowestIteration => Arc::new(LowestIteration),
+            Rule::CertificateWeight => Arc::new(CertificateWeight),
+        }
+    }
+}
+
+/// Prefers the block produced at the lowest iteration, as an earlier
+/// iteration can only be reached if the later ones have not been.
+pub struct LowestIteration;
+
+impl ForkChoice for LowestIteration {
+    fn choose(&self, local: &Contender, remote: &Contender) -> Result<()> {
+        let (local, remote) = (local.header, remote.header);
+        match remote.iteration.cmp(&local.iteration) {
+            Ordering::Greater => Err(anyhow!(
+                "iteration {:?} is higher than the current {:?}",
+                remote.iteration,
+                local.iteration
+            )),
+            Ordering::Equal => Err(anyhow!(
+                "iteration is equal to the current {:?}",
+                local.iteration
+            )), // TODO: This may be a slashing condition
+            Ordering::Less => Ok(()),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "lowest_iteration"
+    }
+}
+
+/// Prefers the block whose certificate carries the most committee credits,
+/// falling back to the lowest iteration on ties.
+///
+/// A provisioner may hold several credits in a committee, thus the credits
+/// rather than the voters are compared.
+pub struct CertificateWeight;
+
+impl ForkChoice for CertificateWeight {
+    fn choose(&self, local: &Contender, remote: &Contender) -> Result<()> {
+        match remote.credits.cmp(&local.credits) {
+            Ordering::Greater => Ok(()),
+            Ordering::Less => Err(anyhow!(
+                "certificate credits {} are lower than the current {}",
+                remote.credits,
+                local.credits
+            )),
+            Ordering::Equal => LowestIteration.choose(local, remote),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "certificate_weight"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(iteration: u8) -> Header {
+        Header {
+            iteration,
+            ..Default::default()
+        }
+    }
+
+    fn contender(header: &Header, credits: usize) -> Contender {
+        Contender { header, credits }
+    }
+
+    #[test]
+    fn test_lowest_iteration() {
+        let fc = Rule::LowestIteration.into_fork_choice();
+
+        let (h1, h2, h3) = (header(1), header(2), header(3));
+        let local = contender(&h2, 64);
+        assert!(fc.choose(&local, &contender(&h1, 1)).is_ok());
+        assert!(fc.choose(&local, &contender(&h2, 64)).is_err());
+        assert!(fc.choose(&local, &contender(&h3, 128)).is_err());
+    }
+
+    #[test]
+    fn test_certificate_weight() {
+        let fc = Rule::CertificateWeight.into_fork_choice();
+
+        let (h0, h1, h2, h3) = (header(0), header(1), header(2), header(3));
+        let local = contender(&h1, 90);
+
+        // Heavier branch wins regardless of the iteration
+        assert!(fc.choose(&local, &contender(&h3, 91)).is_ok());
+
+        // Lighter branch loses regardless of the iteration
+        assert!(fc.choose(&local, &contender(&h0, 89)).is_err());
+
+        // Ties are broken by the lowest iteration
+        assert!(fc.choose(&local, &contender(&h0, 90)).is_ok());
+        assert!(fc.choose(&local, &contender(&h1, 90)).is_err());
+        assert!(fc.choose(&local, &contender(&h2, 90)).is_err());
+    }
+
+    #[test]
+    fn test_rule_activation() {
+        let params = ConsensusParams {
+            certificate_weight_height: 10,
+            ..Default::default()
+        };
+        assert_eq!(Rule::at(&params, 9), Rule::LowestIteration);
+        assert_eq!(Rule::at(&params, 10), Rule::CertificateWeight);
+
+        let default = ConsensusParams::default();
+        assert_eq!(Rule::at(&default, u64::MAX - 1), Rule::LowestIteration);
+    }
+
+    #[test]
+    fn test_rules_disagree_on_competing_branches() {
+        // A late block certified by a larger quorum competing with an early
+        // one certified by a smaller quorum
+        let (h0, h1) = (header(0), header(1));
+        let early = contender(&h0, 70);
+        let late = contender(&h1, 120);
+
+        let lowest = Rule::LowestIteration.into_fork_choice();
+        assert!(lowest.choose(&late, &early).is_ok());
+        assert!(lowest.choose(&early, &late).is_err());
+
+        let weight = Rule::CertificateWeight.into_fork_choice();
+        assert!(weight.choose(&early, &late).is_ok());
+        assert!(weight.choose(&late, &early).is_err());
+    }
+}
@@ -28,6 +28,10 @@ use tracing::{debug, error, info, warn};
 const MAX_BLOCKS_TO_REQUEST: i16 = 50;
 const EXPIRY_TIMEOUT_MILLIS: i16 = 5000;
 
+/// Number of consecutive blocks pooled while syncing up before they are
+/// accepted, their certificates being verified in parallel
+const PREVALIDATION_WINDOW: u64 = 16;
+
 pub(crate) const REDUNDANCY_PEER_FACTOR: usize = 5;
 
 type SharedHashSet = Arc<RwLock<HashSet<[u8; 32]>>>;
@@ -94,6 +98,10 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution> SimpleFSM<N, DB, VM> {
     }
 
     pub async fn on_idle(&mut self, timeout: Duration) {
+        // No block has been accepted within the timeout. Let the acceptor
+        // track it to detect a stalled consensus.
+        self.acc.write().await.on_stalled_round();
+
         let acc = self.acc.read().await;
         let height = acc.get_curr_height().await;
         let iter = acc.get_curr_iteration().await;
@@ -127,6 +135,41 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution> SimpleFSM<N, DB, VM> {
         }
     }
 
+    /// Triggers a resync after the tip has been detected stale.
+    ///
+    /// Blocks following the last finalized one are requested, both to a
+    /// peer that reported a height ahead of the tip and to random alive
+    /// peers, so that either the missing blocks or a competing branch to
+    /// fall back to are received.
+    ///
+    /// The blacklisted blocks are kept, so that the resync does not bring
+    /// back a branch already fallen back from.
+    pub async fn on_stale_tip(&mut self, peer: SocketAddr) {
+        let last_finalized =
+            match self.acc.read().await.get_latest_final_block().await {
+                Ok(blk) => blk,
+                Err(err) => {
+                    error!(event = "stale tip resync failed", ?err);
+                    return;
+                }
+            };
+
+        let get_blocks = Message::new_get_blocks(GetBlocks {
+            locator: last_finalized.header().hash,
+        });
+
+        let network = self.network.read().await;
+        if let Err(e) = network.send_to_peer(&get_blocks, peer).await {
+            warn!("Unable to send GetBlocks to {peer}: {e}");
+        }
+        if let Err(e) = network
+            .send_to_alive_peers(&get_blocks, REDUNDANCY_PEER_FACTOR)
+            .await
+        {
+            warn!("Unable to request GetBlocks {e}");
+        }
+    }
+
     pub async fn on_failed_consensus(&mut self) {
         self.acc.write().await.restart_consensus().await;
     }
@@ -398,23 +441,22 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> InSyncImpl<DB, VM, N> {
             // and we receive a block R_B such that:
             //
             // R_B.PrevBlock == L_B.PrevBlock
-            // R_B.Iteration < L_B.Iteration
+            // R_B is preferred over L_B by the fork-choice rule
             //
             // Then we fallback to N_B.PrevBlock and accept N_B
+            //
+            // The fork-choice rule compares the committee credits of both
+            // certificates, thus it is applied by the fallback itself
             let result = acc.db.read().await.view(|t| {
                 if let Some((prev_header, _)) =
                     t.fetch_block_header(&remote_blk.header().prev_block_hash)?
                 {
                     let local_height = prev_header.height + 1;
                     if let Some(l_b) = t.fetch_block_by_height(local_height)? {
-                        if remote_blk.header().iteration
-                            < l_b.header().iteration
-                        {
-                            return Ok(Some((
-                                l_b.header().clone(),
-                                prev_header.state_hash,
-                            )));
-                        }
+                        return Ok(Some((
+                            l_b.header().clone(),
+                            prev_header.state_hash,
+                        )));
                     }
                 }
 
@@ -677,7 +719,8 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network>
         blk: &Block,
         metadata: Option<Metadata>,
     ) -> anyhow::Result<bool> {
-        let mut acc = self.acc.write().await;
+        let acc = self.acc.clone();
+        let mut acc = acc.write().await;
         let h = blk.header().height;
 
         if self
@@ -692,14 +735,30 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network>
             return Ok(true);
         }
 
-        if h <= acc.get_curr_height().await {
+        let curr_height = acc.get_curr_height().await;
+        if h <= curr_height {
             return Ok(false);
         }
 
-        // Try accepting consecutive block
-        if h == acc.get_curr_height().await + 1 {
-            acc.try_accept_block(blk, false).await?;
+        // add block to the pool, always making room for the one following
+        // the tip
+        if h == curr_height + 1
+            || self.pool.len() < MAX_BLOCKS_TO_REQUEST as usize
+        {
+            self.pool.insert(h, blk.clone());
+            debug!(event = "block saved", len = self.pool.len());
+        }
+
+        // Blocks arriving in order are pooled until a full window of them
+        // can be verified at once
+        let pooled = ((curr_height + 1)..(self.range.1 + 1))
+            .take_while(|height| self.pool.contains_key(height))
+            .count() as u64;
+        if !window_ready(pooled, curr_height, self.range.1) {
+            return Ok(false);
+        }
 
+        if self.accept_pooled(&mut acc).await? > 0 {
             if let Some(metadata) = &metadata {
                 if metadata.src_addr == self.peer_addr {
                     // reset expiry_time only if we receive a valid block from
@@ -707,42 +766,59 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network>
                     self.start_time = SystemTime::now();
                 }
             }
+        }
 
-            // Try to accept other consecutive blocks from the pool, if
-            // available
-            for height in (h + 1)..(self.range.1 + 1) {
-                if let Some(blk) = self.pool.get(&height) {
-                    acc.try_accept_block(blk, false).await?;
-                } else {
-                    break;
-                }
-            }
+        // Check target height is reached
+        if acc.get_curr_height().await == self.range.1 {
+            // Block sync-up procedure manages to download all requested
+            // blocks
+            acc.restart_consensus().await;
 
-            // Check target height is reached
-            if acc.get_curr_height().await == self.range.1 {
-                // Block sync-up procedure manages to download all requested
-                // blocks
-                acc.restart_consensus().await;
+            // Transit to InSync mode
+            return Ok(true);
+        }
 
-                // Transit to InSync mode
-                return Ok(true);
-            }
+        Ok(false)
+    }
 
-            return Ok(false);
+    /// Accepts in order the pooled blocks following the tip, after verifying
+    /// their certificates in parallel. Returns the number of accepted blocks.
+    async fn accept_pooled(
+        &mut self,
+        acc: &mut Acceptor<N, DB, VM>,
+    ) -> anyhow::Result<u64> {
+        let curr_height = acc.get_curr_height().await;
+        let window: Vec<_> = ((curr_height + 1)..(self.range.1 + 1))
+            .map_while(|height| self.pool.get(&height))
+            .collect();
+        acc.prevalidate_synced_blocks(&window).await;
+
+        let mut accepted = 0;
+        for blk in window {
+            acc.try_accept_synced_block(blk).await?;
+            accepted += 1;
         }
 
-        // add block to the pool
-        if self.pool.len() < MAX_BLOCKS_TO_REQUEST as usize {
-            let key = blk.header().height;
-            self.pool.insert(key, blk.clone());
+        for height in (curr_height + 1)..=(curr_height + accepted) {
+            self.pool.remove(&height);
         }
-
-        debug!(event = "block saved", len = self.pool.len());
-
-        Ok(false)
+        Ok(accepted)
     }
 
     async fn on_heartbeat(&mut self) -> anyhow::Result<bool> {
+        // Accept the blocks pooled short of a full window, should no more
+        // blocks arrive
+        {
+            let acc = self.acc.clone();
+            let mut acc = acc.write().await;
+            self.accept_pooled(&mut acc).await?;
+
+            if acc.get_curr_height().await == self.range.1 {
+                acc.restart_consensus().await;
+                return Ok(true);
+            }
+        }
+
         if self
             .start_time
             .checked_add(Duration::from_millis(EXPIRY_TIMEOUT_MILLIS as u64))
@@ -760,6 +836,14 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network>
     }
 }
 
+/// Returns whether the `pooled` consecutive blocks following the tip at
+/// `curr_height` are to be accepted: once they fill a window, or once they
+/// are all the blocks left to sync up to `target`.
+fn window_ready(pooled: u64, curr_height: u64, target: u64) -> bool {
+    let left = target.saturating_sub(curr_height);
+    pooled > 0 && pooled >= PREVALIDATION_WINDOW.min(left)
+}
+
 enum BlockRequest {
     ByHeight(u64),
     ByHash([u8; 32]),
@@ -804,3 +888,23 @@ async fn request_block<N: Network>(
         warn!("could not request block {err}")
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_ready() {
+        let target = 100;
+
+        // Blocks are pooled until a full window follows the tip
+        assert!(!window_ready(0, 10, target));
+        assert!(!window_ready(PREVALIDATION_WINDOW - 1, 10, target));
+        assert!(window_ready(PREVALIDATION_WINDOW, 10, target));
+
+        // The last blocks to sync up are accepted once all pooled
+        assert!(!window_ready(2, 97, target));
+        assert!(window_ready(3, 97, target));
+        assert!(!window_ready(0, target, target));
+    }
+}
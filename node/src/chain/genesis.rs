@@ -7,12 +7,13 @@
 use node_data::ledger::{Block, Header};
 
 /// Generates the genesis state for the chain per specified network type
-pub(crate) fn generate_state(state_hash: [u8; 32]) -> Block {
+pub(crate) fn generate_state(state_hash: [u8; 32], chain_id: u8) -> Block {
     Block::new(
         Header {
             // Mon Mar 25 2024 11:00:00 GMT+0000
             timestamp: 1711364400,
             state_hash,
+            chain_id,
             ..Default::default()
         },
         vec![],
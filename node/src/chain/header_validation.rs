@@ -4,14 +4,19 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use crate::chain::checkpoint::Checkpoint;
 use crate::database;
 use crate::database::Ledger;
 use anyhow::anyhow;
 use dusk_bytes::Serializable;
+use dusk_consensus::commons::get_current_timestamp;
+use dusk_consensus::config::{ConsensusParams, MAX_BLOCK_TIMESTAMP_DRIFT};
 use dusk_consensus::quorum::verifiers;
 use dusk_consensus::quorum::verifiers::QuorumResult;
 use dusk_consensus::user::committee::CommitteeSet;
 use dusk_consensus::user::provisioners::{ContextProvisioners, Provisioners};
+use node_data::bls::PublicKeyBytes;
+use node_data::error::{Classified, ErrorKind};
 use node_data::ledger::to_str;
 use node_data::ledger::Signature;
 use node_data::message::payload::RatificationResult;
@@ -20,7 +25,7 @@ use node_data::{ledger, StepName};
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{debug, info, warn};
 
 // TODO: Use thiserror instead of anyhow
 
@@ -33,6 +38,8 @@ pub(crate) struct Validator<'a, DB: database::DB> {
     pub(crate) db: Arc<RwLock<DB>>,
     prev_header: &'a ledger::Header,
     provisioners: &'a ContextProvisioners,
+    params: &'a ConsensusParams,
+    checkpoint: Option<&'a Checkpoint>,
 }
 
 impl<'a, DB: database::DB> Validator<'a, DB> {
@@ -40,11 +47,15 @@ impl<'a, DB: database::DB> Validator<'a, DB> {
         db: Arc<RwLock<DB>>,
         prev_header: &'a ledger::Header,
         provisioners: &'a ContextProvisioners,
+        params: &'a ConsensusParams,
+        checkpoint: Option<&'a Checkpoint>,
     ) -> Self {
         Self {
             db,
             prev_header,
             provisioners,
+            params,
+            checkpoint,
         }
     }
 
@@ -77,8 +88,14 @@ impl<'a, DB: database::DB> Validator<'a, DB> {
         &self,
         candidate_block: &'a ledger::Header,
     ) -> anyhow::Result<()> {
-        if candidate_block.version > 0 {
-            return Err(anyhow!("unsupported block version"));
+        verify_version(self.params, candidate_block)?;
+
+        if candidate_block.chain_id != self.prev_header.chain_id {
+            return Err(anyhow!(
+                "invalid chain id: {}, expected: {}",
+                candidate_block.chain_id,
+                self.prev_header.chain_id,
+            ));
         }
 
         if candidate_block.hash == [0u8; 32] {
@@ -97,42 +114,34 @@ impl<'a, DB: database::DB> Validator<'a, DB> {
             return Err(anyhow!("invalid previous block hash"));
         }
 
-        // Ensure block is not already in the ledger
-        self.db.read().await.view(|v| {
-            if Ledger::get_block_exists(&v, &candidate_block.hash)? {
-                return Err(anyhow!("block already exists"));
-            }
+        verify_timestamp(
+            self.params,
+            self.prev_header,
+            candidate_block,
+            get_current_timestamp(),
+        )?;
 
-            Ok(())
-        })?;
+        // Ensure block is not already in the ledger
+        let exists = self
+            .db
+            .read()
+            .await
+            .view(|v| Ledger::get_block_exists(&v, &candidate_block.hash))
+            .map_err(transient)?;
+        if exists {
+            return Err(anyhow!("block already exists"));
+        }
 
         // Verify seed field
-        self.verify_seed_field(
-            candidate_block.seed.inner(),
-            candidate_block.generator_bls_pubkey.inner(),
+        verify_seed(
+            &self.prev_header.seed,
+            &candidate_block.seed,
+            &candidate_block.generator_bls_pubkey,
         )?;
 
         Ok(())
     }
 
-    fn verify_seed_field(
-        &self,
-        seed: &[u8; 48],
-        pk_bytes: &[u8; 96],
-    ) -> anyhow::Result<()> {
-        let pk = dusk_bls12_381_sign::PublicKey::from_bytes(pk_bytes)
-            .map_err(|err| anyhow!("invalid pk bytes: {:?}", err))?;
-
-        let signature = dusk_bls12_381_sign::Signature::from_bytes(seed)
-            .map_err(|err| anyhow!("invalid signature bytes: {}", err))?;
-
-        dusk_bls12_381_sign::APK::from(&pk)
-            .verify(&signature, &self.prev_header.seed.inner()[..])
-            .map_err(|err| anyhow!("invalid seed: {:?}", err))?;
-
-        Ok(())
-    }
-
     pub async fn verify_prev_block_cert(
         &self,
         candidate_block: &'a ledger::Header,
@@ -141,18 +150,42 @@ impl<'a, DB: database::DB> Validator<'a, DB> {
             return Ok(());
         }
 
-        let prev_block_seed = self.db.read().await.view(|v| {
-            let prior_tip =
-                Ledger::fetch_block_by_height(&v, self.prev_header.height - 1)?
-                    .ok_or_else(|| anyhow::anyhow!("could not fetch block"))?;
+        // A block accepted in emergency mode is certified by the checkpoint
+        // keys instead of a consensus quorum. Only a block that was itself
+        // accepted as a checkpoint block, past the stall gate, can be.
+        if let Some(checkpoint) = self.checkpoint {
+            let cert = &candidate_block.prev_block_cert;
+            if Checkpoint::is_checkpoint_cert(cert) {
+                if !Checkpoint::is_checkpoint_cert(&self.prev_header.cert) {
+                    return Err(anyhow!(
+                        "checkpoint certificate for a consensus block"
+                    ));
+                }
+                return checkpoint.verify(&self.prev_header.hash, cert);
+            }
+        }
 
-            Ok::<_, anyhow::Error>(prior_tip.header().seed)
-        })?;
+        let prev_block_seed = self
+            .db
+            .read()
+            .await
+            .view(|v| {
+                let prior_tip = Ledger::fetch_block_by_height(
+                    &v,
+                    self.prev_header.height - 1,
+                )?
+                .ok_or_else(|| anyhow::anyhow!("could not fetch block"))?;
+
+                Ok::<_, anyhow::Error>(prior_tip.header().seed)
+            })
+            .map_err(transient)?;
 
         verify_block_cert(
+            self.prev_header.chain_id,
             self.prev_header.prev_block_hash,
             prev_block_seed,
             self.provisioners.prev(),
+            self.params,
             self.prev_header.height,
             &candidate_block.prev_block_cert,
             self.prev_header.iteration,
@@ -170,82 +203,364 @@ impl<'a, DB: database::DB> Validator<'a, DB> {
         &self,
         candidate_block: &'a ledger::Header,
     ) -> anyhow::Result<bool> {
-        // Verify Failed iterations
-        let mut all_failed = true;
-
-        for (iter, cert) in candidate_block
-            .failed_iterations
-            .cert_list
-            .iter()
-            .enumerate()
+        verify_failed_iterations(
+            self.prev_header,
+            self.provisioners.current(),
+            self.params,
+            candidate_block,
+        )
+        .await
+    }
+
+    pub async fn verify_winning_cert(
+        &self,
+        candidate_block: &'a ledger::Header,
+    ) -> anyhow::Result<()> {
+        verify_winning_cert(
+            self.prev_header,
+            self.provisioners.current(),
+            self.params,
+            candidate_block,
+        )
+        .await
+    }
+}
+
+async fn verify_failed_iterations(
+    prev_header: &ledger::Header,
+    provisioners: &Provisioners,
+    params: &ConsensusParams,
+    candidate_block: &ledger::Header,
+) -> anyhow::Result<bool> {
+    // Verify Failed iterations
+    let mut all_failed = true;
+
+    for (iter, cert) in candidate_block
+        .failed_iterations
+        .cert_list
+        .iter()
+        .enumerate()
+    {
+        if let Some((cert, pk)) = cert {
+            info!(event = "verify_cert", cert_type = "failed_cert", iter);
+
+            if let RatificationResult::Success(_) = cert.result {
+                anyhow::bail!("Failed iterations should not contains a RatificationResult::Success");
+            }
+
+            let expected_pk = provisioners.get_generator(
+                iter as u8,
+                prev_header.seed,
+                candidate_block.height,
+            );
+
+            anyhow::ensure!(
+                pk == &expected_pk,
+                "Invalid generator. Expected {expected_pk:?}, actual {pk:?}"
+            );
+
+            let quorums = verify_block_cert(
+                prev_header.chain_id,
+                prev_header.hash,
+                prev_header.seed,
+                provisioners,
+                params,
+                candidate_block.height,
+                cert,
+                iter as u8,
+            )
+            .await?;
+
+            // Ratification quorum is enough to consider the iteration
+            // failed
+            all_failed = all_failed && quorums.1.quorum_reached();
+        } else {
+            all_failed = false;
+        }
+    }
+
+    Ok(all_failed)
+}
+
+async fn verify_winning_cert(
+    prev_header: &ledger::Header,
+    provisioners: &Provisioners,
+    params: &ConsensusParams,
+    candidate_block: &ledger::Header,
+) -> anyhow::Result<()> {
+    verify_block_cert(
+        prev_header.chain_id,
+        prev_header.hash,
+        prev_header.seed,
+        provisioners,
+        params,
+        candidate_block.height,
+        &candidate_block.cert,
+        candidate_block.iteration,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Verifies at once the certificates of a chain of consecutive headers
+/// following `tip`, as downloaded while syncing up.
+///
+/// The certificates of each header only depend on the headers preceding it
+/// and on the provisioners, which are assumed not to change along the chain.
+/// They are thus verified in parallel, one blocking task per header since the
+/// verification is CPU bound.
+///
+/// * `tip_prev_seed` - seed of the block preceding `tip`, if any
+/// * `verified` - whether the certificates of a header were already verified,
+///   in which case they are not verified again
+///
+/// Returns, for each header, whether each of its failed iterations has a
+/// quorum, or `None` if any of its certificates could not be verified or
+/// were already. The headers following the first one not chained to its
+/// predecessor are left out.
+pub(crate) async fn verify_certs_in_parallel(
+    tip: &ledger::Header,
+    tip_prev_seed: Option<ledger::Seed>,
+    headers: Vec<ledger::Header>,
+    verified: impl Fn(&ledger::Header) -> bool,
+    provisioners: &ContextProvisioners,
+    params: &ConsensusParams,
+) -> Vec<Option<bool>> {
+    let prev = Arc::new(provisioners.prev().clone());
+    let current = Arc::new(provisioners.to_current());
+    let runtime = tokio::runtime::Handle::current();
+
+    let mut tasks = Vec::with_capacity(headers.len());
+    let mut prev_header = tip.clone();
+    let mut prev_prev_seed = tip_prev_seed;
+    for (i, header) in headers.into_iter().enumerate() {
+        if header.prev_block_hash != prev_header.hash
+            || header.height != prev_header.height + 1
         {
-            if let Some((cert, pk)) = cert {
-                info!(event = "verify_cert", cert_type = "failed_cert", iter);
+            break;
+        }
 
-                if let RatificationResult::Success(_) = cert.result {
-                    anyhow::bail!("Failed iterations should not contains a RatificationResult::Success");
+        if verified(&header) {
+            tasks.push(None);
+            prev_prev_seed = Some(prev_header.seed);
+            prev_header = header;
+            continue;
+        }
+
+        // The committees certifying the tip were drawn from the provisioners
+        // preceding it, the following ones from the current provisioners
+        let prev_provisioners = match i {
+            0 => prev.clone(),
+            _ => current.clone(),
+        };
+        let current = current.clone();
+        let params = *params;
+        let next_prev_seed = Some(prev_header.seed);
+        let next_prev_header = header.clone();
+
+        let runtime = runtime.clone();
+        let task = tokio::task::spawn_blocking(move || {
+            runtime.block_on(async move {
+                if Checkpoint::is_checkpoint_cert(&header.cert)
+                    || Checkpoint::is_checkpoint_cert(&header.prev_block_cert)
+                {
+                    return Ok(None);
                 }
 
-                let expected_pk = self.provisioners.current().get_generator(
-                    iter as u8,
-                    self.prev_header.seed,
-                    candidate_block.height,
-                );
-
-                anyhow::ensure!(pk == &expected_pk, "Invalid generator. Expected {expected_pk:?}, actual {pk:?}");
-
-                let quorums = verify_block_cert(
-                    self.prev_header.hash,
-                    self.prev_header.seed,
-                    self.provisioners.current(),
-                    candidate_block.height,
-                    cert,
-                    iter as u8,
+                if prev_header.height > 0 {
+                    let prev_prev_seed = prev_prev_seed
+                        .ok_or_else(|| anyhow!("missing previous seed"))?;
+                    verify_block_cert(
+                        prev_header.chain_id,
+                        prev_header.prev_block_hash,
+                        prev_prev_seed,
+                        &prev_provisioners,
+                        &params,
+                        prev_header.height,
+                        &header.prev_block_cert,
+                        prev_header.iteration,
+                    )
+                    .await?;
+                }
+
+                verify_winning_cert(&prev_header, &current, &params, &header)
+                    .await?;
+                verify_failed_iterations(
+                    &prev_header,
+                    &current,
+                    &params,
+                    &header,
                 )
-                .await?;
+                .await
+                .map(Some)
+            })
+        });
+        tasks.push(Some(task));
+
+        prev_prev_seed = next_prev_seed;
+        prev_header = next_prev_header;
+    }
 
-                // Ratification quorum is enough to consider the iteration
-                // failed
-                all_failed = all_failed && quorums.1.quorum_reached();
-            } else {
-                all_failed = false;
+    let mut results = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        let Some(task) = task else {
+            results.push(None);
+            continue;
+        };
+        let res = match task.await {
+            Ok(Ok(attested)) => attested,
+            Ok(Err(err)) => {
+                debug!(event = "certs not verified", %err);
+                None
             }
-        }
+            Err(err) => {
+                warn!(event = "certs verification failed", %err);
+                None
+            }
+        };
+        results.push(res);
+    }
+    results
+}
 
-        Ok(all_failed)
+/// Ensures a block header is of a version supported at its height.
+///
+/// Headers of version [`HEADER_VERSION_EXTENSIBLE`] are accepted from
+/// [`ConsensusParams::header_extensions_height`], the legacy ones remaining
+/// valid.
+///
+/// [`HEADER_VERSION_EXTENSIBLE`]: ledger::HEADER_VERSION_EXTENSIBLE
+fn verify_version(
+    params: &ConsensusParams,
+    candidate_block: &ledger::Header,
+) -> anyhow::Result<()> {
+    let max_version = params.header_version(candidate_block.height);
+    if candidate_block.version > max_version {
+        return Err(anyhow!(
+            "unsupported block version: {}, expected at most: {max_version}",
+            candidate_block.version,
+        ));
     }
 
-    pub async fn verify_winning_cert(
-        &self,
-        candidate_block: &'a ledger::Header,
-    ) -> anyhow::Result<()> {
-        verify_block_cert(
-            self.prev_header.hash,
-            self.prev_header.seed,
-            self.provisioners.current(),
-            candidate_block.height,
-            &candidate_block.cert,
-            candidate_block.iteration,
-        )
-        .await?;
+    Ok(())
+}
 
-        Ok(())
+/// Ensures a block timestamp follows the one of the previous block, and is
+/// not ahead of the local time `now` by more than
+/// [`MAX_BLOCK_TIMESTAMP_DRIFT`].
+///
+/// Contracts read the block timestamp, so its generator must not be free to
+/// pick it. The blocks below [`ConsensusParams::block_timestamps_height`]
+/// are not verified.
+fn verify_timestamp(
+    params: &ConsensusParams,
+    prev_header: &ledger::Header,
+    candidate_block: &ledger::Header,
+    now: u64,
+) -> anyhow::Result<()> {
+    if candidate_block.height < params.block_timestamps_height {
+        return Ok(());
+    }
+
+    if candidate_block.timestamp <= prev_header.timestamp {
+        return Err(anyhow!(
+            "block timestamp {} not after the previous one {}",
+            candidate_block.timestamp,
+            prev_header.timestamp,
+        ));
+    }
+
+    let max_timestamp = now.saturating_add(MAX_BLOCK_TIMESTAMP_DRIFT.as_secs());
+    if candidate_block.timestamp > max_timestamp {
+        // Not a verdict on the block, which may be valid later on
+        let err = anyhow!(
+            "block timestamp {} ahead of the local time {now}",
+            candidate_block.timestamp,
+        );
+        return Err(transient(err));
+    }
+
+    Ok(())
+}
+
+/// Tags a failure to read the local ledger, which says nothing about the
+/// validity of the header checked.
+fn transient(err: anyhow::Error) -> anyhow::Error {
+    Classified::new(ErrorKind::Transient, err).into()
+}
+
+/// Ensures the seed of a block is the signature of the previous block seed by
+/// the block generator.
+///
+/// The seed chain drives the sortition of the generators and committees, so
+/// a generator must not be able to pick a seed of its choice.
+pub(crate) fn verify_seed(
+    prev_seed: &ledger::Seed,
+    seed: &ledger::Seed,
+    generator: &PublicKeyBytes,
+) -> anyhow::Result<()> {
+    let pk = dusk_bls12_381_sign::PublicKey::from_bytes(generator.inner())
+        .map_err(|err| anyhow!("invalid pk bytes: {:?}", err))?;
+
+    let signature = dusk_bls12_381_sign::Signature::from_bytes(seed.inner())
+        .map_err(|err| anyhow!("invalid signature bytes: {:?}", err))?;
+
+    dusk_bls12_381_sign::APK::from(&pk)
+        .verify(&signature, &prev_seed.inner()[..])
+        .map_err(|err| anyhow!("invalid seed: {:?}", err))?;
+
+    Ok(())
+}
+
+/// Ensures the transactions of a block are meant for the block's network and
+/// do not exceed the protocol size limits
+pub(crate) fn verify_block_txs(blk: &ledger::Block) -> anyhow::Result<()> {
+    verify_txs_chain_id(blk)?;
+    verify_block_size(blk, &ledger::SIZE_LIMITS)
+}
+
+/// Ensures all transactions of a block are meant for the block's network
+fn verify_txs_chain_id(blk: &ledger::Block) -> anyhow::Result<()> {
+    let chain_id = blk.header().chain_id;
+    if let Some(tx) = blk.txs().iter().find(|tx| tx.chain_id != chain_id) {
+        return Err(anyhow!(
+            "tx {} has chain id {}, expected: {chain_id}",
+            to_str(&tx.hash()),
+            tx.chain_id,
+        ));
     }
+
+    Ok(())
+}
+
+/// Ensures a block does not exceed the given size limits
+fn verify_block_size(
+    blk: &ledger::Block,
+    limits: &ledger::SizeLimits,
+) -> anyhow::Result<()> {
+    limits
+        .check_block(blk)
+        .map_err(|e| anyhow!("block {} {e}", to_str(&blk.header().hash)))
 }
 
 pub async fn verify_block_cert(
+    chain_id: u8,
     prev_block_hash: [u8; 32],
     curr_seed: Signature,
     curr_eligible_provisioners: &Provisioners,
+    params: &ConsensusParams,
     round: u64,
     cert: &ledger::Certificate,
     iteration: u8,
 ) -> anyhow::Result<(QuorumResult, QuorumResult)> {
-    let committee = RwLock::new(CommitteeSet::new(curr_eligible_provisioners));
+    let committee =
+        RwLock::new(CommitteeSet::new(curr_eligible_provisioners, *params));
 
     let mut result = (QuorumResult::default(), QuorumResult::default());
 
     let consensus_header = ConsensusHeader {
+        chain_id,
         iteration,
         round,
         prev_block_hash,
@@ -307,3 +622,207 @@ pub async fn verify_block_cert(
 
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dusk_bls12_381_sign::{PublicKey, SecretKey};
+    use fake::{Fake, Faker};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn sign_seed(sk: &SecretKey, prev_seed: &ledger::Seed) -> ledger::Seed {
+        let pk = PublicKey::from(sk);
+        ledger::Seed::from(sk.sign(&pk, prev_seed.inner()).to_bytes())
+    }
+
+    #[test]
+    fn test_verify_timestamp() {
+        let params = ConsensusParams {
+            block_timestamps_height: 0,
+            ..Default::default()
+        };
+        let now = 1_700_000_000;
+        let drift = MAX_BLOCK_TIMESTAMP_DRIFT.as_secs();
+        let prev = ledger::Header {
+            timestamp: now - 10,
+            ..Default::default()
+        };
+        let at = |timestamp| ledger::Header {
+            timestamp,
+            ..Default::default()
+        };
+        let verify = |header: &ledger::Header, now| {
+            verify_timestamp(&params, &prev, header, now)
+        };
+
+        assert!(verify(&at(now), now).is_ok());
+        assert!(verify(&at(now - 9), now).is_ok());
+        assert!(verify(&at(now + drift), now).is_ok());
+
+        // Timestamps must increase
+        assert!(verify(&at(now - 10), now).is_err());
+        assert!(verify(&at(0), now).is_err());
+
+        // A block ahead of the local time is rejected, but may be accepted
+        // later on
+        let err = verify(&at(now + drift + 1), now)
+            .expect_err("block to be ahead of the local time");
+        assert_eq!(node_data::error::kind_of(&err), Some(ErrorKind::Transient));
+        assert!(verify(&at(u64::MAX), u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_verify_version() {
+        use ledger::{HEADER_VERSION_EXTENSIBLE, HEADER_VERSION_LEGACY};
+
+        let params = ConsensusParams {
+            header_extensions_height: 10,
+            ..Default::default()
+        };
+        let at = |height, version| ledger::Header {
+            height,
+            version,
+            ..Default::default()
+        };
+        let verify = |header: &ledger::Header| verify_version(&params, header);
+
+        assert!(verify(&at(9, HEADER_VERSION_LEGACY)).is_ok());
+        assert!(verify(&at(9, HEADER_VERSION_EXTENSIBLE)).is_err());
+
+        // Both versions are accepted from the activation height
+        assert!(verify(&at(10, HEADER_VERSION_LEGACY)).is_ok());
+        assert!(verify(&at(10, HEADER_VERSION_EXTENSIBLE)).is_ok());
+        assert!(verify(&at(10, HEADER_VERSION_EXTENSIBLE + 1)).is_err());
+
+        // The generator emits the latest version
+        assert_eq!(params.header_version(9), HEADER_VERSION_LEGACY);
+        assert_eq!(params.header_version(10), HEADER_VERSION_EXTENSIBLE);
+
+        let params = ConsensusParams::default();
+        assert!(verify_version(&params, &at(10, HEADER_VERSION_EXTENSIBLE))
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_timestamp_activation() {
+        let params = ConsensusParams {
+            block_timestamps_height: 10,
+            ..Default::default()
+        };
+        let prev = ledger::Header {
+            height: 8,
+            timestamp: 1_700_000_000,
+            ..Default::default()
+        };
+        let at = |height| ledger::Header {
+            height,
+            timestamp: prev.timestamp,
+            ..Default::default()
+        };
+        let now = prev.timestamp;
+
+        // Blocks generated before the activation may repeat the timestamp of
+        // the previous block
+        assert!(verify_timestamp(&params, &prev, &at(9), now).is_ok());
+        assert!(verify_timestamp(&params, &prev, &at(10), now).is_err());
+
+        // The networks not given a height do not verify the timestamps yet
+        let params = ConsensusParams::default();
+        assert!(verify_timestamp(&params, &prev, &at(9), now).is_ok());
+    }
+
+    #[test]
+    fn test_verify_block_size() {
+        let blk: ledger::Block = Faker.fake();
+        let sizes: Vec<_> = blk.txs().iter().map(|tx| tx.size()).collect();
+        let tx_size = *sizes.iter().max().unwrap();
+        let block_size = sizes.iter().sum();
+        let limits = ledger::SizeLimits {
+            max_tx_bytes: tx_size,
+            max_block_txs: 3,
+            max_block_bytes: block_size,
+        };
+        verify_block_size(&blk, &limits).expect("block to fit the limits");
+
+        let tight = ledger::SizeLimits {
+            max_tx_bytes: tx_size - 1,
+            ..limits
+        };
+        assert!(verify_block_size(&blk, &tight).is_err());
+
+        let tight = ledger::SizeLimits {
+            max_block_txs: 2,
+            ..limits
+        };
+        assert!(verify_block_size(&blk, &tight).is_err());
+
+        let tight = ledger::SizeLimits {
+            max_block_bytes: block_size - 1,
+            ..limits
+        };
+        assert!(verify_block_size(&blk, &tight).is_err());
+
+        // Protocol limits apply regardless of the node configuration
+        let tx = blk.txs()[0].clone();
+        let txs = vec![tx; ledger::SIZE_LIMITS.max_block_txs + 1];
+        let blk = ledger::Block::new(blk.header().clone(), txs).unwrap();
+        assert!(verify_block_txs(&blk).is_err());
+    }
+
+    #[test]
+    fn test_verify_seed() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let sk = SecretKey::random(&mut rng);
+        let generator = PublicKeyBytes(PublicKey::from(&sk).to_bytes());
+
+        let prev_seed = ledger::Seed::from([1u8; 48]);
+        let seed = sign_seed(&sk, &prev_seed);
+        verify_seed(&prev_seed, &seed, &generator).expect("seed to be valid");
+
+        // Seed not chained to the previous one
+        let other_prev_seed = sign_seed(&sk, &prev_seed);
+        let seed = sign_seed(&sk, &other_prev_seed);
+        assert!(verify_seed(&prev_seed, &seed, &generator).is_err());
+
+        // Seed signed by someone else than the generator
+        let other_sk = SecretKey::random(&mut rng);
+        let seed = sign_seed(&other_sk, &prev_seed);
+        assert!(verify_seed(&prev_seed, &seed, &generator).is_err());
+
+        // Seed not being a signature
+        let seed = ledger::Seed::default();
+        assert!(verify_seed(&prev_seed, &seed, &generator).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_certs_in_parallel_skips_verified() {
+        let tip = ledger::Header::default();
+        let chained = |prev: &ledger::Header, hash: u8| ledger::Header {
+            height: prev.height + 1,
+            prev_block_hash: prev.hash,
+            hash: [hash; 32],
+            ..Default::default()
+        };
+
+        let first = chained(&tip, 1);
+        let second = chained(&first, 2);
+        let mut unchained = chained(&second, 3);
+        unchained.prev_block_hash = [0xff; 32];
+
+        let provisioners = ContextProvisioners::new(Provisioners::empty());
+        let results = verify_certs_in_parallel(
+            &tip,
+            None,
+            vec![first, second, unchained],
+            |_| true,
+            &provisioners,
+            &ConsensusParams::default(),
+        )
+        .await;
+
+        // Headers already verified are not verified again, and the ones
+        // following a header not chained to its predecessor are left out
+        assert_eq!(results, vec![None, None]);
+    }
+}
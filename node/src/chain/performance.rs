@@ -0,0 +1,434 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Consensus performance of the node's own provisioner.
+//!
+//! The performance is recorded by the consensus itself, as each round ends,
+//! so that operators can monitor their provisioner without indexing the
+//! chain. The votes of a round are the ones received back by the node,
+//! while the blocks are recorded once accepted, as the tip the next round
+//! is run on, along with the votes of the provisioner their certificate
+//! includes.
+//!
+//! Each round is stored in its own record, in a ring of
+//! [`PERFORMANCE_ROUNDS`] slots, so that a round only rewrites the records
+//! it touches.
+
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use dusk_consensus::config::ConsensusParams;
+use dusk_consensus::user::committee::CommitteeSet;
+use dusk_consensus::user::provisioners::Provisioners;
+use dusk_consensus::user::sortition::{self, Exclusion};
+use dusk_consensus::vote_stats::VoteStats;
+use node_data::bls::PublicKey;
+use node_data::ledger::{Header, Seed};
+use node_data::StepName;
+use serde::{Deserialize, Serialize};
+
+pub use crate::database::rocksdb::PERFORMANCE_ROUNDS;
+
+use crate::database::rocksdb::md_performance_key;
+use crate::database::{Ledger, Metadata};
+
+/// Performance of the provisioner in a single round
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundPerformance {
+    pub round: u64,
+    /// Whether the accepted block was generated by the provisioner
+    pub generated: bool,
+    /// Failed iterations the provisioner was the generator of
+    pub missed_iterations: u32,
+    /// Votes of the provisioner received within the round
+    pub votes: u32,
+    /// Steps the provisioner was a committee member of without voting
+    pub missed_votes: u32,
+    /// Cumulative time between the first vote of a step and the vote of the
+    /// provisioner, in milliseconds
+    pub delay_ms: u64,
+    /// Votes of the provisioner included in the certificate of the accepted
+    /// block
+    #[serde(default)]
+    pub certified_votes: u32,
+    /// Steps of the certificate of the accepted block the provisioner was a
+    /// committee member of
+    #[serde(default)]
+    pub certifiable_votes: u32,
+}
+
+/// Performance of a round as stored, along with the provisioner it is of
+#[derive(Debug, Serialize, Deserialize)]
+struct Record {
+    provisioner: String,
+    #[serde(flatten)]
+    performance: RoundPerformance,
+}
+
+/// Performance of the provisioner in the latest rounds
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Performance {
+    /// Base58 encoded public key of the provisioner
+    pub provisioner: String,
+    pub rounds: VecDeque<RoundPerformance>,
+}
+
+/// Summary of the performance of the provisioner over a range of rounds
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PerformanceReport {
+    pub provisioner: String,
+    pub from_round: Option<u64>,
+    pub to_round: Option<u64>,
+    pub blocks_generated: u32,
+    pub missed_iterations: u32,
+    pub votes_cast: u32,
+    /// Votes cast plus the votes missed
+    pub votes_expected: u32,
+    /// Votes included in the certificates of the accepted blocks
+    pub votes_certified: u32,
+    /// Votes the certificates of the accepted blocks could have included
+    pub votes_certifiable: u32,
+    /// Average time between the first vote of a step and the vote of the
+    /// provisioner, in milliseconds
+    pub avg_vote_delay_ms: Option<u64>,
+}
+
+impl RoundPerformance {
+    /// Records the votes of `provisioner` in a closed round.
+    pub(crate) fn record_votes(
+        &mut self,
+        provisioner: &str,
+        stats: &VoteStats,
+    ) {
+        let own = stats.provisioners.get(provisioner);
+        self.votes = own.map(|p| p.votes).unwrap_or_default();
+        self.missed_votes = own.map(|p| p.missed).unwrap_or_default();
+        self.delay_ms = own.map(|p| p.delay_ms).unwrap_or_default();
+    }
+
+    /// Records the generator and the failed iterations of an accepted block.
+    pub(crate) fn record_block(&mut self, provisioner: &str, header: &Header) {
+        self.generated = header.generator_bls_pubkey.to_base58() == provisioner;
+        self.missed_iterations = header
+            .failed_iterations
+            .to_missed_generators_bytes()
+            .filter(|pk| pk.to_base58() == provisioner)
+            .count() as u32;
+    }
+
+    /// Records the votes of `pk` included in the certificate of an accepted
+    /// block, run on the `seed` of its parent by the `provisioners` of its
+    /// round.
+    pub(crate) fn record_certificate(
+        &mut self,
+        pk: &PublicKey,
+        provisioners: &Provisioners,
+        params: &ConsensusParams,
+        seed: Seed,
+        header: &Header,
+    ) {
+        let set = CommitteeSet::new(provisioners, *params);
+        let generator =
+            set.get_generator(header.iteration, seed, header.height);
+
+        self.certified_votes = 0;
+        self.certifiable_votes = 0;
+        for (step, sv) in [
+            (StepName::Validation, &header.cert.validation),
+            (StepName::Ratification, &header.cert.ratification),
+        ] {
+            let cfg = sortition::Config::new(
+                seed,
+                header.height,
+                header.iteration,
+                step,
+                Exclusion::from_generator(generator),
+                params,
+            );
+            let committee = set.get_or_create(&cfg);
+            if committee.is_member(pk) {
+                self.certifiable_votes += 1;
+                if committee.intersect(sv.bitset).contains_key(pk) {
+                    self.certified_votes += 1;
+                }
+            }
+        }
+    }
+}
+
+impl Performance {
+    /// Summarizes the performance in the last `rounds` rounds.
+    pub fn report(&self, rounds: usize) -> PerformanceReport {
+        let skip = self.rounds.len().saturating_sub(rounds);
+        let rounds = self.rounds.iter().skip(skip);
+
+        let mut report = PerformanceReport {
+            provisioner: self.provisioner.clone(),
+            ..Default::default()
+        };
+        let mut delay_ms = 0;
+        for r in rounds {
+            report.from_round.get_or_insert(r.round);
+            report.to_round = Some(r.round);
+            report.blocks_generated += r.generated as u32;
+            report.missed_iterations += r.missed_iterations;
+            report.votes_cast += r.votes;
+            report.votes_expected += r.votes + r.missed_votes;
+            report.votes_certified += r.certified_votes;
+            report.votes_certifiable += r.certifiable_votes;
+            delay_ms += r.delay_ms;
+        }
+        report.avg_vote_delay_ms = (report.votes_cast > 0)
+            .then(|| delay_ms / report.votes_cast as u64);
+
+        report
+    }
+}
+
+/// Reads the performance of `provisioner` in `round`, or a blank one if not
+/// recorded yet.
+fn read<T: Metadata>(
+    t: &T,
+    provisioner: &str,
+    round: u64,
+) -> Result<RoundPerformance> {
+    let record = t
+        .op_read(&md_performance_key(round))?
+        .map(|bytes| serde_json::from_slice::<Record>(&bytes))
+        .transpose()?
+        .filter(|r| r.provisioner == provisioner)
+        .filter(|r| r.performance.round == round);
+
+    Ok(match record {
+        Some(record) => record.performance,
+        None => RoundPerformance {
+            round,
+            ..Default::default()
+        },
+    })
+}
+
+fn write<T: Metadata>(
+    t: &T,
+    provisioner: &str,
+    performance: RoundPerformance,
+) -> Result<()> {
+    let key = md_performance_key(performance.round);
+    let record = Record {
+        provisioner: provisioner.to_string(),
+        performance,
+    };
+    t.op_write(&key, serde_json::to_vec(&record)?)
+}
+
+/// Records the votes of `pk` in the round of `stats`, and the block of the
+/// tip it was run on, with `provisioners` the ones of the round of the tip.
+pub(crate) fn record<T: Ledger + Metadata>(
+    t: &T,
+    pk: &PublicKey,
+    provisioners: &Provisioners,
+    params: &ConsensusParams,
+    tip: &Header,
+    stats: &VoteStats,
+) -> Result<()> {
+    let provisioner = pk.to_base58();
+
+    let mut block = read(t, &provisioner, tip.height)?;
+    block.record_block(&provisioner, tip);
+    // The genesis block has no certificate
+    if tip.height > 0 {
+        if let Some((prev, _)) = t.fetch_block_header(&tip.prev_block_hash)? {
+            block.record_certificate(pk, provisioners, params, prev.seed, tip);
+        }
+    }
+    write(t, &provisioner, block)?;
+
+    let mut round = read(t, &provisioner, stats.round)?;
+    round.record_votes(&provisioner, stats);
+    write(t, &provisioner, round)
+}
+
+/// Returns the performance of the node's own provisioner, if recorded.
+///
+/// Only the rounds of the provisioner of the latest record are returned,
+/// the ones of a previous key being left to be overwritten.
+pub fn fetch<T: Metadata>(t: &T) -> Result<Option<Performance>> {
+    let mut records = vec![];
+    for slot in 0..PERFORMANCE_ROUNDS {
+        if let Some(bytes) = t.op_read(&md_performance_key(slot))? {
+            records.push(serde_json::from_slice::<Record>(&bytes)?);
+        }
+    }
+    records.sort_by_key(|r| r.performance.round);
+
+    let Some(provisioner) = records.last().map(|r| r.provisioner.clone())
+    else {
+        return Ok(None);
+    };
+    let rounds = records
+        .into_iter()
+        .filter(|r| r.provisioner == provisioner)
+        .map(|r| r.performance)
+        .collect();
+
+    Ok(Some(Performance {
+        provisioner,
+        rounds,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use dusk_consensus::user::cluster::Cluster;
+    use dusk_consensus::vote_stats::ProvisionerStats;
+
+    use crate::database::rocksdb::Backend;
+    use crate::database::DB;
+
+    #[test]
+    fn test_performance() {
+        let dir = tempdir::TempDir::new("test_performance").unwrap();
+        let db = Backend::create_or_open(dir.path());
+
+        let alice = PublicKey::from_sk_seed_u64(1);
+        let bob = PublicKey::from_sk_seed_u64(2);
+        let provisioners = Provisioners::empty();
+        let params = ConsensusParams::default();
+
+        let header = |height, generator: &PublicKey| Header {
+            height,
+            generator_bls_pubkey: *generator.bytes(),
+            ..Default::default()
+        };
+        let stats = |round, votes, missed, delay_ms| {
+            let mut stats = VoteStats::new(round);
+            stats.provisioners.insert(
+                alice.to_base58(),
+                ProvisionerStats {
+                    votes,
+                    missed,
+                    delay_ms,
+                    ..Default::default()
+                },
+            );
+            stats
+        };
+        let record = |pk, tip, stats| {
+            db.update(|t| record(t, pk, &provisioners, &params, &tip, &stats))
+                .unwrap()
+        };
+        let fetch = || db.view(|t| fetch(&t)).unwrap();
+
+        assert_eq!(fetch(), None);
+
+        record(&alice, header(9, &alice), stats(10, 2, 0, 100));
+        record(&alice, header(10, &bob), stats(11, 1, 1, 50));
+        // Not a committee member
+        record(&alice, header(11, &alice), VoteStats::new(12));
+
+        let performance = fetch().unwrap();
+        let rounds: Vec<_> =
+            performance.rounds.iter().map(|r| r.round).collect();
+        assert_eq!(rounds, vec![9, 10, 11, 12]);
+
+        let report = performance.report(usize::MAX);
+        assert_eq!((report.from_round, report.to_round), (Some(9), Some(12)));
+        assert_eq!(report.blocks_generated, 2);
+        assert_eq!((report.votes_cast, report.votes_expected), (3, 4));
+        assert_eq!(report.avg_vote_delay_ms, Some(50));
+
+        let report = performance.report(2);
+        assert_eq!(report.from_round, Some(11));
+        assert_eq!(report.blocks_generated, 1);
+        assert_eq!((report.votes_cast, report.votes_expected), (1, 2));
+
+        // Later rounds overwrite the slots of the oldest ones
+        let round = 9 + PERFORMANCE_ROUNDS;
+        record(&alice, header(round, &bob), stats(round + 1, 1, 0, 10));
+        let performance = fetch().unwrap();
+        let rounds: Vec<_> =
+            performance.rounds.iter().map(|r| r.round).collect();
+        assert_eq!(rounds, vec![11, 12, round, round + 1]);
+        assert_eq!(performance.report(2).votes_cast, 1);
+
+        // Only the rounds of the latest provisioner are returned
+        record(&bob, header(round + 1, &bob), VoteStats::new(round + 2));
+        let performance = fetch().unwrap();
+        assert_eq!(performance.provisioner, bob.to_base58());
+        let rounds: Vec<_> =
+            performance.rounds.iter().map(|r| r.round).collect();
+        assert_eq!(rounds, vec![round + 1, round + 2]);
+        assert_eq!(performance.report(usize::MAX).blocks_generated, 1);
+
+        let report = Performance::default().report(10);
+        assert_eq!(report.avg_vote_delay_ms, None);
+        assert_eq!(report.from_round, None);
+    }
+
+    #[test]
+    fn test_certified_votes() {
+        let keys: Vec<_> = (1..=3).map(PublicKey::from_sk_seed_u64).collect();
+        let mut provisioners = Provisioners::empty();
+        for pk in keys.iter() {
+            provisioners.add_member_with_value(pk.clone(), 1_000_000_000_000);
+        }
+        let params = ConsensusParams::default();
+        let seed = Seed::from([7; 48]);
+
+        let mut header = Header {
+            height: 1,
+            ..Default::default()
+        };
+        let set = CommitteeSet::new(&provisioners, params);
+        let generator = set.get_generator(header.iteration, seed, 1);
+        let voter = keys
+            .iter()
+            .find(|pk| *pk.bytes() != generator)
+            .expect("a voter");
+
+        // Only the validation vote of the voter made it to the certificate
+        let cfg = sortition::Config::new(
+            seed,
+            1,
+            header.iteration,
+            StepName::Validation,
+            Exclusion::from_generator(generator),
+            &params,
+        );
+        let committee = set.get_or_create(&cfg);
+        let mut cluster = Cluster::new();
+        cluster.add(voter);
+        header.cert.validation.bitset = committee.bits(&cluster);
+
+        let mut performance = RoundPerformance::default();
+        performance.record_certificate(
+            voter,
+            &provisioners,
+            &params,
+            seed,
+            &header,
+        );
+        assert_eq!(performance.certifiable_votes, 2);
+        assert_eq!(performance.certified_votes, 1);
+
+        // The generator is excluded from the committees
+        let generator = keys
+            .iter()
+            .find(|pk| *pk.bytes() == generator)
+            .expect("a generator");
+        performance.record_certificate(
+            generator,
+            &provisioners,
+            &params,
+            seed,
+            &header,
+        );
+        assert_eq!(performance.certifiable_votes, 0);
+        assert_eq!(performance.certified_votes, 0);
+    }
+}
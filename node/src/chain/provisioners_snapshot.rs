@@ -0,0 +1,239 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Snapshot of the provisioners the consensus runs with.
+//!
+//! The provisioners, along with their stakes, are stored whenever a block
+//! changes the stakes the consensus runs with and at the end of each epoch,
+//! along with the block they follow. On restart they are loaded from the
+//! snapshot, as long as its block is still part of the chain, instead of
+//! being queried to the stake contract, for the consensus to start right
+//! away. They are then checked against the VM in the background.
+
+use std::io::{self, Read, Write};
+
+use anyhow::Result;
+use dusk_consensus::user::provisioners::{ContextProvisioners, Provisioners};
+use dusk_consensus::user::stake::Stake;
+use node_data::bls::PublicKey;
+use node_data::Serializable;
+
+use node_data::ledger::Header;
+
+use crate::database::rocksdb::MD_PROVISIONERS;
+use crate::database::{Ledger, Metadata};
+
+/// The provisioners of the round following the block at the given height.
+struct Snapshot {
+    height: u64,
+    block_hash: [u8; 32],
+    provisioners: ContextProvisioners,
+}
+
+impl Snapshot {
+    fn write_provisioners<W: Write>(
+        w: &mut W,
+        provisioners: &Provisioners,
+    ) -> io::Result<()> {
+        let members = provisioners.iter().count() as u32;
+        w.write_all(&members.to_le_bytes())?;
+
+        for (pk, stake) in provisioners.iter() {
+            w.write_all(pk.bytes().inner())?;
+            w.write_all(&stake.value().to_le_bytes())?;
+            w.write_all(&stake.reward.to_le_bytes())?;
+            w.write_all(&stake.eligible_since.to_le_bytes())?;
+            w.write_all(&stake.counter.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn read_provisioners<R: Read>(r: &mut R) -> io::Result<Provisioners> {
+        let members = Self::read_u32_le(r)?;
+
+        let mut provisioners = Provisioners::empty();
+        for _ in 0..members {
+            let pk = PublicKey::try_from(Self::read_bytes::<_, 96>(r)?)
+                .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+            let value = Self::read_u64_le(r)?;
+            let reward = Self::read_u64_le(r)?;
+            let eligible_since = Self::read_u64_le(r)?;
+            let counter = Self::read_u64_le(r)?;
+
+            provisioners.add_member_with_stake(
+                pk,
+                Stake::new(value, reward, eligible_since, counter),
+            );
+        }
+
+        Ok(provisioners)
+    }
+}
+
+impl Serializable for Snapshot {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.height.to_le_bytes())?;
+        w.write_all(&self.block_hash)?;
+        Self::write_provisioners(w, self.provisioners.current())?;
+        Self::write_provisioners(w, self.provisioners.prev())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let height = Self::read_u64_le(r)?;
+        let block_hash = Self::read_bytes(r)?;
+        let mut provisioners =
+            ContextProvisioners::new(Self::read_provisioners(r)?);
+        provisioners.set_previous(Self::read_provisioners(r)?);
+
+        Ok(Self {
+            height,
+            block_hash,
+            provisioners,
+        })
+    }
+}
+
+/// Stores the provisioners of the round following the block `header`,
+/// replacing the previous snapshot.
+pub(crate) fn store<T: Metadata>(
+    t: &T,
+    header: &Header,
+    provisioners: &ContextProvisioners,
+) -> Result<()> {
+    let snapshot = Snapshot {
+        height: header.height,
+        block_hash: header.hash,
+        provisioners: provisioners.clone(),
+    };
+
+    let mut bytes = vec![];
+    snapshot.write(&mut bytes)?;
+    t.op_write(MD_PROVISIONERS, bytes)
+}
+
+/// Returns the provisioners of the round following the `tip`, if the
+/// snapshot was taken at a block of its chain.
+///
+/// Since a snapshot is taken whenever the stakes change, the ones of a block
+/// preceding the tip are still current, and the previous provisioners of the
+/// tip are the same.
+pub(crate) fn load<T: Ledger + Metadata>(
+    t: &T,
+    tip: &Header,
+) -> Result<Option<ContextProvisioners>> {
+    let Some(bytes) = t.op_read(MD_PROVISIONERS)? else {
+        return Ok(None);
+    };
+
+    let snapshot = Snapshot::read(&mut &bytes[..])?;
+    if snapshot.height == tip.height {
+        let taken_at_tip = snapshot.block_hash == tip.hash;
+        return Ok(taken_at_tip.then_some(snapshot.provisioners));
+    }
+
+    // The snapshot is not used if its block has been reverted
+    if snapshot.height > tip.height
+        || t.fetch_block_hash_by_height(snapshot.height)?
+            != Some(snapshot.block_hash)
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(ContextProvisioners::new(
+        snapshot.provisioners.to_current(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use fake::{Fake, Faker};
+    use node_data::ledger::Label;
+
+    use crate::database::rocksdb::Backend;
+    use crate::database::DB;
+
+    fn stakes(provisioners: &Provisioners) -> Vec<(String, u64, u64, u64)> {
+        provisioners
+            .iter()
+            .map(|(pk, stake)| {
+                (
+                    pk.to_base58(),
+                    stake.value(),
+                    stake.reward,
+                    stake.eligible_since,
+                )
+            })
+            .collect()
+    }
+
+    fn header(height: u64, hash: u8) -> Header {
+        let mut header: Header = Faker.fake();
+        header.height = height;
+        header.hash = [hash; 32];
+        header
+    }
+
+    #[test]
+    fn test_provisioners_snapshot() {
+        let mut current = Provisioners::empty();
+        for seed in 1..4 {
+            current.add_member_with_stake(
+                PublicKey::from_sk_seed_u64(seed),
+                Stake::new(seed * 1_000, seed, 2160, 0),
+            );
+        }
+        let mut prev = current.clone();
+        prev.replace_stake(
+            PublicKey::from_sk_seed_u64(1),
+            Stake::from_value(1),
+        );
+
+        let mut provisioners = ContextProvisioners::new(current);
+        provisioners.set_previous(prev);
+
+        let dir = tempdir::TempDir::new("test_provisioners_snapshot").unwrap();
+        let db = Backend::create_or_open(dir.path());
+        db.update(|t| {
+            for height in 0..3 {
+                t.store_block(
+                    &header(height, height as u8),
+                    &[],
+                    Label::Final,
+                )?;
+            }
+            store(t, &header(1, 1), &provisioners)
+        })
+        .unwrap();
+
+        let loaded = db
+            .view(|t| load(&t, &header(1, 1)))
+            .unwrap()
+            .expect("snapshot to be taken at the tip");
+        assert_eq!(stakes(loaded.current()), stakes(provisioners.current()));
+        assert_eq!(stakes(loaded.prev()), stakes(provisioners.prev()));
+
+        // The stakes did not change since the snapshot was taken
+        let loaded = db
+            .view(|t| load(&t, &header(2, 2)))
+            .unwrap()
+            .expect("snapshot to be taken before the tip");
+        assert_eq!(stakes(loaded.current()), stakes(provisioners.current()));
+        assert_eq!(stakes(loaded.prev()), stakes(provisioners.current()));
+
+        // A snapshot taken at a reverted block is not used
+        assert!(db.view(|t| load(&t, &header(1, 9))).unwrap().is_none());
+        assert!(db.view(|t| load(&t, &header(0, 0))).unwrap().is_none());
+        db.update(|t| store(t, &header(2, 9), &provisioners))
+            .unwrap();
+        assert!(db.view(|t| load(&t, &header(3, 3))).unwrap().is_none());
+    }
+}
@@ -0,0 +1,146 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::{HashSet, VecDeque};
+
+use node_data::ledger::Hash;
+use node_data::message::payload::Quorum;
+use node_data::Serializable;
+use sha3::{Digest, Sha3_256};
+
+/// Maximum number of remembered Quorum messages
+const MAX_QUORUMS: usize = 1024;
+
+/// Identifies a Quorum message: its round, its iteration and the hash of the
+/// whole message.
+///
+/// The certificate is part of the key, as the Quorum is deduplicated before
+/// being verified: a forged Quorum for a candidate must not shadow the
+/// genuine one.
+pub(crate) type Key = (u64, u8, Hash);
+
+fn key(quorum: &Quorum) -> Key {
+    let mut bytes = vec![];
+    quorum.write(&mut bytes).expect("all written");
+    let hash = Sha3_256::digest(bytes).into();
+    (quorum.header.round, quorum.header.iteration, hash)
+}
+
+/// Bounded in-memory set of the Quorum messages already handled.
+///
+/// The same Quorum can be delivered more than once, being relayed by every
+/// peer, either live or from past iterations. Only its first delivery is
+/// handled, so that the certificate is processed and the block requested at
+/// most once per certificate.
+pub(crate) struct QuorumDedup {
+    seen: HashSet<Key>,
+    /// Keys in insertion order, oldest first
+    order: VecDeque<Key>,
+    capacity: usize,
+}
+
+impl Default for QuorumDedup {
+    fn default() -> Self {
+        Self::new(MAX_QUORUMS)
+    }
+}
+
+impl QuorumDedup {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Records the delivery of `quorum`, returning `false` if it was already
+    /// delivered.
+    ///
+    /// The oldest Quorum is forgotten once the capacity is reached.
+    pub(crate) fn insert(&mut self, quorum: &Quorum) -> bool {
+        let key = key(quorum);
+        if !self.seen.insert(key) {
+            return false;
+        }
+
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use node_data::ledger::{Certificate, StepVotes};
+    use node_data::message::payload::{RatificationResult, Vote};
+    use node_data::message::ConsensusHeader;
+
+    fn quorum(round: u64, iteration: u8, vote: Vote) -> Quorum {
+        let result = match vote {
+            Vote::Valid(_) => RatificationResult::Success(vote),
+            _ => RatificationResult::Fail(vote),
+        };
+        Quorum {
+            header: ConsensusHeader {
+                round,
+                iteration,
+                ..Default::default()
+            },
+            cert: Certificate {
+                result,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_quorum_dedup() {
+        let mut dedup = QuorumDedup::new(2);
+
+        let a = quorum(1, 0, Vote::Valid([1; 32]));
+        assert!(dedup.insert(&a));
+        assert!(!dedup.insert(&a));
+
+        // Another iteration or vote is another Quorum
+        assert!(dedup.insert(&quorum(1, 1, Vote::NoCandidate)));
+        assert!(!dedup.insert(&quorum(1, 1, Vote::NoCandidate)));
+
+        // The oldest Quorum is forgotten once the capacity is reached
+        assert!(dedup.insert(&quorum(1, 0, Vote::Valid([2; 32]))));
+        assert!(dedup.insert(&a));
+    }
+
+    #[test]
+    fn test_forged_quorum_first() {
+        let mut dedup = QuorumDedup::new(8);
+
+        let genuine = {
+            let mut quorum = quorum(1, 0, Vote::Valid([1; 32]));
+            quorum.cert.validation = StepVotes::new([1; 48], 0b0111);
+            quorum.cert.ratification = StepVotes::new([2; 48], 0b1011);
+            quorum
+        };
+        // Same candidate, with a certificate the sender made up
+        let forged = {
+            let mut quorum = genuine.clone();
+            quorum.cert.validation = StepVotes::new([3; 48], 0b0001);
+            quorum
+        };
+
+        assert!(dedup.insert(&forged));
+        assert!(dedup.insert(&genuine));
+        assert!(!dedup.insert(&genuine));
+        assert!(!dedup.insert(&forged));
+    }
+}
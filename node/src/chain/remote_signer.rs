@@ -0,0 +1,177 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Client of a remote service signing the consensus messages, allowing to
+//! keep the BLS secret key off the consensus host.
+//!
+//! The service implements the `dusk.signer.v1.ConsensusSigner` gRPC service
+//! described in `node/proto/signer.proto`.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use dusk_bls12_381_sign::{Signature, APK};
+use dusk_bytes::{DeserializableSlice, Serializable};
+use dusk_consensus::signer::{ConsensusSigner, SignerError};
+use node_data::bls::PublicKey;
+use tonic::client::Grpc;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint};
+use tracing::info;
+
+const PUBLIC_KEY_PATH: &str = "/dusk.signer.v1.ConsensusSigner/PublicKey";
+const SIGN_PATH: &str = "/dusk.signer.v1.ConsensusSigner/Sign";
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PublicKeyRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PublicKeyResponse {
+    /// Raw BLS public key
+    #[prost(bytes = "vec", tag = "1")]
+    pub public_key: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SignRequest {
+    /// Raw BLS public key of the key to sign with
+    #[prost(bytes = "vec", tag = "1")]
+    pub public_key: Vec<u8>,
+    /// Message to sign
+    #[prost(bytes = "vec", tag = "2")]
+    pub message: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SignResponse {
+    /// Raw BLS signature
+    #[prost(bytes = "vec", tag = "1")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoteSignerConfig {
+    /// URI of the signing service, e.g. `https://127.0.0.1:9090`
+    pub endpoint: String,
+    /// Time after which a signing request is given up
+    pub timeout: Duration,
+    /// PEM encoded certificate of the authority the service certificate is
+    /// verified against, instead of the system roots
+    pub ca_cert: Option<PathBuf>,
+}
+
+pub struct RemoteSigner {
+    channel: Channel,
+    pk: PublicKey,
+}
+
+impl RemoteSigner {
+    /// Connects to the signing service, fetching the public key it signs
+    /// for.
+    pub async fn connect(config: RemoteSignerConfig) -> anyhow::Result<Self> {
+        let mut endpoint = Endpoint::from_shared(config.endpoint.clone())?
+            .timeout(config.timeout)
+            .connect_timeout(config.timeout);
+
+        if endpoint.uri().scheme_str() == Some("https") {
+            let mut tls = ClientTlsConfig::new();
+            if let Some(path) = &config.ca_cert {
+                let pem = std::fs::read(path).map_err(|e| {
+                    anyhow!("cannot read {}: {e}", path.display())
+                })?;
+                tls = tls.ca_certificate(Certificate::from_pem(pem));
+            }
+            endpoint = endpoint.tls_config(tls)?;
+        }
+
+        let channel = endpoint.connect().await.map_err(|e| {
+            anyhow!("cannot connect to signer {}: {e}", config.endpoint)
+        })?;
+
+        let response: PublicKeyResponse =
+            unary(channel.clone(), PUBLIC_KEY_PATH, PublicKeyRequest {})
+                .await?;
+
+        let pk =
+            dusk_bls12_381_sign::PublicKey::from_slice(&response.public_key)
+                .map_err(|e| {
+                    anyhow!("signer returned an invalid public key {e:?}")
+                })?;
+        let pk = PublicKey::new(pk);
+
+        info!(
+            event = "connected to remote signer",
+            endpoint = config.endpoint,
+            pubkey = pk.to_bs58()
+        );
+
+        Ok(Self { channel, pk })
+    }
+}
+
+#[async_trait]
+impl ConsensusSigner for RemoteSigner {
+    fn public_key(&self) -> &PublicKey {
+        &self.pk
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, SignerError> {
+        let request = SignRequest {
+            public_key: self.pk.inner().to_bytes().to_vec(),
+            message: msg.to_vec(),
+        };
+
+        let response: SignResponse =
+            unary(self.channel.clone(), SIGN_PATH, request)
+                .await
+                .map_err(|status| match status.code() {
+                    tonic::Code::PermissionDenied
+                    | tonic::Code::InvalidArgument
+                    | tonic::Code::FailedPrecondition => {
+                        SignerError::Refused(status.message().to_string())
+                    }
+                    _ => SignerError::Unavailable(status.to_string()),
+                })?;
+
+        let signature = Signature::from_slice(&response.signature)
+            .map_err(|_| SignerError::InvalidSignature)?;
+
+        // Never broadcast a signature that doesn't verify
+        APK::from(self.pk.inner())
+            .verify(&signature, msg)
+            .map_err(|_| SignerError::InvalidSignature)?;
+
+        Ok(signature)
+    }
+}
+
+async fn unary<Req, Res>(
+    channel: Channel,
+    path: &'static str,
+    request: Req,
+) -> Result<Res, tonic::Status>
+where
+    Req: prost::Message + Send + Sync + 'static,
+    Res: prost::Message + Default + Send + Sync + 'static,
+{
+    let mut grpc = Grpc::new(channel);
+    grpc.ready().await.map_err(|e| {
+        tonic::Status::unavailable(format!("signer not ready: {e}"))
+    })?;
+
+    let response = grpc
+        .unary(
+            tonic::Request::new(request),
+            PathAndQuery::from_static(path),
+            ProstCodec::default(),
+        )
+        .await?;
+
+    Ok(response.into_inner())
+}
@@ -0,0 +1,56 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+/// Maximum number of reverts waiting to be served by the chain loop
+const MAX_PENDING_REVERTS: usize = 1;
+
+/// Outcome of a revert of the chain to its last finalized state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevertOutcome {
+    /// Height of the tip before the revert
+    pub from_height: u64,
+    /// Height of the tip after the revert
+    pub to_height: u64,
+    #[serde(with = "hex::serde")]
+    pub from_state_root: [u8; 32],
+    #[serde(with = "hex::serde")]
+    pub to_state_root: [u8; 32],
+}
+
+pub(crate) type RevertRequest = oneshot::Sender<anyhow::Result<RevertOutcome>>;
+
+/// Handle requesting the chain loop to revert the chain to its last
+/// finalized state.
+///
+/// Reverts are served in between the blocks handled by the loop, so that
+/// they never race with an acceptance.
+#[derive(Clone)]
+pub struct ChainReverts {
+    sender: mpsc::Sender<RevertRequest>,
+}
+
+impl ChainReverts {
+    pub(crate) fn new() -> (Self, mpsc::Receiver<RevertRequest>) {
+        let (sender, receiver) = mpsc::channel(MAX_PENDING_REVERTS);
+        (Self { sender }, receiver)
+    }
+
+    /// Reverts the VM state and the ledger to the last finalized block.
+    pub async fn revert_to_finalized(&self) -> anyhow::Result<RevertOutcome> {
+        let (reply, outcome) = oneshot::channel();
+        self.sender
+            .try_send(reply)
+            .map_err(|_| anyhow!("A revert is already pending"))?;
+
+        outcome
+            .await
+            .map_err(|_| anyhow!("The chain loop is stopped"))?
+    }
+}
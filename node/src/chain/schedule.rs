@@ -0,0 +1,168 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Duties of the node's own provisioner in the upcoming round.
+//!
+//! The committees of a round are extracted from the seed of the previous
+//! block, so they are only known once the tip is accepted, and for the next
+//! round only. Operators can still avoid restarting the node while it is
+//! expected to generate or vote.
+
+use anyhow::Result;
+use dusk_consensus::config::ConsensusParams;
+use dusk_consensus::user::committee::Committee;
+use dusk_consensus::user::provisioners::Provisioners;
+use dusk_consensus::user::sortition::{self, Exclusion};
+use node_data::bls::PublicKey;
+use node_data::ledger::Seed;
+use node_data::StepName;
+use serde::{Deserialize, Serialize};
+
+use crate::database::rocksdb::MD_DUTY_SCHEDULE;
+use crate::database::Metadata;
+
+/// Iterations of the upcoming round the schedule covers
+pub const SCHEDULE_ITERATIONS: u8 = 8;
+
+/// Role of a provisioner in a step of an iteration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Duty {
+    pub iteration: u8,
+    /// Either `proposal`, `validation` or `ratification`
+    pub step: String,
+    /// Votes of the provisioner in the committee, 1 for the generator
+    pub votes: usize,
+}
+
+/// Duties of a provisioner in the first iterations of a round.
+///
+/// An iteration is only run if the previous ones failed to produce a block,
+/// so the duties of the first iteration are the most likely.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DutySchedule {
+    pub round: u64,
+    /// Base58 encoded public key of the provisioner
+    pub provisioner: String,
+    /// Whether the provisioner is eligible in the round at all
+    pub eligible: bool,
+    pub duties: Vec<Duty>,
+}
+
+/// Computes the duties of `pk` in the first [`SCHEDULE_ITERATIONS`] of
+/// `round`, extracted with the seed of the previous block.
+pub(crate) fn schedule(
+    provisioners: &Provisioners,
+    params: &ConsensusParams,
+    seed: Seed,
+    round: u64,
+    pk: &PublicKey,
+) -> DutySchedule {
+    let eligible = provisioners.eligibles(round).any(|(p, _)| p == pk);
+
+    let mut duties = vec![];
+    if eligible {
+        for iteration in 0..SCHEDULE_ITERATIONS {
+            let generator = provisioners.get_generator(iteration, seed, round);
+            if generator == *pk.bytes() {
+                duties.push(Duty {
+                    iteration,
+                    step: "proposal".into(),
+                    votes: 1,
+                });
+            }
+
+            for (step, name) in [
+                (StepName::Validation, "validation"),
+                (StepName::Ratification, "ratification"),
+            ] {
+                let cfg = sortition::Config::new(
+                    seed,
+                    round,
+                    iteration,
+                    step,
+                    Exclusion::from_generator(generator),
+                    params,
+                );
+                let committee = Committee::new(provisioners, &cfg);
+                if let Some(votes) = committee.votes_for(pk) {
+                    duties.push(Duty {
+                        iteration,
+                        step: name.into(),
+                        votes,
+                    });
+                }
+            }
+        }
+    }
+
+    DutySchedule {
+        round,
+        provisioner: pk.to_bs58(),
+        eligible,
+        duties,
+    }
+}
+
+pub(crate) fn store<T: Metadata>(t: &T, schedule: &DutySchedule) -> Result<()> {
+    t.op_write(MD_DUTY_SCHEDULE, serde_json::to_vec(schedule)?)
+}
+
+/// Returns the duties of the node's own provisioner in the upcoming round,
+/// if computed.
+pub fn fetch<T: Metadata>(t: &T) -> Result<Option<DutySchedule>> {
+    t.op_read(MD_DUTY_SCHEDULE)?
+        .map(|bytes| serde_json::from_slice(&bytes).map_err(Into::into))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use dusk_bls12_381_sign::{PublicKey as BlsPublicKey, SecretKey};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_schedule() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let keys: Vec<_> = (0..3)
+            .map(|_| {
+                let sk = SecretKey::random(&mut rng);
+                PublicKey::new(BlsPublicKey::from(&sk))
+            })
+            .collect();
+
+        let mut provisioners = Provisioners::empty();
+        for pk in keys.iter() {
+            provisioners.add_member_with_value(pk.clone(), 1_000_000_000_000);
+        }
+
+        let params = ConsensusParams::default();
+        let seed = Seed::from([7; 48]);
+        let schedules: Vec<_> = keys
+            .iter()
+            .map(|pk| schedule(&provisioners, &params, seed, 1, pk))
+            .collect();
+
+        // Each iteration has exactly one generator
+        for iteration in 0..SCHEDULE_ITERATIONS {
+            let generators = schedules
+                .iter()
+                .flat_map(|s| s.duties.iter())
+                .filter(|d| d.iteration == iteration && d.step == "proposal")
+                .count();
+            assert_eq!(generators, 1);
+        }
+        assert!(schedules.iter().all(|s| s.eligible));
+
+        let unknown =
+            PublicKey::new(BlsPublicKey::from(&SecretKey::random(&mut rng)));
+        let s = schedule(&provisioners, &params, seed, 1, &unknown);
+        assert!(!s.eligible);
+        assert!(s.duties.is_empty());
+    }
+}
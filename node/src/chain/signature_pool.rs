@@ -0,0 +1,289 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::sync::Arc;
+use std::thread;
+
+use dusk_consensus::config::CONSENSUS_MAX_ITER;
+use dusk_consensus::user::provisioners::ContextProvisioners;
+use node_data::message::{AsyncQueue, Message};
+use tokio::sync::Semaphore;
+use tracing::{debug, error, warn};
+
+/// Maximum number of rounds past the tip a message is verified for
+const MAX_ROUNDS_AHEAD: u64 = 10;
+
+/// Maximum number of messages waiting to be verified
+const MAX_PENDING: usize = 1_000;
+
+/// Maximum number of verified messages waiting to be handled
+const MAX_VERIFIED: usize = 1_000;
+
+/// Pool verifying the signatures of the consensus messages received from the
+/// wire, off the message loop.
+///
+/// Before any signature is verified, messages of rounds far from the tip or
+/// signed by a provisioner not eligible for their round are discarded.
+///
+/// Verifications run concurrently on the blocking threads, so that a slow one
+/// does not hold back the messages received after it. Messages are queued as
+/// soon as their signature is verified, hence not necessarily in the order
+/// they were received. Messages with an invalid signature are discarded, as
+/// are the messages received while the pool or the queue is full.
+pub(crate) struct SignaturePool {
+    /// Bounds the number of verifications running at once
+    workers: Arc<Semaphore>,
+    /// Bounds the number of verifications running or waiting to run
+    pending: Arc<Semaphore>,
+    verified: AsyncQueue<Message>,
+}
+
+impl Default for SignaturePool {
+    fn default() -> Self {
+        let workers = thread::available_parallelism()
+            .map(usize::from)
+            .unwrap_or(1);
+        Self::new(workers)
+    }
+}
+
+impl SignaturePool {
+    pub(crate) fn new(workers: usize) -> Self {
+        Self {
+            workers: Arc::new(Semaphore::new(workers.max(1))),
+            pending: Arc::new(Semaphore::new(MAX_PENDING)),
+            verified: AsyncQueue::bounded(MAX_VERIFIED),
+        }
+    }
+
+    /// Queue of the messages whose signature has been verified, marked as
+    /// such.
+    pub(crate) fn verified(&self) -> AsyncQueue<Message> {
+        self.verified.clone()
+    }
+
+    /// Verifies the signature of `msg` in the pool, if it is relevant to the
+    /// rounds following `tip_height`.
+    ///
+    /// Returns whether the message has been admitted.
+    pub(crate) fn submit(
+        &self,
+        msg: Message,
+        tip_height: u64,
+        provisioners: &ContextProvisioners,
+    ) -> bool {
+        if let Err(reason) = precheck(&msg, tip_height, provisioners) {
+            debug!(
+                event = "msg discarded",
+                topic = ?msg.topic(),
+                round = msg.header.round,
+                reason,
+            );
+            return false;
+        }
+
+        let Ok(pending) = self.pending.clone().try_acquire_owned() else {
+            warn!(
+                event = "msg discarded",
+                topic = ?msg.topic(),
+                round = msg.header.round,
+                reason = "signature pool full",
+            );
+            return false;
+        };
+
+        let workers = self.workers.clone();
+        let verified = self.verified.clone();
+
+        tokio::spawn(async move {
+            let _pending = pending;
+            let Ok(_permit) = workers.acquire_owned().await else {
+                return;
+            };
+
+            let res = tokio::task::spawn_blocking(move || {
+                let res = msg.verify_signature();
+                (msg, res)
+            })
+            .await;
+
+            match res {
+                Ok((mut msg, Ok(()))) => {
+                    if let Some(md) = msg.metadata.as_mut() {
+                        md.sig_verified = true;
+                    }
+                    if let Err(err) = verified.try_send(msg) {
+                        warn!(event = "verified msg not queued", ?err);
+                    }
+                }
+                Ok((msg, Err(err))) => {
+                    warn!(
+                        event = "invalid signature",
+                        topic = ?msg.topic(),
+                        round = msg.header.round,
+                        src = ?msg.metadata.map(|md| md.src_addr),
+                        ?err,
+                    );
+                }
+                Err(err) => error!(event = "signature verification", ?err),
+            }
+        });
+
+        true
+    }
+}
+
+/// Checks, without verifying its signature, whether `msg` can be relevant to
+/// the rounds following `tip_height`.
+///
+/// The message must be of the round of the tip up to [`MAX_ROUNDS_AHEAD`]
+/// rounds past it, of an iteration within [`CONSENSUS_MAX_ITER`], and be
+/// signed by a provisioner eligible for its round.
+fn precheck(
+    msg: &Message,
+    tip_height: u64,
+    provisioners: &ContextProvisioners,
+) -> Result<(), &'static str> {
+    let round = msg.header.round;
+    if round < tip_height || round > tip_height + 1 + MAX_ROUNDS_AHEAD {
+        return Err("round out of range");
+    }
+
+    if msg.header.iteration >= CONSENSUS_MAX_ITER {
+        return Err("iteration out of range");
+    }
+
+    // Votes for the tip are cast by the provisioners preceding it
+    let provisioners = if round <= tip_height {
+        provisioners.prev()
+    } else {
+        provisioners.current()
+    };
+    let signer = msg.get_signer().ok_or("not signed")?;
+    if !provisioners.is_eligible(signer, round) {
+        return Err("signer not eligible");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::SocketAddr;
+
+    use dusk_bls12_381_sign::{PublicKey, SecretKey};
+    use dusk_consensus::user::provisioners::{Provisioners, DUSK};
+    use node_data::message::payload::{Validation, Vote};
+    use node_data::message::{
+        ConsensusHeader, Metadata, Payload, SignInfo, StepMessage,
+    };
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn wire_msg(validation: Validation) -> Message {
+        let mut msg = Message::new_validation(validation);
+        msg.metadata = Some(Metadata {
+            height: 0,
+            src_addr: SocketAddr::from(([127, 0, 0, 1], 9000)),
+            sig_verified: false,
+        });
+        msg
+    }
+
+    fn provisioners(pk: PublicKey) -> ContextProvisioners {
+        let mut provisioners = Provisioners::empty();
+        provisioners.add_member_with_value(
+            node_data::bls::PublicKey::new(pk),
+            1_000 * DUSK,
+        );
+        ContextProvisioners::new(provisioners)
+    }
+
+    #[tokio::test]
+    async fn test_signature_pool() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let sk = SecretKey::random(&mut rng);
+        let pk = PublicKey::from(&sk);
+
+        let mut signed = Validation {
+            header: ConsensusHeader::default(),
+            vote: Vote::Valid([1u8; 32]),
+            sign_info: SignInfo::default(),
+        };
+        signed.sign(&sk, &pk);
+        let mut forged = signed.clone();
+        forged.header.round += 1;
+
+        let provisioners = provisioners(pk);
+        let pool = SignaturePool::new(2);
+        assert!(pool.submit(wire_msg(forged), 0, &provisioners));
+        assert!(pool.submit(wire_msg(signed.clone()), 0, &provisioners));
+
+        let msg = pool.verified().recv().await.unwrap();
+        assert!(msg.is_sig_verified());
+        assert!(matches!(msg.payload, Payload::Validation(v) if v == signed));
+
+        // The forged message is discarded
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(pool.verified().try_recv().is_err());
+    }
+
+    #[test]
+    fn test_precheck() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let sk = SecretKey::random(&mut rng);
+        let pk = PublicKey::from(&sk);
+        let provisioners = provisioners(pk);
+
+        let validation = |round, iteration, sk: &SecretKey| {
+            let mut validation = Validation {
+                header: ConsensusHeader {
+                    round,
+                    iteration,
+                    ..Default::default()
+                },
+                vote: Vote::Valid([1u8; 32]),
+                sign_info: SignInfo::default(),
+            };
+            validation.sign(sk, &PublicKey::from(sk));
+            wire_msg(validation)
+        };
+
+        let tip = 100;
+        for round in [tip, tip + 1, tip + 1 + MAX_ROUNDS_AHEAD] {
+            let msg = validation(round, 0, &sk);
+            assert_eq!(precheck(&msg, tip, &provisioners), Ok(()));
+        }
+
+        for round in [tip - 1, tip + 2 + MAX_ROUNDS_AHEAD] {
+            let msg = validation(round, 0, &sk);
+            assert_eq!(
+                precheck(&msg, tip, &provisioners),
+                Err("round out of range")
+            );
+        }
+
+        let msg = validation(tip + 1, CONSENSUS_MAX_ITER, &sk);
+        assert_eq!(
+            precheck(&msg, tip, &provisioners),
+            Err("iteration out of range")
+        );
+
+        // Signed by a key not staked
+        let other = SecretKey::random(&mut rng);
+        let msg = validation(tip + 1, 0, &other);
+        assert_eq!(
+            precheck(&msg, tip, &provisioners),
+            Err("signer not eligible")
+        );
+
+        // Discarded messages are not verified
+        let pool = SignaturePool::new(1);
+        assert!(!pool.submit(msg, tip, &provisioners));
+    }
+}
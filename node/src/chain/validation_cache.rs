@@ -0,0 +1,157 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::{HashMap, VecDeque};
+
+use dusk_consensus::operations::VerificationOutput;
+use node_data::error::{kind_of, ErrorKind};
+use node_data::ledger::Hash;
+
+/// Maximum number of cached verification outcomes
+const MAX_OUTCOMES: usize = 512;
+
+/// Verification performed on a candidate block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Check {
+    Header { disable_winning_cert_check: bool },
+    StateTransition,
+}
+
+/// Identifies a verification: the candidate hash, the hash of the block it
+/// was verified on top of, whose provisioners form the committees checked
+/// against, and the verification performed.
+pub(crate) type Key = (Hash, Hash, Check);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Valid,
+    /// The state transition is valid and leads to the given output
+    Transition(VerificationOutput),
+    Invalid,
+}
+
+/// Bounded in-memory cache of the verification outcomes of candidate blocks.
+///
+/// A candidate received more than once, e.g. both through gossip and as the
+/// reply to a request, is then accepted or rejected without being verified
+/// again.
+pub(crate) struct ValidationCache {
+    outcomes: HashMap<Key, Outcome>,
+    /// Keys in insertion order, oldest first
+    order: VecDeque<Key>,
+    capacity: usize,
+}
+
+impl Default for ValidationCache {
+    fn default() -> Self {
+        Self::new(MAX_OUTCOMES)
+    }
+}
+
+impl ValidationCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            outcomes: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub(crate) fn get(&self, key: &Key) -> Option<Outcome> {
+        self.outcomes.get(key).copied()
+    }
+
+    /// Caches an outcome, evicting the oldest one if the capacity is
+    /// reached.
+    pub(crate) fn insert(&mut self, key: Key, outcome: Outcome) {
+        if self.outcomes.insert(key, outcome).is_some() {
+            return;
+        }
+
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.outcomes.remove(&oldest);
+            }
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+}
+
+/// Returns whether a failed verification is a verdict on the candidate, to be
+/// cached, rather than a failure of the node itself that may not happen on
+/// a later attempt.
+///
+/// Errors are verdicts unless classified otherwise.
+pub(crate) fn is_verdict(err: &anyhow::Error) -> bool {
+    kind_of(err).map_or(true, |kind| kind == ErrorKind::Permanent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_cache() {
+        let mut cache = ValidationCache::new(2);
+
+        let header = Check::Header {
+            disable_winning_cert_check: false,
+        };
+        let a = ([1u8; 32], [0u8; 32], header);
+        let b = ([2u8; 32], [0u8; 32], Check::StateTransition);
+
+        cache.insert(a, Outcome::Valid);
+        cache.insert(b, Outcome::Invalid);
+        assert_eq!(cache.get(&a), Some(Outcome::Valid));
+        assert_eq!(cache.get(&b), Some(Outcome::Invalid));
+
+        // The same candidate verified on top of another block is unknown
+        assert_eq!(cache.get(&([1u8; 32], [9u8; 32], header)), None);
+
+        // Overwriting an outcome doesn't count against the capacity
+        cache.insert(a, Outcome::Invalid);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&a), Some(Outcome::Invalid));
+
+        // The oldest outcome is evicted once the capacity is reached
+        let c = ([3u8; 32], [0u8; 32], header);
+        cache.insert(c, Outcome::Valid);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&a), None);
+        assert_eq!(cache.get(&c), Some(Outcome::Valid));
+    }
+
+    #[test]
+    fn test_only_verdicts_are_cached() {
+        use node_data::error::Classified;
+
+        assert!(is_verdict(&anyhow::anyhow!("invalid seed")));
+
+        let permanent =
+            Classified::new(ErrorKind::Permanent, anyhow::anyhow!("bad proof"));
+        assert!(is_verdict(&anyhow::Error::from(permanent)));
+
+        let transient =
+            Classified::new(ErrorKind::Transient, anyhow::anyhow!("db"));
+        assert!(!is_verdict(&anyhow::Error::from(transient)));
+
+        // The context added on top of an error keeps its kind
+        let transient =
+            Classified::new(ErrorKind::Transient, anyhow::anyhow!("db"));
+        let err = anyhow::Error::from(transient).context("header check");
+        assert!(!is_verdict(&err));
+
+        let critical = Classified::new(
+            ErrorKind::ConsensusCritical,
+            anyhow::anyhow!("vm"),
+        );
+        assert!(!is_verdict(&anyhow::Error::from(critical)));
+    }
+}
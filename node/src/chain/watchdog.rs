@@ -0,0 +1,205 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+/// Number of distinct peers that must report a height ahead of the tip for
+/// it to be considered stale
+const MIN_REPORTING_PEERS: usize = 3;
+
+/// Maximum number of peers whose reported heights are tracked
+const MAX_TRACKED_PEERS: usize = 64;
+
+/// A tip that has not moved for too long while peers are ahead of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct StaleTip {
+    pub tip_height: u64,
+    /// Highest height reported by at least [`MIN_REPORTING_PEERS`] peers
+    pub peer_height: u64,
+    /// One of the peers that reported at least `peer_height`
+    pub peer: SocketAddr,
+    pub stalled_for: Duration,
+}
+
+/// Alert record of the stale tips detected, persisted for monitoring
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StaleTipAlerts {
+    /// Number of stale tips detected since the node started
+    pub alerts: u64,
+    pub last_tip_height: u64,
+    pub last_peer_height: u64,
+    /// Seconds the tip was stalled for when last detected
+    pub last_stalled_secs: u64,
+}
+
+/// Detects a tip that no longer moves while peers report higher heights.
+///
+/// Heights are reported by unauthenticated peers, so a height only counts
+/// once reported by [`MIN_REPORTING_PEERS`] distinct peers, and a single peer
+/// cannot force a resync.
+///
+/// A detection is reported at most once every `timeout`, so that the resync
+/// it triggers has a chance to complete before the next one.
+pub(crate) struct StaleTipWatchdog {
+    timeout: Duration,
+    tip_height: u64,
+    last_progress: Instant,
+    /// Highest height reported by each peer ahead of the tip
+    peer_heights: HashMap<SocketAddr, u64>,
+    alerts: StaleTipAlerts,
+}
+
+impl StaleTipWatchdog {
+    pub fn new(timeout: Duration, tip_height: u64, now: Instant) -> Self {
+        Self {
+            timeout,
+            tip_height,
+            last_progress: now,
+            peer_heights: HashMap::new(),
+            alerts: StaleTipAlerts::default(),
+        }
+    }
+
+    /// Records a height reported by a peer, through a block or a consensus
+    /// message.
+    pub fn on_peer_height(&mut self, height: u64, peer: SocketAddr) {
+        if height <= self.tip_height {
+            return;
+        }
+
+        if let Some(h) = self.peer_heights.get_mut(&peer) {
+            *h = (*h).max(height);
+            return;
+        }
+
+        // Make room by forgetting the peer reporting the lowest height
+        if self.peer_heights.len() >= MAX_TRACKED_PEERS {
+            let lowest = self
+                .peer_heights
+                .iter()
+                .min_by_key(|(_, h)| **h)
+                .map(|(p, h)| (*p, *h));
+            match lowest {
+                Some((p, h)) if h < height => {
+                    self.peer_heights.remove(&p);
+                }
+                _ => return,
+            }
+        }
+        self.peer_heights.insert(peer, height);
+    }
+
+    /// Checks whether the tip, now at `tip_height`, is stale.
+    pub fn check(&mut self, tip_height: u64, now: Instant) -> Option<StaleTip> {
+        if tip_height > self.tip_height {
+            self.tip_height = tip_height;
+            self.last_progress = now;
+            self.peer_heights.retain(|_, h| *h > tip_height);
+            return None;
+        }
+
+        let stalled_for = now.duration_since(self.last_progress);
+        if stalled_for < self.timeout {
+            return None;
+        }
+
+        let mut reports: Vec<_> = self.peer_heights.iter().collect();
+        reports.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let (&peer, &peer_height) = *reports.get(MIN_REPORTING_PEERS - 1)?;
+
+        // Rate-limit the detections
+        self.last_progress = now;
+
+        self.alerts.alerts += 1;
+        self.alerts.last_tip_height = tip_height;
+        self.alerts.last_peer_height = peer_height;
+        self.alerts.last_stalled_secs = stalled_for.as_secs();
+
+        Some(StaleTip {
+            tip_height,
+            peer_height,
+            peer,
+            stalled_for,
+        })
+    }
+
+    pub fn alerts(&self) -> &StaleTipAlerts {
+        &self.alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_stale_tip_only_when_peers_are_ahead() {
+        let timeout = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut watchdog = StaleTipWatchdog::new(timeout, 10, start);
+
+        // No peer ahead
+        assert_eq!(watchdog.check(10, start + timeout), None);
+
+        let peers: Vec<SocketAddr> = (0..MIN_REPORTING_PEERS)
+            .map(|i| format!("127.0.0.1:{}", 9000 + i).parse().unwrap())
+            .collect();
+
+        // Peers behind or at the tip are ignored
+        for peer in &peers {
+            watchdog.on_peer_height(10, *peer);
+        }
+        assert_eq!(watchdog.check(10, start + timeout), None);
+
+        // A single peer cannot make the tip stale, however often it reports
+        for _ in 0..MIN_REPORTING_PEERS {
+            watchdog.on_peer_height(1000, peers[0]);
+        }
+        assert_eq!(watchdog.check(10, start + timeout), None);
+
+        for peer in &peers[1..] {
+            watchdog.on_peer_height(15, *peer);
+        }
+        watchdog.on_peer_height(12, peers[1]);
+
+        // Not stalled for long enough
+        assert_eq!(watchdog.check(10, start + timeout / 2), None);
+
+        let stale = watchdog.check(10, start + timeout).expect("stale tip");
+        assert_eq!(stale.peer_height, 15);
+        assert!(peers[1..].contains(&stale.peer));
+        assert_eq!(watchdog.alerts().alerts, 1);
+
+        // Detections are rate-limited
+        assert_eq!(watchdog.check(10, start + timeout + timeout / 2), None);
+
+        // Progress resets the watchdog
+        let now = start + timeout * 2;
+        assert_eq!(watchdog.check(15, now), None);
+        assert_eq!(watchdog.check(15, now + timeout), None);
+        assert_eq!(watchdog.alerts().alerts, 1);
+    }
+
+    #[test]
+    fn tracked_peers_are_bounded() {
+        let start = Instant::now();
+        let mut watchdog =
+            StaleTipWatchdog::new(Duration::from_secs(60), 10, start);
+
+        for i in 0..MAX_TRACKED_PEERS * 2 {
+            let peer = format!("127.0.0.1:{}", 9000 + i).parse().unwrap();
+            watchdog.on_peer_height(11 + i as u64, peer);
+        }
+        assert_eq!(watchdog.peer_heights.len(), MAX_TRACKED_PEERS);
+        let lowest = watchdog.peer_heights.values().min().copied();
+        assert_eq!(lowest, Some(11 + MAX_TRACKED_PEERS as u64));
+    }
+}
@@ -7,11 +7,14 @@
 use std::collections::HashSet;
 use std::path::Path;
 
+pub mod cold;
+pub mod migration;
 pub mod rocksdb;
 
 use anyhow::Result;
 use node_data::ledger;
 use node_data::ledger::{Label, SpentTransaction};
+use serde::{Deserialize, Serialize};
 
 pub trait DB: Send + Sync + 'static {
     type P<'a>: Persist;
@@ -54,6 +57,23 @@ pub trait Ledger {
     ) -> Result<()>;
 
     fn delete_block(&self, b: &ledger::Block) -> Result<()>;
+
+    /// Stores the events emitted by the block at `height` outside of its
+    /// transactions, by the hooks, rewards and slashes. These follow the
+    /// events of the transactions in the event hash of the block.
+    fn store_block_events(
+        &self,
+        height: u64,
+        events: &[ledger::ContractEvent],
+    ) -> Result<()>;
+
+    /// Returns the events emitted by the block at `height` outside of its
+    /// transactions, in emission order.
+    fn fetch_block_events(
+        &self,
+        height: u64,
+    ) -> Result<Vec<ledger::ContractEvent>>;
+
     fn fetch_block_header(
         &self,
         hash: &[u8],
@@ -80,6 +100,71 @@ pub trait Ledger {
 
     fn fetch_block_label_by_height(&self, height: u64)
         -> Result<Option<Label>>;
+
+    /// Returns at most `limit` of the events emitted by the `source` contract
+    /// with the given `topic`, starting at `from` up to the block at height
+    /// `to` included, in ledger order.
+    fn fetch_events(
+        &self,
+        source: &[u8; 32],
+        topic: &str,
+        from: EventPosition,
+        to: u64,
+        limit: usize,
+    ) -> Result<Vec<IndexedEvent>>;
+
+    /// Returns the gas spent per called contract by the blocks from height
+    /// `from` up to `to` included, in height order.
+    fn fetch_gas_usage(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(u64, Vec<ledger::ContractGas>)>>;
+}
+
+/// Transaction index of the events emitted by a block outside of its
+/// transactions, which follow all of them.
+pub const BLOCK_EVENTS_TX_INDEX: u32 = u32::MAX;
+
+/// Position of a contract event in the ledger
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
+pub struct EventPosition {
+    pub height: u64,
+    /// Index of the emitting transaction in its block, or
+    /// [`BLOCK_EVENTS_TX_INDEX`] for the events emitted by the block itself
+    pub tx_index: u32,
+    /// Index of the event among the ones emitted by the transaction
+    pub event_index: u32,
+}
+
+impl EventPosition {
+    /// Returns the position following this one, used to resume a paginated
+    /// query.
+    pub fn next(&self) -> Self {
+        Self {
+            event_index: self.event_index + 1,
+            ..*self
+        }
+    }
+}
+
+/// A contract event found in the ledger event index
+#[derive(Debug, Clone)]
+pub struct IndexedEvent {
+    pub position: EventPosition,
+    pub tx_hash: [u8; 32],
+    pub event: ledger::ContractEvent,
 }
 
 pub trait Candidate {
@@ -98,6 +183,34 @@ pub trait Candidate {
     fn count(&self) -> usize;
 }
 
+/// Bytes taken by a mempool transaction, along with the fee it pays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxFootprint {
+    pub hash: [u8; 32],
+    /// Size in bytes of the serialized transaction
+    pub size: usize,
+    /// Maximum fee the transaction pays, as gas limit times gas price
+    pub max_fee: u64,
+}
+
+impl From<&ledger::Transaction> for TxFootprint {
+    fn from(tx: &ledger::Transaction) -> Self {
+        Self {
+            hash: tx.hash(),
+            size: tx.size(),
+            max_fee: tx.inner.fee().gas_limit.saturating_mul(tx.gas_price()),
+        }
+    }
+}
+
+/// Number of transactions in the mempool, along with the bytes they take
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MempoolUsage {
+    pub txs: usize,
+    /// Size in bytes of the serialized transactions
+    pub bytes: usize,
+}
+
 pub trait Mempool {
     /// Adds a transaction to the mempool.
     fn add_tx(&self, tx: &ledger::Transaction) -> Result<()>;
@@ -126,6 +239,16 @@ pub trait Mempool {
 
     /// Get all transactions hashes.
     fn get_txs_hashes(&self) -> Result<Vec<[u8; 32]>>;
+
+    /// Get the number of the mempool transactions and the bytes they take,
+    /// without reading them.
+    fn get_txs_usage(&self) -> MempoolUsage;
+
+    /// Get an iterator over the footprints of the mempool transactions, lowest
+    /// fee density first
+    fn get_txs_footprints_by_density(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = TxFootprint> + '_>>;
 }
 
 pub trait Metadata {
@@ -0,0 +1,67 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Cold storage of the ledger data of old blocks.
+//!
+//! The headers and transactions of the final blocks below a height threshold
+//! can be moved out of the main database to a [`ColdStorage`], e.g. a
+//! cheaper disk or an object storage. The main database keeps indexing them
+//! by height and reads them through the cold storage when missing.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+/// Prefix of the keys of the block header records
+pub(crate) const HEADER_PREFIX: u8 = b'h';
+/// Prefix of the keys of the transaction records
+pub(crate) const TX_PREFIX: u8 = b't';
+
+/// A key-value store holding the ledger records moved out of the main
+/// database.
+///
+/// Records are only ever added, and are immutable once stored.
+pub trait ColdStorage: Send + Sync {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// Stores all the records at once.
+    fn put_all(&self, records: Vec<(Vec<u8>, Vec<u8>)>) -> Result<()>;
+}
+
+/// Returns the cold storage key of a record.
+pub(crate) fn key(prefix: u8, id: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(1 + id.len());
+    key.push(prefix);
+    key.extend_from_slice(id);
+    key
+}
+
+/// A [`ColdStorage`] in a RocksDB database of its own, meant to be placed on
+/// a different disk than the main database.
+pub struct RocksColdStorage(rocksdb_lib::DB);
+
+impl RocksColdStorage {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut opts = rocksdb_lib::Options::default();
+        opts.create_if_missing(true);
+
+        Ok(Self(rocksdb_lib::DB::open(&opts, path)?))
+    }
+}
+
+impl ColdStorage for RocksColdStorage {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.0.get(key)?)
+    }
+
+    fn put_all(&self, records: Vec<(Vec<u8>, Vec<u8>)>) -> Result<()> {
+        let mut batch = rocksdb_lib::WriteBatch::default();
+        for (key, value) in records {
+            batch.put(key, value);
+        }
+        Ok(self.0.write(batch)?)
+    }
+}
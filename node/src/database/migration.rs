@@ -0,0 +1,188 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Schema migrations of the node database.
+//!
+//! The version of the schema a database is in is recorded in its metadata.
+//! When the format of its column families or keys changes, the schema
+//! version is bumped and a migration added, bringing the existing databases
+//! to the new format on startup.
+
+use anyhow::{anyhow, Context, Result};
+use tracing::info;
+
+use super::rocksdb::{MD_HASH_KEY, MD_SCHEMA_VERSION};
+use super::{Metadata, DB};
+
+/// A migration of the database schema to the next version.
+pub struct Migration<D: DB> {
+    /// Version the schema is at once migrated
+    pub version: u32,
+    pub description: &'static str,
+    /// Brings the schema from the previous version to `version`
+    pub apply: for<'a, 'b> fn(&'b D::P<'a>) -> Result<()>,
+}
+
+/// Returns the schema version recorded in the metadata, if any.
+pub fn read_version<T: Metadata>(t: &T) -> Result<Option<u32>> {
+    t.op_read(MD_SCHEMA_VERSION)?
+        .map(|v| v.try_into().map(u32::from_le_bytes))
+        .transpose()
+        .map_err(|_| anyhow!("Invalid schema version"))
+}
+
+fn write_version<T: Metadata>(t: &T, version: u32) -> Result<()> {
+    t.op_write(MD_SCHEMA_VERSION, version.to_le_bytes())
+}
+
+/// Brings the schema of `db` to the `latest` version, applying the
+/// `migrations` it misses in order. Returns the schema version.
+///
+/// A database without any block is recorded at the `latest` version, while
+/// a database created before the schema was versioned is assumed to be at
+/// the first one.
+///
+/// Each migration is applied in a transaction of its own, along with the
+/// version bump, so that a failed migration is rolled back and the database
+/// left at the previous version. Databases at a version newer than `latest`
+/// are refused.
+pub fn migrate<D: DB>(
+    db: &D,
+    migrations: &[Migration<D>],
+    latest: u32,
+) -> Result<u32> {
+    let (version, has_tip) = db.view(|t| {
+        anyhow::Ok((read_version(&t)?, t.op_read(MD_HASH_KEY)?.is_some()))
+    })?;
+
+    let mut version = match version {
+        Some(version) => version,
+        None => {
+            let version = if has_tip { 1 } else { latest };
+            db.update(|t| write_version(t, version))?;
+            version
+        }
+    };
+
+    if version > latest {
+        return Err(anyhow!(
+            "Database schema version {version} is newer than the supported \
+             version {latest}"
+        ));
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > version) {
+        if migration.version != version + 1 {
+            return Err(anyhow!(
+                "Missing migration to schema version {}",
+                version + 1
+            ));
+        }
+
+        info!(
+            event = "migrating database",
+            from = version,
+            to = migration.version,
+            description = migration.description,
+        );
+        db.update(|t| {
+            (migration.apply)(t)?;
+            write_version(t, migration.version)
+        })
+        .with_context(|| {
+            format!(
+                "Migration to schema version {} failed and was rolled back",
+                migration.version
+            )
+        })?;
+        version = migration.version;
+    }
+
+    if version != latest {
+        return Err(anyhow!("Missing migration to schema version {latest}"));
+    }
+
+    info!(event = "database schema", version);
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rocksdb_lib::OptimisticTransactionDB;
+
+    use crate::database::rocksdb::{Backend, DBTransaction};
+
+    type Tx<'a> = DBTransaction<'a, OptimisticTransactionDB>;
+
+    const KEY: &[u8] = b"migrated";
+
+    fn to_v2(t: &Tx) -> Result<()> {
+        t.op_write(KEY, [2])
+    }
+
+    fn to_v3(t: &Tx) -> Result<()> {
+        t.op_write(KEY, [3])
+    }
+
+    fn failing(t: &Tx) -> Result<()> {
+        t.op_write(KEY, [4])?;
+        Err(anyhow!("failed"))
+    }
+
+    fn migrations() -> Vec<Migration<Backend>> {
+        vec![
+            Migration {
+                version: 2,
+                description: "v2",
+                apply: to_v2,
+            },
+            Migration {
+                version: 3,
+                description: "v3",
+                apply: to_v3,
+            },
+        ]
+    }
+
+    fn state(db: &Backend) -> (Option<u32>, Option<Vec<u8>>) {
+        db.view(|t| (read_version(&t).unwrap(), t.op_read(KEY).unwrap()))
+    }
+
+    #[test]
+    fn test_migrate() {
+        // A fresh database is created at the latest version
+        let dir = tempdir::TempDir::new("test_migrate_fresh").unwrap();
+        let db = Backend::create_or_open(dir.path());
+        assert_eq!(migrate(&db, &migrations(), 3).unwrap(), 3);
+        assert_eq!(state(&db), (Some(3), None));
+
+        // A database predating the versioning is migrated from the first one
+        let dir = tempdir::TempDir::new("test_migrate_legacy").unwrap();
+        let db = Backend::create_or_open(dir.path());
+        db.update(|t| t.op_write(MD_HASH_KEY, [1; 32])).unwrap();
+        assert_eq!(migrate(&db, &migrations()[..1], 2).unwrap(), 2);
+        assert_eq!(state(&db), (Some(2), Some(vec![2])));
+        assert_eq!(migrate(&db, &migrations(), 3).unwrap(), 3);
+        assert_eq!(state(&db), (Some(3), Some(vec![3])));
+        assert_eq!(migrate(&db, &migrations(), 3).unwrap(), 3);
+
+        // A failed migration is rolled back
+        let mut failed = migrations();
+        failed.push(Migration {
+            version: 4,
+            description: "v4",
+            apply: failing,
+        });
+        assert!(migrate(&db, &failed, 4).is_err());
+        assert_eq!(state(&db), (Some(3), Some(vec![3])));
+
+        // Missing migrations and newer versions are refused
+        assert!(migrate(&db, &migrations(), 4).is_err());
+        assert!(migrate(&db, &migrations()[..1], 2).is_err());
+    }
+}
@@ -4,7 +4,12 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use super::{Candidate, Ledger, Metadata, Persist, DB};
+use super::cold::{self, ColdStorage};
+use super::migration::{self, Migration};
+use super::{
+    Candidate, EventPosition, IndexedEvent, Ledger, MempoolUsage, Metadata,
+    Persist, TxFootprint, BLOCK_EVENTS_TX_INDEX, DB,
+};
 use anyhow::Result;
 
 use node_data::ledger::{self, Label, SpentTransaction};
@@ -24,9 +29,11 @@ use std::io;
 use std::io::Read;
 use std::io::Write;
 use std::path::Path;
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
 use std::vec;
 
+use serde::Serialize;
 use tracing::info;
 
 const CF_LEDGER_HEADER: &str = "cf_ledger_header";
@@ -37,10 +44,29 @@ const CF_CANDIDATES_HEIGHT: &str = "cf_candidates_height";
 const CF_MEMPOOL: &str = "cf_mempool";
 const CF_MEMPOOL_NULLIFIERS: &str = "cf_mempool_nullifiers";
 const CF_MEMPOOL_FEES: &str = "cf_mempool_fees";
+const CF_MEMPOOL_DENSITY: &str = "cf_mempool_density";
 const CF_METADATA: &str = "cf_metadata";
+const CF_LEDGER_EVENTS: &str = "cf_ledger_events";
+const CF_LEDGER_GAS: &str = "cf_ledger_gas";
+const CF_LEDGER_BLOCK_EVENTS: &str = "cf_ledger_block_events";
+const COLUMN_FAMILIES: [&str; 13] = [
+    CF_LEDGER_HEADER,
+    CF_LEDGER_TXS,
+    CF_LEDGER_HEIGHT,
+    CF_CANDIDATES,
+    CF_CANDIDATES_HEIGHT,
+    CF_MEMPOOL,
+    CF_MEMPOOL_NULLIFIERS,
+    CF_MEMPOOL_FEES,
+    CF_MEMPOOL_DENSITY,
+    CF_METADATA,
+    CF_LEDGER_EVENTS,
+    CF_LEDGER_GAS,
+    CF_LEDGER_BLOCK_EVENTS,
+];
 const MAX_MEMPOOL_SIZE: usize = 64 * 1024 * 1024; // 64 MiB
 
-const DB_FOLDER_NAME: &str = "chain.db";
+pub const DB_FOLDER_NAME: &str = "chain.db";
 
 // List of supported metadata keys
 pub const MD_HASH_KEY: &[u8] = b"hash_key";
@@ -48,13 +74,359 @@ pub const MD_STATE_ROOT_KEY: &[u8] = b"state_hash_key";
 pub const MD_AVG_VALIDATION: &[u8] = b"avg_validation_time";
 pub const MD_AVG_RATIFICATION: &[u8] = b"avg_ratification_time";
 pub const MD_AVG_PROPOSAL: &[u8] = b"avg_proposal_time";
+pub const MD_VOTE_STATS: &[u8] = b"vote_stats";
+pub const MD_ABSENCE_STREAKS: &[u8] = b"absence_streaks";
+pub const MD_ROUND_STATE: &[u8] = b"round_state";
+pub const MD_DUTY_SCHEDULE: &[u8] = b"duty_schedule";
+pub const MD_PERFORMANCE: &[u8] = b"performance";
+pub const MD_PROVISIONERS: &[u8] = b"provisioners";
+pub const MD_STALE_TIP: &[u8] = b"stale_tip";
+pub const MD_NOTIFIER: &[u8] = b"notifier";
+/// Height of the most recent final block
+pub const MD_LAST_FINAL: &[u8] = b"last_final";
+
+/// Number of rounds whose vote statistics are kept
+pub const VOTE_STATS_ROUNDS: u64 = 100;
+
+/// Returns the metadata key of the vote statistics of `round`.
+///
+/// Statistics are stored one record per round in a ring of
+/// [`VOTE_STATS_ROUNDS`] slots, each round overwriting the oldest one.
+pub fn md_vote_stats_key(round: u64) -> Vec<u8> {
+    let slot = round % VOTE_STATS_ROUNDS;
+    [MD_VOTE_STATS, &slot.to_be_bytes()[..]].concat()
+}
+/// Number of rounds whose performance is kept
+pub const PERFORMANCE_ROUNDS: u64 = 1_000;
+
+/// Returns the metadata key of the performance of the node's own
+/// provisioner in `round`.
+///
+/// Like the vote statistics, the performance is stored one record per round
+/// in a ring of [`PERFORMANCE_ROUNDS`] slots.
+pub fn md_performance_key(round: u64) -> Vec<u8> {
+    let slot = round % PERFORMANCE_ROUNDS;
+    [MD_PERFORMANCE, &slot.to_be_bytes()[..]].concat()
+}
+/// Height of the first block whose ledger data is not in cold storage
+pub const MD_COLD_HEIGHT: &[u8] = b"cold_height";
+/// Version of the schema the database is in
+pub const MD_SCHEMA_VERSION: &[u8] = b"schema_version";
+
+/// Version of the schema of the databases created by this node
+pub const SCHEMA_VERSION: u32 = 5;
+
+/// Migrations of the schema from its first version, in version order
+const MIGRATIONS: &[Migration<Backend>] = &[
+    Migration {
+        version: 2,
+        description: "record the gas price of the spent transactions",
+        apply: add_gas_price,
+    },
+    Migration {
+        version: 3,
+        description: "encode the spent transactions with their events",
+        apply: encode_spent_txs,
+    },
+    Migration {
+        version: 4,
+        description: "index the events and the gas of the stored blocks",
+        apply: index_ledger,
+    },
+    Migration {
+        version: 5,
+        description: "record the height of the most recent final block",
+        apply: record_last_final,
+    },
+];
+
+/// Inserts the gas price in the spent transactions stored before it was
+/// recorded, right after their gas spent. They were charged their fee cap.
+fn add_gas_price(t: &DBTransaction<'_, OptimisticTransactionDB>) -> Result<()> {
+    let records: Vec<_> = t
+        .inner
+        .iterator_cf(t.ledger_txs_cf, IteratorMode::Start)
+        .collect::<Result<_, _>>()?;
+
+    for (hash, blob) in records {
+        let mut reader = &blob[..];
+        let tx = ledger::Transaction::read(&mut reader)?;
+        // Block height and gas spent
+        let offset = blob.len() - reader.len() + 16;
+        if offset > blob.len() {
+            return Err(anyhow::anyhow!("Invalid spent transaction"));
+        }
+
+        let mut migrated = Vec::with_capacity(blob.len() + 8);
+        migrated.extend_from_slice(&blob[..offset]);
+        migrated.extend_from_slice(&tx.gas_price().to_le_bytes());
+        migrated.extend_from_slice(&blob[offset..]);
+        t.inner.put_cf(t.ledger_txs_cf, hash, migrated)?;
+    }
+    Ok(())
+}
+
+/// Rewrites the spent transactions in the current encoding.
+///
+/// The ones stored without an error were followed by a 64-bit length, read
+/// as a 32-bit one, and end before their events and call gas.
+fn encode_spent_txs(
+    t: &DBTransaction<'_, OptimisticTransactionDB>,
+) -> Result<()> {
+    let records: Vec<_> = t
+        .inner
+        .iterator_cf(t.ledger_txs_cf, IteratorMode::Start)
+        .collect::<Result<_, _>>()?;
+
+    for (hash, blob) in records {
+        let tx = SpentTransaction::read(&mut &blob[..])?;
+        let mut encoded = vec![];
+        tx.write(&mut encoded)?;
+        t.inner.put_cf(t.ledger_txs_cf, hash, encoded)?;
+    }
+    Ok(())
+}
+
+/// Indexes the events and the gas spent per contract of the transactions of
+/// the stored blocks, for the blocks stored before the indexes.
+///
+/// The events emitted by the blocks outside of their transactions are not
+/// recorded before, and cannot be indexed.
+fn index_ledger(t: &DBTransaction<'_, OptimisticTransactionDB>) -> Result<()> {
+    let blocks: Vec<_> = t
+        .inner
+        .iterator_cf(t.ledger_height_cf, IteratorMode::Start)
+        .collect::<Result<_, _>>()?;
+
+    for (_, record) in blocks {
+        let hash = record
+            .get(..32)
+            .ok_or_else(|| anyhow::anyhow!("Invalid height record"))?;
+        let Some((header, tx_ids)) = t.fetch_block_header(hash)? else {
+            continue;
+        };
+
+        let mut txs = Vec::with_capacity(tx_ids.len());
+        for id in tx_ids {
+            let tx = t
+                .get_ledger_tx_by_hash(&id)?
+                .ok_or_else(|| anyhow::anyhow!("Missing transaction"))?;
+            txs.push(tx);
+        }
+        t.index_txs(header.height, &txs)?;
+    }
+    Ok(())
+}
+
+/// Records the height of the most recent final block, scanning the labels
+/// back from the tip, for the databases predating the record.
+fn record_last_final(
+    t: &DBTransaction<'_, OptimisticTransactionDB>,
+) -> Result<()> {
+    let Some(tip) = t.op_read(MD_HASH_KEY)? else {
+        return Ok(());
+    };
+    let Some((tip, _)) = t.fetch_block_header(&tip)? else {
+        return Ok(());
+    };
+
+    for height in (0..=tip.height).rev() {
+        if t.fetch_block_label_by_height(height)? == Some(Label::Final) {
+            return t.op_write(MD_LAST_FINAL, height.to_le_bytes());
+        }
+    }
+    Ok(())
+}
+
+/// Number of blocks moved to cold storage at once
+const COLD_BATCH_BLOCKS: u64 = 1000;
 
 #[derive(Clone)]
 pub struct Backend {
     rocksdb: Arc<OptimisticTransactionDB>,
+    /// Storage of the ledger data of old blocks, if any
+    cold: Option<Arc<dyn ColdStorage>>,
+    /// Usage of the mempool, as committed
+    mempool_usage: Arc<UsageCounter>,
+}
+
+/// Running count of the mempool transactions and of their bytes
+#[derive(Debug, Default)]
+struct UsageCounter {
+    txs: AtomicI64,
+    bytes: AtomicI64,
+}
+
+impl UsageCounter {
+    fn add(&self, txs: i64, bytes: i64) {
+        self.txs.fetch_add(txs, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Disk usage of a column family, as estimated by RocksDB.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ColumnFamilyStats {
+    pub name: &'static str,
+    pub estimated_keys: u64,
+    /// Size of the SST files, in bytes
+    pub sst_files_size: u64,
+    /// Size of the live data, in bytes
+    pub live_data_size: u64,
+    /// Size of the memtables, in bytes
+    pub memtables_size: u64,
+    /// Space that a compaction would reclaim, in bytes
+    pub reclaimable_size: u64,
 }
 
 impl Backend {
+    /// Returns the disk usage of each column family.
+    pub fn stats(&self) -> Result<Vec<ColumnFamilyStats>> {
+        COLUMN_FAMILIES
+            .iter()
+            .map(|&name| {
+                let cf = self
+                    .rocksdb
+                    .cf_handle(name)
+                    .expect("column family must exist");
+                let property = |property: &str| -> Result<u64> {
+                    Ok(self
+                        .rocksdb
+                        .property_int_value_cf(cf, property)?
+                        .unwrap_or_default())
+                };
+
+                let sst_files_size = property("rocksdb.total-sst-files-size")?;
+                let live_data_size =
+                    property("rocksdb.estimate-live-data-size")?;
+
+                Ok(ColumnFamilyStats {
+                    name,
+                    estimated_keys: property("rocksdb.estimate-num-keys")?,
+                    sst_files_size,
+                    live_data_size,
+                    memtables_size: property(
+                        "rocksdb.cur-size-all-mem-tables",
+                    )?,
+                    reclaimable_size: sst_files_size
+                        .saturating_sub(live_data_size),
+                })
+            })
+            .collect()
+    }
+
+    /// Rebuilds the fee density index of the mempool transactions and counts
+    /// them, since the mempool may hold transactions not indexed, e.g. added
+    /// by an older version of the node.
+    fn index_mempool(&self) -> Result<()> {
+        let usage = self.update(|t| {
+            let iter = t.inner.iterator_cf(t.density_cf, IteratorMode::Start);
+            for (key, _) in iter.map(Result::unwrap) {
+                t.inner.delete_cf(t.density_cf, key)?;
+            }
+
+            let mut usage = MempoolUsage::default();
+            let iter = t.inner.iterator_cf(t.mempool_cf, IteratorMode::Start);
+            for (_, blob) in iter.map(Result::unwrap) {
+                let tx = ledger::Transaction::read(&mut &blob[..])?;
+                let footprint = TxFootprint::from(&tx);
+                t.inner.put_cf(
+                    t.density_cf,
+                    density_key(&footprint),
+                    footprint_value(&footprint),
+                )?;
+                usage.txs += 1;
+                usage.bytes += footprint.size;
+            }
+
+            Ok(usage)
+        })?;
+
+        let counter = &self.mempool_usage;
+        counter.txs.store(usage.txs as i64, Ordering::Relaxed);
+        counter.bytes.store(usage.bytes as i64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Reads the headers and transactions missing from the database through
+    /// the given cold storage.
+    pub fn with_cold_storage(mut self, cold: Arc<dyn ColdStorage>) -> Self {
+        self.cold = Some(cold);
+        self
+    }
+
+    /// Moves the headers and transactions of the final blocks more than
+    /// `hot_blocks` below the tip to the cold storage, blocking until done.
+    ///
+    /// Blocks are moved in height order, stopping at the first one that is
+    /// not final. Returns the number of blocks moved.
+    pub fn move_to_cold(&self, hot_blocks: u64) -> Result<u64> {
+        let cold = self
+            .cold
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No cold storage configured"))?;
+
+        let (mut height, tip) = self.view(|t| {
+            let height = t
+                .op_read(MD_COLD_HEIGHT)?
+                .map(|h| h.try_into().map(u64::from_le_bytes))
+                .transpose()
+                .map_err(|_| anyhow::anyhow!("Invalid cold height"))?
+                .unwrap_or_default();
+            let tip = match t.op_read(MD_HASH_KEY)? {
+                Some(hash) => t
+                    .fetch_block_header(&hash)?
+                    .map(|(header, _)| header.height)
+                    .unwrap_or_default(),
+                None => 0,
+            };
+            anyhow::Ok((height, tip))
+        })?;
+        let below = tip.saturating_sub(hot_blocks);
+
+        let mut moved = 0;
+        while height < below {
+            let end = below.min(height + COLD_BATCH_BLOCKS);
+            let (next, count) =
+                self.update(|t| t.move_to_cold(cold.as_ref(), height, end))?;
+
+            moved += count;
+            if next < end {
+                // A block not final yet was found
+                break;
+            }
+            height = next;
+        }
+
+        if moved > 0 {
+            info!("Moved {moved} blocks to cold storage");
+        }
+        Ok(moved)
+    }
+
+    /// Brings the schema of the database to [`SCHEMA_VERSION`], applying
+    /// the migrations it misses.
+    ///
+    /// Fails if a migration fails, leaving the database at the version the
+    /// migration started from.
+    pub fn migrate(&self) -> Result<u32> {
+        migration::migrate(self, MIGRATIONS, SCHEMA_VERSION)
+    }
+
+    /// Compacts every column family, blocking until done.
+    pub fn compact(&self) {
+        for name in COLUMN_FAMILIES {
+            let cf = self
+                .rocksdb
+                .cf_handle(name)
+                .expect("column family must exist");
+
+            info!("Compacting column family {name}");
+            self.rocksdb
+                .compact_range_cf(cf, None::<&[u8]>, None::<&[u8]>);
+        }
+    }
+
     fn begin_tx(&self) -> DBTransaction<'_, OptimisticTransactionDB> {
         // Create a new RocksDB transaction
         let write_options = WriteOptions::default();
@@ -98,6 +470,11 @@ impl Backend {
             .cf_handle(CF_MEMPOOL_FEES)
             .expect("CF_MEMPOOL_FEES column family must exist");
 
+        let density_cf = self
+            .rocksdb
+            .cf_handle(CF_MEMPOOL_DENSITY)
+            .expect("CF_MEMPOOL_DENSITY column family must exist");
+
         let ledger_height_cf = self
             .rocksdb
             .cf_handle(CF_LEDGER_HEIGHT)
@@ -108,10 +485,26 @@ impl Backend {
             .cf_handle(CF_METADATA)
             .expect("CF_METADATA column family must exist");
 
+        let ledger_events_cf = self
+            .rocksdb
+            .cf_handle(CF_LEDGER_EVENTS)
+            .expect("CF_LEDGER_EVENTS column family must exist");
+
+        let ledger_gas_cf = self
+            .rocksdb
+            .cf_handle(CF_LEDGER_GAS)
+            .expect("CF_LEDGER_GAS column family must exist");
+
+        let ledger_block_events_cf = self
+            .rocksdb
+            .cf_handle(CF_LEDGER_BLOCK_EVENTS)
+            .expect("CF_LEDGER_BLOCK_EVENTS column family must exist");
+
         let snapshot = self.rocksdb.snapshot();
 
         DBTransaction::<'_, OptimisticTransactionDB> {
             inner,
+            cold: self.cold.as_deref(),
             candidates_cf,
             candidates_height_cf,
             ledger_cf,
@@ -119,7 +512,13 @@ impl Backend {
             mempool_cf,
             nullifiers_cf,
             fees_cf,
+            density_cf,
+            mempool_usage: &self.mempool_usage,
+            pending_usage: UsageCounter::default(),
             ledger_height_cf,
+            ledger_events_cf,
+            ledger_gas_cf,
+            ledger_block_events_cf,
             metadata_cf,
             snapshot,
         }
@@ -156,6 +555,12 @@ impl DB for Backend {
             ColumnFamilyDescriptor::new(CF_LEDGER_HEADER, Options::default()),
             ColumnFamilyDescriptor::new(CF_LEDGER_TXS, Options::default()),
             ColumnFamilyDescriptor::new(CF_LEDGER_HEIGHT, Options::default()),
+            ColumnFamilyDescriptor::new(CF_LEDGER_EVENTS, Options::default()),
+            ColumnFamilyDescriptor::new(CF_LEDGER_GAS, Options::default()),
+            ColumnFamilyDescriptor::new(
+                CF_LEDGER_BLOCK_EVENTS,
+                Options::default(),
+            ),
             ColumnFamilyDescriptor::new(CF_CANDIDATES, Options::default()),
             ColumnFamilyDescriptor::new(
                 CF_CANDIDATES_HEIGHT,
@@ -164,17 +569,23 @@ impl DB for Backend {
             ColumnFamilyDescriptor::new(CF_MEMPOOL, mp_opts.clone()),
             ColumnFamilyDescriptor::new(CF_MEMPOOL_NULLIFIERS, mp_opts.clone()),
             ColumnFamilyDescriptor::new(CF_MEMPOOL_FEES, mp_opts.clone()),
+            ColumnFamilyDescriptor::new(CF_MEMPOOL_DENSITY, mp_opts.clone()),
             ColumnFamilyDescriptor::new(CF_METADATA, mp_opts),
         ];
 
-        Self {
+        let db = Self {
             rocksdb: Arc::new(
                 rocksdb_lib::OptimisticTransactionDB::open_cf_descriptors(
                     &opts, path, cfs,
                 )
                 .expect("should be a valid database in {path}"),
             ),
-        }
+            cold: None,
+            mempool_usage: Arc::default(),
+        };
+
+        db.index_mempool().expect("mempool to be indexed");
+        db
     }
 
     fn view<F, T>(&self, f: F) -> T
@@ -210,6 +621,7 @@ impl DB for Backend {
 
 pub struct DBTransaction<'db, DB: DBAccess> {
     inner: rocksdb_lib::Transaction<'db, DB>,
+    cold: Option<&'db dyn ColdStorage>,
 
     // TODO: pack all column families into a single array
     // Candidates column family
@@ -220,11 +632,21 @@ pub struct DBTransaction<'db, DB: DBAccess> {
     ledger_cf: &'db ColumnFamily,
     ledger_txs_cf: &'db ColumnFamily,
     ledger_height_cf: &'db ColumnFamily,
+    ledger_events_cf: &'db ColumnFamily,
+    ledger_gas_cf: &'db ColumnFamily,
+    ledger_block_events_cf: &'db ColumnFamily,
 
     // Mempool column families
     mempool_cf: &'db ColumnFamily,
     nullifiers_cf: &'db ColumnFamily,
     fees_cf: &'db ColumnFamily,
+    density_cf: &'db ColumnFamily,
+
+    /// Usage of the mempool, as committed
+    mempool_usage: &'db UsageCounter,
+    /// Change of the usage of the mempool by this transaction, applied on
+    /// commit
+    pending_usage: UsageCounter,
 
     metadata_cf: &'db ColumnFamily,
 
@@ -273,6 +695,8 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
             }
         }
 
+        self.index_txs(header.height, txs)?;
+
         // CF: HEIGHT -> (BLOCK_HASH, BLOCK_LABEL)
         let mut buf = vec![];
         buf.write_all(&header.hash[..])?;
@@ -292,8 +716,42 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
             self.ledger_height_cf,
             b.header().height.to_le_bytes(),
         )?;
+        self.inner
+            .delete_cf(self.ledger_gas_cf, b.header().height.to_be_bytes())?;
+
+        let height = b.header().height;
+        for (event_index, event) in
+            self.fetch_block_events(height)?.iter().enumerate()
+        {
+            let position = EventPosition {
+                height,
+                tx_index: BLOCK_EVENTS_TX_INDEX,
+                event_index: event_index as u32,
+            };
+            self.inner.delete_cf(
+                self.ledger_events_cf,
+                event_key(&event.source, &event.topic, &position),
+            )?;
+        }
+        self.inner
+            .delete_cf(self.ledger_block_events_cf, height.to_be_bytes())?;
+
+        for (tx_index, tx) in b.txs().iter().enumerate() {
+            // Remove the events of the transaction from the index
+            if let Some(spent) = self.get_ledger_tx_by_hash(&tx.hash())? {
+                for (event_index, event) in spent.events.iter().enumerate() {
+                    let position = EventPosition {
+                        height: b.header().height,
+                        tx_index: tx_index as u32,
+                        event_index: event_index as u32,
+                    };
+                    self.inner.delete_cf(
+                        self.ledger_events_cf,
+                        event_key(&event.source, &event.topic, &position),
+                    )?;
+                }
+            }
 
-        for tx in b.txs() {
             self.inner.delete_cf(self.ledger_txs_cf, tx.hash())?;
         }
 
@@ -302,12 +760,67 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
         Ok(())
     }
 
+    fn store_block_events(
+        &self,
+        height: u64,
+        events: &[ledger::ContractEvent],
+    ) -> Result<()> {
+        // COLUMN FAMILY: CF_LEDGER_BLOCK_EVENTS
+        // HEIGHT -> [ContractEvent]
+        let mut buf = vec![];
+        for event in events {
+            event.write(&mut buf)?;
+        }
+        self.inner.put_cf(
+            self.ledger_block_events_cf,
+            height.to_be_bytes(),
+            buf,
+        )?;
+
+        // COLUMN FAMILY: CF_LEDGER_EVENTS
+        // (SOURCE, TOPIC, HEIGHT, BLOCK_EVENTS_TX_INDEX, EVENT_INDEX)
+        //     -> ([0; 32], DATA)
+        for (event_index, event) in events.iter().enumerate() {
+            let position = EventPosition {
+                height,
+                tx_index: BLOCK_EVENTS_TX_INDEX,
+                event_index: event_index as u32,
+            };
+            let key = event_key(&event.source, &event.topic, &position);
+
+            let mut value = Vec::with_capacity(32 + event.data.len());
+            value.extend_from_slice(&[0u8; 32]);
+            value.extend_from_slice(&event.data);
+
+            self.inner.put_cf(self.ledger_events_cf, key, value)?;
+        }
+
+        Ok(())
+    }
+
+    fn fetch_block_events(
+        &self,
+        height: u64,
+    ) -> Result<Vec<ledger::ContractEvent>> {
+        let mut events = vec![];
+        if let Some(blob) = self
+            .snapshot
+            .get_cf(self.ledger_block_events_cf, height.to_be_bytes())?
+        {
+            let mut buf = &blob[..];
+            while !buf.is_empty() {
+                events.push(ledger::ContractEvent::read(&mut buf)?);
+            }
+        }
+        Ok(events)
+    }
+
     fn get_block_exists(&self, hash: &[u8]) -> Result<bool> {
-        Ok(self.snapshot.get_cf(self.ledger_cf, hash)?.is_some())
+        Ok(self.header_blob(hash)?.is_some())
     }
 
     fn fetch_block(&self, hash: &[u8]) -> Result<Option<ledger::Block>> {
-        match self.snapshot.get_cf(self.ledger_cf, hash)? {
+        match self.header_blob(hash)? {
             Some(blob) => {
                 let record = HeaderRecord::read(&mut &blob[..])?;
 
@@ -321,10 +834,18 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
                 );
 
                 let mut txs = vec![];
-                for buf in txs_buffers {
-                    let buf = buf?.unwrap();
-                    let tx =
-                        ledger::SpentTransaction::read(&mut &buf.to_vec()[..])?;
+                for (buf, id) in
+                    txs_buffers.into_iter().zip(&record.transactions_ids)
+                {
+                    let buf = match buf? {
+                        Some(buf) => buf,
+                        None => {
+                            self.cold_blob(cold::TX_PREFIX, id)?.ok_or_else(
+                                || anyhow::anyhow!("Cannot find transaction"),
+                            )?
+                        }
+                    };
+                    let tx = ledger::SpentTransaction::read(&mut &buf[..])?;
                     txs.push(tx.inner);
                 }
 
@@ -341,7 +862,7 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
         &self,
         hash: &[u8],
     ) -> Result<Option<(ledger::Header, Vec<[u8; 32]>)>> {
-        match self.snapshot.get_cf(self.ledger_cf, hash)? {
+        match self.header_blob(hash)? {
             Some(blob) => {
                 let record = HeaderRecord::read(&mut &blob[..])?;
                 Ok(Some((record.header, record.transactions_ids)))
@@ -370,8 +891,7 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
         tx_hash: &[u8],
     ) -> Result<Option<ledger::SpentTransaction>> {
         let tx = self
-            .snapshot
-            .get_cf(self.ledger_txs_cf, tx_hash)?
+            .tx_blob(tx_hash)?
             .map(|blob| ledger::SpentTransaction::read(&mut &blob[..]))
             .transpose()?;
 
@@ -384,7 +904,7 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
     /// This is a convenience method that checks if a transaction exists in the
     /// ledger without unmarshalling the transaction
     fn get_ledger_tx_exists(&self, tx_hash: &[u8]) -> Result<bool> {
-        Ok(self.snapshot.get_cf(self.ledger_txs_cf, tx_hash)?.is_some())
+        Ok(self.tx_blob(tx_hash)?.is_some())
     }
 
     fn fetch_block_by_height(
@@ -410,6 +930,242 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
             .filter(|v| v.len() == LEN)
             .map(|h| Label::from(h[LEN - 1])))
     }
+
+    fn fetch_events(
+        &self,
+        source: &[u8; 32],
+        topic: &str,
+        from: EventPosition,
+        to: u64,
+        limit: usize,
+    ) -> Result<Vec<IndexedEvent>> {
+        let prefix = event_key_prefix(source, topic);
+
+        let mut iter = self.inner.raw_iterator_cf(self.ledger_events_cf);
+        iter.seek(event_key(source, topic, &from));
+
+        let mut events = vec![];
+        while iter.valid() && events.len() < limit {
+            let (Some(key), Some(value)) = (iter.key(), iter.value()) else {
+                break;
+            };
+            if !key.starts_with(&prefix) || value.len() < 32 {
+                break;
+            }
+
+            let position = event_position(&key[prefix.len()..])?;
+            if position.height > to {
+                break;
+            }
+
+            let mut tx_hash = [0u8; 32];
+            tx_hash.copy_from_slice(&value[..32]);
+
+            events.push(IndexedEvent {
+                position,
+                tx_hash,
+                event: ledger::ContractEvent {
+                    source: *source,
+                    topic: topic.to_string(),
+                    data: value[32..].to_vec(),
+                },
+            });
+
+            iter.next();
+        }
+
+        Ok(events)
+    }
+
+    fn fetch_gas_usage(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(u64, Vec<ledger::ContractGas>)>> {
+        let mut iter = self.inner.raw_iterator_cf(self.ledger_gas_cf);
+        iter.seek(from.to_be_bytes());
+
+        let mut blocks = vec![];
+        while iter.valid() {
+            let (Some(key), Some(mut value)) = (iter.key(), iter.value())
+            else {
+                break;
+            };
+            let height = u64::from_be_bytes(key.try_into()?);
+            if height > to {
+                break;
+            }
+
+            let mut usage = vec![];
+            while !value.is_empty() {
+                usage.push(ledger::ContractGas::read(&mut value)?);
+            }
+            blocks.push((height, usage));
+
+            iter.next();
+        }
+
+        Ok(blocks)
+    }
+}
+
+impl<'db, DB: DBAccess> DBTransaction<'db, DB> {
+    /// Indexes the events and the gas spent per contract of the transactions
+    /// of the block at `height`.
+    fn index_txs(&self, height: u64, txs: &[SpentTransaction]) -> Result<()> {
+        // COLUMN FAMILY: CF_LEDGER_EVENTS
+        // (SOURCE, TOPIC, HEIGHT, TX_INDEX, EVENT_INDEX) -> (TX_HASH, DATA)
+        for (tx_index, tx) in txs.iter().enumerate() {
+            let tx_hash = tx.inner.hash();
+            for (event_index, event) in tx.events.iter().enumerate() {
+                let position = EventPosition {
+                    height,
+                    tx_index: tx_index as u32,
+                    event_index: event_index as u32,
+                };
+                let key = event_key(&event.source, &event.topic, &position);
+
+                let mut value = Vec::with_capacity(32 + event.data.len());
+                value.extend_from_slice(&tx_hash);
+                value.extend_from_slice(&event.data);
+
+                self.inner.put_cf(self.ledger_events_cf, key, value)?;
+            }
+        }
+
+        // COLUMN FAMILY: CF_LEDGER_GAS
+        // HEIGHT -> [ContractGas]
+        let usage = ledger::gas_by_contract(txs);
+
+        let mut buf = vec![];
+        for contract_gas in &usage {
+            contract_gas.write(&mut buf)?;
+        }
+
+        self.inner
+            .put_cf(self.ledger_gas_cf, height.to_be_bytes(), buf)?;
+
+        Ok(())
+    }
+
+    /// Returns the header record of a block, reading it through the cold
+    /// storage if missing.
+    fn header_blob(&self, hash: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self.snapshot.get_cf(self.ledger_cf, hash)? {
+            Some(blob) => Ok(Some(blob)),
+            None => self.cold_blob(cold::HEADER_PREFIX, hash),
+        }
+    }
+
+    /// Returns the record of a transaction, reading it through the cold
+    /// storage if missing.
+    fn tx_blob(&self, tx_hash: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self.snapshot.get_cf(self.ledger_txs_cf, tx_hash)? {
+            Some(blob) => Ok(Some(blob)),
+            None => self.cold_blob(cold::TX_PREFIX, tx_hash),
+        }
+    }
+
+    fn cold_blob(&self, prefix: u8, id: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self.cold {
+            Some(cold) => cold.get(&cold::key(prefix, id)),
+            None => Ok(None),
+        }
+    }
+
+    /// Moves the header and transactions of the final blocks from height
+    /// `from` up to `to` excluded to `cold`.
+    ///
+    /// Returns the height of the first block not moved, along with the
+    /// number of blocks moved.
+    fn move_to_cold(
+        &self,
+        cold: &dyn ColdStorage,
+        from: u64,
+        to: u64,
+    ) -> Result<(u64, u64)> {
+        let mut records = vec![];
+        let mut moved = vec![];
+
+        let mut height = from;
+        while height < to {
+            if self.fetch_block_label_by_height(height)? != Some(Label::Final) {
+                break;
+            }
+            let hash = self
+                .fetch_block_hash_by_height(height)?
+                .ok_or_else(|| anyhow::anyhow!("Cannot find block {height}"))?;
+
+            // Blocks already in cold storage have no record left
+            if let Some(blob) = self.snapshot.get_cf(self.ledger_cf, hash)? {
+                let record = HeaderRecord::read(&mut &blob[..])?;
+                for id in &record.transactions_ids {
+                    let tx = self
+                        .snapshot
+                        .get_cf(self.ledger_txs_cf, id)?
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("Cannot find transaction")
+                        })?;
+                    records.push((cold::key(cold::TX_PREFIX, id), tx));
+                }
+                records.push((cold::key(cold::HEADER_PREFIX, &hash), blob));
+                moved.push((hash, record.transactions_ids));
+            }
+
+            height += 1;
+        }
+
+        // Records are stored in cold storage before being deleted, so that
+        // they remain readable if the transaction fails
+        cold.put_all(records)?;
+        for (hash, txs) in &moved {
+            for id in txs {
+                self.inner.delete_cf(self.ledger_txs_cf, id)?;
+            }
+            self.inner.delete_cf(self.ledger_cf, hash)?;
+        }
+        self.op_write(MD_COLD_HEIGHT, height.to_le_bytes())?;
+
+        Ok((height, moved.len() as u64))
+    }
+}
+
+/// Returns the common prefix of the index keys of the events emitted by
+/// `source` with the given `topic`.
+fn event_key_prefix(source: &[u8; 32], topic: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(32 + 4 + topic.len() + 16);
+    key.extend_from_slice(source);
+    // The topic length is part of the prefix, so that a topic is never the
+    // prefix of another one
+    key.extend_from_slice(&(topic.len() as u32).to_be_bytes());
+    key.extend_from_slice(topic.as_bytes());
+    key
+}
+
+/// Returns the index key of an event. Positions are big-endian encoded so
+/// that keys sort in ledger order.
+fn event_key(
+    source: &[u8; 32],
+    topic: &str,
+    position: &EventPosition,
+) -> Vec<u8> {
+    let mut key = event_key_prefix(source, topic);
+    key.extend_from_slice(&position.height.to_be_bytes());
+    key.extend_from_slice(&position.tx_index.to_be_bytes());
+    key.extend_from_slice(&position.event_index.to_be_bytes());
+    key
+}
+
+fn event_position(buf: &[u8]) -> Result<EventPosition> {
+    if buf.len() != 16 {
+        anyhow::bail!("invalid event key length {}", buf.len());
+    }
+
+    Ok(EventPosition {
+        height: u64::from_be_bytes(buf[..8].try_into()?),
+        tx_index: u32::from_be_bytes(buf[8..12].try_into()?),
+        event_index: u32::from_be_bytes(buf[12..].try_into()?),
+    })
 }
 
 /// Implementation of the `Candidate` trait for `DBTransaction<'db, DB>`.
@@ -529,6 +1285,12 @@ impl<'db, DB: DBAccess> Persist for DBTransaction<'db, DB> {
             return Err(anyhow::Error::new(e).context("failed to commit"));
         }
 
+        let pending = &self.pending_usage;
+        self.mempool_usage.add(
+            pending.txs.load(Ordering::Relaxed),
+            pending.bytes.load(Ordering::Relaxed),
+        );
+
         Ok(())
     }
 }
@@ -540,6 +1302,7 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
         tx.write(&mut tx_data)?;
 
         let hash = tx.hash();
+        let exists = self.inner.get_cf(self.mempool_cf, hash)?.is_some();
         self.inner.put_cf(self.mempool_cf, hash, tx_data)?;
 
         // Add Secondary indexes //
@@ -556,6 +1319,19 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
             vec![0],
         )?;
 
+        // Map Density_Hash to the tx footprint to facilitate the eviction of
+        // the lowest fee density first
+        let footprint = TxFootprint::from(tx);
+        self.inner.put_cf(
+            self.density_cf,
+            density_key(&footprint),
+            footprint_value(&footprint),
+        )?;
+
+        if !exists {
+            self.pending_usage.add(1, footprint.size as i64);
+        }
+
         Ok(())
     }
 
@@ -595,6 +1371,12 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
                 serialize_key(tx.gas_price(), hash)?,
             )?;
 
+            // Delete Density_Hash
+            let footprint = TxFootprint::from(&tx);
+            self.inner
+                .delete_cf(self.density_cf, density_key(&footprint))?;
+            self.pending_usage.add(-1, -(footprint.size as i64));
+
             return Ok(true);
         }
 
@@ -645,6 +1427,39 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
 
         Ok(txs_list)
     }
+
+    fn get_txs_usage(&self) -> MempoolUsage {
+        let (committed, pending) = (self.mempool_usage, &self.pending_usage);
+        let sum = |a: &AtomicI64, b: &AtomicI64| {
+            (a.load(Ordering::Relaxed) + b.load(Ordering::Relaxed)).max(0)
+                as usize
+        };
+        MempoolUsage {
+            txs: sum(&committed.txs, &pending.txs),
+            bytes: sum(&committed.bytes, &pending.bytes),
+        }
+    }
+
+    fn get_txs_footprints_by_density(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = TxFootprint> + '_>> {
+        let iter = self
+            .inner
+            .iterator_cf(self.density_cf, IteratorMode::Start)
+            .map_while(|entry| {
+                let (key, value) = entry.ok()?;
+                let hash = key.get(16..)?.try_into().ok()?;
+                let size = value.get(..8)?.try_into().ok()?;
+                let max_fee = value.get(8..16)?.try_into().ok()?;
+                Some(TxFootprint {
+                    hash,
+                    size: u64::from_le_bytes(size) as usize,
+                    max_fee: u64::from_le_bytes(max_fee),
+                })
+            });
+
+        Ok(Box::new(iter))
+    }
 }
 
 pub struct MemPoolIterator<'db, DB: DBAccess, M: Mempool> {
@@ -764,6 +1579,20 @@ fn serialize_key(value: u64, hash: [u8; 32]) -> std::io::Result<Vec<u8>> {
     Ok(w)
 }
 
+/// Returns the key of a transaction in the fee density index, sorting the
+/// transactions by the fee they pay per byte, then by hash.
+fn density_key(footprint: &TxFootprint) -> Vec<u8> {
+    let density =
+        ((footprint.max_fee as u128) << 64) / footprint.size.max(1) as u128;
+    [&density.to_be_bytes()[..], &footprint.hash[..]].concat()
+}
+
+fn footprint_value(footprint: &TxFootprint) -> Vec<u8> {
+    let mut value = (footprint.size as u64).to_le_bytes().to_vec();
+    value.extend(footprint.max_fee.to_le_bytes());
+    value
+}
+
 fn deserialize_key<R: Read>(r: &mut R) -> Result<(u64, [u8; 32])> {
     let mut buf = [0u8; 8];
     r.read_exact(&mut buf)?;
@@ -831,6 +1660,8 @@ mod tests {
     use fake::{Fake, Faker};
     use node_data::ledger::Transaction;
 
+    use crate::database::cold::RocksColdStorage;
+
     #[test]
     fn test_store_block() {
         TestWrapper::new("test_store_block").run(|path| {
@@ -1045,13 +1876,69 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_mempool_txs_footprints() {
+        TestWrapper::new("test_mempool_txs_footprints").run(|path| {
+            let db: Backend = Backend::create_or_open(path);
+            let txs: Vec<_> =
+                (1..5u64).map(ledger::faker::gen_dummy_tx).collect();
+
+            db.update(|txn| {
+                for t in txs.iter() {
+                    txn.add_tx(t)?;
+                }
+                txn.delete_tx(txs[0].hash())?;
+                Ok(())
+            })
+            .unwrap();
+
+            // Changes not committed are not counted
+            let _: Result<()> = db.update(|txn| {
+                txn.delete_tx(txs[1].hash())?;
+                anyhow::bail!("rolled back")
+            });
+
+            let mut expected: Vec<_> =
+                txs[1..].iter().map(TxFootprint::from).collect();
+            expected.sort_by(|a, b| {
+                let a_density = a.max_fee as u128 * b.size as u128;
+                let b_density = b.max_fee as u128 * a.size as u128;
+                a_density.cmp(&b_density)
+            });
+            let usage = MempoolUsage {
+                txs: 3,
+                bytes: expected.iter().map(|f| f.size).sum(),
+            };
+
+            db.view(|txn| {
+                let footprints: Vec<_> =
+                    txn.get_txs_footprints_by_density().unwrap().collect();
+                assert_eq!(footprints, expected);
+                assert_eq!(txn.get_txs_usage(), usage);
+            });
+
+            // The index and the count are rebuilt on open
+            drop(db);
+            let db: Backend = Backend::create_or_open(path);
+            db.view(|txn| {
+                let footprints: Vec<_> =
+                    txn.get_txs_footprints_by_density().unwrap().collect();
+                assert_eq!(footprints, expected);
+                assert_eq!(txn.get_txs_usage(), usage);
+            });
+        });
+    }
+
     fn to_spent_txs(txs: &Vec<Transaction>) -> Vec<SpentTransaction> {
         txs.iter()
             .map(|t| SpentTransaction {
                 inner: t.clone(),
                 block_height: 0,
                 gas_spent: 0,
+                gas_price: t.gas_price(),
                 err: None,
+                events: vec![],
+                call_gas: vec![],
             })
             .collect()
     }
@@ -1090,6 +1977,66 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_migrate_ledger() {
+        TestWrapper::new("test_migrate_ledger").run(|path| {
+            let db: Backend = Backend::create_or_open(path);
+            let b: ledger::Block = Faker.fake();
+            let height = b.header().height;
+            let txs = to_spent_txs(b.txs());
+
+            // Store the block as recorded by the first version of the schema:
+            // the transactions without their gas price, their error followed
+            // by a 64-bit length, and no index
+            db.update(|t| {
+                t.store_block(b.header(), &txs, Label::Final)?;
+                for tx in txs.iter() {
+                    let mut blob = vec![];
+                    tx.inner.write(&mut blob)?;
+                    blob.extend_from_slice(&tx.block_height.to_le_bytes());
+                    blob.extend_from_slice(&tx.gas_spent.to_le_bytes());
+                    blob.extend_from_slice(&0u64.to_le_bytes());
+                    t.inner.put_cf(t.ledger_txs_cf, tx.inner.hash(), blob)?;
+                }
+                t.inner.delete_cf(t.ledger_gas_cf, height.to_be_bytes())?;
+                t.op_write(MD_HASH_KEY, b.header().hash)?;
+                t.op_write(MD_SCHEMA_VERSION, 1u32.to_le_bytes())
+            })
+            .unwrap();
+            assert!(db
+                .view(|v| v.fetch_gas_usage(height, height))
+                .unwrap()
+                .is_empty());
+
+            assert_eq!(db.migrate().unwrap(), SCHEMA_VERSION);
+
+            db.view(|v| {
+                for tx in txs.iter() {
+                    let stored = v
+                        .get_ledger_tx_by_hash(&tx.inner.hash())
+                        .expect("should not return error")
+                        .expect("should find a transaction");
+                    assert_eq!(stored.gas_price, tx.inner.gas_price());
+                    assert!(stored.inner.eq(&tx.inner));
+
+                    let blob = v
+                        .snapshot
+                        .get_cf(v.ledger_txs_cf, tx.inner.hash())
+                        .unwrap()
+                        .unwrap();
+                    let mut encoded = vec![];
+                    tx.write(&mut encoded).unwrap();
+                    assert_eq!(blob, encoded);
+                }
+                let usage = v.fetch_gas_usage(height, height).unwrap();
+                assert_eq!(usage.len(), 1);
+
+                let last_final = v.op_read(MD_LAST_FINAL).unwrap().unwrap();
+                assert_eq!(last_final, height.to_le_bytes());
+            });
+        });
+    }
+
     #[test]
     fn test_fetch_block_hash_by_height() {
         TestWrapper::new("test_fetch_block_hash_by_height").run(|path| {
@@ -1200,6 +2147,299 @@ mod tests {
             .for_each(drop);
     }
 
+    #[test]
+    fn test_fetch_events() {
+        TestWrapper::new("test_fetch_events").run(|path| {
+            let db: Backend = Backend::create_or_open(path);
+            let b: ledger::Block = Faker.fake();
+            assert!(!b.txs().is_empty());
+
+            let source = [1u8; 32];
+            let event = |topic: &str, data: u8| ledger::ContractEvent {
+                source,
+                topic: topic.to_string(),
+                data: vec![data],
+            };
+
+            let mut txs = to_spent_txs(b.txs());
+            txs[0].events = vec![
+                event("transfer", 0),
+                event("mint", 1),
+                event("transfer", 2),
+                // A topic prefixed by another one is not matched
+                event("transfers", 3),
+            ];
+
+            db.update(|ut| ut.store_block(b.header(), &txs, Label::Final))
+                .unwrap();
+
+            let height = b.header().height;
+            let from = EventPosition {
+                height,
+                ..Default::default()
+            };
+
+            db.view(|v| {
+                let events = v
+                    .fetch_events(&source, "transfer", from, height, 10)
+                    .unwrap();
+                let data: Vec<_> =
+                    events.iter().map(|e| e.event.data[0]).collect();
+                assert_eq!(data, vec![0, 2]);
+                assert_eq!(events[1].tx_hash, b.txs()[0].hash());
+
+                // Paginate
+                let page = v
+                    .fetch_events(&source, "transfer", from, height, 1)
+                    .unwrap();
+                assert_eq!(page.len(), 1);
+                let page = v
+                    .fetch_events(
+                        &source,
+                        "transfer",
+                        page[0].position.next(),
+                        height,
+                        1,
+                    )
+                    .unwrap();
+                assert_eq!(page[0].event.data, vec![2]);
+
+                // Out of the height range
+                if let Some(to) = height.checked_sub(1) {
+                    assert!(v
+                        .fetch_events(&source, "transfer", from, to, 10)
+                        .unwrap()
+                        .is_empty());
+                }
+                assert!(v
+                    .fetch_events(&[2u8; 32], "transfer", from, height, 10)
+                    .unwrap()
+                    .is_empty());
+            });
+
+            // Events are removed along with their block
+            db.update(|ut| ut.delete_block(&b)).unwrap();
+            db.view(|v| {
+                assert!(v
+                    .fetch_events(&source, "mint", from, height, 10)
+                    .unwrap()
+                    .is_empty());
+            });
+        });
+    }
+
+    #[test]
+    fn test_block_events() {
+        TestWrapper::new("test_block_events").run(|path| {
+            let db: Backend = Backend::create_or_open(path);
+            let b: ledger::Block = Faker.fake();
+            let height = b.header().height;
+
+            let source = [1u8; 32];
+            let event = |topic: &str, data: u8| ledger::ContractEvent {
+                source,
+                topic: topic.to_string(),
+                data: vec![data],
+            };
+
+            let mut txs = to_spent_txs(b.txs());
+            txs[0].events = vec![event("reward", 0)];
+            let block_events = vec![event("reward", 1), event("slash", 2)];
+
+            db.update(|ut| {
+                ut.store_block(b.header(), &txs, Label::Final)?;
+                ut.store_block_events(height, &block_events)
+            })
+            .unwrap();
+
+            let from = EventPosition {
+                height,
+                ..Default::default()
+            };
+            db.view(|v| {
+                assert_eq!(v.fetch_block_events(height).unwrap(), block_events);
+                assert!(v.fetch_block_events(height + 1).unwrap().is_empty());
+
+                // Block events are indexed after the transaction ones
+                let events = v
+                    .fetch_events(&source, "reward", from, height, 10)
+                    .unwrap();
+                let data: Vec<_> =
+                    events.iter().map(|e| e.event.data[0]).collect();
+                assert_eq!(data, vec![0, 1]);
+                assert_eq!(events[1].position.tx_index, BLOCK_EVENTS_TX_INDEX);
+                assert_eq!(events[1].tx_hash, [0u8; 32]);
+            });
+
+            // Block events are removed along with their block
+            db.update(|ut| ut.delete_block(&b)).unwrap();
+            db.view(|v| {
+                assert!(v.fetch_block_events(height).unwrap().is_empty());
+                assert!(v
+                    .fetch_events(&source, "slash", from, height, 10)
+                    .unwrap()
+                    .is_empty());
+            });
+        });
+    }
+
+    #[test]
+    fn test_fetch_gas_usage() {
+        TestWrapper::new("test_fetch_gas_usage").run(|path| {
+            let db: Backend = Backend::create_or_open(path);
+            let b: ledger::Block = Faker.fake();
+            let height = b.header().height;
+
+            let mut txs = to_spent_txs(b.txs());
+            for (i, tx) in txs.iter_mut().enumerate() {
+                tx.gas_spent = 10 * (i as u64 + 1);
+            }
+
+            // A nested call is attributed to the callee as well
+            let callee = [0xab; 32];
+            let first = txs.first_mut().expect("block to have transactions");
+            first.call_gas = vec![
+                ledger::CallGas {
+                    contract: first.inner.called_contract(),
+                    gas_spent: first.gas_spent,
+                },
+                ledger::CallGas {
+                    contract: callee,
+                    gas_spent: 4,
+                },
+            ];
+            let expected = ledger::gas_by_contract(&txs);
+
+            db.update(|ut| ut.store_block(b.header(), &txs, Label::Final))
+                .unwrap();
+
+            db.view(|v| {
+                let usage = v.fetch_gas_usage(height, height).unwrap();
+                assert_eq!(usage, vec![(height, expected.clone())]);
+
+                let nested = usage[0]
+                    .1
+                    .iter()
+                    .find(|c| c.contract == callee)
+                    .expect("callee to be attributed gas");
+                assert_eq!((nested.gas_spent, nested.transactions), (4, 1));
+
+                let gas_spent: u64 = usage[0]
+                    .1
+                    .iter()
+                    .filter(|c| c.contract != callee)
+                    .map(|c| c.gas_spent)
+                    .sum();
+                assert_eq!(gas_spent, txs.iter().map(|t| t.gas_spent).sum());
+
+                if let Some(to) = height.checked_sub(1) {
+                    assert!(v.fetch_gas_usage(0, to).unwrap().is_empty());
+                }
+            });
+
+            // Usage is removed along with its block
+            db.update(|ut| ut.delete_block(&b)).unwrap();
+            db.view(|v| {
+                assert!(v.fetch_gas_usage(height, height).unwrap().is_empty());
+            });
+        });
+    }
+
+    #[test]
+    fn test_move_to_cold() {
+        TestWrapper::new("test_move_to_cold").run(|path| {
+            let cold = RocksColdStorage::open(path.join("cold"))
+                .expect("cold storage to be opened");
+            let db =
+                Backend::create_or_open(path).with_cold_storage(Arc::new(cold));
+
+            let block_at = |height| {
+                let b: ledger::Block = Faker.fake();
+                let mut header = b.header().clone();
+                header.height = height;
+                ledger::Block::new(header, b.txs().clone()).unwrap()
+            };
+            let old = block_at(0);
+            let not_final = block_at(1);
+            let tip = block_at(10);
+
+            db.update(|ut| {
+                ut.store_block(
+                    old.header(),
+                    &to_spent_txs(old.txs()),
+                    Label::Final,
+                )?;
+                ut.store_block(
+                    not_final.header(),
+                    &to_spent_txs(not_final.txs()),
+                    Label::Attested,
+                )?;
+                ut.store_block(
+                    tip.header(),
+                    &to_spent_txs(tip.txs()),
+                    Label::Final,
+                )
+            })
+            .unwrap();
+
+            // Moving stops at the first block not final
+            assert_eq!(db.move_to_cold(5).unwrap(), 1);
+            assert_eq!(db.move_to_cold(5).unwrap(), 0);
+
+            db.view(|v| {
+                let hash = old.header().hash;
+                assert!(v
+                    .snapshot
+                    .get_cf(v.ledger_cf, hash)
+                    .unwrap()
+                    .is_none());
+
+                // Moved blocks are read through the cold storage
+                assert!(v.get_block_exists(&hash).unwrap());
+                let blk = v.fetch_block(&hash).unwrap().unwrap();
+                assert_eq!(blk.header().hash, hash);
+                assert_eq!(blk.txs().len(), old.txs().len());
+
+                let tx_hash = old.txs()[0].hash();
+                assert!(v.get_ledger_tx_exists(&tx_hash).unwrap());
+                assert!(v.get_ledger_tx_by_hash(&tx_hash).unwrap().is_some());
+
+                // Recent blocks stay in the database
+                let hash = not_final.header().hash;
+                assert!(v
+                    .snapshot
+                    .get_cf(v.ledger_cf, hash)
+                    .unwrap()
+                    .is_some());
+            });
+        });
+    }
+
+    #[test]
+    fn test_stats_and_compact() {
+        TestWrapper::new("test_stats_and_compact").run(|path| {
+            let db: Backend = Backend::create_or_open(path);
+            let b: ledger::Block = Faker.fake();
+
+            db.update(|ut| {
+                ut.store_block(b.header(), &to_spent_txs(b.txs()), Label::Final)
+            })
+            .expect("block to be stored");
+
+            db.compact();
+
+            let stats = db.stats().expect("stats to be read");
+            assert_eq!(stats.len(), COLUMN_FAMILIES.len());
+
+            let header_stats = stats
+                .iter()
+                .find(|s| s.name == CF_LEDGER_HEADER)
+                .expect("header stats to exist");
+            assert!(header_stats.sst_files_size > 0);
+            assert!(header_stats.estimated_keys > 0);
+        });
+    }
+
     struct TestWrapper(tempdir::TempDir);
 
     impl TestWrapper {
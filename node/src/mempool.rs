@@ -4,47 +4,149 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use crate::database::{Ledger, Mempool};
+pub mod admission;
+pub mod budget;
+pub mod preverify;
+
+use crate::database::{Ledger, Mempool, TxFootprint};
 use crate::{database, vm, LongLivedService, Message, Network};
 use async_trait::async_trait;
-use node_data::ledger::Transaction;
+use node_data::error::{kind_of, Classify, ErrorKind};
+use node_data::ledger::{
+    SizeLimitError, Transaction, DEFAULT_CHAIN_ID, SIZE_LIMITS,
+};
 use node_data::message::{AsyncQueue, Payload, Topics};
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 use tokio::sync::RwLock;
-use tracing::{error, warn};
+use tracing::{error, info, warn};
+
+use self::admission::{AcceptAll, AdmissionPolicy};
 
 const TOPICS: &[u8] = &[Topics::Tx as u8];
 
+/// Number of propagation records kept for later inspection
+const PROPAGATION_RECORDS: usize = 1000;
+
+/// Propagation records of the latest submissions since the node started
+static PROPAGATIONS: Mutex<Propagations> = Mutex::new(Propagations::new());
+
 #[derive(Debug, Error)]
-enum TxAcceptanceError {
+pub enum TxAcceptanceError {
     #[error("this transaction exists in the mempool")]
     AlreadyExistsInMempool,
     #[error("this transaction exists in the ledger")]
     AlreadyExistsInLedger,
     #[error("this transaction's input(s) exists in the mempool")]
     NullifierExistsInMempool,
+    #[error("this transaction is meant for chain {0}")]
+    InvalidChainId(u8),
+    #[error("this transaction is too large {0}")]
+    SizeLimit(SizeLimitError),
     #[error("this transaction is invalid {0}")]
     VerificationFailed(String),
+    #[error("this transaction is not admitted {0}")]
+    NotAdmitted(String),
+    #[error("the mempool is full of transactions paying more per byte")]
+    MempoolFull,
     #[error("A generic error occurred {0}")]
     Generic(anyhow::Error),
 }
 
+impl Classify for TxAcceptanceError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            // Spending transactions may be evicted or included in a block
+            Self::NullifierExistsInMempool | Self::MempoolFull => {
+                ErrorKind::Transient
+            }
+            Self::SizeLimit(e) => e.kind(),
+            Self::AlreadyExistsInMempool
+            | Self::AlreadyExistsInLedger
+            | Self::InvalidChainId(_)
+            | Self::VerificationFailed(_)
+            | Self::NotAdmitted(_) => ErrorKind::Permanent,
+            Self::Generic(e) => kind_of(e).unwrap_or(ErrorKind::Transient),
+        }
+    }
+}
+
 impl From<anyhow::Error> for TxAcceptanceError {
     fn from(err: anyhow::Error) -> Self {
         Self::Generic(err)
     }
 }
 
+/// Outcome of the submission of a transaction to the mempool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PropagationStatus {
+    /// The transaction has been added to the mempool and gossiped
+    Accepted { gossiped: bool },
+    /// The transaction is already known, either in the mempool or in the
+    /// ledger
+    Duplicate { reason: String },
+    /// The transaction has been rejected
+    Invalid { reason: String },
+}
+
+/// Record of the submission of a transaction, queryable by its correlation ID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PropagationRecord {
+    pub id: String,
+    pub tx_hash: String,
+    /// Submission time, as seconds since the UNIX epoch
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub status: PropagationStatus,
+}
+
+/// Bounded in-memory store of the latest propagation records.
+///
+/// Records are kept in memory rather than in the database, so that a
+/// submission never costs a database write.
+struct Propagations {
+    records: VecDeque<PropagationRecord>,
+}
+
+impl Propagations {
+    const fn new() -> Self {
+        Self {
+            records: VecDeque::new(),
+        }
+    }
+
+    fn insert(&mut self, record: PropagationRecord) {
+        if self.records.len() >= PROPAGATION_RECORDS {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    fn get(&self, id: &str) -> Option<&PropagationRecord> {
+        self.records.iter().rev().find(|r| r.id == id)
+    }
+}
+
 pub struct MempoolSrv {
     inbound: AsyncQueue<Message>,
+    /// Network accepted transactions must be meant for
+    chain_id: u8,
+    /// Local policy deciding which valid transactions are accepted
+    policy: Arc<dyn AdmissionPolicy>,
+    /// Budget in bytes of the mempool, unbounded if `None`
+    max_bytes: Option<usize>,
 }
 
 impl Default for MempoolSrv {
     fn default() -> Self {
-        Self {
-            inbound: AsyncQueue::unbounded(),
-        }
+        Self::new(DEFAULT_CHAIN_ID)
     }
 }
 
@@ -99,7 +201,9 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
             if let Ok(msg) = self.inbound.recv().await {
                 match &msg.payload {
                     Payload::Transaction(tx) => {
-                        let accept = self.accept_tx::<DB, VM>(&db, &vm, tx);
+                        let src = msg.metadata.as_ref().map(|m| m.src_addr);
+                        let accept =
+                            self.accept_tx::<DB, VM>(&db, &vm, tx, src);
                         if let Err(e) = accept.await {
                             error!("{}", e);
                             continue;
@@ -123,63 +227,337 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
 }
 
 impl MempoolSrv {
+    pub fn new(chain_id: u8) -> Self {
+        Self {
+            inbound: AsyncQueue::unbounded(),
+            chain_id,
+            policy: Arc::new(AcceptAll),
+            max_bytes: None,
+        }
+    }
+
+    /// Sets the local policy deciding which valid transactions are accepted.
+    pub fn with_admission_policy(
+        mut self,
+        policy: Arc<dyn AdmissionPolicy>,
+    ) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the budget in bytes of the mempool, beyond which the transactions
+    /// paying the lowest fee per byte are evicted.
+    pub fn with_max_bytes(mut self, max_bytes: Option<usize>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
     async fn accept_tx<DB: database::DB, VM: vm::VMExecution>(
         &mut self,
         db: &Arc<RwLock<DB>>,
         vm: &Arc<RwLock<VM>>,
         tx: &Transaction,
+        src: Option<SocketAddr>,
     ) -> Result<(), TxAcceptanceError> {
-        // VM Preverify call
-        if let Err(e) = vm.read().await.preverify(tx) {
-            Err(TxAcceptanceError::VerificationFailed(format!("{e:?}")))?;
+        let policy = self.policy.as_ref();
+        accept_tx(self.chain_id, policy, self.max_bytes, src, db, vm, tx).await
+    }
+}
+
+/// Checks a transaction against the chain tip, the mempool state and the
+/// admission policy, and adds it to the mempool.
+///
+/// * `max_bytes` - the budget in bytes of the mempool, the transactions paying
+/// less per byte than `tx` are evicted to stay within it
+/// * `src` - the peer the transaction was received from, `None` if it was
+/// submitted locally
+pub async fn accept_tx<DB: database::DB, VM: vm::VMExecution>(
+    chain_id: u8,
+    policy: &dyn AdmissionPolicy,
+    max_bytes: Option<usize>,
+    src: Option<SocketAddr>,
+    db: &Arc<RwLock<DB>>,
+    vm: &Arc<RwLock<VM>>,
+    tx: &Transaction,
+) -> Result<(), TxAcceptanceError> {
+    check_chain_id(chain_id, tx)?;
+    check_size(tx)?;
+    check_admission(policy, tx, src)?;
+    check_proof(vm, tx).await?;
+
+    let hash = tx.hash();
+
+    // Perform basic checks on the transaction
+    db.read().await.view(|view| {
+        // Replace the mempool transactions paying less for the same
+        // nullifiers
+        for m_tx_hash in check_mempool(&view, tx)? {
+            view.delete_tx(m_tx_hash)?;
         }
 
-        let hash = tx.hash();
+        check_ledger(&view, tx)
+    })?;
 
-        // Perform basic checks on the transaction
-        db.read().await.view(|view| {
-            // ensure transaction does not exist in the mempool
+    tracing::info!(event = "transaction accepted", hash = hex::encode(hash));
 
-            if view.get_tx_exists(hash)? {
-                return Err(TxAcceptanceError::AlreadyExistsInMempool);
-            }
+    // Add transaction to the mempool, evicting the ones paying the least per
+    // byte if over budget
+    let added = db.read().await.update(|db| {
+        if let Some(max_bytes) = max_bytes {
+            let incoming = TxFootprint::from(tx);
+            let footprints = db.get_txs_footprints_by_density()?;
+            let bytes = db.get_txs_usage().bytes;
+            let Some(evicted) = budget::select_evictions(
+                footprints, bytes, &incoming, max_bytes,
+            ) else {
+                return Ok(false);
+            };
 
-            let nullifiers: Vec<_> = tx
-                .inner
-                .nullifiers()
-                .iter()
-                .map(|nullifier| nullifier.to_bytes())
-                .collect();
-
-            // ensure nullifiers do not exist in the mempool
-            for m_tx_hash in view.get_txs_by_nullifiers(&nullifiers) {
-                if let Some(m_tx) = view.get_tx(m_tx_hash)? {
-                    if m_tx.inner.fee().gas_price < tx.inner.fee().gas_price {
-                        view.delete_tx(m_tx_hash)?;
-                    } else {
-                        return Err(
-                            TxAcceptanceError::NullifierExistsInMempool,
-                        );
-                    }
-                }
+            for footprint in evicted {
+                db.delete_tx(footprint.hash)?;
+                budget::record_eviction(&footprint);
+                info!(
+                    event = "transaction evicted",
+                    hash = hex::encode(footprint.hash),
+                    size = footprint.size,
+                    replaced_by = hex::encode(hash),
+                );
             }
+        }
+
+        db.add_tx(tx)?;
+        Ok(true)
+    })?;
+
+    if !added {
+        Err(TxAcceptanceError::MempoolFull)?;
+    }
 
-            // ensure transaction does not exist in the blockchain
-            if view.get_ledger_tx_exists(&hash)? {
-                return Err(TxAcceptanceError::AlreadyExistsInLedger);
+    Ok(())
+}
+
+fn check_chain_id(
+    chain_id: u8,
+    tx: &Transaction,
+) -> Result<(), TxAcceptanceError> {
+    if tx.chain_id != chain_id {
+        Err(TxAcceptanceError::InvalidChainId(tx.chain_id))?;
+    }
+    Ok(())
+}
+
+fn check_size(tx: &Transaction) -> Result<(), TxAcceptanceError> {
+    SIZE_LIMITS
+        .check_tx_size(tx.size())
+        .map_err(TxAcceptanceError::SizeLimit)
+}
+
+fn check_admission(
+    policy: &dyn AdmissionPolicy,
+    tx: &Transaction,
+    src: Option<SocketAddr>,
+) -> Result<(), TxAcceptanceError> {
+    policy
+        .admit(tx, src)
+        .map_err(TxAcceptanceError::NotAdmitted)
+}
+
+/// Verifies the proof of `tx` and that its nullifiers are not spent.
+async fn check_proof<VM: vm::VMExecution>(
+    vm: &Arc<RwLock<VM>>,
+    tx: &Transaction,
+) -> Result<(), TxAcceptanceError> {
+    // VM Preverify call
+    if let Err(e) = vm.read().await.preverify(tx) {
+        match kind_of(&e) {
+            // The transaction could not be verified, not found invalid
+            Some(ErrorKind::Transient) => Err(TxAcceptanceError::Generic(e))?,
+            _ => Err(TxAcceptanceError::VerificationFailed(format!("{e:?}")))?,
+        }
+    }
+    Ok(())
+}
+
+/// Checks `tx` against the mempool, returning the transactions it replaces,
+/// if any.
+///
+/// A transaction replaces the ones spending the same nullifiers if it pays a
+/// higher gas price than all of them.
+fn check_mempool<M: Mempool>(
+    view: &M,
+    tx: &Transaction,
+) -> Result<Vec<[u8; 32]>, TxAcceptanceError> {
+    // ensure transaction does not exist in the mempool
+    if view.get_tx_exists(tx.hash())? {
+        return Err(TxAcceptanceError::AlreadyExistsInMempool);
+    }
+
+    let nullifiers: Vec<_> = tx
+        .inner
+        .nullifiers()
+        .iter()
+        .map(|nullifier| nullifier.to_bytes())
+        .collect();
+
+    // ensure nullifiers do not exist in the mempool
+    let mut replaced = vec![];
+    for m_tx_hash in view.get_txs_by_nullifiers(&nullifiers) {
+        if let Some(m_tx) = view.get_tx(m_tx_hash)? {
+            if m_tx.inner.fee().gas_price < tx.inner.fee().gas_price {
+                replaced.push(m_tx_hash);
+            } else {
+                return Err(TxAcceptanceError::NullifierExistsInMempool);
             }
+        }
+    }
+
+    Ok(replaced)
+}
 
-            Ok(())
-        })?;
+fn check_ledger<L: Ledger>(
+    view: &L,
+    tx: &Transaction,
+) -> Result<(), TxAcceptanceError> {
+    // ensure transaction does not exist in the blockchain
+    if view.get_ledger_tx_exists(&tx.hash())? {
+        return Err(TxAcceptanceError::AlreadyExistsInLedger);
+    }
+    Ok(())
+}
 
-        tracing::info!(
-            event = "transaction accepted",
-            hash = hex::encode(hash)
-        );
+/// Submits a transaction to the mempool and gossips it to the network,
+/// recording the outcome under a new correlation ID.
+pub async fn propagate_tx<N: Network, DB: database::DB, VM: vm::VMExecution>(
+    chain_id: u8,
+    policy: &dyn AdmissionPolicy,
+    max_bytes: Option<usize>,
+    network: &Arc<RwLock<N>>,
+    db: &Arc<RwLock<DB>>,
+    vm: &Arc<RwLock<VM>>,
+    tx: Transaction,
+) -> anyhow::Result<PropagationRecord> {
+    let hash = tx.hash();
 
-        // Add transaction to the mempool
-        db.read().await.update(|db| db.add_tx(tx))?;
+    let accept = accept_tx(chain_id, policy, max_bytes, None, db, vm, &tx);
+    let status = match accept.await {
+        Ok(_) => {
+            let msg = Message::new_transaction(tx);
+            let gossiped = match network.read().await.broadcast(&msg).await {
+                Ok(_) => true,
+                Err(e) => {
+                    warn!("Unable to broadcast accepted tx: {e}");
+                    false
+                }
+            };
+            PropagationStatus::Accepted { gossiped }
+        }
+        Err(
+            e @ (TxAcceptanceError::AlreadyExistsInMempool
+            | TxAcceptanceError::AlreadyExistsInLedger),
+        ) => PropagationStatus::Duplicate {
+            reason: e.to_string(),
+        },
+        Err(TxAcceptanceError::Generic(e)) => return Err(e),
+        Err(e) => PropagationStatus::Invalid {
+            reason: e.to_string(),
+        },
+    };
 
-        Ok(())
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let record = PropagationRecord {
+        id: correlation_id(&hash),
+        tx_hash: hex::encode(hash),
+        timestamp,
+        status,
+    };
+
+    PROPAGATIONS
+        .lock()
+        .expect("propagations lock to be acquired")
+        .insert(record.clone());
+
+    Ok(record)
+}
+
+/// Returns the record of the submission with the given correlation ID, if
+/// it is still kept.
+pub fn propagation_record(id: &str) -> Option<PropagationRecord> {
+    PROPAGATIONS
+        .lock()
+        .expect("propagations lock to be acquired")
+        .get(id)
+        .cloned()
+}
+
+/// Returns a unique ID correlating a submission to its outcome.
+fn correlation_id(tx_hash: &[u8; 32]) -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+
+    let mut hasher = Sha3_256::new();
+    hasher.update(tx_hash);
+    hasher.update(nanos.to_le_bytes());
+    hasher.update(COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+
+    hex::encode(&hasher.finalize()[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(tx_hash: [u8; 32]) -> PropagationRecord {
+        PropagationRecord {
+            id: correlation_id(&tx_hash),
+            tx_hash: hex::encode(tx_hash),
+            timestamp: 0,
+            status: PropagationStatus::Accepted { gossiped: true },
+        }
+    }
+
+    #[test]
+    fn propagation_records_are_bounded() {
+        let mut propagations = Propagations::new();
+
+        let first = record([0; 32]);
+        propagations.insert(first.clone());
+        assert_eq!(propagations.get(&first.id), Some(&first));
+
+        // Submitting the same transaction again yields a new record
+        let again = record([0; 32]);
+        assert_ne!(again.id, first.id);
+        propagations.insert(again.clone());
+        assert_eq!(propagations.get(&again.id), Some(&again));
+
+        for i in 0..PROPAGATION_RECORDS {
+            propagations.insert(record([i as u8; 32]));
+        }
+        assert_eq!(propagations.records.len(), PROPAGATION_RECORDS);
+        assert_eq!(propagations.get(&first.id), None);
+        assert_eq!(propagations.get(&again.id), None);
+    }
+
+    #[test]
+    fn classify_rejections() {
+        use node_data::error::Classified;
+        use TxAcceptanceError::*;
+
+        assert_eq!(NullifierExistsInMempool.kind(), ErrorKind::Transient);
+        assert_eq!(MempoolFull.kind(), ErrorKind::Transient);
+        assert_eq!(AlreadyExistsInLedger.kind(), ErrorKind::Permanent);
+        assert_eq!(InvalidChainId(1).kind(), ErrorKind::Permanent);
+
+        let err = Generic(anyhow::anyhow!("cannot open the database"));
+        assert_eq!(err.kind(), ErrorKind::Transient);
+        let err = Classified::new(ErrorKind::Permanent, anyhow::anyhow!(""));
+        assert_eq!(Generic(err.into()).kind(), ErrorKind::Permanent);
     }
 }
@@ -0,0 +1,190 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Local policies deciding which transactions enter the mempool.
+//!
+//! The policies are checked on top of the validity checks, so that operators
+//! of specialized nodes can keep out of their mempool transactions that are
+//! valid but unwanted, without patching the crate.
+
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use node_data::ledger::{to_str, Transaction};
+
+/// Decides whether a valid transaction is admitted to the mempool.
+pub trait AdmissionPolicy: Send + Sync {
+    /// Returns the reason why `tx` is not admitted, if so.
+    ///
+    /// * `src` - the peer the transaction was received from, `None` if it
+    /// was submitted locally
+    fn admit(
+        &self,
+        tx: &Transaction,
+        src: Option<SocketAddr>,
+    ) -> Result<(), String>;
+}
+
+/// Admits every transaction.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AcceptAll;
+
+impl AdmissionPolicy for AcceptAll {
+    fn admit(
+        &self,
+        _: &Transaction,
+        _: Option<SocketAddr>,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Admits the transactions admitted by all the policies.
+#[derive(Default)]
+pub struct AllOf(pub Vec<Box<dyn AdmissionPolicy>>);
+
+impl AdmissionPolicy for AllOf {
+    fn admit(
+        &self,
+        tx: &Transaction,
+        src: Option<SocketAddr>,
+    ) -> Result<(), String> {
+        self.0.iter().try_for_each(|policy| policy.admit(tx, src))
+    }
+}
+
+/// Admits the transactions paying at least the given gas price.
+#[derive(Debug, Clone, Copy)]
+pub struct MinGasPrice(pub u64);
+
+impl AdmissionPolicy for MinGasPrice {
+    fn admit(
+        &self,
+        tx: &Transaction,
+        _: Option<SocketAddr>,
+    ) -> Result<(), String> {
+        let gas_price = tx.gas_price();
+        if gas_price < self.0 {
+            return Err(format!(
+                "gas price {gas_price} below the minimum {}",
+                self.0
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Admits the transactions calling one of the given contracts, along with the
+/// ones not calling any contract.
+#[derive(Debug, Clone, Default)]
+pub struct ContractAllowlist(pub HashSet<[u8; 32]>);
+
+impl AdmissionPolicy for ContractAllowlist {
+    fn admit(
+        &self,
+        tx: &Transaction,
+        _: Option<SocketAddr>,
+    ) -> Result<(), String> {
+        match &tx.inner.call {
+            Some((contract, _, _)) if !self.0.contains(contract) => {
+                Err(format!("contract {} not allowed", to_str(contract)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Bounds the bytes of the transactions received from each peer within a
+/// time window.
+///
+/// Transactions do not reveal their sender, so they are accounted to the
+/// host they are received from. Transactions submitted locally are not
+/// bounded.
+pub struct MaxBytesPerSender {
+    max_bytes: usize,
+    window: Duration,
+    /// Bytes received from each host since the start of its window
+    received: Mutex<HashMap<IpAddr, (Instant, usize)>>,
+}
+
+impl MaxBytesPerSender {
+    pub fn new(max_bytes: usize, window: Duration) -> Self {
+        Self {
+            max_bytes,
+            window,
+            received: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl AdmissionPolicy for MaxBytesPerSender {
+    fn admit(
+        &self,
+        tx: &Transaction,
+        src: Option<SocketAddr>,
+    ) -> Result<(), String> {
+        let Some(src) = src else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let mut received = self.received.lock().expect("lock to be acquired");
+        received
+            .retain(|_, (start, _)| now.duration_since(*start) < self.window);
+
+        let (_, bytes) = received.entry(src.ip()).or_insert((now, 0));
+        let size = tx.size();
+        if *bytes + size > self.max_bytes {
+            return Err(format!(
+                "more than {} bytes received from {} within {:?}",
+                self.max_bytes,
+                src.ip(),
+                self.window
+            ));
+        }
+        *bytes += size;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use node_data::ledger::faker::gen_dummy_tx;
+
+    #[test]
+    fn test_admission_policies() {
+        let tx = gen_dummy_tx(10);
+        let peer: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+
+        assert!(MinGasPrice(10).admit(&tx, None).is_ok());
+        assert!(MinGasPrice(11).admit(&tx, None).is_err());
+
+        let mut call_tx = tx.clone();
+        call_tx.inner.call = Some(([5; 32], "transfer".into(), vec![]));
+        let allowlist = ContractAllowlist(HashSet::from([[5; 32]]));
+        assert!(allowlist.admit(&call_tx, None).is_ok());
+        assert!(ContractAllowlist::default().admit(&call_tx, None).is_err());
+
+        let size = tx.size();
+        let max_bytes =
+            MaxBytesPerSender::new(2 * size, Duration::from_secs(60));
+        assert!(max_bytes.admit(&tx, Some(peer)).is_ok());
+        assert!(max_bytes.admit(&tx, Some(peer)).is_ok());
+        assert!(max_bytes.admit(&tx, Some(peer)).is_err());
+        assert!(max_bytes.admit(&tx, None).is_ok());
+        let other: SocketAddr = "10.0.0.2:9000".parse().unwrap();
+        assert!(max_bytes.admit(&tx, Some(other)).is_ok());
+
+        let all = AllOf(vec![Box::new(AcceptAll), Box::new(MinGasPrice(11))]);
+        assert!(all.admit(&tx, None).is_err());
+        assert!(AllOf::default().admit(&tx, None).is_ok());
+    }
+}
@@ -0,0 +1,142 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Accounting of the bytes taken by the mempool transactions.
+//!
+//! When a memory budget is set, the transactions paying the lowest fee per
+//! byte are evicted to make room for the ones paying more, so that the
+//! mempool of a public node cannot grow unbounded.
+
+use std::cmp::Ordering as CmpOrdering;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::{Mempool, TxFootprint};
+
+/// Transactions evicted since the node started
+static EVICTED_TXS: AtomicU64 = AtomicU64::new(0);
+/// Bytes of the transactions evicted since the node started
+static EVICTED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Current occupancy of the mempool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Occupancy {
+    pub txs: usize,
+    /// Size in bytes of the serialized transactions
+    pub bytes: usize,
+    /// Budget in bytes of the mempool, `None` if unbounded
+    pub max_bytes: Option<usize>,
+    /// Transactions evicted since the node started
+    pub evicted_txs: u64,
+    /// Bytes of the transactions evicted since the node started
+    pub evicted_bytes: u64,
+}
+
+/// Returns the current occupancy of the mempool.
+pub fn occupancy<M: Mempool>(
+    mempool: &M,
+    max_bytes: Option<usize>,
+) -> anyhow::Result<Occupancy> {
+    let usage = mempool.get_txs_usage();
+
+    Ok(Occupancy {
+        txs: usage.txs,
+        bytes: usage.bytes,
+        max_bytes,
+        evicted_txs: EVICTED_TXS.load(Ordering::Relaxed),
+        evicted_bytes: EVICTED_BYTES.load(Ordering::Relaxed),
+    })
+}
+
+/// Compares the fee paid per byte by two transactions.
+fn cmp_fee_density(a: &TxFootprint, b: &TxFootprint) -> CmpOrdering {
+    let a_density = a.max_fee as u128 * b.size as u128;
+    let b_density = b.max_fee as u128 * a.size as u128;
+    a_density.cmp(&b_density)
+}
+
+/// Returns the transactions to evict for `incoming` to fit in `max_bytes`,
+/// given the mempool `footprints`, lowest fee density first, taking `bytes`
+/// in total.
+///
+/// Returns `None` if `incoming` does not fit without evicting transactions
+/// paying at least its fee density.
+pub(crate) fn select_evictions(
+    footprints: impl Iterator<Item = TxFootprint>,
+    mut bytes: usize,
+    incoming: &TxFootprint,
+    max_bytes: usize,
+) -> Option<Vec<TxFootprint>> {
+    if incoming.size > max_bytes {
+        return None;
+    }
+
+    let mut evicted = vec![];
+    for footprint in footprints {
+        if bytes + incoming.size <= max_bytes {
+            break;
+        }
+        if cmp_fee_density(&footprint, incoming) != CmpOrdering::Less {
+            return None;
+        }
+        bytes = bytes.saturating_sub(footprint.size);
+        evicted.push(footprint);
+    }
+
+    (bytes + incoming.size <= max_bytes).then_some(evicted)
+}
+
+/// Records the eviction of `footprint`.
+pub(crate) fn record_eviction(footprint: &TxFootprint) {
+    EVICTED_TXS.fetch_add(1, Ordering::Relaxed);
+    EVICTED_BYTES.fetch_add(footprint.size as u64, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn footprint(id: u8, size: usize, max_fee: u64) -> TxFootprint {
+        TxFootprint {
+            hash: [id; 32],
+            size,
+            max_fee,
+        }
+    }
+
+    #[test]
+    fn test_select_evictions() {
+        // Lowest fee density first
+        let footprints = vec![
+            footprint(2, 200, 1000),
+            footprint(1, 100, 1000),
+            footprint(3, 100, 5000),
+        ];
+        let select = |incoming: &TxFootprint| {
+            select_evictions(footprints.iter().copied(), 400, incoming, 500)
+        };
+
+        // Room left, nothing to evict
+        let incoming = footprint(4, 100, 100);
+        assert_eq!(select(&incoming), Some(vec![]));
+
+        // The lowest density is evicted first
+        let incoming = footprint(4, 150, 3000);
+        assert_eq!(select(&incoming), Some(vec![footprints[0]]));
+
+        let incoming = footprint(4, 350, 7000);
+        assert_eq!(select(&incoming), Some(footprints[..2].to_vec()));
+
+        // Transactions paying more per byte are kept
+        let incoming = footprint(4, 150, 100);
+        assert_eq!(select(&incoming), None);
+
+        // The transaction exceeds the budget alone
+        let incoming = footprint(4, 600, u64::MAX);
+        assert_eq!(select(&incoming), None);
+    }
+}
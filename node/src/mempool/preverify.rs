@@ -0,0 +1,150 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Dry run of the mempool admission of a transaction.
+//!
+//! External gateways can filter out the transactions the mempool would
+//! reject before propagating them to the network. The checks are the ones of
+//! [`super::accept_tx`], run without executing the transaction nor altering
+//! the mempool.
+
+use std::sync::Arc;
+
+use node_data::ledger::Transaction;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use super::admission::AdmissionPolicy;
+use super::{
+    check_admission, check_chain_id, check_ledger, check_mempool, check_proof,
+    check_size, TxAcceptanceError,
+};
+use crate::{database, vm};
+
+/// Static check run on a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// The transaction is meant for the network of the node
+    ChainId,
+    /// The transaction fits within the size limits
+    Size,
+    /// The local admission policy accepts the transaction
+    Admission,
+    /// The proof is valid and the nullifiers are not spent
+    Proof,
+    /// Neither the transaction nor its nullifiers are in the mempool, unless
+    /// spent by transactions paying less
+    Mempool,
+    /// The transaction is not in the ledger
+    Ledger,
+}
+
+/// Outcome of a single check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub check: Check,
+    pub passed: bool,
+    /// Why the check failed, if so
+    pub reason: Option<String>,
+}
+
+/// Outcome of the checks run on a transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preverification {
+    /// Hex encoded hash of the transaction
+    pub tx_hash: String,
+    /// Whether all the checks passed
+    pub valid: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+impl Preverification {
+    fn new(tx: &Transaction, checks: Vec<CheckResult>) -> Self {
+        Self {
+            tx_hash: hex::encode(tx.hash()),
+            valid: checks.iter().all(|c| c.passed),
+            checks,
+        }
+    }
+}
+
+impl CheckResult {
+    fn new<T>(check: Check, res: Result<T, TxAcceptanceError>) -> Self {
+        let reason = res.err().map(|e| e.to_string());
+        Self {
+            check,
+            passed: reason.is_none(),
+            reason,
+        }
+    }
+}
+
+/// Runs all the static checks of the mempool admission on `tx`, without
+/// stopping at the first failure.
+///
+/// Fails only if a check cannot be run, such as the proof failing to be
+/// verified for a reason unrelated to the transaction.
+pub async fn preverify_tx<DB: database::DB, VM: vm::VMExecution>(
+    chain_id: u8,
+    policy: &dyn AdmissionPolicy,
+    db: &Arc<RwLock<DB>>,
+    vm: &Arc<RwLock<VM>>,
+    tx: &Transaction,
+) -> anyhow::Result<Preverification> {
+    let mut checks = vec![];
+    record(&mut checks, Check::ChainId, check_chain_id(chain_id, tx))?;
+    record(&mut checks, Check::Size, check_size(tx))?;
+    let admission = check_admission(policy, tx, None);
+    record(&mut checks, Check::Admission, admission)?;
+    record(&mut checks, Check::Proof, check_proof(vm, tx).await)?;
+
+    let (mempool, ledger) = db
+        .read()
+        .await
+        .view(|view| (check_mempool(&view, tx), check_ledger(&view, tx)));
+    record(&mut checks, Check::Mempool, mempool)?;
+    record(&mut checks, Check::Ledger, ledger)?;
+
+    Ok(Preverification::new(tx, checks))
+}
+
+/// Records the outcome of `check`, failing if it could not be run.
+fn record<T>(
+    checks: &mut Vec<CheckResult>,
+    check: Check,
+    res: Result<T, TxAcceptanceError>,
+) -> anyhow::Result<()> {
+    match res {
+        Err(TxAcceptanceError::Generic(e)) => Err(e),
+        res => {
+            checks.push(CheckResult::new(check, res));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use node_data::ledger::faker::gen_dummy_tx;
+
+    #[test]
+    fn test_preverification_validity() {
+        let tx = gen_dummy_tx(1);
+        let passed = CheckResult::new(Check::ChainId, Ok(()));
+        let failed = CheckResult::new::<()>(
+            Check::Mempool,
+            Err(TxAcceptanceError::NullifierExistsInMempool),
+        );
+        assert!(passed.passed && passed.reason.is_none());
+        assert!(!failed.passed);
+
+        assert!(Preverification::new(&tx, vec![passed.clone()]).valid);
+        assert!(!Preverification::new(&tx, vec![passed, failed]).valid);
+    }
+}
@@ -4,22 +4,36 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::collections::BTreeMap;
 use std::net::{AddrParseError, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::{BoxedFilter, Message};
 use async_trait::async_trait;
 use kadcast::config::Config;
 use kadcast::{MessageInfo, Peer};
+use node_data::message::payload::Inv;
 use node_data::message::Metadata;
 use node_data::message::{AsyncQueue, Topics};
 use std::sync::atomic::{AtomicU64, Ordering};
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
 use tokio::time::{self, Instant};
 use tracing::{error, info, trace, warn};
 
+mod buffer_pool;
 mod frame;
+pub mod nat;
+pub mod noise;
+pub mod peer_store;
+mod versions;
+
+use buffer_pool::BufferPool;
+pub use frame::PROTOCOL_VERSION;
+use noise::Noise;
+use peer_store::PeerStore;
+use versions::PeerVersions;
 
 const MAX_PENDING_SENDERS: u64 = 1000;
 
@@ -32,6 +46,14 @@ pub struct Listener<const N: usize> {
 
     /// Number of awaiting senders.
     pending_senders: Arc<AtomicU64>,
+
+    /// Noise sessions, if encrypted transport is enabled
+    noise: Option<Arc<Noise>>,
+
+    /// Queue of handshake and hello frames to be sent back to peers
+    outbox: mpsc::UnboundedSender<(Vec<u8>, SocketAddr)>,
+
+    versions: Arc<std::sync::Mutex<PeerVersions>>,
 }
 
 impl<const N: usize> Listener<N> {
@@ -74,18 +96,87 @@ impl<const N: usize> Listener<N> {
             _ => Ok(()),
         }
     }
-}
 
-impl<const N: usize> kadcast::NetworkListen for Listener<N> {
-    fn on_message(&self, blob: Vec<u8>, md: MessageInfo) {
-        match frame::Pdu::decode(&mut &blob.to_vec()[..]) {
+    fn on_noise_frame(&self, noise: &Noise, blob: &[u8], md: MessageInfo) {
+        match noise.open(blob, md.src()) {
+            Ok(opened) => {
+                for reply in opened.replies {
+                    if self.outbox.send((reply, md.src())).is_err() {
+                        error!("unable to reply to {}", md.src());
+                    }
+                }
+
+                if let Some(pdu) = opened.payload {
+                    self.on_pdu(&pdu, md, |_| true);
+                }
+            }
+            Err(e) => warn!("discard noise frame from {} due to {e}", md.src()),
+        }
+    }
+
+    fn observe_version(&self, src: SocketAddr, version: u32) {
+        let greet = self
+            .versions
+            .lock()
+            .expect("lock to be acquired")
+            .observe(src, version);
+        if greet {
+            self.greet(src);
+        }
+    }
+
+    fn on_hello(&self, blob: &[u8], src: SocketAddr) {
+        let Some(hello) = frame::Hello::decode(blob) else {
+            warn!("discard invalid hello frame from {src}");
+            return;
+        };
+
+        let greet = self
+            .versions
+            .lock()
+            .expect("lock to be acquired")
+            .on_hello(src, hello);
+        if greet {
+            self.greet(src);
+        }
+    }
+
+    /// Sends our hello frame to a peer, announcing the protocol versions we
+    /// can decode.
+    fn greet(&self, src: SocketAddr) {
+        let hello = frame::Hello::ours().encode();
+        if self.outbox.send((hello, src)).is_err() {
+            error!("unable to greet {src}");
+        }
+    }
+
+    /// Decodes a PDU and reroutes it to the upper layer, provided its topic
+    /// is allowed.
+    fn on_pdu(
+        &self,
+        blob: &[u8],
+        md: MessageInfo,
+        allowed: impl Fn(Topics) -> bool,
+    ) {
+        match frame::Pdu::decode(&mut &blob[..]) {
             Ok(d) => {
+                self.observe_version(md.src(), d.header.protocol_version());
                 let mut msg = d.payload;
 
+                if !allowed(msg.topic()) {
+                    warn!(
+                        "discard plaintext {:?} message from {}",
+                        msg.topic(),
+                        md.src()
+                    );
+                    return;
+                }
+
                 // Update Transport Data
                 msg.metadata = Some(Metadata {
                     height: md.height(),
                     src_addr: md.src(),
+                    sig_verified: false,
                 });
 
                 // Allow upper layers to fast-discard a message before queueing
@@ -99,6 +190,13 @@ impl<const N: usize> kadcast::NetworkListen for Listener<N> {
                     error!("could not reroute due to {e}");
                 }
             }
+            Err(frame::DecodeError::IncompatibleVersion(version)) => {
+                self.observe_version(md.src(), version);
+                warn!(
+                    "discard message from {} due to incompatible protocol version {version}",
+                    md.src()
+                );
+            }
             Err(err) => {
                 // Dump message blob and topic number
                 let topic = blob.get(node_data::message::TOPIC_FIELD_POS);
@@ -108,43 +206,170 @@ impl<const N: usize> kadcast::NetworkListen for Listener<N> {
     }
 }
 
+impl<const N: usize> kadcast::NetworkListen for Listener<N> {
+    fn on_message(&self, blob: Vec<u8>, md: MessageInfo) {
+        if frame::is_hello_frame(&blob) {
+            self.on_hello(&blob, md.src());
+            return;
+        }
+
+        match &self.noise {
+            Some(noise) if noise::is_noise_frame(&blob) => {
+                self.on_noise_frame(noise, &blob, md)
+            }
+            // Only broadcast messages travel in clear once the transport is
+            // encrypted
+            Some(_) => self.on_pdu(&blob, md, noise::is_broadcast_topic),
+            None => self.on_pdu(&blob, md, |_| true),
+        }
+    }
+}
+
 pub struct Kadcast<const N: usize> {
-    peer: Peer,
+    /// One peer per listening address, the first being the primary one
+    peers: Arc<Vec<Arc<Peer>>>,
+    /// Public address of each peer, if it is an IP address
+    public_addresses: Arc<Vec<Option<SocketAddr>>>,
     routes: Arc<RwLock<RoutesList<N>>>,
     filters: Arc<RwLock<FilterList<N>>>,
     conf: Config,
+    additional_confs: Vec<Config>,
+    noise: Option<Arc<Noise>>,
+    versions: Arc<std::sync::Mutex<PeerVersions>>,
+    /// Buffers the outgoing frames are encoded into
+    buffers: BufferPool,
 
     counter: AtomicU64,
 }
 
 impl<const N: usize> Kadcast<N> {
-    pub fn new(conf: Config) -> Result<Self, AddrParseError> {
+    /// Creates the network layer.
+    ///
+    /// If an `identity` is provided, messages sent to specific peers are
+    /// encrypted over Noise sessions authenticated by the node keys.
+    pub fn new(
+        conf: Config,
+        identity: Option<noise::Identity>,
+    ) -> Result<Self, AddrParseError> {
+        Self::new_multi_address(conf, vec![], identity)
+    }
+
+    /// Creates the network layer, listening on the addresses of `conf` and
+    /// of each of the `additional` configurations.
+    ///
+    /// This allows dual-stack hosts to join the network both over IPv4 and
+    /// IPv6. Each address joins the peers of its own address family, thus
+    /// the bootstrapping nodes are split by family, and messages to a
+    /// specific peer are sent from the address matching its family. Messages
+    /// of the node are broadcast to every family, while received ones are
+    /// only propagated to the family they were received from.
+    pub fn new_multi_address(
+        conf: Config,
+        additional: Vec<Config>,
+        identity: Option<noise::Identity>,
+    ) -> Result<Self, AddrParseError> {
         const INIT: Option<AsyncQueue<Message>> = None;
         let routes = Arc::new(RwLock::new([INIT; N]));
 
         const INIT_FN: Option<BoxedFilter> = None;
         let filters = Arc::new(RwLock::new([INIT_FN; N]));
 
-        info!(
-            "Loading network with public_address {} and private_address {:?}",
-            &conf.public_address, &conf.listen_address
-        );
-        let listener = Listener {
-            routes: routes.clone(),
-            filters: filters.clone(),
-            pending_senders: Arc::new(AtomicU64::new(0)),
-        };
-        let peer = Peer::new(conf.clone(), listener)?;
+        let noise = identity.map(|identity| {
+            info!("Enabling noise encrypted transport");
+            Arc::new(Noise::new(identity))
+        });
+
+        let (outbox, mut outbox_rx) = mpsc::unbounded_channel();
+        let pending_senders = Arc::new(AtomicU64::new(0));
+        let versions = Arc::new(std::sync::Mutex::new(PeerVersions::default()));
+
+        let multi_address = !additional.is_empty();
+        let mut peers = vec![];
+        let mut public_addresses = vec![];
+        for conf in std::iter::once(&conf).chain(&additional) {
+            info!(
+                "Loading network with public_address {} and private_address {:?}",
+                &conf.public_address, &conf.listen_address
+            );
+
+            let public_address = conf.public_address.parse().ok();
+            let mut conf = conf.clone();
+            if multi_address {
+                conf.bootstrapping_nodes
+                    .retain(|node| same_family(public_address, node));
+            }
+
+            let listener = Listener {
+                routes: routes.clone(),
+                filters: filters.clone(),
+                pending_senders: pending_senders.clone(),
+                noise: noise.clone(),
+                outbox: outbox.clone(),
+                versions: versions.clone(),
+            };
+            peers.push(Arc::new(Peer::new(conf, listener)?));
+            public_addresses.push(public_address);
+        }
+        let peers = Arc::new(peers);
+        let public_addresses = Arc::new(public_addresses);
+
+        // Handshake and hello sender task
+        let senders = peers.clone();
+        let addresses = public_addresses.clone();
+        tokio::spawn(async move {
+            while let Some((blob, recv_addr)) = outbox_rx.recv().await {
+                let idx = peer_index(&addresses, recv_addr);
+                senders[idx].send(&blob, recv_addr).await;
+            }
+        });
 
         Ok(Kadcast {
             routes,
             filters,
-            peer,
+            peers,
+            public_addresses,
             conf,
+            additional_confs: additional,
+            noise,
+            versions,
+            buffers: BufferPool::default(),
             counter: AtomicU64::new(0),
         })
     }
 
+    /// Returns the peer listening on the address family of `addr`.
+    fn peer_for(&self, addr: SocketAddr) -> &Peer {
+        &self.peers[peer_index(&self.public_addresses, addr)]
+    }
+
+    /// Sends an encoded message to a given peer, encrypting it if the noise
+    /// transport is enabled.
+    async fn send_encoded(&self, encoded: &[u8], recv_addr: SocketAddr) {
+        let Some(noise) = &self.noise else {
+            self.peer_for(recv_addr).send(encoded, recv_addr).await;
+            return;
+        };
+
+        // Empty if queued until the handshake completes
+        let frames = match noise.seal(encoded, recv_addr) {
+            Ok(frames) => frames,
+            Err(e) => {
+                error!("could not encrypt message to {recv_addr}: {e}");
+                return;
+            }
+        };
+        for frame in frames {
+            self.peer_for(recv_addr).send(&frame, recv_addr).await;
+        }
+    }
+
+    /// Encodes a frame into a pooled buffer, to be put back once sent.
+    fn encode(&self, msg: &Message, reserved: u64) -> std::io::Result<Vec<u8>> {
+        let mut buf = self.buffers.take();
+        frame::Pdu::encode_into(msg, reserved, &mut buf)?;
+        Ok(buf)
+    }
+
     pub fn route_internal(&self, msg: Message) {
         let topic = msg.topic() as usize;
         let routes = self.routes.clone();
@@ -167,13 +392,134 @@ impl<const N: usize> Kadcast<N> {
         }
     }
 
+    /// Returns up to `amount` alive peers, of any address family.
     pub async fn alive_nodes(&self, amount: usize) -> Vec<SocketAddr> {
-        self.peer.alive_nodes(amount).await
+        alive_nodes(&self.peers, amount).await
+    }
+
+    /// Keeps `store` up to date with the alive peers, saving it to `path`
+    /// every `interval`.
+    ///
+    /// Whenever fewer than `min_peers` are alive, the stored peers are dialed,
+    /// the unresponsive ones being redialed with an exponential backoff.
+    pub fn spawn_peer_store(
+        &self,
+        mut store: PeerStore,
+        path: PathBuf,
+        min_peers: usize,
+        interval: Duration,
+    ) {
+        let peers = self.peers.clone();
+        let public_addresses = self.public_addresses.clone();
+
+        tokio::spawn(async move {
+            let mut interval = time::interval(interval);
+            let mut dials = 0;
+            loop {
+                interval.tick().await;
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+
+                let alive =
+                    alive_nodes(&peers, peer_store::MAX_STORED_PEERS).await;
+                for addr in &alive {
+                    store.seen(*addr, now);
+                }
+
+                if alive.len() < min_peers {
+                    let candidates = store
+                        .dialable(now, min_peers)
+                        .into_iter()
+                        .filter(|addr| !alive.contains(addr))
+                        .take(min_peers - alive.len());
+                    for addr in candidates.collect::<Vec<_>>() {
+                        // Any message makes the peer aware of the node. An
+                        // empty inventory is discarded at no cost.
+                        // dials is added to bypass kadcast dupemap
+                        let msg = Message::new_inv(Inv::default());
+                        let Ok(encoded) = frame::Pdu::encode(&msg, dials)
+                        else {
+                            break;
+                        };
+                        dials += 1;
+
+                        trace!("dialing stored peer {addr}");
+                        let idx = peer_index(&public_addresses, addr);
+                        peers[idx].send(&encoded, addr).await;
+                        store.dialed(addr, now);
+                    }
+                }
+
+                if let Err(e) = store.save(&path) {
+                    warn!("Cannot save peer store to {path:?}: {e}");
+                }
+            }
+        });
+    }
+
+    /// Returns false if the peer announced it cannot decode our frames.
+    fn accepts_ours(&self, addr: &SocketAddr) -> bool {
+        self.versions
+            .lock()
+            .expect("lock to be acquired")
+            .accepts_ours(addr)
+    }
+
+    /// Returns the number of peers known to use each protocol version.
+    pub fn peer_protocol_versions(&self) -> BTreeMap<u32, usize> {
+        self.versions.lock().expect("lock to be acquired").summary()
     }
 
+    /// Returns the configuration of the primary address.
     pub fn conf(&self) -> &Config {
         &self.conf
     }
+
+    /// Returns the public addresses advertised to the peers.
+    pub fn public_addresses(&self) -> Vec<String> {
+        std::iter::once(&self.conf)
+            .chain(&self.additional_confs)
+            .map(|conf| conf.public_address.clone())
+            .collect()
+    }
+}
+
+/// Returns up to `amount` alive nodes known to any of `peers`.
+async fn alive_nodes(peers: &[Arc<Peer>], amount: usize) -> Vec<SocketAddr> {
+    let mut nodes = vec![];
+    for peer in peers.iter() {
+        for node in peer.alive_nodes(amount).await {
+            if !nodes.contains(&node) {
+                nodes.push(node);
+            }
+        }
+    }
+    nodes.truncate(amount);
+    nodes
+}
+
+/// Returns whether `node` may be reached from `public_address`.
+///
+/// Nodes given by hostname are assumed to be reachable from any family.
+fn same_family(public_address: Option<SocketAddr>, node: &str) -> bool {
+    match (public_address, node.parse::<SocketAddr>()) {
+        (Some(addr), Ok(node)) => addr.is_ipv4() == node.is_ipv4(),
+        _ => true,
+    }
+}
+
+/// Returns the index of the first public address of the same family as
+/// `addr`, falling back to the primary one.
+fn peer_index(
+    public_addresses: &[Option<SocketAddr>],
+    addr: SocketAddr,
+) -> usize {
+    public_addresses
+        .iter()
+        .position(|a| matches!(a, Some(a) if a.is_ipv4() == addr.is_ipv4()))
+        .unwrap_or(0)
 }
 
 #[async_trait]
@@ -185,13 +531,26 @@ impl<const N: usize> crate::Network for Kadcast<N> {
             None => None,
         };
 
-        let encoded = frame::Pdu::encode(msg, 0).map_err(|err| {
+        let encoded = self.encode(msg, 0).map_err(|err| {
             error!("could not encode message {msg:?}: {err}");
             anyhow::anyhow!("failed to broadcast: {err}")
         })?;
 
         trace!("broadcasting msg ({:?})", msg.topic());
-        self.peer.broadcast(&encoded, height).await;
+        match &msg.metadata {
+            // A received message keeps propagating on the overlay it was
+            // received from only, its height being relative to that overlay
+            Some(Metadata { src_addr, .. }) => {
+                self.peer_for(*src_addr).broadcast(&encoded, height).await;
+            }
+            // A message of our own is sent to the peers of every family
+            None => {
+                for peer in self.peers.iter() {
+                    peer.broadcast(&encoded, height).await;
+                }
+            }
+        }
+        self.buffers.put(encoded);
 
         Ok(())
     }
@@ -202,15 +561,23 @@ impl<const N: usize> crate::Network for Kadcast<N> {
         msg: &Message,
         recv_addr: SocketAddr,
     ) -> anyhow::Result<()> {
+        if !self.accepts_ours(&recv_addr) {
+            anyhow::bail!(
+                "peer {recv_addr} cannot decode protocol version {PROTOCOL_VERSION}"
+            );
+        }
+
         // rnd_count is added to bypass kadcast dupemap
         let rnd_count = self.counter.fetch_add(1, Ordering::SeqCst);
-        let encoded = frame::Pdu::encode(msg, rnd_count)
+        let encoded = self
+            .encode(msg, rnd_count)
             .map_err(|err| anyhow::anyhow!("failed to send_to_peer: {err}"))?;
         let topic = msg.topic();
 
         info!("sending msg ({topic:?}) to peer {recv_addr}");
 
-        self.peer.send(&encoded, recv_addr).await;
+        self.send_encoded(&encoded, recv_addr).await;
+        self.buffers.put(encoded);
 
         Ok(())
     }
@@ -221,15 +588,20 @@ impl<const N: usize> crate::Network for Kadcast<N> {
         msg: &Message,
         amount: usize,
     ) -> anyhow::Result<()> {
-        let encoded = frame::Pdu::encode(msg, 0)
+        let encoded = self
+            .encode(msg, 0)
             .map_err(|err| anyhow::anyhow!("failed to encode: {err}"))?;
         let topic = msg.topic();
 
-        for recv_addr in self.peer.alive_nodes(amount).await {
+        for recv_addr in self.alive_nodes(amount).await {
+            if !self.accepts_ours(&recv_addr) {
+                continue;
+            }
             trace!("sending msg ({topic:?}) to peer {recv_addr}");
 
-            self.peer.send(&encoded, recv_addr).await;
+            self.send_encoded(&encoded, recv_addr).await;
         }
+        self.buffers.put(encoded);
 
         Ok(())
     }
@@ -306,6 +678,28 @@ impl<const N: usize> crate::Network for Kadcast<N> {
     }
 
     fn get_info(&self) -> anyhow::Result<String> {
-        Ok(self.conf.public_address.to_string())
+        Ok(self.public_addresses().join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peers_are_chosen_by_address_family() {
+        let v4: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let v6: SocketAddr = "[2001:db8::1]:9000".parse().unwrap();
+
+        let addresses = [Some(v4), None, Some(v6)];
+        assert_eq!(peer_index(&addresses, "10.0.0.2:9000".parse().unwrap()), 0);
+        assert_eq!(peer_index(&addresses, "[::1]:9000".parse().unwrap()), 2);
+        assert_eq!(peer_index(&[Some(v4)], "[::1]:9000".parse().unwrap()), 0);
+
+        assert!(same_family(Some(v4), "10.0.0.2:9000"));
+        assert!(!same_family(Some(v4), "[2001:db8::2]:9000"));
+        assert!(same_family(Some(v6), "[2001:db8::2]:9000"));
+        assert!(same_family(Some(v6), "bootstrap.dusk.network:9000"));
+        assert!(same_family(None, "10.0.0.2:9000"));
     }
 }
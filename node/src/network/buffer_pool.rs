@@ -0,0 +1,69 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::sync::Mutex;
+
+/// Maximum number of buffers kept in the pool
+const MAX_POOLED_BUFFERS: usize = 16;
+
+/// Capacity above which a buffer is released instead of pooled
+const MAX_POOLED_CAPACITY: usize = 16 * 1024 * 1024;
+
+/// Pool of the buffers the outgoing frames are encoded into.
+///
+/// During sync, blocks are sent back to back, each one growing a fresh
+/// buffer to its size. Pooled buffers keep their capacity, so that frames
+/// are encoded without any allocation once the pool is warm.
+#[derive(Debug, Default)]
+pub(crate) struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+}
+
+impl BufferPool {
+    /// Takes an empty buffer out of the pool, allocating one if none is left.
+    pub(crate) fn take(&self) -> Vec<u8> {
+        self.buffers
+            .lock()
+            .expect("lock to be acquired")
+            .pop()
+            .unwrap_or_default()
+    }
+
+    /// Puts `buf` back into the pool, unless either is too large.
+    pub(crate) fn put(&self, mut buf: Vec<u8>) {
+        if buf.capacity() > MAX_POOLED_CAPACITY {
+            return;
+        }
+        buf.clear();
+
+        let mut buffers = self.buffers.lock().expect("lock to be acquired");
+        if buffers.len() < MAX_POOLED_BUFFERS {
+            buffers.push(buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_are_reused() {
+        let pool = BufferPool::default();
+
+        let mut buf = pool.take();
+        buf.extend_from_slice(&[1; 1024]);
+        pool.put(buf);
+
+        let buf = pool.take();
+        assert!(buf.is_empty());
+        assert!(buf.capacity() >= 1024);
+        assert_eq!(pool.take().capacity(), 0);
+
+        pool.put(Vec::with_capacity(MAX_POOLED_CAPACITY + 1));
+        assert_eq!(pool.take().capacity(), 0);
+    }
+}
@@ -8,7 +8,88 @@ use node_data::message::Message;
 use node_data::Serializable;
 use std::io::{self, Read, Write};
 
-const PROTOCOL_VERSION: [u8; 8] = [0, 0, 0, 0, 1, 0, 0, 0];
+/// Version of the wire protocol, to be increased on every incompatible change
+/// of the messages format.
+///
+/// Every frame carries the version of the node that encoded it, in the last
+/// four bytes (LE) of the header version field. The first ones are always
+/// zero, for plaintext frames to be told apart from noise ones.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest protocol version of the frames that can be decoded.
+///
+/// Frames of the previous version are accepted as well, so that the network
+/// can be upgraded one node at a time.
+pub const MIN_PROTOCOL_VERSION: u32 = PROTOCOL_VERSION.saturating_sub(1);
+
+/// Returns true if frames of the given protocol version can be decoded.
+pub const fn is_compatible(version: u32) -> bool {
+    version >= MIN_PROTOCOL_VERSION && version <= PROTOCOL_VERSION
+}
+
+/// Tag of the hello frames, told apart from plaintext frames whose first byte
+/// is always zero, and from noise ones.
+const TAG_HELLO: u8 = 0xe0;
+/// Size of an encoded hello frame
+const HELLO_SIZE: usize = 9;
+
+/// Hello frame exchanged with a peer the first time a frame is received from
+/// it, announcing the protocol versions each node can decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hello {
+    /// Protocol version of the frames encoded by the node
+    pub version: u32,
+    /// Oldest protocol version of the frames the node can decode
+    pub min_version: u32,
+}
+
+impl Hello {
+    /// Returns the hello frame of this node.
+    pub const fn ours() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+        }
+    }
+
+    /// Returns true if the node can decode frames of the given version.
+    pub fn accepts(&self, version: u32) -> bool {
+        version >= self.min_version && version <= self.version
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(HELLO_SIZE);
+        buf.push(TAG_HELLO);
+        buf.extend(self.version.to_le_bytes());
+        buf.extend(self.min_version.to_le_bytes());
+        buf
+    }
+
+    pub fn decode(blob: &[u8]) -> Option<Self> {
+        if blob.len() != HELLO_SIZE || !is_hello_frame(blob) {
+            return None;
+        }
+        let version = u32::from_le_bytes(blob[1..5].try_into().ok()?);
+        let min_version = u32::from_le_bytes(blob[5..9].try_into().ok()?);
+        (min_version <= version).then_some(Self {
+            version,
+            min_version,
+        })
+    }
+}
+
+/// Returns true if the frame is a hello frame.
+pub fn is_hello_frame(blob: &[u8]) -> bool {
+    blob.first() == Some(&TAG_HELLO)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DecodeError {
+    #[error("incompatible protocol version {0}")]
+    IncompatibleVersion(u32),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
 
 /// Defines PDU (Protocol Data Unit) structure.
 #[derive(Debug, Default)]
@@ -25,33 +106,75 @@ pub struct Header {
     checksum: [u8; 4],
 }
 
+/// Size of the encoded frame header
+const HEADER_SIZE: usize = 20;
+/// Position of the checksum in the encoded frame header
+const CHECKSUM_POS: usize = 16;
+
 impl Pdu {
     pub fn encode(msg: &Message, reserved: u64) -> io::Result<Vec<u8>> {
-        let mut payload_buf = vec![];
-        msg.write(&mut payload_buf)?;
+        let mut buf = vec![];
+        Self::encode_into(msg, reserved, &mut buf)?;
+        Ok(buf)
+    }
 
-        let mut header_buf = vec![];
+    /// Encodes a frame into `buf`, replacing its content.
+    ///
+    /// The payload is written right after the header, whose checksum is
+    /// filled in last, so that a buffer reused across frames is the only
+    /// allocation.
+    pub fn encode_into(
+        msg: &Message,
+        reserved: u64,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        buf.clear();
         Header {
-            checksum: calc_checksum(&payload_buf[..]),
-            version: PROTOCOL_VERSION,
+            checksum: [0u8; 4],
+            version: encode_version(PROTOCOL_VERSION),
             reserved,
         }
-        .write(&mut header_buf)?;
+        .write(buf)?;
+        msg.write(buf)?;
+
+        let checksum = calc_checksum(&buf[HEADER_SIZE..]);
+        buf[CHECKSUM_POS..HEADER_SIZE].copy_from_slice(&checksum);
 
-        Ok([header_buf, payload_buf].concat())
+        Ok(())
     }
 
-    pub fn decode<R: Read>(r: &mut R) -> io::Result<Self>
+    /// Decodes a frame, failing without reading the payload if its protocol
+    /// version is not compatible.
+    pub fn decode<R: Read>(r: &mut R) -> Result<Self, DecodeError>
     where
         Self: Sized,
     {
         let header = Header::read(r)?;
+        let version = header.protocol_version();
+        if !is_compatible(version) {
+            return Err(DecodeError::IncompatibleVersion(version));
+        }
         let payload = Message::read(r)?;
 
         Ok(Pdu { header, payload })
     }
 }
 
+impl Header {
+    /// Returns the protocol version of the node that encoded the frame.
+    pub fn protocol_version(&self) -> u32 {
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&self.version[4..]);
+        u32::from_le_bytes(version)
+    }
+}
+
+fn encode_version(version: u32) -> [u8; 8] {
+    let mut bytes = [0u8; 8];
+    bytes[4..].copy_from_slice(&version.to_le_bytes());
+    bytes
+}
+
 impl Serializable for Header {
     fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
         w.write_all(&self.version)?;
@@ -88,3 +211,80 @@ fn calc_checksum(buf: &[u8]) -> [u8; 4] {
     v.clone_from_slice(&res[0..4]);
     v
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_compatibility() {
+        let msg = Message::new_get_mempool(
+            node_data::message::payload::GetMempool::default(),
+        );
+        let encoded = Pdu::encode(&msg, 0).unwrap();
+        // The previous encoding of version 1
+        assert_eq!(&encoded[..8], &[0, 0, 0, 0, 1, 0, 0, 0]);
+
+        let pdu = Pdu::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(pdu.header.protocol_version(), PROTOCOL_VERSION);
+
+        let mut newer = encoded.clone();
+        newer[4..8].copy_from_slice(&(PROTOCOL_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            Pdu::decode(&mut &newer[..]),
+            Err(DecodeError::IncompatibleVersion(v)) if v == PROTOCOL_VERSION + 1
+        ));
+
+        assert!(is_compatible(PROTOCOL_VERSION - 1));
+        assert!(!is_compatible(PROTOCOL_VERSION + 1));
+        assert!(!is_compatible(u32::MAX));
+    }
+
+    #[test]
+    fn hello_frames() {
+        let hello = Hello::ours();
+        let encoded = hello.encode();
+        assert!(is_hello_frame(&encoded));
+        assert_eq!(Hello::decode(&encoded), Some(hello));
+
+        // Plaintext frames are not hello ones
+        let msg = Message::new_get_mempool(
+            node_data::message::payload::GetMempool::default(),
+        );
+        let pdu = Pdu::encode(&msg, 0).unwrap();
+        assert!(!is_hello_frame(&pdu));
+        assert_eq!(Hello::decode(&pdu), None);
+
+        // A hello accepting no version is invalid
+        let invalid = Hello {
+            version: 1,
+            min_version: 2,
+        };
+        assert_eq!(Hello::decode(&invalid.encode()), None);
+        assert_eq!(Hello::decode(&encoded[..HELLO_SIZE - 1]), None);
+
+        assert!(hello.accepts(PROTOCOL_VERSION));
+        assert!(hello.accepts(MIN_PROTOCOL_VERSION));
+        assert!(!hello.accepts(PROTOCOL_VERSION + 1));
+    }
+
+    #[test]
+    fn encode_into_reused_buffer() {
+        let msg = Message::new_get_mempool(
+            node_data::message::payload::GetMempool::default(),
+        );
+        let encoded = Pdu::encode(&msg, 7).unwrap();
+
+        let mut payload = vec![];
+        msg.write(&mut payload).unwrap();
+        assert_eq!(encoded[HEADER_SIZE..], payload[..]);
+        assert_eq!(encoded[CHECKSUM_POS..HEADER_SIZE], calc_checksum(&payload));
+
+        let mut buf = vec![42; 1024];
+        Pdu::encode_into(&msg, 7, &mut buf).unwrap();
+        assert_eq!(buf, encoded);
+
+        let pdu = Pdu::decode(&mut &buf[..]).unwrap();
+        assert_eq!(pdu.header.reserved, 7);
+    }
+}
@@ -0,0 +1,233 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Port mapping on the NAT gateway of nodes run behind a NAT.
+//!
+//! The UDP port of the node is mapped on the gateway either over UPnP, the
+//! gateway being discovered on the local network, or over NAT-PMP
+//! (RFC 6886), the gateway address being given. The external address of the
+//! mapping is the one to advertise to the peers.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use igd_next::aio::tokio::search_gateway;
+use igd_next::{PortMappingProtocol, SearchOptions};
+use tokio::net::UdpSocket;
+use tokio::time;
+use tracing::{info, warn};
+
+/// Port NAT-PMP gateways listen on
+const NATPMP_PORT: u16 = 5351;
+
+/// Delay before the first retransmission of a NAT-PMP request, doubled on
+/// each retransmission
+const NATPMP_INITIAL_TIMEOUT: Duration = Duration::from_millis(250);
+const NATPMP_MAX_ATTEMPTS: u32 = 6;
+
+const MAPPING_DESCRIPTION: &str = "rusk";
+
+/// Shortest lease requested for a mapping. A zero lease is not renewable, as
+/// gateways either map the port permanently or delete the mapping.
+const MIN_LEASE: Duration = Duration::from_secs(120);
+
+/// NAT gateway the port is mapped on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gateway {
+    /// Gateway discovered over UPnP
+    Upnp,
+    /// NAT-PMP gateway at the given address, usually the default route
+    NatPmp(Ipv4Addr),
+}
+
+/// Maps the UDP `port` of the node on `gateway` for `lease`, requesting the
+/// same external port, and returns the external address of the mapping.
+///
+/// The lease is at least [`MIN_LEASE`].
+pub async fn map_port(
+    gateway: Gateway,
+    port: u16,
+    lease: Duration,
+) -> anyhow::Result<SocketAddr> {
+    let lease = lease.max(MIN_LEASE);
+    match gateway {
+        Gateway::Upnp => map_upnp(port, lease).await,
+        Gateway::NatPmp(addr) => map_natpmp(addr, port, lease).await,
+    }
+}
+
+/// Renews the mapping of `port` on `gateway` halfway through each `lease`.
+///
+/// The external address advertised to the peers cannot change at runtime,
+/// thus a warning is raised if the mapping moves away from `advertised`.
+pub async fn keep_mapped(
+    gateway: Gateway,
+    port: u16,
+    lease: Duration,
+    advertised: SocketAddr,
+) {
+    let lease = lease.max(MIN_LEASE);
+    let mut interval = time::interval(lease / 2);
+    // The first tick completes immediately, the mapping being fresh
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        match map_port(gateway, port, lease).await {
+            Ok(external) if external != advertised => warn!(
+                event = "external address changed",
+                %advertised,
+                %external,
+                "restart the node to advertise the new address"
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("Cannot renew the mapping of port {port}: {e}"),
+        }
+    }
+}
+
+async fn map_upnp(port: u16, lease: Duration) -> anyhow::Result<SocketAddr> {
+    let gateway = search_gateway(SearchOptions::default()).await?;
+    info!("Found UPnP gateway at {}", gateway.addr);
+
+    // The mapping targets the address the node reaches the gateway from
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.connect(gateway.addr).await?;
+    let local = SocketAddr::new(socket.local_addr()?.ip(), port);
+
+    let lease = u32::try_from(lease.as_secs()).unwrap_or(u32::MAX);
+    gateway
+        .add_port(
+            PortMappingProtocol::UDP,
+            port,
+            local,
+            lease,
+            MAPPING_DESCRIPTION,
+        )
+        .await?;
+    let ip = gateway.get_external_ip().await?;
+
+    Ok(SocketAddr::new(ip, port))
+}
+
+async fn map_natpmp(
+    gateway: Ipv4Addr,
+    port: u16,
+    lease: Duration,
+) -> anyhow::Result<SocketAddr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.connect((gateway, NATPMP_PORT)).await?;
+
+    let res = natpmp_request(&socket, &external_address_request()).await?;
+    let ip = parse_external_address(&res)?;
+
+    let lease = u32::try_from(lease.as_secs()).unwrap_or(u32::MAX);
+    let res = natpmp_request(&socket, &mapping_request(port, lease)).await?;
+    let external_port = parse_mapping(&res, port)?;
+
+    Ok(SocketAddr::new(IpAddr::V4(ip), external_port))
+}
+
+/// Sends `req` to the gateway, retransmitting it until a response arrives.
+async fn natpmp_request(
+    socket: &UdpSocket,
+    req: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let mut timeout = NATPMP_INITIAL_TIMEOUT;
+    let mut buf = [0u8; 16];
+    for _ in 0..NATPMP_MAX_ATTEMPTS {
+        socket.send(req).await?;
+        if let Ok(res) = time::timeout(timeout, socket.recv(&mut buf)).await {
+            return Ok(buf[..res?].to_vec());
+        }
+        timeout *= 2;
+    }
+    anyhow::bail!("no response from the NAT-PMP gateway")
+}
+
+fn external_address_request() -> [u8; 2] {
+    // Version 0, opcode 0
+    [0, 0]
+}
+
+fn mapping_request(port: u16, lease: u32) -> [u8; 12] {
+    let mut req = [0u8; 12];
+    // Version 0, opcode 1 (UDP), 2 reserved bytes
+    req[1] = 1;
+    req[4..6].copy_from_slice(&port.to_be_bytes());
+    // Suggested external port
+    req[6..8].copy_from_slice(&port.to_be_bytes());
+    req[8..12].copy_from_slice(&lease.to_be_bytes());
+    req
+}
+
+/// Checks the header of a NAT-PMP response to a request of `opcode`.
+fn check_response(res: &[u8], opcode: u8, len: usize) -> anyhow::Result<()> {
+    if res.len() < len || res[0] != 0 || res[1] != 128 + opcode {
+        anyhow::bail!("malformed NAT-PMP response");
+    }
+    match u16::from_be_bytes([res[2], res[3]]) {
+        0 => Ok(()),
+        code => anyhow::bail!("NAT-PMP request failed with code {code}"),
+    }
+}
+
+fn parse_external_address(res: &[u8]) -> anyhow::Result<Ipv4Addr> {
+    check_response(res, 0, 12)?;
+    Ok(Ipv4Addr::new(res[8], res[9], res[10], res[11]))
+}
+
+/// Returns the external port mapped to the internal `port`.
+fn parse_mapping(res: &[u8], port: u16) -> anyhow::Result<u16> {
+    check_response(res, 1, 16)?;
+    if u16::from_be_bytes([res[8], res[9]]) != port {
+        anyhow::bail!("NAT-PMP mapping of another port");
+    }
+    Ok(u16::from_be_bytes([res[10], res[11]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natpmp_messages() {
+        assert_eq!(
+            mapping_request(9000, 3600),
+            [0, 1, 0, 0, 0x23, 0x28, 0x23, 0x28, 0, 0, 0x0e, 0x10]
+        );
+
+        let res = [0, 128, 0, 0, 0, 0, 0, 1, 203, 0, 113, 7];
+        assert_eq!(
+            parse_external_address(&res).unwrap(),
+            Ipv4Addr::new(203, 0, 113, 7)
+        );
+
+        // Mapped to another external port
+        let res = [
+            0, 129, 0, 0, 0, 0, 0, 1, 0x23, 0x28, 0x23, 0x29, 0, 0, 0x0e, 0x10,
+        ];
+        assert_eq!(parse_mapping(&res, 9000).unwrap(), 9001);
+        assert!(parse_mapping(&res, 9001).is_err());
+
+        // Failure result codes, truncated responses and responses to other
+        // requests are rejected
+        let refused = [0, 129, 0, 2, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(parse_mapping(&refused, 9000).is_err());
+        assert!(parse_mapping(&res[..12], 9000).is_err());
+        assert!(parse_external_address(&res).is_err());
+    }
+
+    #[tokio::test]
+    async fn zero_lease_is_renewed() {
+        let advertised = SocketAddr::from((Ipv4Addr::LOCALHOST, 9000));
+        let gateway = Gateway::NatPmp(Ipv4Addr::LOCALHOST);
+
+        // The first renewal is due halfway through the minimum lease
+        let keep = keep_mapped(gateway, 9000, Duration::ZERO, advertised);
+        let res = time::timeout(Duration::from_millis(50), keep).await;
+        assert!(res.is_err(), "renewal loop to keep running");
+    }
+}
@@ -0,0 +1,872 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Encrypted and authenticated point-to-point transport.
+//!
+//! Peers run a Noise XX handshake on the first message sent to each other.
+//! The Noise static key is derived from the node consensus keys, or randomly
+//! generated at startup when they are held by a remote signer, and each side
+//! proves ownership of its static key by signing it with its BLS secret key,
+//! so that an established session is bound to a provisioner identity.
+//!
+//! Handshake and transport frames are tagged with a leading byte that never
+//! starts a plaintext PDU, so that both can be told apart on the wire. Once
+//! the transport is enabled, plaintext PDUs are only accepted for the
+//! [broadcast topics](is_broadcast_topic): those messages are relayed
+//! verbatim by Kadcast to the whole network, so they cannot be encrypted
+//! hop-by-hop, and are authenticated by their own signatures instead.
+//!
+//! A handshake started by a peer is kept apart from the established session
+//! until the peer has proven its identity, so that an unauthenticated frame
+//! never tears down a session. Sessions are renewed by a new handshake once
+//! they are [`SESSION_LIFETIME`] old, the previous one still opening the
+//! frames in flight for [`RETIRED_SESSION_GRACE`].
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use dusk_bytes::Serializable;
+use dusk_consensus::signer::ConsensusSigner;
+use node_data::bls::PublicKey;
+use node_data::message::Topics;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use snow::params::DHChoice;
+use snow::resolvers::{CryptoResolver, DefaultResolver};
+use snow::types::Dh;
+use snow::{Builder, HandshakeState, StatelessTransportState};
+use tracing::{debug, warn};
+
+const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+
+/// Maximum length of a single Noise message
+const MAX_NOISE_MSG_LEN: usize = 65535;
+/// Length of the AEAD authentication tag
+const TAG_LEN: usize = 16;
+/// Maximum length of the plaintext carried by a single Noise message
+const MAX_CHUNK_LEN: usize = MAX_NOISE_MSG_LEN - TAG_LEN;
+
+/// Time after which an incomplete handshake is restarted
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Maximum number of messages queued while a handshake is in progress
+const MAX_PENDING_MSGS: usize = 64;
+/// Maximum number of peers with a session or a handshake in progress
+const MAX_PEERS: usize = 4096;
+
+/// Age after which a session is renewed
+pub const SESSION_LIFETIME: Duration = Duration::from_secs(600);
+/// Number of messages after which a session is renewed
+const REKEY_AFTER_NONCES: u64 = 1 << 32;
+/// Time a renewed session keeps opening the frames sent before the renewal
+pub const RETIRED_SESSION_GRACE: Duration = Duration::from_secs(30);
+
+/// Number of nonces below the highest received one that are still accepted,
+/// if not received yet
+const REPLAY_WINDOW: u64 = u128::BITS as u64;
+
+/// Domain separator of the message signed to prove a static key ownership
+const SIGN_SEED: &[u8] = b"noise-static-key";
+
+const BLS_PK_LEN: usize = dusk_bls12_381_sign::PublicKey::SIZE;
+const BLS_SIG_LEN: usize = dusk_bls12_381_sign::Signature::SIZE;
+const PROOF_LEN: usize = BLS_PK_LEN + BLS_SIG_LEN;
+
+const TAG_HANDSHAKE_INIT: u8 = 0xf0;
+const TAG_HANDSHAKE_RESP: u8 = 0xf1;
+const TAG_HANDSHAKE_FINAL: u8 = 0xf2;
+const TAG_TRANSPORT: u8 = 0xf3;
+
+/// Returns true if the blob is a Noise frame rather than a plaintext PDU.
+///
+/// A plaintext PDU always starts with the first byte of the protocol version,
+/// which is zero.
+pub fn is_noise_frame(blob: &[u8]) -> bool {
+    matches!(blob.first(), Some(TAG_HANDSHAKE_INIT..=TAG_TRANSPORT))
+}
+
+/// Returns true if messages of the topic are broadcast to the network, and
+/// are thus accepted in clear when the Noise transport is enabled.
+pub fn is_broadcast_topic(topic: Topics) -> bool {
+    matches!(
+        topic,
+        Topics::Tx
+            | Topics::Block
+            | Topics::Candidate
+            | Topics::Validation
+            | Topics::Ratification
+            | Topics::Quorum
+            | Topics::GetVotes
+    )
+}
+
+/// Node identity used to authenticate Noise sessions.
+pub struct Identity {
+    pk: PublicKey,
+    static_key: [u8; 32],
+    static_pub: [u8; 32],
+    /// BLS public key followed by the signature of the Noise static public
+    /// key
+    proof: Vec<u8>,
+}
+
+impl Identity {
+    /// Derives the Noise static keypair from the node consensus keys.
+    pub fn new(sk: dusk_bls12_381_sign::SecretKey, pk: PublicKey) -> Self {
+        use blake2::{digest::consts::U32, Blake2b, Digest};
+
+        let mut h = Blake2b::<U32>::new();
+        h.update(SIGN_SEED);
+        h.update(sk.to_bytes());
+        let static_key: [u8; 32] = h.finalize().into();
+        let static_pub = static_pub(&static_key);
+
+        let sig = sk.sign(pk.inner(), &signable(&static_pub));
+        let proof = [&pk.inner().to_bytes()[..], &sig.to_bytes()[..]].concat();
+
+        Self {
+            pk,
+            static_key,
+            static_pub,
+            proof,
+        }
+    }
+
+    /// Generates a Noise static keypair, having its ownership proven by the
+    /// given signer, so that the consensus keys never reach this host.
+    pub async fn with_signer(signer: &dyn ConsensusSigner) -> Result<Self> {
+        let mut static_key = [0u8; 32];
+        OsRng.fill_bytes(&mut static_key);
+        let static_pub = static_pub(&static_key);
+
+        let pk = signer.public_key().clone();
+        let sig = signer.sign(&signable(&static_pub)).await?;
+        let proof = [&pk.inner().to_bytes()[..], &sig.to_bytes()[..]].concat();
+
+        Ok(Self {
+            pk,
+            static_key,
+            static_pub,
+            proof,
+        })
+    }
+
+    /// Loads the node identity from the consensus keys file, encrypted with
+    /// the passphrase provided by the environment.
+    pub fn load(path: String) -> Result<Self> {
+        let pwd = node_data::keystore::passphrase()?;
+        let (sk, pk) = node_data::bls::load_keys(path, pwd)?;
+        Ok(Self::new(sk, pk))
+    }
+
+    fn builder(&self) -> Result<Builder<'_>> {
+        Ok(Builder::new(NOISE_PARAMS.parse()?)
+            .local_private_key(&self.static_key))
+    }
+
+    fn proof(&self) -> &[u8] {
+        &self.proof
+    }
+}
+
+fn static_pub(static_key: &[u8; 32]) -> [u8; 32] {
+    let mut dh = DefaultResolver
+        .resolve_dh(&DHChoice::Curve25519)
+        .expect("Curve25519 to be supported");
+    dh.set(static_key);
+    dh.pubkey().try_into().expect("32 bytes public key")
+}
+
+struct Handshake {
+    state: Box<HandshakeState>,
+    started: Instant,
+}
+
+impl Handshake {
+    fn new(state: HandshakeState) -> Self {
+        Self {
+            state: Box::new(state),
+            started: Instant::now(),
+        }
+    }
+
+    fn is_alive(&self) -> bool {
+        self.started.elapsed() < HANDSHAKE_TIMEOUT
+    }
+}
+
+struct Session {
+    state: Box<StatelessTransportState>,
+    /// Nonce of the next message sent
+    nonce: u64,
+    window: ReplayWindow,
+    peer: PublicKey,
+    /// Noise static key of the side that started the handshake
+    initiator: [u8; 32],
+    established: Instant,
+}
+
+impl Session {
+    fn needs_renewal(&self) -> bool {
+        self.established.elapsed() >= SESSION_LIFETIME
+            || self.nonce >= REKEY_AFTER_NONCES
+    }
+
+    /// Encrypts a PDU in chunks of at most `MAX_CHUNK_LEN` bytes, each one
+    /// using the next nonce.
+    fn encrypt(&mut self, pdu: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(9 + pdu.len() + TAG_LEN);
+        out.push(TAG_TRANSPORT);
+        out.extend_from_slice(&self.nonce.to_le_bytes());
+
+        let mut buf = vec![0u8; MAX_NOISE_MSG_LEN];
+        for chunk in pdu.chunks(MAX_CHUNK_LEN) {
+            let len = self.state.write_message(self.nonce, chunk, &mut buf)?;
+            out.extend_from_slice(&buf[..len]);
+            self.nonce += 1;
+        }
+
+        Ok(out)
+    }
+
+    /// Decrypts a transport frame, rejecting it if any of its nonces has
+    /// already been received or is too old to tell.
+    fn decrypt(&mut self, body: &[u8]) -> Result<Vec<u8>> {
+        if body.len() < 8 {
+            bail!("truncated transport frame");
+        }
+        let (nonce, body) = body.split_at(8);
+        let nonce = u64::from_le_bytes(nonce.try_into()?);
+
+        let chunks = body.chunks(MAX_NOISE_MSG_LEN).len() as u64;
+        let nonces = nonce
+            ..nonce
+                .checked_add(chunks)
+                .ok_or_else(|| anyhow!("transport nonce overflow"))?;
+        if !nonces.clone().all(|n| self.window.is_fresh(n)) {
+            bail!("replayed transport frame");
+        }
+
+        let mut out = Vec::with_capacity(body.len());
+        let mut buf = vec![0u8; MAX_NOISE_MSG_LEN];
+        for (n, chunk) in nonces.clone().zip(body.chunks(MAX_NOISE_MSG_LEN)) {
+            let len = self.state.read_message(n, chunk, &mut buf)?;
+            out.extend_from_slice(&buf[..len]);
+        }
+
+        // Only authenticated frames move the window
+        nonces.for_each(|n| self.window.insert(n));
+        Ok(out)
+    }
+}
+
+/// Sliding window of the nonces received over a session.
+#[derive(Default)]
+struct ReplayWindow {
+    /// One past the highest nonce received
+    next: u64,
+    /// Nonces received below `next`, bit `i` standing for `next - 1 - i`
+    seen: u128,
+}
+
+impl ReplayWindow {
+    fn is_fresh(&self, nonce: u64) -> bool {
+        if nonce >= self.next {
+            return true;
+        }
+        let age = self.next - 1 - nonce;
+        age < REPLAY_WINDOW && self.seen & (1 << age) == 0
+    }
+
+    fn insert(&mut self, nonce: u64) {
+        if nonce >= self.next {
+            let shift = nonce - self.next + 1;
+            self.seen = match shift < REPLAY_WINDOW {
+                true => self.seen << shift,
+                false => 0,
+            };
+            self.seen |= 1;
+            self.next = nonce + 1;
+        } else {
+            self.seen |= 1 << (self.next - 1 - nonce);
+        }
+    }
+}
+
+/// Noise state shared with a peer.
+#[derive(Default)]
+struct PeerState {
+    current: Option<Session>,
+    /// Session replaced by the current one, and when it was
+    retired: Option<(Session, Instant)>,
+    /// Handshake started by the node
+    initiating: Option<Handshake>,
+    /// Handshake started by the peer, not authenticated yet
+    responding: Option<Handshake>,
+    /// Messages waiting for a session to be established
+    pending: Vec<Vec<u8>>,
+}
+
+impl PeerState {
+    fn is_idle(&self) -> bool {
+        self.current.is_none()
+            && self.pending.is_empty()
+            && !self.initiating.as_ref().is_some_and(Handshake::is_alive)
+            && !self.responding.as_ref().is_some_and(Handshake::is_alive)
+    }
+
+    /// Makes a new session current, returning the pending messages
+    /// encrypted for it.
+    ///
+    /// When both sides start a handshake at the same time, two sessions are
+    /// established in an order that differs on each side. Both sides then
+    /// keep the one started by the lowest static key, retiring the other.
+    fn establish(
+        &mut self,
+        state: HandshakeState,
+        peer: PublicKey,
+        initiator: [u8; 32],
+    ) -> Result<Vec<Vec<u8>>> {
+        let session = Session {
+            state: Box::new(state.into_stateless_transport_mode()?),
+            nonce: 0,
+            window: ReplayWindow::default(),
+            peer,
+            initiator,
+            established: Instant::now(),
+        };
+
+        let retired = match self.current.take() {
+            Some(current)
+                if current.established.elapsed() < HANDSHAKE_TIMEOUT
+                    && current.initiator < session.initiator =>
+            {
+                self.current = Some(current);
+                Some(session)
+            }
+            current => {
+                self.current = Some(session);
+                current
+            }
+        };
+        if let Some(retired) = retired {
+            self.retired = Some((retired, Instant::now()));
+        }
+        self.flush()
+    }
+
+    /// Encrypts the pending messages for the current session.
+    fn flush(&mut self) -> Result<Vec<Vec<u8>>> {
+        let Some(session) = &mut self.current else {
+            return Ok(vec![]);
+        };
+        self.pending
+            .drain(..)
+            .map(|pdu| session.encrypt(&pdu))
+            .collect()
+    }
+}
+
+/// Result of processing an incoming Noise frame.
+#[derive(Default)]
+pub struct Opened {
+    /// Decrypted PDU, if the frame carried one
+    pub payload: Option<Vec<u8>>,
+    /// Frames to be sent back to the source
+    pub replies: Vec<Vec<u8>>,
+}
+
+/// Set of Noise sessions, one per peer address.
+pub struct Noise {
+    identity: Identity,
+    peers: Mutex<HashMap<SocketAddr, PeerState>>,
+}
+
+impl Noise {
+    pub fn new(identity: Identity) -> Self {
+        Self {
+            identity,
+            peers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the identity of the peer at `addr`, if a session is
+    /// established.
+    pub fn peer(&self, addr: &SocketAddr) -> Option<PublicKey> {
+        let peers = self.peers.lock().expect("lock to be acquired");
+        peers
+            .get(addr)
+            .and_then(|state| state.current.as_ref())
+            .map(|session| session.peer.clone())
+    }
+
+    /// Returns the state shared with the peer at `addr`, making room for it
+    /// if needed.
+    fn peer_state<'a>(
+        peers: &'a mut HashMap<SocketAddr, PeerState>,
+        addr: SocketAddr,
+    ) -> Result<&'a mut PeerState> {
+        if !peers.contains_key(&addr) && peers.len() >= MAX_PEERS {
+            peers.retain(|_, state| !state.is_idle());
+            if peers.len() >= MAX_PEERS {
+                bail!("too many noise peers");
+            }
+        }
+        Ok(peers.entry(addr).or_default())
+    }
+
+    /// Encrypts an encoded PDU for the peer at `addr`, returning the frames
+    /// to be sent to it.
+    ///
+    /// If no session is established, the PDU is queued and the frame
+    /// initiating the handshake is returned instead, unless a handshake is
+    /// already in progress. A session due for renewal keeps being used until
+    /// the new one is established.
+    pub fn seal(&self, pdu: &[u8], addr: SocketAddr) -> Result<Vec<Vec<u8>>> {
+        let mut peers = self.peers.lock().expect("lock to be acquired");
+        let state = Self::peer_state(&mut peers, addr)?;
+
+        let mut frames = vec![];
+        let renew = state.current.as_ref().map_or(true, Session::needs_renewal);
+        if renew && !state.initiating.as_ref().is_some_and(Handshake::is_alive)
+        {
+            if state.initiating.is_some() {
+                debug!("restarting noise handshake with {addr}");
+            }
+            let mut handshake = self.identity.builder()?.build_initiator()?;
+            let mut buf = vec![0u8; MAX_NOISE_MSG_LEN];
+            let len = handshake.write_message(&[], &mut buf)?;
+            state.initiating = Some(Handshake::new(handshake));
+            frames.push(frame(TAG_HANDSHAKE_INIT, &buf[..len]));
+        }
+
+        match &mut state.current {
+            Some(session) => frames.push(session.encrypt(pdu)?),
+            None => {
+                if state.pending.len() >= MAX_PENDING_MSGS {
+                    warn!("too many pending messages for {addr}");
+                    state.pending.remove(0);
+                }
+                state.pending.push(pdu.to_vec());
+            }
+        }
+
+        Ok(frames)
+    }
+
+    /// Processes a Noise frame received from `src`.
+    pub fn open(&self, blob: &[u8], src: SocketAddr) -> Result<Opened> {
+        let (tag, body) =
+            blob.split_first().ok_or_else(|| anyhow!("empty frame"))?;
+        let mut peers = self.peers.lock().expect("lock to be acquired");
+
+        match *tag {
+            TAG_HANDSHAKE_INIT => {
+                let state = Self::peer_state(&mut peers, src)?;
+                self.on_init(state, body)
+            }
+            TAG_HANDSHAKE_RESP => match peers.get_mut(&src) {
+                Some(state) => self.on_resp(state, body, src),
+                None => bail!("unexpected handshake response from {src}"),
+            },
+            TAG_HANDSHAKE_FINAL => match peers.get_mut(&src) {
+                Some(state) => on_final(state, body, src),
+                None => bail!("unexpected handshake final message from {src}"),
+            },
+            TAG_TRANSPORT => match peers.get_mut(&src) {
+                Some(state) => Ok(Opened {
+                    payload: Some(on_transport(state, body, src)?),
+                    replies: vec![],
+                }),
+                None => bail!("no noise session with {src}"),
+            },
+            _ => bail!("unknown noise frame tag {tag}"),
+        }
+    }
+
+    /// Answers a handshake started by a peer.
+    ///
+    /// The peer is not authenticated yet, so only the handshake it started
+    /// is replaced, leaving any established session and the handshake
+    /// started by the node untouched.
+    fn on_init(&self, state: &mut PeerState, body: &[u8]) -> Result<Opened> {
+        let mut handshake = self.identity.builder()?.build_responder()?;
+        let mut buf = vec![0u8; MAX_NOISE_MSG_LEN];
+        handshake.read_message(body, &mut buf)?;
+
+        let len = handshake.write_message(self.identity.proof(), &mut buf)?;
+        state.responding = Some(Handshake::new(handshake));
+
+        Ok(Opened {
+            payload: None,
+            replies: vec![frame(TAG_HANDSHAKE_RESP, &buf[..len])],
+        })
+    }
+
+    fn on_resp(
+        &self,
+        state: &mut PeerState,
+        body: &[u8],
+        src: SocketAddr,
+    ) -> Result<Opened> {
+        let Some(handshake) = &mut state.initiating else {
+            bail!("unexpected handshake response from {src}");
+        };
+
+        let mut buf = vec![0u8; MAX_NOISE_MSG_LEN];
+        let len = handshake.state.read_message(body, &mut buf)?;
+        let peer =
+            verify_proof(&buf[..len], handshake.state.get_remote_static())?;
+        let len = handshake
+            .state
+            .write_message(self.identity.proof(), &mut buf)?;
+
+        let handshake = state.initiating.take().expect("handshake to be set");
+        let initiator = self.identity.static_pub;
+        let mut replies = vec![frame(TAG_HANDSHAKE_FINAL, &buf[..len])];
+        replies.extend(state.establish(
+            *handshake.state,
+            peer.clone(),
+            initiator,
+        )?);
+
+        debug!("noise session established with {src} ({peer:?})");
+        Ok(Opened {
+            payload: None,
+            replies,
+        })
+    }
+}
+
+fn on_final(
+    state: &mut PeerState,
+    body: &[u8],
+    src: SocketAddr,
+) -> Result<Opened> {
+    let Some(handshake) = &mut state.responding else {
+        bail!("unexpected handshake final message from {src}");
+    };
+
+    let mut buf = vec![0u8; MAX_NOISE_MSG_LEN];
+    let len = handshake.state.read_message(body, &mut buf)?;
+    let remote_static = handshake.state.get_remote_static();
+    let peer = verify_proof(&buf[..len], remote_static)?;
+    let initiator = remote_static
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| anyhow!("invalid remote static key"))?;
+
+    let handshake = state.responding.take().expect("handshake to be set");
+    let replies = state.establish(*handshake.state, peer.clone(), initiator)?;
+
+    debug!("noise session established with {src} ({peer:?})");
+    Ok(Opened {
+        payload: None,
+        replies,
+    })
+}
+
+/// Decrypts a transport frame with the current session, or with the one it
+/// replaced for the frames sent before the renewal.
+fn on_transport(
+    state: &mut PeerState,
+    body: &[u8],
+    src: SocketAddr,
+) -> Result<Vec<u8>> {
+    let Some(current) = &mut state.current else {
+        bail!("no noise session with {src}");
+    };
+    let err = match current.decrypt(body) {
+        Ok(pdu) => return Ok(pdu),
+        Err(err) => err,
+    };
+
+    match &mut state.retired {
+        Some((retired, since)) if since.elapsed() < RETIRED_SESSION_GRACE => {
+            retired.decrypt(body)
+        }
+        _ => {
+            state.retired = None;
+            Err(err)
+        }
+    }
+}
+
+fn signable(static_pub: &[u8]) -> Vec<u8> {
+    [SIGN_SEED, static_pub].concat()
+}
+
+/// Verifies that the remote static key is signed by the BLS key carried in
+/// the handshake payload.
+fn verify_proof(
+    payload: &[u8],
+    remote_static: Option<&[u8]>,
+) -> Result<PublicKey> {
+    if payload.len() != PROOF_LEN {
+        bail!("invalid noise identity proof length {}", payload.len());
+    }
+
+    let remote_static =
+        remote_static.ok_or_else(|| anyhow!("missing remote static key"))?;
+
+    let mut pk = [0u8; BLS_PK_LEN];
+    pk.copy_from_slice(&payload[..BLS_PK_LEN]);
+    let pk = dusk_bls12_381_sign::PublicKey::from_bytes(&pk)
+        .map_err(|e| anyhow!("invalid peer public key: {e:?}"))?;
+
+    let mut sig = [0u8; BLS_SIG_LEN];
+    sig.copy_from_slice(&payload[BLS_PK_LEN..]);
+    let sig = dusk_bls12_381_sign::Signature::from_bytes(&sig)
+        .map_err(|e| anyhow!("invalid peer signature: {e:?}"))?;
+
+    pk.verify(&sig, &signable(remote_static))
+        .map_err(|e| anyhow!("invalid noise identity proof: {e:?}"))?;
+
+    Ok(PublicKey::new(pk))
+}
+
+fn frame(tag: u8, body: &[u8]) -> Vec<u8> {
+    [&[tag], body].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn identity(rng: &mut StdRng) -> Identity {
+        let sk = dusk_bls12_381_sign::SecretKey::random(rng);
+        let pk = PublicKey::new(dusk_bls12_381_sign::PublicKey::from(&sk));
+        Identity::new(sk, pk)
+    }
+
+    fn addrs() -> (SocketAddr, SocketAddr) {
+        (
+            "127.0.0.1:9000".parse().unwrap(),
+            "127.0.0.1:9001".parse().unwrap(),
+        )
+    }
+
+    /// Runs a handshake started by `alice`, returning the frames she queued
+    /// meanwhile.
+    fn handshake(
+        alice: &Noise,
+        bob: &Noise,
+        pdu: &[u8],
+    ) -> impl Iterator<Item = Vec<u8>> {
+        let (alice_addr, bob_addr) = addrs();
+
+        let frames = alice.seal(pdu, bob_addr).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert!(is_noise_frame(&frames[0]));
+
+        let resp = bob.open(&frames[0], alice_addr).unwrap();
+        assert!(resp.payload.is_none());
+        assert_eq!(resp.replies.len(), 1);
+
+        let fin = alice.open(&resp.replies[0], bob_addr).unwrap();
+        assert_eq!(alice.peer(&bob_addr), Some(bob.identity.pk.clone()));
+
+        let mut replies = fin.replies.into_iter();
+        let done = bob.open(&replies.next().unwrap(), alice_addr).unwrap();
+        assert!(done.replies.is_empty());
+        assert_eq!(bob.peer(&alice_addr), Some(alice.identity.pk.clone()));
+
+        replies
+    }
+
+    #[test]
+    fn test_handshake_and_transport() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let alice = Noise::new(identity(&mut rng));
+        let bob = Noise::new(identity(&mut rng));
+        let (alice_addr, bob_addr) = addrs();
+
+        let pdu = vec![0u8, 1, 2, 3];
+        let (init, queued) = {
+            let mut frames = alice.seal(&pdu, bob_addr).unwrap();
+            // Queued until the handshake completes
+            assert!(alice.seal(&pdu, bob_addr).unwrap().is_empty());
+            (frames.remove(0), frames)
+        };
+        assert!(queued.is_empty());
+
+        let resp = bob.open(&init, alice_addr).unwrap();
+        let fin = alice.open(&resp.replies[0], bob_addr).unwrap();
+        assert_eq!(fin.replies.len(), 3);
+
+        let mut replies = fin.replies.into_iter();
+        bob.open(&replies.next().unwrap(), alice_addr).unwrap();
+        for frame in replies {
+            let opened = bob.open(&frame, alice_addr).unwrap();
+            assert_eq!(opened.payload, Some(pdu.clone()));
+        }
+
+        // Payloads exceeding a single Noise message
+        let large: Vec<u8> = (0..200_000).map(|i| i as u8).collect();
+        let frames = bob.seal(&large, alice_addr).unwrap();
+        let opened = alice.open(&frames[0], bob_addr).unwrap();
+        assert_eq!(opened.payload, Some(large));
+
+        // Tampered frames are rejected
+        let mut frame = alice.seal(&pdu, bob_addr).unwrap().remove(0);
+        let last = frame.len() - 1;
+        frame[last] ^= 1;
+        assert!(bob.open(&frame, alice_addr).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handshake_with_signer() {
+        use dusk_consensus::signer::LocalSigner;
+
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let sk = dusk_bls12_381_sign::SecretKey::random(&mut rng);
+        let pk = PublicKey::new(dusk_bls12_381_sign::PublicKey::from(&sk));
+        let signer = LocalSigner::new(sk, pk);
+
+        let alice = Identity::with_signer(&signer).await.unwrap();
+        let alice = Noise::new(alice);
+        let bob = Noise::new(identity(&mut rng));
+        let (alice_addr, _) = addrs();
+
+        let pdu = vec![0u8, 1, 2, 3];
+        for frame in handshake(&alice, &bob, &pdu) {
+            let opened = bob.open(&frame, alice_addr).unwrap();
+            assert_eq!(opened.payload, Some(pdu.clone()));
+        }
+        assert_eq!(bob.peer(&alice_addr), Some(signer.public_key().clone()));
+    }
+
+    #[test]
+    fn test_replayed_frames_are_rejected() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let alice = Noise::new(identity(&mut rng));
+        let bob = Noise::new(identity(&mut rng));
+        let (alice_addr, bob_addr) = addrs();
+
+        let pdu = vec![0u8, 1, 2, 3];
+        let frame = handshake(&alice, &bob, &pdu).next().unwrap();
+        assert!(bob.open(&frame, alice_addr).is_ok());
+        assert!(bob.open(&frame, alice_addr).is_err());
+
+        // Frames received out of order are accepted once
+        let first = alice.seal(&pdu, bob_addr).unwrap().remove(0);
+        let second = alice.seal(&pdu, bob_addr).unwrap().remove(0);
+        assert!(bob.open(&second, alice_addr).is_ok());
+        assert!(bob.open(&first, alice_addr).is_ok());
+        assert!(bob.open(&first, alice_addr).is_err());
+        assert!(bob.open(&second, alice_addr).is_err());
+    }
+
+    #[test]
+    fn test_spoofed_handshake_keeps_session() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let alice = Noise::new(identity(&mut rng));
+        let bob = Noise::new(identity(&mut rng));
+        let mallory = Noise::new(identity(&mut rng));
+        let (alice_addr, bob_addr) = addrs();
+
+        let pdu = vec![0u8, 1, 2, 3];
+        for frame in handshake(&alice, &bob, &pdu) {
+            bob.open(&frame, alice_addr).unwrap();
+        }
+
+        // An handshake started from alice's address does not replace her
+        // session until it is completed
+        let init = mallory.seal(&pdu, bob_addr).unwrap().remove(0);
+        bob.open(&init, alice_addr).unwrap();
+        assert_eq!(bob.peer(&alice_addr), Some(alice.identity.pk.clone()));
+
+        let frame = alice.seal(&pdu, bob_addr).unwrap().remove(0);
+        let opened = bob.open(&frame, alice_addr).unwrap();
+        assert_eq!(opened.payload, Some(pdu));
+    }
+
+    #[test]
+    fn test_session_renewal() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let alice = Noise::new(identity(&mut rng));
+        let bob = Noise::new(identity(&mut rng));
+        let (alice_addr, bob_addr) = addrs();
+
+        let pdu = vec![0u8, 1, 2, 3];
+        for frame in handshake(&alice, &bob, &pdu) {
+            bob.open(&frame, alice_addr).unwrap();
+        }
+
+        {
+            let mut peers = alice.peers.lock().unwrap();
+            let session = peers.get_mut(&bob_addr).unwrap();
+            session.current.as_mut().unwrap().nonce = REKEY_AFTER_NONCES;
+        }
+
+        // The renewal is started while the session keeps being used
+        let mut frames = alice.seal(&pdu, bob_addr).unwrap();
+        assert_eq!(frames.len(), 2);
+        let in_flight = frames.pop().unwrap();
+
+        let resp = bob.open(&frames[0], alice_addr).unwrap();
+        let fin = alice.open(&resp.replies[0], bob_addr).unwrap();
+        assert_eq!(fin.replies.len(), 1);
+        bob.open(&fin.replies[0], alice_addr).unwrap();
+
+        // Frames sent before the renewal are still opened
+        let opened = bob.open(&in_flight, alice_addr).unwrap();
+        assert_eq!(opened.payload, Some(pdu.clone()));
+
+        let frames = alice.seal(&pdu, bob_addr).unwrap();
+        assert_eq!(frames.len(), 1);
+        let opened = bob.open(&frames[0], alice_addr).unwrap();
+        assert_eq!(opened.payload, Some(pdu));
+    }
+
+    #[test]
+    fn test_concurrent_handshakes() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let alice = Noise::new(identity(&mut rng));
+        let bob = Noise::new(identity(&mut rng));
+        let (alice_addr, bob_addr) = addrs();
+
+        let pdu = vec![0u8, 1, 2, 3];
+        let alice_init = alice.seal(&pdu, bob_addr).unwrap().remove(0);
+        let bob_init = bob.seal(&pdu, alice_addr).unwrap().remove(0);
+
+        let bob_resp = bob.open(&alice_init, alice_addr).unwrap();
+        let alice_resp = alice.open(&bob_init, bob_addr).unwrap();
+
+        let alice_fin = alice.open(&bob_resp.replies[0], bob_addr).unwrap();
+        let bob_fin = bob.open(&alice_resp.replies[0], alice_addr).unwrap();
+        bob.open(&alice_fin.replies[0], alice_addr).unwrap();
+        alice.open(&bob_fin.replies[0], bob_addr).unwrap();
+
+        // Both sides agree on the session to keep
+        for frame in alice_fin.replies.iter().skip(1) {
+            bob.open(frame, alice_addr).unwrap();
+        }
+        for frame in bob_fin.replies.iter().skip(1) {
+            alice.open(frame, bob_addr).unwrap();
+        }
+        let alice_initiator = {
+            let peers = alice.peers.lock().unwrap();
+            peers[&bob_addr].current.as_ref().unwrap().initiator
+        };
+        let bob_initiator = {
+            let peers = bob.peers.lock().unwrap();
+            peers[&alice_addr].current.as_ref().unwrap().initiator
+        };
+        assert_eq!(alice_initiator, bob_initiator);
+    }
+
+    #[test]
+    fn test_broadcast_topics() {
+        assert!(is_broadcast_topic(Topics::Block));
+        assert!(is_broadcast_topic(Topics::Quorum));
+        assert!(!is_broadcast_topic(Topics::GetBlocks));
+        assert!(!is_broadcast_topic(Topics::GetCandidateResp));
+    }
+}
@@ -0,0 +1,234 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Maximum number of peers kept in the store
+pub const MAX_STORED_PEERS: usize = 1000;
+
+/// Consecutive failed dials after which a peer is forgotten
+const MAX_FAILURES: u32 = 10;
+
+/// Delay before redialing a peer after its first failed dial, doubled on
+/// each further failure
+const BASE_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct PeerRecord {
+    /// Unix time the peer was last seen alive, in seconds
+    pub last_seen: u64,
+    /// Dials since the peer was last seen alive
+    pub failures: u32,
+    /// Unix time before which the peer is not dialed, in seconds
+    pub next_dial: u64,
+}
+
+/// Addresses of the peers known to the node, persisted across restarts so
+/// that the node rejoins the network without relying solely on the
+/// bootstrapping nodes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PeerStore {
+    peers: BTreeMap<SocketAddr, PeerRecord>,
+}
+
+impl PeerStore {
+    /// Loads the store from `path`, starting empty if it cannot be read.
+    pub fn load(path: &Path) -> Self {
+        let store = match fs::read(path) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).map_err(anyhow::Error::from)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            Err(e) => Err(anyhow::Error::from(e)),
+        };
+
+        store.unwrap_or_else(|e| {
+            warn!("Cannot load peer store from {path:?}: {e}");
+            Self::default()
+        })
+    }
+
+    /// Saves the store to `path`, replacing the previous one atomically.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    pub fn get(&self, addr: &SocketAddr) -> Option<&PeerRecord> {
+        self.peers.get(addr)
+    }
+
+    /// Records `addr` as alive at `now`.
+    pub fn seen(&mut self, addr: SocketAddr, now: u64) {
+        self.peers.insert(
+            addr,
+            PeerRecord {
+                last_seen: now,
+                failures: 0,
+                next_dial: 0,
+            },
+        );
+        self.evict();
+    }
+
+    /// Records a dial of `addr` at `now`.
+    ///
+    /// The dial counts as failed until the peer is seen alive, so the peer
+    /// is not dialed again before its backoff elapses.
+    pub fn dialed(&mut self, addr: SocketAddr, now: u64) {
+        let record = self.peers.entry(addr).or_default();
+        record.failures += 1;
+        record.next_dial = now + backoff(record.failures).as_secs();
+        self.evict();
+    }
+
+    /// Returns up to `amount` peers whose backoff elapsed at `now`, the
+    /// most recently seen first.
+    pub fn dialable(&self, now: u64, amount: usize) -> Vec<SocketAddr> {
+        let mut peers: Vec<_> = self
+            .peers
+            .iter()
+            .filter(|(_, record)| record.next_dial <= now)
+            .collect();
+        peers.sort_by_key(|(_, record)| std::cmp::Reverse(record.last_seen));
+        peers
+            .into_iter()
+            .take(amount)
+            .map(|(addr, _)| *addr)
+            .collect()
+    }
+
+    /// Forgets the peers failing too many dials, then the least recently
+    /// seen ones beyond the capacity of the store.
+    fn evict(&mut self) {
+        self.peers
+            .retain(|_, record| record.failures <= MAX_FAILURES);
+
+        if self.peers.len() > MAX_STORED_PEERS {
+            let mut last_seen: Vec<_> =
+                self.peers.values().map(|record| record.last_seen).collect();
+            last_seen.sort_unstable_by(|a, b| b.cmp(a));
+            let oldest_kept = last_seen[MAX_STORED_PEERS - 1];
+            self.peers
+                .retain(|_, record| record.last_seen >= oldest_kept);
+
+            // Ties on the oldest kept time are broken by address
+            while self.peers.len() > MAX_STORED_PEERS {
+                let addr = self
+                    .peers
+                    .iter()
+                    .find(|(_, record)| record.last_seen == oldest_kept)
+                    .map(|(addr, _)| *addr)
+                    .expect("a peer at the oldest kept time");
+                self.peers.remove(&addr);
+            }
+        }
+    }
+}
+
+/// Returns the delay before redialing a peer after `failures` failed dials.
+fn backoff(failures: u32) -> Duration {
+    let exp = failures.saturating_sub(1).min(16);
+    BASE_BACKOFF.saturating_mul(1 << exp).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(i: u16) -> SocketAddr {
+        SocketAddr::from(([10, 0, 0, 1], 9000 + i))
+    }
+
+    #[test]
+    fn backoff_is_exponential_and_capped() {
+        assert_eq!(backoff(1), BASE_BACKOFF);
+        assert_eq!(backoff(2), BASE_BACKOFF * 2);
+        assert_eq!(backoff(3), BASE_BACKOFF * 4);
+        assert_eq!(backoff(MAX_FAILURES), MAX_BACKOFF);
+        assert_eq!(backoff(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn dials_back_off_until_seen() {
+        let mut store = PeerStore::default();
+        store.seen(addr(1), 100);
+        store.seen(addr(2), 200);
+        assert_eq!(store.dialable(1000, 10), vec![addr(2), addr(1)]);
+        assert_eq!(store.dialable(1000, 1), vec![addr(2)]);
+
+        store.dialed(addr(2), 1000);
+        assert_eq!(store.dialable(1000, 10), vec![addr(1)]);
+        assert_eq!(store.dialable(1030, 10), vec![addr(2), addr(1)]);
+
+        store.dialed(addr(2), 1030);
+        assert_eq!(store.get(&addr(2)).unwrap().next_dial, 1090);
+
+        // Seeing the peer resets its backoff
+        store.seen(addr(2), 1040);
+        assert_eq!(store.get(&addr(2)).unwrap().failures, 0);
+        assert_eq!(store.dialable(1040, 10), vec![addr(2), addr(1)]);
+    }
+
+    #[test]
+    fn failing_and_oldest_peers_are_forgotten() {
+        let mut store = PeerStore::default();
+        store.seen(addr(0), 0);
+        for _ in 0..=MAX_FAILURES {
+            store.dialed(addr(0), 0);
+        }
+        assert!(store.is_empty());
+
+        for i in 0..=MAX_STORED_PEERS as u16 {
+            store.seen(addr(i), i as u64);
+        }
+        assert_eq!(store.len(), MAX_STORED_PEERS);
+        assert!(store.get(&addr(0)).is_none());
+        assert!(store.get(&addr(MAX_STORED_PEERS as u16)).is_some());
+    }
+
+    #[test]
+    fn persists_across_restarts() {
+        let dir = tempdir::TempDir::new("peer_store").unwrap();
+        let path = dir.path().join("peers.json");
+        assert!(PeerStore::load(&path).is_empty());
+
+        let mut store = PeerStore::default();
+        store.seen(addr(1), 100);
+        store.dialed(addr(2), 100);
+        store.save(&path).unwrap();
+
+        let loaded = PeerStore::load(&path);
+        assert_eq!(loaded.get(&addr(1)), store.get(&addr(1)));
+        assert_eq!(loaded.get(&addr(2)), store.get(&addr(2)));
+
+        // A corrupted store is discarded
+        fs::write(&path, b"{").unwrap();
+        assert!(PeerStore::load(&path).is_empty());
+    }
+}
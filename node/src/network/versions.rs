@@ -0,0 +1,179 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+
+use tracing::warn;
+
+use super::frame::{Hello, PROTOCOL_VERSION};
+
+/// Maximum number of peers whose protocol version is tracked
+const MAX_TRACKED_PEERS: usize = 10_000;
+
+#[derive(Debug, Default)]
+struct PeerVersion {
+    /// Protocol version of the last frame received
+    version: u32,
+    /// Hello frame of the peer, once received
+    hello: Option<Hello>,
+    /// Whether our hello frame was sent to the peer
+    greeted: bool,
+}
+
+/// Protocol versions of the frames received from each peer, and of the ones
+/// they announced in their hello frame.
+///
+/// Broadcast frames are relayed verbatim, so their version is the one of the
+/// node that originated them rather than the one of the relaying peer.
+#[derive(Default)]
+pub struct PeerVersions {
+    peers: HashMap<SocketAddr, PeerVersion>,
+    newest: u32,
+}
+
+impl PeerVersions {
+    /// Records the protocol version of a frame received from `src`.
+    ///
+    /// Returns true if our hello frame is to be sent to the peer, which
+    /// happens once per tracked peer.
+    ///
+    /// The first time a version newer than ours is seen, a warning is logged
+    /// as the node should be upgraded.
+    pub fn observe(&mut self, src: SocketAddr, version: u32) -> bool {
+        if version > PROTOCOL_VERSION && version > self.newest {
+            warn!(
+                event = "newer protocol version",
+                version,
+                ours = PROTOCOL_VERSION,
+                %src,
+                "peers run a newer protocol, an upgrade is required"
+            );
+        }
+        self.newest = self.newest.max(version);
+
+        match self.peer(src) {
+            Some(peer) => {
+                peer.version = version;
+                !std::mem::replace(&mut peer.greeted, true)
+            }
+            None => false,
+        }
+    }
+
+    /// Records the hello frame received from `src`.
+    ///
+    /// Returns true if our hello frame is to be sent back to the peer. A
+    /// warning is logged if the peer cannot decode our frames.
+    pub fn on_hello(&mut self, src: SocketAddr, hello: Hello) -> bool {
+        if !hello.accepts(PROTOCOL_VERSION) {
+            warn!(
+                event = "incompatible peer",
+                version = hello.version,
+                min_version = hello.min_version,
+                ours = PROTOCOL_VERSION,
+                %src,
+                "peer cannot decode our frames"
+            );
+        }
+        let greet = self.observe(src, hello.version);
+        if let Some(peer) = self.peer(src) {
+            peer.hello = Some(hello);
+        }
+        greet
+    }
+
+    /// Returns false if the peer announced it cannot decode our frames.
+    ///
+    /// Peers that did not say hello yet are assumed to be compatible.
+    pub fn accepts_ours(&self, addr: &SocketAddr) -> bool {
+        self.peers
+            .get(addr)
+            .and_then(|peer| peer.hello)
+            .map_or(true, |hello| hello.accepts(PROTOCOL_VERSION))
+    }
+
+    /// Returns the number of peers using each protocol version.
+    pub fn summary(&self) -> BTreeMap<u32, usize> {
+        let mut summary = BTreeMap::new();
+        for peer in self.peers.values() {
+            *summary.entry(peer.version).or_default() += 1;
+        }
+        summary
+    }
+
+    /// Returns the entry of a peer, if it is tracked or can be.
+    fn peer(&mut self, src: SocketAddr) -> Option<&mut PeerVersion> {
+        if self.peers.len() < MAX_TRACKED_PEERS || self.peers.contains_key(&src)
+        {
+            Some(self.peers.entry(src).or_default())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_by_version() {
+        let a: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let b: SocketAddr = "10.0.0.2:9000".parse().unwrap();
+
+        let mut versions = PeerVersions::default();
+        versions.observe(a, PROTOCOL_VERSION - 1);
+        versions.observe(b, PROTOCOL_VERSION);
+        // Upgraded peer
+        versions.observe(a, PROTOCOL_VERSION);
+        versions.observe(b, PROTOCOL_VERSION + 1);
+
+        let summary = versions.summary();
+        assert_eq!(summary.get(&PROTOCOL_VERSION), Some(&1));
+        assert_eq!(summary.get(&(PROTOCOL_VERSION + 1)), Some(&1));
+        assert_eq!(versions.newest, PROTOCOL_VERSION + 1);
+    }
+
+    #[test]
+    fn handshake() {
+        let a: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let b: SocketAddr = "10.0.0.2:9000".parse().unwrap();
+
+        let mut versions = PeerVersions::default();
+
+        // A peer is greeted on its first frame only
+        assert!(versions.observe(a, PROTOCOL_VERSION));
+        assert!(!versions.observe(a, PROTOCOL_VERSION));
+        assert!(!versions.on_hello(a, Hello::ours()));
+        assert!(versions.accepts_ours(&a));
+
+        // A peer saying hello first is greeted back
+        let newer = Hello {
+            version: PROTOCOL_VERSION + 2,
+            min_version: PROTOCOL_VERSION + 1,
+        };
+        assert!(versions.accepts_ours(&b));
+        assert!(versions.on_hello(b, newer));
+        assert!(!versions.accepts_ours(&b));
+        assert_eq!(versions.summary().get(&newer.version), Some(&1));
+    }
+
+    #[test]
+    fn tracked_peers_are_bounded() {
+        let mut versions = PeerVersions::default();
+        for port in 0..MAX_TRACKED_PEERS as u16 {
+            let addr = SocketAddr::from(([10, 0, 0, 1], port));
+            assert!(versions.observe(addr, PROTOCOL_VERSION));
+        }
+
+        // Untracked peers are not greeted, not to greet them on every frame
+        let addr = SocketAddr::from(([10, 0, 0, 2], 9000));
+        assert!(!versions.observe(addr, PROTOCOL_VERSION));
+        assert!(!versions.on_hello(addr, Hello::ours()));
+        assert_eq!(versions.peers.len(), MAX_TRACKED_PEERS);
+    }
+}
@@ -9,7 +9,7 @@ use dusk_consensus::{
     operations::{CallParams, VerificationOutput},
     user::{provisioners::Provisioners, stake::Stake},
 };
-use node_data::ledger::{Block, SpentTransaction, Transaction};
+use node_data::ledger::{Block, ContractEvent, SpentTransaction, Transaction};
 
 #[derive(Default)]
 pub struct Config {}
@@ -30,15 +30,52 @@ pub trait VMExecution: Send + Sync + 'static {
         blk: &Block,
     ) -> anyhow::Result<VerificationOutput>;
 
+    /// Accepts a block, returning its spent transactions, the events it
+    /// emitted outside of them and its verification output.
     fn accept(
         &self,
         blk: &Block,
-    ) -> anyhow::Result<(Vec<SpentTransaction>, VerificationOutput)>;
+    ) -> anyhow::Result<(
+        Vec<SpentTransaction>,
+        Vec<ContractEvent>,
+        VerificationOutput,
+    )>;
 
     fn finalize(
         &self,
         blk: &Block,
-    ) -> anyhow::Result<(Vec<SpentTransaction>, VerificationOutput)>;
+    ) -> anyhow::Result<(
+        Vec<SpentTransaction>,
+        Vec<ContractEvent>,
+        VerificationOutput,
+    )>;
+
+    /// Finalizes a block downloaded while syncing up. The resulting state
+    /// may be committed along with the following blocks, once
+    /// [`Self::commit_deferred`] is called or any state transition is
+    /// requested. Stakes are read on top of the pending blocks, without
+    /// committing them.
+    fn finalize_deferred(
+        &self,
+        blk: &Block,
+    ) -> anyhow::Result<(
+        Vec<SpentTransaction>,
+        Vec<ContractEvent>,
+        VerificationOutput,
+    )> {
+        self.finalize(blk)
+    }
+
+    /// Commits the state of the blocks finalized with
+    /// [`Self::finalize_deferred`], if not committed yet.
+    fn commit_deferred(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Discards the state of the blocks finalized with
+    /// [`Self::finalize_deferred`] and not committed yet, going back to the
+    /// last committed state.
+    fn discard_deferred(&self) {}
 
     fn preverify(&self, tx: &Transaction) -> anyhow::Result<()>;
 
@@ -56,6 +56,15 @@ pub fn verify_bls(
     host_query(Query::VERIFY_BLS, (msg, pk, sig))
 }
 
+/// Get the ID of the network the current block belongs to.
+///
+/// Returns 0 outside of the execution of a block, e.g. in a query.
+#[cfg(feature = "abi")]
+pub fn chain_id() -> u8 {
+    use crate::Metadata;
+    meta_data(Metadata::CHAIN_ID).unwrap_or_default()
+}
+
 /// Get the current block height.
 #[cfg(feature = "abi")]
 pub fn block_height() -> u64 {
@@ -63,6 +72,46 @@ pub fn block_height() -> u64 {
     meta_data(Metadata::BLOCK_HEIGHT).unwrap()
 }
 
+/// Get the timestamp of the current block, in seconds since the unix epoch.
+///
+/// Returns 0 outside of the execution of a block, e.g. in a query.
+#[cfg(feature = "abi")]
+pub fn block_timestamp() -> u64 {
+    use crate::Metadata;
+    meta_data(Metadata::BLOCK_TIMESTAMP).unwrap_or_default()
+}
+
+/// Get the public key of the generator of the current block.
+///
+/// Returns `None` outside of the execution of a block, e.g. in a query.
+#[cfg(feature = "abi")]
+pub fn block_generator() -> Option<dusk_bls12_381_sign::PublicKey> {
+    use crate::Metadata;
+    meta_data(Metadata::BLOCK_GENERATOR)
+}
+
+/// Get the seed of the current block.
+///
+/// The seed is the BLS signature of the block generator over the seed of the
+/// previous block, checked by every node accepting the block. The generator
+/// knows it before building the block, hence knows the outcome of every
+/// transaction reading it, and can choose which of them to include. It is
+/// therefore not a fit source of randomness for a transaction relying on the
+/// seed of its own block.
+///
+/// Contracts needing randomness should commit to the outcome first, and read
+/// the seed of a later block to reveal it: e.g. take a bet in one transaction,
+/// recording the current height, and settle it in a transaction executed
+/// later, with the seed of the first block it is executed in. The generators
+/// of the blocks following the commitment may then only withhold them.
+///
+/// Returns `None` outside of the execution of a block, e.g. in a query.
+#[cfg(feature = "abi")]
+pub fn block_seed() -> Option<[u8; 48]> {
+    use crate::Metadata;
+    meta_data(Metadata::BLOCK_SEED)
+}
+
 /// Query a contract for the types of payment it accepts.
 #[cfg(feature = "abi")]
 pub fn payment_info(
@@ -42,6 +42,49 @@ pub fn new_session(
     )
 }
 
+/// Create a new session based on the given `vm`, to execute the block with
+/// the given height, timestamp, generator and seed on the network with the
+/// given `chain_id`. The vm *must* have been created using [`new_vm`] or
+/// [`new_ephemeral_vm`].
+///
+/// The block data is fixed for the whole session, and can be read by the
+/// contracts through `chain_id`, `block_height`, `block_timestamp`,
+/// `block_generator` and `block_seed`.
+pub fn new_block_session(
+    vm: &VM,
+    base: [u8; 32],
+    chain_id: u8,
+    block_height: u64,
+    block_timestamp: u64,
+    generator: &BlsPublicKey,
+    seed: [u8; 48],
+) -> Result<Session, Error> {
+    vm.session(
+        SessionData::builder()
+            .base(base)
+            .insert(Metadata::CHAIN_ID, chain_id)?
+            .insert(Metadata::BLOCK_HEIGHT, block_height)?
+            .insert(Metadata::BLOCK_TIMESTAMP, block_timestamp)?
+            .insert(Metadata::BLOCK_GENERATOR, *generator)?
+            .insert(Metadata::BLOCK_SEED, seed)?,
+    )
+}
+
+/// Set the block data of the given `session`, so that it can go on to
+/// execute the next block without being committed first.
+pub fn set_block_data(
+    session: &mut Session,
+    block_height: u64,
+    block_timestamp: u64,
+    generator: &BlsPublicKey,
+    seed: [u8; 48],
+) -> Result<(), Error> {
+    session.set_meta(Metadata::BLOCK_HEIGHT, block_height)?;
+    session.set_meta(Metadata::BLOCK_TIMESTAMP, block_timestamp)?;
+    session.set_meta(Metadata::BLOCK_GENERATOR, *generator)?;
+    session.set_meta(Metadata::BLOCK_SEED, seed)
+}
+
 /// Create a new genesis session based on the given `vm`. The vm *must* have
 /// been created using [`new_vm`] or [`new_ephemeral_vm`].
 pub fn new_genesis_session(vm: &VM) -> Session {
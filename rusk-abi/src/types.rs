@@ -26,7 +26,11 @@ impl Query {
 pub(crate) enum Metadata {}
 
 impl Metadata {
+    pub const CHAIN_ID: &'static str = "chain_id";
     pub const BLOCK_HEIGHT: &'static str = "block_height";
+    pub const BLOCK_TIMESTAMP: &'static str = "block_timestamp";
+    pub const BLOCK_GENERATOR: &'static str = "block_generator";
+    pub const BLOCK_SEED: &'static str = "block_seed";
 }
 
 /// Enum representing all possible payment configurations.
@@ -64,10 +64,26 @@ impl HostFnTest {
         rusk_abi::verify_bls(msg, pk, sig)
     }
 
+    pub fn chain_id(&self) -> u8 {
+        rusk_abi::chain_id()
+    }
+
     pub fn block_height(&self) -> u64 {
         rusk_abi::block_height()
     }
 
+    pub fn block_timestamp(&self) -> u64 {
+        rusk_abi::block_timestamp()
+    }
+
+    pub fn block_generator(&self) -> Option<BlsPublicKey> {
+        rusk_abi::block_generator()
+    }
+
+    pub fn block_seed(&self) -> Option<[u8; 48]> {
+        rusk_abi::block_seed()
+    }
+
     pub fn owner(&self) -> [u8; PublicSpendKey::SIZE] {
         rusk_abi::self_owner()
     }
@@ -104,11 +120,31 @@ unsafe fn verify_bls(arg_len: u32) -> u32 {
     })
 }
 
+#[no_mangle]
+unsafe fn chain_id(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.chain_id())
+}
+
 #[no_mangle]
 unsafe fn block_height(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |_: ()| STATE.block_height())
 }
 
+#[no_mangle]
+unsafe fn block_timestamp(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.block_timestamp())
+}
+
+#[no_mangle]
+unsafe fn block_generator(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.block_generator())
+}
+
+#[no_mangle]
+unsafe fn block_seed(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.block_seed())
+}
+
 #[no_mangle]
 unsafe fn contract_owner(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |_: ()| STATE.owner())
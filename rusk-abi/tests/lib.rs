@@ -322,6 +322,74 @@ fn block_height() {
     assert_eq!(height, HEIGHT);
 }
 
+#[test]
+fn block_data() {
+    const CHAIN_ID: u8 = 2;
+    const HEIGHT: u64 = 123;
+    const TIMESTAMP: u64 = 1_700_000_000;
+    const SEED: [u8; 48] = [7; 48];
+
+    let vm =
+        rusk_abi::new_ephemeral_vm().expect("Instantiating VM should succeed");
+    let (mut session, contract_id) = instantiate(&vm, HEIGHT);
+
+    // Outside of a block, no timestamp nor generator is available
+    let timestamp: u64 = session
+        .call(contract_id, "block_timestamp", &(), POINT_LIMIT)
+        .expect("Query should succeed")
+        .data;
+    assert_eq!(timestamp, 0);
+
+    let generator: Option<BlsPublicKey> = session
+        .call(contract_id, "block_generator", &(), POINT_LIMIT)
+        .expect("Query should succeed")
+        .data;
+    assert_eq!(generator, None);
+
+    let seed: Option<[u8; 48]> = session
+        .call(contract_id, "block_seed", &(), POINT_LIMIT)
+        .expect("Query should succeed")
+        .data;
+    assert_eq!(seed, None);
+
+    let base = session.commit().expect("Committing should succeed");
+    let pk = BlsPublicKey::from(&BlsSecretKey::random(&mut OsRng));
+    let mut session = rusk_abi::new_block_session(
+        &vm, base, CHAIN_ID, HEIGHT, TIMESTAMP, &pk, SEED,
+    )
+    .expect("Instantiating new session should succeed");
+
+    let chain_id: u8 = session
+        .call(contract_id, "chain_id", &(), POINT_LIMIT)
+        .expect("Query should succeed")
+        .data;
+    assert_eq!(chain_id, CHAIN_ID);
+
+    let height: u64 = session
+        .call(contract_id, "block_height", &(), POINT_LIMIT)
+        .expect("Query should succeed")
+        .data;
+    assert_eq!(height, HEIGHT);
+
+    let timestamp: u64 = session
+        .call(contract_id, "block_timestamp", &(), POINT_LIMIT)
+        .expect("Query should succeed")
+        .data;
+    assert_eq!(timestamp, TIMESTAMP);
+
+    let generator: Option<BlsPublicKey> = session
+        .call(contract_id, "block_generator", &(), POINT_LIMIT)
+        .expect("Query should succeed")
+        .data;
+    assert_eq!(generator, Some(pk));
+
+    let seed: Option<[u8; 48]> = session
+        .call(contract_id, "block_seed", &(), POINT_LIMIT)
+        .expect("Query should succeed")
+        .data;
+    assert_eq!(seed, Some(SEED));
+}
+
 fn get_owner() -> &'static PublicSpendKey {
     static OWNER: OnceLock<PublicSpendKey> = OnceLock::new();
     OWNER.get_or_init(|| {
@@ -0,0 +1,370 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::time::Duration;
+
+use bytecheck::CheckBytes;
+use dusk_bls12_381::BlsScalar;
+use dusk_bls12_381_sign::PublicKey as BlsPublicKey;
+use dusk_bytes::Serializable;
+use dusk_pki::ViewKey;
+use node_data::error::{Classify, ErrorKind};
+use phoenix_core::transaction::{StakeData, TRANSFER_TREE_DEPTH};
+use phoenix_core::{Note, Transaction};
+use poseidon_merkle::Opening as PoseidonOpening;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use rkyv::ser::serializers::AllocSerializer;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{Archive, Deserialize, Infallible, Serialize};
+use rusk_abi::{ContractId, STAKE_CONTRACT};
+use stake_contract_types::STAKE_TREE_DEPTH;
+
+use crate::{Error, Result};
+
+pub type NoteOpening = PoseidonOpening<(), TRANSFER_TREE_DEPTH, 4>;
+pub type StakeOpening = PoseidonOpening<(), STAKE_TREE_DEPTH, 4>;
+
+const TARGET_CONTRACT: u8 = 0x01;
+const TARGET_HOST: u8 = 0x02;
+
+const CONTENT_TYPE_BINARY: &str = "application/octet-stream";
+const RUSK_ERROR_KIND_HEADER: &str = "Rusk-Error-Kind";
+const RUSK_STATE_ROOT_HEADER: &str = "Rusk-State-Root";
+
+/// Times a request failing with a transient error is retried by default
+const DEFAULT_RETRIES: u32 = 3;
+/// Delay before the first retry, doubled at each subsequent one
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(500);
+/// Upper bound of the delay between two retries
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time an unused connection is kept open to be reused
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const TCP_KEEPALIVE: Duration = Duration::from_secs(30);
+
+/// A provisioner, as listed by [`RuskClient::provisioners`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct Provisioner {
+    /// Base58 encoded public key
+    pub key: String,
+    pub amount: u64,
+    pub eligibility: u64,
+    pub reward: u64,
+}
+
+/// Client of the HTTP API of a Rusk node.
+///
+/// Cloning a client is cheap, and the clones share their connections.
+#[derive(Debug, Clone)]
+pub struct RuskClient {
+    http: reqwest::Client,
+    url: String,
+    state_root: Option<[u8; 32]>,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl RuskClient {
+    /// Creates a client of the node listening at `url`, e.g.
+    /// `http://127.0.0.1:8080`.
+    pub fn new(url: impl Into<String>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .tcp_keepalive(TCP_KEEPALIVE)
+            .build()?;
+
+        let url = url.into().trim_end_matches('/').to_string();
+
+        Ok(Self {
+            http,
+            url,
+            state_root: None,
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_BACKOFF,
+        })
+    }
+
+    /// Sets the times a request failing with a transient error is retried,
+    /// and the delay before the first retry.
+    pub fn with_retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Pins the queries of the client to the given state root, instead of
+    /// the tip of the node.
+    pub fn at_state_root(mut self, state_root: [u8; 32]) -> Self {
+        self.state_root = Some(state_root);
+        self
+    }
+
+    /// Gossips a transaction to the network.
+    pub async fn propagate_tx(&self, tx: &Transaction) -> Result<()> {
+        self.host("Chain", "propagate_tx", tx.to_var_bytes())
+            .await?;
+        Ok(())
+    }
+
+    /// Pre-verifies a transaction against the tip of the node.
+    pub async fn preverify(&self, tx: &Transaction) -> Result<()> {
+        self.host("rusk", "preverify", tx.to_var_bytes()).await?;
+        Ok(())
+    }
+
+    /// Returns the provisioners with their stakes.
+    pub async fn provisioners(&self) -> Result<Vec<Provisioner>> {
+        let data = self.host("rusk", "provisioners", vec![]).await?;
+        serde_json::from_slice(&data)
+            .map_err(|e| Error::InvalidResponse(e.to_string()))
+    }
+
+    /// Returns the stake of a provisioner, if any.
+    pub async fn stake(&self, pk: &BlsPublicKey) -> Result<Option<StakeData>> {
+        self.query(STAKE_CONTRACT, "get_stake", pk).await
+    }
+
+    /// Returns the stake of a provisioner with its opening in the tree
+    /// committing to the stakes, along with the root of the tree.
+    pub async fn stake_opening(
+        &self,
+        pk: &BlsPublicKey,
+    ) -> Result<(BlsScalar, Option<(StakeData, StakeOpening)>)> {
+        let data = pk.to_bytes().to_vec();
+        let data = self.host("rusk", "stake_opening", data).await?;
+        decode(&data)
+    }
+
+    /// Returns the openings of the notes at the given positions, in request
+    /// order, along with the root of the transfer tree.
+    pub async fn openings(
+        &self,
+        positions: &[u64],
+    ) -> Result<(BlsScalar, Vec<Option<NoteOpening>>)> {
+        let data = positions.iter().flat_map(|p| p.to_le_bytes()).collect();
+        let data = self.host("rusk", "openings", data).await?;
+        decode(&data)
+    }
+
+    /// Selects the notes of a view key to spend for the given amount, with
+    /// their openings, skipping the ones at the `exclude` positions.
+    pub async fn select_notes(
+        &self,
+        vk: &ViewKey,
+        amount: u64,
+        max_inputs: u32,
+        exclude: &[u64],
+    ) -> Result<Vec<(Note, NoteOpening)>> {
+        let mut data = vk.to_bytes().to_vec();
+        data.extend(amount.to_le_bytes());
+        data.extend(max_inputs.to_le_bytes());
+        data.extend(exclude.iter().flat_map(|p| p.to_le_bytes()));
+
+        let data = self.host("rusk", "select_notes", data).await?;
+        decode(&data)
+    }
+
+    /// Queries a contract, with the arguments and result serialized with
+    /// rkyv.
+    pub async fn query<A, R>(
+        &self,
+        contract: ContractId,
+        fn_name: &str,
+        fn_arg: &A,
+    ) -> Result<R>
+    where
+        A: Serialize<AllocSerializer<1024>>,
+        R: Archive,
+        R::Archived: Deserialize<R, Infallible>
+            + for<'b> CheckBytes<DefaultValidator<'b>>,
+    {
+        let arg = rkyv::to_bytes::<_, 1024>(fn_arg)
+            .map_err(|e| Error::InvalidRequest(e.to_string()))?;
+        let target = hex::encode(contract.as_bytes());
+
+        let data = self
+            .call(TARGET_CONTRACT, &target, fn_name, arg.into_vec())
+            .await?;
+        decode(&data)
+    }
+
+    async fn host(
+        &self,
+        target: &str,
+        topic: &str,
+        data: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        self.call(TARGET_HOST, target, topic, data).await
+    }
+
+    /// Sends a request, retrying it as long as it fails with a transient
+    /// error and retries are left.
+    async fn call(
+        &self,
+        target_type: u8,
+        target: &str,
+        topic: &str,
+        data: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let body = encode_request(topic, &data);
+
+        let mut attempt = 0;
+        loop {
+            match self.send(target_type, target, body.clone()).await {
+                Err(e) if e.kind().is_retryable() && attempt < self.retries => {
+                    tokio::time::sleep(backoff(self.backoff, attempt)).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    async fn send(
+        &self,
+        target_type: u8,
+        target: &str,
+        body: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let url = format!("{}/{target_type:02}/{target}", self.url);
+
+        let mut request = self
+            .http
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE_BINARY)
+            .body(body);
+        if let Some(root) = &self.state_root {
+            request = request.header(RUSK_STATE_ROOT_HEADER, hex::encode(root));
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let kind = (!status.is_success())
+            .then(|| error_kind(status, response.headers()));
+        let data = response.bytes().await?.to_vec();
+
+        if let Some(kind) = kind {
+            let msg = String::from_utf8_lossy(&data).into_owned();
+            return Err(Error::Rejected(kind, msg));
+        }
+
+        Ok(data)
+    }
+}
+
+/// Encodes a binary request: the length of the topic (u32 LE), the topic and
+/// the request data.
+fn encode_request(topic: &str, data: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(4 + topic.len() + data.len());
+    body.extend((topic.len() as u32).to_le_bytes());
+    body.extend(topic.as_bytes());
+    body.extend(data);
+    body
+}
+
+/// Returns the kind of failure a node answered with, from the error kind
+/// header or, if missing, from the HTTP status.
+fn error_kind(status: StatusCode, headers: &HeaderMap) -> ErrorKind {
+    let kind = headers
+        .get(RUSK_ERROR_KIND_HEADER)
+        .and_then(|kind| kind.to_str().ok())
+        .and_then(|kind| serde_json::from_value(kind.into()).ok());
+
+    kind.unwrap_or(match status {
+        StatusCode::SERVICE_UNAVAILABLE
+        | StatusCode::TOO_MANY_REQUESTS
+        | StatusCode::GATEWAY_TIMEOUT => ErrorKind::Transient,
+        s if s.is_client_error() => ErrorKind::Permanent,
+        _ => ErrorKind::ConsensusCritical,
+    })
+}
+
+/// Returns the delay before the given retry.
+fn backoff(base: Duration, attempt: u32) -> Duration {
+    base.saturating_mul(1 << attempt.min(16)).min(MAX_BACKOFF)
+}
+
+fn decode<R>(data: &[u8]) -> Result<R>
+where
+    R: Archive,
+    R::Archived:
+        Deserialize<R, Infallible> + for<'b> CheckBytes<DefaultValidator<'b>>,
+{
+    let archived = rkyv::check_archived_root::<R>(data)
+        .map_err(|e| Error::InvalidResponse(e.to_string()))?;
+    let result = archived
+        .deserialize(&mut Infallible)
+        .expect("Infallible deserialization");
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn request_encoding() {
+        let body = encode_request("preverify", &[1, 2, 3]);
+
+        assert_eq!(&body[..4], &9u32.to_le_bytes());
+        assert_eq!(&body[4..13], b"preverify");
+        assert_eq!(&body[13..], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn error_kinds() {
+        let mut headers = HeaderMap::new();
+        let kind = |status, headers: &HeaderMap| error_kind(status, headers);
+
+        assert_eq!(
+            kind(StatusCode::SERVICE_UNAVAILABLE, &headers),
+            ErrorKind::Transient
+        );
+        assert_eq!(
+            kind(StatusCode::BAD_REQUEST, &headers),
+            ErrorKind::Permanent
+        );
+        assert_eq!(
+            kind(StatusCode::INTERNAL_SERVER_ERROR, &headers),
+            ErrorKind::ConsensusCritical
+        );
+
+        // The header takes precedence over the status
+        headers.insert(
+            RUSK_ERROR_KIND_HEADER,
+            HeaderValue::from_static("transient"),
+        );
+        assert_eq!(
+            kind(StatusCode::INTERNAL_SERVER_ERROR, &headers),
+            ErrorKind::Transient
+        );
+    }
+
+    #[test]
+    fn backoff_is_bounded() {
+        let base = Duration::from_millis(500);
+
+        assert_eq!(backoff(base, 0), base);
+        assert_eq!(backoff(base, 2), base * 4);
+        assert_eq!(backoff(base, 100), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn only_transient_errors_are_retried() {
+        let rejected = |kind| Error::Rejected(kind, String::new());
+
+        assert!(rejected(ErrorKind::Transient).kind().is_retryable());
+        assert!(!rejected(ErrorKind::Permanent).kind().is_retryable());
+        assert!(!Error::InvalidResponse(String::new()).kind().is_retryable());
+    }
+}
@@ -0,0 +1,54 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fmt;
+
+use node_data::error::{Classify, ErrorKind};
+
+#[derive(Debug)]
+pub enum Error {
+    /// The node could not be reached or did not answer in time
+    Http(reqwest::Error),
+    /// The node answered with an error (kind, message)
+    Rejected(ErrorKind, String),
+    /// The response of the node could not be decoded
+    InvalidResponse(String),
+    /// The request could not be encoded
+    InvalidRequest(String),
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Http(err)
+    }
+}
+
+impl Classify for Error {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Http(_) => ErrorKind::Transient,
+            Error::Rejected(kind, _) => *kind,
+            Error::InvalidResponse(_) | Error::InvalidRequest(_) => {
+                ErrorKind::Permanent
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Http(err) => write!(f, "HTTP Error: {err}"),
+            Error::Rejected(kind, msg) => {
+                write!(f, "Request rejected ({kind}): {msg}")
+            }
+            Error::InvalidResponse(msg) => write!(f, "Invalid response: {msg}"),
+            Error::InvalidRequest(msg) => write!(f, "Invalid request: {msg}"),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Typed client of the Rusk HTTP API.
+//!
+//! Requests and responses are the types of the node, e.g. [`Transaction`],
+//! [`Note`] or [`StakeData`], rather than raw bytes. Connections are kept
+//! alive and reused across requests, and the requests failing with a
+//! transient error are retried.
+//!
+//! [`Transaction`]: phoenix_core::Transaction
+//! [`Note`]: phoenix_core::Note
+//! [`StakeData`]: phoenix_core::transaction::StakeData
+
+mod client;
+mod error;
+
+pub use client::{NoteOpening, Provisioner, RuskClient, StakeOpening};
+pub use error::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
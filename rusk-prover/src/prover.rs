@@ -42,8 +42,18 @@ pub use wfct::WFCT_INPUT_LEN;
 /// Arity of the transfer tree.
 pub const A: usize = 4;
 
-#[derive(Debug, Default)]
-pub struct LocalProver;
+/// Prover of the circuits of the network with the given chain ID.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LocalProver {
+    chain_id: u8,
+}
+
+impl LocalProver {
+    /// Creates a prover of transactions meant for the network `chain_id`.
+    pub const fn new(chain_id: u8) -> Self {
+        Self { chain_id }
+    }
+}
 
 impl crate::Prover for LocalProver {
     fn prove_execute(&self, circuit_inputs: &[u8]) -> ProverResult {
@@ -88,7 +98,7 @@ mod tests {
     fn test_prove_execute() {
         let utx_hex = include_str!("../tests/utx.hex");
         let utx_bytes = hex::decode(utx_hex).unwrap();
-        let prover = LocalProver {};
+        let prover = LocalProver::default();
         let proof = prover.prove_execute(&utx_bytes).unwrap();
         println!("{}", hex::encode(proof));
     }
@@ -29,6 +29,7 @@ pub static EXEC_4_2_PROVER: Lazy<PlonkProver> =
 fn fill_circuit<const I: usize>(
     circuit: &mut ExecuteCircuit<I, (), TRANSFER_TREE_DEPTH, 4>,
     utx: &UnprovenTransaction,
+    chain_id: u8,
 ) -> Result<(), ProverError> {
     for input in utx.inputs() {
         let cis = CircuitInputSignature::from(input.signature());
@@ -63,6 +64,7 @@ fn fill_circuit<const I: usize>(
     }
 
     circuit.set_tx_hash(utx.hash());
+    circuit.set_chain_id(chain_id);
 
     match utx.crossover() {
         Some((crossover, value, blinder)) => {
@@ -88,10 +90,10 @@ impl LocalProver {
         let rng = &mut StdRng::seed_from_u64(0xbeef);
 
         match utx.inputs().len() {
-            1 => local_prove_exec_1_2(&utx, rng),
-            2 => local_prove_exec_2_2(&utx, rng),
-            3 => local_prove_exec_3_2(&utx, rng),
-            4 => local_prove_exec_4_2(&utx, rng),
+            1 => local_prove_exec_1_2(&utx, self.chain_id, rng),
+            2 => local_prove_exec_2_2(&utx, self.chain_id, rng),
+            3 => local_prove_exec_3_2(&utx, self.chain_id, rng),
+            4 => local_prove_exec_4_2(&utx, self.chain_id, rng),
             _ => Err(ProverError::from(format!(
                 "Invalid I/O count: {}/{}",
                 utx.inputs().len(),
@@ -103,6 +105,7 @@ impl LocalProver {
 
 fn local_prove_exec_1_2<R>(
     utx: &UnprovenTransaction,
+    chain_id: u8,
     rng: &mut R,
 ) -> Result<Vec<u8>, ProverError>
 where
@@ -110,7 +113,7 @@ where
 {
     const I: usize = 1;
     let mut circuit = ExecuteCircuitOneTwo::new();
-    fill_circuit::<I>(&mut circuit, utx)?;
+    fill_circuit::<I>(&mut circuit, utx, chain_id)?;
 
     let (proof, _) = EXEC_1_2_PROVER.prove(rng, &circuit).map_err(|e| {
         ProverError::with_context("Failed proving the circuit", e)
@@ -120,6 +123,7 @@ where
 
 fn local_prove_exec_2_2<R>(
     utx: &UnprovenTransaction,
+    chain_id: u8,
     rng: &mut R,
 ) -> Result<Vec<u8>, ProverError>
 where
@@ -127,7 +131,7 @@ where
 {
     const I: usize = 2;
     let mut circuit = ExecuteCircuitTwoTwo::new();
-    fill_circuit::<I>(&mut circuit, utx)?;
+    fill_circuit::<I>(&mut circuit, utx, chain_id)?;
 
     let (proof, _) = EXEC_2_2_PROVER.prove(rng, &circuit).map_err(|e| {
         ProverError::with_context("Failed proving the circuit", e)
@@ -137,6 +141,7 @@ where
 
 fn local_prove_exec_3_2<R>(
     utx: &UnprovenTransaction,
+    chain_id: u8,
     rng: &mut R,
 ) -> Result<Vec<u8>, ProverError>
 where
@@ -144,7 +149,7 @@ where
 {
     const I: usize = 3;
     let mut circuit = ExecuteCircuitThreeTwo::new();
-    fill_circuit::<I>(&mut circuit, utx)?;
+    fill_circuit::<I>(&mut circuit, utx, chain_id)?;
 
     let (proof, _) = EXEC_3_2_PROVER.prove(rng, &circuit).map_err(|e| {
         ProverError::with_context("Failed proving the circuit", e)
@@ -154,6 +159,7 @@ where
 
 fn local_prove_exec_4_2<R>(
     utx: &UnprovenTransaction,
+    chain_id: u8,
     rng: &mut R,
 ) -> Result<Vec<u8>, ProverError>
 where
@@ -161,7 +167,7 @@ where
 {
     const I: usize = 4;
     let mut circuit = ExecuteCircuitFourTwo::new();
-    fill_circuit::<I>(&mut circuit, utx)?;
+    fill_circuit::<I>(&mut circuit, utx, chain_id)?;
 
     let (proof, _) = EXEC_4_2_PROVER.prove(rng, &circuit).map_err(|e| {
         ProverError::with_context("Failed proving the circuit", e)
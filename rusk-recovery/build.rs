@@ -0,0 +1,65 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+/// Buildfile for the recovery tools, exposing the hashes of the bytecode of
+/// the genesis contracts. The contracts themselves are compiled with pinned
+/// settings by `make wasm`, which must have run before building the state.
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// Genesis contracts as (name, target, wasm file name)
+const GENESIS_CONTRACTS: [(&str, &str, &str); 4] = [
+    (
+        "transfer",
+        "wasm64-unknown-unknown",
+        "transfer_contract.wasm",
+    ),
+    ("stake", "wasm32-unknown-unknown", "stake_contract.wasm"),
+    ("license", "wasm32-unknown-unknown", "license_contract.wasm"),
+    (
+        "governance",
+        "wasm32-unknown-unknown",
+        "governance_contract.wasm",
+    ),
+];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Ensure we run the build script again even if we change just the build.rs
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // The genesis contracts are only needed to build the state
+    if env::var_os("CARGO_FEATURE_STATE").is_none() {
+        return Ok(());
+    }
+
+    let target_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?)
+        .parent()
+        .ok_or("no workspace directory")?
+        .join("target");
+
+    for (name, target, file) in GENESIS_CONTRACTS {
+        let wasm = target_dir.join(target).join("release").join(file);
+        if !wasm.exists() {
+            return Err(format!(
+                "missing {}, run `make wasm` first",
+                wasm.display()
+            )
+            .into());
+        }
+
+        println!("cargo:rerun-if-changed={}", wasm.display());
+        let hash = blake3::hash(&fs::read(&wasm)?);
+        println!(
+            "cargo:rustc-env=GENESIS_{}_HASH={}",
+            name.to_uppercase(),
+            hash.to_hex()
+        );
+    }
+
+    Ok(())
+}
@@ -22,10 +22,15 @@ use std::path::Path;
 use tracing::info;
 use url::Url;
 
+pub use self::http::post as http_post;
+pub use genesis::{
+    GenesisContract, GenesisDeployment, GenesisMetadata, GENESIS_CONTRACTS,
+};
 pub use snapshot::{Balance, GenesisStake, Governance, Snapshot};
 use stake_contract_types::StakeData;
 use transfer_contract_types::Mint;
 
+mod genesis;
 mod http;
 mod snapshot;
 pub mod tar;
@@ -53,9 +58,7 @@ fn deploy_governance_contract(
     governance: &Governance,
 ) -> Result<(), Box<dyn Error>> {
     let contract_id = governance.contract();
-    let bytecode = include_bytes!(
-        "../../target/wasm32-unknown-unknown/release/governance_contract.wasm"
-    );
+    let bytecode = genesis::GOVERNANCE.bytecode;
 
     let theme = Theme::default();
     info!(
@@ -151,6 +154,9 @@ fn generate_stake_state(
             )
             .expect("stake to be inserted into the state");
     });
+    session
+        .call::<_, ()>(STAKE_CONTRACT, "update_root", &(), u64::MAX)
+        .expect("Stakes root to be updated after inserting the stakes");
 
     let stake_balance: u64 = snapshot.stakes().map(|s| s.amount).sum();
     if stake_balance > 0 {
@@ -179,17 +185,9 @@ fn generate_empty_state<P: AsRef<Path>>(
     let vm = rusk_abi::new_vm(state_dir)?;
     let mut session = rusk_abi::new_genesis_session(&vm);
 
-    let transfer_code = include_bytes!(
-        "../../target/wasm64-unknown-unknown/release/transfer_contract.wasm"
-    );
-
-    let stake_code = include_bytes!(
-        "../../target/wasm32-unknown-unknown/release/stake_contract.wasm"
-    );
-
-    let license_code = include_bytes!(
-        "../../target/wasm32-unknown-unknown/release/license_contract.wasm"
-    );
+    let transfer_code = genesis::TRANSFER.bytecode;
+    let stake_code = genesis::STAKE.bytecode;
+    let license_code = genesis::LICENSE.bytecode;
 
     info!("{} Genesis Transfer Contract", theme.action("Deploying"));
     session.deploy(
@@ -257,6 +255,19 @@ pub fn deploy<P: AsRef<Path>>(
         None => generate_empty_state(state_dir, snapshot),
     }?;
 
+    // A base state is expected to carry the metadata of its own contracts
+    let mut contracts = match snapshot.base_state() {
+        Some(_) => GenesisMetadata::read(state_dir).unwrap_or_default(),
+        None => {
+            let mut metadata = GenesisMetadata::default();
+            metadata.add(TRANSFER_CONTRACT, &genesis::TRANSFER);
+            metadata.add(STAKE_CONTRACT, &genesis::STAKE);
+            metadata.add(LICENSE_CONTRACT, &genesis::LICENSE);
+            metadata
+        }
+    }
+    .contracts;
+
     let mut session =
         rusk_abi::new_session(&vm, old_commit_id, GENESIS_BLOCK_HEIGHT)?;
 
@@ -271,6 +282,15 @@ pub fn deploy<P: AsRef<Path>>(
     let commit_id = session.commit()?;
     fs::write(state_id_path, commit_id)?;
 
+    let mut metadata = GenesisMetadata {
+        contracts,
+        ..GenesisMetadata::new(commit_id)
+    };
+    for governance in snapshot.governance_contracts() {
+        metadata.add(governance.contract(), &genesis::GOVERNANCE);
+    }
+    metadata.write(state_dir)?;
+
     if old_commit_id != commit_id {
         vm.delete_commit(old_commit_id)?;
     }
@@ -306,6 +326,43 @@ pub fn restore_state<P: AsRef<Path>>(
     Ok((vm, commit_id))
 }
 
+/// Checks that the genesis state in the given directory was built from the
+/// same contracts as the ones embedded in this build, and that the bytecode
+/// deployed in its current commit is the recorded one.
+///
+/// The state may have moved past genesis, so the check is made against
+/// whichever commit the state is currently at.
+pub fn verify_genesis<P: AsRef<Path>>(
+    state_dir: P,
+) -> Result<GenesisMetadata, Box<dyn Error>> {
+    let state_dir = state_dir.as_ref();
+
+    let (vm, commit_id) = restore_state(state_dir)?;
+    let metadata = GenesisMetadata::read(state_dir)?;
+
+    if !vm.commits().contains(&commit_id) {
+        return Err(format!(
+            "Commit {} is missing from the state",
+            hex::encode(commit_id)
+        )
+        .into());
+    }
+
+    let mut mismatches = metadata.mismatches();
+    mismatches.extend(
+        metadata.bytecode_mismatches(state_dir.join(hex::encode(commit_id))),
+    );
+    if !mismatches.is_empty() {
+        return Err(format!(
+            "Genesis doesn't match this build: {}",
+            mismatches.join(", ")
+        )
+        .into());
+    }
+
+    Ok(metadata)
+}
+
 /// Load a state file and save it into the rusk state directory.
 fn load_state<P: AsRef<Path>>(
     state_dir: P,
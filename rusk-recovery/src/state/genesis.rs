@@ -0,0 +1,264 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusk_abi::ContractId;
+use serde_derive::{Deserialize, Serialize};
+
+/// File, within the state directory, holding the genesis metadata
+const GENESIS_METADATA_FILE: &str = "genesis.toml";
+
+/// A contract deployed in the genesis state.
+pub struct GenesisContract {
+    pub name: &'static str,
+    pub bytecode: &'static [u8],
+    /// Hex encoded blake3 hash of the bytecode, computed at build time
+    pub hash: &'static str,
+}
+
+impl GenesisContract {
+    /// Returns true if the embedded bytecode matches the hash computed when
+    /// the contract was compiled.
+    pub fn is_intact(&self) -> bool {
+        blake3::hash(self.bytecode).to_hex().as_str() == self.hash
+    }
+}
+
+pub const TRANSFER: GenesisContract = GenesisContract {
+    name: "transfer",
+    bytecode: include_bytes!(
+        "../../../target/wasm64-unknown-unknown/release/transfer_contract.wasm"
+    ),
+    hash: env!("GENESIS_TRANSFER_HASH"),
+};
+
+pub const STAKE: GenesisContract = GenesisContract {
+    name: "stake",
+    bytecode: include_bytes!(
+        "../../../target/wasm32-unknown-unknown/release/stake_contract.wasm"
+    ),
+    hash: env!("GENESIS_STAKE_HASH"),
+};
+
+pub const LICENSE: GenesisContract = GenesisContract {
+    name: "license",
+    bytecode: include_bytes!(
+        "../../../target/wasm32-unknown-unknown/release/license_contract.wasm"
+    ),
+    hash: env!("GENESIS_LICENSE_HASH"),
+};
+
+pub const GOVERNANCE: GenesisContract = GenesisContract {
+    name: "governance",
+    bytecode: include_bytes!(
+        "../../../target/wasm32-unknown-unknown/release/governance_contract.wasm"
+    ),
+    hash: env!("GENESIS_GOVERNANCE_HASH"),
+};
+
+pub const GENESIS_CONTRACTS: [&GenesisContract; 4] =
+    [&TRANSFER, &STAKE, &LICENSE, &GOVERNANCE];
+
+/// Directory, within a commit of the state, holding the bytecode of each
+/// deployed contract in a file named after the hex encoded contract ID.
+const BYTECODE_DIR: &str = "bytecode";
+
+/// A genesis contract as recorded in the metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisDeployment {
+    pub name: String,
+    /// Hex encoded blake3 hash of the deployed bytecode
+    pub hash: String,
+}
+
+/// Metadata of a genesis commit, recording the contracts deployed in it and
+/// the hashes of their bytecode.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisMetadata {
+    /// Hex encoded genesis commit
+    pub commit: String,
+    /// Deployed contracts, by hex encoded contract ID
+    pub contracts: BTreeMap<String, GenesisDeployment>,
+}
+
+impl GenesisMetadata {
+    pub fn new(commit: [u8; 32]) -> Self {
+        Self {
+            commit: hex::encode(commit),
+            contracts: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, contract_id: ContractId, contract: &GenesisContract) {
+        self.contracts.insert(
+            hex::encode(contract_id),
+            GenesisDeployment {
+                name: contract.name.to_string(),
+                hash: contract.hash.to_string(),
+            },
+        );
+    }
+
+    pub fn path<P: AsRef<Path>>(state_dir: P) -> PathBuf {
+        state_dir.as_ref().join(GENESIS_METADATA_FILE)
+    }
+
+    pub fn read<P: AsRef<Path>>(state_dir: P) -> Result<Self, Box<dyn Error>> {
+        let path = Self::path(state_dir);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    pub fn write<P: AsRef<Path>>(
+        &self,
+        state_dir: P,
+    ) -> Result<(), Box<dyn Error>> {
+        fs::write(Self::path(state_dir), toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Returns the differences between the recorded contracts and the ones
+    /// embedded in this build.
+    pub fn mismatches(&self) -> Vec<String> {
+        let mut mismatches = vec![];
+
+        for contract in GENESIS_CONTRACTS {
+            if !contract.is_intact() {
+                mismatches.push(format!(
+                    "{} bytecode doesn't match its build hash",
+                    contract.name
+                ));
+            }
+        }
+
+        for (id, deployed) in &self.contracts {
+            match GENESIS_CONTRACTS.iter().find(|c| c.name == deployed.name) {
+                Some(c) if c.hash != deployed.hash => mismatches.push(format!(
+                    "{} at {id} has hash {} instead of the expected {}",
+                    deployed.name, deployed.hash, c.hash
+                )),
+                Some(_) => {}
+                None => mismatches.push(format!(
+                    "unknown genesis contract {}",
+                    deployed.name
+                )),
+            }
+        }
+
+        mismatches
+    }
+
+    /// Returns the recorded contracts whose bytecode, as stored in the given
+    /// commit directory of the state, differs from the recorded hash.
+    ///
+    /// Contracts can't be upgraded, so the bytecode deployed at genesis is
+    /// expected to be found unchanged in any later commit.
+    pub fn bytecode_mismatches<P: AsRef<Path>>(
+        &self,
+        commit_dir: P,
+    ) -> Vec<String> {
+        let bytecode_dir = commit_dir.as_ref().join(BYTECODE_DIR);
+
+        self.contracts
+            .iter()
+            .filter_map(|(id, deployed)| {
+                let path = bytecode_dir.join(id);
+                match fs::read(&path) {
+                    Ok(bytecode) => {
+                        let hash = blake3::hash(&bytecode).to_hex();
+                        (hash.as_str() != deployed.hash).then(|| {
+                            format!(
+                                "{} at {id} is deployed with hash {hash}",
+                                deployed.name
+                            )
+                        })
+                    }
+                    Err(e) => Some(format!(
+                        "cannot read {} bytecode at {}: {e}",
+                        deployed.name,
+                        path.display()
+                    )),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rusk_abi::{STAKE_CONTRACT, TRANSFER_CONTRACT};
+
+    #[test]
+    fn metadata_detects_mismatches() {
+        let mut metadata = GenesisMetadata::new([0u8; 32]);
+        metadata.add(TRANSFER_CONTRACT, &TRANSFER);
+        metadata.add(STAKE_CONTRACT, &STAKE);
+        assert!(metadata.mismatches().is_empty());
+
+        let encoded = toml::to_string(&metadata).unwrap();
+        assert_eq!(
+            toml::from_str::<GenesisMetadata>(&encoded).unwrap(),
+            metadata
+        );
+
+        let stake = metadata
+            .contracts
+            .get_mut(&hex::encode(STAKE_CONTRACT))
+            .unwrap();
+        stake.hash = hex::encode([0u8; 32]);
+        metadata.contracts.insert(
+            hex::encode([1u8; 32]),
+            GenesisDeployment {
+                name: "alice".to_string(),
+                hash: String::new(),
+            },
+        );
+        assert_eq!(metadata.mismatches().len(), 2);
+    }
+
+    #[test]
+    fn metadata_detects_deployed_bytecode_mismatches() {
+        let commit_dir = tempfile::tempdir().unwrap();
+        let bytecode_dir = commit_dir.path().join(BYTECODE_DIR);
+        fs::create_dir(&bytecode_dir).unwrap();
+
+        let mut metadata = GenesisMetadata::new([0u8; 32]);
+        metadata.add(TRANSFER_CONTRACT, &TRANSFER);
+        metadata.add(STAKE_CONTRACT, &STAKE);
+
+        fs::write(
+            bytecode_dir.join(hex::encode(TRANSFER_CONTRACT)),
+            TRANSFER.bytecode,
+        )
+        .unwrap();
+        fs::write(
+            bytecode_dir.join(hex::encode(STAKE_CONTRACT)),
+            STAKE.bytecode,
+        )
+        .unwrap();
+        assert!(metadata.bytecode_mismatches(&commit_dir).is_empty());
+
+        // A contract deployed with different bytecode is reported
+        fs::write(
+            bytecode_dir.join(hex::encode(STAKE_CONTRACT)),
+            LICENSE.bytecode,
+        )
+        .unwrap();
+        assert_eq!(metadata.bytecode_mismatches(&commit_dir).len(), 1);
+
+        // And so is a missing one
+        fs::remove_file(bytecode_dir.join(hex::encode(TRANSFER_CONTRACT)))
+            .unwrap();
+        assert_eq!(metadata.bytecode_mismatches(&commit_dir).len(), 2);
+    }
+}
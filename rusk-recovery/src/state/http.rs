@@ -38,3 +38,22 @@ where
 
     Err(format!("State download error: {response:?}").into())
 }
+
+/// Sends `body` to `uri` in a POST request, returning the response body.
+pub fn post<T>(uri: T, body: &[u8]) -> Result<Vec<u8>, Box<dyn Error>>
+where
+    T: AsRef<str>,
+{
+    let mut buffer = vec![];
+
+    let response = request::post(uri, body, &mut buffer)?;
+    if response.status_code().is_success() {
+        return Ok(buffer);
+    }
+
+    Err(format!(
+        "Request error: {response:?} {}",
+        String::from_utf8_lossy(&buffer)
+    )
+    .into())
+}
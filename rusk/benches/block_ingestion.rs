@@ -40,12 +40,13 @@ fn load_txs() -> Vec<Transaction> {
         txs.push(Transaction {
             version: 1,
             r#type: 0,
+            chain_id: 0,
             inner: tx,
         });
     }
 
     for tx in txs.iter() {
-        match rusk::verifier::verify_proof(&tx.inner) {
+        match rusk::verifier::verify_proof(&tx.inner, tx.chain_id) {
             Ok(true) => Ok(()),
             Ok(false) => Err(anyhow::anyhow!("Invalid proof")),
             Err(e) => Err(anyhow::anyhow!("Cannot verify the proof: {e}")),
@@ -101,8 +102,10 @@ pub fn accept_benchmark(c: &mut Criterion) {
 
                         rusk.accept_transactions(
                             BLOCK_HEIGHT,
+                            0,
                             BLOCK_GAS_LIMIT,
                             generator,
+                            [0; 48],
                             txs,
                             None,
                             &[],
@@ -6,6 +6,16 @@
 
 #[cfg(any(feature = "recovery-state", feature = "recovery-keys"))]
 mod command;
+#[cfg(all(
+    feature = "node",
+    any(feature = "recovery-state", feature = "recovery-keys")
+))]
+mod db;
+#[cfg(all(
+    feature = "node",
+    any(feature = "recovery-state", feature = "recovery-keys")
+))]
+mod keys;
 #[cfg(feature = "recovery-state")]
 mod state;
 
@@ -35,6 +35,29 @@ pub enum Command {
         #[clap(short, long, value_parser, num_args(1))]
         output: Option<super::PathBuf>,
     },
+
+    /// Checks that the genesis of the local state was built from the same
+    /// contracts as this binary.
+    #[cfg(feature = "recovery-state")]
+    VerifyGenesis {
+        /// State directory to check, defaults to the one in the profile path.
+        #[clap(short, long, value_parser)]
+        state: Option<super::PathBuf>,
+    },
+
+    /// Manages the encrypted consensus keys.
+    #[cfg(feature = "node")]
+    Keys {
+        #[clap(subcommand)]
+        command: super::keys::KeysCommand,
+    },
+
+    /// Inspects and compacts the node database. The node must be stopped.
+    #[cfg(feature = "node")]
+    Db {
+        #[clap(subcommand)]
+        command: super::db::DbCommand,
+    },
 }
 
 impl Command {
@@ -63,6 +86,14 @@ impl Command {
                 init,
                 output,
             } => super::state::recovery_state(init, force, output),
+            #[cfg(feature = "recovery-state")]
+            Self::VerifyGenesis { state } => {
+                super::state::verify_genesis(state)
+            }
+            #[cfg(feature = "node")]
+            Self::Keys { command } => command.run(),
+            #[cfg(feature = "node")]
+            Self::Db { command } => command.run(),
             #[cfg(feature = "recovery-keys")]
             Self::RecoveryKeys { keep } => {
                 rusk_recovery_tools::keys::exec(keep)
@@ -0,0 +1,493 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::path::PathBuf;
+
+use clap::Subcommand;
+use node::database::rocksdb::{Backend, ColumnFamilyStats};
+use node::database::DB;
+use rusk::audit::{self, AuditLog, Operation};
+use rusk_recovery_tools::Theme;
+use tracing::info;
+
+use crate::config::chain::ChainConfig;
+
+/// Maintenance of the node database.
+#[derive(PartialEq, Eq, Hash, Clone, Subcommand, Debug)]
+pub enum DbCommand {
+    /// Reports the disk usage of each column family, and the space a
+    /// compaction would reclaim.
+    Stats {
+        /// Database directory, defaults to the one of the default config
+        #[clap(short, long, value_parser)]
+        path: Option<PathBuf>,
+    },
+
+    /// Compacts the database, reclaiming the space of deleted data.
+    Compact {
+        /// Database directory, defaults to the one of the default config
+        #[clap(short, long, value_parser)]
+        path: Option<PathBuf>,
+    },
+
+    /// Rebuilds an empty database from the blocks of a peer, up to the
+    /// block leading to the finalized root of an intact state.
+    #[cfg(feature = "recovery-state")]
+    Rebuild {
+        /// HTTP address of the peer to fetch the blocks from
+        #[clap(long)]
+        peer: String,
+
+        /// Database directory, defaults to the one of the default config
+        #[clap(short, long, value_parser)]
+        path: Option<PathBuf>,
+
+        /// State directory, defaults to the one in the profile path
+        #[clap(short, long, value_parser)]
+        state: Option<PathBuf>,
+    },
+}
+
+impl DbCommand {
+    pub fn run(self) -> Result<(), Box<dyn std::error::Error>> {
+        let theme = Theme::default();
+
+        match self {
+            Self::Stats { path } => {
+                let db = open(path);
+                print_stats(&theme, &db.stats()?);
+            }
+            Self::Compact { path } => {
+                let db = open(path);
+                let before = total_size(&db.stats()?);
+
+                db.compact();
+                AuditLog::open(audit::default_path()?)?.record(
+                    Operation::Prune,
+                    &audit::cli_operator(),
+                    None,
+                    None,
+                )?;
+
+                let stats = db.stats()?;
+                print_stats(&theme, &stats);
+                info!(
+                    "{} {} bytes",
+                    theme.success("Reclaimed"),
+                    before.saturating_sub(total_size(&stats))
+                );
+            }
+            #[cfg(feature = "recovery-state")]
+            Self::Rebuild { peer, path, state } => {
+                rebuild::run(&theme, &peer, db_path(path), state)?
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "recovery-state")]
+mod rebuild {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use dusk_consensus::config::ConsensusParams;
+    use dusk_consensus::merkle::merkle_root;
+    use node::chain::verify_block_cert;
+    use node::database::rocksdb::{Backend, DB_FOLDER_NAME, MD_HASH_KEY};
+    use node::database::{Ledger, Metadata, DB};
+    use node::vm::VMExecution;
+    use node_data::ledger::{
+        self, Block, ContractEvent, Header, Label, SpentTransaction,
+    };
+    use node_data::message::payload::Vote;
+    use node_data::Serializable;
+    use rusk::chain::Migrations;
+    use rusk::Rusk;
+    use rusk_recovery_tools::state::http_post;
+    use rusk_recovery_tools::Theme;
+    use serde_json::json;
+    use tracing::info;
+
+    /// Number of blocks requested to the peer at once
+    const BATCH_BLOCKS: u64 = 1000;
+
+    /// Directory the database is rebuilt in, within the database directory
+    const REBUILD_DIR: &str = "rebuild";
+
+    type Error = Box<dyn std::error::Error>;
+
+    /// Receipts of a block: its spent transactions, and the events emitted
+    /// outside of them
+    type Receipts = (Vec<SpentTransaction>, Vec<ContractEvent>);
+
+    /// Stores the blocks of `peer` as final, from the genesis up to the one
+    /// whose state root is the root of the state in `state_dir`.
+    ///
+    /// Blocks are checked to be chained to each other and to match their
+    /// transactions. The chain is anchored by its last block, whose state
+    /// root must be the one of the state and whose certificate must be
+    /// signed by the provisioners of the state. Since every block commits to
+    /// the previous one, this authenticates all of them. The certificate of
+    /// a last block changing the provisioners may thus fail to verify, in
+    /// which case the state of another block is to be used.
+    ///
+    /// The receipts of the transactions are fetched from the peer as well,
+    /// and checked against the event hash of their block. Their gas spent
+    /// and errors are not committed to by the block, and are taken as
+    /// served.
+    ///
+    /// The database is rebuilt aside, replacing the one at `path` only once
+    /// the chain is anchored.
+    pub(super) fn run(
+        theme: &Theme,
+        peer: &str,
+        path: PathBuf,
+        state_dir: Option<PathBuf>,
+    ) -> Result<(), Error> {
+        let state_dir = match state_dir {
+            Some(dir) => dir,
+            None => rusk_profile::get_rusk_state_dir()?,
+        };
+
+        let db_dir = path.join(DB_FOLDER_NAME);
+        if db_dir.exists() {
+            let db = Backend::create_or_open(&path);
+            if db.view(|t| t.op_read(MD_HASH_KEY))?.is_some() {
+                return Err("The database is not empty".into());
+            }
+        }
+
+        let rebuild_dir = path.join(REBUILD_DIR);
+        if rebuild_dir.exists() {
+            fs::remove_dir_all(&rebuild_dir)?;
+        }
+
+        let rebuilt = rebuild(theme, peer, &rebuild_dir, &state_dir);
+        if let Err(err) = rebuilt {
+            fs::remove_dir_all(&rebuild_dir)?;
+            return Err(err);
+        }
+
+        if db_dir.exists() {
+            fs::remove_dir_all(&db_dir)?;
+        }
+        fs::rename(rebuild_dir.join(DB_FOLDER_NAME), &db_dir)?;
+        fs::remove_dir_all(&rebuild_dir)?;
+
+        Ok(())
+    }
+
+    fn rebuild(
+        theme: &Theme,
+        peer: &str,
+        path: &Path,
+        state_dir: &Path,
+    ) -> Result<(), Error> {
+        let db = Backend::create_or_open(path);
+        let rusk = Rusk::new(
+            state_dir,
+            0,
+            None,
+            ConsensusParams::for_chain(0).host_gas,
+            ConsensusParams::for_chain(0).gas_pricing,
+            1,
+            Migrations::default(),
+            None,
+        )?;
+        let state_root = rusk.state_root();
+        info!(
+            "{} blocks up to state root {}",
+            theme.action("Rebuilding"),
+            hex::encode(state_root)
+        );
+
+        let mut prev: Option<Header> = None;
+        loop {
+            let from = prev.as_ref().map_or(0, |h| h.height + 1);
+            let to = from + BATCH_BLOCKS - 1;
+            let blocks = fetch(peer, "blocks", from, to)?
+                .iter()
+                .map(|bytes| Block::read(&mut &bytes[..]))
+                .collect::<Result<Vec<_>, _>>()?;
+            if blocks.is_empty() {
+                return Err(format!(
+                    "No block of the peer leads to state root {}",
+                    hex::encode(state_root)
+                )
+                .into());
+            }
+            let receipts = fetch(peer, "receipts", from, to)?
+                .iter()
+                .map(|bytes| read_receipts(&mut &bytes[..]))
+                .collect::<Result<Vec<_>, _>>()?;
+            if receipts.len() < blocks.len() {
+                return Err("Missing receipts".into());
+            }
+
+            let reached = db.update(|t| {
+                for (blk, (txs, events)) in blocks.iter().zip(receipts) {
+                    verify(blk, prev.as_ref())?;
+                    verify_receipts(blk, &txs, &events)?;
+
+                    let header = blk.header();
+                    if header.state_hash == state_root {
+                        verify_anchor(&rusk, header, prev.as_ref())?;
+                    }
+
+                    t.store_block(header, &txs, Label::Final)?;
+                    t.store_block_events(header.height, &events)?;
+                    prev = Some(header.clone());
+
+                    if header.state_hash == state_root {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            })?;
+
+            let height = prev.as_ref().map_or(0, |h| h.height);
+            if reached {
+                info!("{} {height} blocks", theme.success("Rebuilt"));
+                return Ok(());
+            }
+            info!("{} block {height}", theme.info("Stored"));
+        }
+    }
+
+    /// Ensures a block follows `prev` and its hash and transactions root
+    /// match its content.
+    fn verify(blk: &Block, prev: Option<&Header>) -> anyhow::Result<()> {
+        let header = blk.header();
+
+        let (height, prev_hash) =
+            prev.map_or((0, [0u8; 32]), |p| (p.height + 1, p.hash));
+        if header.height != height {
+            anyhow::bail!("Expected block {height}, got {}", header.height);
+        }
+        if height > 0 && header.prev_block_hash != prev_hash {
+            anyhow::bail!("Block {height} does not follow the previous one");
+        }
+
+        let mut hashable = header.clone();
+        hashable.hash = [0u8; 32];
+        let computed = Block::new(hashable, vec![])?.header().hash;
+        if computed != header.hash {
+            anyhow::bail!("Block {height} has an invalid hash");
+        }
+
+        let tx_hashes: Vec<_> = blk.txs().iter().map(|tx| tx.hash()).collect();
+        if merkle_root(&tx_hashes[..]) != header.txroot {
+            anyhow::bail!("Block {height} has an invalid transactions root");
+        }
+
+        Ok(())
+    }
+
+    /// Ensures the receipts of a block are the ones of its transactions, in
+    /// order, and that the events they hold make up its event hash.
+    fn verify_receipts(
+        blk: &Block,
+        txs: &[SpentTransaction],
+        events: &[ContractEvent],
+    ) -> anyhow::Result<()> {
+        let header = blk.header();
+        let height = header.height;
+
+        if txs.len() != blk.txs().len()
+            || txs.iter().zip(blk.txs()).any(|(spent, tx)| {
+                spent.inner.hash() != tx.hash() || spent.block_height != height
+            })
+        {
+            anyhow::bail!("Block {height} has receipts of other transactions");
+        }
+
+        let all_events = txs.iter().flat_map(|tx| &tx.events).chain(events);
+        if ledger::event_hash(all_events) != header.event_hash {
+            anyhow::bail!("Block {height} has receipts of other events");
+        }
+
+        Ok(())
+    }
+
+    /// Ensures the certificate of the block leading to the state is signed
+    /// by the provisioners of the state.
+    fn verify_anchor(
+        rusk: &Rusk,
+        header: &Header,
+        prev: Option<&Header>,
+    ) -> anyhow::Result<()> {
+        // The genesis state is anchored by its root alone
+        let Some(prev) = prev else {
+            return Ok(());
+        };
+
+        let certified = matches!(
+            header.cert.result.vote(),
+            Vote::Valid(hash) if *hash == header.hash
+        );
+        if !certified {
+            anyhow::bail!("Block {} is not the certified one", header.height);
+        }
+
+        let provisioners = rusk.get_provisioners(header.state_hash)?;
+        let params = ConsensusParams::for_chain(header.chain_id);
+        tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(verify_block_cert(
+                prev.chain_id,
+                prev.hash,
+                prev.seed,
+                &provisioners,
+                &params,
+                header.height,
+                &header.cert,
+                header.iteration,
+            ))
+        })
+        .map_err(|e| {
+            anyhow::anyhow!("Block {} is not certified: {e}", header.height)
+        })?;
+
+        Ok(())
+    }
+
+    /// Reads the receipts of a block, as streamed by `Chain/receipts`.
+    fn read_receipts(r: &mut &[u8]) -> std::io::Result<Receipts> {
+        let txs_len = SpentTransaction::read_u32_le(r)?;
+        let txs = (0..txs_len)
+            .map(|_| SpentTransaction::read(r))
+            .collect::<Result<_, _>>()?;
+
+        let events_len = ContractEvent::read_u32_le(r)?;
+        let events = (0..events_len)
+            .map(|_| ContractEvent::read(r))
+            .collect::<Result<_, _>>()?;
+
+        Ok((txs, events))
+    }
+
+    /// Requests the items of the given `topic` from height `from` to `to` to
+    /// the peer, as a stream of items prefixed by their length.
+    fn fetch(
+        peer: &str,
+        topic: &str,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let mut range = from.to_le_bytes().to_vec();
+        range.extend(to.to_le_bytes());
+        let request = json!({
+            "topic": topic,
+            "data": hex::encode(range),
+        });
+
+        let uri = format!("{}/02/Chain", peer.trim_end_matches('/'));
+        let response = http_post(uri, request.to_string().as_bytes())?;
+        let bytes = hex::decode(response)?;
+
+        let mut items = vec![];
+        let mut reader = &bytes[..];
+        while !reader.is_empty() {
+            let len = Block::read_u32_le(&mut reader)? as usize;
+            if reader.len() < len {
+                return Err("Truncated stream".into());
+            }
+            let (item, rest) = reader.split_at(len);
+            items.push(item.to_vec());
+            reader = rest;
+        }
+        Ok(items)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn block(height: u64, prev: &Header) -> Block {
+            let header = Header {
+                height,
+                prev_block_hash: prev.hash,
+                txroot: merkle_root::<[u8; 32]>(&[]),
+                ..Default::default()
+            };
+            Block::new(header, vec![]).expect("block to be built")
+        }
+
+        #[test]
+        fn blocks_are_chained() {
+            let genesis = block(0, &Header::default());
+            verify(&genesis, None).expect("genesis to be valid");
+
+            let next = block(1, genesis.header());
+            verify(&next, Some(genesis.header())).expect("block to follow");
+
+            // Wrong height, or not following the previous block
+            assert!(verify(&next, None).is_err());
+            assert!(verify(&genesis, Some(genesis.header())).is_err());
+            let fork = Header {
+                hash: [9; 32],
+                ..Default::default()
+            };
+            let other = block(1, &fork);
+            assert!(verify(&other, Some(genesis.header())).is_err());
+
+            // Transactions root not matching the transactions
+            let mut header = next.header().clone();
+            header.txroot = [1; 32];
+            let tampered = Block::new(header, vec![]).unwrap();
+            assert!(verify(&tampered, Some(genesis.header())).is_err());
+        }
+
+        #[test]
+        fn receipts_match_the_event_hash() {
+            let event = |topic: &str| ContractEvent {
+                source: [1; 32],
+                topic: topic.into(),
+                data: vec![1, 2, 3],
+            };
+            let events = vec![event("reward"), event("slash")];
+
+            let mut header = block(0, &Header::default()).header().clone();
+            header.event_hash = ledger::event_hash(&events);
+            let blk = Block::new(header, vec![]).unwrap();
+
+            verify_receipts(&blk, &[], &events).expect("receipts to match");
+
+            // Events missing, reordered or altered
+            assert!(verify_receipts(&blk, &[], &events[..1]).is_err());
+            let reordered = vec![event("slash"), event("reward")];
+            assert!(verify_receipts(&blk, &[], &reordered).is_err());
+            assert!(verify_receipts(&blk, &[], &[event("other")]).is_err());
+        }
+    }
+}
+
+fn db_path(path: Option<PathBuf>) -> PathBuf {
+    path.unwrap_or_else(|| ChainConfig::default().db_path())
+}
+
+fn open(path: Option<PathBuf>) -> Backend {
+    Backend::create_or_open(db_path(path))
+}
+
+fn total_size(stats: &[ColumnFamilyStats]) -> u64 {
+    stats.iter().map(|s| s.sst_files_size).sum()
+}
+
+fn print_stats(theme: &Theme, stats: &[ColumnFamilyStats]) {
+    for s in stats {
+        info!(
+            "{} keys: ~{}, sst: {} bytes, live: ~{} bytes, reclaimable: ~{} bytes",
+            theme.info(s.name),
+            s.estimated_keys,
+            s.sst_files_size,
+            s.live_data_size,
+            s.reclaimable_size,
+        );
+    }
+    info!("{} {} bytes", theme.action("Total"), total_size(stats));
+}
@@ -0,0 +1,108 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::path::PathBuf;
+
+use clap::Subcommand;
+use dusk_bls12_381_sign::{PublicKey, SecretKey};
+use dusk_bytes::Serializable;
+use node_data::keystore::{self, Keystore};
+use rand::rngs::OsRng;
+use rusk_recovery_tools::Theme;
+use tracing::info;
+
+/// Management of the consensus keys. The passphrase is read from
+/// DUSK_CONSENSUS_KEYS_PASS, or from the file at DUSK_CONSENSUS_KEYS_PASS_FILE.
+#[derive(PartialEq, Eq, Hash, Clone, Subcommand, Debug)]
+pub enum KeysCommand {
+    /// Generates new consensus keys into an encrypted keystore file.
+    Generate {
+        /// Path of the keystore file to create
+        #[clap(short, long, value_parser)]
+        output: PathBuf,
+
+        /// Number of PBKDF2 iterations used to derive the encryption key
+        #[clap(long, default_value_t = keystore::DEFAULT_ITERATIONS)]
+        iterations: u32,
+    },
+
+    /// Imports a consensus keys file exported by the wallet into an encrypted
+    /// keystore file.
+    Import {
+        /// Path of the consensus keys file exported by the wallet
+        #[clap(short, long, value_parser)]
+        input: PathBuf,
+
+        /// Path of the keystore file to create
+        #[clap(short, long, value_parser)]
+        output: PathBuf,
+
+        /// Number of PBKDF2 iterations used to derive the encryption key
+        #[clap(long, default_value_t = keystore::DEFAULT_ITERATIONS)]
+        iterations: u32,
+    },
+
+    /// Exports the public key of a keystore, after checking the passphrase
+    /// decrypts it.
+    Export {
+        /// Path of the keystore file
+        #[clap(short, long, value_parser)]
+        keystore: PathBuf,
+    },
+}
+
+impl KeysCommand {
+    pub fn run(self) -> Result<(), Box<dyn std::error::Error>> {
+        let theme = Theme::default();
+        let pwd = keystore::passphrase()?;
+
+        match self {
+            Self::Generate { output, iterations } => {
+                let sk = SecretKey::random(&mut OsRng);
+                store(&theme, &sk, &pwd, iterations, output)
+            }
+            Self::Import {
+                input,
+                output,
+                iterations,
+            } => {
+                let (_, sk) = node_data::bls::read_from_file(input, &pwd)?;
+                store(&theme, &sk, &pwd, iterations, output)
+            }
+            Self::Export { keystore } => {
+                let (_, pk) = Keystore::read(keystore)?.decrypt(&pwd)?;
+                info!("{} {}", theme.action("Public key"), to_base58(&pk));
+                Ok(())
+            }
+        }
+    }
+}
+
+fn store(
+    theme: &Theme,
+    sk: &SecretKey,
+    pwd: &str,
+    iterations: u32,
+    output: PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Keystore::encrypt(&mut OsRng, sk, pwd, iterations)?.write(&output)?;
+
+    info!(
+        "{} keystore at {}",
+        theme.success("Stored"),
+        output.display()
+    );
+    info!(
+        "{} {}",
+        theme.action("Public key"),
+        to_base58(&PublicKey::from(sk))
+    );
+    Ok(())
+}
+
+fn to_base58(pk: &PublicKey) -> String {
+    bs58::encode(pk.to_bytes()).into_string()
+}
@@ -8,6 +8,8 @@ use super::*;
 
 use std::{env, fs, io};
 
+use rusk::audit::{self, AuditLog, Operation};
+use rusk_recovery_tools::state::verify_genesis as verify;
 use rusk_recovery_tools::state::{deploy, restore_state, tar};
 use rusk_recovery_tools::Theme;
 use tracing::info;
@@ -37,6 +39,11 @@ pub fn recovery_state(
         None => None,
     };
 
+    let previous_root = match output_file {
+        Some(_) => None,
+        None => state_root()?,
+    };
+
     if force {
         clean_state()?;
     }
@@ -65,6 +72,16 @@ pub fn recovery_state(
 
     let (_, commit_id) = deploy(&state_dir, &init)?;
 
+    // Exporting a snapshot leaves the node state untouched
+    if output_file.is_none() {
+        AuditLog::open(audit::default_path()?)?.record(
+            Operation::SnapshotImport,
+            &audit::cli_operator(),
+            previous_root,
+            Some(commit_id),
+        )?;
+    }
+
     info!("{} {}", theme.action("Final Root"), hex::encode(commit_id));
 
     info!(
@@ -91,6 +108,47 @@ pub fn recovery_state(
     Ok(())
 }
 
+pub fn verify_genesis(
+    state_dir: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let state_dir = match state_dir {
+        Some(dir) => dir,
+        None => rusk_profile::get_rusk_state_dir()?,
+    };
+
+    let theme = Theme::default();
+    info!(
+        "{} genesis at {}",
+        theme.action("Verifying"),
+        state_dir.display()
+    );
+
+    let metadata = verify(&state_dir)?;
+    for (id, deployed) in &metadata.contracts {
+        info!(
+            "{} {} at {id} {}",
+            theme.success("Matched"),
+            deployed.name,
+            deployed.hash
+        );
+    }
+    info!("{} {}", theme.success("Verified"), metadata.commit);
+
+    Ok(())
+}
+
+/// Returns the root of the existing state, if any.
+fn state_root() -> Result<Option<[u8; 32]>, io::Error> {
+    let state_dir = rusk_profile::get_rusk_state_dir()?;
+    let state_id_path = rusk_profile::to_rusk_state_id_path(state_dir);
+
+    match fs::read(state_id_path) {
+        Ok(id) => Ok(id.try_into().ok()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
 fn clean_state() -> Result<(), io::Error> {
     let state_path = rusk_profile::get_rusk_state_dir()?;
 
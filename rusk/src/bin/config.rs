@@ -10,6 +10,10 @@ pub mod chain;
 pub mod databroker;
 #[cfg(feature = "node")]
 pub mod kadcast;
+#[cfg(feature = "node")]
+pub mod mempool;
+#[cfg(feature = "node")]
+pub mod notifier;
 
 pub mod http;
 
@@ -26,6 +30,10 @@ use self::chain::ChainConfig;
 use self::databroker::DataBrokerConfig;
 #[cfg(feature = "node")]
 use self::kadcast::KadcastConfig;
+#[cfg(feature = "node")]
+use self::mempool::MempoolConfig;
+#[cfg(feature = "node")]
+use self::notifier::NotifierConfig;
 
 use self::http::HttpConfig;
 
@@ -47,6 +55,14 @@ pub(crate) struct Config {
     #[serde(default = "ChainConfig::default")]
     pub(crate) chain: ChainConfig,
 
+    #[cfg(feature = "node")]
+    #[serde(default = "MempoolConfig::default")]
+    pub(crate) mempool: MempoolConfig,
+
+    #[cfg(feature = "node")]
+    #[serde(default = "NotifierConfig::default")]
+    pub(crate) notifier: NotifierConfig,
+
     #[serde(default = "HttpConfig::default")]
     pub(crate) http: HttpConfig,
 }
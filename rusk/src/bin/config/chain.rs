@@ -6,16 +6,120 @@
 
 use std::{path::PathBuf, time::Duration};
 
+use dusk_consensus::config::ConsensusParams;
+use node::chain::anchor::{Anchors, Params as AnchorParams};
+use node::chain::checkpoint::{Checkpoint, Params as CheckpointParams};
+use node::chain::remote_signer::RemoteSignerConfig;
+use node_data::ledger::DEFAULT_CHAIN_ID;
+use rusk::chain::Migrations;
 use serde::{Deserialize, Serialize};
 
 use crate::args::Args;
 
+/// Default time after which a remote signing request is given up
+const DEFAULT_SIGNER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Default number of blocks below the tip kept out of the cold storage
+const DEFAULT_HOT_BLOCKS: u64 = 100_000;
+
+/// Default time between two moves of blocks to the cold storage
+const DEFAULT_COLD_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Default time between two checks of the free disk space
+const DEFAULT_DISK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default free space below which disk space is reclaimed
+const DEFAULT_MIN_FREE_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+/// Default number of blocks below the tip kept out of the cold storage when
+/// the database runs low on space
+const DEFAULT_LOW_SPACE_HOT_BLOCKS: u64 = 10_000;
+
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub(crate) struct ChainConfig {
     db_path: Option<PathBuf>,
     consensus_keys_path: Option<PathBuf>,
+    chain_id: Option<u8>,
     #[serde(with = "humantime_serde")]
     generation_timeout: Option<Duration>,
+    #[serde(default)]
+    checkpoint: CheckpointParams,
+    #[serde(default)]
+    anchors: AnchorParams,
+    remote_signer: Option<RemoteSignerParams>,
+    #[serde(default)]
+    consensus: ConsensusConfig,
+    /// Number of final blocks whose state is committed at once while syncing
+    /// up
+    sync_commit_interval: Option<u64>,
+    cold_storage: Option<ColdStorageParams>,
+    disk_watchdog: Option<DiskWatchdogParams>,
+}
+
+/// Local consensus settings, the committee sizes and quorum thresholds being
+/// given by the chain ID
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub(crate) struct ConsensusConfig {
+    #[serde(default)]
+    instant_finality: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct RemoteSignerParams {
+    endpoint: String,
+    #[serde(default, with = "humantime_serde")]
+    timeout: Option<Duration>,
+    ca_cert: Option<PathBuf>,
+}
+
+/// Location of the ledger data of the final blocks older than `hot_blocks`
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct ColdStorageParams {
+    pub(crate) path: PathBuf,
+    hot_blocks: Option<u64>,
+    #[serde(default, with = "humantime_serde")]
+    interval: Option<Duration>,
+}
+
+impl ColdStorageParams {
+    pub(crate) fn hot_blocks(&self) -> u64 {
+        self.hot_blocks.unwrap_or(DEFAULT_HOT_BLOCKS)
+    }
+
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval.unwrap_or(DEFAULT_COLD_INTERVAL)
+    }
+}
+
+/// Free space thresholds of the state and database volumes, below which disk
+/// space is reclaimed
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct DiskWatchdogParams {
+    #[serde(default, with = "humantime_serde")]
+    interval: Option<Duration>,
+    min_free_state_bytes: Option<u64>,
+    min_free_db_bytes: Option<u64>,
+    /// Blocks below the tip kept out of the cold storage while the database
+    /// is low on space
+    hot_blocks: Option<u64>,
+}
+
+impl DiskWatchdogParams {
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval.unwrap_or(DEFAULT_DISK_INTERVAL)
+    }
+
+    pub(crate) fn min_free_state_bytes(&self) -> u64 {
+        self.min_free_state_bytes.unwrap_or(DEFAULT_MIN_FREE_BYTES)
+    }
+
+    pub(crate) fn min_free_db_bytes(&self) -> u64 {
+        self.min_free_db_bytes.unwrap_or(DEFAULT_MIN_FREE_BYTES)
+    }
+
+    pub(crate) fn hot_blocks(&self) -> u64 {
+        self.hot_blocks.unwrap_or(DEFAULT_LOW_SPACE_HOT_BLOCKS)
+    }
 }
 
 impl ChainConfig {
@@ -55,7 +159,60 @@ impl ChainConfig {
             .to_string()
     }
 
+    pub(crate) fn chain_id(&self) -> u8 {
+        self.chain_id.unwrap_or(DEFAULT_CHAIN_ID)
+    }
+
     pub(crate) fn generation_timeout(&self) -> Option<Duration> {
         self.generation_timeout
     }
+
+    pub(crate) fn sync_commit_interval(&self) -> u64 {
+        self.sync_commit_interval.unwrap_or(1)
+    }
+
+    /// Returns the state migrations of the network.
+    pub(crate) fn migrations(&self) -> Migrations {
+        Migrations::for_chain(self.chain_id())
+    }
+
+    pub(crate) fn cold_storage(&self) -> Option<&ColdStorageParams> {
+        self.cold_storage.as_ref()
+    }
+
+    pub(crate) fn disk_watchdog(&self) -> Option<&DiskWatchdogParams> {
+        self.disk_watchdog.as_ref()
+    }
+
+    pub(crate) fn remote_signer(&self) -> Option<RemoteSignerConfig> {
+        self.remote_signer
+            .as_ref()
+            .map(|params| RemoteSignerConfig {
+                endpoint: params.endpoint.clone(),
+                timeout: params.timeout.unwrap_or(DEFAULT_SIGNER_TIMEOUT),
+                ca_cert: params.ca_cert.clone(),
+            })
+    }
+
+    pub(crate) fn checkpoint(&self) -> anyhow::Result<Option<Checkpoint>> {
+        self.checkpoint.clone().into_checkpoint()
+    }
+
+    pub(crate) fn anchors(&self) -> anyhow::Result<Option<Anchors>> {
+        self.anchors.clone().into_anchors()
+    }
+
+    pub(crate) fn consensus_params(&self) -> anyhow::Result<ConsensusParams> {
+        let c = &self.consensus;
+        let params = ConsensusParams {
+            instant_finality: c.instant_finality,
+            ..ConsensusParams::for_chain(self.chain_id())
+        };
+
+        params
+            .validate()
+            .map_err(|e| anyhow::anyhow!("invalid consensus params: {e}"))?;
+
+        Ok(params)
+    }
 }
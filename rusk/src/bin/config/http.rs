@@ -5,7 +5,10 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use std::path::PathBuf;
+#[cfg(feature = "node")]
+use std::time::Duration;
 
+use rusk::http::OperatorQuorum;
 use serde::{Deserialize, Serialize};
 
 use crate::args::Args;
@@ -17,6 +20,27 @@ pub struct HttpConfig {
     #[serde(default = "default_listen")]
     pub listen: bool,
     listen_address: Option<String>,
+    /// Path of a unix socket to listen on, in addition to `listen_address`
+    pub unix_socket: Option<PathBuf>,
+    /// Token granting access to the admin requests, disabled if not set
+    pub admin_token: Option<String>,
+    /// Operators authorizing the destructive admin requests, refused if not
+    /// set
+    admin_operators: Option<AdminOperators>,
+    /// Gas quotas of the contract queries, unlimited if not set
+    #[cfg(feature = "node")]
+    query_quota: Option<QueryQuotaParams>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct AdminOperators {
+    /// Base58 encoded BLS public keys of the operators
+    keys: Vec<String>,
+    /// Number of operators that must sign a destructive request
+    threshold: usize,
+    /// Identifier of the node the signatures are bound to, so that they
+    /// cannot be replayed on another node
+    node_id: String,
 }
 
 impl Default for HttpConfig {
@@ -24,12 +48,43 @@ impl Default for HttpConfig {
         Self {
             listen: default_listen(),
             listen_address: None,
+            unix_socket: None,
+            admin_token: None,
+            admin_operators: None,
+            #[cfg(feature = "node")]
+            query_quota: None,
             cert: None,
             key: None,
         }
     }
 }
 
+/// Gas limit of a single contract query, and gas a client can spend on its
+/// queries within `window`
+#[cfg(feature = "node")]
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct QueryQuotaParams {
+    query_gas: Option<u64>,
+    client_gas: Option<u64>,
+    #[serde(default, with = "humantime_serde")]
+    window: Option<Duration>,
+}
+
+#[cfg(feature = "node")]
+impl QueryQuotaParams {
+    fn quotas(&self) -> rusk::chain::QueryQuotas {
+        const DEFAULT_QUERY_GAS: u64 = 1_000_000_000;
+        const DEFAULT_CLIENT_GAS: u64 = 60_000_000_000;
+        const DEFAULT_QUOTA_WINDOW: Duration = Duration::from_secs(60);
+
+        rusk::chain::QueryQuotas::new(
+            self.query_gas.unwrap_or(DEFAULT_QUERY_GAS),
+            self.client_gas.unwrap_or(DEFAULT_CLIENT_GAS),
+            self.window.unwrap_or(DEFAULT_QUOTA_WINDOW),
+        )
+    }
+}
+
 const fn default_listen() -> bool {
     true
 }
@@ -41,6 +96,28 @@ impl HttpConfig {
             .unwrap_or("127.0.0.1:8080".into())
     }
 
+    pub fn admin_quorum(
+        &self,
+        chain_id: u8,
+    ) -> anyhow::Result<Option<OperatorQuorum>> {
+        self.admin_operators
+            .as_ref()
+            .map(|ops| {
+                OperatorQuorum::new(
+                    &ops.keys,
+                    ops.threshold,
+                    chain_id,
+                    ops.node_id.clone(),
+                )
+            })
+            .transpose()
+    }
+
+    #[cfg(feature = "node")]
+    pub fn query_quotas(&self) -> Option<rusk::chain::QueryQuotas> {
+        self.query_quota.as_ref().map(QueryQuotaParams::quotas)
+    }
+
     pub(crate) fn merge(&mut self, args: &Args) {
         // Overwrite config ws-listen-addr
         if let Some(http_listen_addr) = &args.http_listen_addr {
@@ -4,33 +4,191 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
 use kadcast::config::Config;
+use node::network::nat::Gateway;
 use serde::{Deserialize, Serialize};
 
 use crate::args::Args;
 
+/// Name of the peer store file in the database directory
+const DEFAULT_PEER_STORE_FILE: &str = "peers.json";
+
+/// Number of alive peers below which the stored peers are dialed
+const DEFAULT_MIN_PEERS: usize = 8;
+
+/// Interval of the peer store updates
+const DEFAULT_PEER_STORE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Lease of the port mapping on the NAT gateway
+const DEFAULT_NAT_LEASE: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Serialize, Deserialize, Clone, Default)]
-pub(crate) struct KadcastConfig(Config);
+pub(crate) struct KadcastConfig {
+    #[serde(flatten)]
+    config: Config,
+
+    /// Encrypt messages sent to specific peers over Noise sessions
+    /// authenticated by the consensus keys
+    #[serde(default)]
+    noise: bool,
+
+    /// Further addresses to listen on, e.g. to also serve IPv6 peers on a
+    /// dual-stack host
+    #[serde(default)]
+    additional_addresses: Vec<AdditionalAddress>,
+
+    /// Peers persisted across restarts, dialed when too few are alive
+    peer_store: Option<PeerStoreParams>,
+
+    /// Port mapping on the NAT gateway, the external address being
+    /// advertised to the peers
+    nat: Option<NatParams>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct AdditionalAddress {
+    public_address: String,
+    listen_address: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct PeerStoreParams {
+    path: Option<PathBuf>,
+    min_peers: Option<usize>,
+    #[serde(default, with = "humantime_serde")]
+    interval: Option<Duration>,
+}
+
+impl PeerStoreParams {
+    /// Returns the path of the store, defaulting to the database directory.
+    pub(crate) fn path(&self, db_path: &Path) -> PathBuf {
+        self.path
+            .clone()
+            .unwrap_or_else(|| db_path.join(DEFAULT_PEER_STORE_FILE))
+    }
+
+    pub(crate) fn min_peers(&self) -> usize {
+        self.min_peers.unwrap_or(DEFAULT_MIN_PEERS)
+    }
+
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval.unwrap_or(DEFAULT_PEER_STORE_INTERVAL)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum NatMethod {
+    Upnp,
+    Natpmp,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct NatParams {
+    method: NatMethod,
+    /// Address of the NAT-PMP gateway
+    gateway: Option<Ipv4Addr>,
+    #[serde(default, with = "humantime_serde")]
+    lease: Option<Duration>,
+}
+
+impl NatParams {
+    pub(crate) fn gateway(&self) -> anyhow::Result<Gateway> {
+        match (self.method, self.gateway) {
+            (NatMethod::Upnp, _) => Ok(Gateway::Upnp),
+            (NatMethod::Natpmp, Some(addr)) => Ok(Gateway::NatPmp(addr)),
+            (NatMethod::Natpmp, None) => {
+                anyhow::bail!("kadcast.nat.gateway is required by NAT-PMP")
+            }
+        }
+    }
+
+    pub(crate) fn lease(&self) -> Duration {
+        self.lease.unwrap_or(DEFAULT_NAT_LEASE)
+    }
+}
 
 impl From<KadcastConfig> for Config {
     fn from(conf: KadcastConfig) -> Self {
-        conf.0
+        conf.config
     }
 }
 
 impl KadcastConfig {
+    pub(crate) fn noise(&self) -> bool {
+        self.noise
+    }
+
+    pub(crate) fn peer_store(&self) -> Option<&PeerStoreParams> {
+        self.peer_store.as_ref()
+    }
+
+    pub(crate) fn nat(&self) -> Option<&NatParams> {
+        self.nat.as_ref()
+    }
+
+    /// Returns the UDP port the node listens on.
+    pub(crate) fn listen_port(&self) -> anyhow::Result<u16> {
+        let addr = self
+            .config
+            .listen_address
+            .as_ref()
+            .unwrap_or(&self.config.public_address);
+        Ok(addr.parse::<SocketAddr>()?.port())
+    }
+
+    /// Advertises `addr` to the peers in place of the configured public
+    /// address.
+    pub(crate) fn set_public_address(&mut self, addr: SocketAddr) {
+        // Without a listen address, the node listens on the public one
+        let public = std::mem::replace(
+            &mut self.config.public_address,
+            addr.to_string(),
+        );
+        self.config.listen_address.get_or_insert(public);
+    }
+
+    /// Bootstraps from `nodes` as well as the configured bootstrapping nodes.
+    pub(crate) fn add_bootstrapping_nodes<I>(&mut self, nodes: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        for node in nodes {
+            if !self.config.bootstrapping_nodes.contains(&node) {
+                self.config.bootstrapping_nodes.push(node);
+            }
+        }
+    }
+
+    /// Returns the network configuration of each additional address.
+    pub(crate) fn additional_configs(&self) -> Vec<Config> {
+        self.additional_addresses
+            .iter()
+            .map(|address| {
+                let mut config = self.config.clone();
+                config.public_address = address.public_address.clone();
+                config.listen_address = address.listen_address.clone();
+                config
+            })
+            .collect()
+    }
+
     pub(crate) fn merge(&mut self, arg: &Args) {
         if let Some(public_address) = &arg.kadcast_public_address {
-            self.0.public_address = public_address.into();
+            self.config.public_address = public_address.into();
         };
         if let Some(listen_address) = &arg.kadcast_listen_address {
-            self.0.listen_address = Some(listen_address.into());
+            self.config.listen_address = Some(listen_address.into());
         };
         if let Some(bootstrapping_nodes) = arg.kadcast_bootstrap.clone() {
-            self.0.bootstrapping_nodes = bootstrapping_nodes
+            self.config.bootstrapping_nodes = bootstrapping_nodes
         };
         if let Some(network_id) = arg.kadcast_network_id {
-            self.0.kadcast_id = Some(network_id)
+            self.config.kadcast_id = Some(network_id)
         };
     }
 }
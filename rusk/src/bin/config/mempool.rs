@@ -0,0 +1,66 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use node::mempool::admission::{
+    AdmissionPolicy, AllOf, ContractAllowlist, MaxBytesPerSender, MinGasPrice,
+};
+use serde::{Deserialize, Serialize};
+
+/// Default time window the bytes received from each peer are bounded within
+const DEFAULT_SENDER_WINDOW: Duration = Duration::from_secs(60);
+
+/// Local policy deciding which valid transactions enter the mempool
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub(crate) struct MempoolConfig {
+    min_gas_price: Option<u64>,
+    /// Hex encoded IDs of the only contracts transactions may call
+    allowed_contracts: Option<Vec<String>>,
+    max_bytes_per_sender: Option<usize>,
+    #[serde(default, with = "humantime_serde")]
+    sender_window: Option<Duration>,
+    /// Budget in bytes of the mempool, unbounded if not set
+    max_bytes: Option<usize>,
+}
+
+impl MempoolConfig {
+    pub(crate) fn max_bytes(&self) -> Option<usize> {
+        self.max_bytes
+    }
+
+    pub(crate) fn admission_policy(
+        &self,
+    ) -> anyhow::Result<Arc<dyn AdmissionPolicy>> {
+        let mut policies: Vec<Box<dyn AdmissionPolicy>> = vec![];
+
+        if let Some(min_gas_price) = self.min_gas_price {
+            policies.push(Box::new(MinGasPrice(min_gas_price)));
+        }
+
+        if let Some(contracts) = &self.allowed_contracts {
+            let contracts = contracts
+                .iter()
+                .map(|id| {
+                    let bytes = hex::decode(id)?;
+                    <[u8; 32]>::try_from(bytes).map_err(|_| {
+                        anyhow::anyhow!("invalid contract id {id}")
+                    })
+                })
+                .collect::<anyhow::Result<HashSet<_>>>()?;
+            policies.push(Box::new(ContractAllowlist(contracts)));
+        }
+
+        if let Some(max_bytes) = self.max_bytes_per_sender {
+            let window = self.sender_window.unwrap_or(DEFAULT_SENDER_WINDOW);
+            policies.push(Box::new(MaxBytesPerSender::new(max_bytes, window)));
+        }
+
+        Ok(Arc::new(AllOf(policies)))
+    }
+}
@@ -0,0 +1,23 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub(crate) struct NotifierConfig(rusk::notifier::Params);
+
+impl From<NotifierConfig> for rusk::notifier::Params {
+    fn from(conf: NotifierConfig) -> Self {
+        conf.0
+    }
+}
+
+impl NotifierConfig {
+    /// Returns true if any webhook is configured
+    pub(crate) fn enabled(&self) -> bool {
+        !self.0.webhooks.is_empty()
+    }
+}
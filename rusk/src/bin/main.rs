@@ -11,23 +11,32 @@ mod config;
 #[cfg(feature = "ephemeral")]
 mod ephemeral;
 
+#[cfg(feature = "node")]
+use std::sync::Arc;
+
 use clap::Parser;
 
 #[cfg(feature = "node")]
 use node::{
-    chain::ChainSrv,
-    database::{rocksdb, DB},
+    chain::{remote_signer::RemoteSigner, ChainSrv},
+    database::{cold::RocksColdStorage, rocksdb, DB},
     databroker::DataBrokerSrv,
     mempool::MempoolSrv,
-    network::Kadcast,
+    network::{nat, noise, peer_store::PeerStore, Kadcast},
     LongLivedService, Node,
 };
+use rusk::audit::AuditLog;
 #[cfg(feature = "node")]
 use rusk::chain::Rusk;
-use rusk::http::DataSources;
+#[cfg(feature = "node")]
+use rusk::disk::{Reclaim, Volume};
+use rusk::http::{Admin, DataSources};
 use rusk::Result;
 
+use tracing::subscriber::SetGlobalDefaultError;
+use tracing::Subscriber;
 use tracing_subscriber::filter::EnvFilter;
+use tracing_subscriber::registry::LookupSpan;
 
 use rusk::http::HttpServer;
 use tracing::info;
@@ -75,15 +84,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .flatten_event(true)
                 .finish();
 
-            tracing::subscriber::set_global_default(subscriber)?;
+            set_global_subscriber(subscriber)?;
         }
         "plain" => {
             let subscriber = subscriber.with_ansi(false).finish();
-            tracing::subscriber::set_global_default(subscriber)?;
+            set_global_subscriber(subscriber)?;
         }
         "coloured" => {
             let subscriber = subscriber.finish();
-            tracing::subscriber::set_global_default(subscriber)?;
+            set_global_subscriber(subscriber)?;
         }
         _ => unreachable!(),
     };
@@ -94,11 +103,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         None => None,
     };
 
+    let audit = AuditLog::open(rusk::audit::default_path()?)?;
+
     #[cfg(feature = "node")]
     let (rusk, node, mut service_list) = {
         let state_dir = rusk_profile::get_rusk_state_dir()?;
         info!("Using state from {state_dir:?}");
-        let rusk = Rusk::new(state_dir, config.chain.generation_timeout())?;
+        let rusk = Rusk::new(
+            state_dir.clone(),
+            config.chain.chain_id(),
+            config.chain.generation_timeout(),
+            config.chain.consensus_params()?.host_gas,
+            config.chain.consensus_params()?.gas_pricing,
+            config.chain.sync_commit_interval(),
+            config.chain.migrations(),
+            Some(audit.clone()),
+        )?;
 
         info!("Rusk VM loaded");
 
@@ -108,10 +128,27 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         type Services =
             dyn LongLivedService<Kadcast<255>, rocksdb::Backend, Rusk>;
 
+        let admission_policy = config.mempool.admission_policy()?;
+        let mempool_max_bytes = config.mempool.max_bytes();
+
+        let chain_srv = ChainSrv::new(
+            config.chain.consensus_keys_path(),
+            config.chain.checkpoint()?,
+            config.chain.chain_id(),
+            config.chain.remote_signer(),
+            config.chain.consensus_params()?,
+        )
+        .with_anchors(config.chain.anchors()?);
+        let chain_reverts = chain_srv.reverts();
+
         // Select list of services to enable
         let service_list: Vec<Box<Services>> = vec![
-            Box::<MempoolSrv>::default(),
-            Box::new(ChainSrv::new(config.chain.consensus_keys_path())),
+            Box::new(
+                MempoolSrv::new(config.chain.chain_id())
+                    .with_admission_policy(admission_policy.clone())
+                    .with_max_bytes(mempool_max_bytes),
+            ),
+            Box::new(chain_srv),
             Box::new(DataBrokerSrv::new(config.clone().databroker.into())),
         ];
 
@@ -124,10 +161,111 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         #[cfg(not(feature = "ephemeral"))]
         let db_path = config.chain.db_path();
 
-        let db = rocksdb::Backend::create_or_open(db_path);
-        let net = Kadcast::new(config.clone().kadcast.into())?;
+        // Known peers are dialed on startup along with the bootstrapping
+        // nodes
+        let mut kadcast = config.kadcast.clone();
+        let peer_store = config.kadcast.peer_store().map(|params| {
+            let path = params.path(&db_path);
+            let store = PeerStore::load(&path);
+            info!("Loaded {} peers from {path:?}", store.len());
+            let now = dusk_consensus::commons::get_current_timestamp();
+            kadcast.add_bootstrapping_nodes(
+                store
+                    .dialable(now, params.min_peers())
+                    .into_iter()
+                    .map(|addr| addr.to_string()),
+            );
+            (store, path, params.min_peers(), params.interval())
+        });
 
-        let node = rusk::chain::RuskNode(Node::new(net, db, rusk.clone()));
+        let mut db = rocksdb::Backend::create_or_open(&db_path);
+        db.migrate()?;
+        if let Some(cold) = config.chain.cold_storage() {
+            info!("Using cold storage in {:?}", cold.path);
+            let storage = RocksColdStorage::open(&cold.path)?;
+            db = db.with_cold_storage(Arc::new(storage));
+            tokio::spawn(move_to_cold(
+                db.clone(),
+                cold.hot_blocks(),
+                cold.interval(),
+            ));
+        }
+        if let Some(watchdog) = config.chain.disk_watchdog() {
+            // Without a cold storage, the database volume is only watched
+            let db_reclaim =
+                config.chain.cold_storage().map(|_| Reclaim::MoveToCold {
+                    hot_blocks: watchdog.hot_blocks(),
+                });
+            let volumes = vec![
+                Volume {
+                    name: "state",
+                    path: state_dir,
+                    min_free_bytes: watchdog.min_free_state_bytes(),
+                    reclaim: Some(Reclaim::PruneCommits),
+                },
+                Volume {
+                    name: "db",
+                    path: db_path,
+                    min_free_bytes: watchdog.min_free_db_bytes(),
+                    reclaim: db_reclaim,
+                },
+            ];
+            tokio::spawn(rusk::disk::watch(
+                volumes,
+                watchdog.interval(),
+                rusk.clone(),
+                db.clone(),
+            ));
+        }
+        if let Some(params) = config.kadcast.nat() {
+            let gateway = params.gateway()?;
+            let port = kadcast.listen_port()?;
+            match nat::map_port(gateway, port, params.lease()).await {
+                Ok(external) => {
+                    info!(
+                        "Mapped port {port} to {external} on the NAT gateway"
+                    );
+                    kadcast.set_public_address(external);
+                    tokio::spawn(nat::keep_mapped(
+                        gateway,
+                        port,
+                        params.lease(),
+                        external,
+                    ));
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Cannot map port {port} on the NAT gateway: {e}"
+                    )
+                }
+            }
+        }
+        let identity =
+            match (config.kadcast.noise(), config.chain.remote_signer()) {
+                (true, Some(signer)) => {
+                    let signer = RemoteSigner::connect(signer).await?;
+                    Some(noise::Identity::with_signer(&signer).await?)
+                }
+                (true, None) => Some(noise::Identity::load(
+                    config.chain.consensus_keys_path(),
+                )?),
+                (false, _) => None,
+            };
+        let net = Kadcast::new_multi_address(
+            kadcast.clone().into(),
+            kadcast.additional_configs(),
+            identity,
+        )?;
+        if let Some((store, path, min_peers, interval)) = peer_store {
+            net.spawn_peer_store(store, path, min_peers, interval);
+        }
+
+        let node = rusk::chain::RuskNode(
+            Node::new(net, db, rusk.clone()),
+            admission_policy,
+            mempool_max_bytes,
+            chain_reverts,
+        );
         (rusk, node, service_list)
     };
     let mut _ws_server = None;
@@ -138,9 +276,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             #[cfg(feature = "node")]
             node: node.clone(),
             #[cfg(feature = "node")]
-            rusk,
+            rusk: match config.http.query_quotas() {
+                Some(quotas) => rusk.reader().with_query_quotas(quotas),
+                None => rusk.reader(),
+            },
             #[cfg(feature = "prover")]
-            prover: rusk_prover::LocalProver,
+            prover: rusk_prover::LocalProver::new(config.chain.chain_id()),
+            admin: match config.http.admin_token.clone() {
+                Some(token) => {
+                    let admin = Admin::new(token, audit.clone());
+                    match config.http.admin_quorum(config.chain.chain_id())? {
+                        Some(quorum) => Some(admin.with_quorum(quorum)),
+                        None => Some(admin),
+                    }
+                }
+                None => None,
+            },
         };
 
         let listen_addr = config.http.listen_addr();
@@ -150,8 +301,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             _ => None,
         };
 
-        _ws_server =
-            Some(HttpServer::bind(handler, listen_addr, cert_and_key).await?);
+        _ws_server = Some(
+            HttpServer::bind_with_unix_socket(
+                handler,
+                listen_addr,
+                cert_and_key,
+                config.http.unix_socket,
+            )
+            .await?,
+        );
+    }
+
+    #[cfg(feature = "node")]
+    if config.notifier.enabled() {
+        info!("Configuring notifier");
+        let notifier = rusk::notifier::Notifier::new(
+            node.db(),
+            config.notifier.clone().into(),
+        )?;
+        tokio::spawn(notifier.run());
     }
 
     #[cfg(feature = "node")]
@@ -175,3 +343,48 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Periodically moves the ledger data of the final blocks more than
+/// `hot_blocks` below the tip to the cold storage of `db`.
+#[cfg(feature = "node")]
+async fn move_to_cold(
+    db: rocksdb::Backend,
+    hot_blocks: u64,
+    interval: std::time::Duration,
+) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+
+        let db = db.clone();
+        let res =
+            tokio::task::spawn_blocking(move || db.move_to_cold(hot_blocks))
+                .await;
+        match res {
+            Ok(Err(err)) => {
+                tracing::warn!("Cannot move blocks to cold storage: {err}")
+            }
+            Err(err) => tracing::error!("Cold storage task failed: {err}"),
+            Ok(Ok(_)) => {}
+        }
+    }
+}
+
+/// Sets `subscriber` as the global default, along with the layer serving the
+/// tokio console if the `console` feature is enabled.
+///
+/// The console only receives the runtime instrumentation if rusk is built
+/// with `RUSTFLAGS="--cfg tokio_unstable"` and the log filter lets the
+/// `tokio=trace,runtime=trace` spans through.
+fn set_global_subscriber<S>(subscriber: S) -> Result<(), SetGlobalDefaultError>
+where
+    S: Subscriber + Send + Sync + 'static + for<'a> LookupSpan<'a>,
+{
+    #[cfg(feature = "console")]
+    let subscriber = {
+        use tracing_subscriber::layer::SubscriberExt;
+        subscriber.with(console_subscriber::spawn())
+    };
+
+    tracing::subscriber::set_global_default(subscriber)
+}
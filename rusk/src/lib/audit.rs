@@ -0,0 +1,188 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Append-only log of the operations changing the node state outside of the
+//! processing of blocks.
+//!
+//! Every entry is a JSON line, flushed to disk before the operation is
+//! considered recorded.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+const AUDIT_LOG_FILE: &str = "audit.log";
+
+/// Operator of the operations performed by the node on its own
+pub const NODE_OPERATOR: &str = "node";
+/// Operator of the operations performed through the command line
+pub const CLI_OPERATOR: &str = "cli";
+/// Operator of the operations performed through the admin requests
+pub const ADMIN_OPERATOR: &str = "admin";
+
+/// Returns the identity of the system user running a command line operation.
+pub fn cli_operator() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".into());
+    format!("{CLI_OPERATOR}:{user}")
+}
+
+/// Returns the identity of the holder of the given admin token.
+///
+/// The token is identified by a fingerprint, not to write it in the log.
+pub fn admin_operator(token: &str) -> String {
+    let digest = Sha3_256::digest(token.as_bytes());
+    format!("{ADMIN_OPERATOR}:{}", hex::encode(&digest[..8]))
+}
+
+/// Returns the path of the audit log in the rusk profile directory.
+pub fn default_path() -> io::Result<PathBuf> {
+    Ok(rusk_profile::get_rusk_profile_dir()?.join(AUDIT_LOG_FILE))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// The state was reverted to a previous commit
+    Revert,
+    /// Deleted data was reclaimed from the database
+    Prune,
+    /// A state was built from a snapshot
+    SnapshotImport,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Seconds since the unix epoch
+    pub timestamp: u64,
+    pub operation: Operation,
+    pub operator: String,
+    /// Hex encoded state root before the operation
+    pub before: Option<String>,
+    /// Hex encoded state root after the operation
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+impl AuditLog {
+    /// Opens the audit log at the given path, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(Self {
+            path,
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    /// Appends an entry for the given operation.
+    pub fn record(
+        &self,
+        operation: Operation,
+        operator: &str,
+        before: Option<[u8; 32]>,
+        after: Option<[u8; 32]>,
+    ) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        let entry = Entry {
+            timestamp,
+            operation,
+            operator: operator.into(),
+            before: before.map(hex::encode),
+            after: after.map(hex::encode),
+        };
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock();
+        file.write_all(&line)?;
+        file.sync_data()
+    }
+
+    /// Returns up to `limit` entries, skipping the first `offset` ones.
+    pub fn entries(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> io::Result<Vec<Entry>> {
+        // Prevent reading a partially written entry
+        let _file = self.file.lock();
+
+        BufReader::new(File::open(&self.path)?)
+            .lines()
+            .skip(offset)
+            .take(limit)
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_appended() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(AUDIT_LOG_FILE);
+
+        let log = AuditLog::open(&path)?;
+        log.record(
+            Operation::Revert,
+            NODE_OPERATOR,
+            Some([1; 32]),
+            Some([0; 32]),
+        )?;
+        log.record(Operation::Prune, &cli_operator(), None, None)?;
+
+        // Reopening the log keeps the previous entries
+        let log = AuditLog::open(&path)?;
+        log.record(
+            Operation::SnapshotImport,
+            &admin_operator("token"),
+            None,
+            Some([2; 32]),
+        )?;
+
+        let entries = log.entries(0, usize::MAX)?;
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].operation, Operation::Revert);
+        assert_eq!(entries[0].before, Some(hex::encode([1; 32])));
+        assert!(entries[1].operator.starts_with("cli:"));
+        assert_eq!(entries[2].operator, admin_operator("token"));
+        assert_eq!(entries[2].after, Some(hex::encode([2; 32])));
+
+        let entries = log.entries(1, 1)?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].operation, Operation::Prune);
+
+        Ok(())
+    }
+
+    #[test]
+    fn operators_are_identified() {
+        assert_ne!(admin_operator("token"), admin_operator("other"));
+        assert!(!admin_operator("secret-token").contains("secret-token"));
+    }
+}
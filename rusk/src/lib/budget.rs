@@ -0,0 +1,372 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Budgets of the heavy operations performed by the node.
+//!
+//! Each subsystem performing CPU or disk intensive work runs it within its
+//! [`TaskBudget`], which bounds the number of such tasks running at once and
+//! keeps track of the time they take. The [`stats`] of all budgets let
+//! operators tell which subsystem is keeping the consensus loop waiting.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Deletion of unused commits of the state.
+pub static COMMIT_DELETION: TaskBudget = TaskBudget::new("commit_deletion", 1);
+
+/// Generation of the proofs requested to the local prover.
+pub static PROOF_GENERATION: TaskBudget =
+    TaskBudget::new("proof_generation", 2);
+
+/// Execution of the blocks downloaded while syncing up.
+pub static SYNC: TaskBudget = TaskBudget::new("sync", 1);
+
+/// Contract queries issued by the async services.
+pub static QUERY: TaskBudget = TaskBudget::new("query", 8);
+
+/// Feeder queries, streaming their results to the clients.
+pub static FEEDER_QUERY: TaskBudget = TaskBudget::new("feeder_query", 4);
+
+/// Streams of blocks served to clients.
+pub static BLOCK_STREAMS: TaskBudget = TaskBudget::new("block_streams", 4);
+
+/// Streams of the finality of the tip served to clients, each polling the
+/// ledger.
+pub static FINALITY_STREAMS: TaskBudget =
+    TaskBudget::new("finality_streams", 64);
+
+/// Returns the statistics of every budget.
+pub fn stats() -> Vec<BudgetStats> {
+    [
+        &COMMIT_DELETION,
+        &PROOF_GENERATION,
+        &SYNC,
+        &QUERY,
+        &FEEDER_QUERY,
+        &BLOCK_STREAMS,
+        &FINALITY_STREAMS,
+    ]
+    .into_iter()
+    .map(TaskBudget::stats)
+    .collect()
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Bound on the number of heavy tasks of a subsystem running at once.
+pub struct TaskBudget {
+    name: &'static str,
+    permits: usize,
+    state: Mutex<State>,
+    cvar: Condvar,
+    /// Queue of the threads dedicated to the budget, spawned on first use
+    jobs: OnceLock<mpsc::Sender<Job>>,
+}
+
+struct State {
+    running: usize,
+    waiting: usize,
+    completed: u64,
+    busy: Duration,
+    waited: Duration,
+    longest: Duration,
+}
+
+/// Statistics of the tasks run within a [`TaskBudget`] since startup.
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetStats {
+    pub name: &'static str,
+    /// Maximum number of tasks running at once
+    pub permits: usize,
+    pub running: usize,
+    /// Tasks waiting for one of the running ones to finish
+    pub waiting: usize,
+    pub completed: u64,
+    /// Total time spent running tasks, in milliseconds
+    pub busy_ms: u128,
+    /// Total time tasks spent waiting to run, in milliseconds
+    pub waited_ms: u128,
+    /// Longest time a single task took to run, in milliseconds
+    pub longest_ms: u128,
+}
+
+impl TaskBudget {
+    pub const fn new(name: &'static str, permits: usize) -> Self {
+        Self {
+            name,
+            permits,
+            state: Mutex::new(State {
+                running: 0,
+                waiting: 0,
+                completed: 0,
+                busy: Duration::ZERO,
+                waited: Duration::ZERO,
+                longest: Duration::ZERO,
+            }),
+            cvar: Condvar::new(),
+            jobs: OnceLock::new(),
+        }
+    }
+
+    /// Runs `task` as soon as fewer than `permits` tasks of this budget are
+    /// running, blocking the current thread until then.
+    pub fn run<T, F: FnOnce() -> T>(&self, task: F) -> T {
+        let queued = self.enqueue();
+        self.run_queued(queued, task)
+    }
+
+    /// Accounts a task as waiting to run, returning the time it was queued.
+    fn enqueue(&self) -> Instant {
+        let mut state = self.state.lock().expect("lock to be acquired");
+        state.waiting += 1;
+        Instant::now()
+    }
+
+    fn run_queued<T, F: FnOnce() -> T>(&self, queued: Instant, task: F) -> T {
+        {
+            let mut state = self.state.lock().expect("lock to be acquired");
+            while state.running >= self.permits {
+                state = self.cvar.wait(state).expect("lock to be acquired");
+            }
+            state.waiting -= 1;
+            state.running += 1;
+            state.waited += queued.elapsed();
+        }
+
+        // The task is accounted for even if it panics
+        let _guard = RunGuard {
+            budget: self,
+            started: Instant::now(),
+        };
+        task()
+    }
+
+    /// Runs `task` on the threads dedicated to the budget, one per permit,
+    /// without blocking the runtime.
+    ///
+    /// Tasks wait for a thread in a queue, rather than holding one of the
+    /// blocking threads of the runtime, so that a flood of them cannot
+    /// exhaust those. Dropping the returned future does not dequeue the task,
+    /// which is expected to check whether its result is still awaited.
+    pub async fn spawn_blocking<T, F>(&'static self, task: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        self.execute(move || {
+            let res = panic::catch_unwind(AssertUnwindSafe(task));
+            let _ = sender.send(res);
+        });
+
+        match receiver.await.expect("budget threads to run the task") {
+            Ok(res) => res,
+            Err(panic) => panic::resume_unwind(panic),
+        }
+    }
+
+    /// Runs `task` on the threads dedicated to the budget, without waiting
+    /// for it to be done. The task runs within the span it was queued from.
+    pub fn execute<F>(&'static self, task: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let queued = self.enqueue();
+        let span = tracing::Span::current();
+        let job = Box::new(move || {
+            // A panicking task must not take a thread of the budget with it
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                span.in_scope(|| self.run_queued(queued, task))
+            }));
+        });
+
+        self.jobs().send(job).expect("budget threads to be running");
+    }
+
+    fn jobs(&'static self) -> &mpsc::Sender<Job> {
+        self.jobs.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel::<Job>();
+            let receiver = Arc::new(Mutex::new(receiver));
+
+            for i in 0..self.permits {
+                let receiver = receiver.clone();
+                thread::Builder::new()
+                    .name(format!("{}-{i}", self.name))
+                    .spawn(move || loop {
+                        let job = receiver
+                            .lock()
+                            .expect("lock to be acquired")
+                            .recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("budget thread to be spawned");
+            }
+
+            sender
+        })
+    }
+
+    /// Takes a permit to run a task if fewer than `permits` tasks of this
+    /// budget are running, without waiting. The task is accounted as running
+    /// until the permit is dropped.
+    pub fn try_acquire(&'static self) -> Option<Permit> {
+        let mut state = self.state.lock().expect("lock to be acquired");
+        if state.running >= self.permits {
+            return None;
+        }
+        state.running += 1;
+
+        Some(Permit {
+            _guard: RunGuard {
+                budget: self,
+                started: Instant::now(),
+            },
+        })
+    }
+
+    pub fn stats(&self) -> BudgetStats {
+        let state = self.state.lock().expect("lock to be acquired");
+        BudgetStats {
+            name: self.name,
+            permits: self.permits,
+            running: state.running,
+            waiting: state.waiting,
+            completed: state.completed,
+            busy_ms: state.busy.as_millis(),
+            waited_ms: state.waited.as_millis(),
+            longest_ms: state.longest.as_millis(),
+        }
+    }
+}
+
+/// Permit to run a task within a [`TaskBudget`], released on drop.
+pub struct Permit {
+    _guard: RunGuard<'static>,
+}
+
+struct RunGuard<'a> {
+    budget: &'a TaskBudget,
+    started: Instant,
+}
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+
+        let mut state = self.budget.state.lock().expect("lock to be acquired");
+        state.running -= 1;
+        state.completed += 1;
+        state.busy += elapsed;
+        state.longest = state.longest.max(elapsed);
+        drop(state);
+
+        self.budget.cvar.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn budget_bounds_running_tasks() {
+        static BUDGET: TaskBudget = TaskBudget::new("test", 2);
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let running = running.clone();
+                let max_running = max_running.clone();
+                thread::spawn(move || {
+                    BUDGET.run(|| {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_running.fetch_max(now, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(10));
+                        running.fetch_sub(1, Ordering::SeqCst);
+                    })
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("thread to finish");
+        }
+
+        assert!(max_running.load(Ordering::SeqCst) <= 2);
+
+        let stats = BUDGET.stats();
+        assert_eq!(stats.completed, 8);
+        assert_eq!(stats.running, 0);
+        assert_eq!(stats.waiting, 0);
+        assert!(stats.longest_ms >= 10);
+    }
+
+    #[test]
+    fn budget_permits_do_not_wait() {
+        static BUDGET: TaskBudget = TaskBudget::new("test_permits", 2);
+
+        let first = BUDGET.try_acquire().expect("permit to be available");
+        let second = BUDGET.try_acquire().expect("permit to be available");
+        assert!(BUDGET.try_acquire().is_none());
+        assert_eq!(BUDGET.stats().running, 2);
+
+        drop(first);
+        let third = BUDGET.try_acquire().expect("permit to be released");
+        drop((second, third));
+
+        let stats = BUDGET.stats();
+        assert_eq!(stats.running, 0);
+        assert_eq!(stats.completed, 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn spawned_tasks_run_on_budget_threads() {
+        static BUDGET: TaskBudget = TaskBudget::new("test_spawn", 2);
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                tokio::spawn(BUDGET.spawn_blocking(|| {
+                    thread::sleep(Duration::from_millis(10));
+                    thread::current().name().map(String::from)
+                }))
+            })
+            .collect();
+        for task in tasks {
+            let name = task.await.expect("task to finish");
+            assert!(name
+                .expect("thread to be named")
+                .starts_with("test_spawn-"));
+        }
+
+        let stats = BUDGET.stats();
+        assert_eq!(stats.completed, 8);
+        assert_eq!(stats.running, 0);
+        assert_eq!(stats.waiting, 0);
+    }
+
+    #[tokio::test]
+    async fn panicking_tasks_keep_budget_threads() {
+        static BUDGET: TaskBudget = TaskBudget::new("test_panic", 1);
+
+        let task = tokio::spawn(BUDGET.spawn_blocking(|| panic!("task panic")));
+        assert!(task.await.unwrap_err().is_panic());
+
+        assert_eq!(BUDGET.spawn_blocking(|| 42).await, 42);
+        assert_eq!(BUDGET.stats().completed, 2);
+    }
+}
@@ -4,38 +4,237 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+mod event_schema;
+mod gas_pricing;
+mod host_gas;
+mod janitor;
+mod migrations;
+mod note_index;
+mod notes;
+mod query_quota;
 mod rusk;
 mod vm;
 
+pub use event_schema::{decode as decode_event, EventSchema};
+pub use gas_pricing::GasPricing;
+pub use host_gas::{
+    stats as host_gas_stats, HostCallStats, HostGasLimits, HostGasStats,
+};
+pub use janitor::{CommitGuard, FinishedDeletion, Janitor, JanitorStatus};
+pub use migrations::{MigrationFn, Migrations};
+pub use notes::{NoteOpening, MAX_DECOYS, MAX_OPENINGS};
+pub use query_quota::{QueryQuotas, Reservation};
+pub use rusk::StakeOpening;
+pub use vm::DEFAULT_QUERY_TIMEOUT;
+
+use std::ops::Deref;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
 use std::time::Duration;
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+
+use dusk_bls12_381_sign::PublicKey as BlsPublicKey;
 
-use node::database::rocksdb::Backend;
+use node::chain::ChainReverts;
+use node::database::rocksdb::{Backend, MD_HASH_KEY};
+use node::database::{Ledger, Metadata, DB};
+use node::mempool::admission::AdmissionPolicy;
 use node::network::Kadcast;
 use rusk_abi::dusk::{dusk, Dusk};
 use rusk_abi::VM;
 
+use crate::audit::AuditLog;
+
 pub const MINIMUM_STAKE: Dusk = dusk(1000.0);
 
 #[derive(Debug, Clone, Copy)]
 pub struct RuskTip {
     pub current: [u8; 32],
     pub base: [u8; 32],
+    /// Commit the tip moved from to `current`, which queries may still be
+    /// running on
+    pub previous: [u8; 32],
 }
 
 #[derive(Clone)]
 pub struct Rusk {
-    pub(crate) tip: Arc<RwLock<RuskTip>>,
-    pub(crate) vm: Arc<VM>,
+    reader: RuskReader,
     dir: PathBuf,
+    /// Network the blocks and transactions executed belong to
+    pub(crate) chain_id: u8,
     pub(crate) generation_timeout: Option<Duration>,
+    pub(crate) host_gas: HostGasLimits,
+    pub(crate) gas_pricing: GasPricing,
+    /// Number of blocks finalized while syncing that are committed at once
+    pub(crate) sync_commit_interval: u64,
+    /// State migrations run at their activation heights
+    pub(crate) migrations: Migrations,
+    /// Blocks finalized while syncing, executed but not committed yet
+    pending: Arc<Mutex<Option<rusk::PendingCommit>>>,
+    /// Candidate states committed ahead of their acceptance
+    precommits: Arc<Mutex<rusk::Precommits>>,
+    /// Requests to the thread syncing the note index with the tip
+    note_syncs: mpsc::SyncSender<()>,
+    audit: Option<AuditLog>,
+}
+
+/// Read-only handle to the state managed by [`Rusk`].
+///
+/// It is cheap to clone, and is what the query services are given: it has no
+/// way of executing transactions or moving the tip of the state.
+#[derive(Clone)]
+pub struct RuskReader {
+    tip: Arc<RwLock<RuskTip>>,
+    vm: Arc<VM>,
+    janitor: Janitor,
+    openings: Arc<Mutex<notes::OpeningCache>>,
+    owned_notes: Arc<Mutex<notes::OwnedNotesCache>>,
+    note_index: Arc<Mutex<note_index::NoteIndex>>,
+    /// Gas quotas of the queries of the clients, unlimited if missing
+    query_quotas: Option<QueryQuotas>,
+}
+
+impl Rusk {
+    /// Returns a read-only handle to the state.
+    pub fn reader(&self) -> RuskReader {
+        self.reader.clone()
+    }
+}
+
+impl Deref for Rusk {
+    type Target = RuskReader;
+
+    fn deref(&self) -> &Self::Target {
+        &self.reader
+    }
+}
+
+/// Graded penalties applied to provisioners missing their generation.
+///
+/// The first `soft_faults` missed generations only slash the reward. The
+/// following `suspension_faults` suspend the eligibility of the stake for
+/// `suspension_epochs` epochs. Any further fault slashes the stake itself.
+///
+/// The fault count of a provisioner is reset as soon as it gets rewarded.
+///
+/// The penalties decide the state root, so the policy applied is the
+/// [`SLASHING_POLICY`] of the protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlashingPolicy {
+    pub soft_faults: u32,
+    pub suspension_faults: u32,
+    pub suspension_epochs: u64,
+    /// Amount slashed, from either the reward or the stake
+    pub amount: SlashAmount,
+}
+
+/// Schedule of the amount slashed from a provisioner for a missed generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlashAmount {
+    /// The emission of the block, vanishing as the emission decays.
+    Emission,
+    /// A fraction of the staked amount, in basis points.
+    Stake { basis_points: u16 },
+    /// Fixed amounts, each one applying from its block height onward.
+    Curve { steps: &'static [(u64, Dusk)] },
+}
+
+impl SlashAmount {
+    /// Returns the amount to slash at the given block height from a
+    /// provisioner with the given staked amount.
+    pub fn amount(&self, block_height: u64, stake: Dusk) -> Dusk {
+        match self {
+            Self::Emission => emission_amount(block_height),
+            Self::Stake { basis_points } => {
+                let amount =
+                    stake as u128 * (*basis_points).min(10_000) as u128;
+                (amount / 10_000) as Dusk
+            }
+            Self::Curve { steps } => steps
+                .iter()
+                .filter(|(height, _)| *height <= block_height)
+                .max_by_key(|(height, _)| *height)
+                .map(|(_, amount)| *amount)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Slashing policy of the protocol.
+///
+/// Missed generations never burn the stake: once past the soft faults, each
+/// further fault suspends the stake again until the provisioner is rewarded.
+pub const SLASHING_POLICY: SlashingPolicy = SlashingPolicy {
+    soft_faults: 3,
+    suspension_faults: u32::MAX,
+    suspension_epochs: 1,
+    amount: SlashAmount::Curve {
+        steps: SLASH_SCHEDULE,
+    },
+};
+
+/// Amounts slashed by the protocol for a missed generation.
+///
+/// These follow the emission schedule, but keep slashing its last amount once
+/// the emission ends.
+const SLASH_SCHEDULE: &[(u64, Dusk)] = &[
+    (1, dusk(16.0)),
+    (12_500_001, dusk(12.8)),
+    (18_750_001, dusk(9.6)),
+    (25_000_001, dusk(8.0)),
+    (31_250_001, dusk(6.4)),
+    (37_500_001, dusk(4.8)),
+    (43_750_001, dusk(3.2)),
+    (50_000_001, dusk(1.6)),
+];
+
+/// Penalty to apply to a provisioner for a missed generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Penalty {
+    /// Slash the reward.
+    Slash,
+    /// Suspend the eligibility for the given number of epochs.
+    Suspend(u64),
+    /// Slash the staked amount.
+    HardSlash,
 }
 
+impl SlashingPolicy {
+    /// Returns the penalty for a provisioner that missed `faults` generations
+    /// since it was last rewarded.
+    pub const fn penalty(&self, faults: u32) -> Penalty {
+        if faults <= self.soft_faults {
+            Penalty::Slash
+        } else if faults - self.soft_faults <= self.suspension_faults {
+            Penalty::Suspend(self.suspension_epochs)
+        } else {
+            Penalty::HardSlash
+        }
+    }
+}
+
+/// Penalty a provisioner gets, or would get, for a missed generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlashOutcome {
+    pub provisioner: BlsPublicKey,
+    /// Missed generations since it was last rewarded, this one included
+    pub faults: u32,
+    pub penalty: Penalty,
+    /// Amount slashed, zero if only suspended
+    pub amount: Dusk,
+}
+
+/// The node, along with the local policy deciding which transactions enter
+/// its mempool, the budget in bytes of the mempool and the handle reverting
+/// its chain.
 #[derive(Clone)]
-pub struct RuskNode(pub node::Node<Kadcast<255>, Backend, Rusk>);
+pub struct RuskNode(
+    pub node::Node<Kadcast<255>, Backend, Rusk>,
+    pub Arc<dyn AdmissionPolicy>,
+    pub Option<usize>,
+    pub ChainReverts,
+);
 
 impl RuskNode {
     pub fn db(&self) -> Arc<tokio::sync::RwLock<Backend>> {
@@ -45,6 +244,19 @@ impl RuskNode {
     pub fn network(&self) -> Arc<tokio::sync::RwLock<Kadcast<255>>> {
         self.0.network() as Arc<tokio::sync::RwLock<Kadcast<255>>>
     }
+
+    /// Returns the chain ID of the network, as stated by the blockchain tip
+    pub async fn chain_id(&self) -> anyhow::Result<u8> {
+        self.db().read().await.view(|t| {
+            let tip = t
+                .op_read(MD_HASH_KEY)?
+                .ok_or_else(|| anyhow::anyhow!("Cannot find tip"))?;
+            let (header, _) = t
+                .fetch_block_header(&tip)?
+                .ok_or_else(|| anyhow::anyhow!("Cannot find tip header"))?;
+            Ok(header.chain_id)
+        })
+    }
 }
 
 /// Calculates the value that the coinbase notes should contain.
@@ -74,3 +286,60 @@ pub const fn emission_amount(block_height: u64) -> Dusk {
         _ => dusk(0.0),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slashing_policy_grades_penalties() {
+        let policy = SlashingPolicy {
+            soft_faults: 2,
+            suspension_faults: 1,
+            suspension_epochs: 3,
+            amount: SlashAmount::Emission,
+        };
+
+        assert_eq!(policy.penalty(1), Penalty::Slash);
+        assert_eq!(policy.penalty(2), Penalty::Slash);
+        assert_eq!(policy.penalty(3), Penalty::Suspend(3));
+        assert_eq!(policy.penalty(4), Penalty::HardSlash);
+    }
+
+    #[test]
+    fn protocol_policy_never_burns_stake() {
+        assert_eq!(SLASHING_POLICY.penalty(0), Penalty::Slash);
+        assert_eq!(SLASHING_POLICY.penalty(3), Penalty::Slash);
+        assert_eq!(SLASHING_POLICY.penalty(4), Penalty::Suspend(1));
+        assert_eq!(SLASHING_POLICY.penalty(u32::MAX), Penalty::Suspend(1));
+    }
+
+    #[test]
+    fn slash_amount_schedules() {
+        let emission = SlashAmount::Emission;
+        assert_eq!(emission.amount(1, dusk(1000.0)), dusk(16.0));
+        assert_eq!(emission.amount(62_500_001, dusk(1000.0)), 0);
+
+        let stake = SlashAmount::Stake { basis_points: 250 };
+        assert_eq!(stake.amount(1, dusk(1000.0)), dusk(25.0));
+        assert_eq!(stake.amount(62_500_001, dusk(1000.0)), dusk(25.0));
+
+        let curve = SlashAmount::Curve {
+            steps: &[(100, dusk(5.0)), (10, dusk(10.0))],
+        };
+        assert_eq!(curve.amount(1, dusk(1000.0)), 0);
+        assert_eq!(curve.amount(10, dusk(1000.0)), dusk(10.0));
+        assert_eq!(curve.amount(99, dusk(1000.0)), dusk(10.0));
+        assert_eq!(curve.amount(100, dusk(1000.0)), dusk(5.0));
+    }
+
+    #[test]
+    fn protocol_slash_amount_outlasts_emission() {
+        let amount = SLASHING_POLICY.amount;
+        for height in [1, 12_500_000, 12_500_001, 50_000_001, 62_500_000] {
+            assert_eq!(amount.amount(height, 0), emission_amount(height));
+        }
+        assert_eq!(amount.amount(62_500_001, 0), dusk(1.6));
+        assert_eq!(amount.amount(u64::MAX, 0), dusk(1.6));
+    }
+}
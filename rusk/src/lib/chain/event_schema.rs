@@ -0,0 +1,159 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dusk_bytes::Serializable;
+use rkyv::AlignedVec;
+use rusk_abi::{STAKE_CONTRACT, TRANSFER_CONTRACT};
+use serde_json::{json, Value};
+use stake_contract_types::StakingEvent;
+use transfer_contract_types::{MigrationEvent, TreeLeaf};
+
+/// Topics of the events emitted by the stake contract, all carrying a
+/// [`StakingEvent`]
+const STAKE_TOPICS: &[&str] = &[
+    "stake",
+    "unstake",
+    "withdraw",
+    "auto_compound",
+    "reward_address",
+    "reward",
+    "compound",
+    "slash",
+    "shifted",
+    "suspended",
+    "hard_slash",
+];
+
+/// Layout of the data of a contract event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventSchema {
+    /// [`StakingEvent`]
+    StakingEvent,
+    /// [`MigrationEvent`]
+    MigrationEvent,
+    /// A [`TreeLeaf`] along with its position in the tree
+    TreeLeaf,
+}
+
+impl EventSchema {
+    /// Identifier of the schema, as returned along with the decoded events
+    pub const fn id(&self) -> &'static str {
+        match self {
+            Self::StakingEvent => "stake.StakingEvent",
+            Self::MigrationEvent => "transfer.MigrationEvent",
+            Self::TreeLeaf => "transfer.TreeLeaf",
+        }
+    }
+
+    /// Decode the rkyv serialized data of an event into JSON, returning
+    /// `None` if the data doesn't follow the schema.
+    pub fn decode(&self, data: &[u8]) -> Option<Value> {
+        // The data stored with the events isn't guaranteed to be aligned
+        let mut aligned = AlignedVec::with_capacity(data.len());
+        aligned.extend_from_slice(data);
+        let data = aligned.as_slice();
+
+        let value = match self {
+            Self::StakingEvent => {
+                let event = rkyv::from_bytes::<StakingEvent>(data).ok()?;
+                json!({
+                    "public_key": bs58::encode(event.public_key.to_bytes())
+                        .into_string(),
+                    "value": event.value,
+                })
+            }
+            Self::MigrationEvent => {
+                let event = rkyv::from_bytes::<MigrationEvent>(data).ok()?;
+                json!({
+                    "contract": hex::encode(event.contract),
+                    "version": event.version,
+                })
+            }
+            Self::TreeLeaf => {
+                let (pos, leaf) =
+                    rkyv::from_bytes::<(u64, TreeLeaf)>(data).ok()?;
+                json!({
+                    "position": pos,
+                    "block_height": leaf.block_height,
+                    "note": hex::encode(leaf.note.to_bytes()),
+                })
+            }
+        };
+        Some(value)
+    }
+}
+
+/// Schema of the events emitted by a contract with a topic, if known.
+///
+/// The layouts of the events are only known for the contracts deployed at
+/// genesis, whose types this node is built with. The events of the other
+/// contracts are served with their raw data.
+pub fn schema(contract: &[u8; 32], topic: &str) -> Option<EventSchema> {
+    if contract == &STAKE_CONTRACT.to_bytes() && STAKE_TOPICS.contains(&topic) {
+        return Some(EventSchema::StakingEvent);
+    }
+
+    if contract == &TRANSFER_CONTRACT.to_bytes() {
+        return match topic {
+            "migration" => Some(EventSchema::MigrationEvent),
+            "TREE_LEAF" => Some(EventSchema::TreeLeaf),
+            _ => None,
+        };
+    }
+
+    None
+}
+
+/// Decode the data of an event, returning the identifier of its schema along
+/// with the decoded JSON.
+///
+/// Returns `None` if no schema is known for the event or if its data doesn't
+/// follow it, in which case only the raw data is available to the caller.
+pub fn decode(
+    contract: &[u8; 32],
+    topic: &str,
+    data: &[u8],
+) -> Option<(&'static str, Value)> {
+    let schema = schema(contract, topic)?;
+    schema.decode(data).map(|value| (schema.id(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use dusk_bls12_381_sign::{PublicKey, SecretKey};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn decodes_genesis_events() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let public_key = PublicKey::from(&SecretKey::random(&mut rng));
+
+        let event = StakingEvent {
+            public_key,
+            value: 42,
+        };
+        let data = rkyv::to_bytes::<_, 256>(&event).unwrap();
+
+        let stake = STAKE_CONTRACT.to_bytes();
+        let (id, value) = decode(&stake, "reward", &data).unwrap();
+        assert_eq!(id, "stake.StakingEvent");
+        assert_eq!(value["value"], 42);
+        assert_eq!(
+            value["public_key"],
+            bs58::encode(public_key.to_bytes()).into_string()
+        );
+
+        // Unknown topics and malformed data are left undecoded
+        assert!(decode(&stake, "unknown", &data).is_none());
+        assert!(decode(&stake, "reward", &[0u8; 3]).is_none());
+
+        // The events of contracts deployed after genesis are left raw
+        assert!(decode(&[0xfe; 32], "reward", &data).is_none());
+    }
+}
@@ -0,0 +1,47 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+pub use dusk_consensus::config::GasPricing;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effective_price_is_capped() {
+        let pricing = GasPricing {
+            base_price: 10,
+            max_tip: Some(2),
+            from_height: 0,
+        };
+        assert_eq!(pricing.effective_price(9), None);
+        assert_eq!(pricing.effective_price(10), Some(10));
+        assert_eq!(pricing.effective_price(11), Some(11));
+        assert_eq!(pricing.effective_price(100), Some(12));
+
+        let pricing = GasPricing {
+            base_price: 10,
+            max_tip: None,
+            from_height: 0,
+        };
+        assert_eq!(pricing.effective_price(100), Some(100));
+    }
+
+    #[test]
+    fn fee_cap_is_charged_before_activation() {
+        let pricing = GasPricing {
+            from_height: 10,
+            ..GasPricing::default()
+        };
+        assert_eq!(pricing.at(9).effective_price(0), Some(0));
+        assert_eq!(pricing.at(9).effective_price(5_000), Some(5_000));
+        assert_eq!(pricing.at(10).effective_price(0), None);
+        assert_eq!(
+            pricing.at(10).effective_price(5_000),
+            Some(pricing.base_price + pricing.max_tip.unwrap())
+        );
+    }
+}
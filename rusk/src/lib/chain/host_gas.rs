@@ -0,0 +1,211 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use bytecheck::CheckBytes;
+pub use dusk_consensus::config::HostGasLimits;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{Archive, Deserialize, Infallible, Serialize};
+use rusk_abi::{
+    CallReceipt, ContractId, Error as PiecrustError, Session,
+    StandardBufSerializer,
+};
+use serde::Serialize as SerdeSerialize;
+
+/// Kind of call made by the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum HostCall {
+    Reward,
+    Slash,
+    Refund,
+    UpdateRoot,
+}
+
+impl HostCall {
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Reward => "reward",
+            Self::Slash => "slash",
+            Self::Refund => "refund",
+            Self::UpdateRoot => "update_root",
+        }
+    }
+
+    const fn limit(&self, limits: &HostGasLimits) -> u64 {
+        match self {
+            Self::Reward => limits.reward,
+            Self::Slash => limits.slash,
+            Self::Refund => limits.refund,
+            Self::UpdateRoot => limits.update_root,
+        }
+    }
+}
+
+/// Gas spent by the host calls of a kind
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, SerdeSerialize)]
+pub struct HostCallStats {
+    pub calls: u64,
+    pub gas_spent: u64,
+    pub max_gas_spent: u64,
+    /// Calls that failed, running out of gas included
+    pub failures: u64,
+}
+
+impl HostCallStats {
+    fn add(&mut self, other: &Self) {
+        self.calls += other.calls;
+        self.gas_spent += other.gas_spent;
+        self.max_gas_spent = self.max_gas_spent.max(other.max_gas_spent);
+        self.failures += other.failures;
+    }
+}
+
+/// Gas spent by the host calls of the blocks accepted since startup, by kind
+/// of call.
+#[derive(Debug, Default, Clone, PartialEq, Eq, SerdeSerialize)]
+pub struct HostGasStats {
+    /// Number of blocks accepted
+    pub blocks: u64,
+    pub total: BTreeMap<&'static str, HostCallStats>,
+    /// Height of the last block accepted
+    pub last_height: Option<u64>,
+    pub last_block: BTreeMap<&'static str, HostCallStats>,
+}
+
+static STATS: Mutex<Option<HostGasStats>> = Mutex::new(None);
+
+/// Returns the gas spent by the host calls of the blocks accepted since
+/// startup.
+pub fn stats() -> HostGasStats {
+    STATS
+        .lock()
+        .expect("host gas stats lock to be acquired")
+        .clone()
+        .unwrap_or_default()
+}
+
+/// Host calls of the execution of a single block, within the limits of its
+/// height.
+///
+/// A block is executed several times, when generated, verified and accepted:
+/// only the gas spent by the execution of an accepted block is recorded in
+/// the [`stats`].
+#[derive(Debug, Clone)]
+pub(crate) struct HostGas {
+    block_height: u64,
+    limits: HostGasLimits,
+    spent: BTreeMap<HostCall, HostCallStats>,
+}
+
+impl HostGas {
+    pub fn new(limits: &HostGasLimits, block_height: u64) -> Self {
+        Self {
+            block_height,
+            limits: limits.at(block_height),
+            spent: BTreeMap::new(),
+        }
+    }
+
+    /// Accounts a call of the given kind, spending `gas_spent`, or failing
+    /// if `None`.
+    fn account(&mut self, call: HostCall, gas_spent: Option<u64>) {
+        let stats = self.spent.entry(call).or_default();
+        stats.calls += 1;
+        match gas_spent {
+            Some(gas_spent) => {
+                stats.gas_spent += gas_spent;
+                stats.max_gas_spent = stats.max_gas_spent.max(gas_spent);
+            }
+            None => stats.failures += 1,
+        }
+    }
+
+    /// Records the gas spent in the [`stats`], once the block is accepted.
+    pub fn record(&self) {
+        let block: BTreeMap<_, _> = self
+            .spent
+            .iter()
+            .map(|(call, stats)| (call.name(), *stats))
+            .collect();
+
+        let mut stats =
+            STATS.lock().expect("host gas stats lock to be acquired");
+        let stats = stats.get_or_insert_with(Default::default);
+        stats.blocks += 1;
+        for (call, spent) in &block {
+            stats.total.entry(call).or_default().add(spent);
+        }
+        stats.last_height = Some(self.block_height);
+        stats.last_block = block;
+    }
+}
+
+/// Calls `fn_name` of `contract` on behalf of the host, within the limit of
+/// its kind of `call`, and accounts the gas spent in `host_gas`.
+pub(crate) fn host_call<A, R>(
+    session: &mut Session,
+    call: HostCall,
+    host_gas: &mut HostGas,
+    contract: ContractId,
+    fn_name: &str,
+    fn_arg: &A,
+) -> Result<CallReceipt<R>, PiecrustError>
+where
+    A: for<'b> Serialize<StandardBufSerializer<'b>>,
+    A::Archived: for<'b> CheckBytes<DefaultValidator<'b>>,
+    R: Archive,
+    R::Archived:
+        Deserialize<R, Infallible> + for<'b> CheckBytes<DefaultValidator<'b>>,
+{
+    let limit = call.limit(&host_gas.limits);
+    let result = session.call(contract, fn_name, fn_arg, limit);
+
+    let gas_spent = result.as_ref().ok().map(|receipt| receipt.gas_spent);
+    host_gas.account(call, gas_spent);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_calls_are_bounded_from_height() {
+        let limits = HostGasLimits {
+            from_height: 10,
+            ..Default::default()
+        };
+
+        let unbounded = HostGas::new(&limits, 9);
+        assert_eq!(HostCall::Refund.limit(&unbounded.limits), u64::MAX);
+        let bounded = HostGas::new(&limits, 10);
+        assert_eq!(HostCall::Refund.limit(&bounded.limits), limits.refund);
+    }
+
+    #[test]
+    fn only_recorded_blocks_are_accounted() {
+        let limits = HostGasLimits::default();
+
+        let mut generated = HostGas::new(&limits, 1);
+        generated.account(HostCall::Reward, Some(10));
+
+        let mut accepted = HostGas::new(&limits, 1);
+        accepted.account(HostCall::Reward, Some(10));
+        accepted.account(HostCall::Reward, Some(30));
+        accepted.account(HostCall::Refund, None);
+        accepted.record();
+
+        let stats = stats();
+        assert_eq!(stats.last_height, Some(1));
+        let reward = &stats.last_block["reward"];
+        assert_eq!((reward.calls, reward.gas_spent), (2, 40));
+        assert_eq!(reward.max_gas_spent, 30);
+        assert_eq!(stats.last_block["refund"].failures, 1);
+    }
+}
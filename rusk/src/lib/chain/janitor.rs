@@ -0,0 +1,420 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::{Duration, Instant};
+use std::{fs, io, thread};
+
+use rusk_abi::VM;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::budget::COMMIT_DELETION;
+
+/// File, within the state directory, persisting the pending deletions
+const PENDING_DELETIONS_FILE: &str = "deletions.pending";
+
+/// Number of finished deletions kept for inspection
+const MAX_FINISHED: usize = 100;
+
+/// Number of attempts after which a deletion is given up
+const MAX_ATTEMPTS: u32 = 10;
+
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Interval at which the janitor thread checks whether it should stop
+const IDLE_WAIT: Duration = Duration::from_secs(1);
+
+/// Deletes unused commits in the background.
+///
+/// Deleting a commit blocks until any session using it is dropped, so
+/// deletions are queued and performed by a dedicated thread. Failed
+/// deletions are retried with exponential backoff, and the queue is
+/// persisted in the state directory so that it survives restarts.
+///
+/// Commits can be pinned with [`Janitor::pin`], deferring their deletion
+/// until every [`CommitGuard`] is dropped.
+#[derive(Clone)]
+pub struct Janitor {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    vm: Arc<VM>,
+    dir: PathBuf,
+    state: Mutex<State>,
+    cvar: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    pending: VecDeque<Pending>,
+    finished: VecDeque<FinishedDeletion>,
+    reclaimed_bytes: u64,
+    /// Number of guards held for each pinned commit
+    pinned: HashMap<[u8; 32], usize>,
+    /// Commit currently being deleted
+    deleting: Option<[u8; 32]>,
+}
+
+struct Pending {
+    commit: [u8; 32],
+    attempts: u32,
+    next_try: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FinishedDeletion {
+    pub commit: String,
+    pub attempts: u32,
+    /// Estimated disk space reclaimed, in bytes
+    pub reclaimed_bytes: u64,
+    /// Last error, if the deletion was given up
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JanitorStatus {
+    pub pending: Vec<String>,
+    /// Commits whose deletion is deferred, as they are in use
+    pub pinned: Vec<String>,
+    pub finished: Vec<FinishedDeletion>,
+    /// Estimated disk space reclaimed since startup, in bytes
+    pub reclaimed_bytes: u64,
+}
+
+impl Janitor {
+    /// Creates a janitor for the commits of `vm`, resuming the deletions
+    /// persisted in `dir`.
+    pub fn new<P: AsRef<Path>>(vm: Arc<VM>, dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+
+        let now = Instant::now();
+        let pending = read_pending(&dir)?
+            .into_iter()
+            .map(|commit| Pending {
+                commit,
+                attempts: 0,
+                next_try: now,
+            })
+            .collect::<VecDeque<_>>();
+
+        if !pending.is_empty() {
+            info!("Resuming {} pending commit deletions", pending.len());
+        }
+
+        let inner = Arc::new(Inner {
+            vm,
+            dir,
+            state: Mutex::new(State {
+                pending,
+                ..Default::default()
+            }),
+            cvar: Condvar::new(),
+        });
+
+        let weak = Arc::downgrade(&inner);
+        thread::Builder::new()
+            .name("commit-janitor".into())
+            .spawn(move || run(weak))?;
+
+        Ok(Self { inner })
+    }
+
+    /// Queues the given commits for deletion.
+    pub fn schedule(&self, commits: Vec<[u8; 32]>) {
+        let mut state = self.inner.state.lock().expect("lock to be acquired");
+
+        let now = Instant::now();
+        for commit in commits {
+            if !state.pending.iter().any(|p| p.commit == commit) {
+                state.pending.push_back(Pending {
+                    commit,
+                    attempts: 0,
+                    next_try: now,
+                });
+            }
+        }
+
+        self.inner.persist(&state);
+        self.inner.cvar.notify_one();
+    }
+
+    /// Removes `commit` from the pending deletions, as it is in use again.
+    ///
+    /// A commit being deleted is not affected, it must be pinned beforehand
+    /// to be kept.
+    pub fn cancel(&self, commit: [u8; 32]) {
+        let mut state = self.inner.state.lock().expect("lock to be acquired");
+
+        let len = state.pending.len();
+        state.pending.retain(|p| p.commit != commit);
+        if state.pending.len() != len {
+            self.inner.persist(&state);
+        }
+    }
+
+    /// Returns the pending and the most recently finished deletions.
+    pub fn status(&self) -> JanitorStatus {
+        let state = self.inner.state.lock().expect("lock to be acquired");
+
+        JanitorStatus {
+            pending: state
+                .pending
+                .iter()
+                .map(|p| hex::encode(p.commit))
+                .collect(),
+            pinned: state.pinned.keys().map(hex::encode).collect(),
+            finished: state.finished.iter().cloned().collect(),
+            reclaimed_bytes: state.reclaimed_bytes,
+        }
+    }
+
+    /// Prevents `commit` from being deleted until the returned guard is
+    /// dropped.
+    ///
+    /// Returns `None` if the commit is being deleted.
+    pub fn pin(&self, commit: [u8; 32]) -> Option<CommitGuard> {
+        let mut state = self.inner.state.lock().expect("lock to be acquired");
+
+        if state.deleting == Some(commit) {
+            return None;
+        }
+        *state.pinned.entry(commit).or_insert(0) += 1;
+
+        Some(CommitGuard {
+            inner: self.inner.clone(),
+            commit,
+        })
+    }
+}
+
+/// Keeps a commit from being deleted by the [`Janitor`] while alive.
+pub struct CommitGuard {
+    inner: Arc<Inner>,
+    commit: [u8; 32],
+}
+
+impl CommitGuard {
+    pub fn commit(&self) -> [u8; 32] {
+        self.commit
+    }
+}
+
+impl Drop for CommitGuard {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock().expect("lock to be acquired");
+
+        if let Some(count) = state.pinned.get_mut(&self.commit) {
+            *count -= 1;
+            if *count == 0 {
+                state.pinned.remove(&self.commit);
+                self.inner.cvar.notify_one();
+            }
+        }
+    }
+}
+
+impl Inner {
+    fn persist(&self, state: &State) {
+        let commits: Vec<_> = state.pending.iter().map(|p| p.commit).collect();
+        if let Err(err) = write_pending(&self.dir, &commits) {
+            warn!("failed persisting pending deletions: {err}");
+        }
+    }
+
+    /// Performs the next due deletion, or waits until one is due.
+    fn tick(&self) {
+        let mut state = self.state.lock().expect("lock to be acquired");
+
+        let now = Instant::now();
+        let pinned = &state.pinned;
+        let due = state
+            .pending
+            .iter()
+            .position(|p| p.next_try <= now && !pinned.contains_key(&p.commit));
+        let Some(idx) = due else {
+            let wait = state
+                .pending
+                .iter()
+                .filter(|p| !pinned.contains_key(&p.commit))
+                .map(|p| p.next_try.saturating_duration_since(now))
+                .min()
+                .unwrap_or(IDLE_WAIT)
+                .min(IDLE_WAIT);
+            let _ = self.cvar.wait_timeout(state, wait);
+            return;
+        };
+
+        let mut pending = state.pending.remove(idx).expect("index to exist");
+        state.deleting = Some(pending.commit);
+        drop(state);
+
+        // Deleting a commit may block until it is no longer in use
+        let commit_dir = self.dir.join(hex::encode(pending.commit));
+        let size = dir_size(&commit_dir);
+        let result =
+            COMMIT_DELETION.run(|| self.vm.delete_commit(pending.commit));
+        pending.attempts += 1;
+
+        let mut state = self.state.lock().expect("lock to be acquired");
+        state.deleting = None;
+        let commit = hex::encode(pending.commit);
+        match result {
+            Ok(_) => {
+                info!("deleted commit {commit}, reclaimed {size} bytes");
+                state.reclaimed_bytes += size;
+                push_finished(&mut state, pending.attempts, size, None, commit);
+            }
+            Err(err) if pending.attempts >= MAX_ATTEMPTS => {
+                warn!("giving up deleting commit {commit}: {err}");
+                let err = Some(format!("{err}"));
+                push_finished(&mut state, pending.attempts, 0, err, commit);
+            }
+            Err(err) => {
+                let backoff = BASE_BACKOFF
+                    .saturating_mul(1 << pending.attempts.min(16))
+                    .min(MAX_BACKOFF);
+                warn!("failed deleting commit {commit}: {err}, retry in {backoff:?}");
+                pending.next_try = Instant::now() + backoff;
+                state.pending.push_back(pending);
+            }
+        }
+
+        self.persist(&state);
+    }
+}
+
+fn run(inner: Weak<Inner>) {
+    // The thread stops as soon as the janitor is dropped
+    while let Some(inner) = inner.upgrade() {
+        inner.tick();
+    }
+}
+
+fn push_finished(
+    state: &mut State,
+    attempts: u32,
+    reclaimed_bytes: u64,
+    error: Option<String>,
+    commit: String,
+) {
+    if state.finished.len() >= MAX_FINISHED {
+        state.finished.pop_front();
+    }
+    state.finished.push_back(FinishedDeletion {
+        commit,
+        attempts,
+        reclaimed_bytes,
+        error,
+    });
+}
+
+fn read_pending(dir: &Path) -> io::Result<Vec<[u8; 32]>> {
+    let path = dir.join(PENDING_DELETIONS_FILE);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| {
+            let mut commit = [0u8; 32];
+            hex::decode_to_slice(line.trim(), &mut commit).ok()?;
+            Some(commit)
+        })
+        .collect())
+}
+
+fn write_pending(dir: &Path, commits: &[[u8; 32]]) -> io::Result<()> {
+    let path = dir.join(PENDING_DELETIONS_FILE);
+    let tmp = path.with_extension("tmp");
+
+    let content: String = commits
+        .iter()
+        .map(|commit| format!("{}\n", hex::encode(commit)))
+        .collect();
+    fs::write(&tmp, content)?;
+    fs::rename(tmp, path)
+}
+
+/// Returns the size of the files within a directory, recursively.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(md) if md.is_dir() => dir_size(&entry.path()),
+            Ok(md) => md.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_deletions_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        assert!(read_pending(dir).unwrap().is_empty());
+
+        let commits = vec![[1u8; 32], [2u8; 32]];
+        write_pending(dir, &commits).unwrap();
+        assert_eq!(read_pending(dir).unwrap(), commits);
+
+        write_pending(dir, &[]).unwrap();
+        assert!(read_pending(dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn pinned_commits_are_not_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let vm = Arc::new(rusk_abi::new_ephemeral_vm().unwrap());
+        let janitor = Janitor::new(vm, dir.path()).unwrap();
+
+        let commit = [3u8; 32];
+        let guard = janitor.pin(commit).expect("commit to be pinned");
+        let other = janitor.pin(commit).expect("commit to be pinned");
+        janitor.schedule(vec![commit]);
+
+        thread::sleep(Duration::from_millis(100));
+        let status = janitor.status();
+        assert_eq!(status.pending, vec![hex::encode(commit)]);
+        assert_eq!(status.pinned, vec![hex::encode(commit)]);
+        assert!(status.finished.is_empty());
+
+        drop(guard);
+        assert_eq!(janitor.status().pinned.len(), 1);
+        drop(other);
+        assert!(janitor.status().pinned.is_empty());
+    }
+
+    #[test]
+    fn cancelled_deletions_are_not_pending() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let vm = Arc::new(rusk_abi::new_ephemeral_vm().unwrap());
+        let janitor = Janitor::new(vm, dir.path()).unwrap();
+
+        let (kept, deleted) = ([4u8; 32], [5u8; 32]);
+        let _guards = [janitor.pin(kept), janitor.pin(deleted)];
+        janitor.schedule(vec![kept, deleted]);
+
+        janitor.cancel(kept);
+        assert_eq!(janitor.status().pending, vec![hex::encode(deleted)]);
+        assert_eq!(read_pending(dir.path()).unwrap(), vec![deleted]);
+    }
+}
@@ -0,0 +1,149 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use rusk_abi::{Error as PiecrustError, Session};
+use tracing::info;
+
+use crate::{Error, Result};
+
+/// A state migration, transforming the state ahead of the transactions of
+/// the block it is activated at.
+///
+/// It is given the gas limit of the migration, which the calls it makes must
+/// fit in, and returns the migrated session along with the gas spent. It must
+/// be deterministic, since every node runs it to reach the same
+/// post-migration root.
+pub type MigrationFn =
+    fn(Session, u64) -> std::result::Result<(Session, u64), PiecrustError>;
+
+/// A migration shipped with this release, along with the network and the
+/// block height it is activated at.
+struct RegisteredMigration {
+    name: &'static str,
+    chain_id: u8,
+    height: u64,
+    gas_limit: u64,
+    run: MigrationFn,
+}
+
+/// Migrations shipped with this release.
+///
+/// A migration is added here by the release introducing it, along with its
+/// activation height, so that every node of the network runs it at the same
+/// block.
+const REGISTRY: &[RegisteredMigration] = &[];
+
+#[derive(Clone)]
+struct StateMigration {
+    name: String,
+    height: u64,
+    gas_limit: u64,
+    run: MigrationFn,
+}
+
+/// Migrations activated at given block heights, run within the execution of
+/// those blocks.
+#[derive(Clone, Default)]
+pub struct Migrations(Vec<StateMigration>);
+
+impl Migrations {
+    /// Activates the registered migrations of the network with the given
+    /// `chain_id`.
+    pub fn for_chain(chain_id: u8) -> Self {
+        REGISTRY.iter().filter(|m| m.chain_id == chain_id).fold(
+            Self::default(),
+            |migrations, m| {
+                migrations.register(m.name, m.height, m.gas_limit, m.run)
+            },
+        )
+    }
+
+    /// Activates `run` at block `height`, within `gas_limit`.
+    ///
+    /// Migrations activated at the same height run in the order of their
+    /// names.
+    pub fn register<N: Into<String>>(
+        mut self,
+        name: N,
+        height: u64,
+        gas_limit: u64,
+        run: MigrationFn,
+    ) -> Self {
+        self.0.push(StateMigration {
+            name: name.into(),
+            height,
+            gas_limit,
+            run,
+        });
+        self.0
+            .sort_by(|a, b| (a.height, &a.name).cmp(&(b.height, &b.name)));
+        self
+    }
+
+    /// Returns the names of the migrations activated at block `height`.
+    pub fn at(&self, height: u64) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter(|m| m.height == height)
+            .map(|m| m.name.as_str())
+            .collect()
+    }
+
+    /// Runs the migrations activated at `block_height` on `session`.
+    ///
+    /// Fails if a migration spends more than its gas limit.
+    pub(crate) fn run(
+        &self,
+        mut session: Session,
+        block_height: u64,
+    ) -> Result<Session> {
+        for migration in self.0.iter().filter(|m| m.height == block_height) {
+            let (migrated, gas_spent) =
+                (migration.run)(session, migration.gas_limit)?;
+            if gas_spent > migration.gas_limit {
+                return Err(Error::Other(
+                    format!(
+                        "Migration {} spent {gas_spent} gas, over its limit {}",
+                        migration.name, migration.gas_limit
+                    )
+                    .into(),
+                ));
+            }
+            session = migrated;
+            info!(
+                event = "state migrated",
+                migration = %migration.name,
+                height = block_height,
+                gas_spent
+            );
+        }
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop(
+        session: Session,
+        _gas_limit: u64,
+    ) -> std::result::Result<(Session, u64), PiecrustError> {
+        Ok((session, 0))
+    }
+
+    #[test]
+    fn migrations_are_activated_by_height() {
+        let migrations = Migrations::default()
+            .register("b", 10, 0, noop)
+            .register("c", 5, 0, noop)
+            .register("a", 10, 0, noop);
+
+        assert_eq!(migrations.at(10), vec!["a", "b"]);
+        assert_eq!(migrations.at(5), vec!["c"]);
+        assert!(migrations.at(11).is_empty());
+    }
+}
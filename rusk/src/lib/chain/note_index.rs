@@ -0,0 +1,393 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+
+use phoenix_core::transaction::TreeLeaf;
+
+use crate::{Error, Result};
+
+/// File, within the state directory, holding the indexed leaves
+const NOTE_INDEX_FILE: &str = "notes.index";
+
+/// Leaves of the transfer tree, indexed by position as blocks are accepted.
+///
+/// Historical commits get deleted once blocks are finalized, so the leaves
+/// are kept in a file of their own, independent of any commit. Each record
+/// is the length (u32 LE) of the rkyv serialized leaf, followed by the leaf
+/// itself, exactly as fed by the transfer contract.
+///
+/// The index follows the tip of the state: leaves of reverted blocks are
+/// dropped and the new ones appended on [`NoteIndex::sync`].
+///
+/// Leaves are read through a [`LeafReader`], so that they can be read
+/// without holding a lock on the index.
+pub(crate) struct NoteIndex {
+    file: File,
+    path: PathBuf,
+    /// Offset in the file of the leaf at each position
+    offsets: Vec<u64>,
+    /// Block height of the leaf at each position
+    heights: Vec<u64>,
+    /// End of the last record
+    end: u64,
+    /// Commit the index was last synced with
+    commit: Option<[u8; 32]>,
+    /// Number of times records were dropped from the file
+    truncations: Arc<AtomicU64>,
+}
+
+impl NoteIndex {
+    /// Opens the index in the state `dir`, creating it if missing.
+    ///
+    /// A record left incomplete by a crash is discarded.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let path = dir.as_ref().join(NOTE_INDEX_FILE);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        let mut index = Self {
+            file,
+            path,
+            offsets: vec![],
+            heights: vec![],
+            end: 0,
+            commit: None,
+            truncations: Default::default(),
+        };
+
+        let mut reader = BufReader::new(&index.file);
+        let mut len = [0u8; 4];
+        loop {
+            match reader.read_exact(&mut len) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                res => res?,
+            }
+            let mut leaf = vec![0u8; u32::from_le_bytes(len) as usize];
+            match reader.read_exact(&mut leaf) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                res => res?,
+            }
+            let Ok(height) = block_height(&leaf) else {
+                break;
+            };
+
+            index.offsets.push(index.end);
+            index.heights.push(height);
+            index.end += 4 + leaf.len() as u64;
+        }
+        index.file.set_len(index.end)?;
+
+        Ok(index)
+    }
+
+    /// Returns the number of indexed leaves.
+    pub fn len(&self) -> u64 {
+        self.offsets.len() as u64
+    }
+
+    /// Returns the commit the index is in sync with, if any.
+    pub fn commit(&self) -> Option<[u8; 32]> {
+        self.commit
+    }
+
+    /// Returns a reader of the indexed leaves, starting from the given
+    /// block `height`.
+    pub fn leaves_from_height(&self, height: u64) -> Result<LeafReader> {
+        let from = self.heights.partition_point(|h| *h < height) as u64;
+        self.reader(from..self.len())
+    }
+
+    /// Returns a reader of the leaves at the given positions, all of which
+    /// must be indexed.
+    ///
+    /// Only the offsets of the leaves are copied, the reader having a handle
+    /// of its own on the file.
+    pub fn reader<I>(&self, positions: I) -> Result<LeafReader>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let records = positions
+            .into_iter()
+            .map(|pos| {
+                let offset = self.offsets[pos as usize];
+                let next = self
+                    .offsets
+                    .get(pos as usize + 1)
+                    .copied()
+                    .unwrap_or(self.end);
+                (offset, next - offset)
+            })
+            .collect::<Vec<_>>();
+
+        Ok(LeafReader {
+            file: File::open(&self.path)?,
+            records: records.into_iter(),
+            truncations: self.truncations.clone(),
+            snapshot: self.truncations.load(Ordering::SeqCst),
+        })
+    }
+
+    /// Brings the index in sync with `commit`, holding `num_notes` leaves.
+    ///
+    /// `leaves_from_pos` returns the leaves of `commit` starting from the
+    /// given position. The last indexed leaf is checked against the one of
+    /// `commit`, the leaves of the blocks that do not match being dropped,
+    /// before the missing leaves are appended.
+    pub fn sync<F>(
+        &mut self,
+        commit: [u8; 32],
+        num_notes: u64,
+        mut leaves_from_pos: F,
+    ) -> Result<()>
+    where
+        F: FnMut(u64) -> Result<Vec<Vec<u8>>>,
+    {
+        self.commit = None;
+        self.truncate(self.len().min(num_notes))?;
+
+        let leaves = loop {
+            if self.len() == 0 {
+                break leaves_from_pos(0)?;
+            }
+
+            let last = self.len() - 1;
+            let mut leaves = leaves_from_pos(last)?;
+            if leaves.first() == Some(&self.leaf(last)?) {
+                leaves.remove(0);
+                break leaves;
+            }
+
+            // The block of the last leaf was reverted, drop all its leaves
+            let height = self.heights[last as usize];
+            let len = self.heights.partition_point(|h| *h < height);
+            self.truncate(len as u64)?;
+        };
+
+        self.append(leaves)?;
+        self.commit = Some(commit);
+
+        Ok(())
+    }
+
+    /// Returns the rkyv serialized leaf at position `pos`.
+    fn leaf(&mut self, pos: u64) -> Result<Vec<u8>> {
+        let offset = self.offsets[pos as usize];
+        let next = self
+            .offsets
+            .get(pos as usize + 1)
+            .copied()
+            .unwrap_or(self.end);
+
+        let mut record = vec![0u8; (next - offset) as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut record)?;
+        record.drain(..4);
+
+        Ok(record)
+    }
+
+    fn append(&mut self, leaves: Vec<Vec<u8>>) -> Result<()> {
+        let mut records = vec![];
+        for leaf in leaves {
+            self.offsets.push(self.end + records.len() as u64);
+            self.heights.push(block_height(&leaf)?);
+            records.extend((leaf.len() as u32).to_le_bytes());
+            records.extend(leaf);
+        }
+
+        self.file.seek(SeekFrom::Start(self.end))?;
+        self.file.write_all(&records)?;
+        self.end += records.len() as u64;
+
+        Ok(())
+    }
+
+    fn truncate(&mut self, len: u64) -> Result<()> {
+        if len >= self.len() {
+            return Ok(());
+        }
+
+        // Readers must notice before the records are overwritten
+        self.truncations.fetch_add(1, Ordering::SeqCst);
+
+        self.end = self.offsets[len as usize];
+        self.offsets.truncate(len as usize);
+        self.heights.truncate(len as usize);
+        self.file.set_len(self.end)?;
+
+        Ok(())
+    }
+}
+
+/// Reader of a snapshot of the leaves of a [`NoteIndex`], yielding the rkyv
+/// serialized leaves.
+///
+/// Records are only ever dropped when blocks are reverted: a reader fails
+/// as soon as the index is truncated, rather than yielding the leaves
+/// appended in place of the ones it was created for.
+pub(crate) struct LeafReader {
+    file: File,
+    /// Offset and length of the records left to read
+    records: std::vec::IntoIter<(u64, u64)>,
+    truncations: Arc<AtomicU64>,
+    /// Number of truncations of the index when the reader was created
+    snapshot: u64,
+}
+
+impl LeafReader {
+    /// Feeds `sender` with the leaves, until either is exhausted.
+    pub fn feed(self, sender: mpsc::Sender<Vec<u8>>) -> Result<()> {
+        for leaf in self {
+            if sender.send(leaf?).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, offset: u64, len: u64) -> Result<Vec<u8>> {
+        let mut record = vec![0u8; len as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut record)?;
+        record.drain(..4);
+
+        Ok(record)
+    }
+}
+
+impl Iterator for LeafReader {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (offset, len) = self.records.next()?;
+        let record = self.read(offset, len);
+
+        if self.truncations.load(Ordering::SeqCst) != self.snapshot {
+            self.records = Vec::new().into_iter();
+            return Some(Err(Error::Other(
+                "The note index was reverted while reading".into(),
+            )));
+        }
+        Some(record)
+    }
+}
+
+fn block_height(leaf: &[u8]) -> Result<u64> {
+    rkyv::from_bytes::<TreeLeaf>(leaf)
+        .map(|leaf| leaf.block_height)
+        .map_err(|_| Error::Other("Invalid indexed leaf".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use dusk_pki::SecretSpendKey;
+    use phoenix_core::Note;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn leaves(heights: &[u64], seed: u64) -> Vec<Vec<u8>> {
+        let rng = &mut StdRng::seed_from_u64(seed);
+        let psk = SecretSpendKey::random(rng).public_spend_key();
+
+        heights
+            .iter()
+            .map(|&block_height| {
+                let note = Note::transparent(rng, &psk, 100);
+                let leaf = TreeLeaf { block_height, note };
+                rkyv::to_bytes::<_, 1024>(&leaf).unwrap().into_vec()
+            })
+            .collect()
+    }
+
+    fn indexed(index: &mut NoteIndex, height: u64) -> Vec<Vec<u8>> {
+        let (sender, receiver) = mpsc::channel();
+        index
+            .leaves_from_height(height)
+            .unwrap()
+            .feed(sender)
+            .unwrap();
+        receiver.into_iter().collect()
+    }
+
+    fn sync(index: &mut NoteIndex, commit: u8, state: &[Vec<u8>]) {
+        index
+            .sync([commit; 32], state.len() as u64, |pos| {
+                Ok(state[pos as usize..].to_vec())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn follows_the_tip_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = NoteIndex::open(&dir).unwrap();
+
+        let mut state = leaves(&[0, 1, 1, 2], 1);
+        sync(&mut index, 1, &state);
+        assert_eq!(index.commit(), Some([1; 32]));
+        assert_eq!(indexed(&mut index, 0), state);
+        assert_eq!(indexed(&mut index, 2), state[3..]);
+
+        // Block 2 is reverted and another block 2 accepted, along with 3
+        state.truncate(3);
+        state.extend(leaves(&[2, 2, 3], 2));
+        sync(&mut index, 2, &state);
+        assert_eq!(indexed(&mut index, 0), state);
+
+        // Back to block 1
+        state.truncate(3);
+        sync(&mut index, 3, &state);
+        assert_eq!(indexed(&mut index, 0), state);
+
+        // The leaves survive a restart, even with a torn last record
+        drop(index);
+        let path = dir.path().join(NOTE_INDEX_FILE);
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(&[42, 0, 0, 0, 1]).unwrap();
+
+        let mut index = NoteIndex::open(&dir).unwrap();
+        assert_eq!(index.commit(), None);
+        assert_eq!(indexed(&mut index, 0), state);
+    }
+
+    #[test]
+    fn readers_fail_once_reverted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = NoteIndex::open(&dir).unwrap();
+
+        let mut state = leaves(&[0, 1, 1, 2], 1);
+        sync(&mut index, 1, &state);
+
+        // Readers are not affected by the leaves appended
+        let mut reader = index.leaves_from_height(1).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap(), state[1]);
+        state.extend(leaves(&[3], 2));
+        sync(&mut index, 2, &state);
+        assert_eq!(reader.next().unwrap().unwrap(), state[2]);
+
+        // Block 2 is reverted and another one accepted in its place
+        state.truncate(3);
+        state.extend(leaves(&[2], 3));
+        sync(&mut index, 3, &state);
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+
+        let reader = index.reader([0, 3]).unwrap();
+        let read: Vec<_> = reader.map(Result::unwrap).collect();
+        assert_eq!(read, vec![state[0].clone(), state[3].clone()]);
+    }
+}
@@ -0,0 +1,796 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::mpsc;
+use std::thread;
+
+use parking_lot::Mutex;
+
+use dusk_bls12_381::BlsScalar;
+use dusk_bytes::Serializable;
+use dusk_pki::ViewKey;
+use phoenix_core::transaction::{TreeLeaf, TRANSFER_TREE_DEPTH};
+use phoenix_core::Note;
+use poseidon_merkle::Opening as PoseidonOpening;
+use rand::{Rng, RngCore};
+use rusk_abi::TRANSFER_CONTRACT;
+use sha3::{Digest, Sha3_256};
+use tracing::{debug, info, warn};
+
+use super::note_index::NoteIndex;
+use super::RuskReader;
+use crate::budget::QUERY;
+use crate::{Error, Result};
+
+const A: usize = 4;
+
+pub type NoteOpening = PoseidonOpening<(), TRANSFER_TREE_DEPTH, A>;
+
+/// Number of recently computed openings kept in memory
+const OPENING_CACHE_SIZE: usize = 8192;
+
+/// Maximum number of openings computed by a single request
+pub const MAX_OPENINGS: usize = 256;
+
+/// Maximum number of threads computing the openings of a single request
+const MAX_OPENING_WORKERS: usize = 4;
+
+/// Gas limit of the computation of a single opening
+const OPENING_GAS_LIMIT: u64 = 0x10000000;
+
+/// Maximum number of decoys sampled by a single request
+pub const MAX_DECOYS: usize = 64;
+
+/// Maximum number of leaves scanned for a view key by a single request
+pub const MAX_SCANNED_LEAVES: u64 = 1 << 16;
+
+/// Number of view keys whose owned notes are kept in memory
+const OWNED_NOTES_CACHE_SIZE: usize = 1024;
+
+/// Openings recently computed, keyed by tree root and note position.
+///
+/// Since an opening is only valid for the root it was computed against,
+/// entries never need to be invalidated: they are simply evicted in insertion
+/// order once the cache is full.
+#[derive(Default)]
+pub(crate) struct OpeningCache {
+    openings: HashMap<([u8; 32], u64), NoteOpening>,
+    order: VecDeque<([u8; 32], u64)>,
+}
+
+impl OpeningCache {
+    fn get(&self, root: &[u8; 32], pos: u64) -> Option<NoteOpening> {
+        self.openings.get(&(*root, pos)).cloned()
+    }
+
+    fn insert(&mut self, root: [u8; 32], pos: u64, opening: NoteOpening) {
+        if self.openings.insert((root, pos), opening).is_some() {
+            return;
+        }
+        self.order.push_back((root, pos));
+
+        while self.order.len() > OPENING_CACHE_SIZE {
+            if let Some(key) = self.order.pop_front() {
+                self.openings.remove(&key);
+            }
+        }
+    }
+}
+
+/// Notes owned by a view key, found scanning the note index.
+#[derive(Default)]
+struct OwnedNotes {
+    /// Number of leaves scanned from the start of the index
+    scanned: u64,
+    /// Last leaf scanned, telling whether the index dropped it since
+    last: Vec<u8>,
+    /// Owned notes, along with their value
+    notes: Vec<(u64, Note)>,
+}
+
+impl OwnedNotes {
+    /// Scans at most `max` of the leaves indexed since the last scan.
+    ///
+    /// The index is only locked to snapshot the leaves to scan, which are
+    /// read once it is released.
+    fn scan(
+        &mut self,
+        index: &Mutex<NoteIndex>,
+        vk: &ViewKey,
+        max: u64,
+    ) -> Result<()> {
+        let (len, mut leaves) = {
+            let index = index.lock();
+            let len = index.len();
+            // The last leaf scanned is read again, to tell whether it was
+            // dropped since
+            let from = self.scanned.saturating_sub(1).min(len);
+            let end = len.min(self.scanned.saturating_add(max));
+            (len, index.reader(from..end)?)
+        };
+
+        // The leaves of reverted blocks are dropped from the index
+        if self.scanned > 0 {
+            let last = leaves.next().transpose()?;
+            if self.scanned > len || last.as_ref() != Some(&self.last) {
+                *self = Self::default();
+                return self.scan(index, vk, max);
+            }
+        }
+
+        for bytes in leaves {
+            let bytes = bytes?;
+            let note = indexed_note(&bytes)?;
+            if vk.owns(&note) {
+                if let Ok(value) = note.value(Some(vk)) {
+                    self.notes.push((value, note));
+                }
+            }
+            self.last = bytes;
+            self.scanned += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Notes owned by the view keys of recent requests, so that the leaves of
+/// the note index are scanned once per view key rather than on every
+/// request.
+///
+/// Entries are evicted in insertion order once the cache is full.
+#[derive(Default)]
+pub(crate) struct OwnedNotesCache {
+    owned: HashMap<[u8; 32], OwnedNotes>,
+    order: VecDeque<[u8; 32]>,
+}
+
+impl OwnedNotesCache {
+    fn take(&mut self, key: &[u8; 32]) -> OwnedNotes {
+        self.owned.remove(key).unwrap_or_default()
+    }
+
+    fn insert(&mut self, key: [u8; 32], owned: OwnedNotes) {
+        if self.owned.insert(key, owned).is_some() {
+            return;
+        }
+        self.order.retain(|k| k != &key);
+        self.order.push_back(key);
+
+        while self.order.len() > OWNED_NOTES_CACHE_SIZE {
+            if let Some(key) = self.order.pop_front() {
+                self.owned.remove(&key);
+            }
+        }
+    }
+}
+
+impl RuskReader {
+    /// Selects the notes owned by `vk` to be spent to cover `target`, along
+    /// with their openings.
+    ///
+    /// At most `max_inputs` notes are selected, preferring the set with the
+    /// smallest change. Since spent notes cannot be told apart using a view
+    /// key, notes known to be spent can be excluded by passing their
+    /// positions in `exclude`.
+    ///
+    /// The notes are selected at the given `state_root`, or at the current
+    /// one if `None`.
+    pub fn select_notes(
+        &self,
+        vk: &ViewKey,
+        target: u64,
+        max_inputs: usize,
+        exclude: &[u64],
+        state_root: Option<[u8; 32]>,
+    ) -> Result<Vec<(Note, NoteOpening)>> {
+        info!("Received select_notes request");
+
+        let commit = state_root.unwrap_or_else(|| self.state_root());
+        let (_, notes) =
+            self.select_notes_at(vk, target, max_inputs, exclude, commit)?;
+
+        Ok(notes)
+    }
+
+    /// Selects the notes to spend as [`Self::select_notes`] does, at the
+    /// given `commit`, along with the root of the transfer tree their
+    /// openings are valid for.
+    fn select_notes_at(
+        &self,
+        vk: &ViewKey,
+        target: u64,
+        max_inputs: usize,
+        exclude: &[u64],
+        commit: [u8; 32],
+    ) -> Result<(BlsScalar, Vec<(Note, NoteOpening)>)> {
+        // Notes and openings must come from the same state
+        let _guard = self.pin(commit)?;
+
+        let num_notes: u64 =
+            self.query_at(commit, TRANSFER_CONTRACT, "num_notes", &())?;
+        let candidates = self
+            .owned_notes(vk, commit, num_notes)?
+            .into_iter()
+            .filter(|(_, note)| !exclude.contains(note.pos()))
+            .collect();
+
+        let notes = select_inputs(candidates, target, max_inputs)
+            .ok_or(Error::NotEnoughNotes(target, max_inputs))?;
+
+        self.with_openings(notes, commit)
+    }
+
+    /// Returns the notes at the given positions along with their openings,
+    /// the root of the transfer tree they are valid for and the state root
+    /// they are read at.
+    ///
+    /// Meant for wallets building the proof of a transaction, which find
+    /// their notes scanning the leaves themselves so that their view key is
+    /// never sent to a node. The notes are read at the state the note index
+    /// is in sync with, usually the current one.
+    ///
+    /// At most [`MAX_OPENINGS`] may be requested at once.
+    pub fn spend_inputs(
+        &self,
+        positions: &[u64],
+    ) -> Result<([u8; 32], BlsScalar, Vec<(Note, NoteOpening)>)> {
+        info!("Received spend_inputs request");
+
+        if positions.len() > MAX_OPENINGS {
+            return Err(Error::TooManyOpenings(positions.len(), MAX_OPENINGS));
+        }
+
+        let (commit, leaves) = {
+            let index = self.note_index.lock();
+            let commit = index.commit().ok_or_else(|| {
+                Error::Other("The note index is not synced".into())
+            })?;
+
+            if let Some(&pos) = positions.iter().find(|&&p| p >= index.len()) {
+                return Err(Error::OpeningPositionNotFound(pos));
+            }
+            (commit, index.reader(positions.iter().copied())?)
+        };
+
+        let notes = leaves
+            .map(|leaf| indexed_note(&leaf?))
+            .collect::<Result<Vec<_>>>()?;
+        let (root, notes) = self.with_openings(notes, commit)?;
+        Ok((commit, root, notes))
+    }
+
+    /// Pairs the given notes with their openings at `commit`, returning the
+    /// root of the transfer tree they are valid for.
+    fn with_openings(
+        &self,
+        notes: Vec<Note>,
+        commit: [u8; 32],
+    ) -> Result<(BlsScalar, Vec<(Note, NoteOpening)>)> {
+        // Openings must come from the same state as the notes
+        let _guard = self.pin(commit)?;
+
+        let positions: Vec<_> = notes.iter().map(|note| *note.pos()).collect();
+        let (root, openings) = self.openings(&positions, Some(commit))?;
+
+        let notes = notes
+            .into_iter()
+            .zip(openings)
+            .map(|(note, opening)| {
+                let opening = opening
+                    .ok_or(Error::OpeningPositionNotFound(*note.pos()))?;
+                Ok((note, opening))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok((root, notes))
+    }
+
+    /// Returns the notes owned by `vk` among the `num_notes` leaves of the
+    /// transfer tree at `commit`, along with their value.
+    ///
+    /// The notes are found scanning the note index, only the leaves indexed
+    /// since the last request with the same view key being scanned. A single
+    /// request scans at most [`MAX_SCANNED_LEAVES`], failing with
+    /// [`Error::ScanIncomplete`] until all the leaves are, so that it is
+    /// retried to resume the scan.
+    fn owned_notes(
+        &self,
+        vk: &ViewKey,
+        commit: [u8; 32],
+        num_notes: u64,
+    ) -> Result<Vec<(u64, Note)>> {
+        let last = {
+            let index = self.note_index.lock();
+            if index.len() < num_notes {
+                return Err(Error::Other(
+                    "The note index is not synced".into(),
+                ));
+            }
+
+            // The index follows the tip, thus it only holds the leaves of an
+            // ancestor of it
+            match num_notes > 0 && index.commit() != Some(commit) {
+                true => Some(index.reader([num_notes - 1])?),
+                false => None,
+            }
+        };
+
+        if let Some(mut last) = last {
+            let (sender, receiver) = mpsc::channel();
+            self.feeder_query(
+                TRANSFER_CONTRACT,
+                "leaves_from_pos",
+                &(num_notes - 1),
+                sender,
+                Some(commit),
+            )?;
+            if receiver.try_iter().next() != last.next().transpose()? {
+                return Err(Error::Other(
+                    "The note index does not follow the state".into(),
+                ));
+            }
+        }
+
+        let key: [u8; 32] = Sha3_256::digest(vk.to_bytes()).into();
+        let mut cache = self.owned_notes.lock();
+        let mut owned = cache.take(&key);
+
+        // A failed scan drops the entry, to be scanned again from scratch
+        owned.scan(&self.note_index, vk, MAX_SCANNED_LEAVES)?;
+        let notes = owned
+            .notes
+            .iter()
+            .filter(|(_, note)| *note.pos() < num_notes)
+            .cloned()
+            .collect();
+        let progress = owned.scanned;
+        cache.insert(key, owned);
+
+        if progress < num_notes {
+            return Err(Error::ScanIncomplete(progress, num_notes));
+        }
+        Ok(notes)
+    }
+
+    /// Samples up to `count` notes uniformly among the leaves of the transfer
+    /// tree, along with their openings and the root of the tree they are
+    /// valid for.
+    ///
+    /// Wallets mix the decoys with their own notes when requesting openings,
+    /// so that the node serving them cannot tell which notes they own. The
+    /// wallets filter their own notes out of the decoys, rather than telling
+    /// the node which ones to exclude.
+    ///
+    /// The notes are sampled at the state the note index is in sync with,
+    /// usually the current one.
+    pub fn decoy_openings(
+        &self,
+        count: usize,
+    ) -> Result<(BlsScalar, Vec<(Note, NoteOpening)>)> {
+        info!("Received decoy_openings request");
+
+        let (commit, leaves) = {
+            let index = self.note_index.lock();
+            let commit = index.commit().ok_or_else(|| {
+                Error::Other("The note index is not synced".into())
+            })?;
+
+            let positions = sample_positions(
+                &mut rand::thread_rng(),
+                index.len(),
+                count.min(MAX_DECOYS),
+            );
+            (commit, index.reader(positions)?)
+        };
+
+        let notes = leaves
+            .map(|leaf| indexed_note(&leaf?))
+            .collect::<Result<Vec<_>>>()?;
+        self.with_openings(notes, commit)
+    }
+
+    /// Feeds `sender` with the rkyv serialized leaves of the transfer tree,
+    /// starting from the given block `height`.
+    ///
+    /// The leaves are read at the given `state_root`, or at the current one
+    /// if `None`. They are served from the note index when it is in sync
+    /// with that state, and from the transfer contract otherwise.
+    pub fn feed_leaves(
+        &self,
+        height: u64,
+        sender: mpsc::Sender<Vec<u8>>,
+        state_root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let commit = state_root.unwrap_or_else(|| self.state_root());
+        let leaves = {
+            let index = self.note_index.lock();
+            match index.commit() == Some(commit) {
+                true => Some(index.leaves_from_height(height)?),
+                false => None,
+            }
+        };
+        // The leaves are read once the index is released, so that slow
+        // receivers never hold back its sync
+        if let Some(leaves) = leaves {
+            return leaves.feed(sender);
+        }
+
+        let _guard = self.pin(commit)?;
+        self.feeder_query(
+            TRANSFER_CONTRACT,
+            "leaves_from_height",
+            &height,
+            sender,
+            Some(commit),
+        )
+    }
+
+    /// Brings the note index in sync with the current state.
+    ///
+    /// Called in the background whenever the tip moves, so that the leaves
+    /// stay available even once the commits they were added in are deleted.
+    /// Failures are only logged: the leaves are then read from the contract
+    /// until the next successful sync.
+    pub(crate) fn sync_note_index(&self) {
+        let commit = self.state_root();
+        let indexed = {
+            let index = self.note_index.lock();
+            if index.commit() == Some(commit) {
+                return;
+            }
+            index.len()
+        };
+
+        let leaves_from_pos = |pos: u64| -> Result<Vec<Vec<u8>>> {
+            let (sender, receiver) = mpsc::channel();
+            self.feeder_query(
+                TRANSFER_CONTRACT,
+                "leaves_from_pos",
+                &pos,
+                sender,
+                Some(commit),
+            )?;
+            Ok(receiver.try_iter().collect())
+        };
+
+        let synced = self.pin(commit).and_then(|_guard| {
+            let num_notes: u64 =
+                self.query_at(commit, TRANSFER_CONTRACT, "num_notes", &())?;
+
+            // The new leaves are read before locking the index, so that the
+            // readers only wait for them to be appended. Only the leaves of
+            // reverted blocks are read with the index locked.
+            let from = indexed.min(num_notes).saturating_sub(1);
+            let mut prefetched = Some((from, leaves_from_pos(from)?));
+
+            let mut index = self.note_index.lock();
+            index.sync(commit, num_notes, |pos| match prefetched.take() {
+                Some((from, leaves)) if from == pos => Ok(leaves),
+                _ => leaves_from_pos(pos),
+            })?;
+            Ok(index.len())
+        });
+
+        match synced {
+            Ok(notes) => debug!(
+                event = "note index synced",
+                commit = hex::encode(commit),
+                notes
+            ),
+            Err(e) => warn!(
+                "Cannot sync the note index with {}: {e}",
+                hex::encode(commit)
+            ),
+        }
+    }
+
+    /// Returns the openings of the notes at the given positions, along with
+    /// the root of the transfer tree they are valid for.
+    ///
+    /// All openings are computed against the same state, the given
+    /// `state_root` or the current one if `None`. Openings already computed
+    /// for the same root are served from memory, while the missing ones are
+    /// computed by a few workers, within the [`QUERY`] budget.
+    ///
+    /// At most [`MAX_OPENINGS`] may be requested at once.
+    pub fn openings(
+        &self,
+        positions: &[u64],
+        state_root: Option<[u8; 32]>,
+    ) -> Result<(BlsScalar, Vec<Option<NoteOpening>>)> {
+        info!("Received openings request");
+
+        if positions.len() > MAX_OPENINGS {
+            return Err(Error::TooManyOpenings(positions.len(), MAX_OPENINGS));
+        }
+
+        let commit = state_root.unwrap_or_else(|| self.state_root());
+        let root: BlsScalar =
+            self.query_at(commit, TRANSFER_CONTRACT, "root", &())?;
+        let root_bytes = root.to_bytes();
+
+        let mut openings: Vec<Option<NoteOpening>> = {
+            let cache = self.openings.lock();
+            positions
+                .iter()
+                .map(|pos| cache.get(&root_bytes, *pos))
+                .collect()
+        };
+
+        let missing: Vec<_> = positions
+            .iter()
+            .zip(&openings)
+            .filter(|(_, opening)| opening.is_none())
+            .map(|(pos, _)| *pos)
+            .collect();
+        if missing.is_empty() {
+            return Ok((root, openings));
+        }
+
+        let workers = thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(MAX_OPENING_WORKERS)
+            .min(missing.len());
+        let chunk_size = missing.len().div_ceil(workers);
+
+        let computed = thread::scope(|s| {
+            let handles: Vec<_> = missing
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let reader = self.clone();
+                    s.spawn(move || {
+                        QUERY.run(|| reader.openings_at(commit, chunk))
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("opening worker not to panic"))
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        let computed: HashMap<_, _> = computed.into_iter().flatten().collect();
+
+        let mut cache = self.openings.lock();
+        for (pos, opening) in positions.iter().zip(openings.iter_mut()) {
+            if opening.is_none() {
+                *opening = computed.get(pos).cloned().flatten();
+                if let Some(opening) = opening {
+                    cache.insert(root_bytes, *pos, opening.clone());
+                }
+            }
+        }
+
+        Ok((root, openings))
+    }
+
+    /// Computes the openings of the notes at the given positions in a single
+    /// session at `commit`, each within [`OPENING_GAS_LIMIT`].
+    fn openings_at(
+        &self,
+        commit: [u8; 32],
+        positions: &[u64],
+    ) -> Result<Vec<(u64, Option<NoteOpening>)>> {
+        // The guard must outlive the session
+        let _guard = self.pin(commit)?;
+        let mut session = self.session(0, Some(commit))?;
+
+        positions
+            .iter()
+            .map(|pos| {
+                let opening = session
+                    .call::<_, Option<NoteOpening>>(
+                        TRANSFER_CONTRACT,
+                        "opening",
+                        pos,
+                        OPENING_GAS_LIMIT,
+                    )?
+                    .data;
+                Ok((*pos, opening))
+            })
+            .collect()
+    }
+}
+
+/// Deserializes the note of a leaf read from the note index.
+fn indexed_note(leaf: &[u8]) -> Result<Note> {
+    rkyv::from_bytes::<TreeLeaf>(leaf)
+        .map(|leaf| leaf.note)
+        .map_err(|_| Error::Other("Invalid indexed leaf".into()))
+}
+
+/// Samples uniformly, without replacement, up to `count` of the positions in
+/// `0..len`, returned in ascending order so that the order does not leak the
+/// sampling.
+///
+/// Floyd's algorithm draws exactly `count` random numbers, however close
+/// `count` is to `len`.
+fn sample_positions<R: RngCore>(
+    rng: &mut R,
+    len: u64,
+    count: usize,
+) -> Vec<u64> {
+    let count = (count as u64).min(len);
+
+    let mut sampled = BTreeSet::new();
+    for j in len - count..len {
+        let pos = rng.gen_range(0..=j);
+        if !sampled.insert(pos) {
+            sampled.insert(j);
+        }
+    }
+
+    sampled.into_iter().collect()
+}
+
+/// Selects at most `max_inputs` of the given `(value, item)` pairs whose
+/// values add up to at least `target`, trying to minimize the excess.
+///
+/// Returns `None` if `target` cannot be covered.
+fn select_inputs<T>(
+    mut candidates: Vec<(u64, T)>,
+    target: u64,
+    max_inputs: usize,
+) -> Option<Vec<T>> {
+    if max_inputs == 0 {
+        return None;
+    }
+
+    // Sort by descending value
+    candidates.sort_by(|a, b| b.0.cmp(&a.0));
+
+    // The smallest single input covering the target is the best fit
+    if let Some(idx) = candidates.iter().rposition(|(v, _)| *v >= target) {
+        return Some(vec![candidates.swap_remove(idx).1]);
+    }
+
+    // Otherwise, take the largest inputs until the target is covered
+    let mut sum = 0u64;
+    let mut count = 0;
+    while sum < target && count < max_inputs && count < candidates.len() {
+        sum = sum.saturating_add(candidates[count].0);
+        count += 1;
+    }
+    if sum < target {
+        return None;
+    }
+
+    // Replace the last picked input with the smallest of the remaining ones
+    // still covering the target
+    let last = count - 1;
+    let missing = target - (sum - candidates[last].0);
+    if let Some(idx) =
+        candidates[count..].iter().rposition(|(v, _)| *v >= missing)
+    {
+        candidates.swap(last, count + idx);
+    }
+
+    candidates.truncate(count);
+    Some(candidates.into_iter().map(|(_, item)| item).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use dusk_pki::SecretSpendKey;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn values(candidates: &[u64], target: u64, max: usize) -> Option<Vec<u64>> {
+        let candidates = candidates.iter().map(|v| (*v, *v)).collect();
+        select_inputs(candidates, target, max).map(|mut selected| {
+            selected.sort();
+            selected
+        })
+    }
+
+    #[test]
+    fn owned_notes_are_scanned_incrementally() {
+        let rng = &mut StdRng::seed_from_u64(0x5ca9);
+        let ssk = SecretSpendKey::random(rng);
+        let vk = ssk.view_key();
+        let other = SecretSpendKey::random(rng).public_spend_key();
+
+        let leaves = |notes: &[(u64, bool)], rng: &mut StdRng| -> Vec<_> {
+            notes
+                .iter()
+                .map(|&(block_height, owned)| {
+                    let psk = match owned {
+                        true => ssk.public_spend_key(),
+                        false => other,
+                    };
+                    let note = Note::transparent(rng, &psk, block_height + 1);
+                    let leaf = TreeLeaf { block_height, note };
+                    rkyv::to_bytes::<_, 1024>(&leaf).unwrap().into_vec()
+                })
+                .collect()
+        };
+        let sync = |index: &Mutex<NoteIndex>, commit: u8, state: &[Vec<u8>]| {
+            index
+                .lock()
+                .sync([commit; 32], state.len() as u64, |pos| {
+                    Ok(state[pos as usize..].to_vec())
+                })
+                .unwrap();
+        };
+        let values = |owned: &OwnedNotes| -> Vec<u64> {
+            owned.notes.iter().map(|(value, _)| *value).collect()
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let index = Mutex::new(NoteIndex::open(&dir).unwrap());
+        let mut state =
+            leaves(&[(0, true), (1, false), (1, true), (2, true)], rng);
+        sync(&index, 1, &state);
+
+        // At most `max` leaves are scanned at once
+        let mut owned = OwnedNotes::default();
+        owned.scan(&index, &vk, 3).unwrap();
+        assert_eq!(owned.scanned, 3);
+        assert_eq!(values(&owned), vec![1, 2]);
+        owned.scan(&index, &vk, 3).unwrap();
+        assert_eq!(owned.scanned, 4);
+        assert_eq!(values(&owned), vec![1, 2, 3]);
+
+        // Only the new leaves are scanned
+        state.extend(leaves(&[(3, true)], rng));
+        sync(&index, 2, &state);
+        owned.scan(&index, &vk, 1).unwrap();
+        assert_eq!(owned.scanned, 5);
+        assert_eq!(values(&owned), vec![1, 2, 3, 4]);
+
+        // Block 3 is reverted and another one accepted in its place
+        state.truncate(4);
+        state.extend(leaves(&[(3, false), (3, true)], rng));
+        sync(&index, 3, &state);
+        owned.scan(&index, &vk, 10).unwrap();
+        assert_eq!(owned.scanned, 6);
+        assert_eq!(values(&owned), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn decoys_are_sampled_without_replacement() {
+        let rng = &mut StdRng::seed_from_u64(0xdec0);
+
+        let sampled = sample_positions(rng, 1000, 10);
+        assert_eq!(sampled.len(), 10);
+        assert!(sampled.windows(2).all(|w| w[0] < w[1]));
+        assert!(sampled.iter().all(|pos| *pos < 1000));
+
+        // All the positions are sampled when fewer than requested
+        assert_eq!(sample_positions(rng, 5, 10), vec![0, 1, 2, 3, 4]);
+        assert_eq!(sample_positions(rng, 64, 64).len(), 64);
+        assert!(sample_positions(rng, 0, 10).is_empty());
+
+        // Each position is as likely to be sampled
+        let mut hits = [0u32; 8];
+        for _ in 0..8000 {
+            for pos in sample_positions(rng, 8, 2) {
+                hits[pos as usize] += 1;
+            }
+        }
+        assert!(hits.iter().all(|h| (1700..2300).contains(h)));
+    }
+
+    #[test]
+    fn select_inputs_minimizes_change() {
+        // Smallest single note covering the target
+        assert_eq!(values(&[5, 30, 12, 50], 10, 4), Some(vec![12]));
+
+        // Largest notes, with the last one replaced by a smaller fit
+        assert_eq!(values(&[10, 8, 6, 3, 1], 14, 4), Some(vec![6, 10]));
+        assert_eq!(values(&[10, 8, 6, 3, 1], 20, 4), Some(vec![3, 8, 10]));
+
+        // Not enough inputs
+        assert_eq!(values(&[10, 8, 6], 20, 2), None);
+        assert_eq!(values(&[10, 8], 20, 4), None);
+        assert_eq!(values(&[10], 5, 0), None);
+    }
+}
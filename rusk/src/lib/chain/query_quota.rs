@@ -0,0 +1,221 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::{Error, Result};
+
+/// Number of clients above which the ones idle for a whole window are
+/// forgotten
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Gas spent by a client within the current window
+#[derive(Debug, Clone, Copy)]
+struct Usage {
+    since: Instant,
+    spent: u64,
+}
+
+/// Metering of the gas spent by the contract queries of each client.
+///
+/// Every query runs with a gas limit, so that a single expensive query
+/// cannot keep a node busy. Each client, told apart by its IP address, is
+/// moreover given an amount of gas to spend within a time window, its
+/// queries being refused once exhausted. Local clients, connected over the
+/// unix socket, only have their queries limited.
+#[derive(Debug, Clone)]
+pub struct QueryQuotas {
+    /// Gas limit of a single query
+    query_gas: u64,
+    /// Gas a client can spend within a window
+    client_gas: u64,
+    window: Duration,
+    usage: Arc<Mutex<HashMap<IpAddr, Usage>>>,
+}
+
+/// Gas reserved for a query, out of the quota of its client.
+///
+/// The gas the query did not spend is given back once [`settled`], while a
+/// reservation dropped without being settled is charged in full.
+///
+/// [`settled`]: Reservation::settle
+#[derive(Debug)]
+pub struct Reservation {
+    quotas: QueryQuotas,
+    client: Option<(IpAddr, Instant)>,
+    gas_limit: u64,
+}
+
+impl QueryQuotas {
+    pub fn new(query_gas: u64, client_gas: u64, window: Duration) -> Self {
+        Self {
+            query_gas,
+            client_gas,
+            window,
+            usage: Arc::default(),
+        }
+    }
+
+    /// Reserves the gas limit of the next query of `client` out of its
+    /// quota, so that concurrent queries cannot spend more than it.
+    ///
+    /// Fails with [`Error::QueryQuotaExceeded`] if the client spent all its
+    /// gas within the current window.
+    pub fn reserve(&self, client: Option<IpAddr>) -> Result<Reservation> {
+        self.reserve_at(client, Instant::now())
+    }
+
+    fn reserve_at(
+        &self,
+        client: Option<IpAddr>,
+        now: Instant,
+    ) -> Result<Reservation> {
+        let Some(client) = client else {
+            return Ok(Reservation {
+                quotas: self.clone(),
+                client: None,
+                gas_limit: self.query_gas,
+            });
+        };
+
+        let mut usage = self.usage.lock();
+        if usage.len() >= MAX_TRACKED_CLIENTS {
+            usage.retain(|_, u| now.duration_since(u.since) < self.window);
+        }
+
+        let usage = usage.entry(client).or_insert(Usage {
+            since: now,
+            spent: 0,
+        });
+        let elapsed = now.duration_since(usage.since);
+        if elapsed >= self.window {
+            *usage = Usage {
+                since: now,
+                spent: 0,
+            };
+        }
+
+        let gas_limit = match self.client_gas.saturating_sub(usage.spent) {
+            0 => {
+                let retry = self.window.saturating_sub(elapsed);
+                return Err(Error::QueryQuotaExceeded(retry));
+            }
+            left => self.query_gas.min(left),
+        };
+        usage.spent += gas_limit;
+
+        Ok(Reservation {
+            quotas: self.clone(),
+            client: Some((client, usage.since)),
+            gas_limit,
+        })
+    }
+}
+
+impl Reservation {
+    /// Returns the gas limit of the query.
+    pub fn gas_limit(&self) -> u64 {
+        self.gas_limit
+    }
+
+    /// Charges the client with the `gas_spent` by the query, giving back
+    /// the rest of the reservation or charging what it spent over it.
+    pub fn settle(self, gas_spent: u64) {
+        let Some((client, since)) = self.client else {
+            return;
+        };
+
+        let mut usage = self.quotas.usage.lock();
+        let Some(usage) = usage.get_mut(&client) else {
+            return;
+        };
+
+        // Nothing is given back once the quota is renewed
+        if gas_spent > self.gas_limit {
+            let overrun = gas_spent - self.gas_limit;
+            usage.spent = usage.spent.saturating_add(overrun);
+        } else if usage.since == since {
+            let unspent = self.gas_limit - gas_spent;
+            usage.spent = usage.spent.saturating_sub(unspent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn clients_are_refused_once_their_quota_is_spent() {
+        let quotas = QueryQuotas::new(100, 250, Duration::from_secs(60));
+        let alice = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        let bob = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+        let start = Instant::now();
+
+        let reservation = quotas.reserve_at(alice, start).unwrap();
+        assert_eq!(reservation.gas_limit(), 100);
+        reservation.settle(100);
+        quotas.reserve_at(alice, start).unwrap().settle(100);
+        let reservation = quotas.reserve_at(alice, start).unwrap();
+        assert_eq!(reservation.gas_limit(), 50);
+        reservation.settle(50);
+
+        let later = start + Duration::from_secs(20);
+        assert!(matches!(
+            quotas.reserve_at(alice, later),
+            Err(Error::QueryQuotaExceeded(retry)) if retry == Duration::from_secs(40)
+        ));
+
+        // Other clients and local ones are not affected
+        assert_eq!(quotas.reserve_at(bob, later).unwrap().gas_limit(), 100);
+        quotas.reserve_at(None, later).unwrap().settle(1_000);
+        assert_eq!(quotas.reserve_at(None, later).unwrap().gas_limit(), 100);
+
+        // The quota is renewed with the next window
+        let next = start + Duration::from_secs(60);
+        quotas.reserve_at(alice, next).unwrap().settle(10);
+        assert_eq!(quotas.reserve_at(alice, next).unwrap().gas_limit(), 100);
+    }
+
+    #[test]
+    fn concurrent_queries_share_the_quota() {
+        let quotas = QueryQuotas::new(100, 250, Duration::from_secs(60));
+        let alice = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        let start = Instant::now();
+
+        // The gas of running queries is reserved until they are settled
+        let first = quotas.reserve_at(alice, start).unwrap();
+        let second = quotas.reserve_at(alice, start).unwrap();
+        let third = quotas.reserve_at(alice, start).unwrap();
+        assert_eq!(third.gas_limit(), 50);
+        assert!(quotas.reserve_at(alice, start).is_err());
+
+        // Unspent gas is given back, unless the query is dropped
+        first.settle(20);
+        drop(second);
+        assert_eq!(quotas.reserve_at(alice, start).unwrap().gas_limit(), 80);
+        assert!(quotas.reserve_at(alice, start).is_err());
+
+        // Reservations of a past window do not give gas back to the next
+        let next = start + Duration::from_secs(60);
+        let reservation = quotas.reserve_at(alice, next).unwrap();
+        third.settle(0);
+        reservation.settle(100);
+        assert_eq!(quotas.reserve_at(alice, next).unwrap().gas_limit(), 100);
+
+        // Gas spent over the reservation is charged as well
+        let reservation = quotas.reserve_at(alice, next).unwrap();
+        reservation.settle(1_000);
+        assert!(quotas.reserve_at(alice, next).is_err());
+    }
+}
@@ -4,33 +4,230 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::collections::{BTreeMap, VecDeque};
 use std::path::Path;
 use std::sync::{mpsc, Arc, LazyLock};
+use std::thread;
 use std::time::{Duration, Instant};
 use std::{fs, io};
 
-use parking_lot::RwLock;
-use sha3::{Digest, Sha3_256};
-use tokio::task;
+use bytecheck::CheckBytes;
+use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{Archive, Deserialize, Infallible, Serialize};
 use tracing::{debug, info, warn};
 
 use dusk_bls12_381::BlsScalar;
 use dusk_bls12_381_sign::PublicKey as BlsPublicKey;
 use dusk_bytes::DeserializableSlice;
 use dusk_consensus::operations::{CallParams, VerificationOutput};
-use node_data::ledger::{SpentTransaction, Transaction};
+use node_data::error::{Classified, ErrorKind};
+use node_data::ledger::{
+    CallGas, ContractEvent, EventHasher, SpentTransaction, Transaction,
+    SIZE_LIMITS,
+};
 use phoenix_core::transaction::StakeData;
 use phoenix_core::Transaction as PhoenixTransaction;
+use poseidon_merkle::Opening as PoseidonOpening;
 use rusk_abi::dusk::Dusk;
 use rusk_abi::{
-    CallReceipt, ContractError, Error as PiecrustError, Event, Session,
+    CallReceipt, ContractData, ContractError, ContractId,
+    Error as PiecrustError, Event, Session, StandardBufSerializer,
     STAKE_CONTRACT, TRANSFER_CONTRACT, VM,
 };
 use rusk_profile::to_rusk_state_id_path;
+use stake_contract_types::STAKE_TREE_DEPTH;
+use transfer_contract_types::{
+    HookEvent, Migration, ModuleId, Subscription, HOOK_FN, MIGRATE_FN,
+    MIGRATE_STATE_FN,
+};
 
-use super::{coinbase_value, emission_amount, Rusk, RuskTip};
+use super::host_gas::{host_call, HostCall, HostGas};
+use super::note_index::NoteIndex;
+use super::vm::SliceArg;
+use super::{
+    coinbase_value, CommitGuard, GasPricing, HostGasLimits, Janitor,
+    JanitorStatus, Migrations, Penalty, Rusk, RuskReader, RuskTip, SlashAmount,
+    SlashOutcome, SlashingPolicy, SLASHING_POLICY,
+};
+use crate::audit::{AuditLog, Operation, NODE_OPERATOR};
 use crate::{Error, Result};
 
+/// Number of stakes decoded by each thread when decoding in parallel.
+const PARALLEL_DECODE_THRESHOLD: usize = 256;
+
+/// Gas limit of each of the queries serving a [`StakeOpening`].
+const STAKE_OPENING_GAS_LIMIT: u64 = 100_000_000;
+
+/// Opening of a stake in the tree committing to the stakes.
+pub type StakeOpening = PoseidonOpening<(), STAKE_TREE_DEPTH, 4>;
+
+/// Session holding the state of blocks finalized while syncing up, not yet
+/// committed
+pub(crate) struct PendingCommit {
+    session: Session,
+    /// Number of blocks executed in the session
+    blocks: u64,
+}
+
+/// Minimum number of transactions executed between two checkpoints of the
+/// state of a candidate block being generated.
+const GENERATION_CHECKPOINT_INTERVAL: usize = 32;
+
+/// Checkpoints are only taken once less than a quarter of the block gas is
+/// left, when a transaction is likely to overflow it.
+const CHECKPOINT_GAS_LEFT_DIVISOR: u64 = 4;
+
+/// Commit holding the state of a candidate block after its first `spent`
+/// transactions, kept while the candidate is generated.
+struct Checkpoint {
+    guard: CommitGuard,
+    spent: usize,
+}
+
+/// Checkpoints taken while generating a candidate block, so that a
+/// transaction can be undone by only re-executing the ones included since the
+/// last of them.
+///
+/// The checkpoints are only of use to the generation, and are scheduled for
+/// deletion once dropped, whether the generation succeeded or not. Their
+/// deletion is still deferred until the sessions on top of them are dropped.
+struct Checkpoints {
+    janitor: Janitor,
+    taken: Vec<Checkpoint>,
+}
+
+impl Checkpoints {
+    fn new(janitor: Janitor) -> Self {
+        Self {
+            janitor,
+            taken: vec![],
+        }
+    }
+
+    /// Returns the number of transactions spent at the last checkpoint.
+    fn spent(&self) -> usize {
+        self.taken.last().map(|c| c.spent).unwrap_or_default()
+    }
+
+    /// Returns whether a checkpoint should be taken after `spent`
+    /// transactions, with `gas_left` out of the block `gas_limit`.
+    ///
+    /// Committing is costly, so checkpoints are only taken in nearly full
+    /// blocks, and spaced by [`GENERATION_CHECKPOINT_INTERVAL`].
+    fn due(&self, spent: usize, gas_left: u64, gas_limit: u64) -> bool {
+        gas_left < gas_limit / CHECKPOINT_GAS_LEFT_DIVISOR
+            && spent - self.spent() >= GENERATION_CHECKPOINT_INTERVAL
+    }
+
+    fn push(&mut self, checkpoint: Checkpoint) {
+        self.taken.push(checkpoint);
+    }
+
+    /// Returns the commit of the last checkpoint taken, or `base_commit` if
+    /// none.
+    fn base(&self, base_commit: [u8; 32]) -> [u8; 32] {
+        self.taken
+            .last()
+            .map(|c| c.guard.commit())
+            .unwrap_or(base_commit)
+    }
+
+    /// Returns the transactions spent since the last checkpoint taken.
+    fn since<'a>(
+        &self,
+        spent_txs: &'a [SpentTransaction],
+    ) -> &'a [SpentTransaction] {
+        &spent_txs[self.spent()..]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.taken.is_empty()
+    }
+}
+
+impl Drop for Checkpoints {
+    fn drop(&mut self) {
+        if !self.taken.is_empty() {
+            self.janitor.schedule(
+                self.taken.iter().map(|c| c.guard.commit()).collect(),
+            );
+        }
+    }
+}
+
+/// Maximum number of candidate states kept by [`Rusk::preverify_candidate`].
+const MAX_PRECOMMITS: usize = 8;
+
+/// State of a candidate block, committed ahead of its acceptance.
+pub(crate) struct Precommit {
+    candidate: [u8; 32],
+    /// Commit the candidate was executed on top of
+    base: [u8; 32],
+    guard: CommitGuard,
+    spent_txs: Vec<SpentTransaction>,
+    events: Vec<ContractEvent>,
+    output: VerificationOutput,
+    host_gas: HostGas,
+}
+
+/// Spent transactions of a block, along with the events it emitted outside of
+/// them and its verification output.
+pub type BlockOutput = (
+    Vec<SpentTransaction>,
+    Vec<ContractEvent>,
+    VerificationOutput,
+);
+
+/// Candidate states committed ahead of their acceptance, oldest first.
+#[derive(Default)]
+pub(crate) struct Precommits(VecDeque<Precommit>);
+
+impl Precommits {
+    fn insert(&mut self, precommit: Precommit) {
+        self.0.retain(|p| p.candidate != precommit.candidate);
+        if self.0.len() == MAX_PRECOMMITS {
+            self.0.pop_front();
+        }
+        self.0.push_back(precommit);
+    }
+
+    fn get(&self, candidate: &[u8; 32], base: [u8; 32]) -> Option<&Precommit> {
+        self.0
+            .iter()
+            .find(|p| &p.candidate == candidate && p.base == base)
+    }
+
+    fn take(
+        &mut self,
+        candidate: &[u8; 32],
+        base: [u8; 32],
+    ) -> Option<Precommit> {
+        let pos = self
+            .0
+            .iter()
+            .position(|p| &p.candidate == candidate && p.base == base)?;
+        self.0.remove(pos)
+    }
+
+    /// Drops the states not executed on top of `base`, releasing their
+    /// commits.
+    fn retain_base(&mut self, base: [u8; 32]) {
+        self.0.retain(|p| p.base == base);
+    }
+
+    /// Returns whether `commit` is the state of a candidate.
+    fn contains(&self, commit: &[u8; 32]) -> bool {
+        self.0.iter().any(|p| &p.guard.commit() == commit)
+    }
+}
+
+/// Gas available to a single contract hook.
+const HOOK_GAS_LIMIT: u64 = 100_000_000;
+
+/// Gas available to all the contract hooks of a block.
+const HOOKS_GAS_BUDGET: u64 = 1_000_000_000;
+
 pub static DUSK_KEY: LazyLock<BlsPublicKey> = LazyLock::new(|| {
     let dusk_cpk_bytes = include_bytes!("../../assets/dusk.cpk");
     BlsPublicKey::from_slice(dusk_cpk_bytes)
@@ -38,9 +235,16 @@ pub static DUSK_KEY: LazyLock<BlsPublicKey> = LazyLock::new(|| {
 });
 
 impl Rusk {
+    #[allow(clippy::too_many_arguments)]
     pub fn new<P: AsRef<Path>>(
         dir: P,
+        chain_id: u8,
         generation_timeout: Option<Duration>,
+        host_gas: HostGasLimits,
+        gas_pricing: GasPricing,
+        sync_commit_interval: u64,
+        migrations: Migrations,
+        audit: Option<AuditLog>,
     ) -> Result<Self> {
         let dir = dir.as_ref();
         let commit_id_path = to_rusk_state_id_path(dir);
@@ -60,43 +264,121 @@ impl Rusk {
         base_commit.copy_from_slice(&base_commit_bytes);
 
         let vm = Arc::new(rusk_abi::new_vm(dir)?);
+        let janitor = Janitor::new(vm.clone(), dir)?;
+        let note_index = NoteIndex::open(dir)?;
+        let (note_syncs, sync_requests) = mpsc::sync_channel(1);
 
         let tip = Arc::new(RwLock::new(RuskTip {
             current: base_commit,
             base: base_commit,
+            previous: base_commit,
         }));
 
-        Ok(Self {
-            tip,
-            vm,
+        let rusk = Self {
+            reader: RuskReader {
+                tip,
+                vm,
+                janitor,
+                openings: Default::default(),
+                owned_notes: Default::default(),
+                note_index: Arc::new(Mutex::new(note_index)),
+                query_quotas: None,
+            },
             dir: dir.into(),
+            chain_id,
             generation_timeout,
-        })
+            host_gas,
+            gas_pricing,
+            sync_commit_interval,
+            migrations,
+            pending: Default::default(),
+            precommits: Default::default(),
+            note_syncs,
+            audit,
+        };
+        rusk.sync_note_index();
+
+        // Syncing reads the leaves added since the last sync, which is kept
+        // off the acceptance of blocks. The thread stops once every copy of
+        // `rusk` is dropped.
+        let reader = rusk.reader();
+        thread::Builder::new()
+            .name("note-index".into())
+            .spawn(move || {
+                while sync_requests.recv().is_ok() {
+                    reader.sync_note_index();
+                }
+            })?;
+
+        Ok(rusk)
     }
 
+    /// Executes the given transactions on top of the current tip, to
+    /// generate a candidate block.
+    ///
+    /// The tip commit is read once up-front: moving the tip while this is
+    /// running does not affect the result.
     pub fn execute_transactions<I: Iterator<Item = Transaction>>(
         &self,
         params: &CallParams,
         txs: I,
     ) -> Result<(Vec<SpentTransaction>, Vec<Transaction>, VerificationOutput)>
+    {
+        let base_commit = self.state_root();
+        self.execute_transactions_at(base_commit, params, txs)
+    }
+
+    /// Executes the given transactions on top of `base_commit`, without
+    /// touching the tip.
+    ///
+    /// The commit is kept from being deleted until the generation is done,
+    /// so the acceptance of the block preceding the candidate can proceed
+    /// concurrently.
+    pub fn execute_transactions_at<I: Iterator<Item = Transaction>>(
+        &self,
+        base_commit: [u8; 32],
+        params: &CallParams,
+        txs: I,
+    ) -> Result<(Vec<SpentTransaction>, Vec<Transaction>, VerificationOutput)>
     {
         let started = Instant::now();
 
+        // The guard must outlive the session
+        let _guard = self
+            .janitor
+            .pin(base_commit)
+            .ok_or(Error::CommitNotFound(base_commit))?;
+
         let block_height = params.round;
+        let block_timestamp = params.timestamp;
+        let block_seed = *params.seed.inner();
         let block_gas_limit = params.block_gas_limit;
         let generator = params.generator_pubkey.inner();
         let missed_generators = &params.missed_generators[..];
 
-        let mut session = self.session(block_height, None)?;
+        let session = self.block_session(
+            block_height,
+            block_timestamp,
+            generator,
+            block_seed,
+            Some(base_commit),
+        )?;
+        let mut session = self.migrations.run(session, block_height)?;
+        let mut host_gas = HostGas::new(&self.host_gas, block_height);
+        let gas_pricing = self.gas_pricing.at(block_height);
 
         let mut block_gas_left = block_gas_limit;
+        let mut block_bytes = 0;
 
         let mut spent_txs = Vec::<SpentTransaction>::new();
         let mut discarded_txs = vec![];
 
         let mut dusk_spent = 0;
 
-        let mut event_hasher = Sha3_256::new();
+        let mut event_hasher = BlockEventHasher::default();
+        let mut block_events = Vec::new();
+
+        let mut checkpoints = Checkpoints::new(self.janitor.clone());
 
         for unspent_tx in txs {
             if let Some(timeout) = self.generation_timeout {
@@ -105,14 +387,78 @@ impl Rusk {
                     break;
                 }
             }
+            if spent_txs.len() >= SIZE_LIMITS.max_block_txs {
+                info!("execute_transactions max block txs reached");
+                break;
+            }
             let tx_id = hex::encode(unspent_tx.hash());
+            let tx_size = unspent_tx.size();
+            if let Err(e) = SIZE_LIMITS.check_tx_size(tx_size) {
+                info!("discard tx {tx_id} due to {e}");
+                discarded_txs.push(unspent_tx);
+                continue;
+            }
+            if block_bytes + tx_size > SIZE_LIMITS.max_block_bytes {
+                info!(
+                    "Skipping {tx_id} due size greater than left: {}",
+                    SIZE_LIMITS.max_block_bytes - block_bytes
+                );
+                continue;
+            }
             if unspent_tx.inner.fee().gas_limit > block_gas_left {
                 info!("Skipping {tx_id} due gas_limit greater than left: {block_gas_left}");
                 continue;
             }
 
-            match execute(&mut session, &unspent_tx.inner) {
-                Ok(receipt) => {
+            match execute(&mut session, &unspent_tx.inner, &gas_pricing) {
+                Ok((mut receipt, gas_price)) => {
+                    if let Some(migration) =
+                        migration(&unspent_tx.inner, &receipt)
+                    {
+                        match migrate(session, &migration, &mut receipt) {
+                            Ok(migrated) => session = migrated,
+                            Err(e) => {
+                                info!("discard tx {tx_id} due to failed migration {e:?}");
+                                // The session is consumed by the failed
+                                // migration, so we rebuild it
+                                session = self.replay(
+                                    &checkpoints,
+                                    base_commit,
+                                    block_height,
+                                    block_timestamp,
+                                    generator,
+                                    block_seed,
+                                    &spent_txs,
+                                )?;
+                                discarded_txs.push(unspent_tx);
+                                continue;
+                            }
+                        }
+                    }
+
+                    if let Err(e) = refund(
+                        &mut session,
+                        &unspent_tx.inner,
+                        &mut receipt,
+                        gas_price,
+                        &mut host_gas,
+                    ) {
+                        warn!("discard tx {tx_id} due to failed refund {e:?}");
+                        // The transaction was spent nonetheless, so the
+                        // session is rebuilt without it
+                        session = self.replay(
+                            &checkpoints,
+                            base_commit,
+                            block_height,
+                            block_timestamp,
+                            generator,
+                            block_seed,
+                            &spent_txs,
+                        )?;
+                        discarded_txs.push(unspent_tx);
+                        continue;
+                    }
+
                     let gas_spent = receipt.gas_spent;
 
                     // If the transaction went over the block gas limit we
@@ -120,14 +466,15 @@ impl Rusk {
                     // transaction, since it is technically valid.
                     if gas_spent > block_gas_left {
                         warn!("This is not supposed to happen with conservative tx inclusion");
-                        session = self.session(block_height, None)?;
-
-                        for spent_tx in &spent_txs {
-                            // We know these transactions were correctly
-                            // executed before, so we don't bother checking.
-                            let _ =
-                                execute(&mut session, &spent_tx.inner.inner);
-                        }
+                        session = self.replay(
+                            &checkpoints,
+                            base_commit,
+                            block_height,
+                            block_timestamp,
+                            generator,
+                            block_seed,
+                            &spent_txs,
+                        )?;
 
                         continue;
                     }
@@ -136,19 +483,48 @@ impl Rusk {
                     let err = receipt.data.err().map(|e| format!("{e}"));
                     info!("Tx {tx_id} executed with {gas_spent} gas and err {err:?}");
 
-                    update_hasher(&mut event_hasher, &receipt.events);
+                    event_hasher.update_tx(&receipt.events);
+                    block_events.extend(receipt.events.iter().cloned());
 
                     block_gas_left -= gas_spent;
-                    let gas_price = unspent_tx.inner.fee.gas_price;
+                    block_bytes += tx_size;
                     dusk_spent += gas_spent * gas_price;
+                    let call_gas = to_call_gas(&unspent_tx, &receipt);
                     spent_txs.push(SpentTransaction {
                         inner: unspent_tx,
                         gas_spent,
+                        gas_price,
                         block_height,
                         err,
+                        events: to_contract_events(receipt.events),
+                        call_gas,
                     });
+
+                    if checkpoints.due(
+                        spent_txs.len(),
+                        block_gas_left,
+                        block_gas_limit,
+                    ) {
+                        let (checkpointed, checkpoint) = self.checkpoint(
+                            session,
+                            block_height,
+                            block_timestamp,
+                            generator,
+                            block_seed,
+                            spent_txs.len(),
+                        )?;
+                        session = checkpointed;
+                        if let Some(checkpoint) = checkpoint {
+                            checkpoints.push(checkpoint);
+                        }
+                    }
+                }
+                Err(e @ ExecuteError::Underpriced { .. }) => {
+                    info!("discard tx {tx_id} due to {e:?}");
+                    discarded_txs.push(unspent_tx);
+                    continue;
                 }
-                Err(e) => {
+                Err(ExecuteError::Unspendable(e)) => {
                     info!("discard tx {tx_id} due to {e:?}");
                     // An unspendable transaction should be discarded
                     discarded_txs.push(unspent_tx);
@@ -157,17 +533,28 @@ impl Rusk {
             }
         }
 
+        // Hooks are run on the same events and with the same gas as when the
+        // block is accepted, for the state roots to match
+        run_hooks(
+            &mut session,
+            &block_events,
+            block_gas_left,
+            &mut event_hasher,
+        )?;
+
         reward_slash_and_update_root(
             &mut session,
             block_height,
             dusk_spent,
             generator,
             missed_generators,
+            &SLASHING_POLICY,
+            &mut host_gas,
             &mut event_hasher,
         )?;
 
         let state_root = session.root();
-        let event_hash = event_hasher.finalize().into();
+        let (event_hash, _) = event_hasher.finalize();
 
         Ok((
             spent_txs,
@@ -179,26 +566,231 @@ impl Rusk {
         ))
     }
 
+    /// Commits the state of the given `session`, continuing the execution of
+    /// the block in a new session on top of it.
+    ///
+    /// The checkpoint is not returned if its commit could not be pinned, in
+    /// which case it is simply not used.
+    fn checkpoint(
+        &self,
+        session: Session,
+        block_height: u64,
+        block_timestamp: u64,
+        generator: &BlsPublicKey,
+        block_seed: [u8; 48],
+        spent: usize,
+    ) -> Result<(Session, Option<Checkpoint>)> {
+        let commit = session.commit()?;
+        let checkpoint = self
+            .janitor
+            .pin(commit)
+            .map(|guard| Checkpoint { guard, spent });
+
+        let session = self.block_session(
+            block_height,
+            block_timestamp,
+            generator,
+            block_seed,
+            Some(commit),
+        )?;
+
+        Ok((session, checkpoint))
+    }
+
+    /// Creates a new session on the last of the `checkpoints`, or on
+    /// `base_commit` if none, re-executing the transactions spent since,
+    /// which are known to have been correctly executed before.
+    #[allow(clippy::too_many_arguments)]
+    fn replay(
+        &self,
+        checkpoints: &Checkpoints,
+        base_commit: [u8; 32],
+        block_height: u64,
+        block_timestamp: u64,
+        generator: &BlsPublicKey,
+        block_seed: [u8; 48],
+        spent_txs: &[SpentTransaction],
+    ) -> Result<Session> {
+        let mut session = self.block_session(
+            block_height,
+            block_timestamp,
+            generator,
+            block_seed,
+            Some(checkpoints.base(base_commit)),
+        )?;
+
+        // Checkpoints are only taken after the migrations
+        if checkpoints.is_empty() {
+            session = self.migrations.run(session, block_height)?;
+        }
+        let mut host_gas = HostGas::new(&self.host_gas, block_height);
+        let gas_pricing = self.gas_pricing.at(block_height);
+
+        for spent_tx in checkpoints.since(spent_txs) {
+            let tx = &spent_tx.inner.inner;
+            if let Ok((mut receipt, gas_price)) =
+                execute(&mut session, tx, &gas_pricing)
+            {
+                if let Some(migration) = migration(tx, &receipt) {
+                    session = migrate(session, &migration, &mut receipt)?;
+                }
+                refund(
+                    &mut session,
+                    tx,
+                    &mut receipt,
+                    gas_price,
+                    &mut host_gas,
+                )?;
+            }
+        }
+
+        Ok(session)
+    }
+
+    /// Computes the state root and event hash a candidate block would lead
+    /// to on top of the current tip, without moving it.
+    ///
+    /// The resulting state is committed and kept for the `candidate` hash,
+    /// so that accepting the candidate on top of the same tip, through
+    /// [`Self::accept_precommitted`], does not execute it again.
+    #[allow(clippy::too_many_arguments)]
+    pub fn preverify_candidate(
+        &self,
+        candidate: [u8; 32],
+        block_height: u64,
+        block_timestamp: u64,
+        block_gas_limit: u64,
+        generator: &BlsPublicKey,
+        block_seed: [u8; 48],
+        txs: &[Transaction],
+        missed_generators: &[BlsPublicKey],
+    ) -> Result<VerificationOutput> {
+        let base = self.state_root();
+        if let Some(precommit) = self.precommits.lock().get(&candidate, base) {
+            return Ok(precommit.output);
+        }
+
+        // The guard must outlive the session
+        let _guard =
+            self.janitor.pin(base).ok_or(Error::CommitNotFound(base))?;
+
+        let session = self.block_session(
+            block_height,
+            block_timestamp,
+            generator,
+            block_seed,
+            Some(base),
+        )?;
+
+        let mut host_gas = HostGas::new(&self.host_gas, block_height);
+        let (spent_txs, events, output, session) = accept(
+            session,
+            self.chain_id,
+            block_height,
+            block_gas_limit,
+            generator,
+            txs,
+            missed_generators,
+            &SLASHING_POLICY,
+            &self.migrations,
+            &mut host_gas,
+            &self.gas_pricing,
+        )?;
+
+        let commit = session.commit()?;
+        // Unused states are deleted along with the other commits once a
+        // block is finalized
+        if let Some(guard) = self.janitor.pin(commit) {
+            self.precommits.lock().insert(Precommit {
+                candidate,
+                base,
+                guard,
+                spent_txs,
+                events,
+                output,
+                host_gas,
+            });
+        }
+
+        Ok(output)
+    }
+
+    /// Moves the tip to the state computed for `candidate` by
+    /// [`Self::preverify_candidate`], if computed on top of the current tip.
+    ///
+    /// Returns `None` if no such state is available, in which case the
+    /// candidate is to be executed as usual.
+    ///
+    /// * `consistency_check` - the output the caller expects. Passing a None
+    ///   value disables the check.
+    /// * `finalize` - whether the state is finalized as well
+    pub fn accept_precommitted(
+        &self,
+        candidate: &[u8; 32],
+        consistency_check: Option<VerificationOutput>,
+        finalize: bool,
+    ) -> Result<Option<BlockOutput>> {
+        let base = self.state_root();
+        let Some(precommit) = self.precommits.lock().take(candidate, base)
+        else {
+            return Ok(None);
+        };
+
+        if let Some(expected_verification) = consistency_check {
+            if expected_verification != precommit.output {
+                return Err(Error::InconsistentState(precommit.output));
+            }
+        }
+
+        let commit = precommit.guard.commit();
+        match finalize {
+            true => self.set_finalized(commit)?,
+            false => self.set_current_commit(commit),
+        }
+        precommit.host_gas.record();
+
+        Ok(Some((
+            precommit.spent_txs,
+            precommit.events,
+            precommit.output,
+        )))
+    }
+
     /// Verify the given transactions are ok.
+    #[allow(clippy::too_many_arguments)]
     pub fn verify_transactions(
         &self,
         block_height: u64,
+        block_timestamp: u64,
         block_gas_limit: u64,
         generator: &BlsPublicKey,
+        block_seed: [u8; 48],
         txs: &[Transaction],
         missed_generators: &[BlsPublicKey],
-    ) -> Result<(Vec<SpentTransaction>, VerificationOutput)> {
-        let session = self.session(block_height, None)?;
+    ) -> Result<BlockOutput> {
+        let session = self.block_session(
+            block_height,
+            block_timestamp,
+            generator,
+            block_seed,
+            None,
+        )?;
 
+        let mut host_gas = HostGas::new(&self.host_gas, block_height);
         accept(
             session,
+            self.chain_id,
             block_height,
             block_gas_limit,
             generator,
             txs,
             missed_generators,
+            &SLASHING_POLICY,
+            &self.migrations,
+            &mut host_gas,
+            &self.gas_pricing,
         )
-        .map(|(a, b, _)| (a, b))
+        .map(|(txs, events, output, _)| (txs, events, output))
     }
 
     /// Accept the given transactions.
@@ -210,21 +802,35 @@ impl Rusk {
     pub fn accept_transactions(
         &self,
         block_height: u64,
+        block_timestamp: u64,
         block_gas_limit: u64,
         generator: BlsPublicKey,
+        block_seed: [u8; 48],
         txs: Vec<Transaction>,
         consistency_check: Option<VerificationOutput>,
         missed_generators: &[BlsPublicKey],
-    ) -> Result<(Vec<SpentTransaction>, VerificationOutput)> {
-        let session = self.session(block_height, None)?;
+    ) -> Result<BlockOutput> {
+        let session = self.block_session(
+            block_height,
+            block_timestamp,
+            &generator,
+            block_seed,
+            None,
+        )?;
 
-        let (spent_txs, verification_output, session) = accept(
+        let mut host_gas = HostGas::new(&self.host_gas, block_height);
+        let (spent_txs, events, verification_output, session) = accept(
             session,
+            self.chain_id,
             block_height,
             block_gas_limit,
             &generator,
             &txs[..],
             missed_generators,
+            &SLASHING_POLICY,
+            &self.migrations,
+            &mut host_gas,
+            &self.gas_pricing,
         )?;
 
         if let Some(expected_verification) = consistency_check {
@@ -236,8 +842,9 @@ impl Rusk {
         }
 
         self.set_current_commit(session.commit()?);
+        host_gas.record();
 
-        Ok((spent_txs, verification_output))
+        Ok((spent_txs, events, verification_output))
     }
 
     /// Finalize the given transactions.
@@ -249,21 +856,35 @@ impl Rusk {
     pub fn finalize_transactions(
         &self,
         block_height: u64,
+        block_timestamp: u64,
         block_gas_limit: u64,
         generator: BlsPublicKey,
+        block_seed: [u8; 48],
         txs: Vec<Transaction>,
         consistency_check: Option<VerificationOutput>,
         missed_generators: &[BlsPublicKey],
-    ) -> Result<(Vec<SpentTransaction>, VerificationOutput)> {
-        let session = self.session(block_height, None)?;
+    ) -> Result<BlockOutput> {
+        let session = self.block_session(
+            block_height,
+            block_timestamp,
+            &generator,
+            block_seed,
+            None,
+        )?;
 
-        let (spent_txs, verification_output, session) = accept(
+        let mut host_gas = HostGas::new(&self.host_gas, block_height);
+        let (spent_txs, events, verification_output, session) = accept(
             session,
+            self.chain_id,
             block_height,
             block_gas_limit,
             &generator,
             &txs[..],
             missed_generators,
+            &SLASHING_POLICY,
+            &self.migrations,
+            &mut host_gas,
+            &self.gas_pricing,
         )?;
 
         if let Some(expected_verification) = consistency_check {
@@ -274,13 +895,167 @@ impl Rusk {
             }
         }
 
-        let commit = session.commit()?;
+        self.set_finalized(session.commit()?)?;
+        host_gas.record();
+
+        Ok((spent_txs, events, verification_output))
+    }
+
+    /// Finalize the given transactions on top of the blocks finalized before
+    /// with this same method, committing the state only once every
+    /// `sync_commit_interval` blocks.
+    ///
+    /// Meant for blocks downloaded while syncing up. The state root of every
+    /// block is still checked against the `consistency_check`, but the blocks
+    /// are only written to disk once [`Self::commit_deferred`] is called or
+    /// enough of them have been executed. On error, the blocks left pending
+    /// are discarded and the state stays at the last committed one.
+    #[allow(clippy::too_many_arguments)]
+    pub fn finalize_deferred_transactions(
+        &self,
+        block_height: u64,
+        block_timestamp: u64,
+        block_gas_limit: u64,
+        generator: BlsPublicKey,
+        block_seed: [u8; 48],
+        txs: Vec<Transaction>,
+        consistency_check: Option<VerificationOutput>,
+        missed_generators: &[BlsPublicKey],
+    ) -> Result<BlockOutput> {
+        if self.sync_commit_interval <= 1 {
+            return self.finalize_transactions(
+                block_height,
+                block_timestamp,
+                block_gas_limit,
+                generator,
+                block_seed,
+                txs,
+                consistency_check,
+                missed_generators,
+            );
+        }
+
+        let mut pending = self.pending.lock();
+
+        let (session, blocks) = match pending.take() {
+            Some(PendingCommit {
+                mut session,
+                blocks,
+            }) => {
+                rusk_abi::set_block_data(
+                    &mut session,
+                    block_height,
+                    block_timestamp,
+                    &generator,
+                    block_seed,
+                )?;
+                (session, blocks)
+            }
+            None => (
+                self.block_session(
+                    block_height,
+                    block_timestamp,
+                    &generator,
+                    block_seed,
+                    None,
+                )?,
+                0,
+            ),
+        };
+
+        let mut host_gas = HostGas::new(&self.host_gas, block_height);
+        let (spent_txs, events, verification_output, session) = accept(
+            session,
+            self.chain_id,
+            block_height,
+            block_gas_limit,
+            &generator,
+            &txs[..],
+            missed_generators,
+            &SLASHING_POLICY,
+            &self.migrations,
+            &mut host_gas,
+            &self.gas_pricing,
+        )?;
+
+        if let Some(expected_verification) = consistency_check {
+            if expected_verification != verification_output {
+                // Drop the session, together with the blocks pending in it,
+                // if the result state root is inconsistent with the callers
+                // one.
+                return Err(Error::InconsistentState(verification_output));
+            }
+        }
+
+        // Pending blocks may still be discarded, but were checked against
+        // the accepted ones
+        host_gas.record();
+
+        let blocks = blocks + 1;
+        if blocks < self.sync_commit_interval {
+            *pending = Some(PendingCommit { session, blocks });
+        } else {
+            self.set_finalized(session.commit()?)?;
+            debug!(event = "deferred blocks committed", blocks);
+        }
+
+        Ok((spent_txs, events, verification_output))
+    }
+
+    /// Commits the blocks left pending by
+    /// [`Self::finalize_deferred_transactions`], if any.
+    pub fn commit_deferred(&self) -> Result<()> {
+        if let Some(PendingCommit { session, blocks }) =
+            self.pending.lock().take()
+        {
+            self.set_finalized(session.commit()?)?;
+            debug!(event = "deferred blocks committed", blocks);
+        }
+        Ok(())
+    }
+
+    /// Discards the blocks left pending by
+    /// [`Self::finalize_deferred_transactions`], if any, the state going back
+    /// to the last committed one.
+    pub fn discard_deferred(&self) {
+        if let Some(PendingCommit { blocks, .. }) = self.pending.lock().take() {
+            warn!(event = "deferred blocks discarded", blocks);
+        }
+    }
+
+    /// Queries a contract on top of the blocks left pending by
+    /// [`Self::finalize_deferred_transactions`], without committing them.
+    pub(crate) fn query_deferred<A, R>(
+        &self,
+        contract_id: ContractId,
+        call_name: &str,
+        call_arg: &A,
+    ) -> Result<R>
+    where
+        A: for<'b> Serialize<StandardBufSerializer<'b>>,
+        A::Archived: for<'b> CheckBytes<DefaultValidator<'b>>,
+        R: Archive,
+        R::Archived: Deserialize<R, Infallible>
+            + for<'b> CheckBytes<DefaultValidator<'b>>,
+    {
+        match self.pending.lock().as_mut() {
+            Some(pending) => Ok(pending
+                .session
+                .call(contract_id, call_name, call_arg, u64::MAX)?
+                .data),
+            None => self.query(contract_id, call_name, call_arg),
+        }
+    }
+
+    /// Makes the given commit both the current and base one, persisting its
+    /// id.
+    fn set_finalized(&self, commit: [u8; 32]) -> Result<()> {
         self.set_base_and_delete(commit);
 
         let commit_id_path = to_rusk_state_id_path(&self.dir);
         fs::write(commit_id_path, commit)?;
 
-        Ok((spent_txs, verification_output))
+        Ok(())
     }
 
     pub fn revert(&self, state_hash: [u8; 32]) -> Result<[u8; 32]> {
@@ -291,46 +1066,256 @@ impl Rusk {
             return Err(Error::CommitNotFound(state_hash));
         }
 
+        // Failing to audit the revert must not fail it, since it is needed
+        // by consensus
+        if let Some(audit) = &self.audit {
+            if let Err(err) = audit.record(
+                Operation::Revert,
+                NODE_OPERATOR,
+                Some(tip.current),
+                Some(state_hash),
+            ) {
+                warn!(event = "revert not audited", ?err);
+            }
+        }
+
+        tip.previous = tip.current;
         tip.current = state_hash;
-        Ok(tip.current)
+        self.janitor.cancel(state_hash);
+        drop(tip);
+
+        self.request_note_sync();
+        Ok(state_hash)
     }
 
     pub fn revert_to_base_root(&self) -> Result<[u8; 32]> {
         self.revert(self.base_root())
     }
 
-    /// Get the base root.
-    pub fn base_root(&self) -> [u8; 32] {
-        self.tip.read().base
+    /// Requests the note index to be synced with the tip in the background.
+    ///
+    /// A request already pending syncs with the latest tip, so requests made
+    /// meanwhile are dropped.
+    fn request_note_sync(&self) {
+        let _ = self.note_syncs.try_send(());
     }
 
-    /// Get the current state root.
-    pub fn state_root(&self) -> [u8; 32] {
-        self.tip.read().current
-    }
+    pub(crate) fn set_current_commit(&self, commit: [u8; 32]) {
+        let mut tip = self.tip.write();
+        tip.previous = tip.current;
+        tip.current = commit;
+        self.janitor.cancel(commit);
+        self.precommits.lock().retain_base(commit);
+        drop(tip);
 
-    /// Returns the nullifiers that already exist from a list of given
-    /// `nullifiers`.
-    pub fn existing_nullifiers(
-        &self,
-        nullifiers: &Vec<BlsScalar>,
-    ) -> Result<Vec<BlsScalar>> {
-        self.query(TRANSFER_CONTRACT, "existing_nullifiers", nullifiers)
+        self.request_note_sync();
     }
 
-    /// Returns the stakes.
-    pub fn provisioners(
-        &self,
-        base_commit: Option<[u8; 32]>,
-    ) -> Result<impl Iterator<Item = (BlsPublicKey, StakeData)>> {
-        let (sender, receiver) = mpsc::channel();
-        self.feeder_query(STAKE_CONTRACT, "stakes", &(), sender, base_commit)?;
-        Ok(receiver.into_iter().map(|bytes| {
-            rkyv::from_bytes::<(BlsPublicKey, StakeData)>(&bytes).expect(
-                "The contract should only return (pk, stake_data) tuples",
-            )
-        }))
-    }
+    pub(crate) fn set_base_and_delete(&self, commit: [u8; 32]) {
+        let mut tip = self.tip.write();
+
+        let current_commit = tip.current;
+        let base_commit = tip.base;
+
+        tip.previous = current_commit;
+        tip.current = commit;
+        tip.base = commit;
+        self.janitor.cancel(commit);
+        self.precommits.lock().retain_base(commit);
+
+        // We will delete all commits except the previous base commit, the
+        // previous current commit and the new commit.
+        let mut commits_to_delete = self.vm.commits();
+        commits_to_delete.retain(|c| {
+            *c != current_commit && *c != base_commit && *c != commit
+        });
+
+        // Delete all commits except the previous base commit, and the current
+        // commit. Deleting commits is blocking, meaning it will wait until any
+        // process using the commit is done. This includes any queries that are
+        // currently executing.
+        // Since we do want commits to be deleted, but don't want block
+        // finalization to wait, the janitor deletes them in the background.
+        self.janitor.schedule(commits_to_delete);
+        drop(tip);
+
+        self.request_note_sync();
+    }
+
+    /// Schedules the deletion of every commit but the ones of the tip and
+    /// the candidate states committed ahead of their acceptance, to reclaim
+    /// disk space.
+    ///
+    /// Returns the number of commits scheduled for deletion.
+    pub fn prune_commits(&self) -> usize {
+        let tip = self.tip.read();
+        // Holding the lock keeps candidate states from being added meanwhile
+        let precommits = self.precommits.lock();
+
+        let mut commits = self.vm.commits();
+        commits.retain(|c| {
+            *c != tip.current
+                && *c != tip.base
+                && *c != tip.previous
+                && !precommits.contains(c)
+        });
+
+        let pruned = commits.len();
+        self.janitor.schedule(commits);
+        pruned
+    }
+
+    /// Perform an action with the underlying data structure.
+    ///
+    /// This should **not be used** internally, to avoid locking the structure
+    /// for too long of a period of time.
+    pub fn with_tip<'a, F, T>(&'a self, closure: F) -> T
+    where
+        F: FnOnce(RwLockWriteGuard<'a, RuskTip>, &'a VM) -> T,
+    {
+        let tip = self.tip.write();
+        closure(tip, &self.vm)
+    }
+
+    /// Simulates the slashing of the generators missed by a candidate block
+    /// at `block_height`, produced by `generator`, on top of the tip.
+    ///
+    /// The penalties are applied in a throwaway session, so the state is left
+    /// untouched. The generator is rewarded before, as when the block is
+    /// accepted, since that clears its faults.
+    pub fn simulate_slashing(
+        &self,
+        block_height: u64,
+        generator: &BlsPublicKey,
+        missed_generators: &[BlsPublicKey],
+    ) -> Result<Vec<SlashOutcome>> {
+        let mut session = self.session(block_height, None)?;
+        let mut host_gas = HostGas::new(&self.host_gas, block_height);
+
+        host_call::<_, ()>(
+            &mut session,
+            HostCall::Reward,
+            &mut host_gas,
+            STAKE_CONTRACT,
+            "reward",
+            &(*generator, 0u64),
+        )?;
+
+        missed_generators
+            .iter()
+            .map(|to_slash| {
+                slash(
+                    &mut session,
+                    block_height,
+                    to_slash,
+                    &SLASHING_POLICY,
+                    &mut host_gas,
+                )
+                .map(|(outcome, _)| outcome)
+            })
+            .collect()
+    }
+}
+
+impl RuskReader {
+    /// Get the base root.
+    pub fn base_root(&self) -> [u8; 32] {
+        self.tip.read().base
+    }
+
+    /// Get the current state root.
+    pub fn state_root(&self) -> [u8; 32] {
+        self.tip.read().current
+    }
+
+    /// Returns the nullifiers that already exist from a list of given
+    /// `nullifiers`.
+    pub fn existing_nullifiers(
+        &self,
+        nullifiers: &[BlsScalar],
+    ) -> Result<Vec<BlsScalar>> {
+        self.query_archived::<_, Vec<BlsScalar>, _, _>(
+            TRANSFER_CONTRACT,
+            "existing_nullifiers",
+            &SliceArg(nullifiers),
+            |existing| {
+                // Most of the time none of the nullifiers exist, in which case
+                // there is nothing to deserialize
+                if existing.is_empty() {
+                    return Vec::new();
+                }
+                existing
+                    .deserialize(&mut Infallible)
+                    .expect("Infallible deserialization")
+            },
+        )
+    }
+
+    /// Returns the transparent balance held by a contract in the transfer
+    /// contract.
+    pub fn contract_balance(&self, contract_id: &ContractId) -> Result<u64> {
+        self.query(TRANSFER_CONTRACT, "module_balance", contract_id)
+    }
+
+    /// Returns the number of migrations a contract went through.
+    pub fn contract_version(&self, contract_id: &ContractId) -> Result<u64> {
+        self.query(TRANSFER_CONTRACT, "contract_version", contract_id)
+    }
+
+    /// Returns the stakes.
+    pub fn provisioners(
+        &self,
+        base_commit: Option<[u8; 32]>,
+    ) -> Result<impl Iterator<Item = (BlsPublicKey, StakeData)>> {
+        Ok(self
+            .raw_provisioners(base_commit)?
+            .into_iter()
+            .map(|bytes| decode_stake(&bytes)))
+    }
+
+    /// Returns all the provisioners, like [`provisioners`], decoding them on
+    /// multiple threads.
+    ///
+    /// [`provisioners`]: RuskReader::provisioners
+    pub fn provisioners_parallel(
+        &self,
+        base_commit: Option<[u8; 32]>,
+    ) -> Result<Vec<(BlsPublicKey, StakeData)>> {
+        let raw = self.raw_provisioners(base_commit)?;
+        Ok(decode_stakes(&raw))
+    }
+
+    /// Returns the total number of provisioners, along with at most `limit`
+    /// of them starting from `offset`, in the order of the stake contract.
+    ///
+    /// Only the requested provisioners are decoded.
+    pub fn provisioners_page(
+        &self,
+        base_commit: Option<[u8; 32]>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(usize, Vec<(BlsPublicKey, StakeData)>)> {
+        let raw = self.raw_provisioners(base_commit)?;
+        let total = raw.len();
+
+        let start = offset.min(total);
+        let end = start.saturating_add(limit).min(total);
+
+        Ok((total, decode_stakes(&raw[start..end])))
+    }
+
+    /// Returns the undecoded `(pk, stake_data)` tuples of the stake contract.
+    fn raw_provisioners(
+        &self,
+        base_commit: Option<[u8; 32]>,
+    ) -> Result<Vec<Vec<u8>>> {
+        // The guard must outlive the query
+        let _guard = base_commit.map(|commit| self.pin(commit)).transpose()?;
+
+        let (sender, receiver) = mpsc::channel();
+        self.feeder_query(STAKE_CONTRACT, "stakes", &(), sender, base_commit)?;
+        Ok(receiver.into_iter().collect())
+    }
 
     /// Fetches the previous state data for stake changes in the contract.
     ///
@@ -367,10 +1352,65 @@ impl Rusk {
         }))
     }
 
+    /// Returns the fault counts of the provisioners before the changes made
+    /// by the last block, along the lines of
+    /// [`last_provisioners_change`](Self::last_provisioners_change).
+    pub fn last_faults_change(
+        &self,
+        base_commit: Option<[u8; 32]>,
+    ) -> Result<impl Iterator<Item = (BlsPublicKey, u32)>> {
+        let (sender, receiver) = mpsc::channel();
+        self.feeder_query(
+            STAKE_CONTRACT,
+            "prev_faults_changes",
+            &(),
+            sender,
+            base_commit,
+        )?;
+        Ok(receiver.into_iter().map(|bytes| {
+            rkyv::from_bytes::<(BlsPublicKey, u32)>(&bytes)
+                .expect("The contract should only return (pk, u32) tuples")
+        }))
+    }
+
     pub fn provisioner(&self, pk: &BlsPublicKey) -> Result<Option<StakeData>> {
         self.query(STAKE_CONTRACT, "get_stake", pk)
     }
 
+    /// Returns the root of the tree committing to the stakes, along with the
+    /// stake of `pk` and its opening in the tree, if any.
+    ///
+    /// The tree is the one at the given `state_root`, or at the current one
+    /// if `None`. A leaf of the tree is the poseidon hash of the hash of the
+    /// compressed public key, followed by the `hash_inputs` of its stake.
+    ///
+    /// The root is emitted as a `stakes_root` event by the block that led to
+    /// the state, so it can be checked against the event hash of its header.
+    pub fn stake_opening(
+        &self,
+        pk: &BlsPublicKey,
+        state_root: Option<[u8; 32]>,
+    ) -> Result<(BlsScalar, Option<(StakeData, StakeOpening)>)> {
+        let commit = state_root.unwrap_or_else(|| self.state_root());
+
+        let root = self.query_at_with_gas(
+            commit,
+            STAKE_CONTRACT,
+            "stakes_root",
+            &(),
+            STAKE_OPENING_GAS_LIMIT,
+        )?;
+        let opening = self.query_at_with_gas(
+            commit,
+            STAKE_CONTRACT,
+            "stake_opening",
+            pk,
+            STAKE_OPENING_GAS_LIMIT,
+        )?;
+
+        Ok((root, opening))
+    }
+
     pub(crate) fn session(
         &self,
         block_height: u64,
@@ -386,41 +1426,58 @@ impl Rusk {
         Ok(session)
     }
 
-    pub(crate) fn set_current_commit(&self, commit: [u8; 32]) {
-        let mut tip = self.tip.write();
-        tip.current = commit;
-    }
-
-    pub(crate) fn set_base_and_delete(&self, commit: [u8; 32]) {
-        let mut tip = self.tip.write();
-
-        let current_commit = tip.current;
-        let base_commit = tip.base;
+    /// Creates a session to execute the block with the given data on top of
+    /// `commit`, or of the tip if not given.
+    fn block_session(
+        &self,
+        block_height: u64,
+        block_timestamp: u64,
+        generator: &BlsPublicKey,
+        block_seed: [u8; 48],
+        commit: Option<[u8; 32]>,
+    ) -> Result<Session> {
+        let commit = commit.unwrap_or_else(|| {
+            let tip = self.tip.read();
+            tip.current
+        });
 
-        tip.current = commit;
-        tip.base = commit;
+        let session = rusk_abi::new_block_session(
+            &self.vm,
+            commit,
+            self.chain_id,
+            block_height,
+            block_timestamp,
+            generator,
+            block_seed,
+        )?;
 
-        // We will delete all commits except the previous base commit, the
-        // previous current commit and the new commit.
-        let mut commits_to_delete = self.vm.commits();
-        commits_to_delete.retain(|c| {
-            *c != current_commit && *c != base_commit && *c != commit
-        });
+        Ok(session)
+    }
 
-        // Delete all commits except the previous base commit, and the current
-        // commit. Deleting commits is blocking, meaning it will wait until any
-        // process using the commit is done. This includes any queries that are
-        // currently executing.
-        // Since we do want commits to be deleted, but don't want block
-        // finalization to wait, we spawn a new task to delete the commits.
-        task::spawn(delete_commits(self.vm.clone(), commits_to_delete));
+    /// Returns the state of the background commit deletions.
+    pub fn commit_deletions(&self) -> JanitorStatus {
+        self.janitor.status()
     }
-}
 
-async fn delete_commits(vm: Arc<VM>, commits: Vec<[u8; 32]>) {
-    for commit in commits {
-        if let Err(err) = vm.delete_commit(commit) {
-            debug!("failed deleting commit {}: {err}", hex::encode(commit));
+    /// Checks that a transaction spends no existing nullifier and carries a
+    /// valid proof.
+    pub fn preverify(&self, tx: &Transaction) -> anyhow::Result<()> {
+        info!("Received preverify request");
+        let tx = &tx.inner;
+        let existing_nullifiers =
+            self.existing_nullifiers(&tx.nullifiers).map_err(|e| {
+                let err = anyhow::anyhow!("Cannot check nullifiers: {e}");
+                Classified::new(ErrorKind::Transient, err)
+            })?;
+
+        if !existing_nullifiers.is_empty() {
+            let err = crate::Error::RepeatingNullifiers(existing_nullifiers);
+            return Err(anyhow::anyhow!("Invalid tx: {err}"));
+        }
+        match crate::verifier::verify_proof(tx, self.chain_id) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(anyhow::anyhow!("Invalid proof")),
+            Err(e) => Err(anyhow::anyhow!("Cannot verify the proof: {e}")),
         }
     }
 }
@@ -428,56 +1485,90 @@ async fn delete_commits(vm: Arc<VM>, commits: Vec<[u8; 32]>) {
 #[allow(clippy::too_many_arguments)]
 fn accept(
     session: Session,
+    chain_id: u8,
     block_height: u64,
     block_gas_limit: u64,
     generator: &BlsPublicKey,
     txs: &[Transaction],
     missed_generators: &[BlsPublicKey],
-) -> Result<(Vec<SpentTransaction>, VerificationOutput, Session)> {
-    let mut session = session;
+    slashing_policy: &SlashingPolicy,
+    migrations: &Migrations,
+    host_gas: &mut HostGas,
+    gas_pricing: &GasPricing,
+) -> Result<(
+    Vec<SpentTransaction>,
+    Vec<ContractEvent>,
+    VerificationOutput,
+    Session,
+)> {
+    let mut session = migrations.run(session, block_height)?;
+    let gas_pricing = gas_pricing.at(block_height);
 
     let mut block_gas_left = block_gas_limit;
 
     let mut spent_txs = Vec::with_capacity(txs.len());
     let mut dusk_spent = 0;
 
-    let mut event_hasher = Sha3_256::new();
+    let mut event_hasher = BlockEventHasher::default();
+    let mut block_events = Vec::new();
+
+    preverify_proofs(txs, chain_id);
 
     for unspent_tx in txs {
         let tx = &unspent_tx.inner;
-        let receipt = execute(&mut session, tx)?;
+        let (mut receipt, gas_price) = execute(&mut session, tx, &gas_pricing)?;
 
-        update_hasher(&mut event_hasher, &receipt.events);
+        if let Some(migration) = migration(tx, &receipt) {
+            session = migrate(session, &migration, &mut receipt)?;
+        }
+        refund(&mut session, tx, &mut receipt, gas_price, host_gas)?;
+
+        event_hasher.update_tx(&receipt.events);
+        block_events.extend(receipt.events.iter().cloned());
         let gas_spent = receipt.gas_spent;
 
-        dusk_spent += gas_spent * tx.fee.gas_price;
+        dusk_spent += gas_spent * gas_price;
         block_gas_left = block_gas_left
             .checked_sub(gas_spent)
             .ok_or(Error::OutOfGas)?;
 
+        let call_gas = to_call_gas(unspent_tx, &receipt);
         spent_txs.push(SpentTransaction {
             inner: unspent_tx.clone(),
             gas_spent,
+            gas_price,
             block_height,
             // We're currently ignoring the result of successful calls
             err: receipt.data.err().map(|e| format!("{e}")),
+            events: to_contract_events(receipt.events),
+            call_gas,
         });
     }
 
+    run_hooks(
+        &mut session,
+        &block_events,
+        block_gas_left,
+        &mut event_hasher,
+    )?;
+
     reward_slash_and_update_root(
         &mut session,
         block_height,
         dusk_spent,
         generator,
         missed_generators,
+        slashing_policy,
+        host_gas,
         &mut event_hasher,
     )?;
 
     let state_root = session.root();
-    let event_hash = event_hasher.finalize().into();
+    let (event_hash, events) = event_hasher.finalize();
 
     Ok((
         spent_txs,
+        events,
         VerificationOutput {
             state_root,
             event_hash,
@@ -486,58 +1577,412 @@ fn accept(
     ))
 }
 
-/// Executes a transaction, returning the receipt of the call and the gas spent.
-/// The following steps are performed:
+fn decode_stake(bytes: &[u8]) -> (BlsPublicKey, StakeData) {
+    rkyv::from_bytes::<(BlsPublicKey, StakeData)>(bytes)
+        .expect("The contract should only return (pk, stake_data) tuples")
+}
+
+/// Decodes the given stakes, in parallel if there are enough of them.
+///
+/// The order of the stakes is preserved.
+fn decode_stakes(raw: &[Vec<u8>]) -> Vec<(BlsPublicKey, StakeData)> {
+    if raw.len() < PARALLEL_DECODE_THRESHOLD {
+        return raw.iter().map(|bytes| decode_stake(bytes)).collect();
+    }
+
+    let threads = thread::available_parallelism()
+        .map(usize::from)
+        .unwrap_or(1)
+        .min(raw.len().div_ceil(PARALLEL_DECODE_THRESHOLD));
+    let chunk_size = raw.len().div_ceil(threads);
+
+    thread::scope(|s| {
+        let handles: Vec<_> = raw
+            .chunks(chunk_size)
+            .map(|chunk| {
+                s.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|bytes| decode_stake(bytes))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("decoding thread not to panic"))
+            .collect()
+    })
+}
+
+/// Verifies the proofs of the given transactions in parallel.
 ///
-/// 1. Call the "spend_and_execute" function on the transfer contract with
+/// Successful verifications are memoized by `rusk_abi::verify_proof`, so the
+/// transfer contract finds them in the cache when the transactions are later
+/// executed sequentially. Invalid proofs are not memoized, and are rejected by
+/// the contract during execution as usual.
+fn preverify_proofs(txs: &[Transaction], chain_id: u8) {
+    if txs.len() < 2 {
+        return;
+    }
+
+    let started = Instant::now();
+    let threads = thread::available_parallelism()
+        .map(usize::from)
+        .unwrap_or(1)
+        .min(txs.len());
+    let chunk_size = txs.len().div_ceil(threads);
+
+    let invalid: usize = thread::scope(|s| {
+        let handles: Vec<_> = txs
+            .chunks(chunk_size)
+            .map(|chunk| {
+                s.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter(|tx| {
+                            !matches!(
+                                crate::verifier::verify_proof(
+                                    &tx.inner, chain_id
+                                ),
+                                Ok(true)
+                            )
+                        })
+                        .count()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or_default())
+            .sum()
+    });
+
+    debug!(
+        event = "proofs preverified",
+        count = txs.len(),
+        invalid,
+        threads,
+        elapsed = ?started.elapsed(),
+    );
+}
+
+/// Failure to execute a transaction
+#[derive(Debug)]
+enum ExecuteError {
+    /// The gas price of the transaction is below the base price, the state
+    /// is left untouched
+    Underpriced { fee_cap: u64, base_price: u64 },
+    /// The transaction is unspendable, the state is left untouched
+    Unspendable(PiecrustError),
+}
+
+impl From<ExecuteError> for Error {
+    fn from(err: ExecuteError) -> Self {
+        match err {
+            ExecuteError::Underpriced {
+                fee_cap,
+                base_price,
+            } => Error::Underpriced(fee_cap, base_price),
+            ExecuteError::Unspendable(err) => err.into(),
+        }
+    }
+}
+
+/// Executes a transaction, returning the receipt of the call and the gas price
+/// it is charged. The following steps are performed:
+///
+/// 1. Compute the effective gas price of the transaction. If its fee cap is
+///    below the base price, an error is returned and the transaction should be
+///    considered invalid.
+///
+/// 2. Call the "spend_and_execute" function on the transfer contract with
 ///    unlimited gas. If this fails, an error is returned. If an error is
 ///    returned the transaction should be considered unspendable/invalid, but no
 ///    re-execution of previous transactions is required.
 ///
-/// 2. Call the "refund" function on the transfer contract with unlimited gas.
-///    The amount charged depends on the gas spent by the transaction, and the
-///    optional contract call in step 1.
+/// The transaction is then spent, and must be given its [`refund`] once any
+/// [`migration`] it authorizes is carried out.
 fn execute(
     session: &mut Session,
     tx: &PhoenixTransaction,
-) -> Result<CallReceipt<Result<Vec<u8>, ContractError>>, PiecrustError> {
+    gas_pricing: &GasPricing,
+) -> Result<(CallReceipt<Result<Vec<u8>, ContractError>>, u64), ExecuteError> {
+    let gas_price = gas_pricing.effective_price(tx.fee.gas_price).ok_or(
+        ExecuteError::Underpriced {
+            fee_cap: tx.fee.gas_price,
+            base_price: gas_pricing.base_price,
+        },
+    )?;
+
     // Spend the inputs and execute the call. If this errors the transaction is
     // unspendable.
-    let mut receipt = session.call::<_, Result<Vec<u8>, ContractError>>(
-        TRANSFER_CONTRACT,
-        "spend_and_execute",
-        tx,
-        tx.fee.gas_limit,
-    )?;
+    let mut receipt = session
+        .call::<_, Result<Vec<u8>, ContractError>>(
+            TRANSFER_CONTRACT,
+            "spend_and_execute",
+            tx,
+            tx.fee.gas_limit,
+        )
+        .map_err(ExecuteError::Unspendable)?;
 
     // Ensure all gas is consumed if there's an error in the contract call
     if receipt.data.is_err() {
         receipt.gas_spent = receipt.gas_limit;
     }
 
-    // Refund the appropriate amount to the transaction. This call is guaranteed
-    // to never error. If it does, then a programming error has occurred. As
-    // such, the call to `Result::expect` is warranted.
-    let refund_receipt = session
-        .call::<_, ()>(
-            TRANSFER_CONTRACT,
-            "refund",
-            &(tx.fee, receipt.gas_spent),
-            u64::MAX,
-        )
-        .expect("Refunding must succeed");
+    Ok((receipt, gas_price))
+}
+
+/// Calls the "refund" function on the transfer contract for an executed
+/// transaction, within the refund gas limit of the host. The amount charged
+/// depends on the gas spent by the transaction, including the one of its
+/// migration, at the effective gas price, the rest of the deposit being
+/// refunded.
+///
+/// This call is not supposed to error. If it does, then a programming error
+/// has occurred in the transfer contract: the transaction is spent and its
+/// effects are kept, so the transactions executed before have to be
+/// re-executed to undo it.
+fn refund(
+    session: &mut Session,
+    tx: &PhoenixTransaction,
+    receipt: &mut CallReceipt<Result<Vec<u8>, ContractError>>,
+    gas_price: u64,
+    host_gas: &mut HostGas,
+) -> Result<(), PiecrustError> {
+    let refund_receipt = host_call::<_, ()>(
+        session,
+        HostCall::Refund,
+        host_gas,
+        TRANSFER_CONTRACT,
+        "refund",
+        &(tx.fee, receipt.gas_spent, gas_price),
+    )?;
 
     receipt.events.extend(refund_receipt.events);
 
-    Ok(receipt)
+    Ok(())
 }
 
-fn update_hasher(hasher: &mut Sha3_256, events: &[Event]) {
+/// Returns the migration authorized by a transaction, if any.
+///
+/// A migration is authorized when the transaction successfully calls the
+/// `migrate` function of the transfer contract.
+fn migration(
+    tx: &PhoenixTransaction,
+    receipt: &CallReceipt<Result<Vec<u8>, ContractError>>,
+) -> Option<Migration> {
+    match (&tx.call, &receipt.data) {
+        (Some((contract, fn_name, data)), Ok(_))
+            if *contract == TRANSFER_CONTRACT.to_bytes()
+                && fn_name == MIGRATE_FN =>
+        {
+            // The argument was already validated by the transfer contract
+            rkyv::from_bytes::<Migration>(data).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Replaces the bytecode of a contract with the one of an authorized
+/// migration, keeping its id and state.
+///
+/// The new bytecode is deployed and its `migrate_state` function is called
+/// with the id of the replaced contract, copying its state over. The new
+/// contract then takes over the id of the replaced one.
+///
+/// Both are bound by the gas the migration transaction has left, and the gas
+/// spent copying the state is added to the one spent by the transaction, so
+/// that it is charged and counted against the block gas limit.
+fn migrate(
+    session: Session,
+    migration: &Migration,
+    receipt: &mut CallReceipt<Result<Vec<u8>, ContractError>>,
+) -> Result<Session, PiecrustError> {
+    let contract = ContractId::from_bytes(migration.contract);
+    let gas_left = receipt.gas_limit.saturating_sub(receipt.gas_spent);
+
+    let mut gas_spent = 0;
+    let session = session.migrate(
+        contract,
+        &migration.bytecode,
+        ContractData::builder().owner(migration.owner.to_bytes()),
+        gas_left,
+        |migrated, session| {
+            let receipt = session.call::<_, ()>(
+                migrated,
+                MIGRATE_STATE_FN,
+                &contract,
+                gas_left,
+            )?;
+            gas_spent = receipt.gas_spent;
+            Ok(())
+        },
+    )?;
+
+    receipt.gas_spent += gas_spent;
+
+    info!(
+        "Contract {} migrated to version {}",
+        hex::encode(migration.contract),
+        migration.version
+    );
+
+    Ok(session)
+}
+
+/// Converts the events emitted by a transaction to the ones stored in its
+/// receipt
+fn to_contract_events(events: Vec<Event>) -> Vec<ContractEvent> {
+    events
+        .into_iter()
+        .map(|event| ContractEvent {
+            source: event.source.to_bytes(),
+            topic: event.topic,
+            data: event.data,
+        })
+        .collect()
+}
+
+/// Gas spent in the calls to each contract while executing a transaction.
+///
+/// The contract called by the transaction is attributed all the gas it spent,
+/// and every other contract the gas spent in the calls it received, nested
+/// calls included, up to the gas spent by the transaction.
+fn to_call_gas<T>(tx: &Transaction, receipt: &CallReceipt<T>) -> Vec<CallGas> {
+    let mut gas = BTreeMap::new();
+    for call in receipt.call_tree.iter() {
+        let spent = gas.entry(call.contract_id.to_bytes()).or_insert(0u64);
+        *spent = spent.saturating_add(call.spent).min(receipt.gas_spent);
+    }
+    gas.insert(tx.called_contract(), receipt.gas_spent);
+
+    gas.into_iter()
+        .map(|(contract, gas_spent)| CallGas {
+            contract,
+            gas_spent,
+        })
+        .collect()
+}
+
+fn update_hasher(hasher: &mut EventHasher, events: &[Event]) {
     for event in events {
-        hasher.update(event.source.as_bytes());
-        hasher.update(event.topic.as_bytes());
-        hasher.update(&event.data);
+        hasher.update(event.source.as_bytes(), &event.topic, &event.data);
+    }
+}
+
+/// Computes the event hash of a block, keeping the events emitted outside of
+/// its transactions, by the hooks, rewards and slashes, to be stored along
+/// with the block.
+#[derive(Default)]
+struct BlockEventHasher {
+    hasher: EventHasher,
+    events: Vec<ContractEvent>,
+}
+
+impl BlockEventHasher {
+    /// Feeds the events emitted by a transaction, stored in its receipt.
+    fn update_tx(&mut self, events: &[Event]) {
+        update_hasher(&mut self.hasher, events);
+    }
+
+    /// Feeds events emitted by the block outside of its transactions.
+    fn update_block(&mut self, events: Vec<Event>) {
+        update_hasher(&mut self.hasher, &events);
+        self.events.extend(to_contract_events(events));
+    }
+
+    /// Returns the event hash, along with the events fed through
+    /// [`Self::update_block`].
+    fn finalize(self) -> ([u8; 32], Vec<ContractEvent>) {
+        (self.hasher.finalize(), self.events)
+    }
+}
+
+/// Calls the hooks of the contracts subscribed to the given events, as
+/// registered in the transfer contract.
+///
+/// Hooks are called in subscriber order, each with the matching events in
+/// emission order, until `gas_limit` is exhausted. It is the gas left in the
+/// block, bounded by the [`HOOKS_GAS_BUDGET`]. A failing hook is charged its
+/// whole gas limit, and doesn't invalidate the block.
+fn run_hooks(
+    session: &mut Session,
+    events: &[Event],
+    gas_limit: u64,
+    event_hasher: &mut BlockEventHasher,
+) -> Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let subscriptions = session
+        .call::<_, Vec<(ModuleId, Subscription)>>(
+            TRANSFER_CONTRACT,
+            "subscriptions",
+            &(),
+            HOOK_GAS_LIMIT,
+        )?
+        .data;
+    if subscriptions.is_empty() {
+        return Ok(());
+    }
+
+    let mut subscribers: BTreeMap<(ModuleId, &str), Vec<ModuleId>> =
+        BTreeMap::new();
+    for (subscriber, s) in &subscriptions {
+        subscribers
+            .entry((s.source, s.topic.as_str()))
+            .or_default()
+            .push(*subscriber);
+    }
+
+    let mut hooks: BTreeMap<ModuleId, Vec<HookEvent>> = BTreeMap::new();
+    for event in events {
+        let source = event.source.to_bytes();
+        let Some(subscribers) =
+            subscribers.get(&(source, event.topic.as_str()))
+        else {
+            continue;
+        };
+        for subscriber in subscribers {
+            hooks.entry(*subscriber).or_default().push(HookEvent {
+                source,
+                topic: event.topic.clone(),
+                data: event.data.clone(),
+            });
+        }
+    }
+
+    let mut gas_left = gas_limit.min(HOOKS_GAS_BUDGET);
+    for (subscriber, events) in hooks {
+        let gas_limit = gas_left.min(HOOK_GAS_LIMIT);
+        if gas_limit == 0 {
+            warn!(event = "hooks gas budget exhausted");
+            break;
+        }
+
+        let contract = ContractId::from_bytes(subscriber);
+        match session.call::<_, ()>(contract, HOOK_FN, &events, gas_limit) {
+            Ok(r) => {
+                gas_left -= r.gas_spent.min(gas_limit);
+                event_hasher.update_block(r.events);
+            }
+            Err(err) => {
+                debug!(
+                    event = "hook failed",
+                    contract = hex::encode(subscriber),
+                    ?err
+                );
+                gas_left -= gas_limit;
+            }
+        }
     }
+
+    Ok(())
 }
 
 fn reward_slash_and_update_root(
@@ -546,46 +1991,208 @@ fn reward_slash_and_update_root(
     dusk_spent: Dusk,
     generator: &BlsPublicKey,
     slashing: &[BlsPublicKey],
-    event_hasher: &mut Sha3_256,
+    slashing_policy: &SlashingPolicy,
+    host_gas: &mut HostGas,
+    event_hasher: &mut BlockEventHasher,
 ) -> Result<()> {
     let (dusk_value, generator_value) =
         coinbase_value(block_height, dusk_spent);
 
-    let r = session.call::<_, ()>(
+    let r = host_call::<_, ()>(
+        session,
+        HostCall::Reward,
+        host_gas,
         STAKE_CONTRACT,
         "reward",
         &(*DUSK_KEY, dusk_value),
-        u64::MAX,
     )?;
-    update_hasher(event_hasher, &r.events);
+    event_hasher.update_block(r.events);
 
-    let r = session.call::<_, ()>(
+    let r = host_call::<_, ()>(
+        session,
+        HostCall::Reward,
+        host_gas,
         STAKE_CONTRACT,
         "reward",
         &(*generator, generator_value),
-        u64::MAX,
     )?;
-    update_hasher(event_hasher, &r.events);
-
-    let slash_amount = emission_amount(block_height);
+    event_hasher.update_block(r.events);
 
     for to_slash in slashing {
-        let r = session.call::<_, ()>(
-            STAKE_CONTRACT,
-            "slash",
-            &(*to_slash, slash_amount),
-            u64::MAX,
-        )?;
-        update_hasher(event_hasher, &r.events);
+        let (_, r) =
+            slash(session, block_height, to_slash, slashing_policy, host_gas)?;
+        event_hasher.update_block(r.events);
     }
 
-    let r = session.call::<_, ()>(
+    // The stakes root is emitted, and thus committed to by the event hash
+    let r = host_call::<_, ()>(
+        session,
+        HostCall::UpdateRoot,
+        host_gas,
+        STAKE_CONTRACT,
+        "update_root",
+        &(),
+    )?;
+    event_hasher.update_block(r.events);
+
+    let r = host_call::<_, ()>(
+        session,
+        HostCall::UpdateRoot,
+        host_gas,
         TRANSFER_CONTRACT,
         "update_root",
         &(),
-        u64::MAX,
     )?;
-    update_hasher(event_hasher, &r.events);
+    event_hasher.update_block(r.events);
 
     Ok(())
 }
+
+/// Applies to `to_slash` the penalty for a missed generation, returning what
+/// was applied along with the receipt of the call.
+fn slash(
+    session: &mut Session,
+    block_height: u64,
+    to_slash: &BlsPublicKey,
+    slashing_policy: &SlashingPolicy,
+    host_gas: &mut HostGas,
+) -> Result<(SlashOutcome, CallReceipt<()>)> {
+    let stake = match slashing_policy.amount {
+        SlashAmount::Stake { .. } => host_call::<_, Option<StakeData>>(
+            session,
+            HostCall::Slash,
+            host_gas,
+            STAKE_CONTRACT,
+            "get_stake",
+            to_slash,
+        )?
+        .data
+        .and_then(|stake| stake.amount)
+        .map(|(value, _)| value)
+        .unwrap_or_default(),
+        _ => 0,
+    };
+    let slash_amount = slashing_policy.amount.amount(block_height, stake);
+
+    let faults = host_call::<_, u32>(
+        session,
+        HostCall::Slash,
+        host_gas,
+        STAKE_CONTRACT,
+        "register_fault",
+        to_slash,
+    )?
+    .data;
+
+    let penalty = slashing_policy.penalty(faults);
+    let (fn_name, amount) = match penalty {
+        Penalty::Slash => ("slash", slash_amount),
+        Penalty::Suspend(epochs) => ("suspend", epochs),
+        Penalty::HardSlash => ("hard_slash", slash_amount),
+    };
+    let r = host_call::<_, ()>(
+        session,
+        HostCall::Slash,
+        host_gas,
+        STAKE_CONTRACT,
+        fn_name,
+        &(*to_slash, amount),
+    )?;
+
+    let amount = match penalty {
+        Penalty::Suspend(_) => 0,
+        _ => slash_amount,
+    };
+    let outcome = SlashOutcome {
+        provisioner: *to_slash,
+        faults,
+        penalty,
+        amount,
+    };
+
+    Ok((outcome, r))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_stakes_in_order() {
+        let raw: Vec<_> = (0..PARALLEL_DECODE_THRESHOLD as u64 * 3 + 1)
+            .map(|i| {
+                let stake = StakeData {
+                    amount: Some((i, 0)),
+                    counter: i,
+                    reward: 0,
+                };
+                rkyv::to_bytes::<_, 256>(&(*DUSK_KEY, stake))
+                    .expect("serializing should succeed")
+                    .to_vec()
+            })
+            .collect();
+
+        let stakes = decode_stakes(&raw);
+        assert_eq!(stakes.len(), raw.len());
+        for (i, (pk, stake)) in stakes.iter().enumerate() {
+            assert_eq!(pk, &*DUSK_KEY);
+            assert_eq!(stake.counter, i as u64);
+        }
+
+        assert!(decode_stakes(&raw[..0]).is_empty());
+    }
+
+    #[test]
+    fn checkpoints_in_nearly_full_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let vm = Arc::new(rusk_abi::new_ephemeral_vm().unwrap());
+        let janitor = Janitor::new(vm, dir.path()).unwrap();
+
+        let interval = GENERATION_CHECKPOINT_INTERVAL;
+        let mut checkpoints = Checkpoints::new(janitor.clone());
+        assert!(!checkpoints.due(interval, 500, 1000), "block not full");
+        assert!(checkpoints.due(interval, 200, 1000));
+        assert!(!checkpoints.due(interval - 1, 200, 1000), "too soon");
+        assert_eq!(checkpoints.base([0; 32]), [0; 32]);
+
+        let commit = [1u8; 32];
+        checkpoints.push(Checkpoint {
+            guard: janitor.pin(commit).unwrap(),
+            spent: interval,
+        });
+        assert_eq!(checkpoints.base([0; 32]), commit);
+        assert!(!checkpoints.due(interval * 2 - 1, 0, 1000));
+        assert!(checkpoints.due(interval * 2, 0, 1000));
+
+        // Keep the commit from being deleted, for it to stay pending
+        let _pinned = janitor.pin(commit).unwrap();
+        drop(checkpoints);
+        assert_eq!(janitor.status().pending, vec![hex::encode(commit)]);
+    }
+
+    #[test]
+    fn block_event_hash_matches_ledger() {
+        let event = |source, topic: &str, data: &[u8]| Event {
+            source,
+            topic: topic.into(),
+            data: data.to_vec(),
+        };
+        let tx_events = vec![event(TRANSFER_CONTRACT, "moonlight", &[1, 2, 3])];
+        let block_events = vec![
+            event(STAKE_CONTRACT, "reward", &[4]),
+            event(STAKE_CONTRACT, "slash", &[5, 6]),
+        ];
+
+        let mut hasher = BlockEventHasher::default();
+        hasher.update_tx(&tx_events);
+        hasher.update_block(block_events.clone());
+        let (hash, stored) = hasher.finalize();
+
+        assert_eq!(stored, to_contract_events(block_events));
+
+        // The hash is reproducible from the events stored with the block
+        let mut all = to_contract_events(tx_events);
+        all.extend(stored);
+        assert_eq!(hash, node_data::ledger::event_hash(&all));
+    }
+}
@@ -6,6 +6,9 @@
 
 mod query;
 
+pub(crate) use query::SliceArg;
+pub use query::DEFAULT_QUERY_TIMEOUT;
+
 use tracing::info;
 
 use dusk_bytes::DeserializableSlice;
@@ -13,9 +16,13 @@ use dusk_consensus::operations::{CallParams, VerificationOutput};
 use dusk_consensus::user::provisioners::Provisioners;
 use dusk_consensus::user::stake::Stake;
 use node::vm::VMExecution;
-use node_data::ledger::{Block, SpentTransaction, Transaction};
+use node_data::error::{Classified, Classify, ErrorKind};
+use node_data::ledger::{Block, ContractEvent, SpentTransaction, Transaction};
+use phoenix_core::transaction::StakeData;
+use rusk_abi::STAKE_CONTRACT;
 
 use super::Rusk;
+use crate::budget::SYNC;
 
 impl VMExecution for Rusk {
     fn execute_state_transition<I: Iterator<Item = Transaction>>(
@@ -28,6 +35,7 @@ impl VMExecution for Rusk {
         VerificationOutput,
     )> {
         info!("Received execute_state_transition request");
+        self.commit_deferred_state()?;
 
         let (txs, discarded_txs, verification_output) =
             self.execute_transactions(params, txs).map_err(|inner| {
@@ -42,20 +50,29 @@ impl VMExecution for Rusk {
         blk: &Block,
     ) -> anyhow::Result<VerificationOutput> {
         info!("Received verify_state_transition request");
+        self.commit_deferred_state()?;
         let generator = blk.header().generator_bls_pubkey;
         let generator =
             dusk_bls12_381_sign::PublicKey::from_slice(&generator.0)
                 .map_err(|e| anyhow::anyhow!("Error in from_slice {e:?}"))?;
 
-        let (_, verification_output) = self
-            .verify_transactions(
+        // The resulting state is kept, should the candidate be accepted
+        let verification_output = self
+            .preverify_candidate(
+                blk.header().hash,
                 blk.header().height,
+                blk.header().timestamp,
                 blk.header().gas_limit,
                 &generator,
+                *blk.header().seed.inner(),
                 blk.txs(),
                 &blk.header().failed_iterations.to_missed_generators()?,
             )
-            .map_err(|inner| anyhow::anyhow!("Cannot verify txs: {inner}!!"))?;
+            .map_err(|inner| {
+                let kind = inner.kind();
+                let err = anyhow::anyhow!("Cannot verify txs: {inner}!!");
+                Classified::new(kind, err)
+            })?;
 
         Ok(verification_output)
     }
@@ -63,81 +80,150 @@ impl VMExecution for Rusk {
     fn accept(
         &self,
         blk: &Block,
-    ) -> anyhow::Result<(Vec<SpentTransaction>, VerificationOutput)> {
+    ) -> anyhow::Result<(
+        Vec<SpentTransaction>,
+        Vec<ContractEvent>,
+        VerificationOutput,
+    )> {
         info!("Received accept request");
+        self.commit_deferred_state()?;
+
+        let expected = VerificationOutput {
+            state_root: blk.header().state_hash,
+            event_hash: blk.header().event_hash,
+        };
+        if let Some(accepted) = self
+            .accept_precommitted(&blk.header().hash, Some(expected), false)
+            .map_err(|inner| anyhow::anyhow!("Cannot accept txs: {inner}!!"))?
+        {
+            return Ok(accepted);
+        }
+
         let generator = blk.header().generator_bls_pubkey;
         let generator =
             dusk_bls12_381_sign::PublicKey::from_slice(&generator.0)
                 .map_err(|e| anyhow::anyhow!("Error in from_slice {e:?}"))?;
 
-        let (txs, verification_output) = self
+        let (txs, events, verification_output) = self
             .accept_transactions(
                 blk.header().height,
+                blk.header().timestamp,
                 blk.header().gas_limit,
                 generator,
+                *blk.header().seed.inner(),
                 blk.txs().clone(),
-                Some(VerificationOutput {
-                    state_root: blk.header().state_hash,
-                    event_hash: blk.header().event_hash,
-                }),
+                Some(expected),
                 &blk.header().failed_iterations.to_missed_generators()?,
             )
             .map_err(|inner| anyhow::anyhow!("Cannot accept txs: {inner}!!"))?;
 
-        Ok((txs, verification_output))
+        Ok((txs, events, verification_output))
     }
 
     fn finalize(
         &self,
         blk: &Block,
-    ) -> anyhow::Result<(Vec<SpentTransaction>, VerificationOutput)> {
+    ) -> anyhow::Result<(
+        Vec<SpentTransaction>,
+        Vec<ContractEvent>,
+        VerificationOutput,
+    )> {
         info!("Received finalize request");
+        self.commit_deferred_state()?;
+
+        let expected = VerificationOutput {
+            state_root: blk.header().state_hash,
+            event_hash: blk.header().event_hash,
+        };
+        if let Some(accepted) = self
+            .accept_precommitted(&blk.header().hash, Some(expected), true)
+            .map_err(|inner| {
+                anyhow::anyhow!("Cannot finalize txs: {inner}!!")
+            })?
+        {
+            return Ok(accepted);
+        }
+
         let generator = blk.header().generator_bls_pubkey;
         let generator =
             dusk_bls12_381_sign::PublicKey::from_slice(&generator.0)
                 .map_err(|e| anyhow::anyhow!("Error in from_slice {e:?}"))?;
 
-        let (txs, state_root) = self
+        let (txs, events, state_root) = self
             .finalize_transactions(
                 blk.header().height,
+                blk.header().timestamp,
                 blk.header().gas_limit,
                 generator,
+                *blk.header().seed.inner(),
                 blk.txs().clone(),
-                Some(VerificationOutput {
-                    state_root: blk.header().state_hash,
-                    event_hash: blk.header().event_hash,
-                }),
+                Some(expected),
                 &blk.header().failed_iterations.to_missed_generators()?,
             )
             .map_err(|inner| {
                 anyhow::anyhow!("Cannot finalize txs: {inner}!!")
             })?;
 
-        Ok((txs, state_root))
+        Ok((txs, events, state_root))
+    }
+
+    fn finalize_deferred(
+        &self,
+        blk: &Block,
+    ) -> anyhow::Result<(
+        Vec<SpentTransaction>,
+        Vec<ContractEvent>,
+        VerificationOutput,
+    )> {
+        info!("Received finalize_deferred request");
+        let generator = blk.header().generator_bls_pubkey;
+        let generator =
+            dusk_bls12_381_sign::PublicKey::from_slice(&generator.0)
+                .map_err(|e| anyhow::anyhow!("Error in from_slice {e:?}"))?;
+
+        let missed_generators =
+            blk.header().failed_iterations.to_missed_generators()?;
+
+        let (txs, events, state_root) = SYNC
+            .run(|| {
+                self.finalize_deferred_transactions(
+                    blk.header().height,
+                    blk.header().timestamp,
+                    blk.header().gas_limit,
+                    generator,
+                    *blk.header().seed.inner(),
+                    blk.txs().clone(),
+                    Some(VerificationOutput {
+                        state_root: blk.header().state_hash,
+                        event_hash: blk.header().event_hash,
+                    }),
+                    &missed_generators,
+                )
+            })
+            .map_err(|inner| {
+                anyhow::anyhow!("Cannot finalize txs: {inner}!!")
+            })?;
+
+        Ok((txs, events, state_root))
+    }
+
+    fn commit_deferred(&self) -> anyhow::Result<()> {
+        self.commit_deferred_state()
+    }
+
+    fn discard_deferred(&self) {
+        Rusk::discard_deferred(self)
     }
 
     fn preverify(&self, tx: &Transaction) -> anyhow::Result<()> {
-        info!("Received preverify request");
-        let tx = &tx.inner;
-        let existing_nullifiers = self
-            .existing_nullifiers(&tx.nullifiers)
-            .map_err(|e| anyhow::anyhow!("Cannot check nullifiers: {e}"))?;
-
-        if !existing_nullifiers.is_empty() {
-            let err = crate::Error::RepeatingNullifiers(existing_nullifiers);
-            return Err(anyhow::anyhow!("Invalid tx: {err}"));
-        }
-        match crate::verifier::verify_proof(tx) {
-            Ok(true) => Ok(()),
-            Ok(false) => Err(anyhow::anyhow!("Invalid proof")),
-            Err(e) => Err(anyhow::anyhow!("Cannot verify the proof: {e}")),
-        }
+        self.reader.preverify(tx)
     }
 
     fn get_provisioners(
         &self,
         base_commit: [u8; 32],
     ) -> anyhow::Result<Provisioners> {
+        self.commit_deferred_state()?;
         self.query_provisioners(Some(base_commit))
     }
 
@@ -145,8 +231,13 @@ impl VMExecution for Rusk {
         &self,
         pk: &dusk_bls12_381_sign::PublicKey,
     ) -> anyhow::Result<Option<Stake>> {
+        // Read on top of the pending blocks, not to commit them at each block
         let stake = self
-            .provisioner(pk)
+            .query_deferred::<_, Option<StakeData>>(
+                STAKE_CONTRACT,
+                "get_stake",
+                pk,
+            )
             .map_err(|e| anyhow::anyhow!("Cannot get provisioner {e}"))?
             .map(|stake| {
                 let (value, eligibility) = stake.amount.unwrap_or_default();
@@ -156,14 +247,17 @@ impl VMExecution for Rusk {
     }
 
     fn get_state_root(&self) -> anyhow::Result<[u8; 32]> {
+        self.commit_deferred_state()?;
         Ok(self.state_root())
     }
 
     fn get_finalized_state_root(&self) -> anyhow::Result<[u8; 32]> {
+        self.commit_deferred_state()?;
         Ok(self.base_root())
     }
 
     fn revert(&self, state_hash: [u8; 32]) -> anyhow::Result<[u8; 32]> {
+        self.commit_deferred_state()?;
         let state_hash = self
             .revert(state_hash)
             .map_err(|inner| anyhow::anyhow!("Cannot revert: {inner}"))?;
@@ -172,6 +266,7 @@ impl VMExecution for Rusk {
     }
 
     fn revert_to_finalized(&self) -> anyhow::Result<[u8; 32]> {
+        self.commit_deferred_state()?;
         let state_hash = self.revert_to_base_root().map_err(|inner| {
             anyhow::anyhow!("Cannot revert to finalized: {inner}")
         })?;
@@ -181,14 +276,24 @@ impl VMExecution for Rusk {
 }
 
 impl Rusk {
+    /// Commits the blocks whose state was deferred while syncing up, so that
+    /// the state they lead to can be looked up or built upon.
+    fn commit_deferred_state(&self) -> anyhow::Result<()> {
+        self.commit_deferred().map_err(|inner| {
+            let err = anyhow::anyhow!("Cannot commit deferred state: {inner}");
+            Classified::new(ErrorKind::Transient, err).into()
+        })
+    }
+
     fn query_provisioners(
         &self,
         base_commit: Option<[u8; 32]>,
     ) -> anyhow::Result<Provisioners> {
         info!("Received get_provisioners request");
         let provisioners = self
-            .provisioners(base_commit)
+            .provisioners_parallel(base_commit)
             .map_err(|e| anyhow::anyhow!("Cannot get provisioners {e}"))?
+            .into_iter()
             .map(|(key, stake)| {
                 let (value, eligibility) = stake.amount.unwrap_or_default();
                 let stake =
@@ -4,17 +4,161 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use crate::chain::Rusk;
-use crate::Result;
+use crate::budget::{FEEDER_QUERY, QUERY};
+use crate::chain::{CommitGuard, QueryQuotas, Reservation, RuskReader};
+use crate::{Error, Result};
 
-use std::sync::mpsc;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
 
 use bytecheck::CheckBytes;
+use rkyv::ser::serializers::AllocSerializer;
+use rkyv::ser::{ScratchSpace, Serializer};
 use rkyv::validation::validators::DefaultValidator;
+use rkyv::vec::{ArchivedVec, VecResolver};
 use rkyv::{Archive, Deserialize, Infallible, Serialize};
 use rusk_abi::{ContractId, StandardBufSerializer};
 
-impl Rusk {
+/// Time after which the async queries stop waiting for their result
+pub const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A slice passed as a query argument, archived as a `Vec` without having to
+/// be copied into one.
+pub(crate) struct SliceArg<'a, T>(pub &'a [T]);
+
+impl<T: Archive> Archive for SliceArg<'_, T> {
+    type Archived = ArchivedVec<T::Archived>;
+    type Resolver = VecResolver;
+
+    unsafe fn resolve(
+        &self,
+        pos: usize,
+        resolver: Self::Resolver,
+        out: *mut Self::Archived,
+    ) {
+        ArchivedVec::resolve_from_len(self.0.len(), pos, resolver, out);
+    }
+}
+
+impl<T, S> Serialize<S> for SliceArg<'_, T>
+where
+    T: Serialize<S>,
+    S: ScratchSpace + Serializer + ?Sized,
+{
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> core::result::Result<Self::Resolver, S::Error> {
+        ArchivedVec::serialize_from_slice(self.0, serializer)
+    }
+}
+
+impl RuskReader {
+    /// Meters the queries of the clients with the given `quotas`.
+    pub fn with_query_quotas(mut self, quotas: QueryQuotas) -> Self {
+        self.query_quotas = Some(quotas);
+        self
+    }
+
+    /// Reserves the gas of the next query of `client` out of its quota, if
+    /// the queries are metered.
+    ///
+    /// Fails with [`Error::QueryQuotaExceeded`] if `client` spent all the
+    /// gas of its queries within the current window.
+    pub fn reserve_query_gas(
+        &self,
+        client: Option<IpAddr>,
+    ) -> Result<Option<Reservation>> {
+        self.query_quotas
+            .as_ref()
+            .map(|quotas| quotas.reserve(client))
+            .transpose()
+    }
+
+    /// Queries a contract with a raw argument on behalf of `client`, at the
+    /// given `commit` or at the tip, within the client query quota.
+    ///
+    /// The gas spent is charged to the client. A failed query is charged
+    /// its whole gas limit, the gas it spent being unknown.
+    pub fn query_raw_metered<S, V>(
+        &self,
+        client: Option<IpAddr>,
+        commit: Option<[u8; 32]>,
+        contract_id: ContractId,
+        fn_name: S,
+        fn_arg: V,
+    ) -> Result<Vec<u8>>
+    where
+        S: AsRef<str>,
+        V: Into<Vec<u8>>,
+    {
+        let Some(reservation) = self.reserve_query_gas(client)? else {
+            return match commit {
+                Some(commit) => {
+                    self.query_raw_at(commit, contract_id, fn_name, fn_arg)
+                }
+                None => self.query_raw(contract_id, fn_name, fn_arg),
+            };
+        };
+
+        // The guard must outlive the session
+        let _guard = commit.map(|commit| self.pin(commit)).transpose()?;
+        let mut session = self.session(0, commit)?;
+
+        let gas_limit = reservation.gas_limit();
+        let receipt = session.call_raw(
+            contract_id,
+            fn_name.as_ref(),
+            fn_arg,
+            gas_limit,
+        )?;
+        reservation.settle(receipt.gas_spent);
+
+        Ok(receipt.data)
+    }
+
+    /// Keeps `commit` from being deleted while the returned guard is alive.
+    ///
+    /// Fails with [`Error::CommitNotFound`] if the commit doesn't exist
+    /// anymore.
+    pub(crate) fn pin(&self, commit: [u8; 32]) -> Result<CommitGuard> {
+        let guard = self
+            .janitor
+            .pin(commit)
+            .ok_or(Error::CommitNotFound(commit))?;
+        if !self.vm.commits().contains(&commit) {
+            return Err(Error::CommitNotFound(commit));
+        }
+        Ok(guard)
+    }
+
+    /// Queries a contract at the given `commit` with a raw argument, like
+    /// [`query_at`].
+    ///
+    /// [`query_at`]: RuskReader::query_at
+    pub fn query_raw_at<S, V>(
+        &self,
+        commit: [u8; 32],
+        contract_id: ContractId,
+        fn_name: S,
+        fn_arg: V,
+    ) -> Result<Vec<u8>>
+    where
+        S: AsRef<str>,
+        V: Into<Vec<u8>>,
+    {
+        // The guard must outlive the session
+        let _guard = self.pin(commit)?;
+        let mut session = self.session(0, Some(commit))?;
+
+        session
+            .call_raw(contract_id, fn_name.as_ref(), fn_arg, u64::MAX)
+            .map(|receipt| receipt.data)
+            .map_err(Into::into)
+    }
+
     pub fn query_raw<S, V>(
         &self,
         contract_id: ContractId,
@@ -56,6 +200,91 @@ impl Rusk {
         Ok(results.pop().unwrap())
     }
 
+    /// Queries a contract at the given `commit`, rather than at the tip.
+    ///
+    /// The commit is kept from being deleted until the query is done, so
+    /// historical queries can safely run concurrently with the acceptance of
+    /// new blocks.
+    pub fn query_at<A, R>(
+        &self,
+        commit: [u8; 32],
+        contract_id: ContractId,
+        call_name: &str,
+        call_arg: &A,
+    ) -> Result<R>
+    where
+        A: for<'b> Serialize<StandardBufSerializer<'b>>,
+        A::Archived: for<'b> bytecheck::CheckBytes<DefaultValidator<'b>>,
+        R: Archive,
+        R::Archived: Deserialize<R, Infallible>
+            + for<'b> CheckBytes<DefaultValidator<'b>>,
+    {
+        self.query_at_with_gas(
+            commit,
+            contract_id,
+            call_name,
+            call_arg,
+            u64::MAX,
+        )
+    }
+
+    /// Queries a contract at the given `commit`, like [`Self::query_at`],
+    /// spending at most `gas_limit`.
+    pub fn query_at_with_gas<A, R>(
+        &self,
+        commit: [u8; 32],
+        contract_id: ContractId,
+        call_name: &str,
+        call_arg: &A,
+        gas_limit: u64,
+    ) -> Result<R>
+    where
+        A: for<'b> Serialize<StandardBufSerializer<'b>>,
+        A::Archived: for<'b> bytecheck::CheckBytes<DefaultValidator<'b>>,
+        R: Archive,
+        R::Archived: Deserialize<R, Infallible>
+            + for<'b> CheckBytes<DefaultValidator<'b>>,
+    {
+        // The guard must outlive the session
+        let _guard = self.pin(commit)?;
+        let mut session = self.session(0, Some(commit))?;
+
+        session
+            .call(contract_id, call_name, call_arg, gas_limit)
+            .map(|receipt| receipt.data)
+            .map_err(Into::into)
+    }
+
+    /// Queries a contract, handing the archived result to `f` instead of
+    /// deserializing it.
+    ///
+    /// Meant for large results of which only a part is needed.
+    pub(crate) fn query_archived<A, R, T, F>(
+        &self,
+        contract_id: ContractId,
+        call_name: &str,
+        call_arg: &A,
+        f: F,
+    ) -> Result<T>
+    where
+        A: Serialize<AllocSerializer<1024>>,
+        R: Archive,
+        R::Archived: for<'b> CheckBytes<DefaultValidator<'b>>,
+        F: FnOnce(&R::Archived) -> T,
+    {
+        let arg = rkyv::to_bytes::<_, 1024>(call_arg).map_err(|err| {
+            Error::Other(format!("Failed serializing argument: {err}").into())
+        })?;
+        let data = self.query_raw(contract_id, call_name, arg.into_vec())?;
+
+        let archived =
+            rkyv::check_archived_root::<R>(&data).map_err(|err| {
+                Error::Other(format!("Invalid query result: {err}").into())
+            })?;
+
+        Ok(f(archived))
+    }
+
     fn query_seq<A, R, F>(
         &self,
         contract_id: ContractId,
@@ -116,28 +345,122 @@ impl Rusk {
         Ok(())
     }
 
+    /// Runs a feeder query with a raw argument, charging the gas it spent
+    /// to the given `reservation`.
+    ///
+    /// Feeder calls always run with the maximum amount of gas, so the gas a
+    /// query spends over its reservation is charged as well, keeping the
+    /// following queries of the client from running until the quota is
+    /// renewed.
     pub fn feeder_query_raw<S, V>(
         &self,
         contract_id: ContractId,
         call_name: S,
         call_arg: V,
         feeder: mpsc::Sender<Vec<u8>>,
+        base_commit: Option<[u8; 32]>,
+        reservation: Option<Reservation>,
     ) -> Result<()>
     where
         S: AsRef<str>,
         V: Into<Vec<u8>>,
     {
+        // The guard must outlive the session
+        let _guard = base_commit.map(|commit| self.pin(commit)).transpose()?;
+
         // For queries we set a point limit of effectively infinite and a block
         // height of zero since this doesn't affect the result.
-        let mut session = self.session(0, None)?;
+        let mut session = self.session(0, base_commit)?;
 
-        session.feeder_call_raw(
+        let receipt = session.feeder_call_raw(
             contract_id,
             call_name.as_ref(),
             call_arg,
             feeder,
         )?;
+        if let Some(reservation) = reservation {
+            reservation.settle(receipt.gas_spent);
+        }
 
         Ok(())
     }
 }
+
+/// Flags a query as cancelled once its caller is gone.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Async variants of the queries, for the services running on the async
+/// runtime.
+///
+/// The queries run on the threads dedicated to the [`QUERY`] budget. A query
+/// timing out, or whose future is dropped, is cancelled if it is still
+/// waiting for a thread. Once started, a contract call cannot be interrupted:
+/// it runs to completion and its result is discarded.
+impl RuskReader {
+    /// Runs the blocking `query` on the query budget, failing with
+    /// [`Error::QueryTimeout`] if not done within `timeout`.
+    pub async fn spawn_query<T, E, F>(
+        &self,
+        timeout: Duration,
+        query: F,
+    ) -> core::result::Result<T, E>
+    where
+        T: Send + 'static,
+        E: From<Error> + Send + 'static,
+        F: FnOnce(&RuskReader) -> core::result::Result<T, E> + Send + 'static,
+    {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let _cancel = CancelOnDrop(cancelled.clone());
+
+        let reader = self.clone();
+        let task = QUERY.spawn_blocking(move || {
+            if cancelled.load(Ordering::Relaxed) {
+                return Err(Error::QueryCancelled.into());
+            }
+            query(&reader)
+        });
+
+        tokio::time::timeout(timeout, task)
+            .await
+            .map_err(|_| Error::QueryTimeout(timeout))?
+    }
+
+    /// Runs the feeder `query` on the threads dedicated to the
+    /// [`FEEDER_QUERY`] budget, in the background.
+    pub fn spawn_feeder_query<F>(&self, query: F)
+    where
+        F: FnOnce(&RuskReader) + Send + 'static,
+    {
+        let reader = self.clone();
+        FEEDER_QUERY.execute(move || query(&reader));
+    }
+
+    /// Queries a contract at the tip, like [`RuskReader::query`], without
+    /// blocking the runtime.
+    pub async fn query_async<A, R>(
+        &self,
+        contract_id: ContractId,
+        call_name: impl Into<String>,
+        call_arg: A,
+        timeout: Duration,
+    ) -> Result<R>
+    where
+        A: for<'b> Serialize<StandardBufSerializer<'b>> + Send + 'static,
+        A::Archived: for<'b> bytecheck::CheckBytes<DefaultValidator<'b>>,
+        R: Archive + Send + 'static,
+        R::Archived: Deserialize<R, Infallible>
+            + for<'b> CheckBytes<DefaultValidator<'b>>,
+    {
+        let call_name = call_name.into();
+        self.spawn_query(timeout, move |reader| {
+            reader.query(contract_id, &call_name, &call_arg)
+        })
+        .await
+    }
+}
@@ -0,0 +1,181 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Watchdog of the free space on the volumes of the state and the database.
+//!
+//! A node running out of space mid-commit can leave its state corrupted, so
+//! the watchdog reclaims space before it happens: unused commits of the state
+//! are pruned, and the ledger data of old blocks is moved to the cold
+//! storage, if any.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use node::database::rocksdb::Backend;
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+use crate::Rusk;
+
+/// Transitions of a volume below its free space threshold since startup
+static LOW_SPACE_ALERTS: AtomicU64 = AtomicU64::new(0);
+/// Space reclaiming runs triggered since startup
+static RECLAIMS: AtomicU64 = AtomicU64::new(0);
+/// Last status of the watched volumes
+static VOLUMES: Mutex<Vec<VolumeStatus>> = Mutex::new(Vec::new());
+
+/// What is reclaimed when a volume runs low on space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reclaim {
+    /// Unused commits of the state are deleted
+    PruneCommits,
+    /// The final blocks more than `hot_blocks` below the tip are moved to
+    /// the cold storage
+    MoveToCold { hot_blocks: u64 },
+}
+
+/// A volume watched for free space
+#[derive(Debug, Clone)]
+pub struct Volume {
+    pub name: &'static str,
+    /// Any path on the volume
+    pub path: PathBuf,
+    /// Free space below which an alert is raised and space reclaimed
+    pub min_free_bytes: u64,
+    /// How space is reclaimed, only alerting if `None`
+    pub reclaim: Option<Reclaim>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VolumeStatus {
+    pub name: &'static str,
+    pub path: PathBuf,
+    pub free_bytes: u64,
+    pub min_free_bytes: u64,
+    /// Whether the free space is below the threshold
+    pub low: bool,
+}
+
+/// Free space of the watched volumes, along with the alerts raised
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskStatus {
+    pub volumes: Vec<VolumeStatus>,
+    /// Transitions of a volume below its threshold since startup
+    pub low_space_alerts: u64,
+    /// Space reclaiming runs triggered since startup
+    pub reclaims: u64,
+}
+
+/// Returns the last status of the watched volumes.
+pub fn status() -> DiskStatus {
+    DiskStatus {
+        volumes: VOLUMES.lock().expect("lock to be acquired").clone(),
+        low_space_alerts: LOW_SPACE_ALERTS.load(Ordering::Relaxed),
+        reclaims: RECLAIMS.load(Ordering::Relaxed),
+    }
+}
+
+/// Returns whether a volume with `free_bytes` left is low on space, and
+/// whether an alert is to be raised for it, given whether it `was_low`.
+fn check(free_bytes: u64, min_free_bytes: u64, was_low: bool) -> (bool, bool) {
+    let low = free_bytes < min_free_bytes;
+    (low, low && !was_low)
+}
+
+/// Checks the free space of `volumes` every `interval`, reclaiming space on
+/// the ones running low.
+pub async fn watch(
+    volumes: Vec<Volume>,
+    interval: Duration,
+    rusk: Rusk,
+    db: Backend,
+) {
+    let mut low = vec![false; volumes.len()];
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+
+        let mut statuses = Vec::with_capacity(volumes.len());
+        for (volume, was_low) in volumes.iter().zip(low.iter_mut()) {
+            let free_bytes = match fs2::available_space(&volume.path) {
+                Ok(free_bytes) => free_bytes,
+                Err(err) => {
+                    warn!("Cannot read free space of {:?}: {err}", volume.path);
+                    continue;
+                }
+            };
+
+            let (is_low, alert) =
+                check(free_bytes, volume.min_free_bytes, *was_low);
+            *was_low = is_low;
+            statuses.push(VolumeStatus {
+                name: volume.name,
+                path: volume.path.clone(),
+                free_bytes,
+                min_free_bytes: volume.min_free_bytes,
+                low: is_low,
+            });
+
+            if alert {
+                LOW_SPACE_ALERTS.fetch_add(1, Ordering::Relaxed);
+                error!(
+                    event = "low disk space",
+                    volume = volume.name,
+                    free_bytes,
+                    min_free_bytes = volume.min_free_bytes,
+                );
+            }
+            if let (true, Some(reclaim)) = (is_low, volume.reclaim) {
+                RECLAIMS.fetch_add(1, Ordering::Relaxed);
+                run_reclaim(reclaim, &rusk, &db).await;
+            }
+        }
+
+        *VOLUMES.lock().expect("lock to be acquired") = statuses;
+    }
+}
+
+async fn run_reclaim(reclaim: Reclaim, rusk: &Rusk, db: &Backend) {
+    match reclaim {
+        Reclaim::PruneCommits => {
+            let pruned = rusk.prune_commits();
+            info!("Scheduled the deletion of {pruned} commits");
+        }
+        Reclaim::MoveToCold { hot_blocks } => {
+            let db = db.clone();
+            let res = tokio::task::spawn_blocking(move || {
+                db.move_to_cold(hot_blocks)
+            })
+            .await;
+            match res {
+                Ok(Err(err)) => {
+                    warn!("Cannot move blocks to cold storage: {err}")
+                }
+                Err(err) => error!("Cold storage task failed: {err}"),
+                Ok(Ok(_)) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        // Plenty of space
+        assert_eq!(check(100, 10, false), (false, false));
+        // Running low raises a single alert
+        assert_eq!(check(9, 10, false), (true, true));
+        assert_eq!(check(5, 10, true), (true, false));
+        // Recovering clears the state, the next drop alerts again
+        assert_eq!(check(10, 10, true), (false, false));
+        assert_eq!(check(0, 10, false), (true, true));
+    }
+}
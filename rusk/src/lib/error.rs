@@ -7,6 +7,7 @@
 use std::{fmt, io};
 
 use dusk_bls12_381::BlsScalar;
+use node_data::error::{Classify, ErrorKind};
 use rusk_abi::dusk::Dusk;
 
 #[derive(Debug)]
@@ -19,6 +20,8 @@ pub enum Error {
     ProofVerification,
     /// Out of gas in block execution
     OutOfGas,
+    /// Gas price of a transaction below the base price (fee cap, base price)
+    Underpriced(u64, u64),
     /// Repeated nullifier in transaction verification
     RepeatingNullifiers(Vec<BlsScalar>),
     /// Wrong inputs and/or outputs in the transaction verification
@@ -29,6 +32,8 @@ pub enum Error {
     OpeningPositionNotFound(u64),
     /// Failed to fetch opening due to undefined Note
     OpeningNoteUndefined(u64),
+    /// Not enough notes to cover the amount (amount, max inputs)
+    NotEnoughNotes(u64, usize),
     /// Bytes Serialization Errors
     Serialization(dusk_bytes::Error),
     /// Originating from Phoenix.
@@ -48,19 +53,72 @@ pub enum Error {
     #[cfg(feature = "node")]
     InconsistentState(dusk_consensus::operations::VerificationOutput),
     /// Other
-    Other(Box<dyn std::error::Error>),
+    Other(Box<dyn std::error::Error + Send + Sync>),
     /// Commit not found amongst existing commits
     CommitNotFound([u8; 32]),
+    /// Query not done within the given time
+    QueryTimeout(std::time::Duration),
+    /// Query cancelled before it started
+    QueryCancelled,
+    /// Query quota of the client spent, renewed after the given time
+    QueryQuotaExceeded(std::time::Duration),
+    /// Leaves left to scan to find the notes of a view key (scanned, total)
+    ScanIncomplete(u64, u64),
+    /// More openings requested at once than allowed (requested, max)
+    TooManyOpenings(usize, usize),
+    /// All the permits of a budget are taken (budget name)
+    BudgetExhausted(&'static str),
 }
 
 impl std::error::Error for Error {}
 
-impl From<Box<dyn std::error::Error>> for Error {
-    fn from(err: Box<dyn std::error::Error>) -> Self {
+impl From<Box<dyn std::error::Error + Send + Sync>> for Error {
+    fn from(err: Box<dyn std::error::Error + Send + Sync>) -> Self {
         Error::Other(err)
     }
 }
 
+impl Classify for Error {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            // The local state cannot be trusted anymore
+            Error::CoinbaseBlockHeight(..) | Error::CoinbaseDuskSpent(..) => {
+                ErrorKind::ConsensusCritical
+            }
+            #[cfg(feature = "node")]
+            Error::InconsistentState(_) => ErrorKind::ConsensusCritical,
+
+            // Resources that may be available later
+            Error::Io(_)
+            | Error::Other(_)
+            | Error::QueryTimeout(_)
+            | Error::QueryCancelled
+            | Error::QueryQuotaExceeded(_)
+            | Error::ScanIncomplete(..)
+            | Error::BudgetExhausted(_) => ErrorKind::Transient,
+
+            Error::BackendRegistrationFailed
+            | Error::RestoreFailed
+            | Error::ProofVerification
+            | Error::OutOfGas
+            | Error::Underpriced(..)
+            | Error::RepeatingNullifiers(_)
+            | Error::InvalidCircuitArguments(..)
+            | Error::BuilderInvalidState
+            | Error::OpeningPositionNotFound(_)
+            | Error::OpeningNoteUndefined(_)
+            | Error::NotEnoughNotes(..)
+            | Error::Serialization(_)
+            | Error::Phoenix(_)
+            | Error::Vm(_)
+            | Error::CommitNotFound(_)
+            | Error::TooManyOpenings(..) => ErrorKind::Permanent,
+            #[cfg(feature = "prover")]
+            Error::ProofCreation(_) => ErrorKind::Permanent,
+        }
+    }
+}
+
 impl From<rusk_abi::Error> for Error {
     fn from(err: rusk_abi::Error) -> Self {
         Error::Vm(err)
@@ -110,6 +168,9 @@ impl fmt::Display for Error {
             Error::OpeningNoteUndefined(pos) => {
                 write!(f, "Note {pos} not found, opening of position")
             }
+            Error::NotEnoughNotes(amount, max_inputs) => {
+                write!(f, "Not enough notes to cover {amount} with {max_inputs} inputs")
+            }
             Error::Serialization(err) => {
                 write!(f, "Serialization Error: {err:?}")
             }
@@ -126,6 +187,10 @@ impl fmt::Display for Error {
             }
             Error::ProofVerification => write!(f, "Proof verification failure"),
             Error::OutOfGas => write!(f, "Out of gas"),
+            Error::Underpriced(fee_cap, base_price) => write!(
+                f,
+                "Gas price {fee_cap} is below the base price {base_price}"
+            ),
             Error::RepeatingNullifiers(n) => {
                 write!(f, "Nullifiers repeat: {n:?}")
             }
@@ -146,6 +211,25 @@ impl fmt::Display for Error {
             Error::CommitNotFound(commit_id) => {
                 write!(f, "Commit not found, id = {}", hex::encode(commit_id),)
             }
+            Error::QueryTimeout(timeout) => {
+                write!(f, "Query timed out after {timeout:?}")
+            }
+            Error::QueryCancelled => write!(f, "Query cancelled"),
+            Error::QueryQuotaExceeded(retry) => {
+                write!(f, "Query quota exceeded, renewed in {retry:?}")
+            }
+            Error::ScanIncomplete(scanned, total) => {
+                write!(
+                    f,
+                    "Scanned {scanned} notes out of {total}, retry to resume"
+                )
+            }
+            Error::TooManyOpenings(requested, max) => {
+                write!(f, "Requested {requested} openings, at most {max}")
+            }
+            Error::BudgetExhausted(budget) => {
+                write!(f, "Too many {budget} running, retry later")
+            }
         }
     }
 }
@@ -6,26 +6,30 @@
 
 #![allow(unused)]
 
+mod admin;
 #[cfg(feature = "node")]
 mod chain;
 mod event;
 #[cfg(feature = "prover")]
 mod prover;
+mod request_id;
 #[cfg(feature = "node")]
 mod rusk;
 mod stream;
+mod verifier;
 
+pub use admin::{Admin, OperatorQuorum};
 pub(crate) use event::{
     BinaryWrapper, DataType, ExecutionError, MessageResponse as EventResponse,
     RequestData, Target,
 };
 use hyper::http::{HeaderName, HeaderValue};
-use tracing::info;
+use tracing::{info, info_span, Instrument, Span};
 
 use std::borrow::Cow;
 use std::convert::Infallible;
 use std::future::Future;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::Path;
 use std::pin::Pin;
 use std::str::FromStr;
@@ -49,15 +53,22 @@ use tungstenite::protocol::{CloseFrame, Message};
 
 use futures_util::stream::iter as stream_iter;
 use futures_util::{SinkExt, StreamExt};
+use tower::Layer;
 
 #[cfg(feature = "node")]
-use crate::chain::{Rusk, RuskNode};
-use crate::VERSION;
+use crate::chain::{RuskNode, RuskReader};
+use crate::{budget, VERSION};
+use node_data::error::{Classify, ErrorKind};
 
 use self::event::{MessageRequest, ResponseData};
+use self::request_id::{RequestIdLayer, RUSK_REQUEST_ID_HEADER};
 use self::stream::{Listener, Stream};
 
 const RUSK_VERSION_HEADER: &str = "Rusk-Version";
+/// Header telling whether a failed request is worth retrying
+const RUSK_ERROR_KIND_HEADER: &str = "Rusk-Error-Kind";
+/// Header carrying the gRPC status code of a failed request
+const GRPC_STATUS_HEADER: &str = "grpc-status";
 
 pub struct HttpServer {
     pub handle: task::JoinHandle<()>,
@@ -76,20 +87,76 @@ impl HttpServer {
         H: HandleRequest,
         P1: AsRef<Path>,
         P2: AsRef<Path>,
+    {
+        Self::bind_with_unix_socket(handler, addr, cert_and_key, None::<&Path>)
+            .await
+    }
+
+    /// Binds the server to `addr` and, if given, to a unix socket at
+    /// `unix_socket` as well, allowing local clients to connect without
+    /// going through the network.
+    pub async fn bind_with_unix_socket<A, H, P1, P2, P3>(
+        handler: H,
+        addr: A,
+        cert_and_key: Option<(P1, P2)>,
+        unix_socket: Option<P3>,
+    ) -> io::Result<Self>
+    where
+        A: ToSocketAddrs,
+        H: HandleRequest,
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+        P3: AsRef<Path>,
     {
         let listener = match cert_and_key {
             Some(cert_and_key) => Listener::bind_tls(addr, cert_and_key).await,
             None => Listener::bind(addr).await,
         }?;
 
+        let unix_listener = match unix_socket {
+            #[cfg(unix)]
+            Some(path) => {
+                let listener = Listener::bind_unix(&path)?;
+                info!("Starting HTTP Listener to {}", path.as_ref().display());
+                Some(listener)
+            }
+            #[cfg(not(unix))]
+            Some(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Unix sockets are not supported on this platform",
+                ))
+            }
+            None => None,
+        };
+
         let (shutdown_sender, shutdown_receiver) = broadcast::channel(1);
 
-        let local_addr = listener.local_addr()?;
+        let local_addr = listener.local_addr()?.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "Listener has no address")
+        })?;
 
         info!("Starting HTTP Listener to {local_addr}");
 
-        let handle =
-            task::spawn(listening_loop(handler, listener, shutdown_receiver));
+        let handler = Arc::new(handler);
+        let handle = task::spawn(async move {
+            match unix_listener {
+                Some(unix_listener) => {
+                    let unix_shutdown = shutdown_receiver.resubscribe();
+                    tokio::join!(
+                        listening_loop(
+                            handler.clone(),
+                            listener,
+                            shutdown_receiver
+                        ),
+                        listening_loop(handler, unix_listener, unix_shutdown),
+                    );
+                }
+                None => {
+                    listening_loop(handler, listener, shutdown_receiver).await
+                }
+            }
+        });
 
         Ok(Self {
             handle,
@@ -101,11 +168,13 @@ impl HttpServer {
 
 pub struct DataSources {
     #[cfg(feature = "node")]
-    pub rusk: Rusk,
+    pub rusk: RuskReader,
     #[cfg(feature = "node")]
     pub node: RuskNode,
     #[cfg(feature = "prover")]
     pub prover: rusk_prover::LocalProver,
+    /// Disabled when no admin token is configured
+    pub admin: Option<Admin>,
 }
 
 #[async_trait]
@@ -127,25 +196,116 @@ impl HandleRequest for DataSources {
             {
                 self.prover.handle(request).await
             }
+            (_, "rusk", "task_budgets") => {
+                Ok(ResponseData::new(serde_json::to_value(budget::stats())?))
+            }
             #[cfg(feature = "node")]
             (Target::Contract(_), ..) | (_, "rusk", _) => {
                 self.rusk.handle(request).await
             }
             #[cfg(feature = "node")]
             (_, "Chain", _) => self.node.handle(request).await,
+            (_, "verifier", _) => verifier::VerifierKeys.handle(request).await,
+            #[cfg(feature = "node")]
+            (_, "admin", "commit_deletions") => match &self.admin {
+                Some(admin) => {
+                    admin.authorize(request)?;
+                    Ok(ResponseData::new(serde_json::to_value(
+                        self.rusk.commit_deletions(),
+                    )?))
+                }
+                None => Err(anyhow::anyhow!("admin requests are disabled")),
+            },
+            #[cfg(feature = "node")]
+            (_, "admin", "vote_stats") => match &self.admin {
+                Some(admin) => {
+                    admin.authorize(request)?;
+                    let rounds = request
+                        .event
+                        .data
+                        .as_string()
+                        .trim()
+                        .parse::<usize>()
+                        .unwrap_or(usize::MAX);
+                    self.node.get_vote_stats(rounds).await
+                }
+                None => Err(anyhow::anyhow!("admin requests are disabled")),
+            },
+            #[cfg(feature = "node")]
+            (_, "admin", "performance") => match &self.admin {
+                Some(admin) => {
+                    admin.authorize(request)?;
+                    let rounds = request
+                        .event
+                        .data
+                        .as_string()
+                        .trim()
+                        .parse::<usize>()
+                        .unwrap_or(usize::MAX);
+                    self.node.get_performance(rounds).await
+                }
+                None => Err(anyhow::anyhow!("admin requests are disabled")),
+            },
+            #[cfg(feature = "node")]
+            (_, "admin", "duty_schedule") => match &self.admin {
+                Some(admin) => {
+                    admin.authorize(request)?;
+                    self.node.get_duty_schedule().await
+                }
+                None => Err(anyhow::anyhow!("admin requests are disabled")),
+            },
+            #[cfg(feature = "node")]
+            (_, "admin", "db_stats") => match &self.admin {
+                Some(admin) => {
+                    admin.authorize(request)?;
+                    self.node.db_stats().await
+                }
+                None => Err(anyhow::anyhow!("admin requests are disabled")),
+            },
+            #[cfg(feature = "node")]
+            (_, "admin", "db_compact") => match &self.admin {
+                Some(admin) => {
+                    admin.authorize(request)?;
+                    self.node.db_compact().await
+                }
+                None => Err(anyhow::anyhow!("admin requests are disabled")),
+            },
+            #[cfg(feature = "node")]
+            (_, "admin", "slashing_dry_run") => match &self.admin {
+                Some(admin) => {
+                    admin.authorize(request)?;
+                    self.node.slashing_dry_run(request.event_data()).await
+                }
+                None => Err(anyhow::anyhow!("admin requests are disabled")),
+            },
+            #[cfg(feature = "node")]
+            (_, "admin", "revert") => match &self.admin {
+                Some(admin) => {
+                    admin.authorize(request)?;
+                    let outcome = self.node.3.revert_to_finalized().await?;
+                    if let Err(err) = admin.record_revert(&outcome) {
+                        tracing::warn!(event = "revert not audited", ?err);
+                    }
+                    Ok(ResponseData::new(serde_json::to_value(outcome)?))
+                }
+                None => Err(anyhow::anyhow!("admin requests are disabled")),
+            },
+            (_, "admin", _) => match &self.admin {
+                Some(admin) => admin.handle(request).await,
+                None => Err(anyhow::anyhow!("admin requests are disabled")),
+            },
             _ => Err(anyhow::anyhow!("unsupported target type")),
         }
     }
 }
 
 async fn listening_loop<H>(
-    handler: H,
+    handler: Arc<H>,
     listener: Listener,
     mut shutdown: broadcast::Receiver<Infallible>,
 ) where
     H: HandleRequest,
 {
-    let handler = Arc::new(handler);
     let http = Http::new();
 
     loop {
@@ -154,15 +314,16 @@ async fn listening_loop<H>(
                 break;
             }
             r = listener.accept() => {
-                let stream = match r {
-                    Ok(stream) => stream,
+                let (stream, addr) = match r {
+                    Ok(accepted) => accepted,
                     Err(_) => break,
                 };
 
-                let service = ExecutionService {
+                let service = RequestIdLayer.layer(ExecutionService {
                     sources: handler.clone(),
-                    shutdown: shutdown.resubscribe()
-                };
+                    shutdown: shutdown.resubscribe(),
+                    client: addr.map(|addr| addr.ip()),
+                });
                 let conn = http.serve_connection(stream, service).with_upgrades();
 
                 task::spawn(conn);
@@ -175,6 +336,7 @@ async fn handle_stream<H: HandleRequest>(
     sources: Arc<H>,
     websocket: HyperWebsocket,
     target: Target,
+    client: Option<IpAddr>,
     mut shutdown: broadcast::Receiver<Infallible>,
 ) {
     let mut stream = match websocket.await {
@@ -300,11 +462,20 @@ async fn handle_stream<H: HandleRequest>(
                     // We received a valid request and should spawn a new task to handle it
                     Ok(mut req) => {
                         req.event.target=target.clone();
-                        task::spawn(handle_execution(
-                            sources.clone(),
-                            req,
-                            responder.clone(),
-                        ));
+                        req.client = client;
+                        // Each message is a request of its own
+                        let span = info_span!(
+                            "request",
+                            request_id = %event_request_id(&req),
+                        );
+                        task::spawn(
+                            handle_execution(
+                                sources.clone(),
+                                req,
+                                responder.clone(),
+                            )
+                            .instrument(span),
+                        );
                     },
                     Err(e) => {
                         let _ = stream.close(Some(CloseFrame {
@@ -323,6 +494,8 @@ async fn handle_stream<H: HandleRequest>(
 struct ExecutionService<H> {
     sources: Arc<H>,
     shutdown: broadcast::Receiver<Infallible>,
+    /// Address of the client, unless connected over the unix socket
+    client: Option<IpAddr>,
 }
 
 impl<H> Service<Request<Body>> for ExecutionService<H>
@@ -354,9 +527,10 @@ where
     fn call(&mut self, mut req: Request<Body>) -> Self::Future {
         let sources = self.sources.clone();
         let shutdown = self.shutdown.resubscribe();
+        let client = self.client;
 
         Box::pin(async move {
-            let response = handle_request(req, shutdown, sources).await;
+            let response = handle_request(req, client, shutdown, sources).await;
             response.or_else(|error| {
                 Ok(Response::builder()
                     .status(StatusCode::INTERNAL_SERVER_ERROR)
@@ -369,6 +543,7 @@ where
 
 async fn handle_request<H>(
     mut req: Request<Body>,
+    client: Option<IpAddr>,
     mut shutdown: broadcast::Receiver<Infallible>,
     sources: Arc<H>,
 ) -> Result<Response<Body>, ExecutionError>
@@ -378,12 +553,16 @@ where
     if hyper_tungstenite::is_upgrade_request(&req) {
         let target = req.uri().path().try_into()?;
         let (response, websocket) = hyper_tungstenite::upgrade(&mut req, None)?;
-        task::spawn(handle_stream(sources, websocket, target, shutdown));
+        task::spawn(
+            handle_stream(sources, websocket, target, client, shutdown)
+                .instrument(Span::current()),
+        );
 
         Ok(response)
     } else {
-        let (execution_request, binary_resp) =
+        let (mut execution_request, binary_resp) =
             MessageRequest::from_request(req).await?;
+        execution_request.client = client;
 
         let mut resp_headers = execution_request.x_headers();
 
@@ -418,8 +597,16 @@ async fn handle_execution<H>(
 ) where
     H: HandleRequest,
 {
+    let request_id = event_request_id(&request);
+    let span = info_span!(
+        "execution",
+        target = request.event.target.inner(),
+        topic = %request.event.topic,
+    );
+
     let mut rsp = sources
         .handle(&request)
+        .instrument(span)
         .await
         .map(|data| {
             let (data, mut headers) = data.into_inner();
@@ -430,12 +617,67 @@ async fn handle_execution<H>(
                 headers,
             }
         })
-        .unwrap_or_else(|e| request.to_error(e.to_string()));
+        .unwrap_or_else(|e| {
+            let mut rsp = request.to_error(e.to_string());
+            if let Some(kind) = error_kind(&e) {
+                rsp.set_header(
+                    RUSK_ERROR_KIND_HEADER,
+                    serde_json::json!(kind.as_str()),
+                );
+                rsp.set_header(
+                    GRPC_STATUS_HEADER,
+                    serde_json::json!(kind.grpc_code()),
+                );
+            }
+            rsp
+        });
 
     rsp.set_header(RUSK_VERSION_HEADER, serde_json::json!(*VERSION));
+    rsp.set_header(RUSK_REQUEST_ID_HEADER, serde_json::json!(request_id));
     let _ = responder.send(rsp);
 }
 
+/// Returns the correlation id of a request, as set by [`RequestIdLayer`]
+/// over HTTP or provided along with a message over websockets.
+fn event_request_id(request: &MessageRequest) -> String {
+    let provided = match request.header(RUSK_REQUEST_ID_HEADER) {
+        Some(serde_json::Value::String(id)) => Some(id.clone()),
+        Some(serde_json::Value::Number(id)) => Some(id.to_string()),
+        _ => None,
+    };
+
+    request_id::request_id(provided.as_deref())
+}
+
+/// Runs `f` on the blocking threads of the runtime, within the span of the
+/// request it serves.
+fn spawn_blocking<F, R>(f: F) -> task::JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let span = Span::current();
+    task::spawn_blocking(move || span.in_scope(f))
+}
+
+/// Returns the kind of the failure of a request, if known.
+fn error_kind(error: &anyhow::Error) -> Option<ErrorKind> {
+    error.chain().find_map(|e| {
+        if let Some(e) = e.downcast_ref::<crate::Error>() {
+            return Some(e.kind());
+        }
+        #[cfg(feature = "node")]
+        if let Some(e) = e.downcast_ref::<node::mempool::TxAcceptanceError>() {
+            return Some(e.kind());
+        }
+        if let Some(e) = e.downcast_ref::<node_data::error::Classified>() {
+            return Some(e.kind());
+        }
+        e.downcast_ref::<node_data::ledger::SizeLimitError>()
+            .map(Classify::kind)
+    })
+}
+
 #[async_trait]
 pub trait HandleRequest: Send + Sync + 'static {
     async fn handle(
@@ -509,11 +751,18 @@ mod tests {
         let client = reqwest::Client::new();
         let response = client
             .post(format!("http://{}/01/target", server.local_addr))
+            .header(RUSK_REQUEST_ID_HEADER, "wallet-42")
             .body(Body::from(request))
             .send()
             .await
             .expect("Requesting should succeed");
 
+        assert_eq!(
+            response.headers().get(RUSK_REQUEST_ID_HEADER).unwrap(),
+            "wallet-42",
+            "Request id to be returned"
+        );
+
         let response_bytes =
             response.bytes().await.expect("There should be a response");
         let response_bytes =
@@ -526,6 +775,64 @@ mod tests {
         );
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_query() {
+        let cert_and_key: Option<(String, String)> = None;
+        let dir = tempfile::tempdir().expect("creating a tempdir to succeed");
+        let socket_path = dir.path().join("rusk-http.sock");
+
+        let _server = HttpServer::bind_with_unix_socket(
+            TestHandle,
+            "localhost:0",
+            cert_and_key,
+            Some(&socket_path),
+        )
+        .await
+        .expect("Binding the server to the socket should succeed");
+
+        let data = Vec::from(&b"I am call data 0"[..]);
+        let data = RequestData::Binary(BinaryWrapper { inner: data });
+
+        let event = EventRequest {
+            target: Target::None,
+            data,
+            topic: "topic".into(),
+        };
+
+        let request = serde_json::to_vec(&event)
+            .expect("Serializing request should succeed");
+
+        let stream = tokio::net::UnixStream::connect(&socket_path)
+            .await
+            .expect("Connecting to the socket should succeed");
+        let (mut sender, conn) = hyper::client::conn::handshake(stream)
+            .await
+            .expect("Handshake should succeed");
+        task::spawn(conn);
+
+        let request = Request::post("/01/target")
+            .header("host", "localhost")
+            .body(Body::from(request))
+            .expect("Request should be valid");
+        let response = sender
+            .send_request(request)
+            .await
+            .expect("Requesting should succeed");
+
+        let response_bytes = body::to_bytes(response.into_body())
+            .await
+            .expect("There should be a response");
+        let response_bytes =
+            hex::decode(response_bytes).expect("data to be hex encoded");
+        let request_bytes = event.data.as_bytes();
+
+        assert_eq!(
+            request_bytes, response_bytes,
+            "Data received the same as sent"
+        );
+    }
+
     #[tokio::test]
     async fn https_query() {
         let cert_path = "tests/assets/cert.pem";
@@ -610,6 +917,7 @@ mod tests {
         let request = MessageRequest {
             event,
             headers: request_x_header.clone(),
+            client: None,
         };
 
         let request = serde_json::to_string(&request).unwrap();
@@ -656,4 +964,70 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn request_ids() {
+        let request = |headers: &str| MessageRequest {
+            headers: serde_json::from_str(headers).unwrap(),
+            event: EventRequest {
+                target: Target::None,
+                data: RequestData::Text("".into()),
+                topic: "topic".into(),
+            },
+            client: None,
+        };
+
+        let id =
+            event_request_id(&request(r#"{"rusk-request-id": "wallet-42"}"#));
+        assert_eq!(id, "wallet-42");
+        let id = event_request_id(&request(r#"{"Rusk-Request-Id": 42}"#));
+        assert_eq!(id, "42");
+
+        // Missing or invalid ids are replaced by random ones
+        assert_eq!(event_request_id(&request("{}")).len(), 32);
+        let id = event_request_id(&request(r#"{"Rusk-Request-Id": "a\nb"}"#));
+        assert_eq!(id.len(), 32);
+    }
+
+    #[test]
+    fn classify_errors() {
+        let err = anyhow::Error::from(crate::Error::OutOfGas);
+        assert_eq!(error_kind(&err), Some(ErrorKind::Permanent));
+
+        let err =
+            anyhow::Error::from(crate::Error::Io(std::io::Error::other("")))
+                .context("cannot query");
+        assert_eq!(error_kind(&err), Some(ErrorKind::Transient));
+
+        assert_eq!(error_kind(&anyhow::anyhow!("Unsupported")), None);
+
+        let err = node_data::error::Classified::new(
+            ErrorKind::Transient,
+            anyhow::anyhow!("Cannot check nullifiers"),
+        );
+        let err = anyhow::Error::from(err).context("cannot preverify");
+        assert_eq!(error_kind(&err), Some(ErrorKind::Transient));
+    }
+
+    #[test]
+    fn grpc_status() {
+        let status = |kind: Option<ErrorKind>| {
+            let mut rsp = EventResponse::from_error("failed".into());
+            if let Some(kind) = kind {
+                rsp.headers.insert(
+                    RUSK_ERROR_KIND_HEADER.into(),
+                    serde_json::json!(kind.as_str()),
+                );
+            }
+            let rsp = rsp.into_http(false).expect("response to be built");
+            let code = &rsp.headers()[GRPC_STATUS_HEADER];
+            (rsp.status().as_u16(), code.to_str().unwrap().to_string())
+        };
+
+        assert_eq!(status(Some(ErrorKind::Transient)), (503, "14".into()));
+        assert_eq!(status(Some(ErrorKind::Permanent)), (400, "9".into()));
+        let kind = Some(ErrorKind::ConsensusCritical);
+        assert_eq!(status(kind), (500, "13".into()));
+        assert_eq!(status(None), (500, "2".into()));
+    }
 }
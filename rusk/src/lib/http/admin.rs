@@ -0,0 +1,415 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use dusk_bls12_381_sign::{PublicKey as BlsPublicKey, Signature, APK};
+use dusk_bytes::Serializable;
+use serde::Deserialize;
+
+use super::*;
+
+use crate::audit::{self, AuditLog};
+
+/// Token the admin requests must carry
+const RUSK_ADMIN_TOKEN_HEADER: &str = "Rusk-Admin-Token";
+/// Seconds since the unix epoch at which a destructive request was signed
+const RUSK_ADMIN_TIMESTAMP_HEADER: &str = "Rusk-Admin-Timestamp";
+/// Unique value of a destructive request, so that its signatures are
+/// accepted once
+const RUSK_ADMIN_NONCE_HEADER: &str = "Rusk-Admin-Nonce";
+/// Comma separated `<bs58 public key>:<bs58 signature>` pairs of the
+/// operators authorizing a destructive request
+const RUSK_ADMIN_SIGNATURES_HEADER: &str = "Rusk-Admin-Signatures";
+
+/// Age after which the signatures of a destructive request are rejected
+const MAX_SIGNATURES_AGE: Duration = Duration::from_secs(300);
+
+/// Maximum length of the nonce of a destructive request
+const MAX_NONCE_LEN: usize = 64;
+
+/// Admin operations changing the node state, which must be authorized by a
+/// quorum of operators on top of the admin token.
+///
+/// Snapshots are imported through the command line, with the node stopped.
+const DESTRUCTIVE_TOPICS: &[&str] = &["revert"];
+
+/// Operator keys authorizing the destructive admin operations, a threshold
+/// of which must sign each request.
+///
+/// The signatures are bound to the chain and the node the request is meant
+/// for, and to a nonce accepted once, so that they cannot be replayed.
+pub struct OperatorQuorum {
+    keys: Vec<BlsPublicKey>,
+    threshold: usize,
+    chain_id: u8,
+    node_id: String,
+    /// Nonces accepted within the last [`MAX_SIGNATURES_AGE`], along with the
+    /// timestamp of their request
+    nonces: Mutex<HashMap<String, u64>>,
+}
+
+impl OperatorQuorum {
+    /// Creates a quorum of `threshold` out of the given base58 encoded BLS
+    /// public keys, authorizing the requests meant for `node_id` on the
+    /// network `chain_id`.
+    pub fn new(
+        keys: &[String],
+        threshold: usize,
+        chain_id: u8,
+        node_id: String,
+    ) -> anyhow::Result<Self> {
+        let keys = keys
+            .iter()
+            .map(|key| decode_bs58::<BlsPublicKey, 96>(key))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        if threshold == 0 || threshold > keys.len() {
+            anyhow::bail!(
+                "Invalid threshold {threshold} for {} operator keys",
+                keys.len()
+            );
+        }
+
+        Ok(Self {
+            keys,
+            threshold,
+            chain_id,
+            node_id,
+            nonces: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the payload the operators sign to authorize a request.
+    pub fn signed_payload(
+        &self,
+        topic: &str,
+        timestamp: u64,
+        nonce: &str,
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(
+            topic.len() + self.node_id.len() + nonce.len() + data.len() + 17,
+        );
+        payload.extend_from_slice(topic.as_bytes());
+        payload.push(self.chain_id);
+        payload.extend_from_slice(&(self.node_id.len() as u32).to_le_bytes());
+        payload.extend_from_slice(self.node_id.as_bytes());
+        payload.extend_from_slice(&timestamp.to_le_bytes());
+        payload.extend_from_slice(&(nonce.len() as u32).to_le_bytes());
+        payload.extend_from_slice(nonce.as_bytes());
+        payload.extend_from_slice(data);
+        payload
+    }
+
+    /// Accepts `nonce` if not used by any request signed within the last
+    /// [`MAX_SIGNATURES_AGE`].
+    fn use_nonce(&self, nonce: &str, timestamp: u64, now: u64) -> bool {
+        let mut nonces = self.nonces.lock().expect("lock to be acquired");
+
+        // Expired requests are rejected anyway
+        nonces.retain(|_, t| now.abs_diff(*t) <= MAX_SIGNATURES_AGE.as_secs());
+        if nonces.contains_key(nonce) {
+            return false;
+        }
+        nonces.insert(nonce.to_string(), timestamp);
+        true
+    }
+
+    /// Checks that `signatures` holds valid signatures of `payload` by at
+    /// least `threshold` distinct operators.
+    fn verify(&self, payload: &[u8], signatures: &str) -> anyhow::Result<()> {
+        let mut signers = vec![];
+        for pair in signatures.split(',').map(str::trim) {
+            let (key, sig) = pair
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Invalid signature {pair}"))?;
+            let key = decode_bs58::<BlsPublicKey, 96>(key)?;
+            let sig = decode_bs58::<Signature, 48>(sig)?;
+
+            if !self.keys.contains(&key) {
+                anyhow::bail!("Unknown operator key {pair}");
+            }
+            APK::from(&key)
+                .verify(&sig, payload)
+                .map_err(|_| anyhow::anyhow!("Invalid signature {pair}"))?;
+            if !signers.contains(&key) {
+                signers.push(key);
+            }
+        }
+
+        if signers.len() < self.threshold {
+            anyhow::bail!(
+                "{} operator signatures, {} required",
+                signers.len(),
+                self.threshold
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Compares two byte strings in a time depending only on their lengths.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn decode_bs58<T, const N: usize>(s: &str) -> anyhow::Result<T>
+where
+    T: Serializable<N>,
+{
+    let bytes = bs58::decode(s).into_vec()?;
+    let bytes: [u8; N] = bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid length of {s}"))?;
+    T::from_bytes(&bytes).map_err(|_| anyhow::anyhow!("Invalid bytes {s}"))
+}
+
+/// Handler of the requests reserved to the node operator.
+pub struct Admin {
+    token: String,
+    audit: AuditLog,
+    quorum: Option<OperatorQuorum>,
+}
+
+impl Admin {
+    pub fn new(token: String, audit: AuditLog) -> Self {
+        Self {
+            token,
+            audit,
+            quorum: None,
+        }
+    }
+
+    /// Sets the operators authorizing the destructive operations, which are
+    /// refused otherwise.
+    pub fn with_quorum(mut self, quorum: OperatorQuorum) -> Self {
+        self.quorum = Some(quorum);
+        self
+    }
+
+    /// Checks that a request carries the admin token, and that the
+    /// destructive ones are signed by a quorum of operators.
+    pub(crate) fn authorize(
+        &self,
+        request: &MessageRequest,
+    ) -> anyhow::Result<()> {
+        match request.header(RUSK_ADMIN_TOKEN_HEADER) {
+            Some(serde_json::Value::String(token))
+                if constant_time_eq(
+                    token.as_bytes(),
+                    self.token.as_bytes(),
+                ) => {}
+            _ => anyhow::bail!("Invalid admin token"),
+        }
+
+        if DESTRUCTIVE_TOPICS.contains(&request.event.topic.as_str()) {
+            self.authorize_destructive(request)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the identity recorded in the audit log for the operations
+    /// performed through the admin requests.
+    pub fn operator(&self) -> String {
+        audit::admin_operator(&self.token)
+    }
+
+    /// Checks that a destructive request is signed by a quorum of operators.
+    fn authorize_destructive(
+        &self,
+        request: &MessageRequest,
+    ) -> anyhow::Result<()> {
+        let quorum = self.quorum.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("No operator keys authorizing the operation")
+        })?;
+
+        let header = |name| match request.header(name) {
+            Some(serde_json::Value::String(value)) => Ok(value),
+            _ => Err(anyhow::anyhow!("Missing {name} header")),
+        };
+        let timestamp: u64 = header(RUSK_ADMIN_TIMESTAMP_HEADER)?.parse()?;
+        let nonce = header(RUSK_ADMIN_NONCE_HEADER)?;
+        let signatures = header(RUSK_ADMIN_SIGNATURES_HEADER)?;
+
+        if nonce.is_empty() || nonce.len() > MAX_NONCE_LEN {
+            anyhow::bail!("Invalid nonce length");
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        if now.abs_diff(timestamp) > MAX_SIGNATURES_AGE.as_secs() {
+            anyhow::bail!("Expired operator signatures");
+        }
+
+        let payload = quorum.signed_payload(
+            &request.event.topic,
+            timestamp,
+            nonce,
+            request.event_data(),
+        );
+        quorum.verify(&payload, signatures)?;
+
+        // The nonce is only used once the signatures are verified, so that
+        // it cannot be burnt by an unsigned request
+        if !quorum.use_nonce(nonce, timestamp, now) {
+            anyhow::bail!("Nonce already used");
+        }
+        Ok(())
+    }
+
+    /// Records a revert of the chain requested through the admin requests.
+    #[cfg(feature = "node")]
+    pub(crate) fn record_revert(
+        &self,
+        outcome: &node::chain::RevertOutcome,
+    ) -> anyhow::Result<()> {
+        self.audit.record(
+            audit::Operation::Revert,
+            &self.operator(),
+            Some(outcome.from_state_root),
+            Some(outcome.to_state_root),
+        )?;
+        Ok(())
+    }
+
+    fn handle_audit(&self, data: &[u8]) -> anyhow::Result<ResponseData> {
+        let page: AuditPage = match data.is_empty() {
+            true => AuditPage::default(),
+            false => serde_json::from_slice(data)?,
+        };
+
+        let entries = self
+            .audit
+            .entries(page.offset, page.limit.unwrap_or(usize::MAX))?;
+        Ok(ResponseData::new(serde_json::to_value(entries)?))
+    }
+}
+
+#[async_trait]
+impl HandleRequest for Admin {
+    async fn handle(
+        &self,
+        request: &MessageRequest,
+    ) -> anyhow::Result<ResponseData> {
+        self.authorize(request)?;
+
+        match request.event.to_route() {
+            (Target::Host(_), "admin", "audit") => {
+                self.handle_audit(request.event_data())
+            }
+            _ => Err(anyhow::anyhow!("Unsupported")),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+struct AuditPage {
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use dusk_bls12_381_sign::SecretKey;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token2"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[test]
+    fn test_operator_quorum() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let operators: Vec<_> = (0..3)
+            .map(|_| {
+                let sk = SecretKey::random(&mut rng);
+                (sk, BlsPublicKey::from(&sk))
+            })
+            .collect();
+        let keys: Vec<_> = operators
+            .iter()
+            .map(|(_, pk)| bs58::encode(pk.to_bytes()).into_string())
+            .collect();
+
+        assert!(OperatorQuorum::new(&keys, 0, 0, "node".into()).is_err());
+        assert!(OperatorQuorum::new(&keys, 4, 0, "node".into()).is_err());
+        let quorum = OperatorQuorum::new(&keys, 2, 0, "node".into()).unwrap();
+
+        let payload = quorum.signed_payload("revert", 42, "nonce", b"data");
+        let sign = |i: usize, payload: &[u8]| {
+            let (sk, pk) = &operators[i];
+            let sig = sk.sign(pk, payload);
+            format!(
+                "{}:{}",
+                keys[i],
+                bs58::encode(sig.to_bytes()).into_string()
+            )
+        };
+
+        let two = format!("{},{}", sign(0, &payload), sign(2, &payload));
+        assert!(quorum.verify(&payload, &two).is_ok());
+
+        // The same operator is only counted once
+        let repeated = format!("{},{}", sign(0, &payload), sign(0, &payload));
+        assert!(quorum.verify(&payload, &repeated).is_err());
+
+        // Signatures of another payload are rejected
+        let other = quorum.signed_payload("revert", 43, "nonce", b"data");
+        assert!(quorum.verify(&other, &two).is_err());
+        let other = quorum.signed_payload("revert", 42, "other", b"data");
+        assert!(quorum.verify(&other, &two).is_err());
+
+        // Signatures meant for another node or chain are rejected
+        let node = OperatorQuorum::new(&keys, 2, 0, "other".into()).unwrap();
+        let other = node.signed_payload("revert", 42, "nonce", b"data");
+        assert!(quorum.verify(&other, &two).is_err());
+        let chain = OperatorQuorum::new(&keys, 2, 1, "node".into()).unwrap();
+        let other = chain.signed_payload("revert", 42, "nonce", b"data");
+        assert!(quorum.verify(&other, &two).is_err());
+
+        // Keys outside of the quorum are rejected
+        let sk = SecretKey::random(&mut rng);
+        let pk = BlsPublicKey::from(&sk);
+        let outsider = format!(
+            "{}:{}",
+            bs58::encode(pk.to_bytes()).into_string(),
+            bs58::encode(sk.sign(&pk, &payload).to_bytes()).into_string()
+        );
+        let forged = format!("{},{}", sign(0, &payload), outsider);
+        assert!(quorum.verify(&payload, &forged).is_err());
+    }
+
+    #[test]
+    fn test_nonce_is_used_once() {
+        let sk = SecretKey::random(&mut StdRng::seed_from_u64(0xbeef));
+        let key = bs58::encode(BlsPublicKey::from(&sk).to_bytes());
+        let quorum =
+            OperatorQuorum::new(&[key.into_string()], 1, 0, "node".into())
+                .unwrap();
+
+        let now = 1_000;
+        assert!(quorum.use_nonce("a", now, now));
+        assert!(!quorum.use_nonce("a", now, now + 1), "replayed");
+        assert!(quorum.use_nonce("b", now, now + 1));
+
+        // Nonces are forgotten once their signatures expire
+        let later = now + MAX_SIGNATURES_AGE.as_secs() + 1;
+        assert!(quorum.use_nonce("a", later, later));
+    }
+}
@@ -6,28 +6,198 @@
 
 pub mod graphql;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
 
-use node::database::rocksdb::{Backend, DBTransaction};
-use node::database::{Mempool, DB};
+use dusk_bls12_381::BlsScalar;
+use dusk_bytes::DeserializableSlice;
+use dusk_consensus::vote_stats::{AbsenceStreaks, VoteStats};
+use node::chain::finality::{self, BlockFinality};
+use node::chain::StaleTipAlerts;
+use node::database::rocksdb::{
+    md_vote_stats_key, Backend, DBTransaction, MD_ABSENCE_STREAKS, MD_HASH_KEY,
+    MD_STALE_TIP, VOTE_STATS_ROUNDS,
+};
+use node::database::{EventPosition, Ledger, Mempool, Metadata, DB};
+use node::mempool;
 use node::network::Kadcast;
 use node::Network;
-use node_data::ledger::Transaction;
+use node_data::ledger::{self, ContractEvent, Transaction};
 use node_data::message::Message;
+use node_data::Serializable;
+use phoenix_core::Note;
 
 use graphql::{DBContext, Query};
 
 use async_graphql::{
     EmptyMutation, EmptySubscription, Name, Schema, Variables,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use tracing::warn;
 
 use super::*;
+use crate::chain::{decode_event, NoteOpening, Penalty};
 use crate::http::RuskNode;
 use crate::{VERSION, VERSION_BUILD};
 
+/// Maximum number of blocks the fee statistics are computed over
+const MAX_FEE_STATS_BLOCKS: usize = 1000;
+const DEFAULT_FEE_STATS_BLOCKS: usize = 20;
+
+/// Number of blocks read ahead of a slow consumer of a blocks stream
+const BLOCKS_STREAM_BUFFER: usize = 16;
+
+/// Maximum number of blocks sent by a single blocks stream
+const MAX_STREAMED_BLOCKS: u64 = 1000;
+
+/// Interval at which the finality of the tip is polled for its stream
+const FINALITY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of events returned by a single events request
+const MAX_EVENTS_PAGE: usize = 1000;
+
+/// Maximum number of blocks the gas usage by contract is computed over
+const MAX_GAS_USAGE_BLOCKS: u64 = 10_000;
+const DEFAULT_GAS_USAGE_CONTRACTS: usize = 20;
+
+/// Number of mempool transactions the gas prices of the proof inputs are
+/// computed over
+const PROOF_INPUTS_GAS_PRICES: usize = 100;
+
+/// Request of the events emitted by a contract with a given topic
+#[derive(Debug, Deserialize)]
+struct EventsRequest {
+    /// Hex encoded id of the contract
+    contract: String,
+    topic: String,
+    /// First block height searched
+    #[serde(default)]
+    from: u64,
+    /// Last block height searched, the tip if omitted
+    to: Option<u64>,
+    limit: Option<usize>,
+    /// Position to resume from, as returned by a previous request
+    cursor: Option<EventPosition>,
+}
+
+/// Request of the gas spent per contract over a range of blocks
+#[derive(Debug, Deserialize)]
+struct GasUsageRequest {
+    from_height: u64,
+    /// Last block height included, the tip if omitted
+    to_height: Option<u64>,
+    /// Maximum number of contracts returned, top consumers first
+    limit: Option<usize>,
+}
+
+/// Everything needed to build the proof of a transaction spending notes,
+/// taken from a single state.
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive_attr(derive(bytecheck::CheckBytes))]
+struct ProofInputs {
+    /// State root the notes are read at
+    state_root: [u8; 32],
+    /// Root of the transfer tree the openings are valid for
+    anchor: BlsScalar,
+    /// Notes at the requested positions, with their openings
+    notes: Vec<(Note, NoteOpening)>,
+    /// Gas prices of the transactions in the mempool
+    gas_price: GasPrices,
+}
+
+/// Gas prices of the mempool transactions, all 1 if the mempool is empty
+#[derive(Debug, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[archive_attr(derive(bytecheck::CheckBytes))]
+struct GasPrices {
+    min: u64,
+    median: u64,
+    max: u64,
+}
+
+/// An event as fed to the event hash, with hex encoded source and data
+#[derive(Debug, Deserialize)]
+struct EventHashEntry {
+    source: String,
+    topic: String,
+    data: String,
+}
+
+/// Gas utilization of a block
+#[derive(Debug, Clone, Serialize)]
+struct BlockFeeStats {
+    height: u64,
+    gas_limit: u64,
+    gas_used: u64,
+    transactions: usize,
+    /// Gas prices of the included transactions, if any
+    gas_price: Option<PriceStats>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct PriceStats {
+    min: u64,
+    median: u64,
+    max: u64,
+}
+
+impl PriceStats {
+    fn from_prices(mut prices: Vec<u64>) -> Option<Self> {
+        if prices.is_empty() {
+            return None;
+        }
+        prices.sort_unstable();
+
+        let mid = prices.len() / 2;
+        let median = if prices.len() % 2 == 0 {
+            // The prices are sorted, so this can't overflow
+            prices[mid - 1] + (prices[mid] - prices[mid - 1]) / 2
+        } else {
+            prices[mid]
+        };
+
+        Some(Self {
+            min: prices[0],
+            median,
+            max: prices[prices.len() - 1],
+        })
+    }
+}
+
+fn finality_json(finality: &BlockFinality) -> serde_json::Value {
+    json!({
+        "height": finality.height,
+        "hash": hex::encode(finality.hash),
+        "finality": finality.finality.as_str(),
+        "confirmations": finality.confirmations,
+        "last_final": finality.last_final,
+    })
+}
+
+/// Parses the range of heights of a blocks stream request, at most
+/// [`MAX_STREAMED_BLOCKS`] long.
+fn stream_range(data: &[u8]) -> anyhow::Result<(u64, u64)> {
+    let (from, to) = match data.len() {
+        8 => {
+            let from = u64::from_le_bytes(data.try_into()?);
+            (from, from.saturating_add(MAX_STREAMED_BLOCKS - 1))
+        }
+        16 => (
+            u64::from_le_bytes(data[..8].try_into()?),
+            u64::from_le_bytes(data[8..].try_into()?),
+        ),
+        len => anyhow::bail!("Invalid Data length {len}"),
+    };
+    if from > to || to - from >= MAX_STREAMED_BLOCKS {
+        anyhow::bail!(
+            "Invalid range {from}..={to}, at most {MAX_STREAMED_BLOCKS} blocks"
+        );
+    }
+    Ok((from, to))
+}
+
 const GQL_VAR_PREFIX: &str = "rusk-gqlvar-";
 
 fn variables_from_request(request: &MessageRequest) -> Variables {
@@ -58,11 +228,34 @@ impl HandleRequest for RuskNode {
             (Target::Host(_), "Chain", "propagate_tx") => {
                 self.propagate_tx(request.event_data()).await
             }
+            (Target::Host(_), "Chain", "propagate_transaction") => {
+                self.propagate_transaction(request.event_data()).await
+            }
+            (Target::Host(_), "Chain", "preverify_transaction") => {
+                self.preverify_transaction(request.event_data()).await
+            }
+            (Target::Host(_), "Chain", "propagation_status") => {
+                let id = request.event.data.as_string();
+                self.propagation_status(id.trim()).await
+            }
+            (Target::Host(_), "Chain", "mempool_occupancy") => {
+                self.mempool_occupancy().await
+            }
             (Target::Host(_), "Chain", "alive_nodes") => {
                 let amount = request.event.data.as_string().trim().parse()?;
                 self.alive_nodes(amount).await
             }
             (Target::Host(_), "Chain", "info") => self.get_info().await,
+            (Target::Host(_), "Chain", "absence_streaks") => {
+                let min_streak = request
+                    .event
+                    .data
+                    .as_string()
+                    .trim()
+                    .parse::<u32>()
+                    .unwrap_or(1);
+                self.get_absence_streaks(min_streak).await
+            }
             (Target::Host(_), "Chain", "gas") => {
                 let max_transactions = request
                     .event
@@ -73,6 +266,56 @@ impl HandleRequest for RuskNode {
                     .unwrap_or(usize::MAX);
                 self.get_gas_price(max_transactions).await
             }
+            (Target::Host(_), "Chain", "blocks") => {
+                self.stream_blocks(request.event_data()).await
+            }
+            (Target::Host(_), "Chain", "receipts") => {
+                self.stream_receipts(request.event_data()).await
+            }
+            (Target::Host(_), "Chain", "stale_tip") => {
+                self.get_stale_tip_alerts().await
+            }
+            (Target::Host(_), "Chain", "events") => {
+                self.get_events(request.event_data()).await
+            }
+            (Target::Host(_), "Chain", "event_hash") => {
+                self.compute_event_hash(request.event_data())
+            }
+            (Target::Host(_), "Chain", "proof_inputs") => {
+                self.get_proof_inputs(request.event_data()).await
+            }
+            (Target::Host(_), "Chain", "gas_usage_by_contract") => {
+                self.get_gas_usage_by_contract(request.event_data()).await
+            }
+            (Target::Host(_), "Chain", "fee_stats") => {
+                let last_n_blocks = request
+                    .event
+                    .data
+                    .as_string()
+                    .trim()
+                    .parse::<usize>()
+                    .unwrap_or(DEFAULT_FEE_STATS_BLOCKS);
+                self.get_fee_stats(last_n_blocks).await
+            }
+            (Target::Host(_), "Chain", "epoch_summary") => {
+                let epoch = request.event.data.as_string().trim().parse()?;
+                self.get_epoch_summary(epoch).await
+            }
+            (Target::Host(_), "Chain", "round_seed") => {
+                let height = request.event.data.as_string().trim().parse()?;
+                self.get_round_seed(height).await
+            }
+            (Target::Host(_), "Chain", "finality") => {
+                let height = request.event.data.as_string();
+                let height = match height.trim() {
+                    "" => None,
+                    height => Some(height.parse()?),
+                };
+                self.get_finality(height).await
+            }
+            (Target::Host(_), "Chain", "finality_stream") => {
+                self.stream_finality().await
+            }
             _ => anyhow::bail!("Unsupported"),
         }
     }
@@ -107,9 +350,10 @@ impl RuskNode {
     }
 
     async fn propagate_tx(&self, tx: &[u8]) -> anyhow::Result<ResponseData> {
-        let tx = phoenix_core::Transaction::from_slice(tx)
+        let tx: Transaction = phoenix_core::Transaction::from_slice(tx)
             .map_err(|e| anyhow::anyhow!("Invalid Data {e:?}"))?
             .into();
+        let tx = tx.with_chain_id(self.chain_id().await?);
         let tx_message = Message::new_transaction(tx);
 
         let network = self.0.network();
@@ -118,6 +362,79 @@ impl RuskNode {
         Ok(ResponseData::new(DataType::None))
     }
 
+    /// Pre-verifies the transaction against the tip, adds it to the mempool
+    /// and gossips it, returning the outcome with its correlation ID.
+    async fn propagate_transaction(
+        &self,
+        tx: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let tx: Transaction = phoenix_core::Transaction::from_slice(tx)
+            .map_err(|e| anyhow::anyhow!("Invalid Data {e:?}"))?
+            .into();
+        let chain_id = self.chain_id().await?;
+        let tx = tx.with_chain_id(chain_id);
+
+        let record = mempool::propagate_tx(
+            chain_id,
+            self.1.as_ref(),
+            self.2,
+            &self.network(),
+            &self.db(),
+            &self.0.vm_handler(),
+            tx,
+        )
+        .await?;
+
+        Ok(ResponseData::new(serde_json::to_value(record)?))
+    }
+
+    /// Runs the static checks of the mempool admission on the transaction,
+    /// without adding it to the mempool nor gossiping it.
+    ///
+    /// The transaction is expected as gossiped on the network, along with the
+    /// chain id it is meant for.
+    async fn preverify_transaction(
+        &self,
+        mut tx: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let tx = Transaction::read(&mut tx)
+            .map_err(|e| anyhow::anyhow!("Invalid Data {e:?}"))?;
+        let chain_id = self.chain_id().await?;
+
+        let res = mempool::preverify::preverify_tx(
+            chain_id,
+            self.1.as_ref(),
+            &self.db(),
+            &self.0.vm_handler(),
+            &tx,
+        )
+        .await?;
+
+        Ok(ResponseData::new(serde_json::to_value(res)?))
+    }
+
+    async fn propagation_status(
+        &self,
+        id: &str,
+    ) -> anyhow::Result<ResponseData> {
+        let record = mempool::propagation_record(id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown correlation id {id}"))?;
+
+        Ok(ResponseData::new(serde_json::to_value(record)?))
+    }
+
+    /// Returns the bytes taken by the mempool transactions, along with the
+    /// evictions since the node started.
+    async fn mempool_occupancy(&self) -> anyhow::Result<ResponseData> {
+        let occupancy = self
+            .db()
+            .read()
+            .await
+            .view(|t| mempool::budget::occupancy(&t, self.2))?;
+
+        Ok(ResponseData::new(serde_json::to_value(occupancy)?))
+    }
+
     async fn alive_nodes(&self, amount: usize) -> anyhow::Result<ResponseData> {
         let nodes = self.0.network().read().await.alive_nodes(amount).await;
         let nodes: Vec<_> = nodes.iter().map(|n| n.to_string()).collect();
@@ -129,14 +446,300 @@ impl RuskNode {
         info.insert("version", VERSION.as_str().into());
         info.insert("version_build", VERSION_BUILD.as_str().into());
 
-        let n_conf = self.network().read().await.conf().clone();
+        let network = self.network();
+        let network = network.read().await;
+        info.insert("protocol_version", node::network::PROTOCOL_VERSION.into());
+        info.insert(
+            "peer_protocol_versions",
+            serde_json::to_value(network.peer_protocol_versions())?,
+        );
+        let n_conf = network.conf().clone();
         info.insert("bootstrapping_nodes", n_conf.bootstrapping_nodes.into());
         info.insert("chain_id", n_conf.kadcast_id.into());
         info.insert("kadcast_address", n_conf.public_address.into());
+        info.insert("kadcast_addresses", network.public_addresses().into());
 
         Ok(ResponseData::new(serde_json::to_value(&info)?))
     }
 
+    /// Returns the disk usage of each column family of the database.
+    pub(crate) async fn db_stats(&self) -> anyhow::Result<ResponseData> {
+        let db = self.db().read().await.clone();
+        let stats = spawn_blocking(move || db.stats()).await??;
+
+        Ok(ResponseData::new(serde_json::to_value(stats)?))
+    }
+
+    /// Compacts the database, returning the disk usage before and after the
+    /// compaction.
+    pub(crate) async fn db_compact(&self) -> anyhow::Result<ResponseData> {
+        let db = self.db().read().await.clone();
+        let (before, after) = spawn_blocking(move || {
+            let before = db.stats()?;
+            db.compact();
+            anyhow::Ok((before, db.stats()?))
+        })
+        .await??;
+
+        Ok(ResponseData::new(json!({
+            "before": before,
+            "after": after,
+        })))
+    }
+
+    /// Streams the blocks from height `from` to height `to` included, in
+    /// order.
+    ///
+    /// The request data is `from` followed by an optional `to` (u64 LE each).
+    /// At most [`MAX_STREAMED_BLOCKS`] blocks are sent, the stream ending at
+    /// the tip or after that many blocks if `to` is omitted. Each block is
+    /// sent with its certificate and transactions, prefixed by its length
+    /// (u32 LE).
+    ///
+    /// Blocks are read from the database as the stream is consumed, with a
+    /// bounded read-ahead. The number of streams served at once is bounded,
+    /// further requests failing until one of them ends.
+    async fn stream_blocks(&self, data: &[u8]) -> anyhow::Result<ResponseData> {
+        self.stream_by_height(data, |db, height, buf| {
+            let block = db.view(|t| t.fetch_block_by_height(height))?;
+            match block {
+                Some(block) => {
+                    block.write(buf)?;
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        })
+        .await
+    }
+
+    /// Streams the receipts of the blocks from height `from` to height `to`
+    /// included, in order, as `Chain/blocks` streams the blocks.
+    ///
+    /// The receipts of a block are its spent transactions, in block order,
+    /// followed by the events emitted outside of them, each list prefixed by
+    /// its length (u32 LE). Meant to rebuild the ledger of another node,
+    /// which checks them against the event hash of the blocks.
+    async fn stream_receipts(
+        &self,
+        data: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        self.stream_by_height(data, |db, height, buf| {
+            db.view(|t| {
+                let Some(hash) = t.fetch_block_hash_by_height(height)? else {
+                    return Ok(false);
+                };
+                let Some((_, txs_id)) = t.fetch_block_header(&hash)? else {
+                    return Ok(false);
+                };
+
+                buf.extend((txs_id.len() as u32).to_le_bytes());
+                for tx_id in &txs_id {
+                    let tx =
+                        t.get_ledger_tx_by_hash(tx_id)?.ok_or_else(|| {
+                            anyhow::anyhow!("Cannot find transaction")
+                        })?;
+                    tx.write(buf)?;
+                }
+
+                let events = t.fetch_block_events(height)?;
+                buf.extend((events.len() as u32).to_le_bytes());
+                for event in &events {
+                    event.write(buf)?;
+                }
+                Ok(true)
+            })
+        })
+        .await
+    }
+
+    /// Streams the items of a range of heights, as serialized by `write`
+    /// into the given buffer. The stream ends at the first height `write`
+    /// returns `false` for.
+    ///
+    /// The range, read-ahead and concurrency are bounded as for
+    /// `Chain/blocks`.
+    async fn stream_by_height<F>(
+        &self,
+        data: &[u8],
+        write: F,
+    ) -> anyhow::Result<ResponseData>
+    where
+        F: Fn(&Backend, u64, &mut Vec<u8>) -> anyhow::Result<bool>
+            + Send
+            + 'static,
+    {
+        let (from, to) = stream_range(data)?;
+
+        let permit = budget::BLOCK_STREAMS
+            .try_acquire()
+            .ok_or(crate::Error::BudgetExhausted("block streams"))?;
+
+        let db = self.db().read().await.clone();
+        let (sender, receiver) = mpsc::sync_channel(BLOCKS_STREAM_BUFFER);
+
+        spawn_blocking(move || {
+            let _permit = permit;
+            for height in from..=to {
+                let mut buf = vec![0u8; 4];
+                match write(&db, height, &mut buf) {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(err) => {
+                        warn!("Cannot read height {height}: {err}");
+                        break;
+                    }
+                }
+                let len = (buf.len() - 4) as u32;
+                buf[..4].copy_from_slice(&len.to_le_bytes());
+
+                // Blocks until the consumer catches up, stopping once it is
+                // gone
+                if sender.send(buf).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(ResponseData::new(receiver))
+    }
+
+    /// Returns the finality of the block at `height`, the tip if omitted.
+    async fn get_finality(
+        &self,
+        height: Option<u64>,
+    ) -> anyhow::Result<ResponseData> {
+        let finality = self.db().read().await.view(|t| {
+            let height = match height {
+                Some(height) => height,
+                None => finality::tip_height(&t)?,
+            };
+            finality::fetch(&t, height)
+        })?;
+
+        let finality =
+            finality.ok_or_else(|| anyhow::anyhow!("Cannot find the block"))?;
+        Ok(ResponseData::new(finality_json(&finality)))
+    }
+
+    /// Streams the finality of the tip, sending it as JSON whenever a block
+    /// is accepted or finalized.
+    ///
+    /// Updates are dropped while the consumer lags behind, since each one
+    /// supersedes the previous.
+    async fn stream_finality(&self) -> anyhow::Result<ResponseData> {
+        let permit = budget::FINALITY_STREAMS
+            .try_acquire()
+            .ok_or(crate::Error::BudgetExhausted("finality streams"))?;
+
+        let db = self.db();
+        let (sender, receiver) = mpsc::sync_channel(BLOCKS_STREAM_BUFFER);
+
+        tokio::spawn(async move {
+            let _permit = permit;
+            let mut interval = tokio::time::interval(FINALITY_POLL_INTERVAL);
+            let mut last = None;
+            loop {
+                interval.tick().await;
+
+                let finality = db
+                    .read()
+                    .await
+                    .view(|t| finality::fetch(&t, finality::tip_height(&t)?));
+                let finality = match finality {
+                    Ok(Some(finality)) => finality,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        warn!("Cannot fetch the finality of the tip: {err}");
+                        continue;
+                    }
+                };
+
+                let current = (finality.hash, finality.last_final);
+                if last == Some(current) {
+                    continue;
+                }
+
+                let update = finality_json(&finality).to_string().into_bytes();
+                match sender.try_send(update) {
+                    Ok(()) => last = Some(current),
+                    Err(mpsc::TrySendError::Full(_)) => {}
+                    Err(mpsc::TrySendError::Disconnected(_)) => break,
+                }
+            }
+        });
+
+        Ok(ResponseData::new(receiver))
+    }
+
+    /// Returns the stale tips detected since the node started.
+    async fn get_stale_tip_alerts(&self) -> anyhow::Result<ResponseData> {
+        let alerts =
+            self.db().read().await.view(|t| t.op_read(MD_STALE_TIP))?;
+
+        let alerts: StaleTipAlerts = match alerts {
+            Some(bytes) => serde_json::from_slice(&bytes)?,
+            None => StaleTipAlerts::default(),
+        };
+
+        Ok(ResponseData::new(serde_json::to_value(alerts)?))
+    }
+
+    /// Returns the statistics of the votes received in the last `rounds`
+    /// rounds, most recent last.
+    pub(crate) async fn get_vote_stats(
+        &self,
+        rounds: usize,
+    ) -> anyhow::Result<ResponseData> {
+        let mut stats = self.db().read().await.view(|t| {
+            let mut stats = vec![];
+            for slot in 0..VOTE_STATS_ROUNDS {
+                if let Some(bytes) = t.op_read(&md_vote_stats_key(slot))? {
+                    stats.push(serde_json::from_slice::<VoteStats>(&bytes)?);
+                }
+            }
+            anyhow::Ok(stats)
+        })?;
+
+        stats.sort_by_key(|s| s.round);
+        stats.drain(..stats.len().saturating_sub(rounds));
+
+        Ok(ResponseData::new(serde_json::to_value(stats)?))
+    }
+
+    /// Returns the committee members that did not vote for at least
+    /// `min_streak` consecutive rounds they were expected to, the longest
+    /// streak first.
+    ///
+    /// The streaks are the ones last written by the consensus, every hundred
+    /// rounds, the round they are as of being returned along with them.
+    async fn get_absence_streaks(
+        &self,
+        min_streak: u32,
+    ) -> anyhow::Result<ResponseData> {
+        let streaks = self.db().read().await.view(|t| {
+            t.op_read(MD_ABSENCE_STREAKS)
+                .map(|bytes| bytes.unwrap_or_default())
+        })?;
+
+        let streaks: AbsenceStreaks = match streaks.is_empty() {
+            true => AbsenceStreaks::default(),
+            false => serde_json::from_slice(&streaks)?,
+        };
+        let absent: Vec<_> = streaks
+            .absent(min_streak)
+            .into_iter()
+            .map(|(key, absence)| {
+                json!({ "provisioner": key, "absence": absence })
+            })
+            .collect();
+
+        Ok(ResponseData::new(json!({
+            "round": streaks.round,
+            "absent": absent,
+        })))
+    }
+
     /// Calculates various statistics for gas prices of transactions in the
     /// mempool.
     ///
@@ -155,16 +758,7 @@ impl RuskNode {
         &self,
         max_transactions: usize,
     ) -> anyhow::Result<ResponseData> {
-        let gas_prices: Vec<u64> =
-            self.db()
-                .read()
-                .await
-                .view(|t| -> anyhow::Result<Vec<u64>> {
-                    Ok(t.get_txs_hashes_sorted_by_fee()?
-                        .take(max_transactions)
-                        .map(|(gas_price, _)| gas_price)
-                        .collect())
-                })?;
+        let gas_prices = self.mempool_gas_prices(max_transactions).await?;
 
         if gas_prices.is_empty() {
             let stats = serde_json::json!({ "average": 1, "max": 1, "median": 1, "min": 1 });
@@ -200,4 +794,501 @@ impl RuskNode {
 
         Ok(ResponseData::new(serde_json::to_value(stats)?))
     }
+
+    /// Returns the gas prices of at most `max_transactions` transactions of
+    /// the mempool, highest first.
+    async fn mempool_gas_prices(
+        &self,
+        max_transactions: usize,
+    ) -> anyhow::Result<Vec<u64>> {
+        self.db()
+            .read()
+            .await
+            .view(|t| -> anyhow::Result<Vec<u64>> {
+                Ok(t.get_txs_hashes_sorted_by_fee()?
+                    .take(max_transactions)
+                    .map(|(gas_price, _)| gas_price)
+                    .collect())
+            })
+    }
+
+    /// Returns everything a wallet needs to build the proof of a transaction
+    /// spending notes, in a single round trip.
+    ///
+    /// The request data is the positions (u64 LE) of the notes to spend, at
+    /// most `MAX_OPENINGS`. Wallets select their notes locally, possibly
+    /// mixing them with decoys, so that no view key is ever sent.
+    ///
+    /// The response is the rkyv serialization of the `ProofInputs`. The notes,
+    /// their openings and the anchor all come from the same state.
+    async fn get_proof_inputs(
+        &self,
+        data: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        if data.is_empty() || data.len() % 8 != 0 {
+            anyhow::bail!("Invalid Data length {}", data.len());
+        }
+
+        let positions: Vec<_> = data
+            .chunks_exact(8)
+            .map(|pos| u64::from_le_bytes(pos.try_into().expect("8 bytes")))
+            .collect();
+
+        let reader = self.0.vm_handler().read().await.reader();
+        let (state_root, anchor, notes) =
+            spawn_blocking(move || reader.spend_inputs(&positions)).await??;
+
+        let gas_price = PriceStats::from_prices(
+            self.mempool_gas_prices(PROOF_INPUTS_GAS_PRICES).await?,
+        )
+        .map_or(
+            GasPrices {
+                min: 1,
+                median: 1,
+                max: 1,
+            },
+            |stats| GasPrices {
+                min: stats.min,
+                median: stats.median,
+                max: stats.max,
+            },
+        );
+
+        let inputs = ProofInputs {
+            state_root,
+            anchor,
+            notes,
+            gas_price,
+        };
+        let bytes = rkyv::to_bytes::<_, 4096>(&inputs)
+            .map_err(|e| anyhow::anyhow!("Cannot serialize inputs {e}"))?;
+
+        Ok(ResponseData::new(bytes.to_vec()))
+    }
+
+    /// Returns the gas utilization of the last `last_n_blocks` blocks, most
+    /// recent last, along with the gas prices of the included transactions.
+    ///
+    /// Meant to let wallets suggest fees based on recent block usage.
+    async fn get_fee_stats(
+        &self,
+        last_n_blocks: usize,
+    ) -> anyhow::Result<ResponseData> {
+        let last_n_blocks = last_n_blocks.min(MAX_FEE_STATS_BLOCKS);
+
+        let (blocks, prices) = self.db().read().await.view(|t| {
+            let mut blocks = Vec::with_capacity(last_n_blocks);
+            let mut all_prices = vec![];
+
+            let mut hash = t.op_read(MD_HASH_KEY)?;
+            while blocks.len() < last_n_blocks {
+                let Some((header, txs_id)) = hash
+                    .map(|h| t.fetch_block_header(&h))
+                    .transpose()?
+                    .flatten()
+                else {
+                    break;
+                };
+
+                let mut gas_used = 0;
+                let mut prices = Vec::with_capacity(txs_id.len());
+                for tx_id in &txs_id {
+                    let tx =
+                        t.get_ledger_tx_by_hash(tx_id)?.ok_or_else(|| {
+                            anyhow::anyhow!("Cannot find transaction")
+                        })?;
+                    gas_used += tx.gas_spent;
+                    prices.push(tx.gas_price);
+                }
+                all_prices.extend_from_slice(&prices);
+
+                blocks.push(BlockFeeStats {
+                    height: header.height,
+                    gas_limit: header.gas_limit,
+                    gas_used,
+                    transactions: txs_id.len(),
+                    gas_price: PriceStats::from_prices(prices),
+                });
+
+                if header.height == 0 {
+                    break;
+                }
+                hash = Some(header.prev_block_hash.to_vec());
+            }
+
+            anyhow::Ok((blocks, all_prices))
+        })?;
+
+        let gas_used: u64 = blocks.iter().map(|b| b.gas_used).sum();
+        let gas_limit: u64 = blocks.iter().map(|b| b.gas_limit).sum();
+        let utilization = match gas_limit {
+            0 => 0f64,
+            limit => gas_used as f64 / limit as f64,
+        };
+        let blocks: Vec<_> = blocks.into_iter().rev().collect();
+
+        Ok(ResponseData::new(json!({
+            "blocks": blocks,
+            "gas_used": gas_used,
+            "gas_limit": gas_limit,
+            "utilization": utilization,
+            "gas_price": PriceStats::from_prices(prices),
+        })))
+    }
+
+    /// Returns the contracts whose calls spent the most gas in the blocks
+    /// from `from_height` up to `to_height`, top consumers first.
+    ///
+    /// The gas spent by a transaction is accounted to the contract it calls,
+    /// or to the transfer contract if it calls none. Meant to let operators
+    /// identify the contracts dominating the block space.
+    async fn get_gas_usage_by_contract(
+        &self,
+        data: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let request: GasUsageRequest = serde_json::from_slice(data)
+            .map_err(|e| anyhow::anyhow!("Invalid Data {e}"))?;
+
+        let from = request.from_height;
+        let to = request.to_height.unwrap_or(u64::MAX);
+        if to < from {
+            anyhow::bail!("Invalid range {from}..={to}");
+        }
+        let to = to.min(from.saturating_add(MAX_GAS_USAGE_BLOCKS - 1));
+        let limit = request.limit.unwrap_or(DEFAULT_GAS_USAGE_CONTRACTS);
+
+        let blocks = self
+            .db()
+            .read()
+            .await
+            .view(|t| t.fetch_gas_usage(from, to))?;
+
+        // Gas spent, transactions and blocks per contract
+        let mut usage = BTreeMap::<[u8; 32], (u64, u64, u64)>::new();
+        for contract_gas in blocks.iter().flat_map(|(_, usage)| usage) {
+            let entry = usage.entry(contract_gas.contract).or_default();
+            entry.0 += contract_gas.gas_spent;
+            entry.1 += contract_gas.transactions as u64;
+            entry.2 += 1;
+        }
+
+        let gas_spent: u64 = usage.values().map(|(gas, _, _)| gas).sum();
+        let mut contracts: Vec<_> = usage.into_iter().collect();
+        contracts.sort_by(|(_, (a, _, _)), (_, (b, _, _))| b.cmp(a));
+
+        let contracts: Vec<_> = contracts
+            .into_iter()
+            .take(limit)
+            .map(|(contract, (gas, transactions, in_blocks))| {
+                let share = match gas_spent {
+                    0 => 0f64,
+                    total => gas as f64 / total as f64,
+                };
+                json!({
+                    "contract": hex::encode(contract),
+                    "gas_spent": gas,
+                    "transactions": transactions,
+                    "blocks": in_blocks,
+                    "share": share,
+                })
+            })
+            .collect();
+
+        Ok(ResponseData::new(json!({
+            "from_height": blocks.first().map(|(h, _)| *h),
+            "to_height": blocks.last().map(|(h, _)| *h),
+            "blocks": blocks.len(),
+            "gas_spent": gas_spent,
+            "contracts": contracts,
+        })))
+    }
+
+    /// Returns the events emitted by a contract with a given topic, in
+    /// ledger order.
+    ///
+    /// At most `limit` events are returned. If more are available, the
+    /// response carries the `cursor` to request the next page with.
+    async fn get_events(&self, data: &[u8]) -> anyhow::Result<ResponseData> {
+        let request: EventsRequest = serde_json::from_slice(data)
+            .map_err(|e| anyhow::anyhow!("Invalid Data {e}"))?;
+
+        let contract: [u8; 32] = hex::decode(&request.contract)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid contract bytes"))?;
+        let limit = request
+            .limit
+            .unwrap_or(MAX_EVENTS_PAGE)
+            .min(MAX_EVENTS_PAGE);
+        let from = request.cursor.unwrap_or(EventPosition {
+            height: request.from,
+            ..Default::default()
+        });
+        let to = request.to.unwrap_or(u64::MAX);
+
+        // Fetch one more event to know whether there is a next page
+        let mut events = self.db().read().await.view(|t| {
+            t.fetch_events(&contract, &request.topic, from, to, limit + 1)
+        })?;
+
+        let cursor = match events.len() > limit {
+            true => events.pop().map(|e| e.position),
+            false => None,
+        };
+
+        let events: Vec<_> = events
+            .into_iter()
+            .map(|e| {
+                let decoded =
+                    decode_event(&contract, &request.topic, &e.event.data);
+                json!({
+                    "height": e.position.height,
+                    "tx_index": e.position.tx_index,
+                    "event_index": e.position.event_index,
+                    "tx_hash": hex::encode(e.tx_hash),
+                    "data": hex::encode(e.event.data),
+                    "schema": decoded.as_ref().map(|(schema, _)| schema),
+                    "decoded": decoded.map(|(_, value)| value),
+                })
+            })
+            .collect();
+
+        Ok(ResponseData::new(json!({
+            "events": events,
+            "cursor": cursor,
+        })))
+    }
+
+    /// Computes the event hash of the given events, in the given order.
+    ///
+    /// The request data is a JSON array of events, each with a hex encoded
+    /// `source` and `data`, and a `topic`. The result matches the
+    /// `event_hash` of a block emitting exactly those events.
+    fn compute_event_hash(&self, data: &[u8]) -> anyhow::Result<ResponseData> {
+        let entries: Vec<EventHashEntry> = serde_json::from_slice(data)
+            .map_err(|e| anyhow::anyhow!("Invalid Data {e}"))?;
+
+        let events = entries
+            .into_iter()
+            .map(|e| {
+                let source = hex::decode(&e.source)?
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Invalid source bytes"))?;
+                let data = hex::decode(&e.data)?;
+                anyhow::Ok(ContractEvent {
+                    source,
+                    topic: e.topic,
+                    data,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let event_hash = ledger::event_hash(&events);
+
+        Ok(ResponseData::new(json!({
+            "event_hash": hex::encode(event_hash),
+        })))
+    }
+
+    /// Reports the penalties the provisioners missed by a candidate block
+    /// would get if the block was accepted on top of the current tip.
+    ///
+    /// The request data is either the hash of a candidate block known to the
+    /// node, or a serialized block header. Nothing is changed in the state,
+    /// so that operators can monitor, and dispute, imminent slashes.
+    pub(crate) async fn slashing_dry_run(
+        &self,
+        data: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let header = match data.len() {
+            32 => self
+                .db()
+                .read()
+                .await
+                .view(|t| t.fetch_candidate_block(data))?
+                .ok_or_else(|| anyhow::anyhow!("Unknown candidate"))?
+                .header()
+                .clone(),
+            _ => ledger::Header::read(&mut &data[..])
+                .map_err(|e| anyhow::anyhow!("Invalid Data {e}"))?,
+        };
+
+        let generator = dusk_bls12_381_sign::PublicKey::from_slice(
+            &header.generator_bls_pubkey.0,
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid generator {e:?}"))?;
+        let missed_generators =
+            header.failed_iterations.to_missed_generators()?;
+
+        let vm = self.0.vm_handler().read().await.clone();
+        let outcomes = spawn_blocking(move || {
+            vm.simulate_slashing(header.height, &generator, &missed_generators)
+        })
+        .await??;
+
+        let slashes: Vec<_> = outcomes
+            .into_iter()
+            .map(|outcome| {
+                let (penalty, epochs) = match outcome.penalty {
+                    Penalty::Slash => ("slash", None),
+                    Penalty::Suspend(epochs) => ("suspend", Some(epochs)),
+                    Penalty::HardSlash => ("hard_slash", None),
+                };
+                json!({
+                    "provisioner": bs58::encode(outcome.provisioner.to_bytes())
+                        .into_string(),
+                    "faults": outcome.faults,
+                    "penalty": penalty,
+                    "suspension_epochs": epochs,
+                    "amount": outcome.amount,
+                })
+            })
+            .collect();
+
+        Ok(ResponseData::new(json!({
+            "height": header.height,
+            "slashes": slashes,
+        })))
+    }
+
+    /// Returns the seed of the block at the given height, along with its
+    /// generator and whether the block is final.
+    ///
+    /// The seed is the BLS signature of the generator over the seed of the
+    /// previous block, so anyone can verify it, and it drives the sortition
+    /// of the following round. Since the generator knows it before anyone
+    /// else, and picks the transactions of its block knowing it,
+    /// applications using it as randomness should only rely on the seed of a
+    /// final block following the one committing to the outcome.
+    async fn get_round_seed(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<ResponseData> {
+        let (header, label) = self.db().read().await.view(|t| {
+            let hash = t
+                .fetch_block_hash_by_height(height)?
+                .ok_or_else(|| anyhow::anyhow!("Unknown height {height}"))?;
+            let (header, _) = t
+                .fetch_block_header(&hash)?
+                .ok_or_else(|| anyhow::anyhow!("Unknown block {height}"))?;
+            let label = t.fetch_block_label_by_height(height)?;
+            anyhow::Ok((header, label))
+        })?;
+
+        Ok(ResponseData::new(json!({
+            "height": header.height,
+            "hash": hex::encode(header.hash),
+            "seed": hex::encode(header.seed.inner()),
+            "generator": bs58::encode(header.generator_bls_pubkey.inner())
+                .into_string(),
+            "final": matches!(label, Some(ledger::Label::Final)),
+        })))
+    }
+
+    /// Returns the iterations of the upcoming round in which the node's own
+    /// provisioner generates the candidate or votes, so that operators can
+    /// schedule maintenance windows around them.
+    pub(crate) async fn get_duty_schedule(
+        &self,
+    ) -> anyhow::Result<ResponseData> {
+        let schedule = self
+            .db()
+            .read()
+            .await
+            .view(|t| node::chain::schedule::fetch(&t))?
+            .ok_or_else(|| anyhow::anyhow!("No duty schedule computed yet"))?;
+
+        Ok(ResponseData::new(serde_json::to_value(schedule)?))
+    }
+
+    /// Returns the consensus performance of the node's own provisioner in
+    /// the last `rounds` rounds, along with the performance of each round.
+    pub(crate) async fn get_performance(
+        &self,
+        rounds: usize,
+    ) -> anyhow::Result<ResponseData> {
+        let performance = self
+            .db()
+            .read()
+            .await
+            .view(|t| node::chain::performance::fetch(&t))?
+            .ok_or_else(|| anyhow::anyhow!("No performance recorded yet"))?;
+
+        let report = performance.report(rounds);
+        let skip = performance.rounds.len().saturating_sub(rounds);
+        let rounds: Vec<_> = performance.rounds.iter().skip(skip).collect();
+
+        Ok(ResponseData::new(json!({
+            "summary": report,
+            "rounds": rounds,
+        })))
+    }
+
+    /// Returns the summary of the given epoch: the provisioners set at its
+    /// end, along with the rewards and slashes of its blocks.
+    async fn get_epoch_summary(
+        &self,
+        epoch: u64,
+    ) -> anyhow::Result<ResponseData> {
+        let summary = self
+            .db()
+            .read()
+            .await
+            .view(|t| node::chain::epoch::fetch(&t, epoch))?
+            .ok_or_else(|| anyhow::anyhow!("No summary of epoch {epoch}"))?;
+
+        Ok(ResponseData::new(json!({
+            "epoch": summary.epoch,
+            "height": summary.height,
+            "provisioners_hash": hex::encode(summary.provisioners_hash),
+            "provisioners": summary.provisioners,
+            "total_stake": summary.total_stake,
+            "rewards": summary.rewards,
+            "slashed": summary.slashed,
+            "total_slashed": summary.total_slashed,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_stats() {
+        assert!(PriceStats::from_prices(vec![]).is_none());
+
+        let stats = PriceStats::from_prices(vec![3, 1, 2]).unwrap();
+        assert_eq!((stats.min, stats.median, stats.max), (1, 2, 3));
+
+        let stats = PriceStats::from_prices(vec![4, 1, 2, 3]).unwrap();
+        assert_eq!((stats.min, stats.median, stats.max), (1, 2, 4));
+
+        let stats = PriceStats::from_prices(vec![u64::MAX, u64::MAX - 2])
+            .expect("prices to be given");
+        assert_eq!(stats.median, u64::MAX - 1);
+    }
+
+    #[test]
+    fn stream_ranges() {
+        let data = |from: u64, to: Option<u64>| {
+            let mut data = from.to_le_bytes().to_vec();
+            if let Some(to) = to {
+                data.extend(to.to_le_bytes());
+            }
+            data
+        };
+
+        assert_eq!(stream_range(&data(5, Some(5))).unwrap(), (5, 5));
+        let max = MAX_STREAMED_BLOCKS;
+        assert_eq!(stream_range(&data(1, Some(max))).unwrap(), (1, max));
+        assert!(stream_range(&data(0, Some(max))).is_err());
+        assert!(stream_range(&data(0, Some(u64::MAX))).is_err());
+        assert!(stream_range(&data(6, Some(5))).is_err());
+        assert!(stream_range(&[0; 4]).is_err());
+
+        // Without an end, the stream is capped
+        assert_eq!(stream_range(&data(0, None)).unwrap(), (0, max - 1));
+        let end = stream_range(&data(u64::MAX, None)).unwrap();
+        assert_eq!(end, (u64::MAX, u64::MAX));
+    }
 }
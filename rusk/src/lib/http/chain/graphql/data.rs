@@ -6,9 +6,12 @@
 
 use std::ops::Deref;
 
-use async_graphql::{FieldError, FieldResult, Object, SimpleObject};
+use async_graphql::{FieldError, FieldResult, Json, Object, SimpleObject};
+use node::chain::finality::{self, BlockFinality};
 use node::database::{Ledger, DB};
 
+use crate::chain::decode_event;
+
 pub struct Block {
     header: node_data::ledger::Header,
     txs_id: Vec<[u8; 32]>,
@@ -59,6 +62,17 @@ impl Deref for TransactionData<'_> {
     }
 }
 
+impl Block {
+    async fn block_finality(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> FieldResult<BlockFinality> {
+        let db = ctx.data::<super::DBContext>()?.read().await;
+        db.view(|t| finality::fetch(&t, self.header.height))?
+            .ok_or_else(|| FieldError::new("Cannot find block"))
+    }
+}
+
 #[Object]
 impl Block {
     #[graphql(name = "header")]
@@ -98,7 +112,7 @@ impl Block {
             .transactions(ctx)
             .await?
             .iter()
-            .map(|t| t.0.gas_spent * t.0.inner.gas_price())
+            .map(|t| t.0.gas_spent * t.0.gas_price)
             .sum();
         Ok(fees)
     }
@@ -115,6 +129,28 @@ impl Block {
             .sum();
         Ok(gas_spent)
     }
+
+    /// Finality of the block under the rolling finality rules, either
+    /// `accepted`, `attested` or `final`
+    pub async fn finality(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> FieldResult<String> {
+        Ok(self
+            .block_finality(ctx)
+            .await?
+            .finality
+            .as_str()
+            .to_string())
+    }
+
+    /// Number of blocks accepted on top of the block
+    pub async fn confirmations(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+    ) -> FieldResult<u64> {
+        Ok(self.block_finality(ctx).await?.confirmations)
+    }
 }
 
 #[Object]
@@ -179,6 +215,32 @@ impl SpentTransaction {
         self.0.gas_spent
     }
 
+    /// Price the gas spent was charged at, up to the gas price of the
+    /// transaction
+    pub async fn gas_price(&self) -> u64 {
+        self.0.gas_price
+    }
+
+    pub async fn events(&self) -> Vec<ContractEvent> {
+        self.0
+            .events
+            .iter()
+            .map(|event| {
+                let decoded =
+                    decode_event(&event.source, &event.topic, &event.data);
+                ContractEvent {
+                    source: hex::encode(event.source),
+                    topic: event.topic.clone(),
+                    data: hex::encode(&event.data),
+                    schema: decoded
+                        .as_ref()
+                        .map(|(schema, _)| schema.to_string()),
+                    decoded: decoded.map(|(_, value)| Json(value)),
+                }
+            })
+            .collect()
+    }
+
     pub async fn block_hash(
         &self,
         ctx: &async_graphql::Context<'_>,
@@ -270,3 +332,14 @@ pub struct CallData {
     fn_name: String,
     data: String,
 }
+
+#[derive(SimpleObject)]
+pub struct ContractEvent {
+    source: String,
+    topic: String,
+    data: String,
+    /// Identifier of the schema the data was decoded with
+    schema: Option<String>,
+    /// Data decoded into JSON, if its schema is known
+    decoded: Option<Json<serde_json::Value>>,
+}
@@ -4,15 +4,17 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use super::RUSK_VERSION_HEADER;
+use super::{GRPC_STATUS_HEADER, RUSK_ERROR_KIND_HEADER, RUSK_VERSION_HEADER};
 use futures_util::{stream, StreamExt};
 use hyper::header::{InvalidHeaderName, InvalidHeaderValue};
 use hyper::Body;
+use node_data::error::{ErrorKind, GRPC_UNKNOWN};
 use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 use serde_with::{self, serde_as};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
 use std::str::FromStr;
 use std::sync::mpsc;
 use tungstenite::http::HeaderValue;
@@ -37,6 +39,9 @@ impl Event {
 pub struct MessageRequest {
     pub headers: serde_json::Map<String, serde_json::Value>,
     pub event: Event,
+    /// Address of the client, unless connected over the unix socket
+    #[serde(skip)]
+    pub client: Option<IpAddr>,
 }
 
 impl MessageRequest {
@@ -119,7 +124,11 @@ impl MessageRequest {
     pub fn parse(bytes: &[u8]) -> anyhow::Result<Self> {
         let (headers, bytes) = parse_header(bytes)?;
         let event = Event::parse(bytes)?;
-        Ok(Self { event, headers })
+        Ok(Self {
+            event,
+            headers,
+            client: None,
+        })
     }
 
     pub async fn from_request(
@@ -142,7 +151,11 @@ impl MessageRequest {
             .collect();
         let (event, binary_response) = Event::from_request(req).await?;
 
-        let req = MessageRequest { event, headers };
+        let req = MessageRequest {
+            event,
+            headers,
+            client: None,
+        };
 
         Ok((req, binary_response))
     }
@@ -190,12 +203,29 @@ impl MessageResponse {
         is_binary: bool,
     ) -> anyhow::Result<hyper::Response<hyper::Body>> {
         if let Some(error) = &self.error {
+            let kind = self
+                .headers
+                .get(RUSK_ERROR_KIND_HEADER)
+                .cloned()
+                .and_then(|kind| serde_json::from_value(kind).ok());
+            let status = match kind {
+                Some(ErrorKind::Transient) => {
+                    hyper::StatusCode::SERVICE_UNAVAILABLE
+                }
+                Some(ErrorKind::Permanent) => hyper::StatusCode::BAD_REQUEST,
+                Some(ErrorKind::ConsensusCritical) | None => {
+                    hyper::StatusCode::INTERNAL_SERVER_ERROR
+                }
+            };
+            let grpc_code = kind.map_or(GRPC_UNKNOWN, |k| k.grpc_code());
             return Ok(hyper::Response::builder()
-                .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                .status(status)
+                .header(GRPC_STATUS_HEADER, grpc_code.to_string())
                 .body(hyper::Body::from(error.to_string()))?);
         }
 
         let mut headers = HashMap::new();
+        let not_modified = matches!(self.data, DataType::NotModified);
 
         let body = {
             match self.data {
@@ -221,10 +251,13 @@ impl MessageResponse {
                         }
                     }))
                 }
-                DataType::None => Body::empty(),
+                DataType::NotModified | DataType::None => Body::empty(),
             }
         };
         let mut response = hyper::Response::new(body);
+        if not_modified {
+            *response.status_mut() = hyper::StatusCode::NOT_MODIFIED;
+        }
         for (k, v) in headers {
             response.headers_mut().insert(k, v);
         }
@@ -320,6 +353,9 @@ pub enum DataType {
     Json(serde_json::Value),
     #[serde(skip)]
     Channel(mpsc::Receiver<Vec<u8>>),
+    /// The requested data is unchanged since the client last fetched it
+    #[serde(skip)]
+    NotModified,
     #[default]
     None,
 }
@@ -7,6 +7,7 @@
 use rusk_prover::{LocalProver, Prover};
 
 use super::*;
+use crate::budget::PROOF_GENERATION;
 
 #[async_trait]
 impl HandleRequest for LocalProver {
@@ -14,15 +15,21 @@ impl HandleRequest for LocalProver {
         &self,
         request: &MessageRequest,
     ) -> anyhow::Result<ResponseData> {
-        let topic = request.event.topic.as_str();
-        let response = match topic {
-            "prove_execute" => self.prove_execute(request.event_data())?,
-            "prove_stct" => self.prove_stct(request.event_data())?,
-            "prove_stco" => self.prove_stco(request.event_data())?,
-            "prove_wfct" => self.prove_wfct(request.event_data())?,
-            "prove_wfco" => self.prove_wfco(request.event_data())?,
+        let prove = match request.event.topic.as_str() {
+            "prove_execute" => LocalProver::prove_execute,
+            "prove_stct" => LocalProver::prove_stct,
+            "prove_stco" => LocalProver::prove_stco,
+            "prove_wfct" => LocalProver::prove_wfct,
+            "prove_wfco" => LocalProver::prove_wfco,
             _ => anyhow::bail!("Unsupported"),
         };
+
+        // Proving is CPU bound, so it is kept off the async runtime
+        let data = request.event_data().to_vec();
+        let prover = *self;
+        let response = PROOF_GENERATION
+            .spawn_blocking(move || prove(&prover, &data))
+            .await?;
         Ok(ResponseData::new(response))
     }
 }
@@ -0,0 +1,155 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Correlation ids of the requests.
+//!
+//! Each request is given an id, the one provided by the client so that a
+//! request can be traced across services, or a random one. It is the id of
+//! the span the request is handled in, entered as well by the threads running
+//! its queries, and is returned along with the response.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use hyper::http::HeaderValue;
+use hyper::{Request, Response};
+use tower::{Layer, Service};
+use tracing::{info_span, Instrument};
+
+/// Header correlating a request with its response and the logs it produced
+pub(crate) const RUSK_REQUEST_ID_HEADER: &str = "Rusk-Request-Id";
+/// Maximum length of a request id provided by the client
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Returns the id provided by the client, if valid, or a random one.
+pub(crate) fn request_id(provided: Option<&str>) -> String {
+    provided
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')
+                })
+        })
+        .map(ToString::to_string)
+        .unwrap_or_else(|| hex::encode(rand::random::<[u8; 16]>()))
+}
+
+/// Middleware giving each HTTP request its correlation id.
+///
+/// The id is set in the headers of the request, for the handlers to find it,
+/// and of the response. The request is handled within a span carrying it.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestId<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestId { inner }
+    }
+}
+
+/// Service set up by [`RequestIdLayer`]
+#[derive(Debug, Clone)]
+pub(crate) struct RequestId<S> {
+    inner: S,
+}
+
+impl<S, B, R> Service<Request<B>> for RequestId<S>
+where
+    S: Service<Request<B>, Response = Response<R>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<
+        Box<
+            dyn Future<Output = Result<Self::Response, Self::Error>>
+                + Send
+                + 'static,
+        >,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        let provided = req
+            .headers()
+            .get(RUSK_REQUEST_ID_HEADER)
+            .and_then(|id| id.to_str().ok());
+        let id = request_id(provided);
+        let value =
+            HeaderValue::from_str(&id).expect("request id to be a header");
+        req.headers_mut()
+            .insert(RUSK_REQUEST_ID_HEADER, value.clone());
+
+        let span = info_span!("request", request_id = %id);
+        let response = self.inner.call(req).instrument(span);
+
+        Box::pin(async move {
+            let mut response = response.await?;
+            response.headers_mut().insert(RUSK_REQUEST_ID_HEADER, value);
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::Infallible;
+
+    use hyper::service::service_fn;
+    use hyper::Body;
+
+    #[test]
+    fn request_ids() {
+        assert_eq!(request_id(Some("wallet-42")), "wallet-42");
+
+        // Missing or invalid ids are replaced by random ones
+        let generated = request_id(None);
+        assert_eq!(generated.len(), 32);
+        assert_ne!(generated, request_id(None));
+        assert_eq!(request_id(Some("a\nb")).len(), 32);
+        assert_eq!(request_id(Some("")).len(), 32);
+        assert_eq!(request_id(Some(&"a".repeat(65))).len(), 32);
+    }
+
+    #[tokio::test]
+    async fn request_id_layer() {
+        let mut service =
+            RequestIdLayer.layer(service_fn(|req: Request<Body>| async move {
+                let id = req.headers()[RUSK_REQUEST_ID_HEADER].clone();
+                Ok::<_, Infallible>(Response::new(Body::from(
+                    id.as_bytes().to_vec(),
+                )))
+            }));
+
+        let req = Request::builder()
+            .header(RUSK_REQUEST_ID_HEADER, "wallet-42")
+            .body(Body::empty())
+            .unwrap();
+        let rsp = service.call(req).await.unwrap();
+        assert_eq!(rsp.headers()[RUSK_REQUEST_ID_HEADER], "wallet-42");
+        let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"wallet-42");
+
+        // The generated id is given to the handlers and returned
+        let rsp = service.call(Request::new(Body::empty())).await.unwrap();
+        let id = rsp.headers()[RUSK_REQUEST_ID_HEADER].clone();
+        assert_eq!(id.len(), 32);
+        let body = hyper::body::to_bytes(rsp.into_body()).await.unwrap();
+        assert_eq!(&body[..], id.as_bytes());
+    }
+}
@@ -7,22 +7,26 @@
 use super::event::Event;
 use super::*;
 
-use dusk_bytes::Serializable;
-use node::vm::VMExecution;
+use dusk_bls12_381_sign::PublicKey as BlsPublicKey;
+use dusk_bytes::{DeserializableSlice, Serializable};
+use dusk_pki::ViewKey;
 use rusk_profile::CRS_17_HASH;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 use std::sync::{mpsc, Arc};
-use std::thread;
-use tokio::task;
 
-use rusk_abi::ContractId;
+use rusk_abi::{ContractId, TRANSFER_CONTRACT};
 
-use crate::chain::Rusk;
+use crate::chain::{RuskReader, DEFAULT_QUERY_TIMEOUT};
 
 const RUSK_FEEDER_HEADER: &str = "Rusk-Feeder";
+/// Hex encoded state root a query is pinned to. If the state is not available
+/// anymore, the query fails with [`crate::Error::CommitNotFound`].
+const RUSK_STATE_ROOT_HEADER: &str = "Rusk-State-Root";
+const RUSK_PROVISIONERS_COUNT_HEADER: &str = "Rusk-Provisioners-Count";
 
 #[async_trait]
-impl HandleRequest for Rusk {
+impl HandleRequest for RuskReader {
     async fn handle(
         &self,
         request: &MessageRequest,
@@ -30,25 +34,71 @@ impl HandleRequest for Rusk {
         match &request.event.to_route() {
             (Target::Contract(_), ..) => {
                 let feeder = request.header(RUSK_FEEDER_HEADER).is_some();
-                self.handle_contract_query(&request.event, feeder)
+                let state_root = state_root(request)?;
+                self.handle_contract_query(
+                    &request.event,
+                    request.client,
+                    feeder,
+                    state_root,
+                )
+                .await
             }
             (Target::Host(_), "rusk", "preverify") => {
-                self.handle_preverify(request.event_data())
+                self.handle_preverify(request.event_data()).await
             }
             (Target::Host(_), "rusk", "provisioners") => {
-                self.get_provisioners()
+                self.get_provisioners(
+                    request.event_data(),
+                    state_root(request)?,
+                )
+                .await
             }
             (Target::Host(_), "rusk", "crs") => self.get_crs(),
+            (Target::Host(_), "rusk", "disk_status") => Ok(ResponseData::new(
+                serde_json::to_value(crate::disk::status())?,
+            )),
+            (Target::Host(_), "rusk", "host_gas") => Ok(ResponseData::new(
+                serde_json::to_value(crate::chain::host_gas_stats())?,
+            )),
+            (Target::Host(_), "rusk", "select_notes") => {
+                self.handle_select_notes(
+                    request.event_data(),
+                    state_root(request)?,
+                )
+                .await
+            }
+            (Target::Host(_), "rusk", "openings") => {
+                self.handle_openings(request.event_data(), state_root(request)?)
+                    .await
+            }
+            (Target::Host(_), "rusk", "decoy_openings") => {
+                self.handle_decoy_openings(request.event_data()).await
+            }
+            (Target::Host(_), "rusk", "stake_opening") => {
+                self.handle_stake_opening(
+                    request.event_data(),
+                    state_root(request)?,
+                )
+                .await
+            }
+            (Target::Host(_), "rusk", "contract_balance") => {
+                self.handle_contract_balance(request.event_data()).await
+            }
+            (Target::Host(_), "rusk", "contract_version") => {
+                self.handle_contract_version(request.event_data()).await
+            }
             _ => Err(anyhow::anyhow!("Unsupported")),
         }
     }
 }
 
-impl Rusk {
-    fn handle_contract_query(
+impl RuskReader {
+    async fn handle_contract_query(
         &self,
         event: &Event,
+        client: Option<IpAddr>,
         feeder: bool,
+        state_root: Option<[u8; 32]>,
     ) -> anyhow::Result<ResponseData> {
         let contract = event.target.inner();
         let contract_bytes = hex::decode(contract)?;
@@ -58,44 +108,265 @@ impl Rusk {
             .map_err(|_| anyhow::anyhow!("Invalid contract bytes"))?;
 
         if feeder {
+            let reservation = self.reserve_query_gas(client)?;
             let (sender, receiver) = mpsc::channel();
 
-            let rusk = self.clone();
             let topic = event.topic.clone();
             let arg = event.data.as_bytes().to_vec();
 
-            thread::spawn(move || {
-                rusk.feeder_query_raw(
-                    ContractId::from_bytes(contract_bytes),
+            let contract = ContractId::from_bytes(contract_bytes);
+            self.spawn_feeder_query(move |rusk| {
+                // Leaves are served from the note index, complete even once
+                // the commits they were added in are deleted
+                if contract == TRANSFER_CONTRACT
+                    && topic == "leaves_from_height"
+                {
+                    if let Ok(height) = rkyv::from_bytes::<u64>(&arg) {
+                        let _ = rusk.feed_leaves(height, sender, state_root);
+                        if let Some(reservation) = reservation {
+                            reservation.settle(0);
+                        }
+                        return;
+                    }
+                }
+                let _ = rusk.feeder_query_raw(
+                    contract,
                     topic,
                     arg,
                     sender,
+                    state_root,
+                    reservation,
                 );
             });
             Ok(ResponseData::new(receiver))
         } else {
+            let contract = ContractId::from_bytes(contract_bytes);
+            let topic = event.topic.clone();
+            let arg = event.data.as_bytes().to_vec();
             let data = self
-                .query_raw(
-                    ContractId::from_bytes(contract_bytes),
-                    event.topic.clone(),
-                    event.data.as_bytes(),
-                )
-                .map_err(|e| anyhow::anyhow!("{e}"))?;
+                .spawn_query(DEFAULT_QUERY_TIMEOUT, move |reader| {
+                    reader.query_raw_metered(
+                        client, state_root, contract, topic, arg,
+                    )
+                })
+                .await?;
             Ok(ResponseData::new(data))
         }
     }
 
-    fn handle_preverify(&self, data: &[u8]) -> anyhow::Result<ResponseData> {
+    async fn handle_preverify(
+        &self,
+        data: &[u8],
+    ) -> anyhow::Result<ResponseData> {
         let tx = phoenix_core::Transaction::from_slice(data)
             .map_err(|e| anyhow::anyhow!("Invalid Data {e:?}"))?;
-        self.preverify(&tx.into())?;
+        self.spawn_query(DEFAULT_QUERY_TIMEOUT, move |reader| {
+            reader.preverify(&tx.into())
+        })
+        .await?;
         Ok(ResponseData::new(DataType::None))
     }
 
-    fn get_provisioners(&self) -> anyhow::Result<ResponseData> {
-        let prov: Vec<_> = self
-            .provisioners(None)
-            .expect("Cannot query state for provisioners")
+    /// Selects the notes to spend for a given amount.
+    ///
+    /// The request data is the view key, followed by the amount (u64 LE), the
+    /// maximum number of inputs (u32 LE) and optionally the positions (u64
+    /// LE) of the notes known to be spent.
+    ///
+    /// The response is the rkyv serialization of the selected notes with
+    /// their openings. The notes of a view key are scanned incrementally: the
+    /// request fails until they all are, and is to be retried meanwhile.
+    async fn handle_select_notes(
+        &self,
+        data: &[u8],
+        state_root: Option<[u8; 32]>,
+    ) -> anyhow::Result<ResponseData> {
+        const VK_SIZE: usize = ViewKey::SIZE;
+
+        if data.len() < VK_SIZE + 12 || (data.len() - VK_SIZE - 12) % 8 != 0 {
+            anyhow::bail!("Invalid Data length {}", data.len());
+        }
+
+        let vk = ViewKey::from_slice(&data[..VK_SIZE])
+            .map_err(|e| anyhow::anyhow!("Invalid Data {e:?}"))?;
+        let target = u64::from_le_bytes(data[VK_SIZE..VK_SIZE + 8].try_into()?);
+        let max_inputs =
+            u32::from_le_bytes(data[VK_SIZE + 8..VK_SIZE + 12].try_into()?);
+        let exclude: Vec<_> = data[VK_SIZE + 12..]
+            .chunks_exact(8)
+            .map(|pos| u64::from_le_bytes(pos.try_into().expect("8 bytes")))
+            .collect();
+
+        // The openings of the notes are computed within the query budget,
+        // so the selection cannot itself run within it
+        let reader = self.clone();
+        let notes = spawn_blocking(move || {
+            reader.select_notes(
+                &vk,
+                target,
+                max_inputs as usize,
+                &exclude,
+                state_root,
+            )
+        })
+        .await??;
+        let bytes = rkyv::to_bytes::<_, 4096>(&notes)
+            .map_err(|e| anyhow::anyhow!("Cannot serialize notes {e}"))?;
+
+        Ok(ResponseData::new(bytes.to_vec()))
+    }
+
+    /// Returns the openings of the notes at the given positions.
+    ///
+    /// The request data is the positions (u64 LE) of the notes, at most
+    /// [`crate::chain::MAX_OPENINGS`].
+    ///
+    /// The response is the rkyv serialization of the root of the transfer
+    /// tree, followed by the opening of each note, if any, in request order.
+    async fn handle_openings(
+        &self,
+        data: &[u8],
+        state_root: Option<[u8; 32]>,
+    ) -> anyhow::Result<ResponseData> {
+        if data.is_empty() || data.len() % 8 != 0 {
+            anyhow::bail!("Invalid Data length {}", data.len());
+        }
+
+        let positions: Vec<_> = data
+            .chunks_exact(8)
+            .map(|pos| u64::from_le_bytes(pos.try_into().expect("8 bytes")))
+            .collect();
+
+        // The openings are computed within the query budget by workers
+        let reader = self.clone();
+        let openings =
+            spawn_blocking(move || reader.openings(&positions, state_root))
+                .await??;
+        let bytes = rkyv::to_bytes::<_, 4096>(&openings)
+            .map_err(|e| anyhow::anyhow!("Cannot serialize openings {e}"))?;
+
+        Ok(ResponseData::new(bytes.to_vec()))
+    }
+
+    /// Returns notes sampled uniformly in the transfer tree, with their
+    /// openings, to be used as decoys.
+    ///
+    /// The request data is the number of decoys (u32 LE), at most
+    /// [`crate::chain::MAX_DECOYS`]. The wallet filters its own notes out of
+    /// the decoys, so that the node never learns them.
+    ///
+    /// The response is the rkyv serialization of the root of the transfer
+    /// tree, followed by the sampled notes with their openings, in position
+    /// order.
+    async fn handle_decoy_openings(
+        &self,
+        data: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let count = u32::from_le_bytes(data.try_into().map_err(|_| {
+            anyhow::anyhow!("Invalid Data length {}", data.len())
+        })?);
+
+        // The openings of the decoys are computed within the query budget
+        let reader = self.clone();
+        let decoys =
+            spawn_blocking(move || reader.decoy_openings(count as usize))
+                .await??;
+        let bytes = rkyv::to_bytes::<_, 4096>(&decoys)
+            .map_err(|e| anyhow::anyhow!("Cannot serialize decoys {e}"))?;
+
+        Ok(ResponseData::new(bytes.to_vec()))
+    }
+
+    /// Returns the stake of a provisioner with its opening in the tree
+    /// committing to the stakes.
+    ///
+    /// The request data is the compressed public key of the provisioner.
+    ///
+    /// The response is the rkyv serialization of the root of the stake tree,
+    /// followed by the stake and its opening, if any.
+    async fn handle_stake_opening(
+        &self,
+        data: &[u8],
+        state_root: Option<[u8; 32]>,
+    ) -> anyhow::Result<ResponseData> {
+        let pk = BlsPublicKey::from_slice(data)
+            .map_err(|e| anyhow::anyhow!("Invalid Data {e:?}"))?;
+
+        let opening = self
+            .spawn_query(DEFAULT_QUERY_TIMEOUT, move |reader| {
+                reader.stake_opening(&pk, state_root)
+            })
+            .await?;
+        let bytes = rkyv::to_bytes::<_, 4096>(&opening)
+            .map_err(|e| anyhow::anyhow!("Cannot serialize opening {e}"))?;
+
+        Ok(ResponseData::new(bytes.to_vec()))
+    }
+
+    /// Returns the transparent balance of the contract whose ID is the
+    /// request data.
+    async fn handle_contract_balance(
+        &self,
+        data: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let contract_id: [u8; 32] = data
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid contract bytes"))?;
+
+        let balance: u64 = self
+            .query_async(
+                TRANSFER_CONTRACT,
+                "module_balance",
+                ContractId::from_bytes(contract_id),
+                DEFAULT_QUERY_TIMEOUT,
+            )
+            .await?;
+        Ok(ResponseData::new(serde_json::to_value(balance)?))
+    }
+
+    async fn handle_contract_version(
+        &self,
+        data: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let contract_id: [u8; 32] = data
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid contract bytes"))?;
+
+        let version: u64 = self
+            .query_async(
+                TRANSFER_CONTRACT,
+                "contract_version",
+                ContractId::from_bytes(contract_id),
+                DEFAULT_QUERY_TIMEOUT,
+            )
+            .await?;
+        Ok(ResponseData::new(serde_json::to_value(version)?))
+    }
+
+    /// Returns the provisioners, all of them unless a [`ProvisionersPage`] is
+    /// requested. The total number of provisioners is returned in the
+    /// [`RUSK_PROVISIONERS_COUNT_HEADER`].
+    async fn get_provisioners(
+        &self,
+        data: &[u8],
+        state_root: Option<[u8; 32]>,
+    ) -> anyhow::Result<ResponseData> {
+        let page: ProvisionersPage = match data.is_empty() {
+            true => ProvisionersPage::default(),
+            false => serde_json::from_slice(data)?,
+        };
+
+        let (total, prov) = self
+            .spawn_query(DEFAULT_QUERY_TIMEOUT, move |reader| {
+                reader.provisioners_page(
+                    state_root,
+                    page.offset,
+                    page.limit.unwrap_or(usize::MAX),
+                )
+            })
+            .await?;
+        let prov: Vec<_> = prov
+            .into_iter()
             .map(|(key, stake)| {
                 let key = bs58::encode(key.to_bytes()).into_string();
                 let (amount, eligibility) = stake.amount.unwrap_or_default();
@@ -108,7 +379,8 @@ impl Rusk {
             })
             .collect();
 
-        Ok(ResponseData::new(serde_json::to_value(prov)?))
+        Ok(ResponseData::new(serde_json::to_value(prov)?)
+            .with_header(RUSK_PROVISIONERS_COUNT_HEADER, total))
     }
 
     fn get_crs(&self) -> anyhow::Result<ResponseData> {
@@ -117,6 +389,28 @@ impl Rusk {
     }
 }
 
+/// Returns the state root the request is pinned to, if any.
+fn state_root(request: &MessageRequest) -> anyhow::Result<Option<[u8; 32]>> {
+    request
+        .header(RUSK_STATE_ROOT_HEADER)
+        .map(|root| {
+            let root = root
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Invalid state root"))?;
+            hex::decode(root)?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Invalid state root length"))
+        })
+        .transpose()
+}
+
+#[derive(Default, Deserialize)]
+struct ProvisionersPage {
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
 #[derive(Serialize)]
 struct Provisioner {
     key: String,
@@ -17,6 +17,8 @@ use hyper::body::HttpBody;
 use rustls_pemfile::{certs, pkcs8_private_keys};
 use tokio::io::{self, AsyncRead, AsyncWrite, ReadBuf};
 use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
 use tokio_rustls::rustls::internal::msgs::codec::Codec;
 use tokio_rustls::rustls::pki_types::{
     CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer,
@@ -27,14 +29,50 @@ use tokio_rustls::TlsAcceptor;
 
 pub struct Listener {
     acceptor: Option<TlsAcceptor>,
-    inner: TcpListener,
+    inner: Inner,
+}
+
+enum Inner {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
 }
 
 impl Listener {
     pub async fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
         Ok(Self {
             acceptor: None,
-            inner: TcpListener::bind(addr).await?,
+            inner: Inner::Tcp(TcpListener::bind(addr).await?),
+        })
+    }
+
+    /// Binds to a unix socket at `path`, replacing any stale socket left
+    /// there.
+    ///
+    /// The socket is only accessible to the owner and the group of the
+    /// process, so that access can be granted through filesystem
+    /// permissions.
+    #[cfg(unix)]
+    pub fn bind_unix<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+        let path = path.as_ref();
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ));
+            }
+            std::fs::remove_file(path)?;
+        }
+
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o660))?;
+
+        Ok(Self {
+            acceptor: None,
+            inner: Inner::Unix(listener),
         })
     }
 
@@ -74,12 +112,21 @@ impl Listener {
 
         Ok(Self {
             acceptor: Some(TlsAcceptor::from(Arc::new(config))),
-            inner: TcpListener::bind(addr).await?,
+            inner: Inner::Tcp(TcpListener::bind(addr).await?),
         })
     }
 
-    pub async fn accept(&self) -> io::Result<Stream> {
-        let (stream, _) = self.inner.accept().await?;
+    /// Accepts a connection, returning the address of the client unless
+    /// connected over the unix socket.
+    pub async fn accept(&self) -> io::Result<(Stream, Option<SocketAddr>)> {
+        let (stream, addr) = match &self.inner {
+            Inner::Tcp(listener) => listener.accept().await?,
+            #[cfg(unix)]
+            Inner::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                return Ok((Stream::Unix(stream), None));
+            }
+        };
 
         let stream = match &self.acceptor {
             None => Stream::Raw(stream),
@@ -89,17 +136,25 @@ impl Listener {
             }
         };
 
-        Ok(stream)
+        Ok((stream, Some(addr)))
     }
 
-    pub fn local_addr(&self) -> io::Result<SocketAddr> {
-        self.inner.local_addr()
+    /// Returns the address the listener is bound to, or `None` for a unix
+    /// socket.
+    pub fn local_addr(&self) -> io::Result<Option<SocketAddr>> {
+        match &self.inner {
+            Inner::Tcp(listener) => listener.local_addr().map(Some),
+            #[cfg(unix)]
+            Inner::Unix(_) => Ok(None),
+        }
     }
 }
 
 pub enum Stream {
     Raw(TcpStream),
     Tls(TlsStream<TcpStream>),
+    #[cfg(unix)]
+    Unix(UnixStream),
 }
 
 impl AsyncRead for Stream {
@@ -111,6 +166,8 @@ impl AsyncRead for Stream {
         match &mut *self {
             Stream::Raw(stream) => Pin::new(stream).poll_read(cx, buf),
             Stream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
         }
     }
 }
@@ -124,6 +181,8 @@ impl AsyncWrite for Stream {
         match &mut *self {
             Stream::Raw(stream) => Pin::new(stream).poll_write(cx, buf),
             Stream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
         }
     }
 
@@ -134,6 +193,8 @@ impl AsyncWrite for Stream {
         match &mut *self {
             Stream::Raw(stream) => Pin::new(stream).poll_flush(cx),
             Stream::Tls(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(unix)]
+            Stream::Unix(stream) => Pin::new(stream).poll_flush(cx),
         }
     }
 
@@ -144,6 +205,8 @@ impl AsyncWrite for Stream {
         match &mut *self {
             Stream::Raw(stream) => Pin::new(stream).poll_shutdown(cx),
             Stream::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(unix)]
+            Stream::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
         }
     }
 }
@@ -0,0 +1,155 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::event::DataType;
+use super::*;
+use crate::verifier::{self, VerifierKey};
+
+/// Version of the verifier keys a response belongs to. Cached keys are to be
+/// fetched again when it changes.
+const VERIFIER_KEYS_VERSION_HEADER: &str = "Rusk-Verifier-Keys-Version";
+
+/// Serves the verifier keys of the circuits used by the node, so that light
+/// verification tools do not need to bundle them.
+pub struct VerifierKeys;
+
+#[async_trait]
+impl HandleRequest for VerifierKeys {
+    async fn handle(
+        &self,
+        request: &MessageRequest,
+    ) -> anyhow::Result<ResponseData> {
+        // Keys are read from the disk by the first request
+        let (version, keys) = spawn_blocking(verifier::verifier_keys).await?;
+
+        let response = respond(request, keys)?;
+
+        Ok(response
+            .with_header(VERIFIER_KEYS_VERSION_HEADER, version.as_str())
+            .with_header("cache-control", "no-cache"))
+    }
+}
+
+fn respond(
+    request: &MessageRequest,
+    keys: &[VerifierKey],
+) -> anyhow::Result<ResponseData> {
+    let data = request.event.data.as_string();
+    let data = data.trim();
+
+    let response = match request.event.topic.as_str() {
+        "keys" => ResponseData::new(serde_json::to_value(keys)?),
+        // The key is looked up either by circuit name or by hash
+        "key" => {
+            let key = keys
+                .iter()
+                .find(|k| k.circuit == data || k.hash == data)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Unknown verifier key {data}")
+                })?;
+
+            let etag = format!("\"{}\"", key.hash);
+            let cached = request
+                .header("if-none-match")
+                .is_some_and(|tags| etag_matches(tags, &key.hash));
+            let data = match cached {
+                true => DataType::NotModified,
+                false => key.data.clone().into(),
+            };
+
+            ResponseData::new(data)
+                .with_header("etag", etag)
+                .with_header("circuit-id", key.circuit_id.as_str())
+        }
+        _ => anyhow::bail!("Unsupported"),
+    };
+
+    Ok(response)
+}
+
+/// Returns whether the entity tags of an `If-None-Match` header match the
+/// given one.
+fn etag_matches(tags: &serde_json::Value, etag: &str) -> bool {
+    // Quoted tags are parsed as JSON strings
+    let tags = match tags {
+        serde_json::Value::String(tags) => tags.clone(),
+        tags => tags.to_string(),
+    };
+
+    tags.split(',').any(|tag| {
+        let tag = tag.trim();
+        let tag = tag.strip_prefix("W/").unwrap_or(tag);
+        tag == "*" || tag.trim_matches('"') == etag
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::event::{Event, RequestData, Target};
+
+    fn request(topic: &str, data: &str, headers: &str) -> MessageRequest {
+        MessageRequest {
+            headers: serde_json::from_str(headers).unwrap(),
+            event: Event {
+                target: Target::None,
+                data: RequestData::Text(data.into()),
+                topic: topic.into(),
+            },
+            client: None,
+        }
+    }
+
+    fn keys() -> Vec<VerifierKey> {
+        vec![VerifierKey {
+            circuit: "LicenseCircuit".into(),
+            circuit_id: "c1".into(),
+            hash: "ab12".into(),
+            data: vec![1, 2, 3],
+        }]
+    }
+
+    #[test]
+    fn key_is_served_by_name_or_hash() {
+        for data in ["LicenseCircuit", "ab12"] {
+            let response = respond(&request("key", data, "{}"), &keys())
+                .expect("the key to be served");
+            let (data, headers) = response.into_inner();
+            assert!(
+                matches!(data, DataType::Binary(w) if w.inner == [1, 2, 3])
+            );
+            assert_eq!(headers["etag"], "\"ab12\"");
+            assert_eq!(headers["circuit-id"], "c1");
+        }
+
+        respond(&request("key", "unknown", "{}"), &keys())
+            .expect_err("an unknown key to be rejected");
+    }
+
+    #[test]
+    fn cached_key_is_not_modified() {
+        for tag in [
+            r#""\"ab12\"""#,
+            r#""W/\"ab12\"""#,
+            r#""\"ff\", \"ab12\"""#,
+            r#""*""#,
+        ] {
+            let headers = format!(r#"{{"if-none-match": {tag}}}"#);
+            let response =
+                respond(&request("key", "LicenseCircuit", &headers), &keys())
+                    .expect("the key to be served");
+            let (data, headers) = response.into_inner();
+            assert!(matches!(data, DataType::NotModified), "{tag}");
+            assert_eq!(headers["etag"], "\"ab12\"");
+        }
+
+        let headers = r#"{"if-none-match": "\"ff\""}"#;
+        let response =
+            respond(&request("key", "LicenseCircuit", headers), &keys())
+                .expect("the key to be served");
+        assert!(matches!(response.into_inner().0, DataType::Binary(_)));
+    }
+}
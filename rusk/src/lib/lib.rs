@@ -6,10 +6,16 @@
 
 #![feature(lazy_cell)]
 
+pub mod audit;
+pub mod budget;
 #[cfg(feature = "node")]
 pub mod chain;
+#[cfg(feature = "node")]
+pub mod disk;
 mod error;
 pub mod http;
+#[cfg(feature = "node")]
+pub mod notifier;
 pub mod verifier;
 mod version;
 
@@ -21,4 +27,4 @@ pub type Result<T, E = Error> = core::result::Result<T, E>;
 pub use chain::Rusk;
 
 #[cfg(feature = "testwallet")]
-mod test_utils;
+pub mod test_utils;
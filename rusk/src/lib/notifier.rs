@@ -0,0 +1,484 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Delivery of block notifications to webhooks.
+//!
+//! A block is notified only once it is buried under the configured number of
+//! confirmations. Should a reorg replace blocks that were already notified, a
+//! revert notification is sent for each of them, most recent first, before
+//! the blocks replacing them are notified.
+//!
+//! Notifications are delivered at least once: the progress is persisted only
+//! after every webhook accepted a notification, and the delivery is retried on
+//! the next poll otherwise.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request, Uri};
+use node::database::rocksdb::{Backend, MD_HASH_KEY, MD_NOTIFIER};
+use node::database::{Ledger, Metadata, DB};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+/// Number of notified blocks remembered to detect reorgs
+const MAX_TRACKED_BLOCKS: usize = 1000;
+
+/// Maximum number of blocks notified per poll, so that catching up doesn't
+/// delay the detection of reorgs
+const MAX_BLOCKS_PER_POLL: u64 = 100;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Params {
+    /// HTTP endpoints notifications are POSTed to
+    #[serde(default)]
+    pub webhooks: Vec<String>,
+    /// Number of blocks built on top of a block before it is notified
+    #[serde(default = "default_confirmations")]
+    pub confirmations: u64,
+    #[serde(default = "default_poll_interval", with = "humantime_serde")]
+    pub poll_interval: Duration,
+    #[serde(default = "default_timeout", with = "humantime_serde")]
+    pub timeout: Duration,
+}
+
+const fn default_confirmations() -> u64 {
+    10
+}
+
+const fn default_poll_interval() -> Duration {
+    Duration::from_secs(5)
+}
+
+const fn default_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            webhooks: vec![],
+            confirmations: default_confirmations(),
+            poll_interval: default_poll_interval(),
+            timeout: default_timeout(),
+        }
+    }
+}
+
+/// A notification sent to the webhooks
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notification {
+    /// A block got enough confirmations
+    Block {
+        height: u64,
+        /// Hex encoded hash of the block
+        hash: String,
+        transactions: Vec<TxNotification>,
+    },
+    /// A previously notified block was reverted
+    Revert { height: u64, hash: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TxNotification {
+    hash: String,
+    gas_spent: u64,
+    err: Option<String>,
+    events: Vec<EventNotification>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventNotification {
+    source: String,
+    topic: String,
+    data: String,
+}
+
+/// Blocks notified so far, persisted in the node metadata
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Progress {
+    /// Height and hash of the most recently notified blocks, in height order
+    notified: VecDeque<(u64, [u8; 32])>,
+}
+
+pub struct Notifier {
+    db: Arc<RwLock<Backend>>,
+    params: Params,
+    webhooks: Vec<Uri>,
+    client: Client<HttpConnector>,
+}
+
+impl Notifier {
+    pub fn new(
+        db: Arc<RwLock<Backend>>,
+        params: Params,
+    ) -> anyhow::Result<Self> {
+        let webhooks = params
+            .webhooks
+            .iter()
+            .map(|w| w.parse())
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            db,
+            params,
+            webhooks,
+            client: Client::new(),
+        })
+    }
+
+    /// Polls the ledger for blocks to notify, until the task is aborted.
+    pub async fn run(self) {
+        info!(
+            event = "notifier started",
+            webhooks = self.webhooks.len(),
+            confirmations = self.params.confirmations,
+        );
+
+        let mut interval = tokio::time::interval(self.params.poll_interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.poll().await {
+                warn!(event = "notifier poll failed", ?err);
+            }
+        }
+    }
+
+    async fn poll(&self) -> anyhow::Result<()> {
+        let mut progress = self.load_progress().await?;
+
+        // Revert the notified blocks that are not in the ledger anymore
+        while let Some(&(height, hash)) = progress.notified.back() {
+            let current = self
+                .db
+                .read()
+                .await
+                .view(|t| t.fetch_block_hash_by_height(height))?;
+            if current == Some(hash) {
+                break;
+            }
+
+            let hash = hex::encode(hash);
+            info!(event = "notifying revert", height, hash);
+            self.deliver(&Notification::Revert { height, hash }).await?;
+            progress.notified.pop_back();
+            self.store_progress(&progress).await?;
+        }
+
+        let tip = self.tip_height().await?;
+        let Some(last) = tip.checked_sub(self.params.confirmations) else {
+            return Ok(());
+        };
+        // A fresh notifier starts from the last confirmed block
+        let next = match progress.notified.back() {
+            Some((height, _)) => height + 1,
+            None => last,
+        };
+
+        for height in next..=last.min(next + MAX_BLOCKS_PER_POLL - 1) {
+            let Some((hash, notification)) =
+                self.block_notification(height).await?
+            else {
+                break;
+            };
+
+            debug!(event = "notifying block", height);
+            self.deliver(&notification).await?;
+
+            progress.notified.push_back((height, hash));
+            if progress.notified.len() > MAX_TRACKED_BLOCKS {
+                progress.notified.pop_front();
+            }
+            self.store_progress(&progress).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn tip_height(&self) -> anyhow::Result<u64> {
+        self.db.read().await.view(|t| {
+            let tip = t
+                .op_read(MD_HASH_KEY)?
+                .ok_or_else(|| anyhow::anyhow!("Cannot find tip"))?;
+            let (header, _) = t
+                .fetch_block_header(&tip)?
+                .ok_or_else(|| anyhow::anyhow!("Cannot find tip header"))?;
+            Ok(header.height)
+        })
+    }
+
+    async fn block_notification(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<Option<([u8; 32], Notification)>> {
+        self.db.read().await.view(|t| {
+            let Some(block) = t.fetch_block_by_height(height)? else {
+                return Ok(None);
+            };
+
+            let mut transactions = Vec::with_capacity(block.txs().len());
+            for tx in block.txs() {
+                let hash = tx.hash();
+                let spent =
+                    t.get_ledger_tx_by_hash(&hash)?.ok_or_else(|| {
+                        anyhow::anyhow!("Cannot find tx {}", hex::encode(hash))
+                    })?;
+
+                transactions.push(TxNotification {
+                    hash: hex::encode(hash),
+                    gas_spent: spent.gas_spent,
+                    err: spent.err,
+                    events: spent
+                        .events
+                        .into_iter()
+                        .map(|e| EventNotification {
+                            source: hex::encode(e.source),
+                            topic: e.topic,
+                            data: hex::encode(e.data),
+                        })
+                        .collect(),
+                });
+            }
+
+            let hash = block.header().hash;
+            let notification = Notification::Block {
+                height,
+                hash: hex::encode(hash),
+                transactions,
+            };
+            Ok(Some((hash, notification)))
+        })
+    }
+
+    /// Posts a notification to every webhook, failing if any of them doesn't
+    /// accept it.
+    async fn deliver(&self, notification: &Notification) -> anyhow::Result<()> {
+        let body = serde_json::to_vec(notification)?;
+
+        for webhook in &self.webhooks {
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri(webhook.clone())
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.clone()))?;
+
+            let response = tokio::time::timeout(
+                self.params.timeout,
+                self.client.request(request),
+            )
+            .await
+            .map_err(|_| anyhow::anyhow!("{webhook} timed out"))??;
+
+            if !response.status().is_success() {
+                anyhow::bail!("{webhook} replied {}", response.status());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn load_progress(&self) -> anyhow::Result<Progress> {
+        let progress = self.db.read().await.view(|t| t.op_read(MD_NOTIFIER))?;
+
+        Ok(match progress {
+            Some(bytes) => serde_json::from_slice(&bytes)?,
+            None => Progress::default(),
+        })
+    }
+
+    async fn store_progress(&self, progress: &Progress) -> anyhow::Result<()> {
+        let progress = serde_json::to_vec(progress)?;
+        self.db
+            .read()
+            .await
+            .update(|t| t.op_write(MD_NOTIFIER, progress))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::ops::RangeInclusive;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Response, Server, StatusCode};
+    use node_data::ledger::{Header, Label};
+    use parking_lot::Mutex;
+
+    /// A webhook recording the notifications it accepts.
+    #[derive(Clone, Default)]
+    struct Webhook {
+        received: Arc<Mutex<Vec<serde_json::Value>>>,
+        failing: Arc<AtomicBool>,
+    }
+
+    impl Webhook {
+        fn serve(&self) -> SocketAddr {
+            let hook = self.clone();
+            let make_svc = make_service_fn(move |_| {
+                let hook = hook.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(
+                        move |req: Request<Body>| {
+                            let hook = hook.clone();
+                            async move {
+                                let body =
+                                    hyper::body::to_bytes(req.into_body())
+                                        .await?;
+                                let mut rsp = Response::new(Body::empty());
+                                if hook.failing.load(Ordering::Relaxed) {
+                                    *rsp.status_mut() =
+                                        StatusCode::SERVICE_UNAVAILABLE;
+                                } else {
+                                    let notification =
+                                        serde_json::from_slice(&body)
+                                            .expect("notification to be JSON");
+                                    hook.received.lock().push(notification);
+                                }
+                                Ok::<_, hyper::Error>(rsp)
+                            }
+                        },
+                    ))
+                }
+            });
+
+            let server =
+                Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+            let addr = server.local_addr();
+            tokio::spawn(server);
+            addr
+        }
+
+        /// Returns the type and height of the notifications received since
+        /// the last call.
+        fn take(&self) -> Vec<(String, u64)> {
+            self.received
+                .lock()
+                .drain(..)
+                .map(|n| {
+                    let ty = n["type"].as_str().expect("a type").to_string();
+                    (ty, n["height"].as_u64().expect("a height"))
+                })
+                .collect()
+        }
+    }
+
+    /// Stores the blocks at the given heights, on top of the ones stored
+    /// already. Blocks of different forks have different hashes.
+    async fn store_blocks(
+        db: &RwLock<Backend>,
+        heights: RangeInclusive<u64>,
+        fork: u8,
+    ) {
+        db.read()
+            .await
+            .update(|t| {
+                for height in heights {
+                    let mut header = Header::default();
+                    header.height = height;
+                    header.hash = [fork; 32];
+                    header.hash[..8].copy_from_slice(&height.to_le_bytes());
+                    t.store_block(&header, &[], Label::Accepted)?;
+                    t.op_write(MD_HASH_KEY, header.hash)?;
+                }
+                Ok(())
+            })
+            .expect("storing blocks to succeed");
+    }
+
+    fn notifier(db: Arc<RwLock<Backend>>, webhook: SocketAddr) -> Notifier {
+        let params = Params {
+            webhooks: vec![format!("http://{webhook}")],
+            ..Default::default()
+        };
+        Notifier::new(db, params).expect("notifier to be created")
+    }
+
+    fn blocks(heights: RangeInclusive<u64>) -> Vec<(String, u64)> {
+        heights.map(|h| ("block".to_string(), h)).collect()
+    }
+
+    #[tokio::test]
+    async fn notifies_confirmed_blocks_at_least_once() {
+        let dir = tempfile::tempdir().expect("creating a tempdir to succeed");
+        let db = Arc::new(RwLock::new(Backend::create_or_open(dir.path())));
+        let webhook = Webhook::default();
+        let notifier = notifier(db.clone(), webhook.serve());
+
+        // A fresh notifier starts from the last confirmed block
+        store_blocks(&db, 0..=20, 0).await;
+        notifier.poll().await.expect("poll to succeed");
+        assert_eq!(webhook.take(), blocks(10..=10));
+
+        store_blocks(&db, 21..=23, 0).await;
+        notifier.poll().await.expect("poll to succeed");
+        assert_eq!(webhook.take(), blocks(11..=13));
+
+        // A rejected notification is delivered again on the next poll
+        store_blocks(&db, 24..=24, 0).await;
+        webhook.failing.store(true, Ordering::Relaxed);
+        assert!(notifier.poll().await.is_err());
+        assert_eq!(webhook.take(), vec![]);
+
+        webhook.failing.store(false, Ordering::Relaxed);
+        notifier.poll().await.expect("poll to succeed");
+        assert_eq!(webhook.take(), blocks(14..=14));
+
+        // The progress survives a restart
+        let notifier = self::notifier(db.clone(), webhook.serve());
+        store_blocks(&db, 25..=25, 0).await;
+        notifier.poll().await.expect("poll to succeed");
+        assert_eq!(webhook.take(), blocks(15..=15));
+    }
+
+    #[tokio::test]
+    async fn reverts_replaced_blocks() {
+        let dir = tempfile::tempdir().expect("creating a tempdir to succeed");
+        let db = Arc::new(RwLock::new(Backend::create_or_open(dir.path())));
+        let webhook = Webhook::default();
+        let notifier = notifier(db.clone(), webhook.serve());
+
+        store_blocks(&db, 0..=20, 0).await;
+        notifier.poll().await.expect("poll to succeed");
+        store_blocks(&db, 21..=22, 0).await;
+        notifier.poll().await.expect("poll to succeed");
+        assert_eq!(webhook.take(), blocks(10..=12));
+
+        // A reorg replaces the blocks from height 11
+        store_blocks(&db, 11..=25, 1).await;
+        notifier.poll().await.expect("poll to succeed");
+
+        let mut expected =
+            vec![("revert".to_string(), 12), ("revert".to_string(), 11)];
+        expected.extend(blocks(11..=15));
+        assert_eq!(webhook.take(), expected);
+    }
+
+    #[tokio::test]
+    async fn catches_up_in_bounded_steps() {
+        let dir = tempfile::tempdir().expect("creating a tempdir to succeed");
+        let db = Arc::new(RwLock::new(Backend::create_or_open(dir.path())));
+        let webhook = Webhook::default();
+        let notifier = notifier(db.clone(), webhook.serve());
+
+        store_blocks(&db, 0..=10, 0).await;
+        notifier.poll().await.expect("poll to succeed");
+        assert_eq!(webhook.take(), blocks(0..=0));
+
+        let tip = 2 * MAX_BLOCKS_PER_POLL;
+        store_blocks(&db, 11..=tip, 0).await;
+        notifier.poll().await.expect("poll to succeed");
+        assert_eq!(webhook.take(), blocks(1..=MAX_BLOCKS_PER_POLL));
+    }
+}
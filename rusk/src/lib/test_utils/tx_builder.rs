@@ -0,0 +1,182 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fmt::Debug;
+
+use dusk_bls12_381::BlsScalar;
+use dusk_pki::PublicSpendKey;
+use dusk_wallet_core::{
+    ProverClient, Transaction as PhoenixTransaction, Wallet,
+};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+use super::wallet::{MockProverClient, TestStateClient, TestStore};
+use crate::{Error, Result, Rusk};
+
+const DEFAULT_GAS_LIMIT: u64 = 1_000_000_000;
+const DEFAULT_GAS_PRICE: u64 = 1;
+const DEFAULT_SEED: u64 = 0xdead;
+
+/// Builds Phoenix transactions spending the notes of the [`TestStore`] keys,
+/// as found in the state of a [`Rusk`] instance.
+///
+/// The inputs, their openings and the change are picked by the wallet, the
+/// proofs computed by the prover client `P`. With the default
+/// [`MockProverClient`] the transactions are built quickly but fail to
+/// execute; use [`super::TestProverClient`] for transactions meant to be
+/// executed.
+///
+/// The randomness is seeded, thus the same calls on the same state build the
+/// same transactions.
+pub struct TxBuilder<P = MockProverClient> {
+    wallet: Wallet<TestStore, TestStateClient, P>,
+    rng: StdRng,
+    sender: u64,
+    gas_limit: u64,
+    gas_price: u64,
+}
+
+impl TxBuilder {
+    /// Creates a builder attaching placeholder proofs to the transactions.
+    pub fn new(rusk: Rusk) -> Self {
+        Self::with_prover(rusk, MockProverClient)
+    }
+}
+
+impl<P> TxBuilder<P>
+where
+    P: ProverClient,
+    P::Error: Debug,
+{
+    /// Creates a builder proving the transactions with `prover`.
+    pub fn with_prover(rusk: Rusk, prover: P) -> Self {
+        Self {
+            wallet: Wallet::new(TestStore, TestStateClient::new(rusk), prover),
+            rng: StdRng::seed_from_u64(DEFAULT_SEED),
+            sender: 0,
+            gas_limit: DEFAULT_GAS_LIMIT,
+            gas_price: DEFAULT_GAS_PRICE,
+        }
+    }
+
+    /// Sets the index of the key whose notes are spent, and that is refunded
+    /// the unspent gas.
+    pub fn sender(mut self, index: u64) -> Self {
+        self.sender = index;
+        self
+    }
+
+    /// Sets the gas limit and price of the transactions.
+    pub fn gas(mut self, limit: u64, price: u64) -> Self {
+        self.gas_limit = limit;
+        self.gas_price = price;
+        self
+    }
+
+    /// Reseeds the randomness of the transactions.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Returns the wallet the transactions are built with, e.g. to check the
+    /// balances and stakes of the keys.
+    pub fn wallet(&self) -> &Wallet<TestStore, TestStateClient, P> {
+        &self.wallet
+    }
+
+    /// Returns the public spend key of the key at `index`.
+    pub fn public_spend_key(&self, index: u64) -> Result<PublicSpendKey> {
+        self.wallet.public_spend_key(index).map_err(wallet_error)
+    }
+
+    /// Transfers `value` to the key at index `receiver`.
+    pub fn transfer(
+        &mut self,
+        receiver: u64,
+        value: u64,
+    ) -> Result<PhoenixTransaction> {
+        let receiver = self.public_spend_key(receiver)?;
+        self.transfer_to(&receiver, value)
+    }
+
+    /// Transfers `value` to the given public spend key.
+    pub fn transfer_to(
+        &mut self,
+        receiver: &PublicSpendKey,
+        value: u64,
+    ) -> Result<PhoenixTransaction> {
+        let refund = self.public_spend_key(self.sender)?;
+        let ref_id = BlsScalar::from(self.rng.next_u64());
+        self.wallet
+            .transfer(
+                &mut self.rng,
+                self.sender,
+                &refund,
+                receiver,
+                value,
+                self.gas_limit,
+                self.gas_price,
+                ref_id,
+            )
+            .map_err(wallet_error)
+    }
+
+    /// Stakes `value` on behalf of the consensus key at index `staker`.
+    pub fn stake(
+        &mut self,
+        staker: u64,
+        value: u64,
+    ) -> Result<PhoenixTransaction> {
+        let refund = self.public_spend_key(self.sender)?;
+        self.wallet
+            .stake(
+                &mut self.rng,
+                self.sender,
+                staker,
+                &refund,
+                value,
+                self.gas_limit,
+                self.gas_price,
+            )
+            .map_err(wallet_error)
+    }
+
+    /// Unstakes the stake of the consensus key at index `staker`.
+    pub fn unstake(&mut self, staker: u64) -> Result<PhoenixTransaction> {
+        let refund = self.public_spend_key(self.sender)?;
+        self.wallet
+            .unstake(
+                &mut self.rng,
+                self.sender,
+                staker,
+                &refund,
+                self.gas_limit,
+                self.gas_price,
+            )
+            .map_err(wallet_error)
+    }
+
+    /// Withdraws the reward of the consensus key at index `staker`.
+    pub fn withdraw(&mut self, staker: u64) -> Result<PhoenixTransaction> {
+        let refund = self.public_spend_key(self.sender)?;
+        self.wallet
+            .withdraw(
+                &mut self.rng,
+                self.sender,
+                staker,
+                &refund,
+                self.gas_limit,
+                self.gas_price,
+            )
+            .map_err(wallet_error)
+    }
+}
+
+fn wallet_error<E: Debug>(err: E) -> Error {
+    Error::Other(format!("Cannot build the transaction: {err:?}").into())
+}
@@ -9,12 +9,29 @@
 use crate::error::Error;
 use crate::Result;
 
+use dusk_bls12_381::BlsScalar;
 use dusk_wallet_core::Transaction;
 use rusk_profile::Circuit as CircuitProfile;
+use serde::Serialize;
+use sha3::{Digest, Sha3_256};
 use transfer_circuits::CircuitOutput;
 
 use std::sync::LazyLock;
 
+/// Circuits whose proofs are verified by the node, either by the host or by
+/// the genesis contracts.
+pub const CIRCUITS: &[&str] = &[
+    "SendToContractTransparentCircuit",
+    "SendToContractObfuscatedCircuit",
+    "WithdrawFromTransparentCircuit",
+    "WithdrawFromObfuscatedCircuit",
+    "ExecuteCircuitOneTwo",
+    "ExecuteCircuitTwoTwo",
+    "ExecuteCircuitThreeTwo",
+    "ExecuteCircuitFourTwo",
+    "LicenseCircuit",
+];
+
 pub static VD_EXEC_1_2: LazyLock<Vec<u8>> =
     LazyLock::new(|| fetch_verifier("ExecuteCircuitOneTwo"));
 
@@ -27,7 +44,8 @@ pub static VD_EXEC_3_2: LazyLock<Vec<u8>> =
 pub static VD_EXEC_4_2: LazyLock<Vec<u8>> =
     LazyLock::new(|| fetch_verifier("ExecuteCircuitFourTwo"));
 
-pub fn verify_proof(tx: &Transaction) -> Result<bool> {
+/// Verifies the proof of a transaction meant for the network `chain_id`.
+pub fn verify_proof(tx: &Transaction, chain_id: u8) -> Result<bool> {
     let tx_hash = rusk_abi::hash(tx.to_hash_input_bytes());
 
     let inputs = &tx.nullifiers;
@@ -62,6 +80,7 @@ pub fn verify_proof(tx: &Transaction) -> Result<bool> {
         (0usize..2usize.saturating_sub(outputs.len()))
             .map(|_| CircuitOutput::ZERO_COMMITMENT.into()),
     );
+    pi.push(BlsScalar::from(chain_id as u64).into());
 
     let vd = match inputs.len() {
         1 => &VD_EXEC_1_2,
@@ -81,6 +100,66 @@ pub fn verify_proof(tx: &Transaction) -> Result<bool> {
     Ok(rusk_abi::verify_proof(vd.to_vec(), proof.clone(), pi))
 }
 
+/// Verifier key of a circuit, as used by the node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerifierKey {
+    pub circuit: String,
+    /// Hex encoded ID of the circuit, changing with its description and the
+    /// plonk version it is compiled with
+    pub circuit_id: String,
+    /// Hex encoded hash of the verifier data
+    pub hash: String,
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+/// Verifier keys of all the [`CIRCUITS`], along with their version.
+static VERIFIER_KEYS: LazyLock<(String, Vec<VerifierKey>)> =
+    LazyLock::new(|| {
+        let keys: Vec<_> =
+            CIRCUITS.iter().map(|name| verifier_key(name)).collect();
+
+        let mut hasher = Sha3_256::new();
+        for key in &keys {
+            hasher.update(key.circuit.as_bytes());
+            hasher.update(key.hash.as_bytes());
+        }
+        let version = hex::encode(hasher.finalize());
+
+        (version, keys)
+    });
+
+/// Returns the verifier key of the given circuit, the very one the node
+/// verifies proofs with.
+fn verifier_key(circuit_name: &str) -> VerifierKey {
+    let data = match circuit_name {
+        "ExecuteCircuitOneTwo" => VD_EXEC_1_2.clone(),
+        "ExecuteCircuitTwoTwo" => VD_EXEC_2_2.clone(),
+        "ExecuteCircuitThreeTwo" => VD_EXEC_3_2.clone(),
+        "ExecuteCircuitFourTwo" => VD_EXEC_4_2.clone(),
+        _ => fetch_verifier(circuit_name),
+    };
+    let circuit_profile = CircuitProfile::from_name(circuit_name)
+        .unwrap_or_else(|_| {
+            panic!("There should be circuit data stored for {}", circuit_name)
+        });
+
+    VerifierKey {
+        circuit: circuit_name.to_string(),
+        circuit_id: circuit_profile.id_str().to_string(),
+        hash: hex::encode(Sha3_256::digest(&data)),
+        data,
+    }
+}
+
+/// Returns the verifier keys of all the [`CIRCUITS`], along with their
+/// version: a hash of the keys, changing whenever any circuit is upgraded.
+///
+/// The keys are read once, by the first call.
+pub fn verifier_keys() -> &'static (String, Vec<VerifierKey>) {
+    &VERIFIER_KEYS
+}
+
 fn fetch_verifier(circuit_name: &str) -> Vec<u8> {
     let circuit_profile = CircuitProfile::from_name(circuit_name)
         .unwrap_or_else(|_| {
@@ -4,7 +4,6 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-pub mod block;
 pub mod keys;
 pub mod state;
 pub mod wallet;
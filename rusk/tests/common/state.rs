@@ -8,6 +8,7 @@ use std::path::Path;
 
 use dusk_bytes::Serializable;
 use node::vm::VMExecution;
+use rusk::chain::{GasPricing, HostGasLimits, Migrations};
 use rusk::{Result, Rusk};
 use rusk_recovery_tools::state::{self, Snapshot};
 
@@ -16,7 +17,10 @@ use dusk_consensus::operations::CallParams;
 use dusk_wallet_core::Transaction as PhoenixTransaction;
 use node_data::{
     bls::PublicKeyBytes,
-    ledger::{Block, Certificate, Header, IterationsInfo, SpentTransaction},
+    ledger::{
+        Block, Certificate, Header, IterationsInfo, SpentTransaction,
+        DEFAULT_CHAIN_ID,
+    },
     message::payload::Vote,
 };
 use tracing::info;
@@ -24,13 +28,40 @@ use tracing::info;
 use crate::common::keys::BLS_SK;
 
 // Creates a Rusk initial state in the given directory
+/// Opens the state already deployed in `dir`.
+pub fn open_state<P: AsRef<Path>>(
+    dir: P,
+    sync_commit_interval: u64,
+) -> Result<Rusk> {
+    open_state_with(dir, sync_commit_interval, Migrations::default())
+}
+
+/// Opens the state already deployed in `dir`, running the given
+/// `migrations`.
+pub fn open_state_with<P: AsRef<Path>>(
+    dir: P,
+    sync_commit_interval: u64,
+    migrations: Migrations,
+) -> Result<Rusk> {
+    Rusk::new(
+        dir.as_ref(),
+        DEFAULT_CHAIN_ID,
+        None,
+        HostGasLimits::default(),
+        GasPricing::default(),
+        sync_commit_interval,
+        migrations,
+        None,
+    )
+}
+
 pub fn new_state<P: AsRef<Path>>(dir: P, snapshot: &Snapshot) -> Result<Rusk> {
     let dir = dir.as_ref();
 
     let (_, commit_id) = state::deploy(dir, snapshot)
         .expect("Deploying initial state should succeed");
 
-    let rusk = Rusk::new(dir, None).expect("Instantiating rusk should succeed");
+    let rusk = open_state(dir, 1).expect("Instantiating rusk should succeed");
 
     assert_eq!(
         commit_id,
@@ -96,6 +127,8 @@ pub fn generator_procedure(
         block_gas_limit,
         generator_pubkey,
         missed_generators,
+        timestamp: 0,
+        seed: Default::default(),
     };
 
     let (transfer_txs, discarded, execute_output) =
@@ -128,7 +161,12 @@ pub fn generator_procedure(
     let verify_output = rusk.verify_state_transition(&block)?;
     info!("verify_state_transition new verification: {verify_output}",);
 
-    let (accept_txs, accept_output) = rusk.accept(&block)?;
+    assert_eq!(
+        verify_output, execute_output,
+        "The verifier should reach the state of the generator"
+    );
+
+    let (accept_txs, _, accept_output) = rusk.accept(&block)?;
 
     assert_eq!(accept_txs.len(), expected.executed, "all txs accepted");
 
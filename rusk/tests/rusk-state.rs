@@ -9,8 +9,12 @@ pub mod common;
 
 use crate::common::*;
 use std::collections::HashMap;
+use std::iter;
 
 use dusk_bls12_381::BlsScalar;
+use dusk_bls12_381_sign::PublicKey;
+use dusk_consensus::operations::CallParams;
+use node::vm::VMExecution;
 use std::path::Path;
 use std::sync::{mpsc, Arc, RwLock};
 
@@ -22,19 +26,24 @@ use phoenix_core::transaction::TreeLeaf;
 use phoenix_core::Note;
 use rand::prelude::*;
 use rand::rngs::StdRng;
-use rusk::chain::{Rusk, RuskTip};
+use rusk::chain::{Migrations, Rusk, RuskTip, MAX_OPENINGS};
 use rusk::Result;
 use rusk_abi::dusk::LUX;
-use rusk_abi::{TRANSFER_CONTRACT, VM};
+use rusk_abi::{Error as PiecrustError, Session, TRANSFER_CONTRACT, VM};
 use tempfile::tempdir;
 use tokio::task;
 use tracing::info;
 
-use crate::common::state::new_state;
+use crate::common::keys::BLS_SK;
+use crate::common::state::{
+    generator_procedure, new_state, open_state, open_state_with,
+};
 use crate::common::wallet::{TestProverClient, TestStateClient, TestStore};
 
 const BLOCK_HEIGHT: u64 = 1;
+const BLOCK_GAS_LIMIT: u64 = 1_000_000_000;
 const INITIAL_BALANCE: u64 = 10_000_000_000;
+const MIGRATION_GAS_LIMIT: u64 = 100_000_000;
 
 // Creates the Rusk initial state for the tests below
 fn initial_state<P: AsRef<Path>>(dir: P) -> Result<Rusk> {
@@ -90,6 +99,31 @@ where
     })
 }
 
+// Pushes a note to the transfer tree, as a state migration
+fn push_note_migration(
+    mut session: Session,
+    gas_limit: u64,
+) -> std::result::Result<(Session, u64), PiecrustError> {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let psk = SecretSpendKey::random(&mut rng).public_spend_key();
+    let note = Note::transparent(&mut rng, &psk, INITIAL_BALANCE);
+
+    let pushed = session.call::<_, Note>(
+        TRANSFER_CONTRACT,
+        "push_note",
+        &(BLOCK_HEIGHT, note),
+        gas_limit,
+    )?;
+    let updated = session.call::<_, ()>(
+        TRANSFER_CONTRACT,
+        "update_root",
+        &(),
+        gas_limit - pushed.gas_spent,
+    )?;
+
+    Ok((session, pushed.gas_spent + updated.gas_spent))
+}
+
 #[test]
 pub fn rusk_state_accepted() -> Result<()> {
     // Setup the logger
@@ -152,6 +186,302 @@ pub fn rusk_state_finalized() -> Result<()> {
     Ok(())
 }
 
+#[test]
+pub fn rusk_state_openings() -> Result<()> {
+    // Setup the logger
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+
+    push_note(&rusk, |_tip, _vm| {});
+
+    // The second request is served from the cache
+    for _ in 0..2 {
+        let (root, openings) = rusk.openings(&[1, 0, 2], None)?;
+
+        assert_eq!(openings.len(), 3, "There should be one entry per note");
+        assert!(openings[0].is_some(), "The new note should have an opening");
+        assert!(openings[1].is_some(), "The note should have an opening");
+        assert!(openings[2].is_none(), "There should be no third note");
+        for opening in openings.iter().flatten() {
+            assert_eq!(opening.root().hash, root);
+        }
+    }
+
+    let positions = vec![0; MAX_OPENINGS + 1];
+    assert!(
+        rusk.openings(&positions, None).is_err(),
+        "Requesting too many openings should fail"
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn rusk_state_spend_inputs() -> Result<()> {
+    // Setup the logger
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+
+    let (state_root, anchor, notes) = rusk.spend_inputs(&[0])?;
+
+    assert_eq!(state_root, rusk.state_root(), "The state should be current");
+    assert_eq!(notes.len(), 1, "There should be one entry per position");
+    let (note, opening) = &notes[0];
+    assert_eq!(*note.pos(), 0, "The note should be at the given position");
+    assert_eq!(opening.root().hash, anchor);
+
+    assert!(
+        rusk.spend_inputs(&[0, 1]).is_err(),
+        "Requesting a missing note should fail"
+    );
+
+    let positions = vec![0; MAX_OPENINGS + 1];
+    assert!(
+        rusk.spend_inputs(&positions).is_err(),
+        "Requesting too many notes should fail"
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn rusk_state_generation_snapshot() -> Result<()> {
+    // Setup the logger
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+    let base_commit = rusk.state_root();
+
+    let generator = PublicKey::from(&*BLS_SK);
+    let params = CallParams {
+        round: BLOCK_HEIGHT,
+        block_gas_limit: BLOCK_GAS_LIMIT,
+        generator_pubkey: node_data::bls::PublicKey::new(generator),
+        missed_generators: vec![],
+        timestamp: 0,
+        seed: Default::default(),
+    };
+
+    let (_, _, before) = rusk.execute_transactions(&params, iter::empty())?;
+
+    // Moving the tip doesn't affect a generation on a snapshot of it
+    push_note(&rusk, |_tip, _vm| {});
+    let (_, _, snapshot) =
+        rusk.execute_transactions_at(base_commit, &params, iter::empty())?;
+    assert_eq!(snapshot, before);
+
+    let (_, _, after) = rusk.execute_transactions(&params, iter::empty())?;
+    assert_ne!(
+        after.state_root, before.state_root,
+        "The generation should be on top of the new tip"
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn rusk_state_prune_commits() -> Result<()> {
+    // Setup the logger
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+    let base_commit = rusk.state_root();
+
+    let generator = PublicKey::from(&*BLS_SK);
+    let preverify = |candidate: u8, height: u64| {
+        rusk.preverify_candidate(
+            [candidate; 32],
+            height,
+            0,
+            BLOCK_GAS_LIMIT,
+            &generator,
+            [0; 48],
+            &[],
+            &[],
+        )
+    };
+    let accept = |candidate: u8| {
+        rusk.accept_precommitted(&[candidate; 32], None, false)
+            .map(|output| output.expect("candidate to be precommitted"))
+    };
+
+    preverify(1, BLOCK_HEIGHT)?;
+    accept(1)?;
+    let previous = rusk.state_root();
+    preverify(2, BLOCK_HEIGHT + 1)?;
+    accept(2)?;
+    let current = rusk.state_root();
+    preverify(3, BLOCK_HEIGHT + 2)?;
+
+    // A commit the tip never moved to
+    let orphan = push_note(&rusk, |mut tip, _vm| {
+        let orphan = tip.current;
+        tip.current = current;
+        orphan
+    });
+    assert!(![base_commit, previous, current].contains(&orphan));
+
+    assert_eq!(rusk.prune_commits(), 1, "Only the orphan should be pruned");
+    let deletions = rusk.commit_deletions();
+    let scheduled: Vec<_> = deletions
+        .pending
+        .into_iter()
+        .chain(deletions.finished.into_iter().map(|f| f.commit))
+        .collect();
+    assert_eq!(scheduled, vec![hex::encode(orphan)]);
+
+    // The candidate state is kept to be accepted later
+    accept(3)?;
+    assert_ne!(rusk.state_root(), current);
+
+    Ok(())
+}
+
+#[test]
+pub fn rusk_state_migration() -> Result<()> {
+    // Setup the logger
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    drop(initial_state(&tmp)?);
+    let migrations = Migrations::default().register(
+        "push_note",
+        BLOCK_HEIGHT,
+        MIGRATION_GAS_LIMIT,
+        push_note_migration,
+    );
+    let rusk = open_state_with(&tmp, 1, migrations)?;
+
+    let plain_tmp =
+        tempdir().expect("Should be able to create temporary directory");
+    let plain = initial_state(&plain_tmp)?;
+
+    // Both the generator and the verifier run the migration
+    generator_procedure(
+        &rusk,
+        &[],
+        BLOCK_HEIGHT,
+        BLOCK_GAS_LIMIT,
+        vec![],
+        None,
+    )
+    .expect("The migrated block should be accepted");
+    generator_procedure(
+        &plain,
+        &[],
+        BLOCK_HEIGHT,
+        BLOCK_GAS_LIMIT,
+        vec![],
+        None,
+    )
+    .expect("The block should be accepted");
+
+    assert_ne!(
+        rusk.state_root(),
+        plain.state_root(),
+        "The migration should change the state"
+    );
+    assert_eq!(
+        leaves_from_height(&rusk, BLOCK_HEIGHT)?.len(),
+        1,
+        "The migration should push a note"
+    );
+
+    // A migration cannot spend more than its gas limit
+    drop(plain);
+    let migrations = Migrations::default().register(
+        "push_note",
+        BLOCK_HEIGHT + 1,
+        1,
+        push_note_migration,
+    );
+    let plain = open_state_with(&plain_tmp, 1, migrations)?;
+    assert!(
+        generator_procedure(
+            &plain,
+            &[],
+            BLOCK_HEIGHT + 1,
+            BLOCK_GAS_LIMIT,
+            vec![],
+            None
+        )
+        .is_err(),
+        "A migration out of gas should fail the block"
+    );
+
+    Ok(())
+}
+
+#[test]
+pub fn rusk_state_deferred_lost_on_restart() -> Result<()> {
+    // Setup the logger
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+    let initial_root = rusk.state_root();
+
+    let generator = PublicKey::from(&*BLS_SK);
+    let finalize_blocks = |rusk: &Rusk| -> Result<()> {
+        for height in BLOCK_HEIGHT..BLOCK_HEIGHT + 2 {
+            rusk.finalize_deferred_transactions(
+                height,
+                0,
+                BLOCK_GAS_LIMIT,
+                generator,
+                [0; 48],
+                vec![],
+                None,
+                &[],
+            )?;
+        }
+        Ok(())
+    };
+
+    drop(rusk);
+    let rusk = open_state(&tmp, 3)?;
+    finalize_blocks(&rusk)?;
+
+    // Reading the stakes must not commit the pending blocks
+    rusk.get_provisioner(&generator)
+        .expect("Reading a provisioner should succeed");
+    assert_eq!(
+        rusk.state_root(),
+        initial_root,
+        "Pending blocks should not be committed"
+    );
+
+    // Stopping the node before the commit loses the pending blocks
+    drop(rusk);
+    let rusk = open_state(&tmp, 3)?;
+    assert_eq!(
+        rusk.state_root(),
+        initial_root,
+        "The state should be the last committed one after a restart"
+    );
+
+    finalize_blocks(&rusk)?;
+    rusk.commit_deferred()?;
+    let committed_root = rusk.state_root();
+    assert_ne!(committed_root, initial_root);
+
+    drop(rusk);
+    let rusk = open_state(&tmp, 3)?;
+    assert_eq!(
+        rusk.state_root(),
+        committed_root,
+        "Committed blocks should persist across restarts"
+    );
+
+    Ok(())
+}
+
 // #[tokio::test(flavor = "multi_thread")]
 #[allow(dead_code)]
 async fn generate_bench_txs() -> Result<(), Box<dyn std::error::Error>> {
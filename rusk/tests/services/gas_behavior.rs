@@ -8,6 +8,7 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::sync::{Arc, RwLock};
 
+use dusk_consensus::config::{BASE_GAS_PRICE, MAX_GAS_TIP};
 use dusk_wallet_core::{self as wallet};
 use rand::prelude::*;
 use rand::rngs::StdRng;
@@ -177,3 +178,88 @@ pub async fn erroring_tx_charged_full() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn fee_cap_above_effective_price_refunded() -> Result<()> {
+    // Setup the logger
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+
+    let cache = Arc::new(RwLock::new(HashMap::new()));
+
+    // Create a wallet
+    let wallet = wallet::Wallet::new(
+        TestStore,
+        TestStateClient {
+            rusk: rusk.clone(),
+            cache,
+        },
+        TestProverClient::default(),
+    );
+
+    let refund = wallet
+        .public_spend_key(SENDER_INDEX_0)
+        .expect("Getting a public spend key should succeed");
+
+    // The fee cap is above the base price plus the maximum tip
+    let fee_cap = BASE_GAS_PRICE + MAX_GAS_TIP + 1_000;
+    let effective_price = BASE_GAS_PRICE + MAX_GAS_TIP;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let tx = wallet
+        .execute(
+            &mut rng,
+            TRANSFER_CONTRACT.to_bytes().into(),
+            String::from("root"),
+            (),
+            SENDER_INDEX_0,
+            &refund,
+            GAS_LIMIT_1,
+            fee_cap,
+        )
+        .expect("Making the transaction should succeed");
+
+    let spent_transactions = generator_procedure(
+        &rusk,
+        &[tx],
+        BLOCK_HEIGHT,
+        BLOCK_GAS_LIMIT,
+        vec![],
+        None,
+    )
+    .expect("generator procedure should succeed");
+    let tx = spent_transactions
+        .first()
+        .expect("There should be one spent transaction");
+
+    assert!(tx.err.is_none(), "The transaction should succeed");
+    assert_eq!(
+        tx.gas_price, effective_price,
+        "The transaction should be charged the effective price"
+    );
+
+    generator_procedure(
+        &rusk,
+        &[],
+        BLOCK_HEIGHT + 1,
+        BLOCK_GAS_LIMIT,
+        vec![],
+        None,
+    )
+    .expect("empty block generator procedure to succeed");
+
+    // The deposit above the effective price of the gas spent is refunded
+    let final_balance = wallet
+        .get_balance(SENDER_INDEX_0)
+        .expect("Getting the final balance should succeed")
+        .value;
+    assert_eq!(
+        final_balance,
+        INITIAL_BALANCE - tx.gas_spent * effective_price,
+        "The sender should only be charged the effective price"
+    );
+
+    Ok(())
+}
@@ -12,7 +12,7 @@ use dusk_bls12_381::BlsScalar;
 use dusk_pki::SecretSpendKey;
 use dusk_wallet_core::{self as wallet, Store};
 use ff::Field;
-use node_data::ledger::SpentTransaction;
+use node_data::ledger::{SpentTransaction, DEFAULT_CHAIN_ID};
 use rand::prelude::*;
 use rand::rngs::StdRng;
 use rusk::{Result, Rusk};
@@ -21,7 +21,9 @@ use tracing::info;
 
 use crate::common::logger;
 use crate::common::state::{generator_procedure, new_state};
-use crate::common::wallet::{TestProverClient, TestStateClient, TestStore};
+use crate::common::wallet::{
+    TestProverClient, TestStateClient, TestStore, TxBuilder,
+};
 
 const BLOCK_GAS_LIMIT: u64 = 100_000_000_000;
 const INITIAL_BALANCE: u64 = 10_000_000_000;
@@ -98,6 +100,18 @@ fn wallet_transfer(
         .expect("Failed to transfer");
     info!("Tx: {}", hex::encode(tx.to_var_bytes()));
 
+    // The proof is only valid on the network it was made for
+    assert!(
+        rusk::verifier::verify_proof(&tx, DEFAULT_CHAIN_ID)
+            .expect("Verifying the proof should succeed"),
+        "Proof should be valid"
+    );
+    assert!(
+        !rusk::verifier::verify_proof(&tx, DEFAULT_CHAIN_ID + 1)
+            .expect("Verifying the proof should succeed"),
+        "Proof should be invalid on another chain"
+    );
+
     let tx_hash_input_bytes = tx.to_hash_input_bytes();
     let tx_hash = rusk_abi::hash(tx_hash_input_bytes);
 
@@ -213,3 +227,34 @@ pub async fn wallet() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn tx_builder() -> Result<()> {
+    // Setup the logger
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+
+    let mut builder = TxBuilder::new(rusk.clone()).gas(1_000_000_000, 2);
+    let tx = builder.transfer(1, 1_000)?;
+
+    assert!(!tx.nullifiers.is_empty(), "Notes should be spent");
+    assert_eq!(tx.outputs.len(), 2, "Transfer and change notes expected");
+    assert_eq!(tx.fee().gas_limit, 1_000_000_000);
+    assert_eq!(tx.fee().gas_price, 2);
+
+    // The placeholder proof is rejected
+    assert!(
+        !rusk::verifier::verify_proof(&tx, DEFAULT_CHAIN_ID)?,
+        "Proof should be invalid"
+    );
+
+    // The same calls on the same state build the same transaction
+    let again = TxBuilder::new(rusk)
+        .gas(1_000_000_000, 2)
+        .transfer(1, 1_000)?;
+    assert_eq!(tx.to_hash_input_bytes(), again.to_hash_input_bytes());
+
+    Ok(())
+}